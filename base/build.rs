@@ -0,0 +1,76 @@
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+	println!("cargo:rerun-if-changed=build.rs");
+	println!("cargo:rerun-if-env-changed=VERGEN_GIT_SHA");
+	println!("cargo:rerun-if-env-changed=BUILD_COMMIT");
+	println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+	let git_hash = std::env::var("VERGEN_GIT_SHA")
+		.or_else(|_| std::env::var("BUILD_COMMIT"))
+		.ok()
+		.filter(|c| !c.is_empty())
+		.or_else(git_hash)
+		.unwrap_or_else(|| "unknown".to_string());
+	if let Some(git_dir) = run("git", &["rev-parse", "--absolute-git-dir"]) {
+		println!("cargo:rerun-if-changed={git_dir}/HEAD");
+		println!("cargo:rerun-if-changed={git_dir}/refs/heads");
+	}
+
+	let epoch = std::env::var("SOURCE_DATE_EPOCH")
+		.ok()
+		.and_then(|s| s.parse().ok())
+		.unwrap_or_else(|| {
+			SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map(|d| d.as_secs())
+				.unwrap_or_default()
+		});
+	let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+	let rustc_version = run(&rustc, &["--version"]).unwrap_or_else(|| "unknown".to_string());
+	let profile = std::env::var("PROFILE").unwrap_or_else(|_| "unknown".to_string());
+
+	println!("cargo:rustc-env=BUILD_GIT_HASH={git_hash}");
+	println!("cargo:rustc-env=BUILD_TIMESTAMP={}", rfc3339(epoch));
+	println!("cargo:rustc-env=BUILD_RUSTC_VERSION={rustc_version}");
+	println!("cargo:rustc-env=BUILD_PROFILE={profile}");
+}
+
+fn git_hash() -> Option<String> {
+	let hash = run("git", &["rev-parse", "--short", "HEAD"])?;
+	let dirty =
+		run("git", &["status", "--porcelain", "--untracked-files=no"]).is_some_and(|s| !s.is_empty());
+	Some(if dirty { format!("{hash}-dirty") } else { hash })
+}
+
+fn run(cmd: &str, args: &[&str]) -> Option<String> {
+	let output = Command::new(cmd).args(args).output().ok()?;
+	if !output.status.success() {
+		return None;
+	}
+	let out = String::from_utf8(output.stdout).ok()?;
+	Some(out.trim().to_string())
+}
+
+/// `YYYY-MM-DDTHH:MM:SSZ` from unix seconds, without pulling a date crate into the build
+fn rfc3339(secs: u64) -> String {
+	let days = (secs / 86_400) as i64;
+	let rem = secs % 86_400;
+	// civil from days, http://howardhinnant.github.io/date_algorithms.html
+	let z = days + 719_468;
+	let era = z.div_euclid(146_097);
+	let doe = z.rem_euclid(146_097);
+	let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + i64::from(month <= 2);
+	format!(
+		"{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+		rem / 3600,
+		rem % 3600 / 60,
+		rem % 60
+	)
+}
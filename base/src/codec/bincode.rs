@@ -1,5 +1,5 @@
-use crate::map_err;
 use crate::result::AppResult;
+use crate::{err, map_err};
 use bincode::{Decode, config, de, enc};
 use tracing::debug;
 
@@ -7,6 +7,8 @@ crate::gen_impl_code_enum! {
 	BinErr {
 		BinEncodeErr = ("BIN001", "Bincode encode error"),
 		BinDecodeErr = ("BIN002", "Bincode decode error"),
+		BinNoVersion = ("BIN003", "Bincode data has no version byte"),
+		BinVersionMismatch = ("BIN004", "Bincode data version mismatch"),
 	}
 }
 
@@ -43,9 +45,56 @@ impl BinDecodeExt for Vec<u8> {
 	}
 }
 
+/// Current layout version written by [`BinVersionedExt`]
+pub const BIN_V1: u8 = 1;
+
+/// Bincode output prefixed with a single `version` byte, so readers can tell
+/// which struct layout a blob was written with
+pub fn bin_encode_versioned<T: enc::Encode>(value: &T, version: u8) -> AppResult<Vec<u8>> {
+	let mut data = vec![version];
+	bincode::encode_into_std_write(value, &mut data, config::standard())
+		.map_err(map_err!(&BinErr::BinEncodeErr))?;
+	Ok(data)
+}
+
+/// Reverse of [`bin_encode_versioned`], returns `(version, value)` for the caller to
+/// dispatch migrations on
+pub fn bin_decode_versioned<T: Decode<()>>(data: &[u8]) -> AppResult<(u8, T)> {
+	let Some((&version, body)) = data.split_first() else {
+		return err!(&BinErr::BinNoVersion);
+	};
+	let value = body.bin_decode::<T>()?;
+	Ok((version, value))
+}
+
+pub trait BinVersionedExt: Sized {
+	fn bin_encode_v1(&self) -> AppResult<Vec<u8>>;
+
+	/// Fails with `BinVersionMismatch` when the blob was not written as [`BIN_V1`]
+	fn bin_decode_v1(data: &[u8]) -> AppResult<Self>;
+}
+
+impl<T: enc::Encode + Decode<()>> BinVersionedExt for T {
+	fn bin_encode_v1(&self) -> AppResult<Vec<u8>> {
+		bin_encode_versioned(self, BIN_V1)
+	}
+
+	fn bin_decode_v1(data: &[u8]) -> AppResult<Self> {
+		let (version, value) = bin_decode_versioned(data)?;
+		if version != BIN_V1 {
+			return err!(
+				&BinErr::BinVersionMismatch,
+				format!("expected {BIN_V1}, got {version}")
+			);
+		}
+		Ok(value)
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use crate::codec::bincode::{BinDecodeExt, BinEncodeExt};
+	use crate::codec::bincode::*;
+	use crate::result::{AppError, ErrorCode};
 	use bincode::{Decode, Encode, config};
 
 	#[derive(Encode, Decode, PartialEq, Debug)]
@@ -69,4 +118,28 @@ mod tests {
 		assert_eq!(world, decoded);
 		assert_eq!(len, encoded.len()); // read all bytes
 	}
+
+	#[test]
+	fn test_versioned() {
+		let world = World(vec![Entity { x: 1.0, y: 2.0 }]);
+
+		let encoded = bin_encode_versioned(&world, 1).unwrap();
+		assert_eq!(encoded[0], 1);
+		assert_eq!(&encoded[1..], world.bin_encode().unwrap());
+
+		let (version, decoded): (u8, World) = bin_decode_versioned(&encoded).unwrap();
+		assert_eq!(version, 1);
+		assert_eq!(decoded, world);
+
+		assert_eq!(
+			World::bin_decode_v1(&world.bin_encode_v1().unwrap()).unwrap(),
+			world
+		);
+		let v2 = bin_encode_versioned(&world, 2).unwrap();
+		assert!(matches!(
+			World::bin_decode_v1(&v2),
+			Err(AppError::ExtCode(code, _)) if code.code() == "BIN004"
+		));
+		assert!(bin_decode_versioned::<World>(&[]).is_err());
+	}
 }
@@ -0,0 +1,645 @@
+//! Canonical JSON for hashing and signing, close to RFC 8785 (JCS)
+//!
+//! - object members sorted by their keys' UTF-16 code units, whatever the map type or the order
+//!   fields were serialized in
+//! - no whitespace between tokens
+//! - strings escape only `"`, `\` and control characters: `\b \t \n \f \r` in short form, the rest
+//!   of `U+0000..U+001F` as lowercase `\u00xx`. Everything else, non-ASCII included, is written
+//!   as UTF-8
+//! - floats in the ECMAScript `Number.prototype.toString` layout of their shortest round-trip
+//!   digits, e.g. `1`, `0.1`, `1e+21`, `-0` as `0`. NaN and Infinity are rejected with
+//!   [`CanonJsonErr::NonFiniteNumber`]
+//! - integers written exactly, unlike JCS which would round those above 2^53
+//!
+//! Object keys must serialize as strings, numbers or bools, the latter two are written as strings
+//! like `serde_json` does.
+
+use crate::result::{AppError, AppResult};
+use serde::ser::{self, Serialize};
+use std::fmt::{Display, LowerExp, Write};
+
+crate::gen_impl_code_enum! {
+	CanonJsonErr {
+		NonFiniteNumber = ("CJSON01", "Canonical JSON cannot encode NaN or Infinity"),
+		KeyNotString = ("CJSON02", "Canonical JSON object key must be a string"),
+		SerializeErr = ("CJSON03", "Canonical JSON serialize error"),
+	}
+}
+
+/// Canonical JSON bytes of `value`
+pub fn to_canonical_vec<T: Serialize + ?Sized>(value: &T) -> AppResult<Vec<u8>> {
+	let node = value
+		.serialize(NodeSerializer)
+		.map_err(Error::into_app_err)?;
+	let mut out = String::new();
+	write_node(&node, &mut out);
+	Ok(out.into_bytes())
+}
+
+/// Sha256 of the [`to_canonical_vec`] bytes of `value`
+#[cfg(feature = "hash")]
+pub fn hash_canonical<T: Serialize + ?Sized>(value: &T) -> AppResult<[u8; 32]> {
+	use sha2::{Digest, Sha256};
+	Ok(Sha256::digest(to_canonical_vec(value)?).into())
+}
+
+#[derive(Debug)]
+enum Error {
+	NonFinite(String),
+	KeyNotString(&'static str),
+	Custom(String),
+}
+
+impl Error {
+	fn into_app_err(self) -> AppError {
+		match self {
+			Self::NonFinite(v) => AppError::ExtCode(&CanonJsonErr::NonFiniteNumber, v),
+			Self::KeyNotString(kind) => AppError::ExtCode(&CanonJsonErr::KeyNotString, kind.into()),
+			Self::Custom(msg) => AppError::ExtCode(&CanonJsonErr::SerializeErr, msg),
+		}
+	}
+}
+
+impl Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::NonFinite(v) => write!(f, "non-finite number {v}"),
+			Self::KeyNotString(kind) => write!(f, "{kind} object key"),
+			Self::Custom(msg) => f.write_str(msg),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+	fn custom<T: Display>(msg: T) -> Self {
+		Self::Custom(msg.to_string())
+	}
+}
+
+/// Serialized value, numbers already formatted
+enum Node {
+	Null,
+	Bool(bool),
+	Number(String),
+	Str(String),
+	Array(Vec<Node>),
+	Object(Vec<(String, Node)>),
+}
+
+/// Wraps `node` as `{variant: node}`, the externally tagged enum layout
+fn tagged(variant: Option<&'static str>, node: Node) -> Node {
+	match variant {
+		Some(variant) => Node::Object(vec![(variant.to_string(), node)]),
+		None => node,
+	}
+}
+
+fn float_node<F: LowerExp + Display>(v: F, finite: bool, zero: bool) -> Result<Node, Error> {
+	if !finite {
+		return Err(Error::NonFinite(v.to_string()));
+	}
+	if zero {
+		return Ok(Node::Number("0".to_string()));
+	}
+	Ok(Node::Number(format_float(&format!("{v:e}"))))
+}
+
+/// ECMAScript layout of a float from its shortest `{:e}` form, e.g. `-1.5e-7`
+fn format_float(sci: &str) -> String {
+	let (sign, sci) = match sci.strip_prefix('-') {
+		Some(abs) => ("-", abs),
+		None => ("", sci),
+	};
+	let (mantissa, exp) = sci.split_once('e').unwrap_or((sci, "0"));
+	let digits = mantissa.replace('.', "");
+	let k = digits.len() as i32;
+	// value is 0.digits * 10^n
+	let n = exp.parse::<i32>().unwrap_or(0) + 1;
+
+	let body = if k <= n && n <= 21 {
+		format!("{digits}{}", "0".repeat((n - k) as usize))
+	} else if 0 < n && n <= 21 {
+		let (int, frac) = digits.split_at(n as usize);
+		format!("{int}.{frac}")
+	} else if -6 < n && n <= 0 {
+		format!("0.{}{digits}", "0".repeat(-n as usize))
+	} else {
+		let e = n - 1;
+		let e_sign = if e < 0 { '-' } else { '+' };
+		let (first, rest) = digits.split_at(1);
+		if rest.is_empty() {
+			format!("{first}e{e_sign}{}", e.abs())
+		} else {
+			format!("{first}.{rest}e{e_sign}{}", e.abs())
+		}
+	};
+	format!("{sign}{body}")
+}
+
+fn write_node(node: &Node, out: &mut String) {
+	match node {
+		Node::Null => out.push_str("null"),
+		Node::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+		Node::Number(n) => out.push_str(n),
+		Node::Str(s) => write_str(s, out),
+		Node::Array(items) => {
+			out.push('[');
+			for (i, item) in items.iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_node(item, out);
+			}
+			out.push(']');
+		}
+		Node::Object(entries) => {
+			let mut sorted: Vec<_> = entries.iter().collect();
+			sorted.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+			out.push('{');
+			for (i, (key, value)) in sorted.into_iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_str(key, out);
+				out.push(':');
+				write_node(value, out);
+			}
+			out.push('}');
+		}
+	}
+}
+
+fn write_str(s: &str, out: &mut String) {
+	out.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\u{08}' => out.push_str("\\b"),
+			'\t' => out.push_str("\\t"),
+			'\n' => out.push_str("\\n"),
+			'\u{0c}' => out.push_str("\\f"),
+			'\r' => out.push_str("\\r"),
+			c if c < ' ' => {
+				let _ = write!(out, "\\u{:04x}", c as u32);
+			}
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+}
+
+struct NodeSerializer;
+
+impl ser::Serializer for NodeSerializer {
+	type Ok = Node;
+	type Error = Error;
+	type SerializeSeq = SeqNode;
+	type SerializeTuple = SeqNode;
+	type SerializeTupleStruct = SeqNode;
+	type SerializeTupleVariant = SeqNode;
+	type SerializeMap = MapNode;
+	type SerializeStruct = MapNode;
+	type SerializeStructVariant = MapNode;
+
+	fn serialize_bool(self, v: bool) -> Result<Node, Error> {
+		Ok(Node::Bool(v))
+	}
+
+	fn serialize_i8(self, v: i8) -> Result<Node, Error> {
+		self.serialize_i64(v as i64)
+	}
+
+	fn serialize_i16(self, v: i16) -> Result<Node, Error> {
+		self.serialize_i64(v as i64)
+	}
+
+	fn serialize_i32(self, v: i32) -> Result<Node, Error> {
+		self.serialize_i64(v as i64)
+	}
+
+	fn serialize_i64(self, v: i64) -> Result<Node, Error> {
+		Ok(Node::Number(v.to_string()))
+	}
+
+	fn serialize_i128(self, v: i128) -> Result<Node, Error> {
+		Ok(Node::Number(v.to_string()))
+	}
+
+	fn serialize_u8(self, v: u8) -> Result<Node, Error> {
+		self.serialize_u64(v as u64)
+	}
+
+	fn serialize_u16(self, v: u16) -> Result<Node, Error> {
+		self.serialize_u64(v as u64)
+	}
+
+	fn serialize_u32(self, v: u32) -> Result<Node, Error> {
+		self.serialize_u64(v as u64)
+	}
+
+	fn serialize_u64(self, v: u64) -> Result<Node, Error> {
+		Ok(Node::Number(v.to_string()))
+	}
+
+	fn serialize_u128(self, v: u128) -> Result<Node, Error> {
+		Ok(Node::Number(v.to_string()))
+	}
+
+	fn serialize_f32(self, v: f32) -> Result<Node, Error> {
+		float_node(v, v.is_finite(), v == 0.0)
+	}
+
+	fn serialize_f64(self, v: f64) -> Result<Node, Error> {
+		float_node(v, v.is_finite(), v == 0.0)
+	}
+
+	fn serialize_char(self, v: char) -> Result<Node, Error> {
+		Ok(Node::Str(v.to_string()))
+	}
+
+	fn serialize_str(self, v: &str) -> Result<Node, Error> {
+		Ok(Node::Str(v.to_string()))
+	}
+
+	fn serialize_bytes(self, v: &[u8]) -> Result<Node, Error> {
+		Ok(Node::Array(
+			v.iter().map(|b| Node::Number(b.to_string())).collect(),
+		))
+	}
+
+	fn serialize_none(self) -> Result<Node, Error> {
+		Ok(Node::Null)
+	}
+
+	fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Node, Error> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<Node, Error> {
+		Ok(Node::Null)
+	}
+
+	fn serialize_unit_struct(self, _name: &'static str) -> Result<Node, Error> {
+		Ok(Node::Null)
+	}
+
+	fn serialize_unit_variant(
+		self,
+		_name: &'static str,
+		_index: u32,
+		variant: &'static str,
+	) -> Result<Node, Error> {
+		Ok(Node::Str(variant.to_string()))
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized>(
+		self,
+		_name: &'static str,
+		value: &T,
+	) -> Result<Node, Error> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized>(
+		self,
+		_name: &'static str,
+		_index: u32,
+		variant: &'static str,
+		value: &T,
+	) -> Result<Node, Error> {
+		Ok(tagged(Some(variant), value.serialize(self)?))
+	}
+
+	fn serialize_seq(self, len: Option<usize>) -> Result<SeqNode, Error> {
+		Ok(SeqNode::new(len.unwrap_or(0), None))
+	}
+
+	fn serialize_tuple(self, len: usize) -> Result<SeqNode, Error> {
+		Ok(SeqNode::new(len, None))
+	}
+
+	fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqNode, Error> {
+		Ok(SeqNode::new(len, None))
+	}
+
+	fn serialize_tuple_variant(
+		self,
+		_name: &'static str,
+		_index: u32,
+		variant: &'static str,
+		len: usize,
+	) -> Result<SeqNode, Error> {
+		Ok(SeqNode::new(len, Some(variant)))
+	}
+
+	fn serialize_map(self, len: Option<usize>) -> Result<MapNode, Error> {
+		Ok(MapNode::new(len.unwrap_or(0), None))
+	}
+
+	fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapNode, Error> {
+		Ok(MapNode::new(len, None))
+	}
+
+	fn serialize_struct_variant(
+		self,
+		_name: &'static str,
+		_index: u32,
+		variant: &'static str,
+		len: usize,
+	) -> Result<MapNode, Error> {
+		Ok(MapNode::new(len, Some(variant)))
+	}
+}
+
+struct SeqNode {
+	items: Vec<Node>,
+	variant: Option<&'static str>,
+}
+
+impl SeqNode {
+	fn new(len: usize, variant: Option<&'static str>) -> Self {
+		Self {
+			items: Vec::with_capacity(len),
+			variant,
+		}
+	}
+
+	fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		self.items.push(value.serialize(NodeSerializer)?);
+		Ok(())
+	}
+
+	fn finish(self) -> Result<Node, Error> {
+		Ok(tagged(self.variant, Node::Array(self.items)))
+	}
+}
+
+impl ser::SerializeSeq for SeqNode {
+	type Ok = Node;
+	type Error = Error;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		self.push(value)
+	}
+
+	fn end(self) -> Result<Node, Error> {
+		self.finish()
+	}
+}
+
+impl ser::SerializeTuple for SeqNode {
+	type Ok = Node;
+	type Error = Error;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		self.push(value)
+	}
+
+	fn end(self) -> Result<Node, Error> {
+		self.finish()
+	}
+}
+
+impl ser::SerializeTupleStruct for SeqNode {
+	type Ok = Node;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		self.push(value)
+	}
+
+	fn end(self) -> Result<Node, Error> {
+		self.finish()
+	}
+}
+
+impl ser::SerializeTupleVariant for SeqNode {
+	type Ok = Node;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		self.push(value)
+	}
+
+	fn end(self) -> Result<Node, Error> {
+		self.finish()
+	}
+}
+
+struct MapNode {
+	entries: Vec<(String, Node)>,
+	key: Option<String>,
+	variant: Option<&'static str>,
+}
+
+impl MapNode {
+	fn new(len: usize, variant: Option<&'static str>) -> Self {
+		Self {
+			entries: Vec::with_capacity(len),
+			key: None,
+			variant,
+		}
+	}
+
+	fn push<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), Error> {
+		self.entries.push((key, value.serialize(NodeSerializer)?));
+		Ok(())
+	}
+
+	fn finish(self) -> Result<Node, Error> {
+		Ok(tagged(self.variant, Node::Object(self.entries)))
+	}
+}
+
+impl ser::SerializeMap for MapNode {
+	type Ok = Node;
+	type Error = Error;
+
+	fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+		let key = match key.serialize(NodeSerializer)? {
+			Node::Str(s) | Node::Number(s) => s,
+			Node::Bool(b) => b.to_string(),
+			Node::Null => return Err(Error::KeyNotString("null")),
+			Node::Array(_) => return Err(Error::KeyNotString("array")),
+			Node::Object(_) => return Err(Error::KeyNotString("object")),
+		};
+		self.key = Some(key);
+		Ok(())
+	}
+
+	fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+		let key = self
+			.key
+			.take()
+			.ok_or_else(|| Error::Custom("map value without a key".to_string()))?;
+		self.push(key, value)
+	}
+
+	fn end(self) -> Result<Node, Error> {
+		self.finish()
+	}
+}
+
+impl ser::SerializeStruct for MapNode {
+	type Ok = Node;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(
+		&mut self,
+		key: &'static str,
+		value: &T,
+	) -> Result<(), Error> {
+		self.push(key.to_string(), value)
+	}
+
+	fn end(self) -> Result<Node, Error> {
+		self.finish()
+	}
+}
+
+impl ser::SerializeStructVariant for MapNode {
+	type Ok = Node;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(
+		&mut self,
+		key: &'static str,
+		value: &T,
+	) -> Result<(), Error> {
+		self.push(key.to_string(), value)
+	}
+
+	fn end(self) -> Result<Node, Error> {
+		self.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::result::ErrorCode;
+	use proptest::prelude::*;
+	use serde::Serialize;
+	use std::collections::{BTreeMap, HashMap};
+
+	fn canonical<T: Serialize + ?Sized>(value: &T) -> String {
+		String::from_utf8(to_canonical_vec(value).unwrap()).unwrap()
+	}
+
+	#[derive(Serialize)]
+	enum Event {
+		Created { id: u32 },
+		Moved(i8, i8),
+		Closed,
+	}
+
+	#[derive(Serialize)]
+	struct Payload {
+		zeta: Option<&'static str>,
+		alpha: Vec<Event>,
+		// after the emoji in UTF-16 order, before it in UTF-8 order
+		#[serde(rename = "\u{FB01}")]
+		ligature: f64,
+		#[serde(rename = "\u{1F600}")]
+		emoji: bool,
+		amount: u64,
+		text: &'static str,
+		attrs: HashMap<u32, f32>,
+	}
+
+	#[test]
+	fn test_fixture() {
+		let payload = Payload {
+			zeta: None,
+			alpha: vec![Event::Closed, Event::Moved(-1, 2), Event::Created { id: 7 }],
+			ligature: 1e21,
+			emoji: true,
+			amount: u64::MAX,
+			text: "a\"\\\u{08}\t\n\u{0c}\r\u{1}\u{7f}é\u{2028}",
+			attrs: HashMap::from([(10, 0.1), (2, -0.0)]),
+		};
+		let expected = concat!(
+			r#"{"alpha":["Closed",{"Moved":[-1,2]},{"Created":{"id":7}}],"#,
+			r#""amount":18446744073709551615,"attrs":{"10":0.1,"2":0},"#,
+			r#""text":"a\"\\\b\t\n\f\r\u0001"#,
+			"\u{7f}é\u{2028}\",",
+			"\"zeta\":null,\"\u{1F600}\":true,\"\u{FB01}\":1e+21}",
+		);
+		assert_eq!(canonical(&payload), expected);
+	}
+
+	#[test]
+	fn test_numbers() {
+		let cases: [(f64, &str); 12] = [
+			(1.0, "1"),
+			(-0.0, "0"),
+			(0.1, "0.1"),
+			(-1.5, "-1.5"),
+			(1e21, "1e+21"),
+			(1e20, "100000000000000000000"),
+			(123456789012345680000.0, "123456789012345680000"),
+			(0.000001, "0.000001"),
+			(1e-7, "1e-7"),
+			(333333333.3333333, "333333333.3333333"),
+			(5e-324, "5e-324"),
+			(f64::MAX, "1.7976931348623157e+308"),
+		];
+		for (value, expected) in cases {
+			assert_eq!(canonical(&value), expected, "{value:?}");
+		}
+		assert_eq!(canonical(&1.1f32), "1.1");
+		assert_eq!(
+			canonical(&(i64::MIN, u128::MAX)),
+			format!("[{},{}]", i64::MIN, u128::MAX)
+		);
+	}
+
+	#[test]
+	fn test_rejects_non_finite() {
+		for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+			let err = to_canonical_vec(&vec![Some(1.0), Some(value)]).unwrap_err();
+			assert_eq!(err.err_code().code(), CanonJsonErr::NonFiniteNumber.code());
+		}
+		let err = to_canonical_vec(&BTreeMap::from([((1, 2), 3)])).unwrap_err();
+		assert_eq!(err.err_code().code(), CanonJsonErr::KeyNotString.code());
+	}
+
+	#[cfg(feature = "hash")]
+	#[test]
+	fn test_hash_canonical() {
+		let value = serde_json::json!({ "b": [true, null], "a": 1 });
+		assert_eq!(canonical(&value), r#"{"a":1,"b":[true,null]}"#);
+		assert_eq!(
+			hex::encode(hash_canonical(&value).unwrap()),
+			"1cc69c7fa23616ca2ec3ee70d24390a6225c8832db8a4c814c7e0e7f942f8668"
+		);
+	}
+
+	/// Map serialized in the order of its entries
+	struct Ordered(Vec<(String, i64)>);
+
+	impl Serialize for Ordered {
+		fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			serializer.collect_map(self.0.iter().map(|(k, v)| (k, v)))
+		}
+	}
+
+	proptest! {
+		#[test]
+		fn test_insertion_order_independent(
+			(entries, shuffled) in prop::collection::btree_map(".*", any::<i64>(), 0..16)
+				.prop_flat_map(|map| {
+					let entries: Vec<_> = map.into_iter().collect();
+					(Just(entries.clone()), Just(entries).prop_shuffle())
+				})
+		) {
+			let expected = to_canonical_vec(&Ordered(entries.clone())).unwrap();
+			prop_assert_eq!(&to_canonical_vec(&Ordered(shuffled)).unwrap(), &expected);
+			let map: HashMap<_, _> = entries.into_iter().collect();
+			prop_assert_eq!(&to_canonical_vec(&map).unwrap(), &expected);
+		}
+	}
+}
@@ -1,5 +1,6 @@
 #[cfg(feature = "bincode")]
 pub mod bincode;
+pub mod canonical_json;
 pub mod error;
 #[cfg(feature = "rkyv-codec")]
 pub mod rkyv;
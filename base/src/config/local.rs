@@ -1,4 +1,5 @@
 use crate::app_err;
+use crate::logger::LogDirectives;
 use crate::result::{AppResult, SysErr};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -28,7 +29,11 @@ pub struct LocalConfig {
 	pub rt_env: RtEnv,
 	/// log level
 	pub log_level: Option<Level>,
+	/// per-target log directives, the base of the logger's env filter when set
+	pub log_directives: Option<LogDirectives>,
 	pub config_path: Option<PathBuf>,
+	/// config profile overlaid on the base config, e.g. `production`
+	pub profile: Option<String>,
 }
 
 impl LocalConfig {
@@ -47,6 +52,17 @@ impl LocalConfig {
 		}
 	}
 
+	pub fn with_profile(self, profile: impl Into<String>) -> Self {
+		Self {
+			profile: Some(profile.into()),
+			..self
+		}
+	}
+
+	pub fn profile(&self) -> Option<&str> {
+		self.profile.as_deref()
+	}
+
 	pub fn log_level(&self) -> Level {
 		self.log_level.unwrap_or(Level::INFO)
 	}
@@ -68,7 +84,9 @@ impl Default for LocalConfig {
 		Self {
 			rt_env: RtEnv::Development,
 			log_level: Some(Level::DEBUG),
+			log_directives: None,
 			config_path: Some(PathBuf::from("./configs/swap-config.yaml")),
+			profile: None,
 		}
 	}
 }
@@ -8,8 +8,9 @@ use figment::Figment;
 use figment::providers::{Env, Format, Toml, Yaml};
 use serde::Deserialize;
 use serde::de::DeserializeOwned;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tracing::warn;
 
 pub trait GlobalConfigClient<C>
 where
@@ -29,10 +30,28 @@ where
 	/// `"APP__"` and split/nested via `"__"`.
 	// fn load(path: PathBuf) -> Result<Self, figment::Error> {
 	fn load(path: PathBuf) -> AppResult<Self> {
-		let config = Figment::new()
+		Self::load_with_profile(path, None)
+	}
+
+	/// Same as [`ConfigExt::load`], additionally merging `{dir}/{stem}.{profile}.{ext}`
+	/// over the base file when a profile is given, e.g. `config.yaml` + `config.production.yaml`.
+	/// A missing profile file is skipped.
+	fn load_with_profile(base: PathBuf, profile: Option<&str>) -> AppResult<Self> {
+		let mut figment = Figment::new()
 			.merge(Toml::string(""))
 			.merge(Yaml::string(""))
-			.merge(Yaml::file_exact(path))
+			.merge(Yaml::file_exact(&base));
+
+		if let Some(profile) = profile {
+			let overlay = profile_path(&base, profile);
+			if overlay.exists() {
+				figment = figment.merge(Yaml::file_exact(overlay));
+			} else {
+				warn!("Profile config {} not found, skipped", overlay.display());
+			}
+		}
+
+		let config = figment
 			.merge(Env::prefixed("APP__").split("__"))
 			.extract()
 			.map_err(map_err!(&SysErr::ConfigLoadFailed))?;
@@ -41,4 +60,59 @@ where
 	}
 }
 
+/// `{dir}/{stem}.{profile}.{ext}` derived from the base config path
+pub fn profile_path(base: &Path, profile: &str) -> PathBuf {
+	let stem = base
+		.file_stem()
+		.map(|s| s.to_string_lossy().into_owned())
+		.unwrap_or_default();
+	let file_name = match base.extension() {
+		Some(ext) => format!("{stem}.{profile}.{}", ext.to_string_lossy()),
+		None => format!("{stem}.{profile}"),
+	};
+	base.with_file_name(file_name)
+}
+
 impl<T> ConfigExt for T where T: for<'de> Deserialize<'de> {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::uuid::UID;
+	use std::fs;
+
+	#[derive(Debug, Deserialize)]
+	struct ServerCfg {
+		host: String,
+		port: u16,
+	}
+
+	#[test]
+	fn test_profile_path() {
+		let path = profile_path(Path::new("configs/config.yaml"), "production");
+		assert_eq!(path, PathBuf::from("configs/config.production.yaml"));
+		let path = profile_path(Path::new("config"), "test");
+		assert_eq!(path, PathBuf::from("config.test"));
+	}
+
+	#[test]
+	fn test_load_with_profile() {
+		let dir = std::env::temp_dir().join(UID.v4_simple_str());
+		fs::create_dir_all(&dir).unwrap();
+		let base = dir.join("config.yaml");
+		fs::write(&base, "host: 127.0.0.1\nport: 8080\n").unwrap();
+		fs::write(dir.join("config.test.yaml"), "port: 9090\n").unwrap();
+
+		let cfg = ServerCfg::load(base.clone()).unwrap();
+		assert_eq!(cfg.port, 8080);
+
+		let cfg = ServerCfg::load_with_profile(base.clone(), Some("test")).unwrap();
+		assert_eq!(cfg.port, 9090);
+		assert_eq!(cfg.host, "127.0.0.1");
+
+		let cfg = ServerCfg::load_with_profile(base, Some("missing")).unwrap();
+		assert_eq!(cfg.port, 8080);
+
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
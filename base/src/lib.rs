@@ -1,6 +1,7 @@
 pub mod codec;
 pub mod config;
 pub mod logger;
+pub mod macros;
 pub mod result;
 pub mod runtimes;
 pub mod tools;
@@ -9,3 +10,6 @@ pub mod utils;
 pub mod validator;
 
 pub use tracing_appender::non_blocking::WorkerGuard;
+
+#[doc(hidden)]
+pub use serde_json;
@@ -1,22 +1,141 @@
 //! Initialize logger.
 
 use crate::config::{LocalConfig, RtEnv};
+use crate::err;
+use crate::result::{AppResult, SysErr};
+use crate::tools::audit::{AUDIT_TARGET, TidLayer, audit_file_layer};
+use crate::validator::Checker;
 use serde::Deserialize;
+use std::fmt::{Display, Formatter};
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::{panic, thread};
-use tracing::{error, level_filters::LevelFilter};
+use tracing::{Event, Level, Subscriber, error, level_filters::LevelFilter};
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_appender::rolling;
+use tracing_subscriber::filter::filter_fn;
 use tracing_subscriber::fmt::Layer;
-use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::layer::{Context, SubscriberExt};
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{EnvFilter, registry};
+use tracing_subscriber::{EnvFilter, Layer as _, registry};
+
+/// `EnvFilter` directives such as `info,sqlx=warn,my_app::indexer=trace`, validated on parse.
+/// A bare level like `INFO` is the single-directive case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogDirectives(String);
+
+impl LogDirectives {
+	pub fn parse(directives: &str) -> AppResult<Self> {
+		let mut parsed = Vec::new();
+		for directive in directives
+			.split(',')
+			.map(str::trim)
+			.filter(|d| !d.is_empty())
+		{
+			if let Err(e) = directive.parse::<tracing_subscriber::filter::Directive>() {
+				return err!(&SysErr::InvalidLogDirective, format!("`{directive}`: {e}"));
+			}
+			parsed.push(directive);
+		}
+		if parsed.is_empty() {
+			return err!(&SysErr::InvalidLogDirective, "no directives given");
+		}
+		Ok(Self(parsed.join(",")))
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+
+	/// The target-less level directive, e.g. `INFO` for `info,sqlx=warn`
+	pub fn level(&self) -> Option<Level> {
+		self.0.split(',').find_map(|d| d.parse::<Level>().ok())
+	}
+
+	pub fn env_filter(&self) -> EnvFilter {
+		EnvFilter::new(&self.0)
+	}
+}
+
+impl FromStr for LogDirectives {
+	type Err = crate::result::AppError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::parse(s)
+	}
+}
+
+impl From<Level> for LogDirectives {
+	fn from(level: Level) -> Self {
+		Self(level.to_string())
+	}
+}
+
+impl Display for LogDirectives {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+/// Drops each `DEBUG` / `TRACE` event unless a random draw falls below `rate`.
+/// Higher levels always pass.
+#[derive(Debug, Clone, Copy)]
+pub struct SamplingLayer {
+	rate: f64,
+}
+
+impl SamplingLayer {
+	/// `rate` in `(0.0, 1.0]`, panics otherwise. See [`SamplingLayer::try_new`] for rates read
+	/// from config.
+	pub fn new(rate: f64) -> Self {
+		assert!(
+			is_valid_rate(rate),
+			"sampling rate {rate} not in (0.0, 1.0]"
+		);
+		Self { rate }
+	}
+
+	/// [`SamplingLayer::new`] returning [`SysErr::InvalidSamplingRate`] for a rate outside
+	/// `(0.0, 1.0]`
+	pub fn try_new(rate: f64) -> AppResult<Self> {
+		if !is_valid_rate(rate) {
+			return err!(&SysErr::InvalidSamplingRate, rate);
+		}
+		Ok(Self { rate })
+	}
+
+	pub fn rate(&self) -> f64 {
+		self.rate
+	}
+}
+
+fn is_valid_rate(rate: f64) -> bool {
+	rate > 0.0 && rate <= 1.0
+}
+
+impl<S: Subscriber> tracing_subscriber::Layer<S> for SamplingLayer {
+	fn event_enabled(&self, event: &Event<'_>, _ctx: Context<'_, S>) -> bool {
+		*event.metadata().level() < Level::DEBUG || rand::random::<f64>() < self.rate
+	}
+}
+
+impl Checker for Logger {
+	fn check(&self) -> AppResult<()> {
+		self.sampling_layer().map(|_| ())
+	}
+}
 
 /// Initialize logger (tracing and panic hook).
 #[derive(Debug, Clone, Default, Deserialize)]
 pub struct Logger {
 	pub path: PathBuf,
 	pub directives: Vec<String>,
+	/// Share of `DEBUG` / `TRACE` events kept, see [`SamplingLayer`]. All kept when unset.
+	pub sampling_rate: Option<f64>,
+	/// Route audit events to `{path}/logs/audit.log` instead of the main log, see
+	/// [`TracingAuditSink`](crate::tools::audit::TracingAuditSink)
+	#[serde(default)]
+	pub audit: bool,
 }
 
 impl Logger {
@@ -24,6 +143,35 @@ impl Logger {
 		Self { path, ..self }
 	}
 
+	/// Keep `DEBUG` / `TRACE` events with probability `rate` in `(0.0, 1.0]`
+	pub fn with_sampling_rate(self, rate: f64) -> Self {
+		let layer = SamplingLayer::new(rate);
+		Self {
+			sampling_rate: Some(layer.rate()),
+			..self
+		}
+	}
+
+	pub fn with_audit(self, audit: bool) -> Self {
+		Self { audit, ..self }
+	}
+
+	/// `None` for a rate of `1.0`, so no layer is installed
+	pub fn sampling_layer(&self) -> AppResult<Option<SamplingLayer>> {
+		self.sampling_rate
+			.filter(|rate| *rate != 1.0)
+			.map(SamplingLayer::try_new)
+			.transpose()
+	}
+
+	/// [`Logger::init`] failing on an invalid config instead of logging without it
+	pub fn try_init(&self, app_args: &LocalConfig) -> AppResult<WorkerGuard> {
+		self.check()?;
+		Ok(self.init(app_args))
+	}
+
+	/// An invalid `sampling_rate` is reported on stderr and every event is kept, see
+	/// [`Logger::try_init`] to fail instead
 	pub fn init(&self, app_args: &LocalConfig) -> WorkerGuard {
 		let app_env: RtEnv = app_args.rt_env;
 		let console_logger = std::io::stdout();
@@ -43,10 +191,21 @@ impl Logger {
 			.with_thread_ids(true)
 			.with_ansi(self.is_ansi(app_args))
 			.with_writer(non_blocking);
+		let audit = self.audit;
+		let layer = layer.with_filter(filter_fn(move |meta| {
+			!audit || meta.target() != AUDIT_TARGET
+		}));
+		let audit_layer = audit.then(|| audit_file_layer(self.path.join("logs")));
 
 		let layered = registry()
 			// .with(max_level)
 			.with(self.build_env_filter(app_args))
+			.with(self.sampling_layer().unwrap_or_else(|e| {
+				eprintln!("log sampling disabled: {e}");
+				None
+			}))
+			.with(TidLayer)
+			.with(audit_layer)
 			.with(layer);
 
 		layered.init();
@@ -56,6 +215,8 @@ impl Logger {
 		guard
 	}
 
+	/// `RUST_LOG` when set, else the cli directives, else the level, then the config file
+	/// directives on top
 	fn build_env_filter(&self, app_args: &LocalConfig) -> EnvFilter {
 		let app_env: RtEnv = app_args.rt_env;
 		let max_level = match app_args.log_level {
@@ -66,11 +227,19 @@ impl Logger {
 			},
 		};
 
-		let mut env_filter = EnvFilter::try_from_default_env()
-			.unwrap_or_else(|_| EnvFilter::new(max_level.to_string()));
+		let mut env_filter =
+			EnvFilter::try_from_default_env().unwrap_or_else(|_| match &app_args.log_directives {
+				Some(directives) => directives.env_filter(),
+				None => EnvFilter::new(max_level.to_string()),
+			});
 		for directive in &self.directives {
 			env_filter = env_filter.add_directive(directive.parse().expect("invalid directive"));
 		}
+		if self.audit {
+			// audit events are kept whatever the log level
+			let directive = format!("{AUDIT_TARGET}=info");
+			env_filter = env_filter.add_directive(directive.parse().expect("invalid directive"));
+		}
 
 		env_filter
 	}
@@ -146,6 +315,126 @@ impl Logger {
 	}
 }
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::result::ErrorCode;
+	use std::sync::{Arc, Mutex};
+	use tracing_subscriber::Registry;
+
+	fn enabled(filter: EnvFilter) -> [bool; 4] {
+		let subscriber = Registry::default().with(filter);
+		tracing::subscriber::with_default(subscriber, || {
+			[
+				tracing::enabled!(target: "app", Level::INFO),
+				tracing::enabled!(target: "app", Level::DEBUG),
+				tracing::enabled!(target: "sqlx::query", Level::INFO),
+				tracing::enabled!(target: "app::indexer", Level::TRACE),
+			]
+		})
+	}
+
+	#[test]
+	fn test_log_directives_parse() {
+		let directives = LogDirectives::parse("INFO").unwrap();
+		assert_eq!(directives.level(), Some(Level::INFO));
+
+		let directives = LogDirectives::parse(" info, sqlx=warn ,app::indexer=trace").unwrap();
+		assert_eq!(directives.as_str(), "info,sqlx=warn,app::indexer=trace");
+		assert_eq!(directives.level(), Some(Level::INFO));
+		assert_eq!(LogDirectives::parse("sqlx=warn").unwrap().level(), None);
+
+		let err = LogDirectives::parse("info,sqlx=loud")
+			.unwrap_err()
+			.to_string();
+		assert!(err.contains("`sqlx=loud`"), "{err}");
+		assert!(LogDirectives::parse(" , ").is_err());
+	}
+
+	#[test]
+	fn test_log_directives_filter() {
+		let directives = LogDirectives::parse("info,sqlx=warn,app::indexer=trace").unwrap();
+		assert_eq!(enabled(directives.env_filter()), [true, false, false, true]);
+
+		let level = LogDirectives::from(Level::DEBUG);
+		assert_eq!(enabled(level.env_filter()), [true, true, true, false]);
+	}
+
+	#[derive(Clone, Default)]
+	struct VecWriter(Arc<Mutex<Vec<u8>>>);
+
+	impl std::io::Write for VecWriter {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.0.lock().unwrap().extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			Ok(())
+		}
+	}
+
+	fn emitted(logger: &Logger, level: Level) -> usize {
+		let writer = VecWriter::default();
+		let output = writer.0.clone();
+		let subscriber = Registry::default()
+			.with(logger.sampling_layer().unwrap())
+			.with(
+				Layer::new()
+					.with_ansi(false)
+					.with_writer(move || writer.clone()),
+			);
+		tracing::subscriber::with_default(subscriber, || {
+			for i in 0..1_000 {
+				match level {
+					Level::DEBUG => tracing::debug!(i, "hot path"),
+					_ => tracing::info!(i, "hot path"),
+				}
+			}
+		});
+		let output = output.lock().unwrap();
+		String::from_utf8_lossy(&output).lines().count()
+	}
+
+	#[test]
+	fn test_sampling_rate() {
+		let logger = Logger::default().with_sampling_rate(0.1);
+		let debug = emitted(&logger, Level::DEBUG);
+		assert!((50..=200).contains(&debug), "{debug}");
+		assert_eq!(emitted(&logger, Level::INFO), 1_000);
+
+		let logger = Logger::default().with_sampling_rate(1.0);
+		assert!(logger.sampling_layer().unwrap().is_none());
+		assert_eq!(emitted(&logger, Level::DEBUG), 1_000);
+		assert_eq!(emitted(&Logger::default(), Level::DEBUG), 1_000);
+	}
+
+	#[test]
+	fn test_sampling_rate_from_config() {
+		for rate in [0.0, -0.5, 1.5, f64::NAN] {
+			let logger = Logger {
+				sampling_rate: Some(rate),
+				..Default::default()
+			};
+			let err = logger.check().unwrap_err();
+			assert_eq!(err.err_code().code(), SysErr::InvalidSamplingRate.code());
+			assert!(logger.sampling_layer().is_err());
+		}
+		let logger = Logger {
+			sampling_rate: Some(0.5),
+			..Default::default()
+		};
+		assert!(logger.check().is_ok());
+		assert_eq!(logger.sampling_layer().unwrap().unwrap().rate(), 0.5);
+	}
+
+	#[test]
+	#[should_panic(expected = "not in (0.0, 1.0]")]
+	fn test_sampling_rate_out_of_range() {
+		Logger::default().with_sampling_rate(0.0);
+	}
+}
+
 #[cfg(test)]
 pub fn init_tracing() -> WorkerGuard {
 	let (non_blocking, guard) = tracing_appender::non_blocking(std::io::stdout());
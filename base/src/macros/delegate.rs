@@ -0,0 +1,176 @@
+/// Macro to delegate methods of a wrapper struct to one of its fields
+///
+/// Used inside an `impl` block, every listed signature gets a body calling the method with the
+/// same name and arguments on the target. `async fn` adds `.await`, and a leading `try` wraps
+/// the call as `Ok(call?)` so the inner error is converted into the declared one.
+///
+/// # Syntax
+///
+/// ```rust
+/// use base_infra::delegate;
+/// use std::collections::HashMap;
+///
+/// struct Registry {
+///     inner: HashMap<String, u32>,
+/// }
+///
+/// impl Registry {
+///     delegate! {
+///         to self.inner {
+///             pub fn insert(&mut self, k: String, v: u32) -> Option<u32>;
+///             pub fn len(&self) -> usize;
+///         }
+///     }
+/// }
+/// ```
+///
+/// # Generated code
+///
+/// ```rust,ignore
+/// pub fn insert(&mut self, k: String, v: u32) -> Option<u32> {
+///     self.inner.insert(k, v)
+/// }
+/// // `try async fn load(&self, id: u64) -> AppResult<u32>;`
+/// async fn load(&self, id: u64) -> AppResult<u32> {
+///     Ok(self.inner.load(id).await?)
+/// }
+/// ```
+///
+/// # Limitations
+///
+/// - Receivers must be `&self` or `&mut self`, arguments plain `name: Type`
+/// - Generic methods are not supported
+#[macro_export]
+macro_rules! delegate {
+	(to $($target:ident).+ { $($items:tt)* }) => {
+		$crate::delegate!(@item [$($target).+] $($items)*);
+	};
+
+	(@item [$($target:tt)+]) => {};
+	(@item [$($target:tt)+]
+		$(#[$meta:meta])* $vis:vis $($kw:ident)+ ($($args:tt)*) $(-> $ret:ty)?;
+		$($rest:tt)*
+	) => {
+		$crate::delegate!(@fn [$($target)+] [$(#[$meta])* $vis] [$($kw)+] ($($args)*) [$($ret)?]);
+		$crate::delegate!(@item [$($target)+] $($rest)*);
+	};
+
+	(@fn $target:tt $attrs:tt [fn $name:ident] $args:tt $ret:tt) => {
+		$crate::delegate!(@sig $target $attrs [] [] [] $name $args $ret);
+	};
+	(@fn $target:tt $attrs:tt [async fn $name:ident] $args:tt $ret:tt) => {
+		$crate::delegate!(@sig $target $attrs [async] [.await] [] $name $args $ret);
+	};
+	(@fn $target:tt $attrs:tt [try fn $name:ident] $args:tt $ret:tt) => {
+		$crate::delegate!(@sig $target $attrs [] [] [try] $name $args $ret);
+	};
+	(@fn $target:tt $attrs:tt [try async fn $name:ident] $args:tt $ret:tt) => {
+		$crate::delegate!(@sig $target $attrs [async] [.await] [try] $name $args $ret);
+	};
+
+	(@sig $target:tt [$($attrs:tt)*] [$($async:tt)?] $await:tt $try:tt $name:ident
+		($($args:tt)*) [$($ret:ty)?]
+	) => {
+		$($attrs)* $($async)? fn $name($($args)*) $(-> $ret)? {
+			$crate::delegate!(@call $try $target $name $await $($args)*)
+		}
+	};
+
+	(@call $try:tt $target:tt $name:ident $await:tt &self $(, $arg:ident: $ty:ty)*) => {
+		$crate::delegate!(@ret $try $target $name $await ($($arg),*))
+	};
+	(@call $try:tt $target:tt $name:ident $await:tt &mut self $(, $arg:ident: $ty:ty)*) => {
+		$crate::delegate!(@ret $try $target $name $await ($($arg),*))
+	};
+
+	(@ret [] [$($target:tt)+] $name:ident [$($await:tt)*] ($($arg:ident),*)) => {
+		$($target)+.$name($($arg),*) $($await)*
+	};
+	(@ret [try] [$($target:tt)+] $name:ident [$($await:tt)*] ($($arg:ident),*)) => {
+		Ok($($target)+.$name($($arg),*) $($await)*?)
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::result::AppResult;
+	use std::collections::HashMap;
+
+	struct Registry {
+		inner: HashMap<String, u32>,
+	}
+
+	impl Registry {
+		delegate! {
+			to self.inner {
+				pub fn insert(&mut self, k: String, v: u32) -> Option<u32>;
+				pub fn get(&self, k: &str) -> Option<&u32>;
+				pub fn remove(&mut self, k: &str) -> Option<u32>;
+				fn clear(&mut self);
+			}
+		}
+	}
+
+	struct Source;
+
+	impl Source {
+		async fn load(&self, id: u32) -> anyhow::Result<u32> {
+			anyhow::ensure!(id > 0, "id must be positive");
+			Ok(id * 10)
+		}
+
+		async fn fetch(&self, id: u32) -> anyhow::Result<u32> {
+			self.load(id).await
+		}
+
+		fn parse(&self, s: &str) -> Result<u32, std::num::ParseIntError> {
+			s.parse()
+		}
+	}
+
+	struct Repo {
+		source: Source,
+	}
+
+	impl Repo {
+		delegate! {
+			to self.source {
+				async fn load(&self, id: u32) -> anyhow::Result<u32>;
+				try fn parse(&self, s: &str) -> anyhow::Result<u32>;
+			}
+		}
+
+		delegate! {
+			to self.source {
+				/// `load` with the error mapped into `AppError`
+				try async fn fetch(&self, id: u32) -> AppResult<u32>;
+			}
+		}
+	}
+
+	#[test]
+	fn test_delegate_map() {
+		let mut registry = Registry {
+			inner: HashMap::new(),
+		};
+		assert_eq!(registry.insert("a".to_string(), 1), None);
+		assert_eq!(registry.insert("a".to_string(), 2), Some(1));
+		assert_eq!(registry.get("a"), Some(&2));
+		assert_eq!(registry.remove("a"), Some(2));
+		assert_eq!(registry.get("a"), None);
+		registry.insert("b".to_string(), 3);
+		registry.clear();
+		assert!(registry.inner.is_empty());
+	}
+
+	#[tokio::test]
+	async fn test_delegate_async_try() {
+		let repo = Repo { source: Source };
+		assert_eq!(repo.load(2).await.unwrap(), 20);
+		assert!(repo.load(0).await.is_err());
+		assert_eq!(repo.fetch(3).await.unwrap(), 30);
+		assert!(repo.fetch(0).await.is_err());
+		assert_eq!(repo.parse("7").unwrap(), 7);
+		assert!(repo.parse("x").is_err());
+	}
+}
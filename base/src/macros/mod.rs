@@ -0,0 +1,4 @@
+/// Macros module
+///
+/// - Method delegation macro (delegate.rs)
+pub mod delegate;
@@ -0,0 +1,161 @@
+use crate::result::{AppError, DynErrCode};
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Semantic group of an error, independent of the concrete code enum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+	NotFound,
+	Unauthorized,
+	Forbidden,
+	Internal,
+	Other,
+}
+
+/// Codes registered with [`register_error_category`], checked before the naming rules
+static CATEGORY_CODES: LazyLock<RwLock<HashMap<&'static str, ErrorCategory>>> =
+	LazyLock::new(Default::default);
+
+/// `(category, code suffix, variant name fragment)`, e.g. `WebErr::NotFound` or a `..404` code
+const CATEGORY_RULES: [(ErrorCategory, &str, &str); 4] = [
+	(ErrorCategory::NotFound, "404", "NotFound"),
+	(ErrorCategory::Unauthorized, "401", "Unauthorized"),
+	(ErrorCategory::Forbidden, "403", "Forbidden"),
+	(ErrorCategory::Internal, "500", "Internal"),
+];
+
+/// Classifies `codes` as `category`, for codes the naming rules miss, e.g. `AUTH01`
+pub fn register_error_category(category: ErrorCategory, codes: &[&'static str]) {
+	let mut registry = CATEGORY_CODES.write().unwrap_or_else(|e| e.into_inner());
+	for code in codes {
+		registry.insert(code, category);
+	}
+}
+
+pub trait ErrorCodeCategory {
+	fn category(&self) -> ErrorCategory;
+}
+
+impl ErrorCodeCategory for DynErrCode {
+	/// Registered codes first, then a code ending in `404`/`401`/`403`/`500`, then the variant
+	/// name containing `NotFound`/`Unauthorized`/`Forbidden`/`Internal`
+	fn category(&self) -> ErrorCategory {
+		let code = self.code();
+		let registered = CATEGORY_CODES
+			.read()
+			.unwrap_or_else(|e| e.into_inner())
+			.get(code)
+			.copied();
+		if let Some(category) = registered {
+			return category;
+		}
+
+		let name = format!("{self:?}");
+		CATEGORY_RULES
+			.iter()
+			.find(|(_, suffix, fragment)| code.ends_with(suffix) || name.contains(fragment))
+			.map_or(ErrorCategory::Other, |(category, _, _)| *category)
+	}
+}
+
+impl ErrorCodeCategory for AppError {
+	/// `HttpErr` is classified by its status, other variants by their code
+	fn category(&self) -> ErrorCategory {
+		let code = match self {
+			AppError::ErrCode(code)
+			| AppError::ExtCode(code, _)
+			| AppError::Anyhow(code, _)
+			| AppError::ExtAnyhow(code, _, _) => *code,
+			#[cfg(feature = "http")]
+			AppError::HttpErr(code, status) => match status.as_u16() {
+				404 => return ErrorCategory::NotFound,
+				401 => return ErrorCategory::Unauthorized,
+				403 => return ErrorCategory::Forbidden,
+				_ if status.is_server_error() => return ErrorCategory::Internal,
+				_ => *code,
+			},
+		};
+		code.category()
+	}
+}
+
+impl AppError {
+	pub fn is_not_found(&self) -> bool {
+		self.category() == ErrorCategory::NotFound
+	}
+
+	pub fn is_unauthorized(&self) -> bool {
+		self.category() == ErrorCategory::Unauthorized
+	}
+
+	pub fn is_forbidden(&self) -> bool {
+		self.category() == ErrorCategory::Forbidden
+	}
+
+	pub fn is_internal(&self) -> bool {
+		self.category() == ErrorCategory::Internal
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::gen_impl_code_enum;
+	use crate::result::{ErrorCode, SysErr};
+
+	gen_impl_code_enum! {
+		CatErr {
+			UserNotFound = ("USR404", "User not found"),
+			MissingToken = ("TKN401", "Missing token"),
+			AccessForbidden = ("ACL001", "Access forbidden"),
+			TokenExpired = ("TKN002", "Token expired"),
+		}
+	}
+
+	#[test]
+	fn test_code_predicates() {
+		let not_found = AppError::ErrCode(&CatErr::UserNotFound);
+		assert!(not_found.is_not_found());
+		assert!(!not_found.is_unauthorized() && !not_found.is_internal());
+
+		let unauthorized = AppError::ExtCode(&CatErr::MissingToken, "no bearer".to_string());
+		assert!(unauthorized.is_unauthorized());
+		assert!(!unauthorized.is_forbidden());
+
+		let forbidden = AppError::Anyhow(&CatErr::AccessForbidden, anyhow::anyhow!("denied"));
+		assert!(forbidden.is_forbidden());
+		assert!(!forbidden.is_not_found());
+
+		let internal = AppError::ErrCode(&SysErr::InternalError);
+		assert!(internal.is_internal());
+		assert_eq!(internal.category(), ErrorCategory::Internal);
+
+		let other = AppError::ErrCode(&SysErr::InvalidParams);
+		assert_eq!(other.category(), ErrorCategory::Other);
+		assert!(!other.is_not_found() && !other.is_unauthorized());
+		assert!(!other.is_forbidden() && !other.is_internal());
+	}
+
+	#[test]
+	fn test_registered_codes() {
+		let expired = AppError::ErrCode(&CatErr::TokenExpired);
+		assert_eq!(expired.category(), ErrorCategory::Other);
+		register_error_category(ErrorCategory::Unauthorized, &[CatErr::TokenExpired.code()]);
+		assert!(expired.is_unauthorized());
+	}
+
+	#[cfg(feature = "http")]
+	#[test]
+	fn test_http_status() {
+		use http::StatusCode;
+
+		let err = AppError::HttpErr(&SysErr::InvalidParams, StatusCode::NOT_FOUND);
+		assert!(err.is_not_found());
+		let err = AppError::HttpErr(&SysErr::InvalidParams, StatusCode::FORBIDDEN);
+		assert!(err.is_forbidden());
+		let err = AppError::HttpErr(&SysErr::InvalidParams, StatusCode::BAD_GATEWAY);
+		assert!(err.is_internal());
+		let err = AppError::HttpErr(&CatErr::UserNotFound, StatusCode::BAD_REQUEST);
+		assert!(err.is_not_found());
+	}
+}
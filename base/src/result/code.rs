@@ -5,6 +5,99 @@ pub type DynErrCode = dyn ErrorCode + Send + Sync + 'static;
 pub trait ErrorCode: Debug + Display + Sync + Send + 'static {
 	fn code(&self) -> &'static str;
 	fn message(&self) -> &'static str;
+
+	/// The message as declared, may hold `{0}` / `{name}` placeholders for [`Self::msg_with`]
+	fn message_template(&self) -> &'static str {
+		self.message()
+	}
+
+	/// The message with its placeholders filled from `args`, pass it to `err!` / `app_err!` as
+	/// the ext string
+	///
+	/// `{0}`, `{1}` index into `args`, `{name}` placeholders take them in order of first
+	/// appearance. A placeholder without an arg is kept as is, `{{` and `}}` are literal braces.
+	fn msg_with(&self, args: &[&dyn Display]) -> String {
+		fill_template(self.message_template(), args)
+	}
+
+	/// Message of an error carrying `ext`: the formatted message itself for a code with
+	/// placeholders, else the message followed by `ext`
+	fn message_with_ext(&self, ext: &str) -> String {
+		if has_placeholders(self.message_template()) {
+			ext.to_string()
+		} else {
+			format!("{} {}", self.message(), ext)
+		}
+	}
+}
+
+/// Splits `template` into literal text and `{...}` placeholder names
+fn parse_template(template: &str) -> Vec<(&str, bool)> {
+	let mut parts = vec![];
+	let mut rest = template;
+	while let Some(pos) = rest.find(['{', '}']) {
+		let (text, tail) = rest.split_at(pos);
+		if !text.is_empty() {
+			parts.push((text, false));
+		}
+		if tail.starts_with("{{") || tail.starts_with("}}") {
+			parts.push((&tail[..1], false));
+			rest = &tail[2..];
+			continue;
+		}
+		let name_end = tail[1..].find('}').map(|end| end + 1);
+		match name_end {
+			Some(end)
+				if tail.starts_with('{')
+					&& end > 1 && tail[1..end]
+					.chars()
+					.all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+			{
+				parts.push((&tail[1..end], true));
+				rest = &tail[end + 1..];
+			}
+			_ => {
+				parts.push((&tail[..1], false));
+				rest = &tail[1..];
+			}
+		}
+	}
+	if !rest.is_empty() {
+		parts.push((rest, false));
+	}
+	parts
+}
+
+fn has_placeholders(template: &str) -> bool {
+	parse_template(template)
+		.iter()
+		.any(|(_, placeholder)| *placeholder)
+}
+
+fn fill_template(template: &str, args: &[&dyn Display]) -> String {
+	let mut names: Vec<&str> = vec![];
+	let mut out = String::with_capacity(template.len());
+	for (part, placeholder) in parse_template(template) {
+		if !placeholder {
+			out.push_str(part);
+			continue;
+		}
+		let index = part.parse::<usize>().ok().unwrap_or_else(|| {
+			names.iter().position(|n| *n == part).unwrap_or_else(|| {
+				names.push(part);
+				names.len() - 1
+			})
+		});
+		match args.get(index) {
+			Some(arg) => out.push_str(&arg.to_string()),
+			None => {
+				out.push('{');
+				out.push_str(part);
+				out.push('}');
+			}
+		}
+	}
+	out
 }
 
 #[macro_export]
@@ -89,18 +182,67 @@ gen_impl_code_enum! {
 		NoCfgFile = ("CFG001", "Config path not specified"),
 		ConfigLoadFailed = ("CFG002", "Config load failed"),
 
+		InvalidLogDirective = ("LOG001", "Invalid log directive"),
+		InvalidSamplingRate = ("LOG002", "Log sampling rate not in (0.0, 1.0]"),
+
 		MutexLockErr = ("MUTEX1", "Cannot currently handle a poisoned lock"),
 
 		ServerBindErr = ("SVR001", "Server bind failed"),
 		ServerStartErr = ("SVR002", "Server start failed"),
 
 		SystemTimeError = ("TIME001", "System time error"),
+		DeadlineExceeded = ("TIME002", "Request deadline exceeded"),
+
+		TaskJoinErr = ("TASK01", "Task panicked or was cancelled"),
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use crate::result::{ErrorCode, SysErr};
+	use crate::result::{AppError, AppResult, ErrorCode, RespData, SysErr};
+
+	gen_impl_code_enum! {
+		OrderErr {
+			Closed = ("ORD001", "Order is closed"),
+			NotFound = ("ORD002", "Order {0} not found"),
+			QtyExceeded = ("ORD003", "Qty {qty} exceeds stock {stock} of {qty}"),
+		}
+	}
+
+	fn find(id: u64) -> AppResult<()> {
+		crate::err!(&OrderErr::NotFound, OrderErr::NotFound.msg_with(&[&id]))
+	}
+
+	#[test]
+	fn test_message_template() {
+		// without placeholders the ext string is appended as before
+		let err = crate::app_err!(&OrderErr::Closed, "id 7");
+		assert_eq!(err.to_string(), "ErrCode[ORD001] Order is closed id 7");
+		assert_eq!(OrderErr::Closed.msg_with(&[&7]), "Order is closed");
+
+		let err = find(42).unwrap_err();
+		assert_eq!(err.to_string(), "ErrCode[ORD002] Order 42 not found");
+		assert_eq!(err.get_reason(), "Order 42 not found");
+		assert_eq!(RespData::with_app_error(err).msg, "Order 42 not found");
+		assert_eq!(OrderErr::NotFound.message_template(), "Order {0} not found");
+
+		let code = &OrderErr::QtyExceeded;
+		let err = AppError::ExtCode(code, code.msg_with(&[&5, &"3"]));
+		assert_eq!(
+			err.to_string(),
+			"ErrCode[ORD003] Qty 5 exceeds stock 3 of 5"
+		);
+		// a missing arg keeps its placeholder
+		assert_eq!(code.msg_with(&[&5]), "Qty 5 exceeds stock {stock} of 5");
+	}
+
+	#[test]
+	fn test_template_escapes() {
+		assert_eq!(super::fill_template("{{0}} is {0}", &[&1]), "{0} is 1");
+		assert_eq!(super::fill_template("{} and { x }", &[&1]), "{} and { x }");
+		assert!(!super::has_placeholders("{{literal}}"));
+		assert!(!super::has_placeholders(SysErr::InvalidParams.message()));
+	}
 
 	#[test]
 	fn test() {
@@ -199,7 +199,7 @@ impl Debug for AppError {
 			AppError::ExtCode(code, ext) => f
 				.debug_struct("AppError")
 				.field("code", &code.code())
-				.field("msg", &format!("{} {}", &code.message(), ext))
+				.field("msg", &code.message_with_ext(ext))
 				.finish(),
 			AppError::Anyhow(code, e) => f
 				.debug_struct("AppError")
@@ -210,7 +210,7 @@ impl Debug for AppError {
 			AppError::ExtAnyhow(code, ext, e) => f
 				.debug_struct("AppError")
 				.field("code", &code.code())
-				.field("msg", &format!("{} {}", &code.message(), ext))
+				.field("msg", &code.message_with_ext(ext))
 				.field("error", e)
 				.finish(),
 			#[cfg(feature = "http")]
@@ -231,7 +231,7 @@ impl Display for AppError {
 				write!(f, "ErrCode[{}] {}", code.code(), code.message())
 			}
 			AppError::ExtCode(code, ext) => {
-				write!(f, "ErrCode[{}] {} {}", code.code(), code.message(), ext)
+				write!(f, "ErrCode[{}] {}", code.code(), code.message_with_ext(ext))
 			}
 			AppError::Anyhow(code, e) => {
 				write!(f, "ErrCode[{}] {}, error: {e}", code.code(), code.message(),)
@@ -239,10 +239,9 @@ impl Display for AppError {
 			AppError::ExtAnyhow(code, ext, e) => {
 				write!(
 					f,
-					"ErrCode[{}] {} {}, error: {e}",
+					"ErrCode[{}] {}, error: {e}",
 					code.code(),
-					code.message(),
-					ext
+					code.message_with_ext(ext)
 				)
 			}
 			#[cfg(feature = "http")]
@@ -263,9 +262,11 @@ impl AppError {
 	pub fn get_reason(&self) -> String {
 		match self {
 			AppError::ErrCode(code) => format!("{}", &code.message()),
-			AppError::ExtCode(code, ext) => format!("{} {ext}", &code.message()),
+			AppError::ExtCode(code, ext) => code.message_with_ext(ext),
 			AppError::Anyhow(code, e) => format!("{}, reason: {e}", code.message()),
-			AppError::ExtAnyhow(code, ext, e) => format!("{} {ext}, reason: {e}", code.message()),
+			AppError::ExtAnyhow(code, ext, e) => {
+				format!("{}, reason: {e}", code.message_with_ext(ext))
+			}
 			#[cfg(feature = "http")]
 			AppError::HttpErr(code, status) => format!(
 				"HttpStatus [{}] ErrCode[{}] message: {}",
@@ -277,6 +278,60 @@ impl AppError {
 	}
 }
 
+impl AppError {
+	pub fn err_code(&self) -> &'static DynErrCode {
+		match self {
+			AppError::ErrCode(code)
+			| AppError::ExtCode(code, _)
+			| AppError::Anyhow(code, _)
+			| AppError::ExtAnyhow(code, _, _) => *code,
+			#[cfg(feature = "http")]
+			AppError::HttpErr(code, _) => *code,
+		}
+	}
+
+	/// Code message plus the extra message, if any
+	fn full_message(&self) -> String {
+		match self {
+			AppError::ExtCode(code, ext) | AppError::ExtAnyhow(code, ext, _) => {
+				code.message_with_ext(ext)
+			}
+			_ => self.err_code().message().to_string(),
+		}
+	}
+
+	/// The `anyhow` chain outermost first, the status for `HttpErr`, empty otherwise
+	fn detail(&self) -> Vec<String> {
+		match self {
+			AppError::Anyhow(_, e) | AppError::ExtAnyhow(_, _, e) => {
+				e.chain().map(ToString::to_string).collect()
+			}
+			#[cfg(feature = "http")]
+			AppError::HttpErr(_, status) => vec![format!("HttpStatus [{status}]")],
+			_ => vec![],
+		}
+	}
+
+	/// `{"code", "message", "detail"}` for JSON logs, `detail` being [`Self::detail`]
+	pub fn to_json_value(&self) -> serde_json::Value {
+		serde_json::json!({
+			"code": self.err_code().code(),
+			"message": self.full_message(),
+			"detail": self.detail(),
+		})
+	}
+
+	/// `err_code`, `err_msg` and `err_detail` (the detail joined by `": "`) for structured
+	/// `tracing` fields
+	pub fn to_log_fields(&self) -> Vec<(&'static str, String)> {
+		vec![
+			("err_code", self.err_code().code().to_string()),
+			("err_msg", self.full_message()),
+			("err_detail", self.detail().join(": ")),
+		]
+	}
+}
+
 impl<T: ErrorCode> From<&'static T> for AppError {
 	fn from(value: &'static T) -> Self {
 		AppError::ErrCode(value)
@@ -293,3 +348,85 @@ impl From<anyhow::Error> for AppError {
 		AppError::Anyhow(&SysErr::InternalError, err)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	crate::gen_impl_code_enum! {
+		JsonErr {
+			Broken = ("JSON01", "Broken"),
+		}
+	}
+
+	fn chained() -> anyhow::Error {
+		anyhow!("disk full").context("write failed")
+	}
+
+	fn fields(err: &AppError) -> Vec<String> {
+		err.to_log_fields().into_iter().map(|(_, v)| v).collect()
+	}
+
+	#[test]
+	fn test_to_json_value() {
+		let cases = [
+			(AppError::ErrCode(&JsonErr::Broken), "Broken", vec![]),
+			(
+				AppError::ExtCode(&JsonErr::Broken, "id=1".into()),
+				"Broken id=1",
+				vec![],
+			),
+			(
+				AppError::Anyhow(&JsonErr::Broken, chained()),
+				"Broken",
+				vec!["write failed", "disk full"],
+			),
+			(
+				AppError::ExtAnyhow(&JsonErr::Broken, "id=1".into(), chained()),
+				"Broken id=1",
+				vec!["write failed", "disk full"],
+			),
+		];
+		for (err, message, detail) in cases {
+			let display = err.to_string();
+			assert_eq!(
+				err.to_json_value(),
+				json!({ "code": "JSON01", "message": message, "detail": detail })
+			);
+			assert!(
+				display.starts_with(&format!("ErrCode[JSON01] {message}")),
+				"{display}"
+			);
+			if let Some(outer) = detail.first() {
+				assert!(display.ends_with(&format!("error: {outer}")), "{display}");
+			}
+
+			let keys = err
+				.to_log_fields()
+				.into_iter()
+				.map(|(k, _)| k)
+				.collect::<Vec<_>>();
+			assert_eq!(keys, ["err_code", "err_msg", "err_detail"]);
+			assert_eq!(
+				fields(&err),
+				["JSON01", message, detail.join(": ").as_str()]
+			);
+		}
+	}
+
+	#[cfg(feature = "http")]
+	#[test]
+	fn test_http_err_json_value() {
+		let err = AppError::HttpErr(&JsonErr::Broken, StatusCode::NOT_FOUND);
+		assert_eq!(
+			err.to_json_value(),
+			json!({ "code": "JSON01", "message": "Broken", "detail": ["HttpStatus [404 Not Found]"] })
+		);
+		assert!(err.to_string().starts_with("HttpStatus [404 Not Found]"));
+		assert_eq!(
+			fields(&err),
+			["JSON01", "Broken", "HttpStatus [404 Not Found]"]
+		);
+	}
+}
@@ -1,7 +1,9 @@
+mod category;
 mod code;
 mod error;
 mod resp;
 
+pub use category::*;
 pub use code::*;
 pub use error::*;
 pub use resp::*;
@@ -46,11 +48,32 @@ where
 	}
 }
 
+/// Inspect the error without consuming it, keeps `?` chains readable
+///
+/// `query.one(db).await.tap_err(log_err_code(&DBErr::QueryFailed))?`
+pub trait TapResult<T, E> {
+	fn tap_err<F: FnOnce(&E)>(self, f: F) -> Result<T, E>;
+}
+
+impl<T, E> TapResult<T, E> for Result<T, E> {
+	fn tap_err<F: FnOnce(&E)>(self, f: F) -> Result<T, E> {
+		if let Err(e) = &self {
+			f(e);
+		}
+		self
+	}
+}
+
+/// Log the error with an extra error code, for use with [`TapResult::tap_err`]
+pub fn log_err_code(code: &'static DynErrCode) -> impl FnOnce(&AppError) {
+	move |err| tracing::error!("{} {}", code, err)
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::logger::init_tracing;
 	use crate::nar_err;
-	use crate::result::AnyhowCtx;
+	use crate::result::{AnyhowCtx, AppError, ErrorCode, TapResult, log_err_code};
 
 	crate::gen_impl_code_enum! {
 		TstErr {
@@ -72,4 +95,21 @@ mod tests {
 			}
 		}
 	}
+
+	#[test]
+	fn test_tap_err() {
+		let mut count = 0;
+		let res = Result::<(), AppError>::Err(AppError::ErrCode(&TstErr::TestErr))
+			.tap_err(|_| count += 1)
+			.tap_err(log_err_code(&TstErr::TestErr));
+		assert_eq!(count, 1);
+		match res {
+			Err(AppError::ErrCode(code)) => assert_eq!(code.code(), "TEST01"),
+			other => panic!("unexpected result: {other:?}"),
+		}
+
+		let ok = Result::<u8, AppError>::Ok(1).tap_err(|_| count += 1);
+		assert_eq!(ok.unwrap(), 1);
+		assert_eq!(count, 1);
+	}
 }
@@ -1,11 +1,17 @@
 use crate::result::{AppError, DynErrCode, ErrorCode, SysErr};
-use serde::Serialize;
+use crate::validator::{FieldError, FieldErrors};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "utoipa")]
+use utoipa::ToSchema;
 
 #[derive(Debug, Clone, Serialize)]
 pub struct RespData<T> {
 	pub code: String,
 	pub msg: String,
 	pub data: Option<T>,
+	/// Failing fields of a validation or business error
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub errors: Option<Vec<FieldError>>,
 }
 
 impl<T> RespData<T> {
@@ -15,26 +21,62 @@ impl<T> RespData<T> {
 			code: success.code().into(),
 			msg: success.message().into(),
 			data: Some(data),
+			errors: None,
+		}
+	}
+
+	/// `RespData::success(items).with_meta(PageMeta { total, page, page_size })`
+	pub fn with_meta<M: Serialize>(self, meta: M) -> RespDataWithMeta<T, M> {
+		RespDataWithMeta {
+			code: self.code,
+			msg: self.msg,
+			data: self.data,
+			meta,
 		}
 	}
 }
 
+/// [`RespData`] with metadata such as [`PageMeta`], serialized next to `data`
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[derive(Debug, Clone, Serialize)]
+pub struct RespDataWithMeta<T, M> {
+	pub code: String,
+	pub msg: String,
+	pub data: Option<T>,
+	pub meta: M,
+}
+
+/// Pagination metadata for [`RespData::with_meta`]
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PageMeta {
+	/// Total record count
+	pub total: u64,
+	/// Current page number, starting from 1
+	pub page: u64,
+	/// Page size
+	pub page_size: u64,
+}
+
 impl RespData<()> {
 	pub fn with_code(code: &DynErrCode) -> Self {
 		Self {
 			code: code.code().into(),
 			msg: code.message().into(),
 			data: None,
+			errors: None,
 		}
 	}
 	pub fn with_ext_code(code: &DynErrCode, ext: String) -> Self {
 		Self {
 			code: code.code().into(),
-			msg: format!("{} {}", code.message(), ext),
+			msg: code.message_with_ext(&ext),
 			data: None,
+			errors: None,
 		}
 	}
 
+	/// `errors` is filled when `e` is a [`FieldErrors`]
 	pub fn with_anyhow(code: &DynErrCode, e: anyhow::Error) -> Self {
 		let msg = if code.message().is_empty() {
 			format!("{e}")
@@ -45,14 +87,26 @@ impl RespData<()> {
 			code: code.code().into(),
 			msg,
 			data: None,
+			errors: field_errors(&e),
 		}
 	}
 
 	pub fn with_ext_anyhow(code: &DynErrCode, ext: String, e: anyhow::Error) -> Self {
 		Self {
 			code: code.code().into(),
-			msg: format!("{} {}: {}", code.message(), ext, e),
+			msg: format!("{}: {}", code.message_with_ext(&ext), e),
+			data: None,
+			errors: field_errors(&e),
+		}
+	}
+
+	/// `RespData::fail_with_fields(&SysErr::InvalidParams, errors.into_fields())`
+	pub fn fail_with_fields(code: &DynErrCode, fields: Vec<FieldError>) -> Self {
+		Self {
+			code: code.code().into(),
+			msg: code.message().into(),
 			data: None,
+			errors: Some(fields),
 		}
 	}
 
@@ -72,6 +126,62 @@ impl RespData<()> {
 			code: code.into(),
 			msg: msg.into(),
 			data: None,
+			errors: None,
 		}
 	}
 }
+
+fn field_errors(e: &anyhow::Error) -> Option<Vec<FieldError>> {
+	e.downcast_ref::<FieldErrors>()
+		.map(|errors| errors.fields().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn test_with_meta_json() {
+		let meta = PageMeta {
+			total: 12,
+			page: 2,
+			page_size: 5,
+		};
+		let resp = RespData::success(vec![6, 7]).with_meta(meta);
+		let value = serde_json::to_value(&resp).unwrap();
+		assert_eq!(value["data"], json!([6, 7]));
+		assert_eq!(
+			value["meta"],
+			json!({ "total": 12, "page": 2, "page_size": 5 })
+		);
+		assert_eq!(value["code"], SysErr::Success.code());
+		assert_eq!(value.as_object().unwrap().len(), 4);
+	}
+
+	#[test]
+	fn test_field_errors_json() {
+		let value = serde_json::to_value(RespData::with_code(&SysErr::InvalidParams)).unwrap();
+		assert_eq!(value.as_object().unwrap().len(), 3);
+		assert!(value.get("errors").is_none());
+
+		let mut errors = FieldErrors::new();
+		errors.add("email", "must contain `@`");
+		let fields = errors.clone().into_fields();
+		let resp = RespData::fail_with_fields(&SysErr::InvalidParams, fields);
+		let value = serde_json::to_value(&resp).unwrap();
+		assert_eq!(value["code"], SysErr::InvalidParams.code());
+		assert_eq!(value["data"], json!(null));
+		assert_eq!(
+			value["errors"],
+			json!([{ "field": "email", "code": "", "msg": "must contain `@`" }])
+		);
+
+		let Err(err) = errors.into_result(&SysErr::InvalidParams) else {
+			unreachable!()
+		};
+		let value = serde_json::to_value(RespData::with_app_error(err)).unwrap();
+		assert_eq!(value["errors"][0]["field"], "email");
+		assert_eq!(value["errors"][0]["code"], SysErr::InvalidParams.code());
+	}
+}
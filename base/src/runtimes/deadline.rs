@@ -0,0 +1,110 @@
+use crate::result::{AppResult, SysErr};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+tokio::task_local! {
+	static DEADLINE: Deadline;
+}
+
+/// Point in time by which the current request must be answered, carried in a task local so
+/// downstream db and http calls can stop early instead of outliving the request
+///
+/// Everything here is a no-op outside of [`Deadline::scope`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+	pub fn after(timeout: Duration) -> Self {
+		Self(Instant::now() + timeout)
+	}
+
+	pub fn at(instant: Instant) -> Self {
+		Self(instant)
+	}
+
+	pub fn instant(&self) -> Instant {
+		self.0
+	}
+
+	/// Time left, zero once passed
+	pub fn time_left(&self) -> Duration {
+		self.0.saturating_duration_since(Instant::now())
+	}
+
+	pub fn is_expired(&self) -> bool {
+		Instant::now() >= self.0
+	}
+
+	/// Runs `fut` with this deadline, or the current one if that ends earlier
+	pub async fn scope<F: Future>(self, fut: F) -> F::Output {
+		let deadline = match Self::current() {
+			Some(current) if current.0 < self.0 => current,
+			_ => self,
+		};
+		DEADLINE.scope(deadline, fut).await
+	}
+
+	/// Deadline of the current task, if any
+	pub fn current() -> Option<Self> {
+		DEADLINE.try_with(|d| *d).ok()
+	}
+
+	/// Time left of the current deadline, `None` without one
+	pub fn remaining() -> Option<Duration> {
+		Self::current().map(|d| d.time_left())
+	}
+
+	/// `timeout` capped by the time left of the current deadline
+	pub fn cap(timeout: Duration) -> Duration {
+		Self::remaining().map_or(timeout, |left| left.min(timeout))
+	}
+
+	/// [`SysErr::DeadlineExceeded`] once the current deadline has passed
+	pub fn check() -> AppResult<()> {
+		match Self::current() {
+			Some(d) if d.is_expired() => crate::err!(&SysErr::DeadlineExceeded),
+			_ => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::result::ErrorCode;
+
+	#[tokio::test]
+	async fn test_no_deadline() {
+		assert_eq!(Deadline::current(), None);
+		assert_eq!(Deadline::remaining(), None);
+		assert_eq!(Deadline::cap(Duration::from_secs(3)), Duration::from_secs(3));
+		assert!(Deadline::check().is_ok());
+	}
+
+	#[tokio::test]
+	async fn test_scope() {
+		let outer = Deadline::after(Duration::from_millis(50));
+		outer
+			.scope(async move {
+				assert_eq!(Deadline::current(), Some(outer));
+				assert!(Deadline::cap(Duration::from_secs(3)) <= Duration::from_millis(50));
+
+				// a later inner deadline keeps the outer one
+				Deadline::after(Duration::from_secs(10))
+					.scope(async move { assert_eq!(Deadline::current(), Some(outer)) })
+					.await;
+				let inner = Deadline::after(Duration::from_millis(10));
+				inner
+					.scope(async move { assert_eq!(Deadline::current(), Some(inner)) })
+					.await;
+
+				tokio::time::sleep(Duration::from_millis(60)).await;
+				assert_eq!(Deadline::remaining(), Some(Duration::ZERO));
+				let err = Deadline::check().unwrap_err();
+				assert_eq!(err.err_code().code(), SysErr::DeadlineExceeded.code());
+			})
+			.await;
+		assert_eq!(Deadline::current(), None);
+	}
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "tokio-pool")]
+mod deadline;
 #[cfg(feature = "rayon-pool")]
 mod rayon;
 #[cfg(feature = "tokio-pool")]
@@ -5,6 +7,8 @@ mod task_util;
 #[cfg(feature = "tokio-pool")]
 mod tokio;
 
+#[cfg(feature = "tokio-pool")]
+pub use deadline::*;
 #[cfg(feature = "rayon-pool")]
 pub use rayon::*;
 #[cfg(feature = "tokio-pool")]
@@ -0,0 +1,332 @@
+use crate::map_err;
+use crate::result::AppResult;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::span::{Attributes, Id};
+use tracing::{Subscriber, error};
+use tracing_appender::rolling;
+use tracing_subscriber::Layer;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Tracing target of [`TracingAuditSink`] events
+pub const AUDIT_TARGET: &str = "audit";
+
+crate::gen_impl_code_enum! {
+	AuditErr {
+		AuditEncodeErr = ("AUDIT01", "Audit event encode failed"),
+		AuditWriteErr = ("AUDIT02", "Audit sink write failed"),
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+	Success,
+	Failure,
+	Denied,
+}
+
+impl Display for AuditOutcome {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let outcome = match self {
+			Self::Success => "success",
+			Self::Failure => "failure",
+			Self::Denied => "denied",
+		};
+		f.write_str(outcome)
+	}
+}
+
+/// One sensitive operation, who did what to which resource and how it ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+	pub actor: String,
+	pub action: String,
+	pub resource: String,
+	pub outcome: AuditOutcome,
+	#[serde(default)]
+	pub detail: serde_json::Value,
+	/// Unix millis
+	pub ts: i64,
+	/// `tid` of the current span, see [`TidLayer`]
+	pub tid: Option<String>,
+}
+
+impl AuditEvent {
+	/// Stamped with the current time and [`current_tid`]
+	pub fn new(
+		actor: impl Into<String>,
+		action: impl Into<String>,
+		resource: impl Into<String>,
+		outcome: AuditOutcome,
+	) -> Self {
+		let ts = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|d| d.as_millis() as i64)
+			.unwrap_or_default();
+		Self {
+			actor: actor.into(),
+			action: action.into(),
+			resource: resource.into(),
+			outcome,
+			detail: serde_json::Value::Null,
+			ts,
+			tid: current_tid(),
+		}
+	}
+
+	pub fn with_detail(self, detail: serde_json::Value) -> Self {
+		Self { detail, ..self }
+	}
+}
+
+/// Append-only destination of audit events
+pub trait AuditSink: Send + Sync {
+	fn record(&self, event: &AuditEvent) -> AppResult<()>;
+}
+
+static AUDIT_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// Events a sink failed to record, see [`record_audit`]
+pub fn audit_failures() -> u64 {
+	AUDIT_FAILURES.load(Ordering::Relaxed)
+}
+
+/// Records `event` on `sink`. A failure is counted in [`audit_failures`] and logged with the
+/// whole event, so it still ends up in the regular log.
+pub fn record_audit<S: AuditSink + ?Sized>(sink: &S, event: AuditEvent) -> AppResult<()> {
+	let res = sink.record(&event);
+	if let Err(e) = &res {
+		AUDIT_FAILURES.fetch_add(1, Ordering::Relaxed);
+		let event = serde_json::to_string(&event).unwrap_or_else(|_| format!("{event:?}"));
+		error!(audit = %event, "audit sink failed: {e}");
+	}
+	res
+}
+
+/// Writes each event as one json line on the [`AUDIT_TARGET`] tracing target, see
+/// [`Logger::audit`](crate::logger::Logger::audit) to route them to their own file
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingAuditSink;
+
+impl AuditSink for TracingAuditSink {
+	fn record(&self, event: &AuditEvent) -> AppResult<()> {
+		let json = serde_json::to_string(event).map_err(map_err!(&AuditErr::AuditEncodeErr))?;
+		tracing::info!(target: AUDIT_TARGET, "{json}");
+		Ok(())
+	}
+}
+
+/// Layer writing only [`AUDIT_TARGET`] events to `{dir}/audit.log`, rotated daily
+///
+/// The file is written synchronously, a buffered writer could lose events on exit.
+pub fn audit_file_layer<S>(dir: impl AsRef<Path>) -> impl Layer<S>
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	tracing_subscriber::fmt::layer()
+		.with_ansi(false)
+		.with_target(false)
+		.with_level(false)
+		.with_writer(rolling::daily(dir.as_ref(), "audit.log"))
+		.with_filter(Targets::new().with_target(AUDIT_TARGET, tracing::Level::INFO))
+}
+
+struct Tid(String);
+
+/// Keeps the `tid` field of new spans, e.g. the request id of the `api` span, for [`current_tid`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TidLayer;
+
+impl<S> Layer<S> for TidLayer
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+		let mut visitor = TidVisitor(None);
+		attrs.record(&mut visitor);
+		if let (Some(tid), Some(span)) = (visitor.0, ctx.span(id)) {
+			span.extensions_mut().insert(Tid(tid));
+		}
+	}
+}
+
+struct TidVisitor(Option<String>);
+
+impl tracing::field::Visit for TidVisitor {
+	fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+		if field.name() == "tid" {
+			self.0 = Some(value.to_string());
+		}
+	}
+
+	fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+		if field.name() == "tid" {
+			self.0 = Some(format!("{value:?}"));
+		}
+	}
+}
+
+/// `tid` of the innermost current span carrying one, needs [`TidLayer`] on a registry
+/// subscriber
+pub fn current_tid() -> Option<String> {
+	tracing::Span::current().with_subscriber(|(id, dispatch)| {
+		let registry = dispatch.downcast_ref::<tracing_subscriber::Registry>()?;
+		let span = registry.span(id)?;
+		span.scope()
+			.find_map(|span| span.extensions().get::<Tid>().map(|tid| tid.0.clone()))
+	})?
+}
+
+/// Records an [`AuditEvent`] on a sink with [`record_audit`], `tid` from the current span
+///
+/// ```ignore
+/// audit!(sink, actor: user, action: "user.delete", resource: id, outcome: AuditOutcome::Success,
+///     detail: { "reason": reason });
+/// ```
+#[macro_export]
+macro_rules! audit {
+	($sink:expr, actor: $actor:expr, action: $action:expr, resource: $resource:expr,
+		outcome: $outcome:expr $(, detail: $detail:tt)? $(,)?) => {
+		$crate::tools::audit::record_audit(
+			&$sink,
+			$crate::tools::audit::AuditEvent::new($actor, $action, $resource, $outcome)
+				$(.with_detail($crate::serde_json::json!($detail)))?,
+		)
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::result::ErrorCode;
+	use std::sync::{Arc, Mutex};
+	use tracing_subscriber::Registry;
+	use tracing_subscriber::layer::SubscriberExt;
+
+	#[derive(Default)]
+	struct MemSink(Mutex<Vec<AuditEvent>>);
+
+	impl AuditSink for MemSink {
+		fn record(&self, event: &AuditEvent) -> AppResult<()> {
+			self.0.lock().unwrap().push(event.clone());
+			Ok(())
+		}
+	}
+
+	struct FailingSink;
+
+	impl AuditSink for FailingSink {
+		fn record(&self, _event: &AuditEvent) -> AppResult<()> {
+			crate::err!(&AuditErr::AuditWriteErr)
+		}
+	}
+
+	#[derive(Clone, Default)]
+	struct VecWriter(Arc<Mutex<Vec<u8>>>);
+
+	impl std::io::Write for VecWriter {
+		fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+			self.0.lock().unwrap().extend_from_slice(buf);
+			Ok(buf.len())
+		}
+
+		fn flush(&mut self) -> std::io::Result<()> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn test_macro_fields() {
+		let sink = MemSink::default();
+		let subscriber = Registry::default().with(TidLayer);
+		tracing::subscriber::with_default(subscriber, || {
+			let span = tracing::info_span!("api", tid = "req-1");
+			let _api = span.enter();
+			let _inner = tracing::info_span!("handler").entered();
+			audit!(sink, actor: "ann", action: "user.delete", resource: format!("user/{}", 7),
+				outcome: AuditOutcome::Success, detail: { "reason": "gdpr", "hard": true })
+			.unwrap();
+		});
+		audit!(sink, actor: "bob", action: "login", resource: "session", outcome: AuditOutcome::Denied)
+			.unwrap();
+
+		let events = sink.0.lock().unwrap();
+		let event = &events[0];
+		assert_eq!(
+			(
+				event.actor.as_str(),
+				event.action.as_str(),
+				event.resource.as_str()
+			),
+			("ann", "user.delete", "user/7")
+		);
+		assert_eq!(event.outcome, AuditOutcome::Success);
+		assert_eq!(
+			event.detail,
+			serde_json::json!({ "reason": "gdpr", "hard": true })
+		);
+		assert_eq!(event.tid.as_deref(), Some("req-1"));
+		assert!(event.ts > 0);
+		assert_eq!(events[1].tid, None);
+		assert_eq!(events[1].detail, serde_json::Value::Null);
+	}
+
+	#[test]
+	fn test_tracing_sink() {
+		let writer = VecWriter::default();
+		let output = writer.0.clone();
+		let subscriber = Registry::default().with(TidLayer).with(
+			tracing_subscriber::fmt::layer()
+				.with_ansi(false)
+				.with_writer(move || writer.clone()),
+		);
+		tracing::subscriber::with_default(subscriber, || {
+			let _span = tracing::info_span!("api", tid = "req-2").entered();
+			audit!(TracingAuditSink, actor: "ann", action: "key.rotate", resource: "key/1",
+				outcome: AuditOutcome::Failure)
+			.unwrap();
+		});
+
+		let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+		assert!(output.contains(AUDIT_TARGET), "{output}");
+		let json = &output[output.find(r#"{"actor"#).unwrap()..].trim_end();
+		let event: AuditEvent = serde_json::from_str(json).unwrap();
+		assert_eq!(event.action, "key.rotate");
+		assert_eq!(event.outcome, AuditOutcome::Failure);
+		assert_eq!(event.tid.as_deref(), Some("req-2"));
+	}
+
+	#[test]
+	fn test_failure_counted() {
+		let before = audit_failures();
+		let err = audit!(FailingSink, actor: "ann", action: "a", resource: "r",
+			outcome: AuditOutcome::Success)
+		.unwrap_err();
+		assert_eq!(err.err_code().code(), AuditErr::AuditWriteErr.code());
+		assert!(audit_failures() > before);
+	}
+
+	#[test]
+	fn test_audit_file_layer() {
+		let dir = std::env::temp_dir().join(crate::utils::uuid::UID.v4_simple_str());
+		let subscriber = Registry::default().with(audit_file_layer(&dir));
+		tracing::subscriber::with_default(subscriber, || {
+			tracing::info!("not audited");
+			TracingAuditSink
+				.record(&AuditEvent::new("ann", "a", "r", AuditOutcome::Success))
+				.unwrap();
+		});
+
+		let file = std::fs::read_dir(&dir).unwrap().next().unwrap().unwrap();
+		let content = std::fs::read_to_string(file.path()).unwrap();
+		assert_eq!(content.lines().count(), 1, "{content}");
+		assert!(content.contains(r#""actor":"ann""#), "{content}");
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+}
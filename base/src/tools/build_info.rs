@@ -0,0 +1,62 @@
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+
+/// Build metadata captured by `build.rs`
+///
+/// The git hash honors `VERGEN_GIT_SHA` / `BUILD_COMMIT` at compile time before asking git, and
+/// the timestamp honors `SOURCE_DATE_EPOCH` for reproducible builds.
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+	pub version: &'static str,
+	pub git_hash: &'static str,
+	pub build_timestamp: &'static str,
+	pub rustc_version: &'static str,
+	pub profile: &'static str,
+}
+
+static CURRENT: BuildInfo = BuildInfo {
+	version: env!("CARGO_PKG_VERSION"),
+	git_hash: env!("BUILD_GIT_HASH"),
+	build_timestamp: env!("BUILD_TIMESTAMP"),
+	rustc_version: env!("BUILD_RUSTC_VERSION"),
+	profile: env!("BUILD_PROFILE"),
+};
+
+impl BuildInfo {
+	pub fn current() -> &'static BuildInfo {
+		&CURRENT
+	}
+}
+
+/// `0.1.0 (3f2a9c1, release) built 2025-01-01T00:00:00Z with rustc 1.85.0 (..)`
+impl Display for BuildInfo {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(
+			f,
+			"{} ({}, {}) built {} with {}",
+			self.version, self.git_hash, self.profile, self.build_timestamp, self.rustc_version
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_current() {
+		let info = BuildInfo::current();
+		assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+		for field in [
+			info.git_hash,
+			info.build_timestamp,
+			info.rustc_version,
+			info.profile,
+		] {
+			assert!(!field.is_empty());
+		}
+		assert!(info.rustc_version.starts_with("rustc "));
+		assert_eq!(info.build_timestamp.len(), "2025-01-01T00:00:00Z".len());
+		assert!(info.to_string().starts_with(&format!("{} (", info.version)));
+	}
+}
@@ -0,0 +1,253 @@
+use crate::utils::hash::fnv1a;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::warn;
+
+/// One entry of the `flags:` config section
+///
+/// ```yaml
+/// flags:
+///   new_checkout: { enabled: true, rollout_pct: 20, allow_list: ["user-1"] }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlagConfig {
+	/// Kill switch, a disabled flag is off for every subject
+	pub enabled: bool,
+	/// Share of subjects that get the flag, `0..=100`
+	pub rollout_pct: u8,
+	/// Subjects that always get an enabled flag, whatever the rollout
+	pub allow_list: Vec<String>,
+}
+
+impl Default for FlagConfig {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			rollout_pct: 100,
+			allow_list: Vec::new(),
+		}
+	}
+}
+
+impl FlagConfig {
+	pub fn on() -> Self {
+		Self {
+			enabled: true,
+			..Default::default()
+		}
+	}
+
+	pub fn off() -> Self {
+		Self::default()
+	}
+
+	pub fn is_enabled(&self, name: &str, subject_id: &str) -> bool {
+		if !self.enabled {
+			return false;
+		}
+		if self.allow_list.iter().any(|s| s == subject_id) {
+			return true;
+		}
+		rollout_bucket(name, subject_id) < self.rollout_pct.min(100)
+	}
+}
+
+/// Stable `0..100` bucket of a subject for a flag, so a subject keeps its answer across
+/// restarts and replicas while different flags roll out to different subjects
+pub fn rollout_bucket(name: &str, subject_id: &str) -> u8 {
+	let hash = fnv1a(format!("{name}:{subject_id}").as_bytes());
+	(hash % 100) as u8
+}
+
+#[derive(Default)]
+struct Inner {
+	config: RwLock<HashMap<String, FlagConfig>>,
+	overrides: RwLock<HashMap<String, FlagConfig>>,
+	warned: Mutex<HashSet<String>>,
+}
+
+/// Boolean and percentage feature flags from config, with runtime overrides
+///
+/// Cheap to clone, clones share state. [`FeatureFlags::reload`] swaps the config flags, e.g. on
+/// a config file change, while overrides set with [`FeatureFlags::set_override`] win over the
+/// config until cleared. Unknown flags are disabled, with one warning per name.
+#[derive(Clone, Default)]
+pub struct FeatureFlags {
+	inner: Arc<Inner>,
+}
+
+impl FeatureFlags {
+	pub fn new(flags: HashMap<String, FlagConfig>) -> Self {
+		let inner = Inner {
+			config: RwLock::new(flags),
+			..Default::default()
+		};
+		Self {
+			inner: Arc::new(inner),
+		}
+	}
+
+	pub fn is_enabled(&self, name: &str, subject_id: &str) -> bool {
+		match self.get(name) {
+			Some(flag) => flag.is_enabled(name, subject_id),
+			None => {
+				let mut warned = self.inner.warned.lock().unwrap_or_else(|e| e.into_inner());
+				if warned.insert(name.to_string()) {
+					warn!(flag = name, "unknown feature flag, treated as disabled");
+				}
+				false
+			}
+		}
+	}
+
+	/// Effective config of a flag, the override if any
+	pub fn get(&self, name: &str) -> Option<FlagConfig> {
+		let overrides = self
+			.inner
+			.overrides
+			.read()
+			.unwrap_or_else(|e| e.into_inner());
+		if let Some(flag) = overrides.get(name) {
+			return Some(flag.clone());
+		}
+		let config = self.inner.config.read().unwrap_or_else(|e| e.into_inner());
+		config.get(name).cloned()
+	}
+
+	/// Replaces the config flags, overrides are kept
+	pub fn reload(&self, flags: HashMap<String, FlagConfig>) {
+		*self.inner.config.write().unwrap_or_else(|e| e.into_inner()) = flags;
+	}
+
+	pub fn set_override(&self, name: impl Into<String>, flag: FlagConfig) {
+		let mut overrides = self
+			.inner
+			.overrides
+			.write()
+			.unwrap_or_else(|e| e.into_inner());
+		overrides.insert(name.into(), flag);
+	}
+
+	/// Falls back to the config flag, `true` if an override was set
+	pub fn clear_override(&self, name: &str) -> bool {
+		let mut overrides = self
+			.inner
+			.overrides
+			.write()
+			.unwrap_or_else(|e| e.into_inner());
+		overrides.remove(name).is_some()
+	}
+
+	/// Every known flag with overrides applied, sorted by name
+	pub fn effective(&self) -> BTreeMap<String, FlagConfig> {
+		let mut flags: BTreeMap<_, _> = self
+			.inner
+			.config
+			.read()
+			.unwrap_or_else(|e| e.into_inner())
+			.clone()
+			.into_iter()
+			.collect();
+		let overrides = self
+			.inner
+			.overrides
+			.read()
+			.unwrap_or_else(|e| e.into_inner());
+		for (name, flag) in overrides.iter() {
+			flags.insert(name.clone(), flag.clone());
+		}
+		flags
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use figment::Figment;
+	use figment::providers::{Format, Yaml};
+
+	#[derive(Deserialize)]
+	struct AppCfg {
+		flags: HashMap<String, FlagConfig>,
+	}
+
+	fn flags(yaml: &str) -> HashMap<String, FlagConfig> {
+		let cfg: AppCfg = Figment::new().merge(Yaml::string(yaml)).extract().unwrap();
+		cfg.flags
+	}
+
+	#[test]
+	fn test_rollout_bucketing() {
+		assert_eq!(
+			rollout_bucket("checkout", "user-1"),
+			rollout_bucket("checkout", "user-1")
+		);
+		let half = FlagConfig {
+			rollout_pct: 50,
+			..FlagConfig::on()
+		};
+		let enabled = (0..1000)
+			.filter(|i| half.is_enabled("checkout", &format!("user-{i}")))
+			.count();
+		assert!((400..600).contains(&enabled), "{enabled}");
+
+		let subjects: Vec<_> = (0..1000).map(|i| format!("user-{i}")).collect();
+		let first: Vec<_> = subjects
+			.iter()
+			.map(|s| half.is_enabled("checkout", s))
+			.collect();
+		let again: Vec<_> = subjects
+			.iter()
+			.map(|s| half.is_enabled("checkout", s))
+			.collect();
+		assert_eq!(first, again);
+		// raising the rollout only adds subjects
+		let more = FlagConfig {
+			rollout_pct: 80,
+			..FlagConfig::on()
+		};
+		assert!(
+			subjects
+				.iter()
+				.all(|s| !half.is_enabled("checkout", s) || more.is_enabled("checkout", s))
+		);
+	}
+
+	#[test]
+	fn test_allow_list() {
+		let flags = FeatureFlags::new(flags(
+			"flags:\n  beta: { enabled: true, rollout_pct: 0, allow_list: [vip] }\n  \
+			 legacy: { enabled: false, allow_list: [vip] }\n  all: { enabled: true }\n",
+		));
+		assert!(flags.is_enabled("beta", "vip"));
+		assert!(!flags.is_enabled("beta", "user-1"));
+		assert!(!flags.is_enabled("legacy", "vip"));
+		assert!(flags.is_enabled("all", "user-1"));
+		assert!(!flags.is_enabled("missing", "vip"));
+		assert!(!flags.is_enabled("missing", "vip"));
+	}
+
+	#[test]
+	fn test_reload_and_override() {
+		let flags = FeatureFlags::new(flags("flags:\n  checkout: { enabled: false }\n"));
+		let shared = flags.clone();
+		assert!(!shared.is_enabled("checkout", "user-1"));
+
+		flags.reload(self::flags("flags:\n  checkout: { enabled: true }\n"));
+		assert!(shared.is_enabled("checkout", "user-1"));
+
+		flags.set_override("checkout", FlagConfig::off());
+		flags.reload(self::flags(
+			"flags:\n  checkout: { enabled: true }\n  search: {}\n",
+		));
+		assert!(!shared.is_enabled("checkout", "user-1"));
+		assert_eq!(shared.effective().len(), 2);
+		assert_eq!(shared.effective()["checkout"], FlagConfig::off());
+
+		assert!(flags.clear_override("checkout"));
+		assert!(!flags.clear_override("checkout"));
+		assert!(shared.is_enabled("checkout", "user-1"));
+	}
+}
@@ -1 +1,4 @@
+pub mod audit;
+pub mod build_info;
+pub mod flags;
 pub mod retry;
@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::ops::RangeBounds;
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct SimpleMap<K, V> {
 	data: Vec<Element<K, V>>,
@@ -24,3 +27,118 @@ impl<K, V> Default for SimpleMap<K, V> {
 		Self::new()
 	}
 }
+
+/// Ordered map allowing several values per key, e.g. in-memory events keyed by timestamp
+///
+/// Values under one key keep their insertion order.
+#[derive(Debug, Clone)]
+pub struct SortedMultiMap<K: Ord, V> {
+	data: BTreeMap<K, Vec<V>>,
+	len: usize,
+}
+
+impl<K: Ord, V> SortedMultiMap<K, V> {
+	pub fn new() -> Self {
+		Self {
+			data: BTreeMap::new(),
+			len: 0,
+		}
+	}
+
+	/// Number of values over all keys
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	pub fn insert(&mut self, k: K, v: V) {
+		self.data.entry(k).or_default().push(v);
+		self.len += 1;
+	}
+
+	pub fn get(&self, k: &K) -> &[V] {
+		self.data.get(k).map_or(&[], Vec::as_slice)
+	}
+
+	pub fn range<R: RangeBounds<K>>(&self, range: R) -> impl Iterator<Item = (&K, &V)> {
+		self.data
+			.range(range)
+			.flat_map(|(k, vs)| vs.iter().map(move |v| (k, v)))
+	}
+
+	pub fn remove_all(&mut self, k: &K) -> Vec<V> {
+		let values = self.data.remove(k).unwrap_or_default();
+		self.len -= values.len();
+		values
+	}
+
+	/// Removes and returns every entry with a key `<= threshold`, sorted by key
+	pub fn drain_up_to(&mut self, threshold: &K) -> Vec<(K, V)>
+	where
+		K: Clone,
+	{
+		let mut rest = self.data.split_off(threshold);
+		if let Some((k, vs)) = rest.remove_entry(threshold) {
+			self.data.insert(k, vs);
+		}
+		let drained = std::mem::replace(&mut self.data, rest);
+		let entries: Vec<(K, V)> = drained
+			.into_iter()
+			.flat_map(|(k, vs)| vs.into_iter().map(move |v| (k.clone(), v)))
+			.collect();
+		self.len -= entries.len();
+		entries
+	}
+}
+
+impl<K: Ord, V> Default for SortedMultiMap<K, V> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn events() -> SortedMultiMap<u64, &'static str> {
+		let mut map = SortedMultiMap::new();
+		for (ts, ev) in [
+			(30, "c"),
+			(10, "a"),
+			(50, "e"),
+			(20, "b1"),
+			(20, "b2"),
+			(40, "d"),
+		] {
+			map.insert(ts, ev);
+		}
+		map
+	}
+
+	#[test]
+	fn test_range_sorted() {
+		let map = events();
+		assert_eq!(map.len(), 6);
+		assert_eq!(map.get(&20), ["b1", "b2"]);
+		assert!(map.get(&25).is_empty());
+
+		let ranged: Vec<_> = map.range(15..=40).map(|(k, v)| (*k, *v)).collect();
+		assert_eq!(ranged, [(20, "b1"), (20, "b2"), (30, "c"), (40, "d")]);
+	}
+
+	#[test]
+	fn test_drain_up_to() {
+		let mut map = events();
+		assert_eq!(map.drain_up_to(&20), [(10, "a"), (20, "b1"), (20, "b2")]);
+		assert_eq!(map.len(), 3);
+		assert!(map.drain_up_to(&25).is_empty());
+
+		assert_eq!(map.remove_all(&40), ["d"]);
+		assert_eq!(map.drain_up_to(&100), [(30, "c"), (50, "e")]);
+		assert!(map.is_empty());
+	}
+}
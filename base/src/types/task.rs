@@ -1,5 +1,11 @@
 use crate::result::AppError;
+#[cfg(feature = "tokio-pool")]
+use crate::result::{AppResult, SysErr};
 use std::fmt::{Display, Formatter};
+#[cfg(feature = "tokio-pool")]
+use std::pin::Pin;
+#[cfg(feature = "tokio-pool")]
+use std::task::Poll;
 
 #[derive(Debug)]
 pub enum TaskStatus {
@@ -14,3 +20,196 @@ impl Display for TaskStatus {
 		}
 	}
 }
+
+/// Spawned tasks of one kind whose results are collected together
+#[cfg(feature = "tokio-pool")]
+pub struct TaskGroup<T> {
+	handles: Vec<tokio::task::JoinHandle<AppResult<T>>>,
+}
+
+#[cfg(feature = "tokio-pool")]
+impl<T: Send + 'static> Default for TaskGroup<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(feature = "tokio-pool")]
+impl<T: Send + 'static> TaskGroup<T> {
+	pub fn new() -> Self {
+		Self { handles: vec![] }
+	}
+
+	pub fn len(&self) -> usize {
+		self.handles.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.handles.is_empty()
+	}
+
+	pub fn spawn<F>(&mut self, f: F)
+	where
+		F: Future<Output = AppResult<T>> + Send + 'static,
+	{
+		self.handles.push(tokio::spawn(f));
+	}
+
+	/// The name shows up in tokio-console when built with `--cfg tokio_unstable`
+	pub fn spawn_with_name<F>(&mut self, name: &str, f: F)
+	where
+		F: Future<Output = AppResult<T>> + Send + 'static,
+	{
+		#[cfg(tokio_unstable)]
+		let handle = match tokio::task::Builder::new().name(name).spawn(f) {
+			Ok(handle) => handle,
+			Err(e) => {
+				let err = AppError::ExtCode(&SysErr::TaskJoinErr, format!("spawn {name}: {e}"));
+				tokio::spawn(async move { Err(err) })
+			}
+		};
+		#[cfg(not(tokio_unstable))]
+		let handle = {
+			use tracing::Instrument;
+			tokio::spawn(f.instrument(tracing::info_span!("task", task = name)))
+		};
+		self.handles.push(handle);
+	}
+
+	/// Waits for every task, results keep the spawn order
+	pub async fn join_all(self) -> Vec<AppResult<T>> {
+		let mut results = Vec::with_capacity(self.handles.len());
+		for handle in self.handles {
+			results.push(handle.await.unwrap_or_else(|e| Err(join_error(e))));
+		}
+		results
+	}
+
+	/// Waits for every task, values keep the spawn order. Returns the first `Err` to complete as
+	/// soon as it does, aborting the tasks still running.
+	pub async fn join_all_ok(self) -> AppResult<Vec<T>> {
+		let mut handles: Vec<_> = self.handles.into_iter().map(Some).collect();
+		let mut values: Vec<Option<T>> = handles.iter().map(|_| None).collect();
+		let joined = std::future::poll_fn(|cx| {
+			let mut pending = false;
+			for (slot, value) in handles.iter_mut().zip(values.iter_mut()) {
+				let Some(handle) = slot.as_mut() else {
+					continue;
+				};
+				match Pin::new(handle).poll(cx) {
+					Poll::Pending => pending = true,
+					Poll::Ready(res) => {
+						*slot = None;
+						match res.unwrap_or_else(|e| Err(join_error(e))) {
+							Ok(v) => *value = Some(v),
+							Err(e) => return Poll::Ready(Err(e)),
+						}
+					}
+				}
+			}
+			if pending {
+				Poll::Pending
+			} else {
+				Poll::Ready(Ok(()))
+			}
+		})
+		.await;
+
+		if let Err(e) = joined {
+			handles.into_iter().flatten().for_each(|h| h.abort());
+			return Err(e);
+		}
+		Ok(values
+			.into_iter()
+			.map(|v| v.expect("every task joined"))
+			.collect())
+	}
+}
+
+#[cfg(feature = "tokio-pool")]
+fn join_error(e: tokio::task::JoinError) -> AppError {
+	AppError::Anyhow(&SysErr::TaskJoinErr, e.into())
+}
+
+#[cfg(all(test, feature = "tokio-pool"))]
+mod tests {
+	use super::*;
+	use crate::result::ErrorCode;
+	use std::time::Duration;
+
+	fn group() -> TaskGroup<u32> {
+		let mut group = TaskGroup::new();
+		for i in 1..=5u32 {
+			group.spawn_with_name(&format!("task-{i}"), async move {
+				tokio::time::sleep(Duration::from_millis(10 * i as u64)).await;
+				if i == 3 {
+					return Err(AppError::ExtCode(
+						&SysErr::InternalError,
+						format!("task {i}"),
+					));
+				}
+				Ok(i)
+			});
+		}
+		group
+	}
+
+	fn assert_task3(err: &AppError) {
+		match err {
+			AppError::ExtCode(code, msg) => {
+				assert_eq!(code.code(), SysErr::InternalError.code());
+				assert_eq!(msg, "task 3");
+			}
+			other => panic!("unexpected error: {other:?}"),
+		}
+	}
+
+	#[tokio::test]
+	async fn test_join_all() {
+		let results = group().join_all().await;
+		assert_eq!(results.len(), 5);
+		for (i, res) in results.iter().enumerate() {
+			match res {
+				Ok(v) => assert_eq!(*v, i as u32 + 1),
+				Err(e) => {
+					assert_eq!(i, 2);
+					assert_task3(e);
+				}
+			}
+		}
+	}
+
+	#[tokio::test]
+	async fn test_join_all_ok() {
+		let err = group().join_all_ok().await.unwrap_err();
+		assert_task3(&err);
+
+		let mut ok = TaskGroup::new();
+		ok.spawn(async { Ok(1) });
+		ok.spawn(async { Ok(2) });
+		assert_eq!(ok.join_all_ok().await.unwrap(), vec![1, 2]);
+	}
+
+	#[tokio::test]
+	async fn test_join_all_ok_fails_fast() {
+		let (alive_tx, alive_rx) = tokio::sync::oneshot::channel::<()>();
+		let mut group = TaskGroup::<u32>::new();
+		group.spawn(async move {
+			let _alive = alive_tx;
+			tokio::time::sleep(Duration::from_secs(60)).await;
+			Ok(1)
+		});
+		group.spawn(async {
+			Err(AppError::ExtCode(
+				&SysErr::InternalError,
+				"task 3".to_string(),
+			))
+		});
+
+		let res = tokio::time::timeout(Duration::from_secs(5), group.join_all_ok()).await;
+		assert_task3(&res.expect("returned on the first error").unwrap_err());
+		// the sleeper was aborted, dropping its sender
+		let aborted = tokio::time::timeout(Duration::from_secs(5), alive_rx).await;
+		assert!(aborted.expect("sleeper aborted").is_err());
+	}
+}
@@ -0,0 +1,99 @@
+#[cfg(feature = "hash")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "hash")]
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "hash")]
+type HmacSha256 = Hmac<Sha256>;
+
+/// 64-bit FNV-1a of `data`, for keys that must stay stable across processes and releases
+/// (`DefaultHasher` output may change between rust releases)
+pub fn fnv1a(data: &[u8]) -> u64 {
+	data.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &b| {
+		(hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+	})
+}
+
+/// Lowercase hex sha256 digest
+#[cfg(feature = "hash")]
+pub fn sha256_hex(data: impl AsRef<[u8]>) -> String {
+	hex::encode(Sha256::digest(data.as_ref()))
+}
+
+#[cfg(feature = "hash")]
+pub fn hmac_sha256(secret: &[u8], data: &[u8]) -> Vec<u8> {
+	let mut mac = HmacSha256::new_from_slice(secret).expect("hmac accepts keys of any length");
+	mac.update(data);
+	mac.finalize().into_bytes().to_vec()
+}
+
+/// Lowercase hex HMAC-SHA256, the format most webhook senders put in their signature header
+#[cfg(feature = "hash")]
+pub fn hmac_sha256_hex(secret: &[u8], data: &[u8]) -> String {
+	hex::encode(hmac_sha256(secret, data))
+}
+
+/// Constant-time check of a raw HMAC-SHA256 signature
+#[cfg(feature = "hash")]
+pub fn verify_hmac_sha256(secret: &[u8], data: &[u8], signature: &[u8]) -> bool {
+	let mut mac = HmacSha256::new_from_slice(secret).expect("hmac accepts keys of any length");
+	mac.update(data);
+	mac.verify_slice(signature).is_ok()
+}
+
+/// [`verify_hmac_sha256`] for a hex encoded signature, invalid hex never verifies
+#[cfg(feature = "hash")]
+pub fn verify_hmac_sha256_hex(secret: &[u8], data: &[u8], signature_hex: &str) -> bool {
+	match hex::decode(signature_hex.trim()) {
+		Ok(signature) => verify_hmac_sha256(secret, data, &signature),
+		Err(_) => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_fnv1a() {
+		assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+		assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+		assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+	}
+
+	#[cfg(feature = "hash")]
+	#[test]
+	fn test_hmac_sha256() {
+		// RFC 4231 test case 2
+		let sig = hmac_sha256_hex(b"Jefe", b"what do ya want for nothing?");
+		assert_eq!(
+			sig,
+			"5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+		);
+		assert!(verify_hmac_sha256_hex(
+			b"Jefe",
+			b"what do ya want for nothing?",
+			&sig
+		));
+		assert!(!verify_hmac_sha256_hex(
+			b"Jefe",
+			b"what do ya want for something?",
+			&sig
+		));
+		assert!(!verify_hmac_sha256_hex(
+			b"Jefe",
+			b"what do ya want for nothing?",
+			"zz"
+		));
+		assert!(!verify_hmac_sha256(b"Jefe", b"", &[]));
+	}
+
+	#[cfg(feature = "hash")]
+	#[test]
+	fn test_sha256_hex() {
+		assert_eq!(
+			sha256_hex("abc"),
+			"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+		);
+	}
+}
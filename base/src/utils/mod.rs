@@ -1,3 +1,4 @@
+pub mod hash;
 mod str_util;
 pub mod time;
 pub mod uuid;
@@ -12,6 +12,14 @@ pub fn unix_timestamp() -> AppResult<i64> {
 		.map_err(map_err!(&SysErr::SystemTimeError))
 }
 
+/// Get current Unix timestamp in milliseconds, see [`unix_timestamp`]
+pub fn unix_timestamp_millis() -> AppResult<i64> {
+	std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.map(|d| d.as_millis() as i64)
+		.map_err(map_err!(&SysErr::SystemTimeError))
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -25,6 +33,13 @@ mod tests {
 		assert!(timestamp < 1893456000); // 2030-01-01 00:00:00 UTC
 	}
 
+	#[test]
+	fn test_unix_timestamp_millis() {
+		let secs = unix_timestamp().unwrap();
+		let millis = unix_timestamp_millis().unwrap();
+		assert!((millis / 1000 - secs).abs() <= 1);
+	}
+
 	#[test]
 	fn test_unix_timestamp_consistency() {
 		let ts1 = unix_timestamp().expect("Failed to get first timestamp");
@@ -1,3 +1,5 @@
+use crate::map_err;
+use crate::result::{AppResult, SysErr};
 use uuid::Uuid;
 use uuid::fmt::Simple;
 pub struct UID;
@@ -21,6 +23,32 @@ impl UID {
 		let (_, low) = self.v4().as_u64_pair();
 		low
 	}
+
+	pub fn v4_bytes(&self) -> [u8; 16] {
+		self.v4().into_bytes()
+	}
+
+	/// Time ordered, sorts by creation time as bytes
+	pub fn v7_bytes(&self) -> [u8; 16] {
+		Uuid::now_v7().into_bytes()
+	}
+
+	pub fn from_bytes(&self, bytes: [u8; 16]) -> Uuid {
+		Uuid::from_bytes(bytes)
+	}
+
+	/// Parses the hyphenated or simple form, any version
+	pub fn from_str(&self, s: &str) -> AppResult<Uuid> {
+		Uuid::try_parse(s).map_err(map_err!(
+			&SysErr::InvalidParams,
+			format!("invalid uuid: {s}")
+		))
+	}
+
+	/// Whether `s` is a syntactically valid UUID, any version
+	pub fn is_valid(&self, s: &str) -> bool {
+		Uuid::try_parse(s).is_ok()
+	}
 }
 
 #[cfg(test)]
@@ -62,4 +90,44 @@ mod tests {
 		let my_uuid = UID.v4_short();
 		println!("{}", my_uuid);
 	}
+
+	#[test]
+	fn test_bytes_round_trip() {
+		let bytes = UID.v4_bytes();
+		let uuid = UID.from_bytes(bytes);
+		assert_eq!(uuid.as_bytes(), &bytes);
+		assert_eq!(uuid.get_version_num(), 4);
+
+		let v7 = UID.from_bytes(UID.v7_bytes());
+		assert_eq!(v7.get_version_num(), 7);
+		assert!(UID.v7_bytes() >= v7.into_bytes());
+	}
+
+	#[test]
+	fn test_from_str() {
+		let uuid = UID.v4();
+		let hyphenated = uuid.hyphenated().to_string();
+		let simple = uuid.simple().to_string();
+		assert_eq!(UID.from_str(&hyphenated).unwrap(), uuid);
+		assert_eq!(UID.from_str(&simple).unwrap(), uuid);
+		assert_eq!(UID.from_str(&hyphenated.to_uppercase()).unwrap(), uuid);
+
+		let err = UID.from_str("not-a-uuid").unwrap_err();
+		assert!(
+			err.to_string().contains("invalid uuid: not-a-uuid"),
+			"{err}"
+		);
+		assert!(UID.from_str(&simple[1..]).is_err());
+	}
+
+	#[test]
+	fn test_is_valid() {
+		assert!(UID.is_valid(&UID.v4_simple_str()));
+		assert!(UID.is_valid("00000000-0000-0000-0000-000000000000"));
+		// version nibble is not checked
+		assert!(UID.is_valid("67e55044-10b1-f26f-9247-bb680e5fe0c8"));
+		assert!(!UID.is_valid(""));
+		assert!(!UID.is_valid("67e55044-10b1-426f-9247-bb680e5fe0c"));
+		assert!(!UID.is_valid("67e55044-10b1-426f-9247-bb680e5fe0cz"));
+	}
 }
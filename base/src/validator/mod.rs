@@ -1,4 +1,5 @@
-use crate::result::AppResult;
+use crate::result::{AppError, AppResult, DynErrCode};
+use std::fmt::{Display, Formatter};
 
 /// Usage
 ///
@@ -34,3 +35,120 @@ where
 		self.check()
 	}
 }
+
+/// One failed field of a request payload
+#[cfg_attr(feature = "utoipa", derive(utoipa::ToSchema))]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct FieldError {
+	/// Path of the field, e.g. `items[0].name`, empty for the payload as a whole
+	pub field: String,
+	/// Error code, e.g. `SYS002`
+	pub code: String,
+	/// Error message
+	pub msg: String,
+}
+
+/// Collects failing fields in a [`Checker`], so callers can report all of them at once
+///
+/// ```ignore
+/// let mut errors = FieldErrors::new();
+/// if self.name.is_empty() {
+///     errors.add("name", "must not be empty");
+/// }
+/// errors.into_result(&SysErr::InvalidParams)
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FieldErrors(Vec<FieldError>);
+
+impl FieldErrors {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a failure reported with the code given to [`FieldErrors::into_result`]
+	pub fn add(&mut self, field: impl Into<String>, msg: impl Into<String>) {
+		self.0.push(FieldError {
+			field: field.into(),
+			code: String::new(),
+			msg: msg.into(),
+		});
+	}
+
+	/// Adds a failure with its own code
+	pub fn add_code(&mut self, field: impl Into<String>, code: &DynErrCode, msg: impl Into<String>) {
+		self.0.push(FieldError {
+			field: field.into(),
+			code: code.code().to_string(),
+			msg: msg.into(),
+		});
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	pub fn fields(&self) -> &[FieldError] {
+		&self.0
+	}
+
+	pub fn into_fields(self) -> Vec<FieldError> {
+		self.0
+	}
+
+	/// `Ok` when nothing failed, else `AppError::Anyhow(code, self)`, fields added without a code
+	/// get `code`
+	pub fn into_result(mut self, code: &'static DynErrCode) -> AppResult<()> {
+		if self.is_empty() {
+			return Ok(());
+		}
+		for field in self.0.iter_mut().filter(|f| f.code.is_empty()) {
+			field.code = code.code().to_string();
+		}
+		Err(AppError::Anyhow(code, anyhow::Error::new(self)))
+	}
+}
+
+impl Display for FieldErrors {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let fields: Vec<String> = self
+			.0
+			.iter()
+			.map(|e| format!("{}: {}", e.field, e.msg))
+			.collect();
+		write!(f, "{}", fields.join("; "))
+	}
+}
+
+impl std::error::Error for FieldErrors {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::result::{ErrorCode, SysErr};
+
+	#[test]
+	fn test_field_errors() {
+		assert!(
+			FieldErrors::new()
+				.into_result(&SysErr::InvalidParams)
+				.is_ok()
+		);
+
+		let mut errors = FieldErrors::new();
+		errors.add("name", "must not be empty");
+		errors.add_code("age", &SysErr::InternalError, "must be positive");
+		match errors.into_result(&SysErr::InvalidParams) {
+			Err(AppError::Anyhow(_, e)) => {
+				let errors = e.downcast_ref::<FieldErrors>().unwrap();
+				assert_eq!(errors.fields().len(), 2);
+				assert_eq!(errors.fields()[0].code, SysErr::InvalidParams.code());
+				assert_eq!(errors.fields()[1].code, SysErr::InternalError.code());
+				assert_eq!(
+					e.to_string(),
+					"name: must not be empty; age: must be positive"
+				);
+			}
+			other => panic!("unexpected {other:?}"),
+		}
+	}
+}
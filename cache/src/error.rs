@@ -18,5 +18,9 @@ pub enum BaseError {
 gen_impl_code_enum! {
 	CacheErr {
 		CacheNotInit = ("Cache1", "cache not initialized for ttl"),
+		MetricsRegisterErr = ("Cache2", "cache metrics register failed"),
+		MsgpackErr = ("Cache3", "cache value MessagePack codec failed"),
+		CborErr = ("Cache4", "cache value CBOR codec failed"),
+		JsonErr = ("Cache5", "cache serde_json codec failed"),
 	}
 }
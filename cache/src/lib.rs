@@ -7,8 +7,19 @@ pub mod schema_codec;
 use crate::memory::NeverMemCache;
 use std::fmt;
 use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tracing::info;
 
+// for the json / msgpack / cbor codec macros
+#[cfg(feature = "cbor")]
+#[doc(hidden)]
+pub use ciborium;
+#[cfg(feature = "msgpack")]
+#[doc(hidden)]
+pub use rmp_serde;
+#[doc(hidden)]
+pub use serde_json;
+
 pub type BsResult<T> = Result<T, error::BaseError>;
 
 #[async_trait::async_trait]
@@ -27,6 +38,8 @@ where
 	// async fn clear(&self);
 }
 
+static CACHE_INITIALIZED: AtomicBool = AtomicBool::new(false);
+
 pub fn init_cache() {
 	info!("Start init memory cache");
 	// SecondsMemCache.init_cache();
@@ -34,5 +47,11 @@ pub fn init_cache() {
 	// MinuteMemCache.init_cache();
 	// HourMemCache.init_cache();
 	NeverMemCache.init_cache();
+	CACHE_INITIALIZED.store(true, Ordering::Release);
 	info!("Init memory cache done");
 }
+
+/// Whether [`init_cache`] has completed
+pub fn cache_initialized() -> bool {
+	CACHE_INITIALIZED.load(Ordering::Acquire)
+}
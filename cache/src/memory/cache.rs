@@ -1,4 +1,4 @@
-use crate::memory::{AsyncBytesCache, AsyncMemCache, TtlBytesCache};
+use crate::memory::{AsyncBytesCache, AsyncMemCache, MemCacheBucket, TtlBytesCache};
 use crate::schema::CacheTtl;
 use moka::future::Cache;
 use std::time::Duration;
@@ -15,13 +15,12 @@ impl SecondsMemCache {
 	}
 }
 
-#[async_trait::async_trait]
-impl AsyncMemCache for SecondsMemCache {
-	fn ttl(&self) -> CacheTtl {
-		CacheTtl::OneSecond
-	}
+impl MemCacheBucket for SecondsMemCache {
+	const TTL: CacheTtl = CacheTtl::OneSecond;
 }
 
+impl AsyncMemCache for SecondsMemCache {}
+
 pub struct Sec30MemCache;
 impl Sec30MemCache {
 	pub fn init_cache(&self) {
@@ -34,13 +33,12 @@ impl Sec30MemCache {
 	}
 }
 
-#[async_trait::async_trait]
-impl AsyncMemCache for Sec30MemCache {
-	fn ttl(&self) -> CacheTtl {
-		CacheTtl::Seconds(30)
-	}
+impl MemCacheBucket for Sec30MemCache {
+	const TTL: CacheTtl = CacheTtl::Seconds(30);
 }
 
+impl AsyncMemCache for Sec30MemCache {}
+
 pub struct MinuteMemCache;
 impl MinuteMemCache {
 	pub fn init_cache(&self) {
@@ -53,13 +51,12 @@ impl MinuteMemCache {
 	}
 }
 
-#[async_trait::async_trait]
-impl AsyncMemCache for MinuteMemCache {
-	fn ttl(&self) -> CacheTtl {
-		CacheTtl::OneMinute
-	}
+impl MemCacheBucket for MinuteMemCache {
+	const TTL: CacheTtl = CacheTtl::OneMinute;
 }
 
+impl AsyncMemCache for MinuteMemCache {}
+
 pub struct HourMemCache;
 impl HourMemCache {
 	pub fn init_cache(&self) {
@@ -71,13 +68,12 @@ impl HourMemCache {
 		TtlBytesCache::new(CacheTtl::OneHour).insert(one_hours_cache);
 	}
 }
-#[async_trait::async_trait]
-impl AsyncMemCache for HourMemCache {
-	fn ttl(&self) -> CacheTtl {
-		CacheTtl::OneHour
-	}
+impl MemCacheBucket for HourMemCache {
+	const TTL: CacheTtl = CacheTtl::OneHour;
 }
 
+impl AsyncMemCache for HourMemCache {}
+
 pub struct NeverMemCache;
 impl NeverMemCache {
 	pub fn init_cache(&self) {
@@ -86,9 +82,13 @@ impl NeverMemCache {
 	}
 }
 
-#[async_trait::async_trait]
-impl AsyncMemCache for NeverMemCache {
-	fn ttl(&self) -> CacheTtl {
-		CacheTtl::Never
-	}
+impl MemCacheBucket for NeverMemCache {
+	const TTL: CacheTtl = CacheTtl::Never;
 }
+
+impl AsyncMemCache for NeverMemCache {}
+
+/// Cache type of schemas from `define_cache_schema!`, which carry their own ttl bucket
+pub struct SchemaMemCache;
+
+impl AsyncMemCache for SchemaMemCache {}
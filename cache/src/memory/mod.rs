@@ -1,4 +1,5 @@
 mod cache;
+mod stats;
 
 use crate::error::CacheErr;
 use crate::schema::{CacheTtl, KeyCodec, Schema, ValueCodec};
@@ -6,7 +7,8 @@ use base_infra::nar_err;
 use base_infra::result::AppResult;
 pub use cache::*;
 use moka::future::Cache;
-use std::sync::LazyLock;
+pub use stats::*;
+use std::sync::{Arc, LazyLock};
 
 pub type BytesCache = moka::sync::Cache<Vec<u8>, Vec<u8>>;
 pub type AsyncBytesCache = Cache<Vec<u8>, Vec<u8>>;
@@ -30,19 +32,149 @@ impl TtlBytesCache {
 	}
 }
 
+/// [`BytesCache`] counting hits, misses and writes into [`CacheStats`]
+#[derive(Clone)]
+pub struct InstrumentedBytesCache {
+	cache: BytesCache,
+	ttl: CacheTtl,
+	stats: Arc<CacheStats>,
+}
+
+impl InstrumentedBytesCache {
+	pub fn new(cache: BytesCache, ttl: CacheTtl) -> Self {
+		Self::with_stats(cache, ttl, Arc::new(CacheStats::default()))
+	}
+
+	/// Shares `stats` with other caches, e.g. to aggregate per ttl
+	pub fn with_stats(cache: BytesCache, ttl: CacheTtl, stats: Arc<CacheStats>) -> Self {
+		Self { cache, ttl, stats }
+	}
+
+	/// Direct access, bypassing the counters
+	pub fn inner(&self) -> &BytesCache {
+		&self.cache
+	}
+
+	pub fn ttl(&self) -> CacheTtl {
+		self.ttl
+	}
+
+	pub fn stats(&self) -> &Arc<CacheStats> {
+		&self.stats
+	}
+
+	pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		let value = self.cache.get(key);
+		self.stats.record_get(value.is_some());
+		value
+	}
+
+	pub fn insert(&self, key: Vec<u8>, value: Vec<u8>) {
+		self.cache.insert(key, value);
+		self.stats.record_insert();
+	}
+
+	pub fn remove(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.stats.record_remove();
+		self.cache.remove(key)
+	}
+
+	/// Registers `cache_hits_total{ttl=..}` and `cache_misses_total{ttl=..}` gauges, read from
+	/// [`CacheStats`] on every scrape
+	#[cfg(feature = "metrics")]
+	pub fn to_prometheus_metrics(&self, registry: &prometheus::Registry) -> AppResult<()> {
+		let collector = metrics::StatsCollector::new(Some(self.ttl), self.stats.clone())?;
+		let ttl = format!("{:?}", self.ttl);
+		registry
+			.register(Box::new(collector))
+			.map_err(base_infra::map_err!(&CacheErr::MetricsRegisterErr, ttl))
+	}
+}
+
+#[cfg(feature = "metrics")]
+mod metrics {
+	use super::CacheStats;
+	use crate::error::CacheErr;
+	use crate::schema::CacheTtl;
+	use base_infra::map_err;
+	use base_infra::result::AppResult;
+	use prometheus::core::{Collector, Desc};
+	use prometheus::{IntGauge, Opts, proto};
+	use std::sync::Arc;
+
+	pub(super) struct StatsCollector {
+		stats: Arc<CacheStats>,
+		hits: IntGauge,
+		misses: IntGauge,
+	}
+
+	impl StatsCollector {
+		/// Gauges get a `ttl` label when given one
+		pub(super) fn new(ttl: Option<CacheTtl>, stats: Arc<CacheStats>) -> AppResult<Self> {
+			let gauge = |name: &str, help: &str| {
+				let opts = Opts::new(name, help);
+				let opts = match ttl {
+					Some(ttl) => opts.const_label("ttl", format!("{ttl:?}")),
+					None => opts,
+				};
+				IntGauge::with_opts(opts).map_err(map_err!(&CacheErr::MetricsRegisterErr, name))
+			};
+			Ok(Self {
+				stats,
+				hits: gauge("cache_hits_total", "Memory cache hits")?,
+				misses: gauge("cache_misses_total", "Memory cache misses")?,
+			})
+		}
+	}
+
+	impl Collector for StatsCollector {
+		fn desc(&self) -> Vec<&Desc> {
+			self.hits
+				.desc()
+				.into_iter()
+				.chain(self.misses.desc())
+				.collect()
+		}
+
+		fn collect(&self) -> Vec<proto::MetricFamily> {
+			self.hits.set(self.stats.hits() as i64);
+			self.misses.set(self.stats.misses() as i64);
+			self.hits
+				.collect()
+				.into_iter()
+				.chain(self.misses.collect())
+				.collect()
+		}
+	}
+}
+
 pub trait MemCache {
 	fn cache<S: Schema>(&self) -> AppResult<BytesCache>;
 }
 
+/// Cache type initializing a single [`CacheTtl`] bucket, schemas declared with it by
+/// `define_schema!` take its ttl
+pub trait MemCacheBucket {
+	const TTL: CacheTtl;
+}
+
+/// Schema typed access, each schema is served from the bucket of its [`Schema::TTL`] whichever
+/// cache type it is called on
 #[async_trait::async_trait]
 pub trait AsyncMemCache {
-	fn ttl(&self) -> CacheTtl;
+	/// Bucket this cache type initializes
+	fn ttl(&self) -> CacheTtl
+	where
+		Self: MemCacheBucket,
+	{
+		Self::TTL
+	}
 
 	fn async_cache<S: Schema>(&self) -> AppResult<AsyncBytesCache> {
-		let ttl = self.ttl();
-		TtlBytesCache::new(ttl)
-			.get()
-			.ok_or_else(nar_err!(&CacheErr::CacheNotInit, format!("{:?}", ttl)))
+		TtlBytesCache::new(S::TTL).get().ok_or_else(nar_err!(
+			&CacheErr::CacheNotInit,
+			format!("{:?} of {}", S::TTL, S::NAME)
+		))
 	}
 
 	async fn async_store<S: Schema>(&self, key: &S::Key, value: &S::Value) -> AppResult<()> {
@@ -66,3 +198,80 @@ pub trait AsyncMemCache {
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn cache() -> InstrumentedBytesCache {
+		InstrumentedBytesCache::new(BytesCache::new(16), CacheTtl::Never)
+	}
+
+	#[test]
+	fn test_instrumented_stats() {
+		let cache = cache();
+		assert_eq!(cache.get(b"a"), None);
+		cache.insert(b"a".to_vec(), b"1".to_vec());
+		cache.insert(b"b".to_vec(), b"2".to_vec());
+		assert_eq!(cache.get(b"a"), Some(b"1".to_vec()));
+		assert_eq!(cache.get(b"b"), Some(b"2".to_vec()));
+		assert_eq!(cache.remove(b"a"), Some(b"1".to_vec()));
+		assert_eq!(cache.get(b"a"), None);
+
+		// bypasses the counters
+		assert!(cache.inner().get(b"b".as_slice()).is_some());
+
+		let stats = cache.stats();
+		assert_eq!((stats.hits(), stats.misses()), (2, 2));
+		assert_eq!((stats.inserts(), stats.removes()), (2, 1));
+	}
+
+	#[cfg(feature = "metrics")]
+	#[test]
+	fn test_prometheus_metrics() {
+		let registry = prometheus::Registry::new();
+		let cache = cache();
+		cache.to_prometheus_metrics(&registry).unwrap();
+		cache.insert(b"a".to_vec(), b"1".to_vec());
+		cache.get(b"a");
+		cache.get(b"b");
+		cache.get(b"c");
+
+		let families = registry.gather();
+		let value = |name: &str| {
+			let family = families.iter().find(|f| f.name() == name).unwrap();
+			let metric = &family.get_metric()[0];
+			assert_eq!(metric.get_label()[0].value(), "Never");
+			metric.get_gauge().get_value()
+		};
+		assert_eq!(value("cache_hits_total"), 1.0);
+		assert_eq!(value("cache_misses_total"), 2.0);
+
+		// same ttl twice is rejected
+		assert!(cache.to_prometheus_metrics(&registry).is_err());
+	}
+
+	#[cfg(feature = "metrics")]
+	#[test]
+	fn test_register_shared_stats() {
+		let registry = prometheus::Registry::new();
+		let stats = Arc::new(CacheStats::default());
+		stats.register(&registry).unwrap();
+		let a =
+			InstrumentedBytesCache::with_stats(BytesCache::new(16), CacheTtl::Never, stats.clone());
+		let b =
+			InstrumentedBytesCache::with_stats(BytesCache::new(16), CacheTtl::Never, stats.clone());
+		a.insert(b"a".to_vec(), b"1".to_vec());
+		a.get(b"a");
+		b.get(b"a");
+
+		let families = registry.gather();
+		let hits = families
+			.iter()
+			.find(|f| f.name() == "cache_hits_total")
+			.unwrap();
+		assert!(hits.get_metric()[0].get_label().is_empty());
+		assert_eq!(hits.get_metric()[0].get_gauge().get_value(), 1.0);
+		assert!(stats.register(&registry).is_err());
+	}
+}
@@ -0,0 +1,62 @@
+#[cfg(feature = "metrics")]
+use crate::error::CacheErr;
+#[cfg(feature = "metrics")]
+use base_infra::result::AppResult;
+#[cfg(feature = "metrics")]
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Hit / miss / write counters of a memory cache
+#[derive(Debug, Default)]
+pub struct CacheStats {
+	hits: AtomicU64,
+	misses: AtomicU64,
+	inserts: AtomicU64,
+	removes: AtomicU64,
+}
+
+impl CacheStats {
+	pub fn hits(&self) -> u64 {
+		self.hits.load(Ordering::Relaxed)
+	}
+
+	pub fn misses(&self) -> u64 {
+		self.misses.load(Ordering::Relaxed)
+	}
+
+	pub fn inserts(&self) -> u64 {
+		self.inserts.load(Ordering::Relaxed)
+	}
+
+	pub fn removes(&self) -> u64 {
+		self.removes.load(Ordering::Relaxed)
+	}
+
+	pub fn record_get(&self, hit: bool) {
+		let counter = if hit { &self.hits } else { &self.misses };
+		counter.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_insert(&self) {
+		self.inserts.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub fn record_remove(&self) {
+		self.removes.fetch_add(1, Ordering::Relaxed);
+	}
+}
+
+#[cfg(feature = "metrics")]
+impl CacheStats {
+	/// Registers `cache_hits_total` and `cache_misses_total` gauges without a `ttl` label, e.g.
+	/// for stats shared by every cache through [`super::InstrumentedBytesCache::with_stats`]
+	pub fn register(self: &Arc<Self>, registry: &prometheus::Registry) -> AppResult<()> {
+		let collector = super::metrics::StatsCollector::new(None, self.clone())?;
+		registry
+			.register(Box::new(collector))
+			.map_err(base_infra::map_err!(
+				&CacheErr::MetricsRegisterErr,
+				"shared"
+			))
+	}
+}
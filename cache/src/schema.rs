@@ -9,7 +9,8 @@ macro_rules! define_schema {
 		pub(crate) struct $schema_type;
 
 		impl $crate::schema::Schema for $schema_type {
-			// const TTL: $crate::cache::schema::CacheTtl = $cache_ttl;
+			const NAME: &'static str = stringify!($schema_type);
+			const TTL: $crate::schema::CacheTtl = <$cache as $crate::memory::MemCacheBucket>::TTL;
 			type Cache = $cache;
 			type Key = $key_type;
 			type Value = $value_type;
@@ -24,7 +25,8 @@ macro_rules! define_pub_schema {
 		pub struct $schema_type;
 
 		impl $crate::schema::Schema for $schema_type {
-			// const TTL: $crate::cache::schema::CacheTtl = $cache_ttl;
+			const NAME: &'static str = stringify!($schema_type);
+			const TTL: $crate::schema::CacheTtl = <$cache as $crate::memory::MemCacheBucket>::TTL;
 			type Cache = $cache;
 			type Key = $key_type;
 			type Value = $value_type;
@@ -32,6 +34,40 @@ macro_rules! define_pub_schema {
 	};
 }
 
+/// Declares a schema served from the `$ttl` bucket, with bincode codecs or serde_json ones when
+/// `json` is passed
+///
+/// ```ignore
+/// define_cache_schema!(UserSchema, UserId, User, "user", CacheTtl::OneMinute);
+/// define_cache_schema!(TokenSchema, String, Token, "token", CacheTtl::Never, json);
+/// ```
+#[macro_export]
+macro_rules! define_cache_schema {
+	($schema_type:ident, $key_type:ty, $value_type:ty, $name:literal, $ttl:expr) => {
+		$crate::define_cache_schema!($schema_type, $key_type, $value_type, $name, $ttl, bincode);
+	};
+	($schema_type:ident, $key_type:ty, $value_type:ty, $name:literal, $ttl:expr, bincode) => {
+		$crate::define_cache_schema!(@schema $schema_type, $key_type, $value_type, $name, $ttl);
+		$crate::impl_schema_bin_codec!($schema_type, $key_type, $value_type);
+	};
+	($schema_type:ident, $key_type:ty, $value_type:ty, $name:literal, $ttl:expr, json) => {
+		$crate::define_cache_schema!(@schema $schema_type, $key_type, $value_type, $name, $ttl);
+		$crate::impl_schema_serde_codec!($schema_type, $key_type, $value_type);
+	};
+	(@schema $schema_type:ident, $key_type:ty, $value_type:ty, $name:literal, $ttl:expr) => {
+		#[derive(Debug)]
+		pub struct $schema_type;
+
+		impl $crate::schema::Schema for $schema_type {
+			const NAME: &'static str = $name;
+			const TTL: $crate::schema::CacheTtl = $ttl;
+			type Cache = $crate::memory::SchemaMemCache;
+			type Key = $key_type;
+			type Value = $value_type;
+		}
+	};
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CacheTtl {
 	OneSecond,
@@ -66,8 +102,81 @@ pub trait ValueCodec<S: Schema + ?Sized>: Sized + Debug + Send + Sync {
 }
 
 pub trait Schema: Debug + Send + Sync + 'static {
-	// const TTL: CacheTtl;
+	const NAME: &'static str;
+	/// Bucket [`AsyncMemCache::async_cache`] serves the schema from
+	const TTL: CacheTtl;
 	type Cache: BaseCache<Self>;
 	type Key: KeyCodec<Self>;
 	type Value: ValueCodec<Self>;
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::memory::{HourMemCache, SchemaMemCache, Sec30MemCache};
+	use bincode::{Decode, Encode};
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+	pub struct Session {
+		user: String,
+	}
+
+	crate::define_cache_schema!(
+		SessionSchema,
+		u64,
+		Session,
+		"session",
+		CacheTtl::Seconds(30)
+	);
+	crate::define_cache_schema!(
+		ProfileSchema,
+		String,
+		Session,
+		"profile",
+		CacheTtl::OneHour,
+		json
+	);
+
+	#[tokio::test]
+	async fn test_cache_schema_buckets() {
+		assert_eq!(
+			(SessionSchema::NAME, SessionSchema::TTL),
+			("session", CacheTtl::Seconds(30))
+		);
+		assert_eq!(ProfileSchema::TTL, CacheTtl::OneHour);
+		assert_eq!(HourMemCache.ttl(), CacheTtl::OneHour);
+
+		let cache = SchemaMemCache;
+		let err = cache.async_cache::<ProfileSchema>().err().unwrap();
+		assert!(err.to_string().contains("OneHour of profile"), "{err}");
+
+		Sec30MemCache.init_cache();
+		HourMemCache.init_cache();
+		let session = Session {
+			user: "ann".to_string(),
+		};
+		cache
+			.async_store::<SessionSchema>(&7, &session)
+			.await
+			.unwrap();
+		cache
+			.async_store::<ProfileSchema>(&"ann".to_string(), &session)
+			.await
+			.unwrap();
+
+		let sessions = cache.async_cache::<SessionSchema>().unwrap();
+		let profiles = cache.async_cache::<ProfileSchema>().unwrap();
+		let session_key = 7u64.encode_key().unwrap();
+		assert!(sessions.get(&session_key).await.is_some());
+		assert!(profiles.get(&session_key).await.is_none());
+		assert_eq!(
+			profiles.get(&b"\"ann\"".to_vec()).await,
+			Some(br#"{"user":"ann"}"#.to_vec())
+		);
+
+		// the schema picks the bucket, not the cache type it is called on
+		let loaded = HourMemCache.async_load::<SessionSchema>(&7).await.unwrap();
+		assert_eq!(loaded, Some(session));
+	}
+}
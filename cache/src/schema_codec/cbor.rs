@@ -0,0 +1,69 @@
+/// A macro to generate the `ValueCodec` implementation for a given schema type with CBOR.
+#[macro_export]
+macro_rules! impl_schema_value_cbor_codec {
+	($schema_type:ty, $value_type:ty) => {
+		impl $crate::schema::ValueCodec<$schema_type> for $value_type {
+			fn encode_value(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				let mut bytes = Vec::new();
+				$crate::ciborium::into_writer(self, &mut bytes)
+					.map_err(base_infra::map_err!(&$crate::error::CacheErr::CborErr))?;
+				Ok(bytes)
+			}
+
+			fn decode_value(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				$crate::ciborium::from_reader(data)
+					.map_err(base_infra::map_err!(&$crate::error::CacheErr::CborErr))
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::memory::NeverMemCache;
+	use crate::schema::{KeyCodec, ValueCodec};
+	use base_infra::result::AppResult;
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+	pub(crate) struct Profile {
+		name: String,
+		age: u32,
+		tags: Vec<String>,
+	}
+
+	#[derive(Debug, PartialEq)]
+	pub(crate) struct ProfileKey(u64);
+
+	impl KeyCodec<ProfileSchema> for ProfileKey {
+		fn encode_key(&self) -> AppResult<Vec<u8>> {
+			Ok(self.0.to_be_bytes().to_vec())
+		}
+
+		fn decode_key(data: &[u8]) -> AppResult<Self> {
+			let bytes = data.try_into().map_err(anyhow::Error::from)?;
+			Ok(Self(u64::from_be_bytes(bytes)))
+		}
+	}
+
+	crate::define_schema!(ProfileSchema, ProfileKey, Profile, NeverMemCache);
+	crate::impl_schema_value_cbor_codec!(ProfileSchema, Profile);
+
+	/// `cbor2.dumps({"name": "ann", "age": 300, "tags": ["a"]})`
+	const FIXTURE: &[u8] = &[
+		0xa3, 0x64, b'n', b'a', b'm', b'e', 0x63, b'a', b'n', b'n', 0x63, b'a', b'g', b'e', 0x19,
+		0x01, 0x2c, 0x64, b't', b'a', b'g', b's', 0x81, 0x61, b'a',
+	];
+
+	#[test]
+	fn test_cbor_fixture() {
+		let profile = Profile {
+			name: "ann".to_string(),
+			age: 300,
+			tags: vec!["a".to_string()],
+		};
+		assert_eq!(profile.encode_value().unwrap(), FIXTURE);
+		assert_eq!(Profile::decode_value(FIXTURE).unwrap(), profile);
+		assert!(Profile::decode_value(&FIXTURE[..4]).is_err());
+	}
+}
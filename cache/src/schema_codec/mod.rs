@@ -1,4 +1,8 @@
 pub mod bincode;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
 
 /// A macro to generate the `ValueCodec` implementation for a given schema type.
 #[macro_export]
@@ -6,12 +10,35 @@ macro_rules! impl_schema_value_serde_codec {
 	($schema_type:ty, $value_type:ty) => {
 		impl $crate::schema::ValueCodec<$schema_type> for $value_type {
 			fn encode_value(&self) -> base_infra::result::AppResult<Vec<u8>> {
-				serde_json::to_vec(self).map_err(Into::into)
+				$crate::serde_json::to_vec(self)
+					.map_err(base_infra::map_err!(&$crate::error::CacheErr::JsonErr))
 			}
 
 			fn decode_value(data: &[u8]) -> base_infra::result::AppResult<Self> {
-				serde_json::from_slice(data).map_err(Into::into)
+				$crate::serde_json::from_slice(data)
+					.map_err(base_infra::map_err!(&$crate::error::CacheErr::JsonErr))
 			}
 		}
 	};
 }
+
+/// A macro to generate the `KeyCodec` and `ValueCodec` implementations for a given schema type
+/// with serde_json.
+#[macro_export]
+macro_rules! impl_schema_serde_codec {
+	($schema_type:ty, $key_type:ty, $value_type:ty) => {
+		impl $crate::schema::KeyCodec<$schema_type> for $key_type {
+			fn encode_key(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				$crate::serde_json::to_vec(self)
+					.map_err(base_infra::map_err!(&$crate::error::CacheErr::JsonErr))
+			}
+
+			fn decode_key(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				$crate::serde_json::from_slice(data)
+					.map_err(base_infra::map_err!(&$crate::error::CacheErr::JsonErr))
+			}
+		}
+
+		$crate::impl_schema_value_serde_codec!($schema_type, $value_type);
+	};
+}
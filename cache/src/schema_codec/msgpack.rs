@@ -0,0 +1,68 @@
+/// A macro to generate the `ValueCodec` implementation for a given schema type with MessagePack,
+/// structs encoded as maps so other languages can read them by field name.
+#[macro_export]
+macro_rules! impl_schema_value_msgpack_codec {
+	($schema_type:ty, $value_type:ty) => {
+		impl $crate::schema::ValueCodec<$schema_type> for $value_type {
+			fn encode_value(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				$crate::rmp_serde::to_vec_named(self)
+					.map_err(base_infra::map_err!(&$crate::error::CacheErr::MsgpackErr))
+			}
+
+			fn decode_value(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				$crate::rmp_serde::from_slice(data)
+					.map_err(base_infra::map_err!(&$crate::error::CacheErr::MsgpackErr))
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::memory::NeverMemCache;
+	use crate::schema::{KeyCodec, ValueCodec};
+	use base_infra::result::AppResult;
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+	pub(crate) struct Profile {
+		name: String,
+		age: u32,
+		tags: Vec<String>,
+	}
+
+	#[derive(Debug, PartialEq)]
+	pub(crate) struct ProfileKey(u64);
+
+	impl KeyCodec<ProfileSchema> for ProfileKey {
+		fn encode_key(&self) -> AppResult<Vec<u8>> {
+			Ok(self.0.to_be_bytes().to_vec())
+		}
+
+		fn decode_key(data: &[u8]) -> AppResult<Self> {
+			let bytes = data.try_into().map_err(anyhow::Error::from)?;
+			Ok(Self(u64::from_be_bytes(bytes)))
+		}
+	}
+
+	crate::define_schema!(ProfileSchema, ProfileKey, Profile, NeverMemCache);
+	crate::impl_schema_value_msgpack_codec!(ProfileSchema, Profile);
+
+	/// `msgpack.packb({"name": "ann", "age": 300, "tags": ["a"]})`
+	const FIXTURE: &[u8] = &[
+		0x83, 0xa4, b'n', b'a', b'm', b'e', 0xa3, b'a', b'n', b'n', 0xa3, b'a', b'g', b'e', 0xcd,
+		0x01, 0x2c, 0xa4, b't', b'a', b'g', b's', 0x91, 0xa1, b'a',
+	];
+
+	#[test]
+	fn test_msgpack_fixture() {
+		let profile = Profile {
+			name: "ann".to_string(),
+			age: 300,
+			tags: vec!["a".to_string()],
+		};
+		assert_eq!(profile.encode_value().unwrap(), FIXTURE);
+		assert_eq!(Profile::decode_value(FIXTURE).unwrap(), profile);
+		assert!(Profile::decode_value(&FIXTURE[..4]).is_err());
+	}
+}
@@ -0,0 +1,261 @@
+use crate::error::CliErr;
+use base_infra::map_err;
+use base_infra::result::AppResult;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+const APP_ENV: &str = "APP_ENV";
+const APP_ENV_FLAG: &str = "--app-env";
+
+/// Env var prefix and dotenv file selection, so services sharing a host don't read
+/// each other's settings.
+///
+/// With prefix `SWAP`, `--app-env` reads `SWAP_APP_ENV`, `--log-level` reads
+/// `SWAP_LOG_LEVEL` and so on:
+///
+/// ```ignore
+/// let cli: Cli = EnvOptions::new("SWAP").parse();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EnvOptions {
+	/// Empty keeps clap's default names, e.g. `APP_ENV`
+	pub prefix: &'static str,
+	/// Defaults to `.env.{app_env}`, then `.env`, when present
+	pub dotenv_file: Option<PathBuf>,
+}
+
+impl EnvOptions {
+	pub fn new(prefix: &'static str) -> Self {
+		Self {
+			prefix,
+			dotenv_file: None,
+		}
+	}
+
+	pub fn with_dotenv_file(self, path: impl Into<PathBuf>) -> Self {
+		Self {
+			dotenv_file: Some(path.into()),
+			..self
+		}
+	}
+
+	/// `{PREFIX}_{name}`, or `name` when no prefix is set
+	pub fn env_name(&self, name: &str) -> String {
+		match self.prefix {
+			"" => name.to_string(),
+			prefix => format!("{}_{name}", prefix.to_ascii_uppercase()),
+		}
+	}
+
+	/// `P`'s command with every env-backed arg renamed via [`EnvOptions::env_name`]
+	pub fn command<P: CommandFactory>(&self) -> clap::Command {
+		P::command().mut_args(|arg| match arg.get_env() {
+			Some(env) => {
+				let env = self.env_name(&env.to_string_lossy());
+				arg.env(env)
+			}
+			None => arg,
+		})
+	}
+
+	/// [`load_env`], then parse the process args, exiting on error like [`clap::Parser::parse`]
+	pub fn parse<P: CommandFactory + FromArgMatches>(&self) -> P {
+		self.try_parse_from(std::env::args_os())
+			.unwrap_or_else(|e| e.exit())
+	}
+
+	pub fn try_parse_from<P, I, T>(&self, argv: I) -> Result<P, clap::Error>
+	where
+		P: CommandFactory + FromArgMatches,
+		I: IntoIterator<Item = T>,
+		T: Into<OsString> + Clone,
+	{
+		let argv: Vec<OsString> = argv.into_iter().map(Into::into).collect();
+		load_env_from(self, &argv)
+			.map_err(|e| clap::Error::raw(clap::error::ErrorKind::Io, format!("{e}\n")))?;
+
+		let mut cmd = self.command::<P>();
+		let matches = cmd.try_get_matches_from_mut(argv)?;
+		log_overrides(&cmd, &matches);
+		P::from_arg_matches(&matches).map_err(|e| e.format(&mut cmd))
+	}
+}
+
+/// Loads `options.dotenv_file`, or the default `.env.{app_env}` / `.env` when present,
+/// into the process env. Variables already set in the env are kept.
+///
+/// `app_env` comes from `--app-env` in the process args, then `{PREFIX}_APP_ENV`.
+/// Returns the loaded file, if any.
+pub fn load_env(options: &EnvOptions) -> AppResult<Option<PathBuf>> {
+	let argv: Vec<OsString> = std::env::args_os().collect();
+	load_env_from(options, &argv)
+}
+
+fn load_env_from(options: &EnvOptions, argv: &[OsString]) -> AppResult<Option<PathBuf>> {
+	let path = match &options.dotenv_file {
+		Some(path) => path.clone(),
+		None => {
+			let app_env = app_env_arg(argv).or_else(|| std::env::var(options.env_name(APP_ENV)).ok());
+			match default_dotenv(app_env.as_deref()) {
+				Some(path) => path,
+				None => return Ok(None),
+			}
+		}
+	};
+
+	dotenvy::from_path(&path).map_err(map_err!(&CliErr::DotenvLoadErr, path.display()))?;
+	info!("Loaded env from {}", path.display());
+	Ok(Some(path))
+}
+
+fn default_dotenv(app_env: Option<&str>) -> Option<PathBuf> {
+	app_env
+		.map(|env| PathBuf::from(format!(".env.{}", env.to_ascii_lowercase())))
+		.into_iter()
+		.chain([PathBuf::from(".env")])
+		.find(|path| path.is_file())
+}
+
+/// Value of `--app-env x` / `--app-env=x`
+fn app_env_arg(argv: &[OsString]) -> Option<String> {
+	let mut args = argv.iter().skip(1).map(|arg| arg.to_string_lossy());
+	while let Some(arg) = args.next() {
+		if arg == APP_ENV_FLAG {
+			return args.next().map(|v| v.into_owned());
+		}
+		if let Some(value) = arg
+			.strip_prefix(APP_ENV_FLAG)
+			.and_then(|v| v.strip_prefix('='))
+		{
+			return Some(value.to_string());
+		}
+	}
+	None
+}
+
+/// Flags always win over env; say so when both are set and disagree
+fn log_overrides(cmd: &clap::Command, matches: &ArgMatches) {
+	for arg in cmd.get_arguments() {
+		let (Some(env), id) = (arg.get_env(), arg.get_id().as_str()) else {
+			continue;
+		};
+		if matches.value_source(id) != Some(ValueSource::CommandLine) {
+			continue;
+		}
+		let (Some(env_value), Some(mut raw)) = (std::env::var_os(env), matches.get_raw(id)) else {
+			continue;
+		};
+		if raw.all(|flag| flag != env_value) {
+			warn!(
+				"--{} overrides {} from env",
+				arg.get_long().unwrap_or(id),
+				env.to_string_lossy()
+			);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{AppArgs, AppEnv, Cli, NoExtra};
+	use base_infra::config::LocalConfig;
+	use std::io::Write;
+	use tracing::Level;
+
+	fn set_env(name: &str, value: &str) {
+		// SAFETY: every test uses its own prefix, so no other thread reads these vars
+		unsafe { std::env::set_var(name, value) };
+	}
+
+	fn dotenv_file(content: &str) -> tempfile::NamedTempFile {
+		let mut file = tempfile::NamedTempFile::new().unwrap();
+		file.write_all(content.as_bytes()).unwrap();
+		file
+	}
+
+	#[test]
+	fn test_env_name() {
+		assert_eq!(EnvOptions::default().env_name("APP_ENV"), "APP_ENV");
+		assert_eq!(
+			EnvOptions::new("swap").env_name("LOG_LEVEL"),
+			"SWAP_LOG_LEVEL"
+		);
+
+		let cmd = EnvOptions::new("SVC").command::<AppArgs>();
+		let envs: Vec<_> = cmd
+			.get_arguments()
+			.filter_map(|arg| arg.get_env())
+			.map(|env| env.to_string_lossy().into_owned())
+			.collect();
+		assert_eq!(
+			envs,
+			["SVC_APP_ENV", "SVC_LOG_LEVEL", "SVC_CONFIG", "SVC_PROFILE"]
+		);
+	}
+
+	#[test]
+	fn test_prefixed_env_and_flag_precedence() {
+		let options = EnvOptions::new("ENV_TEST_A");
+		set_env("ENV_TEST_A_APP_ENV", "production");
+		set_env("ENV_TEST_A_LOG_LEVEL", "WARN");
+		set_env("ENV_TEST_A_PROFILE", "staging");
+
+		let args: AppArgs = options.try_parse_from(["app"]).unwrap();
+		assert!(matches!(args.app_env, AppEnv::Production));
+		assert_eq!(args.log_level.unwrap().level(), Some(Level::WARN));
+		assert_eq!(args.profile.as_deref(), Some("staging"));
+
+		let args: AppArgs = options
+			.try_parse_from(["app", "--log-level", "DEBUG", "--profile=canary"])
+			.unwrap();
+		assert_eq!(args.log_level.unwrap().level(), Some(Level::DEBUG));
+		assert_eq!(args.profile.as_deref(), Some("canary"));
+
+		// unprefixed names are ignored once a prefix is set
+		let err = EnvOptions::new("ENV_TEST_NONE").try_parse_from::<AppArgs, _, _>(["app"]);
+		assert!(err.is_err());
+	}
+
+	#[test]
+	fn test_dotenv_file() {
+		let file = dotenv_file(
+			"ENV_TEST_B_APP_ENV=development\n\
+			 ENV_TEST_B_CONFIG=from-dotenv.yaml\n\
+			 ENV_TEST_B_PROFILE=dotenv\n",
+		);
+		set_env("ENV_TEST_B_PROFILE", "process");
+		let options = EnvOptions::new("ENV_TEST_B").with_dotenv_file(file.path());
+
+		assert_eq!(load_env(&options).unwrap().as_deref(), Some(file.path()));
+		let cli: Cli<NoExtra> = options
+			.try_parse_from(["app", "--config", "from-flag.yaml"])
+			.unwrap();
+		let local = LocalConfig::try_from(cli.args).unwrap();
+		assert!(local.rt_env.is_dev());
+		// flag > process env > dotenv file
+		assert_eq!(local.config_path.unwrap(), PathBuf::from("from-flag.yaml"));
+		assert_eq!(local.profile.as_deref(), Some("process"));
+		assert_eq!(
+			std::env::var("ENV_TEST_B_CONFIG").unwrap(),
+			"from-dotenv.yaml"
+		);
+
+		let missing = EnvOptions::new("ENV_TEST_C").with_dotenv_file("/nonexistent/.env");
+		assert!(load_env(&missing).is_err());
+		assert!(missing.try_parse_from::<AppArgs, _, _>(["app"]).is_err());
+	}
+
+	#[test]
+	fn test_app_env_arg() {
+		let argv = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+		let app_env = app_env_arg(&argv(&["app", "--app-env", "production"]));
+		assert_eq!(app_env.as_deref(), Some("production"));
+		let app_env = app_env_arg(&argv(&["app", "-c", "--app-env=development"]));
+		assert_eq!(app_env.as_deref(), Some("development"));
+		assert_eq!(app_env_arg(&argv(&["app", "--profile", "x"])), None);
+	}
+}
@@ -0,0 +1,10 @@
+base_infra::gen_impl_code_enum! {
+	CliErr {
+		NoCmdHandler = ("CMD001", "No handler registered for command"),
+		DumpConfigErr = ("CMD002", "Failed to dump config as yaml"),
+		DotenvLoadErr = ("CMD003", "Failed to load dotenv file"),
+		GenerateErr = ("CMD004", "Failed to generate completions or man page"),
+		ConfigFileExists = ("CMD005", "Config file already exists, use --force to overwrite"),
+		WriteConfigErr = ("CMD006", "Failed to write config file"),
+	}
+}
@@ -0,0 +1,158 @@
+use crate::error::CliErr;
+use base_infra::map_err;
+use base_infra::result::AppResult;
+use clap::CommandFactory;
+pub use clap_complete::Shell;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const COMPLETIONS_CMD: &str = "completions";
+const MAN_CMD: &str = "man";
+
+/// Completion script for `P`'s full command tree, app-specific subcommands included
+pub fn write_completions<P: CommandFactory>(
+	shell: Shell,
+	bin_name: &str,
+	out: &mut dyn Write,
+) -> AppResult<()> {
+	let mut cmd = P::command();
+	clap_complete::generate(shell, &mut cmd, bin_name, out);
+	Ok(())
+}
+
+/// Roff man page for `P`'s top level command
+pub fn write_man<P: CommandFactory>(bin_name: &str, out: &mut dyn Write) -> AppResult<()> {
+	let cmd = P::command().name(bin_name.to_string());
+	clap_mangen::Man::new(cmd)
+		.render(out)
+		.map_err(map_err!(&CliErr::GenerateErr, "man page"))
+}
+
+/// Writes the completion script to stdout, or into `out` as a file named per the shell's convention
+pub fn generate_completions<P: CommandFactory>(
+	shell: Shell,
+	bin_name: &str,
+	out: Option<&Path>,
+) -> AppResult<()> {
+	match out {
+		Some(dir) => {
+			let mut cmd = P::command();
+			let path = clap_complete::generate_to(shell, &mut cmd, bin_name, dir)
+				.map_err(map_err!(&CliErr::GenerateErr, dir.display()))?;
+			eprintln!("completions written to {}", path.display());
+			Ok(())
+		}
+		None => write_completions::<P>(shell, bin_name, &mut std::io::stdout()),
+	}
+}
+
+/// Writes the man page to stdout, or one page per subcommand into `out`
+pub fn generate_man<P: CommandFactory>(bin_name: &str, out: Option<&Path>) -> AppResult<()> {
+	match out {
+		Some(dir) => {
+			let cmd = P::command().name(bin_name.to_string());
+			clap_mangen::generate_to(cmd, dir)
+				.map_err(map_err!(&CliErr::GenerateErr, dir.display()))?;
+			eprintln!("man pages written to {}", dir.display());
+			Ok(())
+		}
+		None => write_man::<P>(bin_name, &mut std::io::stdout()),
+	}
+}
+
+/// Handles `completions` / `man` ahead of the regular parse, so they need neither
+/// `--app-env` nor a loadable config.
+///
+/// Returns `None` when `argv` asks for another command.
+pub fn generate_from<P, I, T>(argv: I) -> Option<AppResult<()>>
+where
+	P: CommandFactory,
+	I: IntoIterator<Item = T>,
+	T: Into<OsString> + Clone,
+{
+	let argv: Vec<OsString> = argv.into_iter().map(Into::into).collect();
+	let matches = P::command()
+		.subcommand_negates_reqs(true)
+		.try_get_matches_from(&argv)
+		.ok()?;
+	let bin_name = bin_name(argv.first());
+
+	match matches.subcommand()? {
+		(COMPLETIONS_CMD, sub) => {
+			let shell = *sub.get_one::<Shell>("shell")?;
+			let out = sub.get_one::<PathBuf>("out").map(PathBuf::as_path);
+			Some(generate_completions::<P>(shell, &bin_name, out))
+		}
+		(MAN_CMD, sub) => {
+			let out = sub.get_one::<PathBuf>("out").map(PathBuf::as_path);
+			Some(generate_man::<P>(&bin_name, out))
+		}
+		_ => None,
+	}
+}
+
+/// File name of `argv[0]`, i.e. the installed binary rather than the crate defining the parser
+pub fn bin_name(arg0: Option<&OsString>) -> String {
+	arg0.and_then(|arg| Path::new(arg).file_name())
+		.map(|name| name.to_string_lossy().into_owned())
+		.unwrap_or_else(|| env!("CARGO_PKG_NAME").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Cli;
+
+	#[derive(clap::Subcommand, Debug, Clone, PartialEq, Eq)]
+	enum Extra {
+		/// Rebuild the search index
+		Reindex,
+	}
+
+	fn completions(shell: Shell) -> String {
+		let mut out = Vec::new();
+		write_completions::<Cli<Extra>>(shell, "svc", &mut out).unwrap();
+		String::from_utf8(out).unwrap()
+	}
+
+	#[test]
+	fn test_completions() {
+		let bash = completions(Shell::Bash);
+		assert!(bash.contains("svc"), "{bash}");
+		assert!(bash.contains("reindex"), "{bash}");
+		assert!(bash.contains("check-config"), "{bash}");
+
+		let zsh = completions(Shell::Zsh);
+		assert!(zsh.starts_with("#compdef svc"), "{zsh}");
+		assert!(zsh.contains("reindex"), "{zsh}");
+	}
+
+	#[test]
+	fn test_man() {
+		let mut out = Vec::new();
+		write_man::<Cli<Extra>>("svc", &mut out).unwrap();
+		let man = String::from_utf8(out).unwrap();
+		assert!(man.contains(".TH svc"), "{man}");
+		assert!(man.contains("reindex"), "{man}");
+	}
+
+	#[test]
+	fn test_generate_from() {
+		let dir = tempfile::tempdir().unwrap();
+		let out = dir.path().to_str().unwrap();
+
+		// no --app-env and no config needed
+		let argv = ["/usr/bin/svc", "completions", "bash", "--out", out];
+		generate_from::<Cli<Extra>, _, _>(argv).unwrap().unwrap();
+		assert!(dir.path().join("svc.bash").is_file());
+
+		let argv = ["svc", "man", "--out", out];
+		generate_from::<Cli<Extra>, _, _>(argv).unwrap().unwrap();
+		assert!(dir.path().join("svc.1").is_file());
+		assert!(dir.path().join("svc-reindex.1").is_file());
+
+		assert!(generate_from::<Cli<Extra>, _, _>(["svc", "migrate"]).is_none());
+		assert!(generate_from::<Cli<Extra>, _, _>(["svc", "--app-env", "development"]).is_none());
+	}
+}
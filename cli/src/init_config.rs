@@ -0,0 +1,182 @@
+use crate::error::CliErr;
+use base_infra::config::ConfigExt;
+use base_infra::result::AppResult;
+use base_infra::{err, map_err};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Comments for `init-config` templates, keyed by dotted field path
+///
+/// ```ignore
+/// impl ConfigDocs for AppConfig {
+///     fn docs() -> Vec<(&'static str, &'static str)> {
+///         vec![("database.url", "Postgres connection url")]
+///     }
+/// }
+/// ```
+pub trait ConfigDocs {
+	fn docs() -> Vec<(&'static str, &'static str)> {
+		Vec::new()
+	}
+}
+
+/// YAML skeleton of `T::default()` with [`ConfigDocs`] comments above their keys
+pub fn config_template<T>() -> AppResult<String>
+where
+	T: Serialize + Default + ConfigExt + ConfigDocs,
+{
+	let yaml = serde_yaml::to_string(&T::default()).map_err(map_err!(&CliErr::DumpConfigErr))?;
+	let docs: HashMap<&str, &str> = T::docs().into_iter().collect();
+	Ok(annotate(&yaml, &docs))
+}
+
+/// Writes [`config_template`] to `out`, refusing to replace an existing file unless `force`
+pub fn init_config<T>(out: &Path, force: bool) -> AppResult<()>
+where
+	T: Serialize + Default + ConfigExt + ConfigDocs,
+{
+	if out.exists() && !force {
+		return err!(&CliErr::ConfigFileExists, out.display());
+	}
+	let template = config_template::<T>()?;
+	std::fs::write(out, template).map_err(map_err!(&CliErr::WriteConfigErr, out.display()))
+}
+
+/// Inserts `# comment` lines above mapping keys whose dotted path has a doc.
+/// Keys nested in sequences are not annotated.
+fn annotate(yaml: &str, docs: &HashMap<&str, &str>) -> String {
+	let mut out = String::with_capacity(yaml.len());
+	let mut parents: Vec<(usize, &str)> = Vec::new();
+
+	for line in yaml.lines() {
+		let trimmed = line.trim_start();
+		let indent = line.len() - trimmed.len();
+		let key = match trimmed.split_once(':') {
+			Some((key, rest))
+				if !trimmed.starts_with('-') && (rest.is_empty() || rest.starts_with(' ')) =>
+			{
+				Some(key)
+			}
+			_ => None,
+		};
+
+		if let Some(key) = key {
+			while parents.last().is_some_and(|(i, _)| *i >= indent) {
+				parents.pop();
+			}
+			let path = parents
+				.iter()
+				.map(|(_, k)| *k)
+				.chain([key])
+				.collect::<Vec<_>>()
+				.join(".");
+			if let Some(doc) = docs.get(path.as_str()) {
+				for doc_line in doc.lines() {
+					out.push_str(&format!("{:indent$}# {doc_line}\n", ""));
+				}
+			}
+			parents.push((indent, key));
+		}
+		out.push_str(line);
+		out.push('\n');
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde::Deserialize;
+
+	#[derive(Debug, Serialize, Deserialize, PartialEq)]
+	struct AppConfig {
+		name: String,
+		server: ServerConfig,
+		database: DbConfig,
+		tags: Vec<String>,
+	}
+
+	#[derive(Debug, Serialize, Deserialize, PartialEq)]
+	struct ServerConfig {
+		host: String,
+		port: u16,
+	}
+
+	#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+	struct DbConfig {
+		url: String,
+		pool: PoolConfig,
+	}
+
+	#[derive(Debug, Serialize, Deserialize, PartialEq)]
+	struct PoolConfig {
+		max: u32,
+	}
+
+	impl Default for AppConfig {
+		fn default() -> Self {
+			Self {
+				name: "demo".to_string(),
+				server: ServerConfig {
+					host: "localhost".to_string(),
+					port: 8080,
+				},
+				database: DbConfig::default(),
+				tags: vec!["a".to_string()],
+			}
+		}
+	}
+
+	impl Default for PoolConfig {
+		fn default() -> Self {
+			Self { max: 10 }
+		}
+	}
+
+	impl ConfigDocs for AppConfig {
+		fn docs() -> Vec<(&'static str, &'static str)> {
+			vec![
+				("name", "Service name"),
+				("server.port", "Listen port"),
+				("database", "Primary database\nRequired in production"),
+				("database.pool.max", "Max pooled connections"),
+			]
+		}
+	}
+
+	#[test]
+	fn test_config_template() {
+		let template = config_template::<AppConfig>().unwrap();
+		let parsed: AppConfig = serde_yaml::from_str(&template).unwrap();
+		assert_eq!(parsed, AppConfig::default());
+
+		let lines: Vec<&str> = template.lines().collect();
+		let above = |key: &str| {
+			let i = lines.iter().position(|l| *l == key).unwrap();
+			lines[i - 1]
+		};
+		assert_eq!(lines[0], "# Service name");
+		assert_eq!(above("  port: 8080"), "  # Listen port");
+		assert_eq!(above("database:"), "# Required in production");
+		assert_eq!(above("    max: 10"), "    # Max pooled connections");
+		assert_eq!(above("  host: localhost"), "server:");
+	}
+
+	#[test]
+	fn test_init_config_overwrite_guard() {
+		let dir = tempfile::tempdir().unwrap();
+		let out = dir.path().join("config.yaml");
+
+		init_config::<AppConfig>(&out, false).unwrap();
+		let written = std::fs::read_to_string(&out).unwrap();
+		assert!(written.contains("# Listen port"));
+
+		std::fs::write(&out, "name: edited\n").unwrap();
+		assert!(init_config::<AppConfig>(&out, false).is_err());
+		assert_eq!(std::fs::read_to_string(&out).unwrap(), "name: edited\n");
+
+		init_config::<AppConfig>(&out, true).unwrap();
+		assert_eq!(std::fs::read_to_string(&out).unwrap(), written);
+	}
+}
@@ -1,7 +1,24 @@
-use base_infra::config::{LocalConfig, RtEnv};
+mod command;
+mod env;
+pub mod error;
+mod generate;
+mod init_config;
+
+pub use command::*;
+pub use env::*;
+pub use generate::*;
+pub use init_config::*;
+
+use base_infra::app_err;
+use base_infra::config::{ConfigExt, LocalConfig, RtEnv};
+use base_infra::logger::LogDirectives;
+use base_infra::result::{AppResult, SysErr};
+use base_infra::tools::build_info::BuildInfo;
+use base_infra::validator::Validator;
 pub use clap::Parser;
 use std::path::PathBuf;
-use tracing::Level;
+use std::process::ExitCode;
+use tracing::level_filters::LevelFilter;
 
 #[derive(clap::ValueEnum, Clone, Debug, Copy)]
 pub enum AppEnv {
@@ -13,36 +30,334 @@ pub enum AppEnv {
 pub struct AppArgs {
 	#[clap(long, env, value_enum)]
 	pub app_env: AppEnv,
-	/// log level
+	/// log level or per-target directives, e.g. `info,sqlx=warn,my_app::indexer=trace`
 	#[clap(long, env, default_value = "INFO")]
-	#[arg(value_parser = parse_level)]
-	pub log_level: Option<Level>,
+	#[arg(value_parser = parse_log_directives)]
+	pub log_level: Option<LogDirectives>,
 	/// Path to application configuration file (or template for local test mode).
 	#[clap(long, env, value_parser)]
 	pub config: Option<PathBuf>,
-	/// Git commit  hash
+	/// Config profile, merges `{stem}.{profile}.{ext}` over the base config file
+	#[clap(long, short = 'p', env, value_parser)]
+	pub profile: Option<String>,
+	/// Print the build commit and metadata, then exit
 	#[clap(long, short = 'c', value_parser)]
 	pub commit: bool,
+	/// Load and validate the config file, then exit
+	#[clap(long, value_parser)]
+	pub config_check: bool,
 }
 
-fn parse_level(level: &str) -> anyhow::Result<Level> {
-	let level: Level = level
-		.parse()
-		.map_err(|e| anyhow::anyhow!("Invalid log level: {:?}", e))?;
-	Ok(level)
+impl AppArgs {
+	/// Handle terminating flags such as `--commit`.
+	///
+	/// Returns `true` when a flag was handled and the process should exit:
+	///
+	/// ```ignore
+	/// let args = AppArgs::parse();
+	/// if args.handle_flags() {
+	///     std::process::exit(0);
+	/// }
+	/// ```
+	pub fn handle_flags(&self) -> bool {
+		if self.commit {
+			println!("{}", build_info_text());
+			return true;
+		}
+		false
+	}
+
+	/// Handle `--config-check` for the config type `C`.
+	///
+	/// Prints `Config OK` or the error to stderr and returns the exit code when the flag is set:
+	///
+	/// ```ignore
+	/// if let Some(code) = args.handle_config_check::<AppConfig>() {
+	///     return code;
+	/// }
+	/// ```
+	pub fn handle_config_check<C>(&self) -> Option<ExitCode>
+	where
+		C: ConfigExt + Validator,
+	{
+		if !self.config_check {
+			return None;
+		}
+		match self.check_config::<C>() {
+			Ok(()) => {
+				eprintln!("Config OK");
+				Some(ExitCode::SUCCESS)
+			}
+			Err(e) => {
+				eprintln!("{e}");
+				Some(ExitCode::FAILURE)
+			}
+		}
+	}
+
+	/// Loads `C` from `--config` with `--profile` and the `APP__` env overlay, then validates it
+	pub fn check_config<C>(&self) -> AppResult<()>
+	where
+		C: ConfigExt + Validator,
+	{
+		let path = self.config.clone().ok_or(app_err!(&SysErr::NoCfgFile))?;
+		C::load_with_profile(path, self.profile.as_deref())?.validate()
+	}
 }
 
-impl From<AppArgs> for LocalConfig {
-	fn from(value: AppArgs) -> Self {
-		let env: RtEnv = match value.app_env {
-			AppEnv::Development => RtEnv::Development,
-			AppEnv::Production => RtEnv::Production,
-		};
+/// Build commit hash.
+///
+/// Resolved from `VERGEN_GIT_SHA` or `BUILD_COMMIT` at compile time, then `BUILD_COMMIT` at
+/// runtime, falling back to the git hash in [`BuildInfo`]. Typically injected by a `build.rs`
+/// using `vergen`:
+///
+/// ```ignore
+/// // build.rs, with `vergen-gitcl = { version = "1", features = ["build"] }` in build-dependencies
+/// fn main() -> anyhow::Result<()> {
+///     let git = vergen_gitcl::GitclBuilder::default().sha(true).build()?;
+///     vergen_gitcl::Emitter::default().add_instructions(&git)?.emit()
+/// }
+/// ```
+///
+/// or by exporting `BUILD_COMMIT=$(git rev-parse --short HEAD)` before `cargo build`.
+pub fn build_commit() -> String {
+	option_env!("VERGEN_GIT_SHA")
+		.or(option_env!("BUILD_COMMIT"))
+		.map(str::to_string)
+		.or_else(|| std::env::var("BUILD_COMMIT").ok())
+		.filter(|c| !c.is_empty())
+		.unwrap_or_else(|| BuildInfo::current().git_hash.to_string())
+}
+
+/// Output of `--commit`, one `key: value` per line
+pub fn build_info_text() -> String {
+	let info = BuildInfo::current();
+	format!(
+		"commit: {}\nversion: {}\nbuilt: {}\nrustc: {}\nprofile: {}",
+		build_commit(),
+		info.version,
+		info.build_timestamp,
+		info.rustc_version,
+		info.profile
+	)
+}
+
+/// Levels accepted by `--log-level`, alone or as the level of a directive
+const ALLOWED_LEVELS: &str = "trace, debug, info, warn, error, off";
+
+fn parse_log_directives(directives: &str) -> anyhow::Result<LogDirectives> {
+	let parsed = LogDirectives::parse(directives)
+		.map_err(|e| anyhow::anyhow!("Invalid log level: {e}, allowed levels: {ALLOWED_LEVELS}"))?;
+	// a bare word is a valid target directive, but as a flag value it is a mistyped level
+	let unknown = parsed
+		.as_str()
+		.split(',')
+		.find(|d| !d.contains(['=', '[', ':']) && d.parse::<LevelFilter>().is_err());
+	if let Some(unknown) = unknown {
+		anyhow::bail!("Invalid log level `{unknown}`, allowed levels: {ALLOWED_LEVELS}");
+	}
+	Ok(parsed)
+}
+
+impl AppArgs {
+	/// Infallible conversion without the production check
+	#[deprecated(
+		note = "use `LocalConfig::try_from(args)`, which requires a config path in production"
+	)]
+	pub fn into_local_config(self) -> LocalConfig {
+		local_config(self)
+	}
+}
+
+/// Fails for `--app-env production` without `--config`, which would only surface when the
+/// config is loaded
+impl TryFrom<AppArgs> for LocalConfig {
+	type Error = anyhow::Error;
+
+	fn try_from(value: AppArgs) -> anyhow::Result<Self> {
+		if matches!(value.app_env, AppEnv::Production) && value.config.is_none() {
+			anyhow::bail!(
+				"--app-env production requires an explicit config file, pass `--config <path>` or set the CONFIG env var"
+			);
+		}
+		Ok(local_config(value))
+	}
+}
+
+fn local_config(value: AppArgs) -> LocalConfig {
+	let env: RtEnv = match value.app_env {
+		AppEnv::Development => RtEnv::Development,
+		AppEnv::Production => RtEnv::Production,
+	};
+
+	LocalConfig {
+		rt_env: env,
+		log_level: value.log_level.as_ref().and_then(LogDirectives::level),
+		log_directives: value.log_level,
+		config_path: value.config,
+		profile: value.profile,
+	}
+}
 
-		Self {
-			rt_env: env,
-			log_level: value.log_level,
-			config_path: value.config,
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use base_infra::validator::Checker;
+	use serde::Deserialize;
+	use std::io::Write;
+
+	#[derive(Deserialize)]
+	struct ServerCfg {
+		port: u16,
+	}
+
+	impl Checker for ServerCfg {
+		fn check(&self) -> AppResult<()> {
+			if self.port == 0 {
+				return base_infra::err!(&SysErr::ConfigError, "port must be positive");
+			}
+			Ok(())
+		}
+	}
+
+	fn args(commit: bool) -> AppArgs {
+		AppArgs::parse_from(if commit {
+			vec!["app", "--app-env", "development", "--commit"]
+		} else {
+			vec!["app", "--app-env", "development"]
+		})
+	}
+
+	#[test]
+	fn test_handle_flags() {
+		let args = args(true);
+		assert!(args.commit);
+		assert!(args.handle_flags());
+		assert!(!build_commit().is_empty());
+		if let Some(sha) = option_env!("VERGEN_GIT_SHA") {
+			assert_eq!(build_commit(), sha);
 		}
+
+		assert!(!self::args(false).handle_flags());
+	}
+
+	#[test]
+	fn test_build_info_text() {
+		let text = build_info_text();
+		let lines: Vec<_> = text.lines().collect();
+		let keys: Vec<_> = lines
+			.iter()
+			.map(|line| line.split_once(": ").unwrap().0)
+			.collect();
+		assert_eq!(keys, ["commit", "version", "built", "rustc", "profile"]);
+		assert_eq!(lines[0], format!("commit: {}", build_commit()));
+		assert!(lines.iter().all(|line| !line.ends_with(": ")));
+	}
+
+	#[test]
+	fn test_log_directives_arg() {
+		let args = AppArgs::try_parse_from([
+			"app",
+			"--app-env",
+			"development",
+			"--log-level",
+			"info,sqlx=warn,my_app::indexer=trace",
+		])
+		.unwrap();
+		let config = LocalConfig::try_from(args).unwrap();
+		assert_eq!(config.log_level, Some(tracing::Level::INFO));
+		assert_eq!(
+			config.log_directives.unwrap().as_str(),
+			"info,sqlx=warn,my_app::indexer=trace"
+		);
+
+		let err = AppArgs::try_parse_from([
+			"app",
+			"--app-env",
+			"development",
+			"--log-level",
+			"info,sqlx=loud",
+		])
+		.err()
+		.unwrap();
+		assert!(err.to_string().contains("sqlx=loud"), "{err}");
+		assert!(err.to_string().contains(ALLOWED_LEVELS), "{err}");
+
+		let err = AppArgs::try_parse_from(["app", "--app-env", "development", "--log-level", "loud"])
+			.err()
+			.unwrap();
+		assert!(err.to_string().contains("`loud`"), "{err}");
+		assert!(err.to_string().contains(ALLOWED_LEVELS), "{err}");
+
+		let argv = [
+			"app",
+			"--app-env",
+			"development",
+			"--log-level",
+			"OFF,my_app=debug",
+		];
+		assert!(AppArgs::try_parse_from(argv).is_ok());
+	}
+
+	#[test]
+	fn test_try_into_local_config() {
+		let prod = |config: Option<&str>| {
+			let mut argv = vec!["app", "--app-env", "production"];
+			argv.extend(config.map(|c| ["--config", c]).into_iter().flatten());
+			AppArgs::parse_from(argv)
+		};
+
+		let local = LocalConfig::try_from(prod(Some("app.yaml"))).unwrap();
+		assert!(!local.rt_env.is_dev());
+		assert_eq!(local.config_path.unwrap(), PathBuf::from("app.yaml"));
+
+		let err = LocalConfig::try_from(prod(None)).unwrap_err();
+		assert!(err.to_string().contains("--config <path>"), "{err}");
+
+		let local = LocalConfig::try_from(args(false)).unwrap();
+		assert!(local.rt_env.is_dev() && local.config_path.is_none());
+
+		#[allow(deprecated)]
+		let local = prod(None).into_local_config();
+		assert!(local.config_path.is_none());
+	}
+
+	#[test]
+	fn test_config_check() {
+		let check = |yaml: &str| {
+			let mut file = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+			file.write_all(yaml.as_bytes()).unwrap();
+			let path = file.path().to_str().unwrap();
+			let argv = [
+				"app",
+				"--app-env",
+				"development",
+				"--config",
+				path,
+				"--config-check",
+			];
+			let args = AppArgs::parse_from(argv);
+			(
+				args.check_config::<ServerCfg>(),
+				args.handle_config_check::<ServerCfg>(),
+			)
+		};
+
+		let (result, code) = check("port: 8080\n");
+		assert!(result.is_ok());
+		assert_eq!(code, Some(ExitCode::SUCCESS));
+
+		let (result, code) = check("port: 0\n");
+		let err = result.unwrap_err();
+		assert!(err.to_string().contains("port must be positive"), "{err}");
+		assert_eq!(code, Some(ExitCode::FAILURE));
+
+		let (result, code) = check("port: [1, 2]\n");
+		assert!(result.is_err());
+		assert_eq!(code, Some(ExitCode::FAILURE));
+
+		let args = self::args(false);
+		assert!(args.handle_config_check::<ServerCfg>().is_none());
+		assert!(args.check_config::<ServerCfg>().is_err());
 	}
 }
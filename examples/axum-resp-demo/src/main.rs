@@ -65,7 +65,7 @@ async fn main() -> anyhow::Result<()> {
 mod tests {
 	use axum::response::IntoResponse;
 	use axum_resp_macro::resp_data;
-	use base_infra::result::SysErr;
+	use base_infra::result::{AppResult, SysErr};
 	use serde::Serialize;
 	use std::time::Duration;
 	use tokio::time::sleep;
@@ -2,5 +2,8 @@ use cli_infra::{AppArgs, Parser};
 
 fn main() {
 	let args = AppArgs::parse();
+	if args.handle_flags() {
+		std::process::exit(0);
+	}
 	println!("{:?}", args.commit);
 }
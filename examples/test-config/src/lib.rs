@@ -1,16 +1,14 @@
 use crate::config::TestAppConfig;
 use base_infra::WorkerGuard;
 use base_infra::config::{ConfigExt, LocalConfig};
-use clap::Parser;
-use cli_infra::AppArgs;
+use cli_infra::{AppArgs, EnvOptions};
 use std::sync::Arc;
 use tracing::debug;
 
 pub mod config;
 
 pub async fn setup_logger() -> anyhow::Result<(Arc<TestAppConfig>, WorkerGuard)> {
-	dotenvy::dotenv().ok();
-	let local_cfg: LocalConfig = AppArgs::parse().into();
+	let local_cfg = LocalConfig::try_from(EnvOptions::default().parse::<AppArgs>())?;
 	eprintln!(">>>cli config: {local_cfg:?}");
 
 	let app_cfg = get_config_client_test(&local_cfg).await?;
@@ -22,6 +20,6 @@ pub async fn setup_logger() -> anyhow::Result<(Arc<TestAppConfig>, WorkerGuard)>
 }
 
 pub async fn get_config_client_test(local_cfg: &LocalConfig) -> anyhow::Result<Arc<TestAppConfig>> {
-	let app_cfg = TestAppConfig::load(local_cfg.config_path()?)?;
+	let app_cfg = TestAppConfig::load_with_profile(local_cfg.config_path()?, local_cfg.profile())?;
 	Ok(Arc::new(app_cfg))
 }
@@ -22,6 +22,23 @@ pub struct RocksdbConfig {
 	pub block_size: u64,
 	/// Whether cache index and filter blocks into block cache.
 	pub cache_index_and_filter_blocks: bool,
+	/// Row cache size for Rocks DB, `None` disables it.
+	///
+	/// The row cache keeps deserialized key-value pairs of point lookups above the block layer. It
+	/// is shared by all column families and is independent of the block cache.
+	pub row_cache_size: Option<u64>,
+	/// Block cache size for read-only opens, `None` uses `block_cache_size`.
+	///
+	/// Read-only opens allocate almost no write buffers, so they can afford a larger cache.
+	pub readonly_block_cache_size: Option<u64>,
+	/// Compression level of L1~Ln (LZ4), -1 keeps the RocksDB default
+	pub compression_level: i32,
+	/// Compression level of the bottommost level (ZSTD), -1 keeps the RocksDB default
+	pub bottommost_compression_level: i32,
+	/// Sample bytes used to train the bottommost ZSTD dictionary, 0 samples without training
+	pub zstd_max_train_bytes: u32,
+	/// Bottommost ZSTD dictionary size in bytes, 0 disables dictionary compression
+	pub zstd_dict_size: u32,
 }
 
 impl Default for RocksdbConfig {
@@ -41,6 +58,25 @@ impl Default for RocksdbConfig {
 			block_size: 4 * (1u64 << 10),
 			// Whether cache index and filter blocks into block cache.
 			cache_index_and_filter_blocks: false,
+			// Row cache is off by default
+			row_cache_size: None,
+			readonly_block_cache_size: None,
+			compression_level: -1,
+			bottommost_compression_level: -1,
+			zstd_max_train_bytes: 0,
+			zstd_dict_size: 0,
+		}
+	}
+}
+
+impl RocksdbConfig {
+	/// The config used by read-only opens, with `readonly_block_cache_size` as the block cache
+	pub fn readonly(&self) -> Self {
+		Self {
+			block_cache_size: self
+				.readonly_block_cache_size
+				.unwrap_or(self.block_cache_size),
+			..*self
 		}
 	}
 }
@@ -0,0 +1,163 @@
+//! Append-only [`AuditSink`] on a column family (feature `audit`).
+
+use crate::codec::orderedcode::{CompositeKey, KeyReader, OrderedKey};
+use crate::schemadb::RksDB;
+use crate::schemadb::schema::ValueCodec;
+use base_infra::map_err;
+use base_infra::result::AppResult;
+use base_infra::tools::audit::{AuditErr, AuditEvent, AuditSink};
+use std::sync::{Arc, Mutex};
+
+/// Events sort by time then write order, `seq` counts every event of the sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AuditKey {
+	/// [`AuditEvent::ts`], unix millis
+	pub ts: i64,
+	pub seq: u64,
+}
+
+impl OrderedKey for AuditKey {
+	fn write_key(&self, key: &mut CompositeKey) {
+		*key = std::mem::take(key).push_i64(self.ts).push_u64(self.seq);
+	}
+
+	fn read_key(reader: &mut KeyReader<'_>) -> AppResult<Self> {
+		Ok(Self {
+			ts: reader.read_i64()?,
+			seq: reader.read_u64()?,
+		})
+	}
+}
+
+crate::define_pub_schema!(AuditLogSchema, AuditKey, AuditEvent, "audit_log");
+
+crate::impl_schema_ordered_key_codec!(AuditLogSchema, AuditKey);
+
+// json, as `detail` is a free-form `serde_json::Value`
+impl ValueCodec<AuditLogSchema> for AuditEvent {
+	fn encode_value(&self) -> AppResult<Vec<u8>> {
+		serde_json::to_vec(self).map_err(map_err!(&AuditErr::AuditEncodeErr))
+	}
+
+	fn decode_value(data: &[u8]) -> AppResult<Self> {
+		serde_json::from_slice(data).map_err(map_err!(&AuditErr::AuditEncodeErr))
+	}
+}
+
+/// Writes each event under a new, strictly increasing [`AuditKey`] of [`AuditLogSchema`], never
+/// overwriting or deleting one. The db must be opened with the `audit_log` column family.
+pub struct RksDbAuditSink {
+	db: Arc<RksDB>,
+	last: Mutex<Option<AuditKey>>,
+}
+
+impl RksDbAuditSink {
+	/// Carries on after the last stored key, so a restart can't reuse one
+	pub fn new(db: Arc<RksDB>) -> AppResult<Self> {
+		let mut iter = db.rev_iter::<AuditLogSchema>()?;
+		iter.seek_to_last();
+		let last = iter.next().transpose()?.map(|(key, _)| key);
+		drop(iter);
+		Ok(Self {
+			db,
+			last: Mutex::new(last),
+		})
+	}
+
+	/// Stored events with a key `ts >= since`, oldest first
+	pub fn events_since(&self, since: i64) -> AppResult<Vec<(AuditKey, AuditEvent)>> {
+		let mut iter = self.db.iter::<AuditLogSchema>()?;
+		iter.seek(&AuditKey { ts: since, seq: 0 })?;
+		iter.collect()
+	}
+}
+
+impl AuditSink for RksDbAuditSink {
+	/// The key `ts` is the event one, or the last key one if the clock went back
+	fn record(&self, event: &AuditEvent) -> AppResult<()> {
+		let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+		let key = match *last {
+			Some(prev) => AuditKey {
+				ts: event.ts.max(prev.ts),
+				seq: prev.seq + 1,
+			},
+			None => AuditKey {
+				ts: event.ts,
+				seq: 0,
+			},
+		};
+		self.db.put::<AuditLogSchema>(&key, event)?;
+		*last = Some(key);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schemadb::schema::Schema;
+	use crate::testing::TestDb;
+	use base_infra::audit;
+	use base_infra::tools::audit::AuditOutcome;
+
+	fn event(ts: i64, action: &str) -> AuditEvent {
+		AuditEvent {
+			ts,
+			..AuditEvent::new("ann", action, "doc/1", AuditOutcome::Success)
+		}
+	}
+
+	#[test]
+	fn test_append_in_order() {
+		let db = TestDb::new(&[AuditLogSchema::COLUMN_FAMILY_NAME]);
+		let sink = RksDbAuditSink::new(db.shared()).unwrap();
+		for (ts, action) in [(10, "a"), (20, "b"), (20, "c"), (30, "d")] {
+			sink.record(&event(ts, action)).unwrap();
+		}
+		audit!(sink, actor: "bob", action: "e", resource: "doc/2", outcome: AuditOutcome::Denied,
+			detail: { "why": "no role" })
+		.unwrap();
+
+		let actions: Vec<_> = db
+			.all::<AuditLogSchema>()
+			.unwrap()
+			.into_iter()
+			.map(|(_, e)| e.action)
+			.collect();
+		assert_eq!(actions, ["a", "b", "c", "d", "e"]);
+		let since: Vec<_> = sink
+			.events_since(20)
+			.unwrap()
+			.into_iter()
+			.map(|(k, e)| (k.ts, e.action))
+			.take(3)
+			.collect();
+		assert_eq!(
+			since,
+			[(20, "b".into()), (20, "c".into()), (30, "d".into())]
+		);
+		let (_, last) = sink.events_since(31).unwrap().pop().unwrap();
+		assert_eq!(last.detail, serde_json::json!({ "why": "no role" }));
+	}
+
+	#[test]
+	fn test_keys_survive_reopen() {
+		let db = TestDb::new(&[AuditLogSchema::COLUMN_FAMILY_NAME]);
+		{
+			let sink = RksDbAuditSink::new(db.shared()).unwrap();
+			sink.record(&event(5, "a")).unwrap();
+			sink.record(&event(5, "b")).unwrap();
+		}
+		let db = db.reopen();
+		let sink = RksDbAuditSink::new(db.shared()).unwrap();
+		sink.record(&event(5, "c")).unwrap();
+
+		// written with an earlier clock, still after the stored events
+		sink.record(&event(1, "d")).unwrap();
+
+		let events = db.all::<AuditLogSchema>().unwrap();
+		let keys: Vec<_> = events.iter().map(|(k, _)| (k.ts, k.seq)).collect();
+		assert_eq!(keys, [(5, 0), (5, 1), (5, 2), (5, 3)]);
+		assert_eq!(events[3].1.ts, 1);
+	}
+}
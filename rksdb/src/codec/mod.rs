@@ -0,0 +1,342 @@
+pub mod orderedcode;
+
+/// ```rust
+/// pub struct SchemaKey;
+///
+/// impl SchemaKey {
+/// 	pub fn encode(&self) -> AppResult<Vec<u8>> {
+/// 		Ok(vec![])
+/// 	}
+///
+/// 	pub fn decode(bytes: &[u8]) -> AppResult<SchemaKey> {
+/// 		Ok(SchemaKey)
+/// 	}
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_schema_key_codec {
+	($schema_type:ty, $key_type:ty) => {
+		impl $crate::schemadb::schema::KeyCodec<$schema_type> for $key_type {
+			fn encode_key(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				self.encode()
+			}
+
+			fn decode_key(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				Self::decode(data)
+			}
+		}
+	};
+}
+
+/// ```rust
+/// pub struct SchemaValue;
+///
+/// impl SchemaValue {
+/// 	pub fn encode(&self) -> AppResult<Vec<u8>> {
+/// 		Ok(vec![])
+/// 	}
+///
+/// 	pub fn decode(bytes: &[u8]) -> AppResult<SchemaValue> {
+/// 		Ok(SchemaValue)
+/// 	}
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_schema_value_codec {
+	($schema_type:ty, $value_type:ty) => {
+		impl $crate::schemadb::schema::ValueCodec<$schema_type> for $value_type {
+			fn encode_value(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				self.encode()
+			}
+
+			fn decode_value(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				Self::decode(data)
+			}
+		}
+	};
+}
+
+/// ```rust
+/// use base_infra::codec::rkyv::RkyvCodecExt;
+/// use base_infra::impl_rkyv_codec;
+/// use rkyv_derive::{Archive, Deserialize, Serialize};
+///
+/// #[derive(Clone, Debug, Default, PartialEq, Archive, Deserialize, Serialize)]
+/// pub struct Value {
+/// 	pub val0: f64,
+/// 	pub val1: Option<String>,
+/// }
+///
+/// // first use impl_rkyv_codec
+/// impl_rkyv_codec!(Value, ArchivedValue);
+///
+/// // and use impl_schema_value_rkyv_codec
+/// // impl_schema_value_rkyv_codec!(...);
+/// ```
+#[macro_export]
+macro_rules! impl_schema_value_rkyv_codec {
+	($schema_type:ty, $value_type:ty) => {
+		impl $crate::schemadb::schema::ValueCodec<$schema_type> for $value_type {
+			fn encode_value(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				use base_infra::codec::rkyv::RkyvCodecExt;
+				self.rkyv_encode()
+			}
+
+			fn decode_value(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				use base_infra::codec::rkyv::RkyvCodecExt;
+				Self::rkyv_decode(data)
+			}
+		}
+	};
+}
+
+/// A macro to generate the `KeyCodec` and `ValueCodec` implementations for a given schema type  with bincode.
+#[macro_export]
+macro_rules! impl_schema_bin_codec {
+	($schema_type:ty, $key_type:ty, $value_type:ty) => {
+		impl $crate::schemadb::schema::KeyCodec<$schema_type> for $key_type {
+			fn encode_key(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				use base_infra::codec::bincode::BinEncodeExt;
+				self.bin_encode()
+			}
+
+			fn decode_key(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				use base_infra::codec::bincode::BinDecodeExt;
+				data.bin_decode::<$key_type>()
+			}
+		}
+
+		impl $crate::schemadb::schema::ValueCodec<$schema_type> for $value_type {
+			fn encode_value(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				use base_infra::codec::bincode::BinEncodeExt;
+				self.bin_encode()
+			}
+
+			fn decode_value(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				use base_infra::codec::bincode::BinDecodeExt;
+				data.bin_decode::<$value_type>()
+			}
+		}
+	};
+}
+
+/// A macro to generate the `ValueCodec` implementations for a given schema type  with bincode.
+#[macro_export]
+macro_rules! impl_schema_value_bin_codec {
+	($schema_type:ty, $value_type:ty) => {
+		impl $crate::schemadb::schema::ValueCodec<$schema_type> for $value_type {
+			fn encode_value(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				use base_infra::codec::bincode::BinEncodeExt;
+				self.bin_encode()
+			}
+
+			fn decode_value(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				use base_infra::codec::bincode::BinDecodeExt;
+				data.bin_decode::<$value_type>()
+			}
+		}
+	};
+}
+
+/// A macro to generate the `KeyCodec` and `ValueCodec` implementations for a given schema type with BCS.
+#[macro_export]
+macro_rules! impl_schema_bcs_codec {
+	($schema_type:ty, $key_type:ty, $value_type:ty) => {
+		$crate::impl_schema_key_bcs_codec!($schema_type, $key_type);
+		$crate::impl_schema_value_bcs_codec!($schema_type, $value_type);
+	};
+}
+
+/// A macro to generate the `KeyCodec` implementation for a given schema type with BCS.
+#[macro_export]
+macro_rules! impl_schema_key_bcs_codec {
+	($schema_type:ty, $key_type:ty) => {
+		impl $crate::schemadb::schema::KeyCodec<$schema_type> for $key_type {
+			fn encode_key(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				$crate::bcs::to_bytes(self)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::BcsErr))
+			}
+
+			fn decode_key(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				$crate::bcs::from_bytes(data)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::BcsErr))
+			}
+		}
+	};
+}
+
+/// A macro to generate the `ValueCodec` implementation for a given schema type with BCS.
+#[macro_export]
+macro_rules! impl_schema_value_bcs_codec {
+	($schema_type:ty, $value_type:ty) => {
+		impl $crate::schemadb::schema::ValueCodec<$schema_type> for $value_type {
+			fn encode_value(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				$crate::bcs::to_bytes(self)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::BcsErr))
+			}
+
+			fn decode_value(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				$crate::bcs::from_bytes(data)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::BcsErr))
+			}
+		}
+	};
+}
+
+/// A macro to generate the `KeyCodec` and `ValueCodec` implementations for a given schema type with
+/// MessagePack, structs encoded as maps so other languages can read them by field name.
+#[cfg(feature = "msgpack")]
+#[macro_export]
+macro_rules! impl_schema_msgpack_codec {
+	($schema_type:ty, $key_type:ty, $value_type:ty) => {
+		impl $crate::schemadb::schema::KeyCodec<$schema_type> for $key_type {
+			fn encode_key(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				$crate::rmp_serde::to_vec_named(self)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::MsgpackErr))
+			}
+
+			fn decode_key(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				$crate::rmp_serde::from_slice(data)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::MsgpackErr))
+			}
+		}
+
+		$crate::impl_schema_value_msgpack_codec!($schema_type, $value_type);
+	};
+}
+
+/// A macro to generate the `ValueCodec` implementation for a given schema type with MessagePack.
+#[cfg(feature = "msgpack")]
+#[macro_export]
+macro_rules! impl_schema_value_msgpack_codec {
+	($schema_type:ty, $value_type:ty) => {
+		impl $crate::schemadb::schema::ValueCodec<$schema_type> for $value_type {
+			fn encode_value(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				$crate::rmp_serde::to_vec_named(self)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::MsgpackErr))
+			}
+
+			fn decode_value(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				$crate::rmp_serde::from_slice(data)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::MsgpackErr))
+			}
+		}
+	};
+}
+
+/// A macro to generate the `KeyCodec` and `ValueCodec` implementations for a given schema type with
+/// CBOR.
+#[cfg(feature = "cbor")]
+#[macro_export]
+macro_rules! impl_schema_cbor_codec {
+	($schema_type:ty, $key_type:ty, $value_type:ty) => {
+		impl $crate::schemadb::schema::KeyCodec<$schema_type> for $key_type {
+			fn encode_key(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				let mut bytes = Vec::new();
+				$crate::ciborium::into_writer(self, &mut bytes)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::CborErr))?;
+				Ok(bytes)
+			}
+
+			fn decode_key(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				$crate::ciborium::from_reader(data)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::CborErr))
+			}
+		}
+
+		$crate::impl_schema_value_cbor_codec!($schema_type, $value_type);
+	};
+}
+
+/// A macro to generate the `ValueCodec` implementation for a given schema type with CBOR.
+#[cfg(feature = "cbor")]
+#[macro_export]
+macro_rules! impl_schema_value_cbor_codec {
+	($schema_type:ty, $value_type:ty) => {
+		impl $crate::schemadb::schema::ValueCodec<$schema_type> for $value_type {
+			fn encode_value(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				let mut bytes = Vec::new();
+				$crate::ciborium::into_writer(self, &mut bytes)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::CborErr))?;
+				Ok(bytes)
+			}
+
+			fn decode_value(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				$crate::ciborium::from_reader(data)
+					.map_err(base_infra::map_err!(&$crate::errors::RksErr::CborErr))
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::define_schema;
+	use crate::schemadb::schema::{KeyCodec, ValueCodec};
+	use crate::testing::TestDb;
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+	pub(crate) struct Account {
+		owner: String,
+		balance: u64,
+	}
+
+	#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+	pub(crate) struct AccountId(u32, String);
+
+	define_schema!(BcsSchema, AccountId, Account, "bcs_accounts");
+	crate::impl_schema_bcs_codec!(BcsSchema, AccountId, Account);
+
+	define_schema!(KeyOnlySchema, AccountId, u64, "bcs_key_only");
+	crate::impl_schema_key_bcs_codec!(KeyOnlySchema, AccountId);
+	crate::impl_schema_value_bin_codec!(KeyOnlySchema, u64);
+
+	define_schema!(ValueOnlySchema, AccountId, Account, "bcs_value_only");
+	crate::impl_schema_key_bcs_codec!(ValueOnlySchema, AccountId);
+	crate::impl_schema_value_bcs_codec!(ValueOnlySchema, Account);
+
+	fn account() -> Account {
+		Account {
+			owner: "alice".to_string(),
+			balance: 42,
+		}
+	}
+
+	#[test]
+	fn test_bcs_round_trip() {
+		let id = AccountId(7, "main".to_string());
+		let key = <AccountId as KeyCodec<KeyOnlySchema>>::encode_key(&id).unwrap();
+		// bcs is canonical: little-endian u32, then a length-prefixed string
+		assert_eq!(key, [7, 0, 0, 0, 4, b'm', b'a', b'i', b'n']);
+		assert_eq!(
+			<AccountId as KeyCodec<KeyOnlySchema>>::decode_key(&key).unwrap(),
+			id
+		);
+
+		let value = <Account as ValueCodec<ValueOnlySchema>>::encode_value(&account()).unwrap();
+		assert_eq!(
+			<Account as ValueCodec<BcsSchema>>::decode_value(&value).unwrap(),
+			account()
+		);
+
+		let err = <Account as ValueCodec<ValueOnlySchema>>::decode_value(&value[..3]).unwrap_err();
+		assert!(err.to_string().contains("bcs001"), "{err}");
+		let err = <AccountId as KeyCodec<BcsSchema>>::decode_key(&[1]).unwrap_err();
+		assert!(err.to_string().contains("bcs001"), "{err}");
+	}
+
+	#[test]
+	fn test_bcs_schemas_in_db() {
+		let db = TestDb::new(&["bcs_accounts", "bcs_key_only", "bcs_value_only"]);
+		let id = AccountId(1, "main".to_string());
+
+		db.put::<BcsSchema>(id.clone(), account()).unwrap();
+		db.put::<KeyOnlySchema>(id.clone(), 99).unwrap();
+		db.put::<ValueOnlySchema>(id.clone(), account()).unwrap();
+
+		assert_eq!(db.get::<BcsSchema>(&id).unwrap(), Some(account()));
+		assert_eq!(db.get::<KeyOnlySchema>(&id).unwrap(), Some(99));
+		assert_eq!(db.get::<ValueOnlySchema>(&id).unwrap(), Some(account()));
+	}
+}
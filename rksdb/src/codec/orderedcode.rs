@@ -0,0 +1,373 @@
+//! Order-preserving key encoding: the byte order of encoded keys is the natural order of the
+//! values, so range and prefix scans work on composite keys.
+//!
+//! - unsigned integers: big-endian, fixed width
+//! - `i64`: big-endian with the sign bit flipped, negatives sort first
+//! - bytes / strings: `0x00` escaped as `0x00 0xFF`, terminated by `0x00 0x01`, so segments sort
+//!   lexicographically and a prefix sorts before its extensions
+//!
+//! Tuples encode their fields in order, giving lexicographic tuple order.
+
+use crate::errors::RksErr;
+use base_infra::err;
+use base_infra::result::AppResult;
+
+const ESCAPE: u8 = 0x00;
+const ESCAPED_ZERO: u8 = 0xFF;
+const TERMINATOR: u8 = 0x01;
+
+/// Builder of an order-preserving composite key
+#[derive(Debug, Default, Clone)]
+pub struct CompositeKey {
+	buf: Vec<u8>,
+}
+
+impl CompositeKey {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn push_u16(mut self, v: u16) -> Self {
+		self.buf.extend_from_slice(&v.to_be_bytes());
+		self
+	}
+
+	pub fn push_u32(mut self, v: u32) -> Self {
+		self.buf.extend_from_slice(&v.to_be_bytes());
+		self
+	}
+
+	pub fn push_u64(mut self, v: u64) -> Self {
+		self.buf.extend_from_slice(&v.to_be_bytes());
+		self
+	}
+
+	pub fn push_i64(mut self, v: i64) -> Self {
+		self.buf
+			.extend_from_slice(&((v as u64) ^ (1 << 63)).to_be_bytes());
+		self
+	}
+
+	pub fn push_bytes(mut self, v: &[u8]) -> Self {
+		for &b in v {
+			if b == ESCAPE {
+				self.buf.extend_from_slice(&[ESCAPE, ESCAPED_ZERO]);
+			} else {
+				self.buf.push(b);
+			}
+		}
+		self.buf.extend_from_slice(&[ESCAPE, TERMINATOR]);
+		self
+	}
+
+	pub fn push_str(self, v: &str) -> Self {
+		self.push_bytes(v.as_bytes())
+	}
+
+	/// Appends `v` through its [`OrderedKey`] impl
+	pub fn push<T: OrderedKey + ?Sized>(mut self, v: &T) -> Self {
+		v.write_key(&mut self);
+		self
+	}
+
+	pub fn into_bytes(self) -> Vec<u8> {
+		self.buf
+	}
+}
+
+/// Reads the segments of a [`CompositeKey`] back, in the order they were pushed
+#[derive(Debug)]
+pub struct KeyReader<'a> {
+	data: &'a [u8],
+}
+
+impl<'a> KeyReader<'a> {
+	pub fn new(data: &'a [u8]) -> Self {
+		Self { data }
+	}
+
+	fn take<const N: usize>(&mut self) -> AppResult<[u8; N]> {
+		if self.data.len() < N {
+			return err!(
+				&RksErr::OrderedKeyErr,
+				format!("need {N} bytes, {} left", self.data.len())
+			);
+		}
+		let (head, tail) = self.data.split_at(N);
+		self.data = tail;
+		Ok(head.try_into().expect("split at N"))
+	}
+
+	pub fn read_u16(&mut self) -> AppResult<u16> {
+		self.take().map(u16::from_be_bytes)
+	}
+
+	pub fn read_u32(&mut self) -> AppResult<u32> {
+		self.take().map(u32::from_be_bytes)
+	}
+
+	pub fn read_u64(&mut self) -> AppResult<u64> {
+		self.take().map(u64::from_be_bytes)
+	}
+
+	pub fn read_i64(&mut self) -> AppResult<i64> {
+		self.take()
+			.map(|bytes| (u64::from_be_bytes(bytes) ^ (1 << 63)) as i64)
+	}
+
+	pub fn read_bytes(&mut self) -> AppResult<Vec<u8>> {
+		let mut out = Vec::new();
+		let mut iter = self.data.iter().enumerate();
+		while let Some((i, &b)) = iter.next() {
+			if b != ESCAPE {
+				out.push(b);
+				continue;
+			}
+			match iter.next() {
+				Some((_, &ESCAPED_ZERO)) => out.push(ESCAPE),
+				Some((_, &TERMINATOR)) => {
+					self.data = &self.data[i + 2..];
+					return Ok(out);
+				}
+				other => {
+					return err!(
+						&RksErr::OrderedKeyErr,
+						format!("bad escape {:?} at {i}", other.map(|(_, b)| b))
+					);
+				}
+			}
+		}
+		err!(&RksErr::OrderedKeyErr, "unterminated byte segment")
+	}
+
+	pub fn read_str(&mut self) -> AppResult<String> {
+		String::from_utf8(self.read_bytes()?).or_else(|e| err!(&RksErr::OrderedKeyErr, e.to_string()))
+	}
+
+	/// Reads a `T` through its [`OrderedKey`] impl
+	pub fn read<T: OrderedKey>(&mut self) -> AppResult<T> {
+		T::read_key(self)
+	}
+
+	/// Errors if bytes are left over
+	pub fn finish(self) -> AppResult<()> {
+		if self.data.is_empty() {
+			Ok(())
+		} else {
+			err!(
+				&RksErr::OrderedKeyErr,
+				format!("{} trailing bytes", self.data.len())
+			)
+		}
+	}
+}
+
+/// A key type with an order-preserving encoding, see [`impl_schema_ordered_key_codec!`]
+pub trait OrderedKey: Sized {
+	fn write_key(&self, key: &mut CompositeKey);
+
+	fn read_key(reader: &mut KeyReader<'_>) -> AppResult<Self>;
+
+	fn encode_ordered(&self) -> Vec<u8> {
+		CompositeKey::new().push(self).into_bytes()
+	}
+
+	/// Decodes a key holding exactly one `Self`
+	fn decode_ordered(data: &[u8]) -> AppResult<Self> {
+		let mut reader = KeyReader::new(data);
+		let key = Self::read_key(&mut reader)?;
+		reader.finish()?;
+		Ok(key)
+	}
+}
+
+macro_rules! impl_ordered_key {
+	($ty:ty, $push:ident, $read:ident) => {
+		impl OrderedKey for $ty {
+			fn write_key(&self, key: &mut CompositeKey) {
+				*key = std::mem::take(key).$push(*self);
+			}
+
+			fn read_key(reader: &mut KeyReader<'_>) -> AppResult<Self> {
+				reader.$read()
+			}
+		}
+	};
+}
+
+impl_ordered_key!(u16, push_u16, read_u16);
+impl_ordered_key!(u32, push_u32, read_u32);
+impl_ordered_key!(u64, push_u64, read_u64);
+impl_ordered_key!(i64, push_i64, read_i64);
+
+impl OrderedKey for String {
+	fn write_key(&self, key: &mut CompositeKey) {
+		*key = std::mem::take(key).push_str(self);
+	}
+
+	fn read_key(reader: &mut KeyReader<'_>) -> AppResult<Self> {
+		reader.read_str()
+	}
+}
+
+impl OrderedKey for Vec<u8> {
+	fn write_key(&self, key: &mut CompositeKey) {
+		*key = std::mem::take(key).push_bytes(self);
+	}
+
+	fn read_key(reader: &mut KeyReader<'_>) -> AppResult<Self> {
+		reader.read_bytes()
+	}
+}
+
+macro_rules! impl_ordered_key_tuple {
+	($($name:ident),+) => {
+		impl<$($name: OrderedKey),+> OrderedKey for ($($name,)+) {
+			#[allow(non_snake_case)]
+			fn write_key(&self, key: &mut CompositeKey) {
+				let ($($name,)+) = self;
+				$($name.write_key(key);)+
+			}
+
+			fn read_key(reader: &mut KeyReader<'_>) -> AppResult<Self> {
+				Ok(($($name::read_key(reader)?,)+))
+			}
+		}
+	};
+}
+
+impl_ordered_key_tuple!(A, B);
+impl_ordered_key_tuple!(A, B, C);
+impl_ordered_key_tuple!(A, B, C, D);
+
+/// A macro to generate the `KeyCodec` implementation for a given schema type from the
+/// [`OrderedKey`](crate::codec::orderedcode::OrderedKey) impl of the key type.
+#[macro_export]
+macro_rules! impl_schema_ordered_key_codec {
+	($schema_type:ty, $key_type:ty) => {
+		impl $crate::schemadb::schema::KeyCodec<$schema_type> for $key_type {
+			fn encode_key(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				Ok($crate::codec::orderedcode::OrderedKey::encode_ordered(self))
+			}
+
+			fn decode_key(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				<$key_type as $crate::codec::orderedcode::OrderedKey>::decode_ordered(data)
+			}
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rand::Rng;
+
+	type Tuple = (u32, i64, String, Vec<u8>);
+
+	fn random_tuple(rng: &mut impl Rng) -> Tuple {
+		// small domains so equal prefixes and the 0x00 escape actually show up
+		let s = (0..rng.random_range(0..3))
+			.map(|_| ['a', 'b', '\0'][rng.random_range(0..3)])
+			.collect();
+		let bytes = (0..rng.random_range(0..4))
+			.map(|_| [0x00, 0x01, 0x7f, 0xff][rng.random_range(0..4)])
+			.collect();
+		let int = match rng.random_range(0..3) {
+			0 => i64::MIN + rng.random_range(0..3),
+			1 => i64::MAX - rng.random_range(0..3),
+			_ => rng.random_range(-3..3),
+		};
+		(rng.random_range(0..3), int, s, bytes)
+	}
+
+	#[test]
+	fn test_encode_order_matches_tuple_order() {
+		let mut rng = rand::rng();
+		let mut samples = (0..2_000)
+			.map(|_| random_tuple(&mut rng))
+			.collect::<Vec<_>>();
+		let mut by_bytes = samples
+			.iter()
+			.map(|t| (t.encode_ordered(), t.clone()))
+			.collect::<Vec<_>>();
+		samples.sort();
+		by_bytes.sort();
+		assert_eq!(
+			by_bytes.into_iter().map(|(_, t)| t).collect::<Vec<_>>(),
+			samples
+		);
+
+		for t in samples {
+			assert_eq!(Tuple::decode_ordered(&t.encode_ordered()).unwrap(), t);
+		}
+	}
+
+	#[test]
+	fn test_fixed_width_order() {
+		let mut rng = rand::rng();
+		let mut ints = (0..1_000)
+			.map(|_| {
+				(
+					rng.random::<u16>(),
+					rng.random::<u64>(),
+					rng.random::<i64>(),
+				)
+			})
+			.collect::<Vec<_>>();
+		ints.extend([
+			(0, 0, i64::MIN),
+			(u16::MAX, u64::MAX, i64::MAX),
+			(1, 1, -1),
+			(1, 1, 0),
+		]);
+		let mut encoded = ints
+			.iter()
+			.map(|(a, b, c)| {
+				CompositeKey::new()
+					.push_u16(*a)
+					.push_u64(*b)
+					.push_i64(*c)
+					.into_bytes()
+			})
+			.collect::<Vec<_>>();
+		ints.sort();
+		encoded.sort();
+		for (bytes, expected) in encoded.iter().zip(&ints) {
+			let mut reader = KeyReader::new(bytes);
+			let decoded = (
+				reader.read_u16().unwrap(),
+				reader.read_u64().unwrap(),
+				reader.read_i64().unwrap(),
+			);
+			reader.finish().unwrap();
+			assert_eq!(&decoded, expected);
+		}
+	}
+
+	#[test]
+	fn test_segments() {
+		let key = CompositeKey::new()
+			.push_str("a\0b")
+			.push_bytes(&[0, 0xff])
+			.push_u32(7)
+			.into_bytes();
+		assert_eq!(
+			key,
+			[b'a', 0, 0xff, b'b', 0, 1, 0, 0xff, 0xff, 0, 1, 0, 0, 0, 7]
+		);
+		let mut reader = KeyReader::new(&key);
+		assert_eq!(reader.read_str().unwrap(), "a\0b");
+		assert_eq!(reader.read_bytes().unwrap(), [0, 0xff]);
+		assert_eq!(reader.read_u32().unwrap(), 7);
+		reader.finish().unwrap();
+
+		// a prefix sorts before its extensions
+		assert!("ab".to_string().encode_ordered() < "ab\0".to_string().encode_ordered());
+		assert!("ab".to_string().encode_ordered() < "abc".to_string().encode_ordered());
+
+		assert!(String::decode_ordered(b"ab").is_err());
+		assert!(String::decode_ordered(&[b'a', 0, 7]).is_err());
+		assert!(u32::decode_ordered(&[0, 0, 1]).is_err());
+		assert!(u16::decode_ordered(&[0, 0, 1]).is_err());
+	}
+}
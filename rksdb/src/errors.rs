@@ -7,6 +7,12 @@ gen_impl_code_enum! {
 	RksErr {
 		RksDbErr = ("RksDb01", "RksDB error"),
 		BcsErr = ("bcs001", "BCS error"),
+		Unhealthy = ("RksDb02", "RksDB background errors detected"),
+		TxConflict = ("RksDb03", "RksDB transaction conflict"),
+		Registry = ("RksDb04", "RksDB registry error"),
+		OrderedKeyErr = ("RksDb05", "RksDB ordered key decode error"),
+		MsgpackErr = ("mpack1", "MessagePack error"),
+		CborErr = ("cbor01", "CBOR error"),
 	}
 }
 
@@ -28,6 +34,9 @@ pub enum RksDbError {
 	RocksDbIncompleteResult(String),
 	#[error("Other RocksDB Error: {0}")]
 	OtherRocksDbError(String),
+	/// Optimistic transaction failed validation on commit, safe to retry.
+	#[error("Transaction conflict: {0}")]
+	TransactionConflict(String),
 }
 
 impl From<anyhow::Error> for RksDbError {
@@ -50,7 +59,11 @@ impl From<std::num::ParseIntError> for RksDbError {
 
 impl From<RksDbError> for AppError {
 	fn from(err: RksDbError) -> Self {
-		AppError::Anyhow(&RksErr::RksDbErr, anyhow!(err))
+		let code = match err {
+			RksDbError::TransactionConflict(_) => &RksErr::TxConflict,
+			_ => &RksErr::RksDbErr,
+		};
+		AppError::Anyhow(code, anyhow!(err))
 	}
 }
 
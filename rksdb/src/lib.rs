@@ -1,7 +1,12 @@
+#[cfg(feature = "audit")]
+pub mod audit;
 pub mod codec;
 pub mod errors;
 mod rdb_opts;
+pub mod registry;
 pub mod schemadb;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
 use crate::{
 	errors::RksDbError,
@@ -18,6 +23,16 @@ use rocksdb::{BlockBasedOptions, Cache, ColumnFamilyDescriptor, DBCompressionTyp
 use rksdb_cfg::{RksDbDirPaths, RocksdbConfig};
 pub use rocksdb::DEFAULT_COLUMN_FAMILY_NAME;
 
+// for the bcs / msgpack / cbor codec macros
+#[doc(hidden)]
+pub use bcs;
+#[cfg(feature = "cbor")]
+#[doc(hidden)]
+pub use ciborium;
+#[cfg(feature = "msgpack")]
+#[doc(hidden)]
+pub use rmp_serde;
+
 pub type DbResult<T, E = RksDbError> = Result<T, E>;
 
 pub type CfPost = fn(ColumnFamilyName, &mut Options);
@@ -54,8 +69,27 @@ pub trait OpenRocksDB {
 	}
 
 	fn gen_db_cfds(with_ttl: bool, rocksdb_config: &RocksdbConfig) -> Vec<ColumnFamilyDescriptor> {
-		let post = Self::cf_opts_post_processor();
+		Self::gen_db_cfds_with_post(with_ttl, rocksdb_config, Self::cf_opts_post_processor())
+	}
+
+	/// Column families for read-only opens: [`RocksdbConfig::readonly`], with
+	/// [`readonly_cf_opts`] applied after [`OpenRocksDB::cf_opts_post_processor`]
+	fn gen_readonly_db_cfds(
+		with_ttl: bool,
+		rocksdb_config: &RocksdbConfig,
+	) -> Vec<ColumnFamilyDescriptor> {
+		Self::gen_db_cfds_with_post(
+			with_ttl,
+			&rocksdb_config.readonly(),
+			readonly_cf_post::<Self>,
+		)
+	}
 
+	fn gen_db_cfds_with_post(
+		with_ttl: bool,
+		rocksdb_config: &RocksdbConfig,
+		post: CfPost,
+	) -> Vec<ColumnFamilyDescriptor> {
 		if with_ttl {
 			let cfs = Self::get_db_column_families_with_ttl();
 			build_cfds_with_post(rocksdb_config, &cfs, post)
@@ -73,9 +107,8 @@ pub trait OpenRocksDB {
 	) -> AppResult<RksDB> {
 		let started_at = Instant::now();
 
-		let cfds = Self::gen_db_cfds(with_ttl, db_config);
-
 		let db = if readonly {
+			let cfds = Self::gen_readonly_db_cfds(with_ttl, db_config);
 			RksDB::open_cf_readonly(
 				&gen_rocksdb_options(db_config, true),
 				path.clone(),
@@ -83,6 +116,7 @@ pub trait OpenRocksDB {
 				cfds,
 			)?
 		} else {
+			let cfds = Self::gen_db_cfds(with_ttl, db_config);
 			RksDB::open_cf(
 				&gen_rocksdb_options(db_config, false),
 				path.clone(),
@@ -104,6 +138,26 @@ pub trait OpenRocksDB {
 #[inline]
 pub fn noop_cf_post(_: ColumnFamilyName, _: &mut Options) {}
 
+fn readonly_cf_post<T: OpenRocksDB + ?Sized>(cf_name: ColumnFamilyName, cf_opts: &mut Options) {
+	T::cf_opts_post_processor()(cf_name, cf_opts);
+	readonly_cf_opts(cf_opts);
+}
+
+/// RocksDB default of `CompressionOptions::window_bits`, only used by zlib
+const ZLIB_WINDOW_BITS: i32 = -14;
+
+/// `CompressionOptions::kDefaultCompressionLevel`, each codec picks its own default
+const DEFAULT_COMPRESSION_LEVEL: i32 = 32767;
+
+/// -1 in the config means the codec default, a literal -1 would be a fast negative ZSTD level
+fn compression_level(level: i32) -> i32 {
+	if level == -1 {
+		DEFAULT_COMPRESSION_LEVEL
+	} else {
+		level
+	}
+}
+
 pub fn build_table_opts(rocksdb_config: &RocksdbConfig) -> (BlockBasedOptions, Cache) {
 	let mut table_opts = BlockBasedOptions::default();
 	table_opts.set_cache_index_and_filter_blocks(rocksdb_config.cache_index_and_filter_blocks);
@@ -146,9 +200,22 @@ pub fn build_cfds_with_post(
 
 		// L1~Ln LZ4
 		cf_opts.set_compression_type(DBCompressionType::Lz4);
+		cf_opts.set_compression_options(
+			ZLIB_WINDOW_BITS,
+			compression_level(rocksdb_config.compression_level),
+			0,
+			0,
+		);
 		// bottommost ZSTD
 		cf_opts.set_bottommost_compression_type(DBCompressionType::Zstd);
-		cf_opts.set_bottommost_zstd_max_train_bytes(0, true);
+		cf_opts.set_bottommost_compression_options(
+			ZLIB_WINDOW_BITS,
+			compression_level(rocksdb_config.bottommost_compression_level),
+			0,
+			rocksdb_config.zstd_dict_size as i32,
+			true,
+		);
+		cf_opts.set_bottommost_zstd_max_train_bytes(rocksdb_config.zstd_max_train_bytes as i32, true);
 
 		cf_opts.set_level_compaction_dynamic_level_bytes(true);
 		cf_opts.set_block_based_table_factory(&table_opts);
@@ -160,6 +227,68 @@ pub fn build_cfds_with_post(
 	cfds
 }
 
+/// Filter setup of a column family and the db wide filter hit counters, see
+/// [`verify_cf_bloom_filter`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomFilterStatus {
+	/// The SST files of the column family carry a filter block
+	pub configured: bool,
+	/// Lookups the filter ruled out
+	pub useful_count: u64,
+	/// Lookups the filter let through, true positives included
+	pub full_positive_count: u64,
+	/// `full_positive / (useful + full_positive + 1)`, only meaningful over probes of absent keys
+	pub false_positive_rate: f64,
+}
+
+/// Checks the bloom filter of `cf_name` after open, to catch a misconfigured table factory early.
+///
+/// `configured` comes from the `filter block size` of `rocksdb.aggregated-table-properties`, so
+/// the column family needs at least one SST file. The counters are the
+/// `rocksdb.bloom.filter.useful` / `rocksdb.bloom.filter.full.positive` statistics tickers,
+/// which stay 0 unless the db was opened with `Options::enable_statistics`.
+pub fn verify_cf_bloom_filter(db: &RksDB, cf_name: &str) -> AppResult<BloomFilterStatus> {
+	let table_props = db
+		.inner
+		.property_value_cf(
+			db.get_cf_handle(cf_name)?,
+			"rocksdb.aggregated-table-properties",
+		)
+		.map_err(RksDbError::from)?
+		.unwrap_or_default();
+	let filter_size = table_props
+		.split(';')
+		.filter_map(|prop| prop.split_once('='))
+		.find(|(name, _)| name.trim() == "filter block size")
+		.and_then(|(_, size)| size.trim().parse::<u64>().ok())
+		.unwrap_or_default();
+
+	let stats = db
+		.inner
+		.property_value("rocksdb.options-statistics")
+		.map_err(RksDbError::from)?
+		.unwrap_or_default();
+	let useful_count = stats_ticker(&stats, "rocksdb.bloom.filter.useful");
+	let full_positive_count = stats_ticker(&stats, "rocksdb.bloom.filter.full.positive");
+
+	Ok(BloomFilterStatus {
+		configured: filter_size > 0,
+		useful_count,
+		full_positive_count,
+		false_positive_rate: full_positive_count as f64
+			/ (useful_count + full_positive_count + 1) as f64,
+	})
+}
+
+/// Count of `ticker` in the statistics dump, lines look like `<ticker> COUNT : <n>`
+fn stats_ticker(stats: &str, ticker: &str) -> u64 {
+	stats
+		.lines()
+		.find_map(|line| line.strip_prefix(ticker)?.trim().strip_prefix("COUNT :"))
+		.and_then(|count| count.trim().parse().ok())
+		.unwrap_or_default()
+}
+
 // pub trait OpenRocksDB {
 // 	fn new(
 // 		path: PathBuf,
@@ -286,3 +415,113 @@ pub fn build_cfds_with_post(
 // 		cfds
 // 	}
 // }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schemadb::schema::Schema;
+	use tempfile::TempDir;
+
+	crate::define_schema!(KvSchema, u64, Vec<u8>, "kv");
+	crate::impl_schema_bin_codec!(KvSchema, u64, Vec<u8>);
+
+	const KEYS: u64 = 10_000;
+
+	fn probe(db: &RksDB) -> BloomFilterStatus {
+		for i in 0..KEYS {
+			db.put::<KvSchema>(&(i * 2), &vec![1; 16]).unwrap();
+		}
+		db.flush_cf(KvSchema::COLUMN_FAMILY_NAME).unwrap();
+		assert_eq!(db.get::<KvSchema>(&42).unwrap(), Some(vec![1; 16]));
+		// odd keys were never written
+		for i in 0..KEYS {
+			assert_eq!(db.get::<KvSchema>(&(i * 2 + 1)).unwrap(), None);
+		}
+		verify_cf_bloom_filter(db, KvSchema::COLUMN_FAMILY_NAME).unwrap()
+	}
+
+	fn db_opts() -> Options {
+		let mut opts = gen_rocksdb_options(&RocksdbConfig::default(), false);
+		opts.enable_statistics();
+		opts
+	}
+
+	#[test]
+	fn test_verify_bloom_filter() {
+		let dir = TempDir::new().unwrap();
+		let cfds = build_cfds_with_post(
+			&RocksdbConfig::default(),
+			&["default", KvSchema::COLUMN_FAMILY_NAME],
+			noop_cf_post,
+		);
+		let db = RksDB::open_cf(&db_opts(), dir.path(), "bloom_db", cfds).unwrap();
+		let status = probe(&db);
+		assert!(status.configured);
+		assert!(status.useful_count > KEYS / 2, "{status:?}");
+		assert!(status.false_positive_rate < 0.02, "{status:?}");
+	}
+
+	#[test]
+	fn test_verify_no_bloom_filter() {
+		let dir = TempDir::new().unwrap();
+		let cfs = vec!["default", KvSchema::COLUMN_FAMILY_NAME];
+		let db = RksDB::open(dir.path(), "plain_db", cfs, &db_opts()).unwrap();
+		let status = probe(&db);
+		assert!(!status.configured);
+		assert_eq!(status.useful_count, 0);
+		assert_eq!(status.false_positive_rate, 0.0);
+
+		assert_eq!(stats_ticker("a.b COUNT : 7\na.b.c COUNT : 9\n", "a.b.c"), 9);
+		assert_eq!(stats_ticker("", "a.b"), 0);
+	}
+
+	/// Bottommost SST size of records sharing a layout, which a dictionary captures across blocks
+	fn compressed_size(config: &RocksdbConfig) -> u64 {
+		use rand::{Rng, SeedableRng};
+
+		let dir = TempDir::new().unwrap();
+		let cfds = build_cfds_with_post(
+			config,
+			&["default", KvSchema::COLUMN_FAMILY_NAME],
+			noop_cf_post,
+		);
+		let opts = gen_rocksdb_options(config, false);
+		let db = RksDB::open_cf(&opts, dir.path(), "dict_db", cfds).unwrap();
+		let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+		// overlapping L0 files, a single one would be trivially moved to the bottommost level
+		// without being recompressed
+		for part in 0..4u64 {
+			for i in (part..20_000u64).step_by(4) {
+				let record = format!(
+					r#"{{"id":{i},"account":"acct-{:08x}","status":"settled","currency":"USD","region":"eu-west-1","amount":{}}}"#,
+					rng.random::<u32>(),
+					rng.random_range(0..1_000_000u32),
+				);
+				db.put::<KvSchema>(&i, &record.into_bytes()).unwrap();
+			}
+			db.flush_cf(KvSchema::COLUMN_FAMILY_NAME).unwrap();
+		}
+		db.compact_all();
+		db.get_property(KvSchema::COLUMN_FAMILY_NAME, "rocksdb.total-sst-files-size")
+			.unwrap()
+	}
+
+	#[test]
+	fn test_zstd_dictionary() {
+		let plain = compressed_size(&RocksdbConfig::default());
+		let dict = compressed_size(&RocksdbConfig {
+			zstd_dict_size: 16 << 10,
+			zstd_max_train_bytes: 1 << 20,
+			..Default::default()
+		});
+		assert!(dict * 100 < plain * 95, "dict {dict} vs plain {plain}");
+
+		let leveled = compressed_size(&RocksdbConfig {
+			bottommost_compression_level: 19,
+			..Default::default()
+		});
+		assert!(leveled < plain, "level 19 {leveled} vs default {plain}");
+		assert_eq!(compression_level(-1), DEFAULT_COMPRESSION_LEVEL);
+		assert_eq!(compression_level(3), 3);
+	}
+}
@@ -1,15 +1,155 @@
 use rksdb_cfg::RocksdbConfig;
-use rocksdb::Options;
+use rocksdb::{Cache, Options};
+
+/// Smallest memtable rocksdb accepts, read-only opens never fill it
+const READONLY_WRITE_BUFFER_SIZE: usize = 64 << 10;
 
 pub fn gen_rocksdb_options(config: &RocksdbConfig, readonly: bool) -> Options {
+	gen_rocksdb_options_with_cache(config, readonly).0
+}
+
+/// Same as [`gen_rocksdb_options`], also returning the row cache handle to read its usage.
+///
+/// `Options` holds its own handle to the row cache and the opened DB keeps it, so dropping the
+/// returned one does not free the cache.
+pub fn gen_rocksdb_options_with_cache(
+	config: &RocksdbConfig,
+	readonly: bool,
+) -> (Options, Option<Cache>) {
 	let mut db_opts = Options::default();
 	db_opts.set_max_open_files(config.max_open_files);
-	db_opts.set_max_total_wal_size(config.max_total_wal_size);
-	db_opts.set_max_background_jobs(config.max_background_jobs);
-	if !readonly {
+	if readonly {
+		// nothing is flushed or compacted, keep the write side minimal
+		db_opts.set_max_background_jobs(1);
+		db_opts.set_db_write_buffer_size(READONLY_WRITE_BUFFER_SIZE);
+		readonly_cf_opts(&mut db_opts);
+	} else {
+		db_opts.set_max_total_wal_size(config.max_total_wal_size);
+		db_opts.set_max_background_jobs(config.max_background_jobs);
 		db_opts.create_if_missing(true);
 		db_opts.create_missing_column_families(true);
 	}
 
-	db_opts
+	let row_cache = config.row_cache_size.map(|size| {
+		let cache = Cache::new_lru_cache(size as usize);
+		db_opts.set_row_cache(&cache);
+		cache
+	});
+
+	(db_opts, row_cache)
+}
+
+/// Minimal memtables and no auto compactions for column families opened read-only
+pub fn readonly_cf_opts(cf_opts: &mut Options) {
+	cf_opts.set_write_buffer_size(READONLY_WRITE_BUFFER_SIZE);
+	cf_opts.set_max_write_buffer_number(2);
+	cf_opts.set_min_write_buffer_number_to_merge(1);
+	cf_opts.set_disable_auto_compactions(true);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::OpenRocksDB;
+	use crate::schemadb::{ColumnFamilyName, RksDB};
+	use base_infra::result::AppResult;
+	use rksdb_cfg::RksDbDirPaths;
+	use std::path::PathBuf;
+	use tempfile::TempDir;
+
+	crate::define_schema!(RowSchema, u32, Vec<u8>, "row");
+	crate::impl_schema_bin_codec!(RowSchema, u32, Vec<u8>);
+
+	#[test]
+	fn test_row_cache() {
+		const CAPACITY: usize = 1 << 20;
+		let config = RocksdbConfig {
+			row_cache_size: Some(CAPACITY as u64),
+			..Default::default()
+		};
+		let (opts, row_cache) = gen_rocksdb_options_with_cache(&config, false);
+		let row_cache = row_cache.expect("row cache enabled");
+
+		let dir = TempDir::new().unwrap();
+		let db = RksDB::open(dir.path(), "row_cache_db", vec!["default", "row"], &opts).unwrap();
+		for i in 0..100u32 {
+			db.put::<RowSchema>(&i, &vec![i as u8; 64]).unwrap();
+		}
+		db.flush_cf("row").unwrap();
+
+		for i in 0..100u32 {
+			assert_eq!(db.get::<RowSchema>(&i).unwrap(), Some(vec![i as u8; 64]));
+		}
+		// rocksdb has no row cache capacity property, so check the usage through the handle
+		let usage = row_cache.get_usage();
+		assert!(usage > 0 && usage <= CAPACITY, "row cache usage {usage}");
+
+		assert_eq!(
+			gen_rocksdb_options_with_cache(&RocksdbConfig::default(), false)
+				.1
+				.map(|c| c.get_usage()),
+			None
+		);
+	}
+
+	struct RowDb(RksDB);
+
+	impl OpenRocksDB for RowDb {
+		fn new_inner(db: RksDB) -> AppResult<Self> {
+			Ok(Self(db))
+		}
+
+		fn get_db_column_families() -> Vec<ColumnFamilyName> {
+			vec!["default", "row"]
+		}
+
+		fn get_db_path(db_paths: RksDbDirPaths) -> PathBuf {
+			db_paths.rdb_root_path().clone()
+		}
+	}
+
+	#[test]
+	fn test_readonly_profile() {
+		const BLOCK_CACHE: u64 = 1 << 20;
+		const READONLY_BLOCK_CACHE: u64 = 4 << 20;
+		let config = RocksdbConfig {
+			block_cache_size: BLOCK_CACHE,
+			readonly_block_cache_size: Some(READONLY_BLOCK_CACHE),
+			..Default::default()
+		};
+		assert_eq!(config.readonly().block_cache_size, READONLY_BLOCK_CACHE);
+		assert_eq!(
+			RocksdbConfig::default().readonly(),
+			RocksdbConfig::default()
+		);
+
+		let dir = TempDir::new().unwrap();
+		let path = dir.path().to_path_buf();
+		{
+			let db = RowDb::new(path.clone(), "readonly_db", &config, false, false).unwrap();
+			for i in 0..100u32 {
+				db.0.put::<RowSchema>(&i, &vec![i as u8; 64]).unwrap();
+			}
+			db.0.flush_cf("row").unwrap();
+			let capacity =
+				db.0.get_property("row", "rocksdb.block-cache-capacity")
+					.unwrap();
+			assert_eq!(capacity, BLOCK_CACHE);
+		}
+
+		let db = RowDb::new(path.clone(), "readonly_db", &config, true, false).unwrap();
+		let capacity =
+			db.0.get_property("row", "rocksdb.block-cache-capacity")
+				.unwrap();
+		assert_eq!(capacity, READONLY_BLOCK_CACHE);
+		for i in 0..100u32 {
+			assert_eq!(db.0.get::<RowSchema>(&i).unwrap(), Some(vec![i as u8; 64]));
+		}
+		assert!(db.0.put::<RowSchema>(&100, &vec![0; 64]).is_err());
+
+		// read-only opens never create the db
+		let missing = dir.path().join("missing");
+		assert!(RowDb::new(missing.clone(), "missing_db", &config, true, false).is_err());
+		assert!(!missing.exists());
+	}
 }
@@ -0,0 +1,345 @@
+//! Opens and owns the [`RksDB`]s of an application by [`OpenRocksDB`] type.
+
+use crate::OpenRocksDB;
+use crate::errors::RksErr;
+use crate::schemadb::RksDB;
+use base_infra::err;
+use base_infra::map_err;
+use base_infra::result::{AppResult, SysErr};
+use rksdb_cfg::{RksDbDirPaths, RocksdbConfig};
+use std::any::TypeId;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+type OpenFn = fn(PathBuf, &str, &RocksdbConfig, bool, bool) -> AppResult<RksDB>;
+
+struct DbEntry {
+	type_id: TypeId,
+	name: String,
+	with_ttl: bool,
+	path: fn(RksDbDirPaths) -> PathBuf,
+	open: OpenFn,
+	db: Option<Arc<RksDB>>,
+}
+
+/// Size of one registered db, summed over its column families
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbStats {
+	pub name: String,
+	pub estimate_num_keys: u64,
+	pub total_sst_files_size: u64,
+}
+
+/// Registered dbs are opened together by [`DbRegistry::open_all`] and closed in reverse
+/// registration order by [`DbRegistry::close`] or on drop.
+///
+/// ```ignore
+/// let mut registry = DbRegistry::new();
+/// registry.register::<StateDb>("state", true)?;
+/// registry.register::<IndexDb>("index", false)?;
+/// registry.open_all(&config.get_dir_paths(), &rocksdb_config, false)?;
+/// let state = registry.get::<StateDb>().unwrap();
+/// ```
+#[derive(Default)]
+pub struct DbRegistry {
+	entries: Vec<DbEntry>,
+}
+
+impl DbRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Registers `T` under `name`, which is also the name the db is opened with
+	pub fn register<T: OpenRocksDB + 'static>(
+		&mut self,
+		name: &str,
+		with_ttl: bool,
+	) -> AppResult<()> {
+		let type_id = TypeId::of::<T>();
+		if self
+			.entries
+			.iter()
+			.any(|e| e.type_id == type_id || e.name == name)
+		{
+			return err!(&RksErr::Registry, format!("{name} registered twice"));
+		}
+		self.entries.push(DbEntry {
+			type_id,
+			name: name.to_string(),
+			with_ttl,
+			path: T::get_db_path,
+			open: T::open_rocksdb,
+			db: None,
+		});
+		Ok(())
+	}
+
+	/// Opens every registered db in registration order, the already opened ones stay open on error
+	pub fn open_all(
+		&mut self,
+		db_paths: &RksDbDirPaths,
+		db_config: &RocksdbConfig,
+		readonly: bool,
+	) -> AppResult<()> {
+		for entry in self.entries.iter_mut() {
+			if entry.db.is_none() {
+				let path = (entry.path)(db_paths.clone());
+				let db = (entry.open)(path, &entry.name, db_config, readonly, entry.with_ttl)?;
+				entry.db = Some(Arc::new(db));
+			}
+		}
+		Ok(())
+	}
+
+	/// [`Self::open_all`] with the dbs opened concurrently on the blocking thread pool
+	pub async fn open_all_async(
+		&mut self,
+		db_paths: &RksDbDirPaths,
+		db_config: &RocksdbConfig,
+		readonly: bool,
+	) -> AppResult<()> {
+		let tasks = self
+			.entries
+			.iter()
+			.filter(|entry| entry.db.is_none())
+			.map(|entry| {
+				let path = (entry.path)(db_paths.clone());
+				let (open, name, with_ttl) = (entry.open, entry.name.clone(), entry.with_ttl);
+				let db_config = *db_config;
+				tokio::task::spawn_blocking(move || {
+					open(path, &name, &db_config, readonly, with_ttl).map(|db| (name, db))
+				})
+			})
+			.collect::<Vec<_>>();
+
+		let mut first_err = None;
+		for res in futures::future::join_all(tasks).await {
+			match res.map_err(map_err!(&SysErr::TaskJoinErr)).and_then(|r| r) {
+				Ok((name, db)) => {
+					if let Some(entry) = self.entries.iter_mut().find(|e| e.name == name) {
+						entry.db = Some(Arc::new(db));
+					}
+				}
+				Err(e) => {
+					first_err.get_or_insert(e);
+				}
+			}
+		}
+		first_err.map_or(Ok(()), Err)
+	}
+
+	/// Handle of the db registered as `T`, `None` until opened
+	pub fn get<T: OpenRocksDB + 'static>(&self) -> Option<Arc<RksDB>> {
+		let type_id = TypeId::of::<T>();
+		self.entries
+			.iter()
+			.find(|e| e.type_id == type_id)
+			.and_then(|e| e.db.clone())
+	}
+
+	pub fn get_by_name(&self, name: &str) -> Option<Arc<RksDB>> {
+		self.entries
+			.iter()
+			.find(|e| e.name == name)
+			.and_then(|e| e.db.clone())
+	}
+
+	/// Names of the registered dbs, in registration order
+	pub fn names(&self) -> Vec<&str> {
+		self.entries.iter().map(|e| e.name.as_str()).collect()
+	}
+
+	fn opened(&self) -> impl Iterator<Item = &Arc<RksDB>> {
+		self.entries.iter().filter_map(|e| e.db.as_ref())
+	}
+
+	/// Stats of every opened db, in registration order
+	pub fn stats(&self) -> AppResult<Vec<DbStats>> {
+		self.opened()
+			.map(|db| {
+				let mut stats = DbStats {
+					name: db.name().to_string(),
+					estimate_num_keys: 0,
+					total_sst_files_size: 0,
+				};
+				for cf_name in db.list_column_families() {
+					stats.estimate_num_keys +=
+						db.get_property(&cf_name, "rocksdb.estimate-num-keys")?;
+					stats.total_sst_files_size +=
+						db.get_property(&cf_name, "rocksdb.total-sst-files-size")?;
+				}
+				Ok(stats)
+			})
+			.collect()
+	}
+
+	/// [`RksDB::health`] of every opened db, failing on the first unhealthy one
+	pub fn health(&self) -> AppResult<()> {
+		for entry in &self.entries {
+			match &entry.db {
+				Some(db) => db.health()?,
+				None => return err!(&RksErr::Unhealthy, format!("{} not opened", entry.name)),
+			}
+		}
+		Ok(())
+	}
+
+	/// Closes the dbs in reverse registration order. A db still shared elsewhere closes when its
+	/// last handle drops.
+	pub fn close(&mut self) {
+		for entry in self.entries.iter_mut().rev() {
+			if let Some(db) = entry.db.take() {
+				match Arc::try_unwrap(db) {
+					Ok(db) => drop(db),
+					Err(db) => warn!(
+						rocksdb_name = entry.name,
+						handles = Arc::strong_count(&db) - 1,
+						"RksDB still in use, closing on last handle drop."
+					),
+				}
+			}
+		}
+		info!("DbRegistry closed.");
+	}
+}
+
+impl Drop for DbRegistry {
+	fn drop(&mut self) {
+		if self.entries.iter().any(|e| e.db.is_some()) {
+			self.close();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::schemadb::ColumnFamilyName;
+	use tempfile::TempDir;
+
+	crate::define_schema!(StateSchema, u32, Vec<u8>, "state");
+	crate::impl_schema_bin_codec!(StateSchema, u32, Vec<u8>);
+	crate::define_schema!(IndexSchema, u32, Vec<u8>, "index");
+	crate::impl_schema_bin_codec!(IndexSchema, u32, Vec<u8>);
+
+	struct StateDb;
+	struct IndexDb;
+
+	impl OpenRocksDB for StateDb {
+		fn new_inner(_: RksDB) -> AppResult<Self> {
+			Ok(Self)
+		}
+
+		fn get_db_column_families() -> Vec<ColumnFamilyName> {
+			vec!["default", "state"]
+		}
+
+		fn get_db_path(db_paths: RksDbDirPaths) -> PathBuf {
+			db_paths.rdb_root_path().join("state")
+		}
+	}
+
+	impl OpenRocksDB for IndexDb {
+		fn new_inner(_: RksDB) -> AppResult<Self> {
+			Ok(Self)
+		}
+
+		fn get_db_column_families() -> Vec<ColumnFamilyName> {
+			vec!["default", "index"]
+		}
+
+		fn get_db_path(db_paths: RksDbDirPaths) -> PathBuf {
+			db_paths.rdb_root_path().join("index")
+		}
+	}
+
+	fn registry() -> DbRegistry {
+		let mut registry = DbRegistry::new();
+		registry.register::<StateDb>("state", true).unwrap();
+		registry.register::<IndexDb>("index", false).unwrap();
+		registry
+	}
+
+	fn use_both(registry: &DbRegistry) {
+		let state = registry.get::<StateDb>().unwrap();
+		let index = registry.get_by_name("index").unwrap();
+		for i in 0..10u32 {
+			state.put::<StateSchema>(&i, &vec![1; 8]).unwrap();
+			index.put::<IndexSchema>(&i, &vec![2; 8]).unwrap();
+		}
+		assert!(state.has_column_family("state"));
+		assert!(!state.has_column_family("index"));
+		assert!(
+			RksDB::get_ttl_column_families()
+				.iter()
+				.all(|cf| state.has_column_family(cf))
+		);
+		assert_eq!(index.get::<IndexSchema>(&3).unwrap(), Some(vec![2; 8]));
+		state.flush_all().unwrap();
+
+		let stats = registry.stats().unwrap();
+		assert_eq!(
+			stats.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(),
+			["state", "index"]
+		);
+		assert!(stats[0].total_sst_files_size > 0);
+		assert!(stats[1].estimate_num_keys > 0);
+		registry.health().unwrap();
+	}
+
+	#[test]
+	fn test_open_all() {
+		let dir = TempDir::new().unwrap();
+		let paths = RksDbDirPaths::from_path(dir.path());
+		let mut registry = registry();
+		assert!(registry.get::<StateDb>().is_none());
+		assert!(registry.health().is_err());
+		assert!(registry.register::<StateDb>("other", false).is_err());
+		assert!(registry.register::<IndexDb>("state", false).is_err());
+
+		registry
+			.open_all(&paths, &RocksdbConfig::default(), false)
+			.unwrap();
+		assert_eq!(registry.names(), ["state", "index"]);
+		use_both(&registry);
+
+		let shared = registry.get::<IndexDb>().unwrap();
+		registry.close();
+		assert!(registry.get::<StateDb>().is_none());
+		// the shared handle outlives the registry
+		assert_eq!(shared.get::<IndexSchema>(&3).unwrap(), Some(vec![2; 8]));
+		drop(shared);
+
+		let mut readonly = self::registry();
+		readonly
+			.open_all(&paths, &RocksdbConfig::default(), true)
+			.unwrap();
+		let index = readonly.get::<IndexDb>().unwrap();
+		assert_eq!(index.get::<IndexSchema>(&9).unwrap(), Some(vec![2; 8]));
+		assert!(index.put::<IndexSchema>(&10, &vec![]).is_err());
+	}
+
+	#[tokio::test]
+	async fn test_open_all_async() {
+		let dir = TempDir::new().unwrap();
+		let paths = RksDbDirPaths::from_path(dir.path());
+		let mut registry = registry();
+		registry
+			.open_all_async(&paths, &RocksdbConfig::default(), false)
+			.await
+			.unwrap();
+		use_both(&registry);
+		drop(registry);
+
+		// closed on drop, so the dbs can be opened again
+		let mut registry = self::registry();
+		registry
+			.open_all_async(&paths, &RocksdbConfig::default(), false)
+			.await
+			.unwrap();
+		let state = registry.get::<StateDb>().unwrap();
+		assert_eq!(state.get::<StateSchema>(&0).unwrap(), Some(vec![1; 8]));
+	}
+}
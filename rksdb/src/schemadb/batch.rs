@@ -1,5 +1,8 @@
+use crate::schemadb::RksDB;
+use crate::schemadb::ryw::{ReadYourWrites, ReadYourWritesBatch};
 use crate::schemadb::schema::{KeyCodec, Schema, ValueCodec};
 use base_infra::result::AppResult;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Mutex;
 
@@ -15,7 +18,7 @@ pub enum WriteOp {
 /// will be applied in the order in which they are added to the `SchemaBatch`.
 #[derive(Debug)]
 pub struct SchemaBatch {
-	pub(crate) rows: Mutex<HashMap<ColumnFamilyName, Vec<WriteOp>>>,
+	pub(crate) rows: Mutex<HashMap<Cow<'static, str>, Vec<WriteOp>>>,
 }
 
 impl Default for SchemaBatch {
@@ -39,7 +42,7 @@ impl SchemaBatch {
 		self.rows
 			.lock()
 			.unwrap()
-			.entry(S::COLUMN_FAMILY_NAME)
+			.entry(Cow::Borrowed(S::COLUMN_FAMILY_NAME))
 			.or_default()
 			.push(WriteOp::Value { key, value });
 
@@ -52,10 +55,173 @@ impl SchemaBatch {
 		self.rows
 			.lock()
 			.unwrap()
-			.entry(S::COLUMN_FAMILY_NAME)
+			.entry(Cow::Borrowed(S::COLUMN_FAMILY_NAME))
 			.or_default()
 			.push(WriteOp::Deletion { key });
 
 		Ok(())
 	}
+
+	/// Adds an insert/update of already encoded bytes under any column family.
+	///
+	/// `cf_name` is only checked by [`RksDB::write_schemas`], an unknown one fails the whole batch.
+	pub fn put_raw(&self, cf_name: &str, key: Vec<u8>, value: Vec<u8>) {
+		self.push_raw(cf_name, WriteOp::Value { key, value });
+	}
+
+	/// Adds a delete of an already encoded key under any column family, see
+	/// [`SchemaBatch::put_raw`].
+	pub fn delete_raw(&self, cf_name: &str, key: Vec<u8>) {
+		self.push_raw(cf_name, WriteOp::Deletion { key });
+	}
+
+	fn push_raw(&self, cf_name: &str, op: WriteOp) {
+		self.rows
+			.lock()
+			.unwrap()
+			.entry(Cow::Owned(cf_name.to_string()))
+			.or_default()
+			.push(op);
+	}
+}
+
+/// A [`SchemaBatch`] bound to a DB, reads see its staged writes before falling back to the DB.
+///
+/// Staging and lookups are those of [`ReadYourWritesBatch`].
+pub struct CowBatch<'db> {
+	inner: ReadYourWritesBatch<'db>,
+}
+
+impl<'db> CowBatch<'db> {
+	pub fn new(db: &'db RksDB) -> Self {
+		Self {
+			inner: ReadYourWritesBatch::new(db),
+		}
+	}
+
+	pub fn put<S: Schema>(&self, key: &S::Key, value: &S::Value) -> AppResult<()> {
+		self.inner.put::<S>(key, value)
+	}
+
+	pub fn delete<S: Schema>(&self, key: &S::Key) -> AppResult<()> {
+		self.inner.delete::<S>(key)
+	}
+
+	/// The staged value for `key` if any, otherwise the committed one.
+	pub fn get<S: Schema>(&self, key: &S::Key) -> AppResult<Option<S::Value>> {
+		ReadYourWrites::get::<S>(&self.inner, key)
+	}
+
+	/// Writes all staged operations atomically.
+	pub fn commit(self) -> AppResult<()> {
+		self.inner.commit()
+	}
+
+	pub fn into_batch(self) -> SchemaBatch {
+		self.inner.into_batch()
+	}
+}
+
+impl ReadYourWrites for CowBatch<'_> {
+	fn get<S: Schema>(&self, key: &S::Key) -> AppResult<Option<S::Value>> {
+		CowBatch::get::<S>(self, key)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::TestDb;
+	use bincode::{Decode, Encode};
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
+	pub struct TestKey(u32);
+
+	#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
+	pub struct TestValue(u64);
+
+	crate::define_schema!(TestSchema, TestKey, TestValue, "cow_schema");
+	crate::impl_schema_bin_codec!(TestSchema, TestKey, TestValue);
+
+	#[test]
+	fn test_cow_batch_read_after_write() {
+		let test_db = TestDb::new(&[TestSchema::COLUMN_FAMILY_NAME]);
+		let db = test_db.inner();
+		db.put::<TestSchema>(&TestKey(2), &TestValue(20)).unwrap();
+
+		let batch = CowBatch::new(db);
+		batch.put::<TestSchema>(&TestKey(1), &TestValue(1)).unwrap();
+		batch
+			.put::<TestSchema>(&TestKey(1), &TestValue(10))
+			.unwrap();
+		batch.delete::<TestSchema>(&TestKey(2)).unwrap();
+
+		assert_eq!(
+			batch.get::<TestSchema>(&TestKey(1)).unwrap(),
+			Some(TestValue(10))
+		);
+		assert_eq!(batch.get::<TestSchema>(&TestKey(2)).unwrap(), None);
+		assert_eq!(db.get::<TestSchema>(&TestKey(1)).unwrap(), None);
+		assert_eq!(
+			db.get::<TestSchema>(&TestKey(2)).unwrap(),
+			Some(TestValue(20))
+		);
+
+		batch.commit().unwrap();
+		assert_eq!(
+			db.get::<TestSchema>(&TestKey(1)).unwrap(),
+			Some(TestValue(10))
+		);
+		assert_eq!(db.get::<TestSchema>(&TestKey(2)).unwrap(), None);
+	}
+
+	fn raw_key(key: u32) -> Vec<u8> {
+		<TestKey as KeyCodec<TestSchema>>::encode_key(&TestKey(key)).unwrap()
+	}
+
+	#[test]
+	fn test_raw_ops() {
+		let test_db = TestDb::new(&[TestSchema::COLUMN_FAMILY_NAME]);
+		let db = test_db.inner();
+		db.put::<TestSchema>(&TestKey(1), &TestValue(1)).unwrap();
+		db.put::<TestSchema>(&TestKey(2), &TestValue(2)).unwrap();
+
+		let batch = SchemaBatch::new();
+		batch.put::<TestSchema>(&TestKey(3), &TestValue(3)).unwrap();
+		batch.delete_raw(TestSchema::COLUMN_FAMILY_NAME, raw_key(1));
+		let value = <TestValue as ValueCodec<TestSchema>>::encode_value(&TestValue(40)).unwrap();
+		batch.put_raw(TestSchema::COLUMN_FAMILY_NAME, raw_key(4), value);
+		db.write_schemas(batch).unwrap();
+
+		let rows = db.get_all::<TestSchema>().unwrap();
+		assert_eq!(
+			rows,
+			vec![
+				(TestKey(2), TestValue(2)),
+				(TestKey(3), TestValue(3)),
+				(TestKey(4), TestValue(40)),
+			]
+		);
+	}
+
+	#[test]
+	fn test_raw_ops_unknown_cf() {
+		let test_db = TestDb::new(&[TestSchema::COLUMN_FAMILY_NAME]);
+		let db = test_db.inner();
+		db.put::<TestSchema>(&TestKey(1), &TestValue(1)).unwrap();
+
+		let batch = SchemaBatch::new();
+		batch.put::<TestSchema>(&TestKey(2), &TestValue(2)).unwrap();
+		batch.delete::<TestSchema>(&TestKey(1)).unwrap();
+		batch.delete_raw("no_such_cf", raw_key(1));
+		let err = db.write_schemas(batch).unwrap_err();
+		assert!(err.to_string().contains("no_such_cf"), "{err}");
+
+		// nothing of the batch was applied
+		assert_eq!(
+			db.get_all::<TestSchema>().unwrap(),
+			vec![(TestKey(1), TestValue(1))]
+		);
+	}
 }
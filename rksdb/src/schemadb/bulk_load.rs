@@ -0,0 +1,64 @@
+use crate::schemadb::RksDB;
+use base_infra::result::AppResult;
+use tracing::{info, warn};
+
+/// Guard for bulk loads: disables auto compactions and WAL fsync on creation. [`Self::finish`]
+/// (or drop) flushes the memtables, re-enables auto compactions and restores the previous WAL
+/// fsync setting. Only `finish` also compacts every column family, drop leaves the loaded files to
+/// the background compactions.
+///
+/// Auto compactions are re-enabled unconditionally, RocksDB does not expose the previous value.
+pub struct BulkLoadSession<'a> {
+	db: &'a RksDB,
+	prev_sync_writes: bool,
+	finished: bool,
+}
+
+impl<'a> BulkLoadSession<'a> {
+	pub(crate) fn new(db: &'a RksDB) -> AppResult<Self> {
+		db.set_auto_compaction(false)?;
+		let prev_sync_writes = db.set_sync_writes(false);
+		info!(rocksdb_name = db.name(), "Bulk load session started.");
+		Ok(Self {
+			db,
+			prev_sync_writes,
+			finished: false,
+		})
+	}
+
+	pub fn db(&self) -> &RksDB {
+		self.db
+	}
+
+	/// Ends the session, returning the first error hit while restoring.
+	pub fn finish(mut self) -> AppResult<()> {
+		self.finished = true;
+		self.restore()?;
+		self.db.compact_all();
+		info!(rocksdb_name = self.db.name(), "Bulk load session finished.");
+		Ok(())
+	}
+
+	fn restore(&self) -> AppResult<()> {
+		self.db.set_sync_writes(self.prev_sync_writes);
+		// unsynced writes become durable once in SST files
+		let flushed = self.db.flush_all();
+		self.db.set_auto_compaction(true)?;
+		flushed
+	}
+}
+
+impl Drop for BulkLoadSession<'_> {
+	fn drop(&mut self) {
+		if self.finished {
+			return;
+		}
+		if let Err(err) = self.restore() {
+			warn!(
+				rocksdb_name = self.db.name(),
+				error = %err,
+				"Failed to restore settings after bulk load."
+			);
+		}
+	}
+}
@@ -1,15 +1,22 @@
 use crate::{
-	errors::RksDbError,
+	errors::{RksDbError, RksErr},
 	schemadb::{
 		batch::{SchemaBatch, WriteOp},
+		bulk_load::BulkLoadSession,
 		iterator::{ScanDirection, SchemaIterator},
-		schema::{KeyCodec, Schema, ValueCodec},
-		utils::{DeUnc, IntoDbResult, OpenMode, default_write_options},
+		schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec},
+		utils::{DeUnc, IntoDbResult, OpenMode, default_read_options, default_write_options},
 	},
 };
 use anyhow::format_err;
-use base_infra::result::AppResult;
-use rocksdb::{ColumnFamilyDescriptor, DBCompressionType, Options, ReadOptions};
+use base_infra::result::{AppResult, SysErr};
+use base_infra::runtimes::Deadline;
+use base_infra::{err, map_err};
+use rocksdb::{
+	ColumnFamilyDescriptor, DBCompressionType, DEFAULT_COLUMN_FAMILY_NAME, Options, ReadOptions,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{collections::HashSet, path::Path};
 use tracing::{info, warn};
 
@@ -18,6 +25,10 @@ use tracing::{info, warn};
 #[derive(Debug)]
 pub struct RksDB {
 	name: String, // for logging
+	/// Every CF opened with the db, including `default` and unrecognized ones
+	cf_names: Vec<String>,
+	/// Whether writes fsync the WAL, turned off during a [`BulkLoadSession`]
+	sync_writes: AtomicBool,
 	pub(crate) inner: rocksdb::DB,
 }
 
@@ -88,6 +99,12 @@ impl RksDB {
 		cfds: Vec<ColumnFamilyDescriptor>,
 		open_mode: OpenMode,
 	) -> AppResult<RksDB> {
+		// rocksdb creates the directory for its info log even when the read-only open fails
+		if matches!(open_mode, OpenMode::ReadOnly) && !path.as_ref().exists() {
+			let path = path.as_ref().display();
+			return Err(RksDbError::NotFound(format!("RocksDB {name} at {path}")).into());
+		}
+
 		// ignore error, since it'll fail to list cfs on the first open
 		let existing_cfs = rocksdb::DB::list_cf(db_opts, path.de_unc()).unwrap_or_default();
 
@@ -104,7 +121,18 @@ impl RksDB {
 				ColumnFamilyDescriptor::new(cf.to_string(), cf_opts)
 			})
 			.collect::<Vec<_>>();
-		let all_cfds = cfds.into_iter().chain(unrecognized_cfds);
+		let all_cfds = cfds
+			.into_iter()
+			.chain(unrecognized_cfds)
+			.collect::<Vec<_>>();
+		let mut cf_names = all_cfds
+			.iter()
+			.map(|cfd| cfd.name().to_string())
+			.collect::<Vec<_>>();
+		// rocksdb always opens the default CF
+		if !cf_names.iter().any(|cf| cf == DEFAULT_COLUMN_FAMILY_NAME) {
+			cf_names.push(DEFAULT_COLUMN_FAMILY_NAME.to_string());
+		}
 
 		let inner = {
 			use OpenMode::*;
@@ -130,10 +158,15 @@ impl RksDB {
 		}
 		.into_db_res()?;
 
-		Ok(Self::log_construct(name, open_mode, inner))
+		Ok(Self::log_construct(name, open_mode, cf_names, inner))
 	}
 
-	fn log_construct(name: &str, open_mode: OpenMode, inner: rocksdb::DB) -> RksDB {
+	fn log_construct(
+		name: &str,
+		open_mode: OpenMode,
+		cf_names: Vec<String>,
+		inner: rocksdb::DB,
+	) -> RksDB {
 		info!(
 			rocksdb_name = name,
 			open_mode = ?open_mode,
@@ -141,6 +174,8 @@ impl RksDB {
 		);
 		RksDB {
 			name: name.to_string(),
+			cf_names,
+			sync_writes: AtomicBool::new(true),
 			inner,
 		}
 	}
@@ -216,6 +251,40 @@ impl RksDB {
 		Ok(res_vec)
 	}
 
+	/// Whether any key of `S` starts with the encoded `prefix`, without decoding anything
+	pub fn any_in_prefix<S: Schema>(&self, prefix: &[u8]) -> AppResult<bool> {
+		let cf_handle = self.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
+		let mut iter = self.inner.raw_iterator_cf(cf_handle);
+		iter.seek(prefix);
+		if !iter.valid() {
+			iter.status().into_db_res()?;
+			return Ok(false);
+		}
+		Ok(iter.key().is_some_and(|key| key.starts_with(prefix)))
+	}
+
+	/// Number of keys of `S` in `[start, end)`, scanning at most `limit` of them.
+	///
+	/// Only keys are decoded, so `limit: Some(n)` answers "at least `n`?" cheaply.
+	pub fn count_range<S: Schema>(
+		&self,
+		start: &impl SeekKeyCodec<S>,
+		end: &impl SeekKeyCodec<S>,
+		limit: Option<usize>,
+	) -> AppResult<usize> {
+		let mut opts = default_read_options();
+		opts.set_iterate_upper_bound(end.encode_seek_key()?);
+		let mut iter = self.iter_with_opts::<S>(opts)?;
+		iter.seek(start)?;
+
+		let limit = limit.unwrap_or(usize::MAX);
+		let mut count = 0;
+		while count < limit && iter.next_key()?.is_some() {
+			count += 1;
+		}
+		Ok(count)
+	}
+
 	/// Writes single record.
 	pub fn put<S: Schema>(&self, key: &S::Key, value: &S::Value) -> AppResult<()> {
 		// Not necessary to use a batch, but we'd like a central place to bump counters.
@@ -286,12 +355,56 @@ impl RksDB {
 		}
 
 		self.inner
-			.write_opt(db_batch, &default_write_options())
+			.write_opt(db_batch, &self.write_options())
 			.into_db_res()?;
 
 		Ok(())
 	}
 
+	fn write_options(&self) -> rocksdb::WriteOptions {
+		let mut opts = default_write_options();
+		opts.set_sync(self.sync_writes());
+		opts
+	}
+
+	/// Whether writes fsync the WAL before returning
+	pub fn sync_writes(&self) -> bool {
+		self.sync_writes.load(Ordering::Acquire)
+	}
+
+	/// Sets whether writes fsync the WAL, returning the previous setting
+	pub(crate) fn set_sync_writes(&self, sync: bool) -> bool {
+		self.sync_writes.swap(sync, Ordering::AcqRel)
+	}
+
+	/// [`Self::write_schemas`] on the blocking thread pool, keeping the write syscall off the
+	/// async worker threads, refused once the current [`Deadline`] has passed
+	pub async fn write_schemas_async(self: &Arc<Self>, batch: SchemaBatch) -> AppResult<()> {
+		Deadline::check()?;
+		let db = Arc::clone(self);
+		tokio::task::spawn_blocking(move || db.write_schemas(batch))
+			.await
+			.map_err(map_err!(&SysErr::TaskJoinErr))?
+	}
+
+	/// [`Self::put`] through [`Self::write_schemas_async`]
+	pub async fn put_async<S: Schema>(
+		self: &Arc<Self>,
+		key: &S::Key,
+		value: &S::Value,
+	) -> AppResult<()> {
+		let batch = SchemaBatch::new();
+		batch.put::<S>(key, value)?;
+		self.write_schemas_async(batch).await
+	}
+
+	/// [`Self::delete`] through [`Self::write_schemas_async`]
+	pub async fn delete_async<S: Schema>(self: &Arc<Self>, key: &S::Key) -> AppResult<()> {
+		let batch = SchemaBatch::new();
+		batch.delete::<S>(key)?;
+		self.write_schemas_async(batch).await
+	}
+
 	pub(crate) fn get_cf_handle(&self, cf_name: &str) -> AppResult<&rocksdb::ColumnFamily> {
 		self.inner
 			.cf_handle(cf_name)
@@ -304,6 +417,19 @@ impl RksDB {
 			.map_err(Into::into)
 	}
 
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Names of the open column families, in open order
+	pub fn list_column_families(&self) -> Vec<String> {
+		self.cf_names.clone()
+	}
+
+	pub fn has_column_family(&self, name: &str) -> bool {
+		self.inner.cf_handle(name).is_some()
+	}
+
 	/// Flushes memtable data. This is only used for testing `get_approximate_sizes_cf` in unit
 	/// tests.
 	pub fn flush_cf(&self, cf_name: &str) -> AppResult<()> {
@@ -313,6 +439,49 @@ impl RksDB {
 			.into_db_res()?)
 	}
 
+	/// Flushes the memtables of every column family.
+	pub fn flush_all(&self) -> AppResult<()> {
+		for cf_name in &self.cf_names {
+			self.flush_cf(cf_name)?;
+		}
+		Ok(())
+	}
+
+	/// Flushes the WAL buffer to the file, fsyncing it if `sync`.
+	pub fn flush_wal(&self, sync: bool) -> AppResult<()> {
+		Ok(self.inner.flush_wal(sync).into_db_res()?)
+	}
+
+	/// Toggles `disable_auto_compactions` on every column family.
+	pub fn set_auto_compaction(&self, enabled: bool) -> AppResult<()> {
+		let disabled = if enabled { "false" } else { "true" };
+		for cf_name in &self.cf_names {
+			self.inner
+				.set_options_cf(
+					self.get_cf_handle(cf_name)?,
+					&[("disable_auto_compactions", disabled)],
+				)
+				.into_db_res()?;
+		}
+		Ok(())
+	}
+
+	/// Compacts the full key range of every column family.
+	pub fn compact_all(&self) {
+		for cf_name in &self.cf_names {
+			if let Some(cf) = self.inner.cf_handle(cf_name) {
+				self.inner
+					.compact_range_cf(cf, None::<&[u8]>, None::<&[u8]>);
+			}
+		}
+	}
+
+	/// Starts a [`BulkLoadSession`]: auto compactions and WAL fsync are off until it is finished
+	/// or dropped.
+	pub fn bulk_load_session(&self) -> AppResult<BulkLoadSession<'_>> {
+		BulkLoadSession::new(self)
+	}
+
 	pub fn get_property(&self, cf_name: &str, property_name: &str) -> AppResult<u64> {
 		Ok(self
 			.inner
@@ -329,6 +498,22 @@ impl RksDB {
 			})?)
 	}
 
+	/// Liveness probe: fails if RocksDB reports background errors (e.g. failed flush/compaction).
+	pub fn health(&self) -> AppResult<()> {
+		let bg_errors = self
+			.inner
+			.property_int_value("rocksdb.background-errors")
+			.into_db_res()?
+			.unwrap_or_default();
+		if bg_errors > 0 {
+			return err!(
+				&RksErr::Unhealthy,
+				format!("{}: {} background errors", self.name, bg_errors)
+			);
+		}
+		Ok(())
+	}
+
 	/// Creates new physical DB checkpoint in directory specified by `path`.
 	pub fn create_checkpoint<P: AsRef<Path>>(&self, path: P) -> AppResult<()> {
 		rocksdb::checkpoint::Checkpoint::new(&self.inner)
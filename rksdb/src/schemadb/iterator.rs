@@ -3,7 +3,12 @@ use crate::schemadb::{
 	schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec},
 };
 use base_infra::result::AppResult;
+use futures::Stream;
+use std::collections::VecDeque;
 use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::runtime::{Handle, RuntimeFlavor};
 
 pub enum ScanDirection {
 	Forward,
@@ -77,20 +82,75 @@ where
 		Ok(())
 	}
 
-	fn next_impl(&mut self) -> AppResult<Option<(S::Key, S::Value)>> {
-		if let Status::Advancing = self.status {
-			match self.direction {
+	/// Whether the iterator sits on an entry: the one the next call returns right after a seek,
+	/// the last returned one after that
+	pub fn valid(&self) -> bool {
+		!matches!(self.status, Status::Invalid) && self.db_iter.valid()
+	}
+
+	/// Error of the underlying rocksdb iterator, e.g. a corrupted block hit mid-scan
+	pub fn status(&self) -> AppResult<()> {
+		Ok(self.db_iter.status().into_db_res()?)
+	}
+
+	/// Raw key at the current position, see [`SchemaIterator::valid`]
+	pub fn key_bytes(&self) -> Option<&[u8]> {
+		if self.valid() {
+			self.db_iter.key()
+		} else {
+			None
+		}
+	}
+
+	/// Raw value at the current position, see [`SchemaIterator::valid`]
+	pub fn value_bytes(&self) -> Option<&[u8]> {
+		if self.valid() {
+			self.db_iter.value()
+		} else {
+			None
+		}
+	}
+
+	/// Decodes the key at the current position without advancing
+	pub fn position(&self) -> AppResult<Option<S::Key>> {
+		self.key_bytes()
+			.map(<S::Key as KeyCodec<S>>::decode_key)
+			.transpose()
+	}
+
+	/// Moves to the next entry, `false` once the iterator is exhausted. A rocksdb error is
+	/// returned once, the iterator is exhausted after it.
+	fn advance(&mut self) -> AppResult<bool> {
+		match self.status {
+			Status::Advancing => match self.direction {
 				ScanDirection::Forward => self.db_iter.next(),
 				ScanDirection::Backward => self.db_iter.prev(),
-			}
-		} else {
-			self.status = Status::Advancing;
+			},
+			Status::Invalid => return Ok(false),
+			Status::Initialized | Status::DoneSeek => self.status = Status::Advancing,
 		}
 
 		if !self.db_iter.valid() {
-			self.db_iter.status().into_db_res()?;
 			// advancing an invalid raw iter results in seg fault
 			self.status = Status::Invalid;
+			self.db_iter.status().into_db_res()?;
+			return Ok(false);
+		}
+		Ok(true)
+	}
+
+	/// Yields the next key only, leaving its value undecoded
+	pub fn next_key(&mut self) -> AppResult<Option<S::Key>> {
+		if !self.advance()? {
+			return Ok(None);
+		}
+
+		let raw_key = self.db_iter.key().expect("db_iter.key() failed.");
+		<S::Key as KeyCodec<S>>::decode_key(raw_key).map(Some)
+	}
+
+	fn next_impl(&mut self) -> AppResult<Option<(S::Key, S::Value)>> {
+		if !self.advance()? {
 			return Ok(None);
 		}
 
@@ -102,6 +162,166 @@ where
 
 		Ok(Some((key?, value?)))
 	}
+
+	/// Converts into a [`Stream`] reading `chunk_size` items per blocking section and yielding
+	/// to the runtime between chunks.
+	///
+	/// On a multi-thread runtime each chunk runs in `tokio::task::block_in_place`, otherwise it
+	/// runs inline.
+	pub fn into_stream(self, chunk_size: usize) -> SchemaStream<'a, S> {
+		ChunkedStream::new(self, chunk_size)
+	}
+
+	/// Decodes and passes each entry to `f`, yielding only its `Some` outputs. Dropped
+	/// entries never leave [`Iterator::next`].
+	pub fn filter_map<B, F>(self, f: F) -> FilterMapIter<'a, S, B, F>
+	where
+		F: FnMut(S::Key, S::Value) -> AppResult<Option<B>>,
+	{
+		FilterMapIter { iter: self, f }
+	}
+}
+
+/// Iterator returned by [`SchemaIterator::filter_map`]
+pub struct FilterMapIter<'a, S, B, F>
+where
+	S: Schema,
+	F: FnMut(S::Key, S::Value) -> AppResult<Option<B>>,
+{
+	iter: SchemaIterator<'a, S>,
+	f: F,
+}
+
+impl<'a, S, B, F> FilterMapIter<'a, S, B, F>
+where
+	S: Schema,
+	F: FnMut(S::Key, S::Value) -> AppResult<Option<B>>,
+{
+	/// See [`SchemaIterator::into_stream`]
+	pub fn into_stream(self, chunk_size: usize) -> ChunkedStream<Self> {
+		ChunkedStream::new(self, chunk_size)
+	}
+}
+
+impl<S, B, F> Iterator for FilterMapIter<'_, S, B, F>
+where
+	S: Schema,
+	F: FnMut(S::Key, S::Value) -> AppResult<Option<B>>,
+{
+	type Item = AppResult<B>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			let (key, value) = match self.iter.next_impl() {
+				Ok(Some(kv)) => kv,
+				Ok(None) => return None,
+				Err(e) => return Some(Err(e)),
+			};
+			match (self.f)(key, value) {
+				Ok(Some(out)) => return Some(Ok(out)),
+				Ok(None) => continue,
+				Err(e) => return Some(Err(e)),
+			}
+		}
+	}
+}
+
+/// Async adapter over [`SchemaIterator`], see [`SchemaIterator::into_stream`]
+pub type SchemaStream<'a, S> = ChunkedStream<SchemaIterator<'a, S>>;
+
+/// Async adapter over a blocking db iterator, see [`SchemaIterator::into_stream`]
+pub struct ChunkedStream<I: Iterator> {
+	iter: Option<I>,
+	chunk_size: usize,
+	buffer: VecDeque<I::Item>,
+	yielded: bool,
+}
+
+impl<I: Iterator> Unpin for ChunkedStream<I> {}
+
+impl<I, T> ChunkedStream<I>
+where
+	I: Iterator<Item = AppResult<T>>,
+{
+	fn new(iter: I, chunk_size: usize) -> Self {
+		ChunkedStream {
+			iter: Some(iter),
+			chunk_size: chunk_size.max(1),
+			buffer: VecDeque::new(),
+			yielded: false,
+		}
+	}
+
+	fn fill_chunk(&mut self) {
+		let Some(iter) = self.iter.as_mut() else {
+			return;
+		};
+
+		let chunk_size = self.chunk_size;
+		let mut read_chunk = || {
+			let mut chunk = Vec::with_capacity(chunk_size);
+			let mut finished = false;
+			while chunk.len() < chunk_size {
+				match iter.next() {
+					Some(item) => {
+						// stop after the first error
+						finished = item.is_err();
+						chunk.push(item);
+						if finished {
+							break;
+						}
+					}
+					None => {
+						finished = true;
+						break;
+					}
+				}
+			}
+			(chunk, finished)
+		};
+
+		let multi_thread = Handle::try_current()
+			.map(|h| h.runtime_flavor() == RuntimeFlavor::MultiThread)
+			.unwrap_or(false);
+		let (chunk, finished) = if multi_thread {
+			tokio::task::block_in_place(read_chunk)
+		} else {
+			read_chunk()
+		};
+
+		self.buffer.extend(chunk);
+		if finished {
+			self.iter = None;
+		}
+	}
+}
+
+impl<I, T> Stream for ChunkedStream<I>
+where
+	I: Iterator<Item = AppResult<T>>,
+{
+	type Item = AppResult<T>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		if let Some(item) = this.buffer.pop_front() {
+			return Poll::Ready(Some(item));
+		}
+		if this.iter.is_none() {
+			return Poll::Ready(None);
+		}
+
+		// Give other tasks a turn before reading the next chunk
+		if !this.yielded {
+			this.yielded = true;
+			cx.waker().wake_by_ref();
+			return Poll::Pending;
+		}
+		this.yielded = false;
+
+		this.fill_chunk();
+		Poll::Ready(this.buffer.pop_front())
+	}
 }
 
 impl<S> Iterator for SchemaIterator<'_, S>
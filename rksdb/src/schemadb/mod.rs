@@ -3,16 +3,22 @@
 #[macro_use]
 pub mod schema;
 pub mod batch;
+pub mod bulk_load;
 pub mod db_impl;
 pub mod iterator;
+pub mod ryw;
+pub mod transaction;
 pub mod ttl;
 pub mod utils;
 
 // Re-export public types and traits
-pub use batch::{ColumnFamilyName, SchemaBatch};
+pub use batch::{ColumnFamilyName, CowBatch, SchemaBatch};
+pub use bulk_load::BulkLoadSession;
 pub use db_impl::RksDB;
+pub use ryw::{ReadYourWrites, ReadYourWritesBatch};
 pub use schema::Schema;
-pub use utils::IntoDbResult;
+pub use transaction::{RksTransaction, RksTransactionalDB};
+pub use utils::{IntoDbResult, default_read_options, prefix_read_options};
 
 /// Type alias to `rocksdb::ReadOptions`. See [`rocksdb doc`](https://github.com/pingcap/rust-rocksdb/blob/master/src/rocksdb_options.rs)
 pub use rocksdb::{
@@ -0,0 +1,187 @@
+use crate::schemadb::{
+	RksDB,
+	batch::{SchemaBatch, WriteOp},
+	schema::{KeyCodec, Schema, ValueCodec},
+};
+use base_infra::result::AppResult;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Reads that observe writes staged but not yet committed
+pub trait ReadYourWrites {
+	fn get<S: Schema>(&self, key: &S::Key) -> AppResult<Option<S::Value>>;
+}
+
+/// [`SchemaBatch`] wrapper that remembers the latest staged op per key, so reads through it see
+/// the pending writes before [`ReadYourWritesBatch::commit`] is called.
+pub struct ReadYourWritesBatch<'a> {
+	db: &'a RksDB,
+	batch: SchemaBatch,
+	staged: Mutex<HashMap<(String, Vec<u8>), WriteOp>>,
+}
+
+impl<'a> ReadYourWritesBatch<'a> {
+	pub fn new(db: &'a RksDB) -> Self {
+		Self {
+			db,
+			batch: SchemaBatch::new(),
+			staged: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Stages an insert/update operation.
+	pub fn put<S: Schema>(&self, key: &S::Key, value: &S::Value) -> AppResult<()> {
+		self.batch.put::<S>(key, value)?;
+		let key = <S::Key as KeyCodec<S>>::encode_key(key)?;
+		let value = <S::Value as ValueCodec<S>>::encode_value(value)?;
+		self.stage(
+			S::COLUMN_FAMILY_NAME,
+			key.clone(),
+			WriteOp::Value { key, value },
+		);
+		Ok(())
+	}
+
+	/// Stages a delete operation.
+	pub fn delete<S: Schema>(&self, key: &S::Key) -> AppResult<()> {
+		self.batch.delete::<S>(key)?;
+		let key = <S::Key as KeyCodec<S>>::encode_key(key)?;
+		self.stage(
+			S::COLUMN_FAMILY_NAME,
+			key.clone(),
+			WriteOp::Deletion { key },
+		);
+		Ok(())
+	}
+
+	fn stage(&self, cf_name: &str, key: Vec<u8>, op: WriteOp) {
+		self.staged
+			.lock()
+			.expect("Cannot currently handle a poisoned lock")
+			.insert((cf_name.to_string(), key), op);
+	}
+
+	/// Number of distinct keys staged.
+	pub fn len(&self) -> usize {
+		self.staged
+			.lock()
+			.expect("Cannot currently handle a poisoned lock")
+			.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Writes all staged operations atomically.
+	pub fn commit(self) -> AppResult<()> {
+		self.db.write_schemas(self.batch)
+	}
+
+	/// Drops the wrapper, returning the underlying batch.
+	pub fn into_batch(self) -> SchemaBatch {
+		self.batch
+	}
+}
+
+impl ReadYourWrites for ReadYourWritesBatch<'_> {
+	fn get<S: Schema>(&self, key: &S::Key) -> AppResult<Option<S::Value>> {
+		let raw_key = <S::Key as KeyCodec<S>>::encode_key(key)?;
+		let staged_key = (S::COLUMN_FAMILY_NAME.to_string(), raw_key);
+		{
+			let staged = self
+				.staged
+				.lock()
+				.expect("Cannot currently handle a poisoned lock");
+			match staged.get(&staged_key) {
+				Some(WriteOp::Value { value, .. }) => {
+					return <S::Value as ValueCodec<S>>::decode_value(value).map(Some);
+				}
+				Some(WriteOp::Deletion { .. }) => return Ok(None),
+				None => {}
+			}
+		}
+		self.db.get::<S>(key)
+	}
+}
+
+impl RksDB {
+	/// Starts a [`ReadYourWritesBatch`] bound to this DB.
+	pub fn begin_ryw_batch(&self) -> ReadYourWritesBatch<'_> {
+		ReadYourWritesBatch::new(self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::testing::TestDb;
+	use bincode::{Decode, Encode};
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
+	pub struct TestKey(u32);
+
+	#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
+	pub struct TestValue(String);
+
+	crate::define_schema!(TestSchema, TestKey, TestValue, "test_schema");
+	crate::impl_schema_bin_codec!(TestSchema, TestKey, TestValue);
+
+	#[test]
+	fn test_read_staged_write() {
+		let test_db = TestDb::new(&[TestSchema::COLUMN_FAMILY_NAME]);
+		let db = test_db.inner();
+		db.put::<TestSchema>(&TestKey(1), &TestValue("old".to_string()))
+			.unwrap();
+		db.put::<TestSchema>(&TestKey(2), &TestValue("kept".to_string()))
+			.unwrap();
+
+		let batch = db.begin_ryw_batch();
+		batch
+			.put::<TestSchema>(&TestKey(1), &TestValue("new".to_string()))
+			.unwrap();
+		batch.delete::<TestSchema>(&TestKey(2)).unwrap();
+		batch
+			.put::<TestSchema>(&TestKey(3), &TestValue("added".to_string()))
+			.unwrap();
+
+		// staged writes are visible through the batch only
+		assert_eq!(
+			batch.get::<TestSchema>(&TestKey(1)).unwrap(),
+			Some(TestValue("new".to_string()))
+		);
+		assert_eq!(batch.get::<TestSchema>(&TestKey(2)).unwrap(), None);
+		assert_eq!(
+			batch.get::<TestSchema>(&TestKey(3)).unwrap(),
+			Some(TestValue("added".to_string()))
+		);
+		assert_eq!(
+			db.get::<TestSchema>(&TestKey(1)).unwrap(),
+			Some(TestValue("old".to_string()))
+		);
+
+		batch.commit().unwrap();
+		assert_eq!(
+			db.get::<TestSchema>(&TestKey(1)).unwrap(),
+			Some(TestValue("new".to_string()))
+		);
+		assert_eq!(db.get::<TestSchema>(&TestKey(2)).unwrap(), None);
+	}
+
+	#[test]
+	fn test_falls_back_to_db() {
+		let test_db = TestDb::new(&[TestSchema::COLUMN_FAMILY_NAME]);
+		let db = test_db.inner();
+		db.put::<TestSchema>(&TestKey(9), &TestValue("stored".to_string()))
+			.unwrap();
+
+		let batch = db.begin_ryw_batch();
+		assert!(batch.is_empty());
+		assert_eq!(
+			batch.get::<TestSchema>(&TestKey(9)).unwrap(),
+			Some(TestValue("stored".to_string()))
+		);
+		assert_eq!(batch.get::<TestSchema>(&TestKey(10)).unwrap(), None);
+	}
+}
@@ -0,0 +1,208 @@
+use crate::errors::RksDbError;
+use crate::schemadb::{
+	RksDB,
+	schema::{KeyCodec, Schema, ValueCodec},
+	utils::{DeUnc, IntoDbResult, default_write_options},
+};
+use anyhow::format_err;
+use base_infra::result::AppResult;
+use rocksdb::{
+	ColumnFamily, ColumnFamilyDescriptor, ErrorKind, OptimisticTransactionDB,
+	OptimisticTransactionOptions, Options, Transaction,
+};
+use std::path::Path;
+use tracing::info;
+
+/// RocksDB opened as an `OptimisticTransactionDB`, for read-modify-write flows spanning several
+/// column families. Conflicts are detected on commit.
+pub struct RksTransactionalDB {
+	name: String,
+	inner: OptimisticTransactionDB,
+}
+
+/// A pending optimistic transaction, see [`RksTransactionalDB::commit`].
+pub struct RksTransaction<'a> {
+	db: &'a RksTransactionalDB,
+	inner: Transaction<'a, OptimisticTransactionDB>,
+}
+
+impl RksDB {
+	pub fn open_transactional(
+		path: impl AsRef<Path>,
+		name: &str,
+		cfds: Vec<ColumnFamilyDescriptor>,
+		opts: &Options,
+	) -> AppResult<RksTransactionalDB> {
+		let inner =
+			OptimisticTransactionDB::open_cf_descriptors(opts, path.de_unc(), cfds).into_db_res()?;
+		info!(rocksdb_name = name, "Opened transactional RocksDB.");
+		Ok(RksTransactionalDB {
+			name: name.to_string(),
+			inner,
+		})
+	}
+}
+
+impl RksTransactionalDB {
+	pub fn begin(&self) -> RksTransaction<'_> {
+		let inner = self.inner.transaction_opt(
+			&default_write_options(),
+			&OptimisticTransactionOptions::default(),
+		);
+		RksTransaction { db: self, inner }
+	}
+
+	/// Commits `tx`, returning [`RksDbError::TransactionConflict`] if a key it read or wrote was
+	/// changed by another commit after it started.
+	pub fn commit(&self, tx: RksTransaction) -> AppResult<()> {
+		tx.inner.commit().map_err(|e| match e.kind() {
+			ErrorKind::Busy | ErrorKind::TryAgain => {
+				RksDbError::TransactionConflict(format!("{}: {}", self.name, e))
+			}
+			_ => RksDbError::OtherRocksDbError(e.to_string()),
+		})?;
+		Ok(())
+	}
+
+	fn get_cf_handle(&self, cf_name: &str) -> AppResult<&ColumnFamily> {
+		self.inner
+			.cf_handle(cf_name)
+			.ok_or_else(|| {
+				format_err!(
+					"DB::cf_handle not found for column family name: {}",
+					cf_name
+				)
+			})
+			.map_err(Into::into)
+	}
+}
+
+impl RksTransaction<'_> {
+	/// Reads a record and tracks the key, so a concurrent commit to it fails this transaction.
+	pub fn get<S: Schema>(&self, schema_key: &S::Key) -> AppResult<Option<S::Value>> {
+		let k = <S::Key as KeyCodec<S>>::encode_key(schema_key)?;
+		let cf_handle = self.db.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
+
+		let result = self
+			.inner
+			.get_for_update_cf(cf_handle, k, true)
+			.into_db_res()?;
+		result
+			.map(|raw_value| <S::Value as ValueCodec<S>>::decode_value(&raw_value))
+			.transpose()
+	}
+
+	pub fn put<S: Schema>(&self, key: &S::Key, value: &S::Value) -> AppResult<()> {
+		let key = <S::Key as KeyCodec<S>>::encode_key(key)?;
+		let value = <S::Value as ValueCodec<S>>::encode_value(value)?;
+		let cf_handle = self.db.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
+		self.inner.put_cf(cf_handle, key, value).into_db_res()?;
+		Ok(())
+	}
+
+	pub fn delete<S: Schema>(&self, key: &S::Key) -> AppResult<()> {
+		let key = <S::Key as KeyCodec<S>>::encode_key(key)?;
+		let cf_handle = self.db.get_cf_handle(S::COLUMN_FAMILY_NAME)?;
+		self.inner.delete_cf(cf_handle, key).into_db_res()?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::errors::RksErr;
+	use base_infra::result::{AppError, ErrorCode};
+	use bincode::{Decode, Encode};
+	use serde::{Deserialize, Serialize};
+	use tempfile::TempDir;
+
+	#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
+	pub struct TestKey(u32);
+
+	#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
+	pub struct TestValue(u64);
+
+	crate::define_schema!(BalanceSchema, TestKey, TestValue, "balance");
+	crate::impl_schema_bin_codec!(BalanceSchema, TestKey, TestValue);
+	crate::define_schema!(LedgerSchema, TestKey, TestValue, "ledger");
+	crate::impl_schema_bin_codec!(LedgerSchema, TestKey, TestValue);
+
+	fn create_test_db() -> (TempDir, RksTransactionalDB) {
+		let temp_dir = TempDir::new().unwrap();
+		let mut opts = Options::default();
+		opts.create_if_missing(true);
+		opts.create_missing_column_families(true);
+
+		let cfds = [
+			BalanceSchema::COLUMN_FAMILY_NAME,
+			LedgerSchema::COLUMN_FAMILY_NAME,
+		]
+		.into_iter()
+		.map(|cf| ColumnFamilyDescriptor::new(cf, Options::default()))
+		.collect::<Vec<_>>();
+		let db = RksDB::open_transactional(temp_dir.path(), "tx_db", cfds, &opts).unwrap();
+		(temp_dir, db)
+	}
+
+	#[test]
+	fn test_multi_cf_commit() {
+		let (_dir, db) = create_test_db();
+		let tx = db.begin();
+		tx.put::<BalanceSchema>(&TestKey(1), &TestValue(100))
+			.unwrap();
+		tx.put::<LedgerSchema>(&TestKey(1), &TestValue(1)).unwrap();
+		assert_eq!(
+			tx.get::<BalanceSchema>(&TestKey(1)).unwrap(),
+			Some(TestValue(100))
+		);
+		db.commit(tx).unwrap();
+
+		let tx = db.begin();
+		assert_eq!(
+			tx.get::<LedgerSchema>(&TestKey(1)).unwrap(),
+			Some(TestValue(1))
+		);
+		tx.delete::<LedgerSchema>(&TestKey(1)).unwrap();
+		db.commit(tx).unwrap();
+		assert_eq!(db.begin().get::<LedgerSchema>(&TestKey(1)).unwrap(), None);
+	}
+
+	#[test]
+	fn test_concurrent_conflict() {
+		let (_dir, db) = create_test_db();
+		let setup = db.begin();
+		setup
+			.put::<BalanceSchema>(&TestKey(1), &TestValue(100))
+			.unwrap();
+		db.commit(setup).unwrap();
+
+		// both read the balance then write it back
+		let tx1 = db.begin();
+		let tx2 = db.begin();
+		let b1 = tx1.get::<BalanceSchema>(&TestKey(1)).unwrap().unwrap();
+		let b2 = tx2.get::<BalanceSchema>(&TestKey(1)).unwrap().unwrap();
+		tx1.put::<BalanceSchema>(&TestKey(1), &TestValue(b1.0 - 30))
+			.unwrap();
+		tx2.put::<BalanceSchema>(&TestKey(1), &TestValue(b2.0 - 50))
+			.unwrap();
+
+		let results = [db.commit(tx1), db.commit(tx2)];
+		assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+		match &results[1] {
+			Err(AppError::Anyhow(code, err)) => {
+				assert_eq!(code.code(), RksErr::TxConflict.code());
+				assert!(matches!(
+					err.downcast_ref::<RksDbError>(),
+					Some(RksDbError::TransactionConflict(_))
+				));
+			}
+			other => panic!("expected conflict, got {other:?}"),
+		}
+
+		assert_eq!(
+			db.begin().get::<BalanceSchema>(&TestKey(1)).unwrap(),
+			Some(TestValue(70))
+		);
+	}
+}
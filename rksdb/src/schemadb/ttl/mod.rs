@@ -1,7 +1,10 @@
+use crate::codec::orderedcode::{CompositeKey, KeyReader, OrderedKey};
 use crate::schemadb::{
 	ColumnFamilyName, RksDB, SchemaBatch,
 	schema::{KeyCodec, Schema},
+	utils::IntoDbResult,
 };
+use base_infra::codec::bincode::BinDecodeExt;
 use base_infra::result::AppResult;
 use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
@@ -11,6 +14,12 @@ pub mod schedule;
 
 /// TTL expiration index Key uses (expire_timestamp, schema_name, original_key) as composite key
 /// Enables scanning by time and deleting expiration index by original key
+///
+/// The cleanup scan stops at the first key that is not expired, so the encoded key must sort by
+/// `expire_timestamp` numerically. bincode writes `u64` as little-endian varint which does not, the
+/// key is therefore encoded with [`orderedcode`](crate::codec::orderedcode). Indexes written with
+/// the older layout, the whole key encoded with bincode, are rewritten by
+/// [`RksDB::migrate_ttl_expiration_index`].
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
 pub struct TtlExpirationKey {
 	/// Expiration timestamp (Unix, seconds)
@@ -63,8 +72,41 @@ crate::define_pub_schema!(
 	"ttl_single_index"
 );
 
+// Expiration index key keeps numeric order of `expire_timestamp`, see `TtlExpirationKey`
+impl OrderedKey for TtlExpirationKey {
+	fn write_key(&self, key: &mut CompositeKey) {
+		*key = std::mem::take(key)
+			.push_u64(self.expire_timestamp)
+			.push_str(&self.schema_name)
+			.push_bytes(&self.original_key);
+	}
+
+	fn read_key(reader: &mut KeyReader<'_>) -> AppResult<Self> {
+		Ok(Self {
+			expire_timestamp: reader.read_u64()?,
+			schema_name: reader.read_str()?,
+			original_key: reader.read_bytes()?,
+		})
+	}
+}
+
+crate::impl_schema_ordered_key_codec!(TtlExpirationSchema, TtlExpirationKey);
+
+impl TtlExpirationKey {
+	/// Decodes the pre-`orderedcode` layout, the whole key encoded with bincode
+	fn decode_legacy(data: &[u8]) -> AppResult<Self> {
+		data.bin_decode::<TtlExpirationKey>()
+	}
+
+	#[cfg(test)]
+	fn encode_legacy(&self) -> AppResult<Vec<u8>> {
+		use base_infra::codec::bincode::BinEncodeExt;
+		self.bin_encode()
+	}
+}
+
 // Implement encoding for expiration index schema
-crate::impl_schema_bin_codec!(TtlExpirationSchema, TtlExpirationKey, TtlExpirationValue);
+crate::impl_schema_value_bin_codec!(TtlExpirationSchema, TtlExpirationValue);
 
 // Implement encoding for single key index schema
 crate::impl_schema_bin_codec!(TtlSingleSchema, TtlSingleKey, TtlSingleValue);
@@ -104,7 +146,7 @@ impl RksDB {
 
 		// 2. Serialize key for TTL index
 		let original_key_bytes = <S::Key as KeyCodec<S>>::encode_key(key)?;
-		let schema_name = std::any::type_name::<S>().to_string();
+		let schema_name = ttl_schema_name::<S>();
 
 		// 3. Write expiration time index (for time-ordered scan)
 		let ttl_expiration_key = TtlExpirationKey {
@@ -141,7 +183,7 @@ impl RksDB {
 		// 1. Check TTL
 		let original_key_bytes = <S::Key as KeyCodec<S>>::encode_key(schema_key)?;
 		let ttl_single_key = TtlSingleKey {
-			schema_name: std::any::type_name::<S>().to_string(),
+			schema_name: ttl_schema_name::<S>(),
 			original_key: original_key_bytes,
 		};
 
@@ -158,13 +200,89 @@ impl RksDB {
 		self.get::<S>(schema_key)
 	}
 
+	/// Overwrite the value of a TTL'd key, keeping its expiry. Only the data record is written,
+	/// both TTL indexes stay as they are.
+	///
+	/// # Returns
+	/// - `true`: Value updated
+	/// - `false`: Key has no TTL record (absent or written without TTL) or is expired
+	pub fn update_value_preserve_ttl<S: Schema>(
+		&self,
+		key: &S::Key,
+		value: &S::Value,
+	) -> AppResult<bool> {
+		let ttl_single_key = TtlSingleKey {
+			schema_name: ttl_schema_name::<S>(),
+			original_key: <S::Key as KeyCodec<S>>::encode_key(key)?,
+		};
+
+		match self.get::<TtlSingleSchema>(&ttl_single_key)? {
+			Some(ttl_single_value) if ttl_single_value.expire_timestamp > current_timestamp() => {
+				let batch = SchemaBatch::new();
+				batch.put::<S>(key, value)?;
+				self.write_schemas(batch)?;
+				Ok(true)
+			}
+			_ => Ok(false),
+		}
+	}
+
+	/// Keys of `S` expiring within the next `within_secs`, soonest first, at most `limit`. For
+	/// acting on data before it goes, e.g. notifying users whose session is about to end.
+	///
+	/// Already expired entries awaiting cleanup are left out. Index entries that fail to decode
+	/// are skipped with a warning.
+	pub fn scan_expiring<S: Schema>(
+		&self,
+		within_secs: u64,
+		limit: usize,
+	) -> AppResult<Vec<(S::Key, u64)>> {
+		let now = current_timestamp();
+		let until = now.saturating_add(within_secs);
+		let schema_name = ttl_schema_name::<S>();
+
+		let mut iter = self.iter::<TtlExpirationSchema>()?;
+		iter.seek(&TtlExpirationKey {
+			expire_timestamp: now + 1,
+			schema_name: String::new(),
+			original_key: Vec::new(),
+		})?;
+
+		let mut expiring = Vec::new();
+		while expiring.len() < limit {
+			let expiration_key = match iter.next_key() {
+				Ok(Some(key)) => key,
+				Ok(None) => break,
+				// a decode error leaves the iterator on the entry, a rocksdb error exhausts it
+				Err(e) if iter.valid() => {
+					tracing::warn!("Skipping undecodable TTL expiration entry: {e}");
+					continue;
+				}
+				Err(e) => return Err(e),
+			};
+			if expiration_key.expire_timestamp > until {
+				break;
+			}
+			if expiration_key.schema_name != schema_name {
+				continue;
+			}
+			match <S::Key as KeyCodec<S>>::decode_key(&expiration_key.original_key) {
+				Ok(key) => expiring.push((key, expiration_key.expire_timestamp)),
+				Err(e) => {
+					tracing::warn!("Skipping TTL entry of {schema_name} with undecodable key: {e}")
+				}
+			}
+		}
+		Ok(expiring)
+	}
+
 	/// Manually delete expired data
 	///
 	/// # Parameters
 	/// - `key`: The key to check and delete
 	pub fn delete_expired<S: Schema>(&self, key: &S::Key) -> AppResult<()> {
 		let original_key_bytes = <S::Key as KeyCodec<S>>::encode_key(key)?;
-		let schema_name = std::any::type_name::<S>().to_string();
+		let schema_name = ttl_schema_name::<S>();
 
 		// Check if there is a TTL record
 		let ttl_single_key = TtlSingleKey {
@@ -281,20 +399,86 @@ impl RksDB {
 			};
 			batch.delete::<TtlSingleSchema>(&ttl_single_key)?;
 
-			// 3. Delete original data (via low-level RocksDB API)
-			self.delete_raw_key(&expiration_value.cf_name, &expiration_key.original_key)?;
+			// 3. Delete original data, in the same batch as its index records
+			batch.delete_raw(
+				&expiration_value.cf_name,
+				expiration_key.original_key.clone(),
+			);
 		}
 
 		self.write_schemas(batch)
 	}
 
-	/// Low-level method to delete original data
-	fn delete_raw_key(&self, cf_name: &str, key_bytes: &[u8]) -> AppResult<()> {
-		let cf_handle = self.get_cf_handle(cf_name)?;
-		self.inner
-			.delete_cf(cf_handle, key_bytes)
-			.map_err(crate::errors::RksDbError::from)?;
-		Ok(())
+	/// Rewrites expiration index keys still in the pre-`orderedcode` layout, returns how many were
+	/// rewritten. Run once after upgrading, before the cleanup scheduler starts.
+	pub fn migrate_ttl_expiration_index(&self) -> AppResult<usize> {
+		let cf_name = TtlExpirationSchema::COLUMN_FAMILY_NAME;
+		let batch = SchemaBatch::new();
+		let mut migrated = 0;
+
+		let mut iter = self.inner.raw_iterator_cf(self.get_cf_handle(cf_name)?);
+		iter.seek_to_first();
+		while let Some((raw_key, raw_value)) = iter.item() {
+			let current = TtlExpirationKey::decode_ordered(raw_key)
+				.is_ok_and(|key| key.encode_ordered() == raw_key);
+			if !current {
+				let key = TtlExpirationKey::decode_legacy(raw_key)?;
+				batch.delete_raw(cf_name, raw_key.to_vec());
+				batch.put_raw(cf_name, key.encode_ordered(), raw_value.to_vec());
+				migrated += 1;
+			}
+			iter.next();
+		}
+		iter.status().into_db_res()?;
+
+		if migrated > 0 {
+			self.write_schemas(batch)?;
+		}
+		Ok(migrated)
+	}
+
+	/// Rewrites the TTL index entries of `S` recorded under its rust type name, as written before
+	/// the indexes were keyed by column family name, returns how many were rewritten. Run once per
+	/// TTL'd schema after upgrading and [`Self::migrate_ttl_expiration_index`], before the cleanup
+	/// scheduler starts.
+	pub fn migrate_ttl_schema_name<S: Schema>(&self) -> AppResult<usize> {
+		let legacy_name = std::any::type_name::<S>();
+		let schema_name = ttl_schema_name::<S>();
+		let batch = SchemaBatch::new();
+		let mut migrated = 0;
+
+		let mut iter = self.iter::<TtlExpirationSchema>()?;
+		iter.seek_to_first();
+		while let Some((expiration_key, expiration_value)) = iter.next().transpose()? {
+			if expiration_key.schema_name != legacy_name {
+				continue;
+			}
+			let legacy_single_key = TtlSingleKey {
+				schema_name: expiration_key.schema_name.clone(),
+				original_key: expiration_key.original_key.clone(),
+			};
+			if let Some(single_value) = self.get::<TtlSingleSchema>(&legacy_single_key)? {
+				let single_key = TtlSingleKey {
+					schema_name: schema_name.clone(),
+					..legacy_single_key.clone()
+				};
+				batch.put::<TtlSingleSchema>(&single_key, &single_value)?;
+			}
+			batch.delete::<TtlSingleSchema>(&legacy_single_key)?;
+
+			batch.delete::<TtlExpirationSchema>(&expiration_key)?;
+			let expiration_key = TtlExpirationKey {
+				schema_name: schema_name.clone(),
+				..expiration_key
+			};
+			batch.put::<TtlExpirationSchema>(&expiration_key, &expiration_value)?;
+			migrated += 1;
+		}
+
+		if migrated > 0 {
+			self.write_schemas(batch)?;
+		}
+		Ok(migrated)
 	}
 
 	/// Get all column family names including TTL-related ones
@@ -328,6 +512,12 @@ impl RksDB {
 	}
 }
 
+/// Name a schema's entries are recorded under in the TTL indexes, its column family name which
+/// stays the same across compilers and when the type is moved or renamed
+fn ttl_schema_name<S: Schema>() -> String {
+	S::COLUMN_FAMILY_NAME.to_string()
+}
+
 /// Get current Unix timestamp (seconds)
 pub fn current_timestamp() -> u64 {
 	SystemTime::now()
@@ -360,6 +550,7 @@ pub fn timestamp_after_days(days: u64) -> u64 {
 mod tests {
 	use super::*;
 	use crate::schemadb::schema::Schema;
+	use crate::testing::TestDb;
 	use base_infra::codec::bincode::BinEncodeExt;
 	use serde::{Deserialize, Serialize};
 
@@ -372,76 +563,347 @@ mod tests {
 	crate::define_schema!(TestSchema, TestKey, TestValue, "test_schema");
 	crate::impl_schema_bin_codec!(TestSchema, TestKey, TestValue);
 
-	fn create_test_db() -> RksDB {
-		use rocksdb::Options;
-		use tempfile::TempDir;
-
-		let temp_dir = TempDir::new().unwrap();
-		let path = temp_dir.path().to_path_buf();
-
-		let mut column_families = vec![TestSchema::COLUMN_FAMILY_NAME];
-		column_families.extend(RksDB::get_ttl_column_families());
-
-		let mut opts = Options::default();
-		opts.create_if_missing(true);
-		opts.create_missing_column_families(true);
-
-		RksDB::open(path, "test_db", column_families, &opts).unwrap()
-	}
-
 	#[test]
 	fn test_put_and_get_with_ttl() {
-		let db = create_test_db();
+		let db = TestDb::for_schema::<TestSchema>();
 		let key = TestKey(1, 2);
 		let value = TestValue(1, "hello".to_string(), true);
 		let expire_at = timestamp_after_seconds(10);
 
 		// Write data with TTL
-		db.put_with_ttl::<TestSchema>(&key, &value, expire_at)
+		db.inner()
+			.put_with_ttl::<TestSchema>(&key, &value, expire_at)
 			.unwrap();
 
 		// Read data should succeed
-		let result = db.get_check_ttl::<TestSchema>(&key).unwrap();
-		assert_eq!(result, Some(value));
+		let result = db.inner().get_check_ttl::<TestSchema>(&key).unwrap();
+		assert_eq!(result, Some(value.clone()));
+
+		// Data without TTL sits next to it
+		let plain = TestKey(3, 4);
+		db.put::<TestSchema>(plain.clone(), value.clone()).unwrap();
+		assert_eq!(db.get::<TestSchema>(&plain).unwrap(), Some(value.clone()));
+		assert_eq!(
+			db.all::<TestSchema>().unwrap(),
+			vec![(key, value.clone()), (plain, value)]
+		);
 	}
 
 	#[test]
 	fn test_expired_data_removal() {
-		let db = create_test_db();
+		let db = TestDb::for_schema::<TestSchema>();
 		let key = TestKey(1, 2);
 		let value = TestValue(1, "hello".to_string(), true);
 		let past_time = timestamp_after_seconds(1); // 1 seconds ago
 
 		// Write already-expired data
-		db.put_with_ttl::<TestSchema>(&key, &value, past_time)
+		db.inner()
+			.put_with_ttl::<TestSchema>(&key, &value, past_time)
 			.unwrap();
 		std::thread::sleep(std::time::Duration::from_secs(1));
 
 		// Read should return None (expired)
-		let result = db.get_check_ttl::<TestSchema>(&key).unwrap();
+		let result = db.inner().get_check_ttl::<TestSchema>(&key).unwrap();
 		assert_eq!(result, None);
 	}
 
+	#[test]
+	fn test_update_value_preserve_ttl() {
+		let db = TestDb::for_schema::<TestSchema>();
+		let key = TestKey(1, 2);
+		let short = TestKey(3, 4);
+		let value = TestValue(1, "hello".to_string(), true);
+		let updated = TestValue(2, "world".to_string(), false);
+		let expire_at = timestamp_after_seconds(4);
+		db.inner()
+			.put_with_ttl::<TestSchema>(&key, &value, expire_at)
+			.unwrap();
+		db.inner()
+			.put_with_ttl::<TestSchema>(&short, &value, timestamp_after_seconds(1))
+			.unwrap();
+		let ttl_single_key = TtlSingleKey {
+			schema_name: ttl_schema_name::<TestSchema>(),
+			original_key: key.bin_encode().unwrap(),
+		};
+		let (ttl_records, _) = db.inner().get_ttl_stats().unwrap();
+
+		// halfway through the TTL of `key`, `short` is expired by now
+		std::thread::sleep(std::time::Duration::from_secs(2));
+		let updated_ok = db
+			.inner()
+			.update_value_preserve_ttl::<TestSchema>(&key, &updated);
+		assert!(updated_ok.unwrap());
+		assert_eq!(
+			db.inner().get_check_ttl::<TestSchema>(&key).unwrap(),
+			Some(updated.clone())
+		);
+		let ttl = db.inner().get::<TtlSingleSchema>(&ttl_single_key).unwrap();
+		assert_eq!(ttl.unwrap().expire_timestamp, expire_at);
+		assert_eq!(db.inner().get_ttl_stats().unwrap().0, ttl_records);
+
+		let expired = db
+			.inner()
+			.update_value_preserve_ttl::<TestSchema>(&short, &updated);
+		assert!(!expired.unwrap());
+		assert_eq!(db.get::<TestSchema>(&short).unwrap(), Some(value.clone()));
+
+		let absent = TestKey(5, 6);
+		let missing = db
+			.inner()
+			.update_value_preserve_ttl::<TestSchema>(&absent, &updated);
+		assert!(!missing.unwrap());
+		db.put::<TestSchema>(absent.clone(), value.clone()).unwrap();
+		let plain = db
+			.inner()
+			.update_value_preserve_ttl::<TestSchema>(&absent, &updated);
+		assert!(!plain.unwrap());
+		assert_eq!(db.get::<TestSchema>(&absent).unwrap(), Some(value));
+	}
+
+	#[test]
+	fn test_migrate_ttl_expiration_index() {
+		let db = TestDb::for_schema::<TestSchema>();
+		let cf_name = TtlExpirationSchema::COLUMN_FAMILY_NAME;
+		let value = TtlExpirationValue {
+			cf_name: TestSchema::COLUMN_FAMILY_NAME.to_string(),
+		};
+		let keys = [300, 2, 1_000].map(|ts| TtlExpirationKey {
+			expire_timestamp: ts,
+			schema_name: "schema".to_string(),
+			original_key: vec![0, ts as u8],
+		});
+
+		// as written by `impl_schema_bin_codec!`: varint timestamp, then length prefixed fields
+		let fixture = [2, 6, b's', b'c', b'h', b'e', b'm', b'a', 2, 0, 2];
+		assert_eq!(keys[1].encode_legacy().unwrap(), fixture);
+		assert_eq!(TtlExpirationKey::decode_legacy(&fixture).unwrap(), keys[1]);
+
+		let legacy = SchemaBatch::new();
+		for key in &keys[..2] {
+			legacy.put_raw(
+				cf_name,
+				key.encode_legacy().unwrap(),
+				value.bin_encode().unwrap(),
+			);
+		}
+		db.inner().write_schemas(legacy).unwrap();
+		db.inner()
+			.put::<TtlExpirationSchema>(&keys[2], &value)
+			.unwrap();
+
+		assert_eq!(db.inner().migrate_ttl_expiration_index().unwrap(), 2);
+		assert_eq!(db.inner().migrate_ttl_expiration_index().unwrap(), 0);
+
+		let mut iter = db.inner().iter::<TtlExpirationSchema>().unwrap();
+		iter.seek_to_first();
+		let scanned = iter.collect::<AppResult<Vec<_>>>().unwrap();
+		let mut expected = keys.to_vec();
+		expected.sort_by_key(|key| key.expire_timestamp);
+		assert_eq!(
+			scanned,
+			expected
+				.into_iter()
+				.map(|key| (key, value.clone()))
+				.collect::<Vec<_>>()
+		);
+	}
+
 	#[test]
 	fn test_cleanup_expired() {
-		let db = create_test_db();
+		let db = TestDb::for_schema::<TestSchema>();
 		let key = TestKey(1, 2);
 		let value = TestValue(1, "hello".to_string(), true);
 		let past_time = current_timestamp() - 10;
 
 		// Write already-expired data
-		db.put_with_ttl::<TestSchema>(&key, &value, past_time)
+		db.inner()
+			.put_with_ttl::<TestSchema>(&key, &value, past_time)
 			.unwrap();
 
+		assert_eq!(db.count::<TestSchema>(), 1);
+
 		// Call cleanup
-		db.cleanup_expired(current_timestamp()).unwrap();
+		db.inner().cleanup_expired(current_timestamp()).unwrap();
+		assert_eq!(db.count::<TestSchema>(), 0);
 
 		// Verify expiration index cleaned
 		let ttl_single_key = TtlSingleKey {
-			schema_name: std::any::type_name::<TestSchema>().to_string(),
+			schema_name: ttl_schema_name::<TestSchema>(),
 			original_key: key.bin_encode().unwrap(),
 		};
-		let result = db.get::<TtlSingleSchema>(&ttl_single_key).unwrap();
+		let result = db.inner().get::<TtlSingleSchema>(&ttl_single_key).unwrap();
 		assert_eq!(result, None);
 	}
+
+	#[test]
+	fn test_expiration_key_scan_order() {
+		let db = TestDb::for_schema::<TestSchema>();
+		let now = current_timestamp();
+		let timestamps = [10_000, now + 256, 1, 512, now + 255, 100, 511, 255];
+		for ts in timestamps {
+			let key = TtlExpirationKey {
+				expire_timestamp: ts,
+				schema_name: "schema".to_string(),
+				original_key: vec![1, 2, 3],
+			};
+			let value = TtlExpirationValue {
+				cf_name: TestSchema::COLUMN_FAMILY_NAME.to_string(),
+			};
+			db.inner().put::<TtlExpirationSchema>(&key, &value).unwrap();
+		}
+
+		let mut iter = db.inner().iter::<TtlExpirationSchema>().unwrap();
+		iter.seek_to_first();
+		let scanned: Vec<u64> = iter.map(|res| res.unwrap().0.expire_timestamp).collect();
+		let mut expected = timestamps.to_vec();
+		expected.sort();
+		assert_eq!(scanned, expected);
+
+		let key = TtlExpirationKey {
+			expire_timestamp: now,
+			schema_name: "schema".to_string(),
+			original_key: vec![9],
+		};
+		let encoded = <TtlExpirationKey as KeyCodec<TtlExpirationSchema>>::encode_key(&key).unwrap();
+		assert_eq!(&encoded[..8], &now.to_be_bytes());
+		let decoded =
+			<TtlExpirationKey as KeyCodec<TtlExpirationSchema>>::decode_key(&encoded).unwrap();
+		assert_eq!(decoded, key);
+	}
+
+	#[test]
+	fn test_migrate_ttl_schema_name() {
+		let db = TestDb::for_schema::<TestSchema>();
+		let legacy_name = std::any::type_name::<TestSchema>().to_string();
+		let key = TestKey(1, 2);
+		let value = TestValue(1, "hello".to_string(), true);
+		let expire_at = timestamp_after_seconds(60);
+
+		// as written when the indexes were keyed by the rust type name
+		let batch = SchemaBatch::new();
+		batch.put::<TestSchema>(&key, &value).unwrap();
+		let expiration_key = TtlExpirationKey {
+			expire_timestamp: expire_at,
+			schema_name: legacy_name.clone(),
+			original_key: key.bin_encode().unwrap(),
+		};
+		let expiration_value = TtlExpirationValue {
+			cf_name: TestSchema::COLUMN_FAMILY_NAME.to_string(),
+		};
+		batch
+			.put::<TtlExpirationSchema>(&expiration_key, &expiration_value)
+			.unwrap();
+		let single_key = TtlSingleKey {
+			schema_name: legacy_name,
+			original_key: key.bin_encode().unwrap(),
+		};
+		let single_value = TtlSingleValue {
+			expire_timestamp: expire_at,
+			cf_name: TestSchema::COLUMN_FAMILY_NAME.to_string(),
+		};
+		batch
+			.put::<TtlSingleSchema>(&single_key, &single_value)
+			.unwrap();
+		db.inner().write_schemas(batch).unwrap();
+		assert!(
+			db.inner()
+				.scan_expiring::<TestSchema>(120, 10)
+				.unwrap()
+				.is_empty()
+		);
+
+		assert_eq!(
+			db.inner().migrate_ttl_schema_name::<TestSchema>().unwrap(),
+			1
+		);
+		assert_eq!(
+			db.inner().migrate_ttl_schema_name::<TestSchema>().unwrap(),
+			0
+		);
+
+		assert_eq!(
+			db.inner().scan_expiring::<TestSchema>(120, 10).unwrap(),
+			vec![(key.clone(), expire_at)]
+		);
+		assert_eq!(db.get::<TtlSingleSchema>(&single_key).unwrap(), None);
+		let migrated = TtlSingleKey {
+			schema_name: TestSchema::COLUMN_FAMILY_NAME.to_string(),
+			original_key: key.bin_encode().unwrap(),
+		};
+		assert_eq!(
+			db.get::<TtlSingleSchema>(&migrated).unwrap(),
+			Some(single_value)
+		);
+		assert_eq!(db.inner().get_ttl_stats().unwrap(), (1, 0));
+	}
+
+	#[test]
+	fn test_scan_expiring() {
+		crate::define_schema!(OtherSchema, TestKey, TestValue, "other_schema");
+		crate::impl_schema_bin_codec!(OtherSchema, TestKey, TestValue);
+
+		let db = TestDb::with_ttl(&[
+			TestSchema::COLUMN_FAMILY_NAME,
+			OtherSchema::COLUMN_FAMILY_NAME,
+		]);
+		let now = current_timestamp();
+		let value = TestValue(1, "hello".to_string(), true);
+		for (i, offset) in [300, 50, 5000, 100].into_iter().enumerate() {
+			db.inner()
+				.put_with_ttl::<TestSchema>(&TestKey(i as i32, 0), &value, now + offset)
+				.unwrap();
+		}
+		db.inner()
+			.put_with_ttl::<OtherSchema>(&TestKey(9, 9), &value, now + 60)
+			.unwrap();
+
+		// already expired, a key that is not a `TestKey`, and an entry that is not an index key
+		let batch = SchemaBatch::new();
+		let cf_value = TtlExpirationValue {
+			cf_name: TestSchema::COLUMN_FAMILY_NAME.to_string(),
+		};
+		let expired = TtlExpirationKey {
+			expire_timestamp: now - 10,
+			schema_name: ttl_schema_name::<TestSchema>(),
+			original_key: TestKey(7, 7).bin_encode().unwrap(),
+		};
+		batch
+			.put::<TtlExpirationSchema>(&expired, &cf_value)
+			.unwrap();
+		let foreign = TtlExpirationKey {
+			expire_timestamp: now + 70,
+			schema_name: ttl_schema_name::<TestSchema>(),
+			original_key: vec![0xff],
+		};
+		batch
+			.put::<TtlExpirationSchema>(&foreign, &cf_value)
+			.unwrap();
+		let mut garbage = (now + 80).to_be_bytes().to_vec();
+		garbage.push(0xff);
+		batch.put_raw(TtlExpirationSchema::COLUMN_FAMILY_NAME, garbage, vec![]);
+		db.inner().write_schemas(batch).unwrap();
+
+		let expiring = db.inner().scan_expiring::<TestSchema>(1000, 10).unwrap();
+		assert_eq!(
+			expiring,
+			vec![
+				(TestKey(1, 0), now + 50),
+				(TestKey(3, 0), now + 100),
+				(TestKey(0, 0), now + 300),
+			]
+		);
+		let expiring = db.inner().scan_expiring::<TestSchema>(1000, 2).unwrap();
+		assert_eq!(
+			expiring,
+			vec![(TestKey(1, 0), now + 50), (TestKey(3, 0), now + 100)]
+		);
+		let expiring = db.inner().scan_expiring::<OtherSchema>(1000, 10).unwrap();
+		assert_eq!(expiring, vec![(TestKey(9, 9), now + 60)]);
+		assert!(
+			db.inner()
+				.scan_expiring::<TestSchema>(10, 10)
+				.unwrap()
+				.is_empty()
+		);
+	}
 }
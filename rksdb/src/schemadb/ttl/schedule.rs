@@ -266,12 +266,10 @@ impl Default for RksdbTtlSchedulerManager {
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::schemadb::Schema;
+	use crate::testing::TestDb;
 	use base_infra::codec::bincode::{BinDecodeExt, BinEncodeExt};
 	use bincode::{Decode, Encode};
 	use serde::{Deserialize, Serialize};
-	use std::sync::Arc;
-	use tempfile::TempDir;
 	use tokio::time::{Duration, sleep};
 
 	#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
@@ -283,33 +281,16 @@ mod tests {
 	crate::define_schema!(TestSchema, TestKey, TestValue, "test_schema");
 	crate::impl_schema_bin_codec!(TestSchema, TestKey, TestValue);
 
-	async fn create_test_db() -> Arc<RksDB> {
-		use rocksdb::Options;
-
-		let temp_dir = TempDir::new().unwrap();
-		let path = temp_dir.path().to_path_buf();
-
-		let mut column_families = vec![TestSchema::COLUMN_FAMILY_NAME];
-		column_families.extend(RksDB::get_ttl_column_families());
-
-		let mut opts = Options::default();
-		opts.create_if_missing(true);
-		opts.create_missing_column_families(true);
-
-		let db = RksDB::open(path, "test_db", column_families, &opts).unwrap();
-		Arc::new(db)
-	}
-
 	#[tokio::test]
 	async fn test_scheduler_start_stop() {
-		let db = create_test_db().await;
+		let db = TestDb::for_schema::<TestSchema>();
 		let config = TtlScheduleConfig {
 			cleanup_interval_seconds: 1,
 			enable_cleanup: true,
 			max_cleanup_batch_size: 100,
 		};
 
-		let mut scheduler = RksdbTtlScheduler::new(db, config);
+		let mut scheduler = RksdbTtlScheduler::new(db.shared(), config);
 
 		// Start scheduler
 		scheduler.start().unwrap();
@@ -326,7 +307,7 @@ mod tests {
 
 	#[tokio::test]
 	async fn test_scheduler_cleanup() {
-		let db = create_test_db().await;
+		let db = TestDb::for_schema::<TestSchema>();
 		let config = TtlScheduleConfig {
 			cleanup_interval_seconds: 1,
 			enable_cleanup: true,
@@ -338,24 +319,25 @@ mod tests {
 		let value = TestValue("test".to_string());
 		let past_time = super::super::current_timestamp() - 10;
 
-		db.put_with_ttl::<TestSchema>(&key, &value, past_time)
+		db.inner()
+			.put_with_ttl::<TestSchema>(&key, &value, past_time)
 			.unwrap();
 
-		let scheduler = RksdbTtlScheduler::new(Arc::clone(&db), config);
+		let scheduler = RksdbTtlScheduler::new(db.shared(), config);
 
 		// Trigger immediate cleanup
 		let cleanup_time = scheduler.trigger_cleanup().unwrap();
 		assert!(cleanup_time > 0);
 
 		// Verify data is cleaned
-		let result = db.get_check_ttl::<TestSchema>(&key).unwrap();
+		let result = db.inner().get_check_ttl::<TestSchema>(&key).unwrap();
 		assert_eq!(result, None);
 	}
 
 	#[tokio::test]
 	async fn test_scheduler_manager() {
-		let db1 = create_test_db().await;
-		let db2 = create_test_db().await;
+		let db1 = TestDb::for_schema::<TestSchema>();
+		let db2 = TestDb::for_schema::<TestSchema>();
 
 		let config = TtlScheduleConfig {
 			cleanup_interval_seconds: 2,
@@ -364,8 +346,8 @@ mod tests {
 		};
 
 		let mut manager = RksdbTtlSchedulerManager::new();
-		manager.add_scheduler(db1, config.clone());
-		manager.add_scheduler(db2, config);
+		manager.add_scheduler(db1.shared(), config.clone());
+		manager.add_scheduler(db2.shared(), config);
 
 		// Start all schedulers
 		manager.start_all().unwrap();
@@ -383,14 +365,14 @@ mod tests {
 
 	#[tokio::test]
 	async fn test_disabled_scheduler() {
-		let db = create_test_db().await;
+		let db = TestDb::for_schema::<TestSchema>();
 		let config = TtlScheduleConfig {
 			cleanup_interval_seconds: 1,
 			enable_cleanup: false, // Disable cleanup
 			max_cleanup_batch_size: 100,
 		};
 
-		let mut scheduler = RksdbTtlScheduler::new(db, config);
+		let mut scheduler = RksdbTtlScheduler::new(db.shared(), config);
 
 		// Start scheduler (should not actually start)
 		scheduler.start().unwrap();
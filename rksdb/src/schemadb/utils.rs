@@ -19,6 +19,43 @@ pub(crate) fn default_write_options() -> rocksdb::WriteOptions {
 	opts
 }
 
+/// Read options for iterators: async io prefetching, background purge of obsolete files when
+/// an iterator is dropped and pinned blocks so keys and values borrow without copies.
+pub fn default_read_options() -> rocksdb::ReadOptions {
+	let mut opts = rocksdb::ReadOptions::default();
+	opts.set_async_io(true);
+	opts.set_background_purge_on_iterator_cleanup(true);
+	opts.set_pin_data(true);
+	opts
+}
+
+/// [`default_read_options`] bounded to keys starting with `prefix`.
+///
+/// With a prefix extractor on the column family (e.g. `SliceTransform::create_fixed_prefix`
+/// of the same length) this also lets RocksDB use the prefix bloom filter.
+pub fn prefix_read_options(prefix: &[u8]) -> rocksdb::ReadOptions {
+	let mut opts = default_read_options();
+	opts.set_iterate_lower_bound(prefix);
+	if let Some(upper) = prefix_upper_bound(prefix) {
+		opts.set_iterate_upper_bound(upper);
+	}
+	opts.set_prefix_same_as_start(true);
+	opts
+}
+
+/// Smallest key greater than every key starting with `prefix`, `None` when the prefix is all
+/// `0xff` bytes and no such key exists
+fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+	let mut upper = prefix.to_vec();
+	while let Some(last) = upper.pop() {
+		if last < u8::MAX {
+			upper.push(last + 1);
+			return Some(upper);
+		}
+	}
+	None
+}
+
 pub(crate) trait DeUnc: AsRef<Path> {
 	fn de_unc(&self) -> &Path {
 		// `dunce` is needed to "de-UNC" because rocksdb doesn't take Windows UNC paths like `\\?\C:\`
@@ -68,3 +105,16 @@ impl<T> IntoDbResult<T> for Result<T, Error> {
 fn from_io_err(io_err: Error) -> RksDbError {
 	RksDbError::Other(io_err.to_string())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_prefix_upper_bound() {
+		assert_eq!(prefix_upper_bound(&[1, 2]), Some(vec![1, 3]));
+		assert_eq!(prefix_upper_bound(&[1, 0xff]), Some(vec![2]));
+		assert_eq!(prefix_upper_bound(&[0xff, 0xff]), None);
+		assert_eq!(prefix_upper_bound(&[]), None);
+	}
+}
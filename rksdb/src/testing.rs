@@ -0,0 +1,149 @@
+//! Temp-dir-backed [`RksDB`] for tests, here and in downstream crates (feature `testing`).
+
+use crate::schemadb::schema::Schema;
+use crate::schemadb::{ColumnFamilyName, RksDB};
+use base_infra::result::AppResult;
+use rocksdb::Options;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// An [`RksDB`] in a temp directory that lives as long as the db and is removed on drop.
+pub struct TestDb {
+	db: Arc<RksDB>,
+	column_families: Vec<ColumnFamilyName>,
+	dir: TempDir,
+}
+
+impl TestDb {
+	pub fn new(column_families: &[ColumnFamilyName]) -> Self {
+		let dir = TempDir::new().expect("create temp dir");
+		let db = Self::open(dir.path(), column_families);
+		Self {
+			db: Arc::new(db),
+			column_families: column_families.to_vec(),
+			dir,
+		}
+	}
+
+	/// `column_families` plus the TTL ones
+	pub fn with_ttl(column_families: &[ColumnFamilyName]) -> Self {
+		let mut cfs = column_families.to_vec();
+		cfs.extend(RksDB::get_ttl_column_families());
+		Self::new(&cfs)
+	}
+
+	/// [`Self::with_ttl`] for the column family of `S`
+	pub fn for_schema<S: Schema>() -> Self {
+		Self::with_ttl(&[S::COLUMN_FAMILY_NAME])
+	}
+
+	fn open(path: &Path, column_families: &[ColumnFamilyName]) -> RksDB {
+		let mut opts = Options::default();
+		opts.create_if_missing(true);
+		opts.create_missing_column_families(true);
+		RksDB::open(path, "test_db", column_families.to_vec(), &opts).expect("open test db")
+	}
+
+	/// Closes the db and opens it again from the same directory, like a process restart.
+	///
+	/// # Panics
+	/// If a handle from [`Self::shared`] is still alive.
+	pub fn reopen(self) -> Self {
+		let Self {
+			db,
+			column_families,
+			dir,
+		} = self;
+		drop(Arc::into_inner(db).expect("reopen with shared handles alive"));
+
+		let db = Self::open(dir.path(), &column_families);
+		Self {
+			db: Arc::new(db),
+			column_families,
+			dir,
+		}
+	}
+
+	pub fn path(&self) -> &Path {
+		self.dir.path()
+	}
+
+	pub fn put<S: Schema>(&self, k: S::Key, v: S::Value) -> AppResult<()> {
+		self.db.put::<S>(&k, &v)
+	}
+
+	pub fn get<S: Schema>(&self, k: &S::Key) -> AppResult<Option<S::Value>> {
+		self.db.get::<S>(k)
+	}
+
+	/// All pairs of `S` in key order
+	pub fn all<S: Schema>(&self) -> AppResult<Vec<(S::Key, S::Value)>> {
+		let mut iter = self.db.iter::<S>()?;
+		iter.seek_to_first();
+		iter.collect()
+	}
+
+	pub fn count<S: Schema>(&self) -> u64 {
+		self.all::<S>().expect("scan test db").len() as u64
+	}
+
+	pub fn inner(&self) -> &RksDB {
+		&self.db
+	}
+
+	/// Shared handle for APIs taking `Arc<RksDB>`, valid while `self` is alive
+	pub fn shared(&self) -> Arc<RksDB> {
+		Arc::clone(&self.db)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	crate::define_schema!(NumSchema, u32, u64, "num");
+	crate::impl_schema_bin_codec!(NumSchema, u32, u64);
+
+	#[test]
+	fn test_reopen_keeps_data() {
+		let db = TestDb::new(&["default", NumSchema::COLUMN_FAMILY_NAME]);
+		for i in 0..10u32 {
+			db.put::<NumSchema>(i, i as u64 * 2).unwrap();
+		}
+		let path = db.path().to_path_buf();
+
+		let db = db.reopen();
+		assert_eq!(db.path(), path);
+		assert_eq!(db.count::<NumSchema>(), 10);
+		assert_eq!(db.get::<NumSchema>(&3).unwrap(), Some(6));
+		assert!(
+			!db.inner()
+				.has_column_family(crate::schemadb::ttl::TtlSingleSchema::COLUMN_FAMILY_NAME)
+		);
+
+		let ttl = TestDb::for_schema::<NumSchema>().reopen();
+		assert_eq!(
+			ttl.inner().list_column_families().len(),
+			2 + RksDB::get_ttl_column_families().len()
+		);
+	}
+
+	#[test]
+	#[should_panic(expected = "shared handles alive")]
+	fn test_reopen_with_shared_handle() {
+		let db = TestDb::new(&["default"]);
+		let _shared = db.shared();
+		db.reopen();
+	}
+
+	#[test]
+	fn test_drop_removes_dir() {
+		let db = TestDb::for_schema::<NumSchema>();
+		db.put::<NumSchema>(1, 1).unwrap();
+		let path = db.path().to_path_buf();
+		assert!(path.exists());
+		drop(db);
+		assert!(!path.exists());
+	}
+}
@@ -0,0 +1,110 @@
+//! Fixtures under `tests/fixtures` are the encoding of `reading()` by the reference MessagePack
+//! and CBOR implementations, i.e. what Python's `msgpack.packb` / `cbor2.dumps` write for
+//! `{"sensor": "t-01", "delta": -7, "count": 300, "ok": True, "tags": ["hot", "roof"], "note": None}`.
+#![cfg(any(feature = "msgpack", feature = "cbor"))]
+
+use rksdb_infra::define_pub_schema;
+use rksdb_infra::schemadb::schema::{KeyCodec, ValueCodec};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reading {
+	sensor: String,
+	delta: i64,
+	count: u32,
+	ok: bool,
+	tags: Vec<String>,
+	note: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorKey(String, u32);
+
+/// Big-endian `u64` keys for the value-only schemas
+macro_rules! impl_u64_key {
+	($schema:ty) => {
+		impl KeyCodec<$schema> for u64 {
+			fn encode_key(&self) -> base_infra::result::AppResult<Vec<u8>> {
+				Ok(self.to_be_bytes().to_vec())
+			}
+
+			fn decode_key(data: &[u8]) -> base_infra::result::AppResult<Self> {
+				let bytes = data.try_into().map_err(anyhow::Error::from)?;
+				Ok(u64::from_be_bytes(bytes))
+			}
+		}
+	};
+}
+
+fn reading() -> Reading {
+	Reading {
+		sensor: "t-01".to_string(),
+		delta: -7,
+		count: 300,
+		ok: true,
+		tags: vec!["hot".to_string(), "roof".to_string()],
+		note: None,
+	}
+}
+
+#[cfg(feature = "msgpack")]
+mod msgpack {
+	use super::*;
+
+	define_pub_schema!(ReadingSchema, SensorKey, Reading, "readings_msgpack");
+	rksdb_infra::impl_schema_msgpack_codec!(ReadingSchema, SensorKey, Reading);
+
+	define_pub_schema!(ValueOnlySchema, u64, Reading, "readings_value_msgpack");
+	rksdb_infra::impl_schema_value_msgpack_codec!(ValueOnlySchema, Reading);
+	impl_u64_key!(ValueOnlySchema);
+
+	const FIXTURE: &[u8] = include_bytes!("fixtures/reading.msgpack");
+
+	#[test]
+	fn test_msgpack_fixture() {
+		let encoded = <Reading as ValueCodec<ReadingSchema>>::encode_value(&reading()).unwrap();
+		assert_eq!(encoded, FIXTURE);
+		let decoded = <Reading as ValueCodec<ReadingSchema>>::decode_value(FIXTURE).unwrap();
+		assert_eq!(decoded, reading());
+		let value_only = <Reading as ValueCodec<ValueOnlySchema>>::decode_value(FIXTURE).unwrap();
+		assert_eq!(value_only, reading());
+
+		let key = SensorKey("t-01".to_string(), 7);
+		let key_bytes = key.encode_key().unwrap();
+		assert_eq!(SensorKey::decode_key(&key_bytes).unwrap(), key);
+
+		let err = <Reading as ValueCodec<ReadingSchema>>::decode_value(&FIXTURE[..10]).unwrap_err();
+		assert!(err.to_string().contains("mpack1"), "{err}");
+	}
+}
+
+#[cfg(feature = "cbor")]
+mod cbor {
+	use super::*;
+
+	define_pub_schema!(ReadingSchema, SensorKey, Reading, "readings_cbor");
+	rksdb_infra::impl_schema_cbor_codec!(ReadingSchema, SensorKey, Reading);
+
+	define_pub_schema!(ValueOnlySchema, u64, Reading, "readings_value_cbor");
+	rksdb_infra::impl_schema_value_cbor_codec!(ValueOnlySchema, Reading);
+	impl_u64_key!(ValueOnlySchema);
+
+	const FIXTURE: &[u8] = include_bytes!("fixtures/reading.cbor");
+
+	#[test]
+	fn test_cbor_fixture() {
+		let encoded = <Reading as ValueCodec<ReadingSchema>>::encode_value(&reading()).unwrap();
+		assert_eq!(encoded, FIXTURE);
+		let decoded = <Reading as ValueCodec<ReadingSchema>>::decode_value(FIXTURE).unwrap();
+		assert_eq!(decoded, reading());
+		let value_only = <Reading as ValueCodec<ValueOnlySchema>>::decode_value(FIXTURE).unwrap();
+		assert_eq!(value_only, reading());
+
+		let key = SensorKey("t-01".to_string(), 7);
+		let key_bytes = key.encode_key().unwrap();
+		assert_eq!(SensorKey::decode_key(&key_bytes).unwrap(), key);
+
+		let err = <Reading as ValueCodec<ReadingSchema>>::decode_value(&FIXTURE[..10]).unwrap_err();
+		assert!(err.to_string().contains("cbor01"), "{err}");
+	}
+}
@@ -400,3 +400,163 @@ fn test_unrecognised_column_family() {
 
 	RksDB::open(tmpdir.path(), "test", vec!["cf1"], &opts).unwrap();
 }
+
+#[test]
+fn test_list_column_families() {
+	let db = TestDB::new();
+	let mut cfs = db.list_column_families();
+	cfs.sort();
+	let mut expected = get_column_families();
+	expected.sort();
+	assert_eq!(cfs, expected);
+
+	assert!(db.has_column_family(TestSchema1::COLUMN_FAMILY_NAME));
+	assert!(!db.has_column_family("nonexistent"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_write_schemas_async() {
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicU64, Ordering};
+	use std::time::Duration;
+
+	fn assert_send_sync<T: Send + Sync>() {}
+	assert_send_sync::<RksDB>();
+
+	let tmpdir = aptos_temppath::TempPath::new();
+	let db = Arc::new(open_db(&tmpdir));
+
+	let ticks = Arc::new(AtomicU64::new(0));
+	let ticker = {
+		let ticks = ticks.clone();
+		tokio::spawn(async move {
+			for _ in 0..10 {
+				tokio::time::sleep(Duration::from_millis(1)).await;
+				ticks.fetch_add(1, Ordering::SeqCst);
+			}
+		})
+	};
+
+	let writers = (0..8u32)
+		.map(|i| {
+			let db = db.clone();
+			tokio::spawn(async move {
+				let batch = SchemaBatch::new();
+				for j in 0..100 {
+					let n = i * 100 + j;
+					batch
+						.put::<TestSchema1>(&TestField(n), &TestField(n + 1))
+						.unwrap();
+				}
+				db.write_schemas_async(batch).await
+			})
+		})
+		.collect::<Vec<_>>();
+	let written = tokio::time::timeout(Duration::from_secs(10), futures::future::join_all(writers))
+		.await
+		.expect("async writes deadlocked");
+	for res in written {
+		res.unwrap().unwrap();
+	}
+
+	db.put_async::<TestSchema2>(&TestField(1), &TestField(2))
+		.await
+		.unwrap();
+	db.delete_async::<TestSchema1>(&TestField(0)).await.unwrap();
+
+	assert_eq!(db.get::<TestSchema1>(&TestField(0)).unwrap(), None);
+	assert_eq!(
+		db.get::<TestSchema1>(&TestField(799)).unwrap(),
+		Some(TestField(800))
+	);
+	assert_eq!(
+		db.get::<TestSchema2>(&TestField(1)).unwrap(),
+		Some(TestField(2))
+	);
+	let mut iter = db.iter::<TestSchema1>().unwrap();
+	iter.seek_to_first();
+	assert_eq!(iter.count(), 799);
+
+	ticker.await.unwrap();
+	assert_eq!(ticks.load(Ordering::SeqCst), 10);
+}
+
+#[tokio::test]
+async fn test_write_async_deadline() {
+	use base_infra::result::{ErrorCode, SysErr};
+	use base_infra::runtimes::Deadline;
+	use std::sync::Arc;
+	use std::time::Duration;
+
+	let tmpdir = aptos_temppath::TempPath::new();
+	let db = Arc::new(open_db(&tmpdir));
+
+	let err = Deadline::after(Duration::ZERO)
+		.scope(db.put_async::<TestSchema1>(&TestField(1), &TestField(2)))
+		.await
+		.unwrap_err();
+	assert_eq!(err.err_code().code(), SysErr::DeadlineExceeded.code());
+	assert_eq!(db.get::<TestSchema1>(&TestField(1)).unwrap(), None);
+
+	Deadline::after(Duration::from_secs(5))
+		.scope(db.put_async::<TestSchema1>(&TestField(1), &TestField(2)))
+		.await
+		.unwrap();
+	assert_eq!(
+		db.get::<TestSchema1>(&TestField(1)).unwrap(),
+		Some(TestField(2))
+	);
+}
+
+#[test]
+fn test_bulk_load_session() {
+	let db = TestDB::new();
+	let sst_size = || {
+		db.get_property("TestCF1", "rocksdb.total-sst-files-size")
+			.unwrap()
+	};
+	let load_round = |round: u32| {
+		let batch = SchemaBatch::new();
+		for i in 0..100 {
+			batch
+				.put::<TestSchema1>(&TestField(i), &TestField(round))
+				.unwrap();
+		}
+		db.write_schemas(batch).unwrap();
+		db.flush_cf("TestCF1").unwrap();
+	};
+
+	let session = db.bulk_load_session().unwrap();
+	assert!(!session.db().sync_writes());
+	load_round(0);
+	let one_round = sst_size();
+	// past the level0 compaction trigger, every overwrite stays in its own file
+	for round in 1..8 {
+		load_round(round);
+	}
+	session.db().flush_wal(true).unwrap();
+	assert!(sst_size() > one_round * 6, "{}", sst_size());
+
+	session.finish().unwrap();
+	assert!(db.sync_writes());
+	assert!(sst_size() < one_round * 2, "{}", sst_size());
+	for i in 0..100 {
+		assert_eq!(
+			db.get::<TestSchema1>(&TestField(i)).unwrap(),
+			Some(TestField(7))
+		);
+	}
+
+	// settings come back on drop as well
+	{
+		let _session = db.bulk_load_session().unwrap();
+		assert!(!db.sync_writes());
+	}
+	assert!(db.sync_writes());
+	db.put::<TestSchema2>(&TestField(1), &TestField(1)).unwrap();
+	db.flush_all().unwrap();
+	assert_eq!(
+		db.get::<TestSchema2>(&TestField(1)).unwrap(),
+		Some(TestField(1))
+	);
+}
@@ -2,13 +2,16 @@
 // Parts of the project are originally copyright © Meta Platforms, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use base_infra::result::AppResult;
+use base_infra::result::{AppError, AppResult, SysErr};
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use rksdb_infra::define_schema;
 use rksdb_infra::schemadb::iterator::SchemaIterator;
 use rksdb_infra::schemadb::schema::{KeyCodec, Schema, SeekKeyCodec, ValueCodec};
-use rksdb_infra::schemadb::{IntoDbResult, RksDB};
-use rocksdb::{ColumnFamilyDescriptor, SliceTransform, DEFAULT_COLUMN_FAMILY_NAME};
+use rksdb_infra::schemadb::{
+	IntoDbResult, RksDB, SchemaBatch, default_read_options, prefix_read_options,
+};
+use rocksdb::{ColumnFamilyDescriptor, DEFAULT_COLUMN_FAMILY_NAME, SliceTransform};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 define_schema!(TestSchema, TestKey, TestValue, "TestCF");
 
@@ -102,14 +105,39 @@ struct TestDB {
 }
 
 impl TestDB {
-	fn new() -> Self {
+	/// Empty db with the `TestSchema` column family opened with `cf_opts`
+	fn with_cf_opts(cf_opts: rocksdb::Options) -> Self {
 		let tmpdir = aptos_temppath::TempPath::new();
-		let column_families = vec![DEFAULT_COLUMN_FAMILY_NAME, TestSchema::COLUMN_FAMILY_NAME];
 		let mut db_opts = rocksdb::Options::default();
 		db_opts.create_if_missing(true);
 		db_opts.create_missing_column_families(true);
-		let db = RksDB::open(tmpdir.path(), "test", column_families, &db_opts).unwrap();
+		let db = RksDB::open_cf(
+			&db_opts,
+			tmpdir.path(),
+			"test",
+			vec![
+				ColumnFamilyDescriptor::new(DEFAULT_COLUMN_FAMILY_NAME, rocksdb::Options::default()),
+				ColumnFamilyDescriptor::new(TestSchema::COLUMN_FAMILY_NAME, cf_opts),
+				ColumnFamilyDescriptor::new(
+					CountingSchema::COLUMN_FAMILY_NAME,
+					rocksdb::Options::default(),
+				),
+			],
+		)
+		.unwrap();
+
+		TestDB {
+			_tmpdir: tmpdir,
+			db,
+		}
+	}
+
+	fn empty() -> Self {
+		Self::with_cf_opts(rocksdb::Options::default())
+	}
 
+	fn new() -> Self {
+		let db = Self::empty();
 		db.put::<TestSchema>(&TestKey(1, 0, 0), &TestValue(100))
 			.unwrap();
 		db.put::<TestSchema>(&TestKey(1, 0, 2), &TestValue(102))
@@ -126,11 +154,7 @@ impl TestDB {
 			.unwrap();
 		db.put::<TestSchema>(&TestKey(2, 0, 2), &TestValue(202))
 			.unwrap();
-
-		TestDB {
-			_tmpdir: tmpdir,
-			db,
-		}
+		db
 	}
 }
 
@@ -383,7 +407,7 @@ impl TestDBWithPrefixExtractor {
 	}
 
 	fn iter_with_same_prefix(&self) -> SchemaIterator<TestSchema> {
-		let mut opts = rocksdb::ReadOptions::default();
+		let mut opts = default_read_options();
 		opts.set_prefix_same_as_start(true);
 		self.db
 			.iter_with_opts(opts)
@@ -391,7 +415,7 @@ impl TestDBWithPrefixExtractor {
 	}
 
 	fn iter_with_max_skipped_deletions(&self, num_skips: u64) -> SchemaIterator<TestSchema> {
-		let mut opts = rocksdb::ReadOptions::default();
+		let mut opts = default_read_options();
 		opts.set_max_skippable_internal_keys(num_skips);
 		self.db
 			.iter_with_opts(opts)
@@ -399,7 +423,7 @@ impl TestDBWithPrefixExtractor {
 	}
 
 	fn iter_with_upper_bound(&self, upper_bound: Vec<u8>) -> SchemaIterator<TestSchema> {
-		let mut opts = rocksdb::ReadOptions::default();
+		let mut opts = default_read_options();
 		opts.set_iterate_upper_bound(upper_bound);
 		self.db
 			.iter_with_opts(opts)
@@ -535,3 +559,329 @@ fn test_iter_with_upper_bound() {
 	iter.seek(&KeyPrefix1(1)).unwrap();
 	assert_eq!(collect_values_mut(&mut iter), [122, 123, 125]);
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_into_stream() {
+	use futures::StreamExt;
+	use rksdb_infra::schemadb::SchemaBatch;
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicU64, Ordering};
+	use std::time::Duration;
+
+	let db = TestDB::empty();
+	let batch = SchemaBatch::new();
+	for i in 0..10_000 {
+		batch
+			.put::<TestSchema>(&TestKey(i, 0, 0), &TestValue(i))
+			.unwrap();
+	}
+	db.write_schemas(batch).unwrap();
+
+	let ticks = Arc::new(AtomicU64::new(0));
+	let ticker = {
+		let ticks = ticks.clone();
+		tokio::spawn(async move {
+			for _ in 0..20 {
+				tokio::time::sleep(Duration::from_millis(1)).await;
+				ticks.fetch_add(1, Ordering::SeqCst);
+			}
+		})
+	};
+
+	let mut iter = db.iter();
+	iter.seek_to_first();
+	let mut stream = iter.into_stream(256);
+	let mut values = Vec::with_capacity(10_000);
+	while let Some(row) = stream.next().await {
+		values.push(row.unwrap().1.0);
+	}
+
+	assert_eq!(values.len(), 10_000);
+	assert!(values.windows(2).all(|w| w[0] < w[1]));
+
+	tokio::time::timeout(Duration::from_secs(5), ticker)
+		.await
+		.expect("concurrent task starved")
+		.unwrap();
+	assert_eq!(ticks.load(Ordering::SeqCst), 20);
+}
+
+fn filter_map_db() -> TestDB {
+	let db = TestDB::empty();
+	for i in 0..100 {
+		db.put::<TestSchema>(&TestKey(i, 0, 0), &TestValue(i * 10))
+			.unwrap();
+	}
+	db
+}
+
+fn even_values(key: TestKey, value: TestValue) -> AppResult<Option<(u32, u32)>> {
+	Ok((key.0 % 2 == 0).then_some((key.0, value.0)))
+}
+
+#[test]
+fn test_filter_map() {
+	let db = filter_map_db();
+
+	let mut iter = db.iter();
+	iter.seek_to_first();
+	let rows = iter
+		.filter_map(even_values)
+		.collect::<AppResult<Vec<_>>>()
+		.unwrap();
+
+	assert_eq!(rows.len(), 50);
+	let expected: Vec<_> = (0..100).step_by(2).map(|i| (i, i * 10)).collect();
+	assert_eq!(rows, expected);
+
+	let mut iter = db.rev_iter();
+	iter.seek_to_last();
+	let mut filtered = iter.filter_map(|key, _| match key.0 {
+		97 => Err(AppError::ErrCode(&SysErr::InternalError)),
+		k => Ok(Some(k)),
+	});
+	assert_eq!(filtered.next().unwrap().unwrap(), 99);
+	assert_eq!(filtered.next().unwrap().unwrap(), 98);
+	assert!(filtered.next().unwrap().is_err());
+	assert_eq!(filtered.next().unwrap().unwrap(), 96);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_filter_map_into_stream() {
+	use futures::StreamExt;
+
+	let db = filter_map_db();
+	let mut iter = db.iter();
+	iter.seek_to_first();
+	let rows: Vec<_> = iter
+		.filter_map(even_values)
+		.into_stream(16)
+		.map(Result::unwrap)
+		.collect()
+		.await;
+	assert_eq!(rows.len(), 50);
+	assert!(rows.iter().all(|(k, v)| k % 2 == 0 && *v == k * 10));
+}
+
+#[test]
+fn test_prefix_read_options() {
+	let mut cf_opts = rocksdb::Options::default();
+	cf_opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(8));
+	let db = TestDB::with_cf_opts(cf_opts);
+
+	let rows = [
+		(TestKey(1, 1, 1), 111),
+		(TestKey(1, 2, 2), 122),
+		(TestKey(1, 2, 3), 123),
+		(TestKey(1, 3, 0), 130),
+		(TestKey(2, 2, 2), 222),
+		(TestKey(u32::MAX, u32::MAX, 1), 999),
+	];
+	for (key, value) in rows {
+		db.put::<TestSchema>(&key, &TestValue(value)).unwrap();
+	}
+
+	let prefix = KeyPrefix2(1, 2).encode_seek_key().unwrap();
+	let mut iter = db
+		.iter_with_opts::<TestSchema>(prefix_read_options(&prefix))
+		.unwrap();
+	iter.seek_to_first();
+	assert_eq!(collect_values_mut(&mut iter), [122, 123]);
+
+	// all-0xff prefix has no upper bound
+	let prefix = KeyPrefix2(u32::MAX, u32::MAX).encode_seek_key().unwrap();
+	let mut iter = db
+		.iter_with_opts::<TestSchema>(prefix_read_options(&prefix))
+		.unwrap();
+	iter.seek_to_first();
+	assert_eq!(collect_values_mut(&mut iter), [999]);
+}
+
+/// Keys of `TestSchema` in `[start, end)`, from a full scan
+fn ground_truth(db: &RksDB, start: &TestKey, end: &TestKey) -> usize {
+	let start = start.encode_key().unwrap();
+	let end = end.encode_key().unwrap();
+	db.get_all::<TestSchema>()
+		.unwrap()
+		.iter()
+		.map(|(key, _)| key.encode_key().unwrap())
+		.filter(|key| *key >= start && *key < end)
+		.count()
+}
+
+fn any_in(db: &RksDB, prefix: impl SeekKeyCodec<TestSchema>) -> bool {
+	let prefix = prefix.encode_seek_key().unwrap();
+	db.any_in_prefix::<TestSchema>(&prefix).unwrap()
+}
+
+#[test]
+fn test_any_in_prefix_and_count_range() {
+	let db = TestDB::new();
+	db.put::<TestSchema>(&TestKey(u32::MAX, u32::MAX, 1), &TestValue(999))
+		.unwrap();
+
+	assert!(db.any_in_prefix::<TestSchema>(&[]).unwrap());
+	assert!(any_in(&db, KeyPrefix1(1)));
+	assert!(any_in(&db, KeyPrefix2(1, 1)));
+	assert!(!any_in(&db, KeyPrefix2(1, 2)));
+	assert!(!any_in(&db, KeyPrefix1(3)));
+	// the very end of the keyspace
+	let last = KeyPrefix2(u32::MAX, u32::MAX).encode_seek_key().unwrap();
+	assert!(db.any_in_prefix::<TestSchema>(&last).unwrap());
+	assert!(!db.any_in_prefix::<TestSchema>(&[0xff; 13]).unwrap());
+
+	let ranges = [
+		(TestKey(0, 0, 0), TestKey(u32::MAX, u32::MAX, u32::MAX)),
+		(TestKey(1, 0, 0), TestKey(2, 0, 0)),
+		(TestKey(1, 0, 1), TestKey(1, 1, 3)),
+		(TestKey(1, 1, 4), TestKey(1, 1, 5)),
+		(TestKey(2, 0, 0), TestKey(1, 0, 0)),
+		(TestKey(3, 0, 0), TestKey(4, 0, 0)),
+		(
+			TestKey(u32::MAX, u32::MAX, 0),
+			TestKey(u32::MAX, u32::MAX, u32::MAX),
+		),
+	];
+	for (start, end) in &ranges {
+		let expected = ground_truth(&db, start, end);
+		assert_eq!(
+			db.count_range::<TestSchema>(start, end, None).unwrap(),
+			expected
+		);
+		assert_eq!(
+			db.count_range::<TestSchema>(start, end, Some(2)).unwrap(),
+			expected.min(2)
+		);
+	}
+	assert_eq!(
+		db.count_range::<TestSchema>(&KeyPrefix1(1), &KeyPrefix1(2), None)
+			.unwrap(),
+		6
+	);
+	assert_eq!(
+		db.count_range::<TestSchema>(&KeyPrefix1(1), &KeyPrefix1(2), Some(0))
+			.unwrap(),
+		0
+	);
+}
+
+#[test]
+fn test_any_in_prefix_and_count_range_empty_db() {
+	let db = filter_map_db();
+	for i in 0..100 {
+		db.delete::<TestSchema>(&TestKey(i, 0, 0)).unwrap();
+	}
+	assert!(!db.any_in_prefix::<TestSchema>(&[]).unwrap());
+	assert_eq!(
+		db.count_range::<TestSchema>(&KeyPrefix1(0), &KeyPrefix1(u32::MAX), None)
+			.unwrap(),
+		0
+	);
+}
+
+static KEY_DECODES: AtomicUsize = AtomicUsize::new(0);
+static VALUE_DECODES: AtomicUsize = AtomicUsize::new(0);
+
+define_schema!(CountingSchema, CountedKey, TestValue, "CountingCF");
+
+/// Counts decodes to check which work the scans do
+#[derive(Debug, Eq, PartialEq)]
+pub struct CountedKey(u32);
+
+impl KeyCodec<CountingSchema> for CountedKey {
+	fn encode_key(&self) -> AppResult<Vec<u8>> {
+		Ok(self.0.to_be_bytes().to_vec())
+	}
+
+	fn decode_key(data: &[u8]) -> AppResult<Self> {
+		KEY_DECODES.fetch_add(1, Ordering::SeqCst);
+		let mut reader = std::io::Cursor::new(data);
+		Ok(CountedKey(reader.read_u32::<BigEndian>().into_db_res()?))
+	}
+}
+
+impl ValueCodec<CountingSchema> for TestValue {
+	fn encode_value(&self) -> AppResult<Vec<u8>> {
+		Ok(self.0.to_be_bytes().to_vec())
+	}
+
+	fn decode_value(data: &[u8]) -> AppResult<Self> {
+		VALUE_DECODES.fetch_add(1, Ordering::SeqCst);
+		<TestValue as ValueCodec<TestSchema>>::decode_value(data)
+	}
+}
+
+#[test]
+fn test_count_range_limit_short_circuits() {
+	let db = TestDB::empty();
+	for i in 0..100 {
+		db.put::<CountingSchema>(&CountedKey(i), &TestValue(i))
+			.unwrap();
+	}
+
+	let (start, end) = (CountedKey(0), CountedKey(100));
+	assert_eq!(
+		db.count_range::<CountingSchema>(&start, &end, Some(10))
+			.unwrap(),
+		10
+	);
+	assert_eq!(KEY_DECODES.load(Ordering::SeqCst), 10);
+
+	assert_eq!(
+		db.count_range::<CountingSchema>(&start, &end, None)
+			.unwrap(),
+		100
+	);
+	assert_eq!(KEY_DECODES.load(Ordering::SeqCst), 110);
+
+	assert!(db.any_in_prefix::<CountingSchema>(&[0, 0]).unwrap());
+	assert_eq!(KEY_DECODES.load(Ordering::SeqCst), 110);
+	assert_eq!(VALUE_DECODES.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_position_and_decode_errors() {
+	let db = TestDB::new();
+	let corrupted = TestKey(1, 0, 3).encode_key().unwrap();
+	let batch = SchemaBatch::new();
+	batch.put_raw(
+		TestSchema::COLUMN_FAMILY_NAME,
+		corrupted.clone(),
+		vec![0xff],
+	);
+	db.write_schemas(batch).unwrap();
+
+	let mut iter = db.iter();
+	assert!(!iter.valid());
+	assert_eq!(iter.position().unwrap(), None);
+
+	iter.seek(&TestKey(1, 0, 2)).unwrap();
+	assert!(iter.valid());
+	assert_eq!(iter.position().unwrap(), Some(TestKey(1, 0, 2)));
+	assert_eq!(iter.value_bytes(), Some(&102u32.to_be_bytes()[..]));
+
+	assert_eq!(iter.next().unwrap().unwrap().1, TestValue(102));
+	// the value fails to decode, the key and the rocksdb status are fine
+	assert!(iter.next().unwrap().is_err());
+	assert_eq!(iter.key_bytes(), Some(&corrupted[..]));
+	assert_eq!(iter.value_bytes(), Some(&[0xff][..]));
+	assert_eq!(iter.position().unwrap(), Some(TestKey(1, 0, 3)));
+	iter.status().unwrap();
+
+	// later entries are still readable
+	assert_eq!(
+		collect_values_mut(&mut iter),
+		[104, 110, 112, 114, 200, 202]
+	);
+	assert!(!iter.valid());
+	assert_eq!(iter.key_bytes(), None);
+	assert_eq!(iter.position().unwrap(), None);
+	assert!(iter.next().is_none());
+	assert!(iter.next().is_none());
+	iter.status().unwrap();
+
+	let mut rev_iter = db.rev_iter();
+	rev_iter.seek_to_last();
+	let errors = rev_iter.filter(|row| row.is_err()).count();
+	assert_eq!(errors, 1);
+}
@@ -0,0 +1,92 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Expr, ExprLit, Fields, Lit, LitStr, parse_macro_input};
+
+/// Derive form of `sql_infra::sql_enum!`, producing the same impls for a fieldless enum.
+///
+/// `#[sql_name = "..."]` on a variant overrides its stored name.
+///
+/// ```ignore
+/// use sql_infra::SqlEnum;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq, SqlEnum)]
+/// pub enum OrderStatus {
+///     Pending,
+///     #[sql_name = "filled_all"]
+///     Filled,
+///     Cancelled,
+/// }
+/// ```
+#[proc_macro_derive(SqlEnum, attributes(sql_name))]
+pub fn derive_sql_enum(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	expand(input).unwrap_or_else(|err| err.to_compile_error().into())
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+	let Data::Enum(data) = &input.data else {
+		return Err(syn::Error::new_spanned(
+			&input.ident,
+			"SqlEnum can only be derived for enums",
+		));
+	};
+	if !input.generics.params.is_empty() {
+		return Err(syn::Error::new_spanned(
+			&input.generics,
+			"SqlEnum does not support generic enums",
+		));
+	}
+	if data.variants.is_empty() {
+		return Err(syn::Error::new_spanned(
+			&input.ident,
+			"SqlEnum needs at least one variant",
+		));
+	}
+
+	let mut variants = Vec::with_capacity(data.variants.len());
+	for variant in &data.variants {
+		if !matches!(variant.fields, Fields::Unit) {
+			return Err(syn::Error::new_spanned(
+				variant,
+				"SqlEnum only supports fieldless variants",
+			));
+		}
+		let ident = &variant.ident;
+		let variant = match sql_name(&variant.attrs)? {
+			Some(name) => quote! { #[sql_name = #name] #ident },
+			None => quote! { #ident },
+		};
+		variants.push(variant);
+	}
+
+	let name = &input.ident;
+	Ok(quote! {
+		::sql_infra::sql_enum!(#name { #(#variants),* });
+	}
+	.into())
+}
+
+fn sql_name(attrs: &[syn::Attribute]) -> syn::Result<Option<LitStr>> {
+	let mut found = None;
+	for attr in attrs.iter().filter(|a| a.path().is_ident("sql_name")) {
+		if found.is_some() {
+			return Err(syn::Error::new_spanned(
+				attr,
+				"duplicate sql_name attribute",
+			));
+		}
+		let value = &attr.meta.require_name_value()?.value;
+		match value {
+			Expr::Lit(ExprLit {
+				lit: Lit::Str(lit), ..
+			}) => found = Some(lit.clone()),
+			_ => {
+				return Err(syn::Error::new_spanned(
+					value,
+					r#"expected `#[sql_name = "..."]`"#,
+				));
+			}
+		}
+	}
+	Ok(found)
+}
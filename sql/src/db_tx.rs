@@ -3,9 +3,10 @@ use crate::error::DBErr;
 use crate::sea_ext::page::PageQuery;
 use base_infra::map_err;
 use base_infra::result::AppResult;
+use base_infra::runtimes::Deadline;
 use sea_orm::prelude::async_trait;
 use sea_orm::{
-	ConnectionTrait, DatabaseConnection, DatabaseTransaction, Paginator, SelectorTrait,
+	ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbBackend, Paginator, SelectorTrait,
 	TransactionTrait,
 };
 
@@ -56,12 +57,23 @@ impl DatabaseConn {
 		DatabaseTx::new(&self.pool)
 	}
 
+	/// Under a [`Deadline`] the transaction is refused once it has passed, and on Postgres its
+	/// statements get a `statement_timeout` of the time left
 	pub async fn begin_tx(&self, biz: &str) -> AppResult<DatabaseTransaction> {
+		Deadline::check()?;
 		let tx = self
 			.pool
 			.begin()
 			.await
 			.map_err(map_err!(&DBErr::SqlxTxOpenError, biz))?;
+		if let Some(left) = Deadline::remaining()
+			&& tx.get_database_backend() == DbBackend::Postgres
+		{
+			let millis = left.as_millis().max(1);
+			tx.execute_unprepared(&format!("SET LOCAL statement_timeout = {millis}"))
+				.await
+				.map_err(map_err!(&DBErr::SqlxTxOpenError, biz))?;
+		}
 		Ok(tx)
 	}
 }
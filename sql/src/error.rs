@@ -4,16 +4,37 @@ gen_impl_code_enum! {
 	DBErr {
 		InitDbPoolErr = ("DBP001", "error while initializing the database connection pool"),
 		RunMigrationsErr = ("DBP002", "error while running database migrations"),
+		PingErr = ("DBP003", "error while pinging the database"),
 		SqlxTxOpenError = ("DBTX00", "Sqlx transaction open error"),
 		SqlxTxCommitError = ("DBTX01", "Sqlx transaction commit error"),
 		SqlxError = ("DB0000", "Sqlx error"),
+		RecordNotFound = ("DBREC1", "Record not found"),
+		UniqueViolation = ("DBREC2", "Unique constraint violated"),
+		ForeignKeyViolation = ("DBREC3", "Foreign key constraint violated"),
 
 		PaginatorItemsAndPages = ("DBPG01", "Get total items and pages error"),
 		PaginatorFetchPage = ("DBPG02", "Execute Paginator fetch_page error"),
+		PageOutOfRange = ("DBPG03", "Page offset out of range"),
+		GroupedCountsErr = ("DBGC01", "Execute grouped counts query error"),
 
 		// version
 		GetVersion = ("DBVER01", "Get version error"),
 		VersionNotFound = ("DBVER02", "Version not found"),
 		TryGetVersion = ("DBVER03", "Try get version from `QueryResult` error"),
+
+		// encrypted columns
+		EncryptionKey = ("DBENC01", "Invalid or missing column encryption key"),
+		EncryptColumn = ("DBENC02", "Encrypt column value error"),
+		DecryptColumn = ("DBENC03", "Decrypt column value error"),
+
+		// outbox
+		OutboxEnqueueErr = ("DBOB01", "Enqueue outbox event error"),
+		OutboxQueryErr = ("DBOB02", "Query outbox events error"),
+		OutboxUpdateErr = ("DBOB03", "Update outbox event error"),
+		MetricsRegisterErr = ("DBOB04", "Outbox metrics register failed"),
+
+		// advisory lock
+		AdvisoryLockErr = ("DBLK01", "Advisory lock query error"),
+		AdvisoryLockUnsupported = ("DBLK02", "Advisory locks need a Postgres connection"),
 	}
 }
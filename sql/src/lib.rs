@@ -11,9 +11,13 @@ pub mod cfgs;
 pub mod db_tx;
 pub mod error;
 pub mod macros;
+#[cfg(feature = "outbox")]
+pub mod outbox;
 pub mod sea_ext;
 pub mod utils;
 
+pub use sql_enum_macro::SqlEnum;
+
 use crate::cfgs::DbCfgTrait;
 
 #[async_trait::async_trait]
@@ -74,6 +78,11 @@ where
 	}
 }
 
+/// Checks the pool can still reach the database
+pub async fn ping_db(conn: &DatabaseConnection) -> AppResult<()> {
+	conn.ping().await.map_err(map_err!(&DBErr::PingErr))
+}
+
 impl Deref for DatabaseConn {
 	type Target = DatabaseConnection;
 
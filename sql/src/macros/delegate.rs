@@ -5,17 +5,21 @@
 ///
 /// # Syntax
 ///
-/// ```rust
+/// ```ignore
 /// use sql_infra::autogen_delegate_repo_trait;
 ///
 /// autogen_delegate_repo_trait! {
 ///     impl TraitName for StructName {
 ///         delegate_to: method_name();
+///         // Optional, required by `&mut self` methods
+///         delegate_mut_to: method_name_mut();
 ///
-///         // Manually specify all trait method signatures
+///         // Manually specify all trait method signatures, in any order
 ///         async fn method1(&self, param1: Type1) -> ReturnType1;
-///         async fn method2(&self, param1: Type1, param2: Type2) -> ReturnType2;
+///         async fn method2(&mut self, param1: Type1, param2: Type2) -> ReturnType2;
+///         async fn method3<'a>(&'a self) -> &'a ReturnType3;
 ///         fn sync_method(&self, param: Type) -> ReturnType;
+///         fn sync_method_mut(&mut self, param: Type) -> ReturnType;
 ///     }
 /// }
 /// ```
@@ -24,12 +28,14 @@
 ///
 /// The macro invocation above will generate:
 ///
-/// ```rust
+/// ```ignore
 /// #[async_trait::async_trait]
 /// pub trait TraitName {
 ///     async fn method1(&self, param1: Type1) -> ReturnType1;
-///     async fn method2(&self, param1: Type1, param2: Type2) -> ReturnType2;
+///     async fn method2(&mut self, param1: Type1, param2: Type2) -> ReturnType2;
+///     async fn method3<'a>(&'a self) -> &'a ReturnType3;
 ///     fn sync_method(&self, param: Type) -> ReturnType;
+///     fn sync_method_mut(&mut self, param: Type) -> ReturnType;
 /// }
 ///
 /// #[async_trait::async_trait]
@@ -37,12 +43,18 @@
 ///     async fn method1(&self, param1: Type1) -> ReturnType1 {
 ///         self.method_name().method1(param1).await
 ///     }
-///     async fn method2(&self, param1: Type1, param2: Type2) -> ReturnType2 {
-///         self.method_name().method2(param1, param2).await
+///     async fn method2(&mut self, param1: Type1, param2: Type2) -> ReturnType2 {
+///         self.method_name_mut().method2(param1, param2).await
+///     }
+///     async fn method3<'a>(&'a self) -> &'a ReturnType3 {
+///         self.method_name().method3().await
 ///     }
 ///     fn sync_method(&self, param: Type) -> ReturnType {
 ///         self.method_name().sync_method(param)
 ///     }
+///     fn sync_method_mut(&mut self, param: Type) -> ReturnType {
+///         self.method_name_mut().sync_method_mut(param)
+///     }
 /// }
 /// ```
 ///
@@ -51,14 +63,25 @@
 /// - **Auto-generate trait**: Create trait definitions from method signatures
 /// - **Auto-generate delegate impl**: Generate delegate implementation for the struct
 /// - **Async support**: Automatically handle `async` methods and `.await` calls
+/// - **Mutable delegates**: `&mut self` methods go through `delegate_mut_to`
+/// - **Lifetimes**: `<'a>(&'a self)` lets methods return references tied to the struct
 /// - **Type safety**: Compile-time checking for signature matching
 /// - **Simplified syntax**: One macro to do both
 ///
+/// # async_trait
+///
+/// Both the trait and the impl use `#[async_trait]`, which boxes every async method into a
+/// `Pin<Box<dyn Future + Send>>`. The delegate target therefore has to be `Sync` for `&self`
+/// methods and `Send` for `&mut self` methods, and so do the parameters. The struct is
+/// borrowed for the whole call, so a `&mut self` method holds the exclusive borrow until the
+/// boxed future completes.
+///
 /// # Limitations
 ///
 /// - The delegate target must implement the same trait
 /// - All method signatures must be specified manually (Rust macro system limitation)
 /// - Delegated method calls must be simple (no complex expressions)
+/// - Methods only take a single lifetime parameter, bound to `self`
 /// - The generated trait is always public
 #[macro_export]
 macro_rules! autogen_delegate_repo_trait {
@@ -66,48 +89,195 @@ macro_rules! autogen_delegate_repo_trait {
     (
         impl $trait_name:ident for $struct_name:ident {
             delegate_to: $delegate_method:ident();
+            delegate_mut_to: $delegate_mut_method:ident();
+
+            $($methods:tt)*
+        }
+    ) => {
+        $crate::autogen_delegate_repo_trait! {
+            @munch $trait_name $struct_name $delegate_method { $delegate_mut_method } [] []
+            $($methods)*
+        }
+    };
 
-            $(
-                async fn $async_method_name:ident(
-                    &self
-                    $(, $async_param_name:ident: $async_param_type:ty)*
-                ) -> $async_return_type:ty;
-            )*
+    (
+        impl $trait_name:ident for $struct_name:ident {
+            delegate_to: $delegate_method:ident();
 
-            $(
-                fn $sync_method_name:ident(
-                    &self
-                    $(, $sync_param_name:ident: $sync_param_type:ty)*
-                ) -> $sync_return_type:ty;
-            )*
+            $($methods:tt)*
         }
     ) => {
-        // First, generate the trait definition
-        #[async_trait::async_trait]
-        pub trait $trait_name {
-            $(
-                async fn $async_method_name(&self $(, $async_param_name: $async_param_type)*) -> $async_return_type;
-            )*
+        $crate::autogen_delegate_repo_trait! {
+            @munch $trait_name $struct_name $delegate_method {} [] []
+            $($methods)*
+        }
+    };
 
-            $(
-                fn $sync_method_name(&self $(, $sync_param_name: $sync_param_type)*) -> $sync_return_type;
-            )*
+    // async fn name(&self, ..)
+    (
+        @munch $trait_name:ident $struct_name:ident $delegate:ident { $($delegate_mut:ident)? }
+        [$($trait_items:tt)*] [$($impl_items:tt)*]
+        async fn $method_name:ident(&self $(, $param_name:ident: $param_type:ty)*) -> $return_type:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::autogen_delegate_repo_trait! {
+            @munch $trait_name $struct_name $delegate { $($delegate_mut)? }
+            [
+                $($trait_items)*
+                async fn $method_name(&self $(, $param_name: $param_type)*) -> $return_type;
+            ]
+            [
+                $($impl_items)*
+                async fn $method_name(&self $(, $param_name: $param_type)*) -> $return_type {
+                    self.$delegate().$method_name($($param_name),*).await
+                }
+            ]
+            $($rest)*
         }
+    };
 
-        // Then, generate the delegate implementation
-        #[async_trait::async_trait]
-        impl $trait_name for $struct_name {
-            $(
-                async fn $async_method_name(&self $(, $async_param_name: $async_param_type)*) -> $async_return_type {
-                    self.$delegate_method().$async_method_name($($async_param_name),*).await
+    // async fn name<'a>(&'a self, ..)
+    (
+        @munch $trait_name:ident $struct_name:ident $delegate:ident { $($delegate_mut:ident)? }
+        [$($trait_items:tt)*] [$($impl_items:tt)*]
+        async fn $method_name:ident<$lt:lifetime>(&$self_lt:lifetime self $(, $param_name:ident: $param_type:ty)*) -> $return_type:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::autogen_delegate_repo_trait! {
+            @munch $trait_name $struct_name $delegate { $($delegate_mut)? }
+            [
+                $($trait_items)*
+                async fn $method_name<$lt>(&$self_lt self $(, $param_name: $param_type)*) -> $return_type;
+            ]
+            [
+                $($impl_items)*
+                async fn $method_name<$lt>(&$self_lt self $(, $param_name: $param_type)*) -> $return_type {
+                    self.$delegate().$method_name($($param_name),*).await
                 }
-            )*
+            ]
+            $($rest)*
+        }
+    };
 
-            $(
-                fn $sync_method_name(&self $(, $sync_param_name: $sync_param_type)*) -> $sync_return_type {
-                    self.$delegate_method().$sync_method_name($($sync_param_name),*)
+    // async fn name(&mut self, ..)
+    (
+        @munch $trait_name:ident $struct_name:ident $delegate:ident { $delegate_mut:ident }
+        [$($trait_items:tt)*] [$($impl_items:tt)*]
+        async fn $method_name:ident(&mut self $(, $param_name:ident: $param_type:ty)*) -> $return_type:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::autogen_delegate_repo_trait! {
+            @munch $trait_name $struct_name $delegate { $delegate_mut }
+            [
+                $($trait_items)*
+                async fn $method_name(&mut self $(, $param_name: $param_type)*) -> $return_type;
+            ]
+            [
+                $($impl_items)*
+                async fn $method_name(&mut self $(, $param_name: $param_type)*) -> $return_type {
+                    self.$delegate_mut().$method_name($($param_name),*).await
                 }
-            )*
+            ]
+            $($rest)*
+        }
+    };
+
+    // fn name(&self, ..)
+    (
+        @munch $trait_name:ident $struct_name:ident $delegate:ident { $($delegate_mut:ident)? }
+        [$($trait_items:tt)*] [$($impl_items:tt)*]
+        fn $method_name:ident(&self $(, $param_name:ident: $param_type:ty)*) -> $return_type:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::autogen_delegate_repo_trait! {
+            @munch $trait_name $struct_name $delegate { $($delegate_mut)? }
+            [
+                $($trait_items)*
+                fn $method_name(&self $(, $param_name: $param_type)*) -> $return_type;
+            ]
+            [
+                $($impl_items)*
+                fn $method_name(&self $(, $param_name: $param_type)*) -> $return_type {
+                    self.$delegate().$method_name($($param_name),*)
+                }
+            ]
+            $($rest)*
+        }
+    };
+
+    // fn name<'a>(&'a self, ..)
+    (
+        @munch $trait_name:ident $struct_name:ident $delegate:ident { $($delegate_mut:ident)? }
+        [$($trait_items:tt)*] [$($impl_items:tt)*]
+        fn $method_name:ident<$lt:lifetime>(&$self_lt:lifetime self $(, $param_name:ident: $param_type:ty)*) -> $return_type:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::autogen_delegate_repo_trait! {
+            @munch $trait_name $struct_name $delegate { $($delegate_mut)? }
+            [
+                $($trait_items)*
+                fn $method_name<$lt>(&$self_lt self $(, $param_name: $param_type)*) -> $return_type;
+            ]
+            [
+                $($impl_items)*
+                fn $method_name<$lt>(&$self_lt self $(, $param_name: $param_type)*) -> $return_type {
+                    self.$delegate().$method_name($($param_name),*)
+                }
+            ]
+            $($rest)*
+        }
+    };
+
+    // fn name(&mut self, ..)
+    (
+        @munch $trait_name:ident $struct_name:ident $delegate:ident { $delegate_mut:ident }
+        [$($trait_items:tt)*] [$($impl_items:tt)*]
+        fn $method_name:ident(&mut self $(, $param_name:ident: $param_type:ty)*) -> $return_type:ty;
+        $($rest:tt)*
+    ) => {
+        $crate::autogen_delegate_repo_trait! {
+            @munch $trait_name $struct_name $delegate { $delegate_mut }
+            [
+                $($trait_items)*
+                fn $method_name(&mut self $(, $param_name: $param_type)*) -> $return_type;
+            ]
+            [
+                $($impl_items)*
+                fn $method_name(&mut self $(, $param_name: $param_type)*) -> $return_type {
+                    self.$delegate_mut().$method_name($($param_name),*)
+                }
+            ]
+            $($rest)*
+        }
+    };
+
+    // `&mut self` without `delegate_mut_to`
+    (
+        @munch $trait_name:ident $struct_name:ident $delegate:ident {}
+        [$($trait_items:tt)*] [$($impl_items:tt)*]
+        $(async)? fn $method_name:ident(&mut self $($params:tt)*) -> $return_type:ty;
+        $($rest:tt)*
+    ) => {
+        compile_error!(concat!(
+            "`",
+            stringify!($method_name),
+            "` takes `&mut self`, add `delegate_mut_to: method_mut();`"
+        ));
+    };
+
+    // All methods consumed: emit the trait and the delegate implementation
+    (
+        @munch $trait_name:ident $struct_name:ident $delegate:ident { $($delegate_mut:ident)? }
+        [$($trait_items:tt)*] [$($impl_items:tt)*]
+    ) => {
+        #[async_trait::async_trait]
+        pub trait $trait_name {
+            $($trait_items)*
+        }
+
+        #[async_trait::async_trait]
+        impl $trait_name for $struct_name {
+            $($impl_items)*
         }
     };
 }
@@ -2,5 +2,7 @@
 ///
 /// This module contains various macro definitions used across the project, including:
 /// - Delegation macro (delegate.rs)
+/// - String column enum macro (sql_enum.rs)
 /// - Other common macros
 pub mod delegate;
+pub mod sql_enum;
@@ -0,0 +1,115 @@
+/// Maps a fieldless enum to a string column holding the variant name.
+///
+/// Implements `Iden`, `TryGetable`, `ValueType` (a `String` column), `Nullable` and
+/// `From<Enum> for Value`, plus `sql_name` / `from_sql_name`. `#[sql_name = "..."]` overrides the
+/// stored name of a variant. `#[derive(SqlEnum)]` expands to this macro for enums that prefer a
+/// derive.
+///
+/// ```ignore
+/// use sql_infra::sql_enum;
+///
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// pub enum OrderStatus {
+///     Pending,
+///     Filled,
+///     Cancelled,
+/// }
+///
+/// sql_enum!(OrderStatus {
+///     Pending,
+///     #[sql_name = "filled_all"]
+///     Filled,
+///     Cancelled,
+/// });
+///
+/// // `status VARCHAR` stores "Pending", "filled_all" or "Cancelled"
+/// let value: sea_orm::Value = OrderStatus::Filled.into();
+/// ```
+#[macro_export]
+macro_rules! sql_enum {
+	(@name $variant:ident) => {
+		stringify!($variant)
+	};
+	(@name $variant:ident $sql_name:literal) => {
+		$sql_name
+	};
+	($name:ident { $( $(#[sql_name = $sql_name:literal])? $variant:ident ),+ $(,)? }) => {
+		impl $name {
+			/// Name stored in the database
+			pub const fn sql_name(&self) -> &'static str {
+				match self {
+					$( Self::$variant => $crate::sql_enum!(@name $variant $($sql_name)?), )+
+				}
+			}
+
+			pub fn from_sql_name(name: &str) -> ::std::option::Option<Self> {
+				$(
+					if name == $crate::sql_enum!(@name $variant $($sql_name)?) {
+						return ::std::option::Option::Some(Self::$variant);
+					}
+				)+
+				::std::option::Option::None
+			}
+		}
+
+		impl ::sea_orm::sea_query::Iden for $name {
+			fn unquoted(&self, s: &mut dyn ::std::fmt::Write) {
+				s.write_str(self.sql_name()).unwrap();
+			}
+		}
+
+		impl ::std::convert::From<$name> for ::sea_orm::Value {
+			fn from(value: $name) -> Self {
+				::sea_orm::Value::String(::std::option::Option::Some(::std::boxed::Box::new(
+					value.sql_name().to_string(),
+				)))
+			}
+		}
+
+		impl ::sea_orm::TryGetable for $name {
+			fn try_get_by<I: ::sea_orm::ColIdx>(
+				res: &::sea_orm::QueryResult,
+				idx: I,
+			) -> ::std::result::Result<Self, ::sea_orm::TryGetError> {
+				let name = <::std::string::String as ::sea_orm::TryGetable>::try_get_by(res, idx)?;
+				Self::from_sql_name(&name).ok_or_else(|| {
+					::sea_orm::TryGetError::DbErr(::sea_orm::DbErr::Type(format!(
+						"unknown {} value: {name}",
+						stringify!($name)
+					)))
+				})
+			}
+		}
+
+		impl ::sea_orm::sea_query::ValueType for $name {
+			fn try_from(
+				v: ::sea_orm::Value,
+			) -> ::std::result::Result<Self, ::sea_orm::sea_query::ValueTypeErr> {
+				match v {
+					::sea_orm::Value::String(::std::option::Option::Some(name)) => {
+						Self::from_sql_name(&name).ok_or(::sea_orm::sea_query::ValueTypeErr)
+					}
+					_ => ::std::result::Result::Err(::sea_orm::sea_query::ValueTypeErr),
+				}
+			}
+
+			fn type_name() -> ::std::string::String {
+				stringify!($name).to_string()
+			}
+
+			fn array_type() -> ::sea_orm::sea_query::ArrayType {
+				::sea_orm::sea_query::ArrayType::String
+			}
+
+			fn column_type() -> ::sea_orm::sea_query::ColumnType {
+				::sea_orm::sea_query::ColumnType::String(::sea_orm::sea_query::StringLen::None)
+			}
+		}
+
+		impl ::sea_orm::sea_query::Nullable for $name {
+			fn null() -> ::sea_orm::Value {
+				::sea_orm::Value::String(::std::option::Option::None)
+			}
+		}
+	};
+}
@@ -0,0 +1,434 @@
+//! Transactional outbox: events are inserted with the business rows in one transaction and
+//! published afterwards by an [`OutboxRelay`], so a commit never loses or invents an event.
+
+use crate::error::DBErr;
+use crate::sql_enum;
+use base_infra::map_err;
+use base_infra::result::AppResult;
+use base_infra::types::task::TaskGroup;
+use sea_orm::sea_query::{Expr, Index};
+use sea_orm::{
+	ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+	PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Schema,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot;
+use tokio::sync::oneshot::error::TryRecvError;
+use tracing::{debug, info, warn};
+
+pub use outbox_event::Model as OutboxEvent;
+
+const STATUS_INDEX: &str = "idx_outbox_events_status_id";
+
+/// Delivery state of an outbox row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxStatus {
+	Pending,
+	Published,
+	/// Gave up after [`OutboxRelayConfig::max_attempts`] failed publishes
+	Dead,
+}
+
+sql_enum!(OutboxStatus {
+	#[sql_name = "pending"]
+	Pending,
+	#[sql_name = "published"]
+	Published,
+	#[sql_name = "dead"]
+	Dead,
+});
+
+pub mod outbox_event {
+	use super::OutboxStatus;
+	use sea_orm::entity::prelude::*;
+
+	#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+	#[sea_orm(table_name = "outbox_events")]
+	pub struct Model {
+		#[sea_orm(primary_key)]
+		pub id: i64,
+		pub topic: String,
+		/// JSON document, see [`Model::payload_json`]
+		#[sea_orm(column_type = "Text")]
+		pub payload: String,
+		pub status: OutboxStatus,
+		pub attempts: i32,
+		#[sea_orm(column_type = "Text", nullable)]
+		pub last_error: Option<String>,
+		/// Unix millis
+		pub created_at: i64,
+		pub published_at: Option<i64>,
+	}
+
+	#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+	pub enum Relation {}
+
+	impl ActiveModelBehavior for ActiveModel {}
+}
+
+use outbox_event::{Column, Entity as OutboxEvents};
+
+impl OutboxEvent {
+	pub fn payload_json(&self) -> AppResult<serde_json::Value> {
+		serde_json::from_str(&self.payload).map_err(map_err!(&DBErr::OutboxQueryErr, self.id))
+	}
+}
+
+/// Creates `outbox_events` and its `(status, id)` index when missing, meant to run from the
+/// application's migrations
+pub async fn create_outbox_table(db: &impl ConnectionTrait) -> AppResult<()> {
+	let backend = db.get_database_backend();
+	let mut table = Schema::new(backend).create_table_from_entity(OutboxEvents);
+	table.if_not_exists();
+	let index = Index::create()
+		.if_not_exists()
+		.name(STATUS_INDEX)
+		.table(OutboxEvents)
+		.col(Column::Status)
+		.col(Column::Id)
+		.to_owned();
+
+	db.execute(backend.build(&table))
+		.await
+		.map_err(map_err!(&DBErr::RunMigrationsErr, "create outbox_events"))?;
+	db.execute(backend.build(&index))
+		.await
+		.map_err(map_err!(&DBErr::RunMigrationsErr, STATUS_INDEX))?;
+	Ok(())
+}
+
+/// Inserts a pending event and returns its id. Pass the transaction that writes the business
+/// rows, so the event commits or rolls back with them.
+pub async fn enqueue<C: ConnectionTrait>(
+	txn: &C,
+	topic: &str,
+	payload: &serde_json::Value,
+) -> AppResult<i64> {
+	let event = outbox_event::ActiveModel {
+		topic: ActiveValue::Set(topic.to_string()),
+		payload: ActiveValue::Set(payload.to_string()),
+		status: ActiveValue::Set(OutboxStatus::Pending),
+		attempts: ActiveValue::Set(0),
+		last_error: ActiveValue::Set(None),
+		created_at: ActiveValue::Set(now_millis()),
+		published_at: ActiveValue::Set(None),
+		..Default::default()
+	};
+	let event = event
+		.insert(txn)
+		.await
+		.map_err(map_err!(&DBErr::OutboxEnqueueErr, topic))?;
+	Ok(event.id)
+}
+
+/// Marks a pending event published. `false` when it was no longer pending, so marking a
+/// re-delivered event again is a no-op.
+pub async fn mark_published(db: &impl ConnectionTrait, id: i64) -> AppResult<bool> {
+	let res = OutboxEvents::update_many()
+		.col_expr(Column::Status, Expr::value(OutboxStatus::Published))
+		.col_expr(Column::PublishedAt, Expr::value(now_millis()))
+		.filter(Column::Id.eq(id))
+		.filter(Column::Status.eq(OutboxStatus::Pending))
+		.exec(db)
+		.await
+		.map_err(map_err!(&DBErr::OutboxUpdateErr, id))?;
+	Ok(res.rows_affected == 1)
+}
+
+/// Number of events still waiting to be published
+pub async fn backlog(db: &impl ConnectionTrait) -> AppResult<u64> {
+	OutboxEvents::find()
+		.filter(Column::Status.eq(OutboxStatus::Pending))
+		.count(db)
+		.await
+		.map_err(map_err!(&DBErr::OutboxQueryErr, "backlog"))
+}
+
+/// Sink the relay hands events to, e.g. a message broker producer
+#[async_trait::async_trait]
+pub trait Publisher: Send + Sync + 'static {
+	/// Delivery is at-least-once: an event may be published again if the relay stops between
+	/// publishing and marking it, so consumers dedupe on [`OutboxEvent::id`]
+	async fn publish(&self, event: &OutboxEvent) -> AppResult<()>;
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxRelayConfig {
+	/// Pause between polls once the backlog is drained
+	pub poll_interval_ms: u64,
+	/// Max rows read per poll
+	pub batch_size: u64,
+	/// Failed publishes before an event is marked [`OutboxStatus::Dead`]
+	pub max_attempts: i32,
+}
+
+impl Default for OutboxRelayConfig {
+	fn default() -> Self {
+		Self {
+			poll_interval_ms: 500,
+			batch_size: 100,
+			max_attempts: 5,
+		}
+	}
+}
+
+/// Counters of an [`OutboxRelay`], refreshed after every poll
+#[derive(Debug, Default)]
+pub struct OutboxStats {
+	backlog: AtomicU64,
+	published: AtomicU64,
+	dead: AtomicU64,
+}
+
+impl OutboxStats {
+	pub fn backlog(&self) -> u64 {
+		self.backlog.load(Ordering::Relaxed)
+	}
+
+	pub fn published(&self) -> u64 {
+		self.published.load(Ordering::Relaxed)
+	}
+
+	pub fn dead(&self) -> u64 {
+		self.dead.load(Ordering::Relaxed)
+	}
+}
+
+/// Polls pending events in id order and publishes them.
+///
+/// A failed publish stops the poll so later events never overtake it; the event is retried on
+/// the next poll until it succeeds or runs out of attempts.
+pub struct OutboxRelay<P: Publisher> {
+	inner: Arc<RelayInner<P>>,
+}
+
+struct RelayInner<P> {
+	db: DatabaseConnection,
+	publisher: P,
+	config: OutboxRelayConfig,
+	stats: Arc<OutboxStats>,
+}
+
+impl<P: Publisher> OutboxRelay<P> {
+	pub fn new(db: DatabaseConnection, publisher: P, config: OutboxRelayConfig) -> Self {
+		let inner = RelayInner {
+			db,
+			publisher,
+			config,
+			stats: Arc::new(OutboxStats::default()),
+		};
+		Self {
+			inner: Arc::new(inner),
+		}
+	}
+
+	pub fn stats(&self) -> &Arc<OutboxStats> {
+		&self.inner.stats
+	}
+
+	/// Runs a single poll and returns the number of events published
+	pub async fn relay_once(&self) -> AppResult<usize> {
+		self.inner.relay_once().await
+	}
+
+	/// Starts polling in the background until the returned handle is stopped or dropped
+	pub fn start(self) -> OutboxRelayHandle {
+		let (shutdown_tx, mut shutdown_rx) = oneshot::channel();
+		let stats = Arc::clone(&self.inner.stats);
+		let inner = self.inner;
+		let interval = Duration::from_millis(inner.config.poll_interval_ms);
+		let mut task = TaskGroup::new();
+		task.spawn_with_name("outbox-relay", async move {
+			loop {
+				let drained = match inner.relay_once().await {
+					Ok(published) => published < inner.config.batch_size as usize,
+					Err(e) => {
+						warn!("outbox relay poll failed: {e}");
+						true
+					}
+				};
+				if drained {
+					tokio::select! {
+						_ = &mut shutdown_rx => break,
+						_ = tokio::time::sleep(interval) => {}
+					}
+				} else if !matches!(shutdown_rx.try_recv(), Err(TryRecvError::Empty)) {
+					break;
+				}
+			}
+			Ok(())
+		});
+		info!("outbox relay started, poll interval: {interval:?}");
+		OutboxRelayHandle {
+			shutdown_tx,
+			task,
+			stats,
+		}
+	}
+
+	/// Registers `outbox_backlog`, `outbox_published_total` and `outbox_dead_total` gauges, read
+	/// from [`OutboxStats`] on every scrape
+	#[cfg(feature = "metrics")]
+	pub fn to_prometheus_metrics(&self, registry: &prometheus::Registry) -> AppResult<()> {
+		let collector = metrics::StatsCollector::new(self.inner.stats.clone())?;
+		registry
+			.register(Box::new(collector))
+			.map_err(map_err!(&DBErr::MetricsRegisterErr, "outbox"))
+	}
+}
+
+/// Background poll of a started [`OutboxRelay`], dropping it ends the poll after the one in
+/// progress
+pub struct OutboxRelayHandle {
+	shutdown_tx: oneshot::Sender<()>,
+	task: TaskGroup<()>,
+	stats: Arc<OutboxStats>,
+}
+
+impl OutboxRelayHandle {
+	pub fn stats(&self) -> &Arc<OutboxStats> {
+		&self.stats
+	}
+
+	pub fn is_running(&self) -> bool {
+		!self.shutdown_tx.is_closed()
+	}
+
+	/// Stops the background poll, waiting for the one in progress to finish
+	pub async fn stop(self) -> AppResult<()> {
+		let _ = self.shutdown_tx.send(());
+		self.task.join_all_ok().await?;
+		info!("outbox relay stopped");
+		Ok(())
+	}
+}
+
+impl<P: Publisher> RelayInner<P> {
+	async fn relay_once(&self) -> AppResult<usize> {
+		let events = OutboxEvents::find()
+			.filter(Column::Status.eq(OutboxStatus::Pending))
+			.order_by_asc(Column::Id)
+			.limit(self.config.batch_size)
+			.all(&self.db)
+			.await
+			.map_err(map_err!(&DBErr::OutboxQueryErr, "pending"))?;
+
+		let mut published = 0;
+		for event in &events {
+			match self.publisher.publish(event).await {
+				Ok(()) => {
+					if mark_published(&self.db, event.id).await? {
+						self.stats.published.fetch_add(1, Ordering::Relaxed);
+					}
+					published += 1;
+				}
+				Err(e) => {
+					if !self.record_failure(event, &e.to_string()).await? {
+						break;
+					}
+				}
+			}
+		}
+
+		let pending = backlog(&self.db).await?;
+		self.stats.backlog.store(pending, Ordering::Relaxed);
+		if published > 0 {
+			debug!("outbox relay published {published} events, backlog: {pending}");
+		}
+		Ok(published)
+	}
+
+	/// Bumps the attempts of a failed event, `true` when that made it dead
+	async fn record_failure(&self, event: &OutboxEvent, error: &str) -> AppResult<bool> {
+		let attempts = event.attempts + 1;
+		let dead = attempts >= self.config.max_attempts;
+		let status = if dead {
+			OutboxStatus::Dead
+		} else {
+			OutboxStatus::Pending
+		};
+		OutboxEvents::update_many()
+			.col_expr(Column::Attempts, Expr::value(attempts))
+			.col_expr(Column::LastError, Expr::value(error))
+			.col_expr(Column::Status, Expr::value(status))
+			.filter(Column::Id.eq(event.id))
+			.filter(Column::Status.eq(OutboxStatus::Pending))
+			.exec(&self.db)
+			.await
+			.map_err(map_err!(&DBErr::OutboxUpdateErr, event.id))?;
+
+		if dead {
+			self.stats.dead.fetch_add(1, Ordering::Relaxed);
+			warn!(
+				"outbox event {} ({}) is dead after {attempts} attempts: {error}",
+				event.id, event.topic
+			);
+		} else {
+			debug!(
+				"outbox event {} publish failed, attempt {attempts}: {error}",
+				event.id
+			);
+		}
+		Ok(dead)
+	}
+}
+
+fn now_millis() -> i64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |d| d.as_millis() as i64)
+}
+
+#[cfg(feature = "metrics")]
+mod metrics {
+	use super::OutboxStats;
+	use crate::error::DBErr;
+	use base_infra::map_err;
+	use base_infra::result::AppResult;
+	use prometheus::core::{Collector, Desc};
+	use prometheus::{IntGauge, proto};
+	use std::sync::Arc;
+
+	pub(super) struct StatsCollector {
+		stats: Arc<OutboxStats>,
+		backlog: IntGauge,
+		published: IntGauge,
+		dead: IntGauge,
+	}
+
+	impl StatsCollector {
+		pub(super) fn new(stats: Arc<OutboxStats>) -> AppResult<Self> {
+			let gauge = |name: &str, help: &str| {
+				IntGauge::new(name, help).map_err(map_err!(&DBErr::MetricsRegisterErr, name))
+			};
+			Ok(Self {
+				stats,
+				backlog: gauge("outbox_backlog", "Outbox events waiting to be published")?,
+				published: gauge("outbox_published_total", "Outbox events published")?,
+				dead: gauge("outbox_dead_total", "Outbox events given up on")?,
+			})
+		}
+
+		fn gauges(&self) -> [&IntGauge; 3] {
+			[&self.backlog, &self.published, &self.dead]
+		}
+	}
+
+	impl Collector for StatsCollector {
+		fn desc(&self) -> Vec<&Desc> {
+			self.gauges().into_iter().flat_map(|g| g.desc()).collect()
+		}
+
+		fn collect(&self) -> Vec<proto::MetricFamily> {
+			self.backlog.set(self.stats.backlog() as i64);
+			self.published.set(self.stats.published() as i64);
+			self.dead.set(self.stats.dead() as i64);
+			self.gauges()
+				.into_iter()
+				.flat_map(|g| g.collect())
+				.collect()
+		}
+	}
+}
@@ -0,0 +1,263 @@
+//! AES-256-GCM encrypted columns (feature `encrypted-columns`).
+//!
+//! Values are stored as `base64(nonce || ciphertext || tag)` in a string column, the plaintext
+//! being the JSON encoding of `T`.
+
+use crate::error::DBErr;
+use base_infra::result::AppResult;
+use base_infra::{app_err, map_err};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use sea_orm::{ColIdx, DbErr, QueryResult, TryGetError, TryGetable, Value};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::fmt::{Debug, Formatter};
+use std::sync::OnceLock;
+
+/// Env var holding the key as 64 hex chars
+pub const DB_ENCRYPTION_KEY: &str = "DB_ENCRYPTION_KEY";
+
+pub type EncryptionKey = [u8; 32];
+
+/// Reads the key from [`DB_ENCRYPTION_KEY`]
+pub fn env_encryption_key() -> AppResult<EncryptionKey> {
+	let hex_key = std::env::var(DB_ENCRYPTION_KEY)
+		.map_err(map_err!(&DBErr::EncryptionKey, DB_ENCRYPTION_KEY))?;
+	let bytes = hex::decode(hex_key.trim()).map_err(map_err!(&DBErr::EncryptionKey))?;
+	bytes.try_into().map_err(|bytes: Vec<u8>| {
+		app_err!(
+			&DBErr::EncryptionKey,
+			format!("expected 32 bytes, got {}", bytes.len())
+		)
+	})
+}
+
+fn cipher(key: &EncryptionKey) -> LessSafeKey {
+	LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).expect("AES-256 key is 32 bytes"))
+}
+
+struct ColumnKey {
+	key: EncryptionKey,
+	cipher: LessSafeKey,
+}
+
+static COLUMN_KEY: OnceLock<ColumnKey> = OnceLock::new();
+
+/// Sets the process column key used by `TryGetable` and [`EncryptedColumn::with_env_key`], once
+/// at startup. Setting the same key again is a no-op, a different one is an error.
+pub fn set_encryption_key(key: EncryptionKey) -> AppResult<()> {
+	let stored = COLUMN_KEY.get_or_init(|| ColumnKey {
+		key,
+		cipher: cipher(&key),
+	});
+	if stored.key != key {
+		return Err(app_err!(
+			&DBErr::EncryptionKey,
+			"a different column key is already set"
+		));
+	}
+	Ok(())
+}
+
+/// The process column key, read from [`DB_ENCRYPTION_KEY`] on first use unless
+/// [`set_encryption_key`] ran before
+fn column_key() -> AppResult<&'static ColumnKey> {
+	if let Some(key) = COLUMN_KEY.get() {
+		return Ok(key);
+	}
+	let key = env_encryption_key()?;
+	Ok(COLUMN_KEY.get_or_init(|| ColumnKey {
+		key,
+		cipher: cipher(&key),
+	}))
+}
+
+/// A `T` encrypted at rest. The ciphertext is computed once, on creation.
+///
+/// Reading through `TryGetable` decrypts with the process column key, see
+/// [`set_encryption_key`], use [`Self::decrypt`] to read with an explicit key.
+#[derive(Clone)]
+pub struct EncryptedColumn<T> {
+	plaintext: T,
+	ciphertext: String,
+}
+
+impl<T: Serialize + DeserializeOwned> EncryptedColumn<T> {
+	pub fn new(plaintext: T, key: &EncryptionKey) -> AppResult<Self> {
+		Self::seal(plaintext, &cipher(key))
+	}
+
+	fn seal(plaintext: T, cipher: &LessSafeKey) -> AppResult<Self> {
+		let mut in_out = serde_json::to_vec(&plaintext).map_err(map_err!(&DBErr::EncryptColumn))?;
+		let nonce_bytes: [u8; NONCE_LEN] = rand::random();
+		cipher
+			.seal_in_place_append_tag(
+				Nonce::assume_unique_for_key(nonce_bytes),
+				Aad::empty(),
+				&mut in_out,
+			)
+			.map_err(map_err!(&DBErr::EncryptColumn))?;
+
+		let mut stored = nonce_bytes.to_vec();
+		stored.extend(in_out);
+		Ok(Self {
+			plaintext,
+			ciphertext: STANDARD.encode(stored),
+		})
+	}
+
+	/// [`Self::new`] with the process column key, see [`set_encryption_key`]
+	pub fn with_env_key(plaintext: T) -> AppResult<Self> {
+		Self::seal(plaintext, &column_key()?.cipher)
+	}
+
+	/// Decrypts a stored value
+	pub fn decrypt(ciphertext: &str, key: &EncryptionKey) -> AppResult<Self> {
+		Self::open(ciphertext, &cipher(key))
+	}
+
+	fn open(ciphertext: &str, cipher: &LessSafeKey) -> AppResult<Self> {
+		let mut stored = STANDARD
+			.decode(ciphertext)
+			.map_err(map_err!(&DBErr::DecryptColumn))?;
+		if stored.len() < NONCE_LEN {
+			return Err(app_err!(
+				&DBErr::DecryptColumn,
+				"ciphertext shorter than the nonce"
+			));
+		}
+		let (nonce, sealed) = stored.split_at_mut(NONCE_LEN);
+		let nonce =
+			Nonce::try_assume_unique_for_key(nonce).map_err(map_err!(&DBErr::DecryptColumn))?;
+		let json = cipher
+			.open_in_place(nonce, Aad::empty(), sealed)
+			.map_err(map_err!(
+				&DBErr::DecryptColumn,
+				"wrong key or tampered value"
+			))?;
+		let plaintext = serde_json::from_slice(json).map_err(map_err!(&DBErr::DecryptColumn))?;
+		Ok(Self {
+			plaintext,
+			ciphertext: ciphertext.to_string(),
+		})
+	}
+}
+
+impl<T> EncryptedColumn<T> {
+	pub fn plaintext(&self) -> &T {
+		&self.plaintext
+	}
+
+	pub fn into_inner(self) -> T {
+		self.plaintext
+	}
+
+	/// The stored form, `base64(nonce || ciphertext || tag)`
+	pub fn ciphertext(&self) -> &str {
+		&self.ciphertext
+	}
+}
+
+/// Never prints the plaintext
+impl<T> Debug for EncryptedColumn<T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_tuple("EncryptedColumn")
+			.field(&self.ciphertext)
+			.finish()
+	}
+}
+
+impl<T> From<EncryptedColumn<T>> for Value {
+	fn from(value: EncryptedColumn<T>) -> Self {
+		Value::String(Some(Box::new(value.ciphertext)))
+	}
+}
+
+impl<T: Serialize + DeserializeOwned> TryGetable for EncryptedColumn<T> {
+	fn try_get_by<I: ColIdx>(res: &QueryResult, idx: I) -> Result<Self, TryGetError> {
+		let ciphertext = String::try_get_by(res, idx)?;
+		column_key()
+			.and_then(|key| Self::open(&ciphertext, &key.cipher))
+			.map_err(|e| TryGetError::DbErr(DbErr::Type(e.to_string())))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sea_orm::{ConnectionTrait, Database, DbBackend, Statement};
+	use serde::Deserialize;
+
+	const KEY_HEX: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+	#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+	struct Contact {
+		email: String,
+		phone: Option<String>,
+	}
+
+	fn key() -> EncryptionKey {
+		hex::decode(KEY_HEX).unwrap().try_into().unwrap()
+	}
+
+	#[test]
+	fn test_round_trip() {
+		let contact = Contact {
+			email: "alice@example.com".to_string(),
+			phone: None,
+		};
+		let encrypted = EncryptedColumn::new(contact.clone(), &key()).unwrap();
+		assert!(!encrypted.ciphertext().contains("alice"));
+		assert!(!format!("{encrypted:?}").contains("alice"));
+
+		let decrypted = EncryptedColumn::<Contact>::decrypt(encrypted.ciphertext(), &key()).unwrap();
+		assert_eq!(decrypted.plaintext(), &contact);
+
+		// fresh nonce every time
+		let again = EncryptedColumn::new(contact, &key()).unwrap();
+		assert_ne!(again.ciphertext(), encrypted.ciphertext());
+
+		let mut wrong_key = key();
+		wrong_key[0] ^= 1;
+		assert!(EncryptedColumn::<Contact>::decrypt(encrypted.ciphertext(), &wrong_key).is_err());
+		assert!(EncryptedColumn::<Contact>::decrypt("AAAA", &key()).is_err());
+	}
+
+	#[tokio::test]
+	async fn test_sqlite_column() {
+		set_encryption_key(key()).unwrap();
+		set_encryption_key(key()).unwrap();
+		let mut other = key();
+		other[0] ^= 1;
+		assert!(set_encryption_key(other).is_err());
+
+		let db = Database::connect("sqlite::memory:").await.unwrap();
+		db.execute_unprepared("CREATE TABLE users (id INTEGER PRIMARY KEY, ssn TEXT NOT NULL)")
+			.await
+			.unwrap();
+		let ssn = EncryptedColumn::with_env_key("123-45-6789".to_string()).unwrap();
+		db.execute(Statement::from_sql_and_values(
+			DbBackend::Sqlite,
+			"INSERT INTO users (id, ssn) VALUES ($1, $2)",
+			[1.into(), ssn.into()],
+		))
+		.await
+		.unwrap();
+
+		let select = Statement::from_string(DbBackend::Sqlite, "SELECT ssn FROM users");
+		let row = db.query_one(select).await.unwrap().unwrap();
+		let stored: String = row.try_get("", "ssn").unwrap();
+		assert!(!stored.contains("123-45-6789"));
+		assert!(
+			!STANDARD
+				.decode(&stored)
+				.unwrap()
+				.windows(11)
+				.any(|w| w == b"123-45-6789")
+		);
+
+		let ssn: EncryptedColumn<String> = row.try_get("", "ssn").unwrap();
+		assert_eq!(ssn.into_inner(), "123-45-6789");
+	}
+}
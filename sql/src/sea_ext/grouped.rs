@@ -0,0 +1,189 @@
+use crate::error::DBErr;
+use base_infra::map_err;
+use base_infra::result::AppResult;
+use sea_orm::sea_query::{Alias, Asterisk, Expr, Func, SimpleExpr};
+use sea_orm::{
+	ColumnTrait, ColumnType, Condition, ConnectionTrait, EntityTrait, Order, QueryFilter, QueryOrder,
+	QueryResult, QuerySelect, QueryTrait, Value,
+};
+
+const VALUE_ALIAS: &str = "value";
+const COUNT_ALIAS: &str = "count";
+
+/// One row of `SELECT col, COUNT(*) .. GROUP BY col`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupedCount {
+	pub value: Value,
+	pub count: i64,
+}
+
+/// Most frequent values of `col`, highest count first, ties ordered by value
+pub async fn grouped_counts<E, C>(
+	db: &impl ConnectionTrait,
+	col: C,
+	limit: u64,
+) -> AppResult<Vec<GroupedCount>>
+where
+	E: EntityTrait,
+	C: ColumnTrait,
+{
+	grouped_counts_filtered::<E, C>(db, col, Condition::all(), limit).await
+}
+
+/// [`grouped_counts`] over the rows matching `cond`
+pub async fn grouped_counts_filtered<E, C>(
+	db: &impl ConnectionTrait,
+	col: C,
+	cond: Condition,
+	limit: u64,
+) -> AppResult<Vec<GroupedCount>>
+where
+	E: EntityTrait,
+	C: ColumnTrait,
+{
+	let count: SimpleExpr = Func::count(Expr::col(Asterisk)).into();
+	let stmt = E::find()
+		.select_only()
+		.column_as(col, VALUE_ALIAS)
+		.expr_as(count, COUNT_ALIAS)
+		.filter(cond)
+		.group_by(col)
+		.order_by(Expr::col(Alias::new(COUNT_ALIAS)), Order::Desc)
+		.order_by(Expr::col(Alias::new(VALUE_ALIAS)), Order::Asc)
+		.limit(limit)
+		.build(db.get_database_backend());
+
+	let rows = db
+		.query_all(stmt)
+		.await
+		.map_err(map_err!(&DBErr::GroupedCountsErr))?;
+	let column_type = col.def().get_column_type().clone();
+	rows.iter()
+		.map(|row| {
+			Ok(GroupedCount {
+				value: read_value(row, &column_type)?,
+				count: row
+					.try_get("", COUNT_ALIAS)
+					.map_err(map_err!(&DBErr::GroupedCountsErr))?,
+			})
+		})
+		.collect()
+}
+
+/// Decodes the grouped column by its declared type, unknown types are read as text
+fn read_value(row: &QueryResult, column_type: &ColumnType) -> AppResult<Value> {
+	fn get<T>(row: &QueryResult) -> AppResult<Value>
+	where
+		Option<T>: sea_orm::TryGetable + Into<Value>,
+	{
+		row.try_get::<Option<T>>("", VALUE_ALIAS)
+			.map(Into::into)
+			.map_err(map_err!(&DBErr::GroupedCountsErr))
+	}
+
+	match column_type {
+		ColumnType::Boolean => get::<bool>(row),
+		ColumnType::TinyInteger => get::<i8>(row),
+		ColumnType::SmallInteger => get::<i16>(row),
+		ColumnType::Integer => get::<i32>(row),
+		ColumnType::BigInteger => get::<i64>(row),
+		ColumnType::TinyUnsigned => get::<u8>(row),
+		ColumnType::SmallUnsigned => get::<u16>(row),
+		ColumnType::Unsigned => get::<u32>(row),
+		ColumnType::BigUnsigned => get::<u64>(row),
+		ColumnType::Float => get::<f32>(row),
+		ColumnType::Double => get::<f64>(row),
+		ColumnType::Binary(_) | ColumnType::VarBinary(_) | ColumnType::Blob => get::<Vec<u8>>(row),
+		_ => get::<String>(row),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sea_orm::{Database, DatabaseConnection, DbBackend, Schema, Set};
+
+	mod trade {
+		use sea_orm::entity::prelude::*;
+
+		#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+		#[sea_orm(table_name = "trade")]
+		pub struct Model {
+			#[sea_orm(primary_key)]
+			pub id: i32,
+			pub category: String,
+			pub size: i32,
+		}
+
+		#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+		pub enum Relation {}
+
+		impl ActiveModelBehavior for ActiveModel {}
+	}
+
+	async fn setup() -> DatabaseConnection {
+		let db = Database::connect("sqlite::memory:").await.unwrap();
+		let stmt = Schema::new(DbBackend::Sqlite).create_table_from_entity(trade::Entity);
+		db.execute(db.get_database_backend().build(&stmt))
+			.await
+			.unwrap();
+
+		let categories = ["A", "B", "A", "C", "B", "B", "A", "C", "B", "B"];
+		let rows = categories
+			.iter()
+			.enumerate()
+			.map(|(i, category)| trade::ActiveModel {
+				id: Set(i as i32 + 1),
+				category: Set(category.to_string()),
+				size: Set(i as i32 % 2),
+			});
+		trade::Entity::insert_many(rows).exec(&db).await.unwrap();
+		db
+	}
+
+	#[tokio::test]
+	async fn test_grouped_counts() {
+		let db = setup().await;
+		let counts = grouped_counts::<trade::Entity, _>(&db, trade::Column::Category, 2)
+			.await
+			.unwrap();
+		assert_eq!(
+			counts,
+			[
+				GroupedCount {
+					value: Value::from("B"),
+					count: 5
+				},
+				GroupedCount {
+					value: Value::from("A"),
+					count: 3
+				},
+			]
+		);
+
+		let sizes = grouped_counts::<trade::Entity, _>(&db, trade::Column::Size, 10)
+			.await
+			.unwrap();
+		assert_eq!(sizes.len(), 2);
+		assert_eq!(sizes[0].value, Value::Int(Some(0)));
+	}
+
+	#[tokio::test]
+	async fn test_grouped_counts_filtered() {
+		let db = setup().await;
+		let cond = Condition::all().add(trade::Column::Id.lte(5));
+		let counts =
+			grouped_counts_filtered::<trade::Entity, _>(&db, trade::Column::Category, cond, 10)
+				.await
+				.unwrap();
+		let counts: Vec<_> = counts.into_iter().map(|c| (c.value, c.count)).collect();
+		assert_eq!(
+			counts,
+			[
+				(Value::from("A"), 2),
+				(Value::from("B"), 2),
+				(Value::from("C"), 1),
+			]
+		);
+	}
+}
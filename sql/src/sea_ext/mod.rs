@@ -2,6 +2,11 @@
 //! This module provides custom implementations for uint types (U64, U128, U256)
 //! to enable seamless database operations without string conversions.
 
+#[cfg(feature = "encrypted-columns")]
+pub mod encrypted;
+pub mod grouped;
 pub mod page;
 pub mod pgsql;
 pub mod uint_types;
+#[cfg(feature = "pgsql")]
+pub mod window;
@@ -1,3 +1,10 @@
+use crate::error::DBErr;
+use base_infra::result::AppResult;
+use base_infra::{err, map_err};
+use sea_orm::{
+	ConnectionTrait, DbBackend, EntityTrait, PaginatorTrait, QuerySelect, QueryTrait, Select,
+	Statement,
+};
 use serde::{Deserialize, Serialize};
 
 pub trait PageSizeTrait {
@@ -5,27 +12,43 @@ pub trait PageSizeTrait {
 	fn page_size(&self) -> u64;
 }
 
+/// How [`paginate`] reports the total
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum PageMode {
+	/// Exact `COUNT(*)`
+	#[default]
+	WithTotal,
+	/// No count, `has_next` from fetching one row past the page
+	NoTotal,
+	/// Planner estimate from `pg_class.reltuples` plus `has_next`, for an unfiltered query of
+	/// one Postgres table. Any other query falls back to [`PageMode::NoTotal`].
+	EstimatedTotal,
+}
+
+/// `total` / `total_pages` are left out of the JSON when unknown and `has_next` when not
+/// computed, so a [`PageMode::WithTotal`] page serializes as before.
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct PageQuery {
 	pub page: u64,
 	pub page_size: u64,
-	pub total: u64,
-	pub total_pages: u64,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub total: Option<u64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub total_pages: Option<u64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub has_next: Option<bool>,
 }
 
 impl PageQuery {
 	pub fn new(page: u64, page_size: u64, total: u64) -> Self {
-		let total_pages = if total % page_size == 0 {
-			total / page_size
-		} else {
-			total / page_size + 1
-		};
 		Self {
 			page,
 			page_size,
-			total,
-			total_pages,
+			total: None,
+			total_pages: None,
+			has_next: None,
 		}
+		.with_total(total)
 	}
 
 	pub fn with_total(self, total: u64) -> Self {
@@ -36,21 +59,36 @@ impl PageQuery {
 		};
 
 		Self {
-			total,
-			total_pages,
+			total: Some(total),
+			total_pages: Some(total_pages),
+			..self
+		}
+	}
+
+	/// Unknown total, see [`PageMode::NoTotal`]
+	pub fn with_has_next(self, has_next: bool) -> Self {
+		Self {
+			total: None,
+			total_pages: None,
+			has_next: Some(has_next),
 			..self
 		}
 	}
 }
 
+impl PageSizeTrait for PageQuery {
+	fn page(&self) -> u64 {
+		self.page
+	}
+
+	fn page_size(&self) -> u64 {
+		self.page_size
+	}
+}
+
 impl Default for PageQuery {
 	fn default() -> Self {
-		Self {
-			page: 1,
-			page_size: 10,
-			total: 0,
-			total_pages: 0,
-		}
+		Self::new(1, 10, 0)
 	}
 }
 
@@ -74,3 +112,256 @@ impl Default for SqlPageResp<()> {
 		}
 	}
 }
+
+/// `COUNT(*)` over the same filters as `select`
+pub async fn count_matching<E, C>(select: Select<E>, db: &C) -> AppResult<u64>
+where
+	E: EntityTrait,
+	E::Model: Sync,
+	C: ConnectionTrait,
+{
+	select
+		.count(db)
+		.await
+		.map_err(map_err!(&DBErr::PaginatorItemsAndPages))
+}
+
+/// [`paginate`] with [`PageMode::WithTotal`]
+pub async fn paginate_with_count<E, C>(
+	select: Select<E>,
+	req: &impl PageSizeTrait,
+	db: &C,
+) -> AppResult<SqlPageResp<E::Model>>
+where
+	E: EntityTrait,
+	E::Model: Sync,
+	C: ConnectionTrait,
+{
+	paginate(select, req, PageMode::WithTotal, db).await
+}
+
+/// Runs the `limit/offset` page query built from `select`, with the total `mode` asks for,
+/// page starts from 1.
+///
+/// The count and page queries are not atomic, pass a `DatabaseTransaction` as `db` if the count
+/// must match the page exactly.
+pub async fn paginate<E, C>(
+	select: Select<E>,
+	req: &impl PageSizeTrait,
+	mode: PageMode,
+	db: &C,
+) -> AppResult<SqlPageResp<E::Model>>
+where
+	E: EntityTrait,
+	E::Model: Sync,
+	C: ConnectionTrait,
+{
+	let page = PageQuery::new(req.page().max(1), req.page_size().max(1), 0);
+	let Some(offset) = (page.page - 1).checked_mul(page.page_size) else {
+		return err!(
+			&DBErr::PageOutOfRange,
+			format!("page {} of size {}", page.page, page.page_size)
+		);
+	};
+	if mode == PageMode::WithTotal {
+		let total = count_matching(select.clone(), db).await?;
+		let list = select
+			.offset(offset)
+			.limit(page.page_size)
+			.all(db)
+			.await
+			.map_err(map_err!(&DBErr::PaginatorFetchPage))?;
+		return Ok(SqlPageResp::new(list, page.with_total(total)));
+	}
+
+	let estimate = match mode {
+		PageMode::EstimatedTotal => estimated_total(&select, db).await?,
+		_ => None,
+	};
+	let mut list = select
+		.offset(offset)
+		.limit(page.page_size.saturating_add(1))
+		.all(db)
+		.await
+		.map_err(map_err!(&DBErr::PaginatorFetchPage))?;
+	let has_next = list.len() as u64 > page.page_size;
+	list.truncate(page.page_size as usize);
+
+	let page = page.with_has_next(has_next);
+	let page = match estimate {
+		// never below what this page proves exists
+		Some(total) => {
+			let seen = offset.saturating_add(list.len() as u64 + has_next as u64);
+			page.with_total(total.max(seen))
+		}
+		None => page,
+	};
+	Ok(SqlPageResp::new(list, page))
+}
+
+/// `pg_class.reltuples` of the table of `E` when `select` is a plain `E::find()`, `None` on other
+/// backends, for any other query or a never analyzed table
+async fn estimated_total<E, C>(select: &Select<E>, db: &C) -> AppResult<Option<u64>>
+where
+	E: EntityTrait,
+	C: ConnectionTrait,
+{
+	let backend = db.get_database_backend();
+	if backend != DbBackend::Postgres {
+		return Ok(None);
+	}
+	// sea-query keeps the where / join clauses private, compare with the unfiltered query instead
+	let unordered = |select: &Select<E>| {
+		let mut query = select.clone().into_query();
+		query.clear_order_by();
+		backend.build(&query)
+	};
+	if unordered(select) != unordered(&E::find()) {
+		return Ok(None);
+	}
+
+	let entity = E::default();
+	let table = match entity.schema_name() {
+		Some(schema) => format!(
+			"{}.{}",
+			quote_ident(schema),
+			quote_ident(entity.table_name())
+		),
+		None => quote_ident(entity.table_name()),
+	};
+	let stmt = Statement::from_sql_and_values(
+		backend,
+		"SELECT reltuples::bigint AS estimate FROM pg_class WHERE oid = to_regclass($1)",
+		[table.into()],
+	);
+	let row = db
+		.query_one(stmt)
+		.await
+		.map_err(map_err!(&DBErr::PaginatorItemsAndPages))?;
+	let estimate = match row {
+		Some(row) => row
+			.try_get::<Option<i64>>("", "estimate")
+			.map_err(map_err!(&DBErr::PaginatorItemsAndPages))?,
+		None => None,
+	};
+	// -1 until the first VACUUM / ANALYZE
+	Ok(estimate.and_then(|n| u64::try_from(n).ok()))
+}
+
+fn quote_ident(ident: &str) -> String {
+	format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sea_orm::entity::prelude::*;
+	use sea_orm::{Database, DbBackend, QueryFilter, QueryOrder, Schema, Set};
+
+	mod item {
+		use sea_orm::entity::prelude::*;
+
+		#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+		#[sea_orm(table_name = "item")]
+		pub struct Model {
+			#[sea_orm(primary_key)]
+			pub id: i32,
+			pub name: String,
+		}
+
+		#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+		pub enum Relation {}
+
+		impl ActiveModelBehavior for ActiveModel {}
+	}
+
+	async fn setup() -> DatabaseConnection {
+		let db = Database::connect("sqlite::memory:").await.unwrap();
+		let stmt = Schema::new(DbBackend::Sqlite).create_table_from_entity(item::Entity);
+		db.execute(db.get_database_backend().build(&stmt))
+			.await
+			.unwrap();
+
+		let rows = (1..=15).map(|i| item::ActiveModel {
+			id: Set(i),
+			name: Set(format!("item-{i}")),
+		});
+		item::Entity::insert_many(rows).exec(&db).await.unwrap();
+		db
+	}
+
+	#[tokio::test]
+	async fn test_paginate_with_count() {
+		let db = setup().await;
+		let req = PageQuery::new(2, 5, 0);
+		let select = item::Entity::find().order_by_asc(item::Column::Id);
+		let resp = paginate_with_count(select, &req, &db).await.unwrap();
+		assert_eq!(resp.page.total, Some(15));
+		assert_eq!(resp.page.total_pages, Some(3));
+		assert_eq!(resp.page.has_next, None);
+		assert_eq!(resp.list.len(), 5);
+		assert_eq!(resp.list[0].id, 6);
+
+		let filtered = item::Entity::find().filter(item::Column::Id.gt(12));
+		assert_eq!(count_matching(filtered, &db).await.unwrap(), 3);
+	}
+
+	#[tokio::test]
+	async fn test_no_total_parity() {
+		let db = setup().await;
+		for page_size in [1, 4, 5, 15, 16] {
+			for page in 1..=(15 / page_size + 2) {
+				let req = PageQuery::new(page, page_size, 0);
+				let select = || item::Entity::find().order_by_asc(item::Column::Id);
+				let with_total = paginate(select(), &req, PageMode::WithTotal, &db)
+					.await
+					.unwrap();
+				let no_total = paginate(select(), &req, PageMode::NoTotal, &db)
+					.await
+					.unwrap();
+				assert_eq!(no_total.list, with_total.list, "{page}/{page_size}");
+				assert_eq!(no_total.page.total, None);
+				assert_eq!(no_total.page.total_pages, None);
+				let has_next = page * page_size < 15;
+				assert_eq!(no_total.page.has_next, Some(has_next), "{page}/{page_size}");
+			}
+		}
+
+		// sqlite has no estimate
+		let req = PageQuery::new(3, 5, 0);
+		let resp = paginate(item::Entity::find(), &req, PageMode::EstimatedTotal, &db)
+			.await
+			.unwrap();
+		assert_eq!((resp.list.len(), resp.page.total), (5, None));
+		assert_eq!(resp.page.has_next, Some(false));
+	}
+
+	#[tokio::test]
+	async fn test_page_out_of_range() {
+		let db = setup().await;
+		let req = PageQuery::new(u64::MAX, 100, 0);
+		for mode in [PageMode::WithTotal, PageMode::NoTotal] {
+			let err = paginate(item::Entity::find(), &req, mode, &db)
+				.await
+				.unwrap_err();
+			assert!(err.to_string().contains("DBPG03"), "{err}");
+		}
+	}
+
+	#[test]
+	fn test_page_query_json() {
+		let json = serde_json::to_value(PageQuery::new(2, 5, 12)).unwrap();
+		assert_eq!(
+			json,
+			serde_json::json!({ "page": 2, "page_size": 5, "total": 12, "total_pages": 3 })
+		);
+		let page: PageQuery = serde_json::from_value(json).unwrap();
+		assert_eq!(page.total, Some(12));
+
+		let no_total = PageQuery::new(2, 5, 12).with_has_next(true);
+		assert_eq!(
+			serde_json::to_value(no_total).unwrap(),
+			serde_json::json!({ "page": 2, "page_size": 5, "has_next": true })
+		);
+	}
+}
@@ -0,0 +1,231 @@
+//! Window function helpers for analytics queries
+//!
+//! ```ignore
+//! let mut query = Query::select();
+//! query.column(Trade::Id).from(Trade::Table);
+//! WindowFn::row_number(
+//!     PartitionBy::col(Trade::Category),
+//!     Some(OrderBy::asc(Trade::Timestamp)),
+//! )
+//! .select_as(&mut query, "rn");
+//! // SELECT "id", ROW_NUMBER() OVER ( PARTITION BY "category" ORDER BY "timestamp" ASC ) AS "rn" ..
+//! ```
+
+use sea_orm::Order;
+use sea_orm::sea_query::{
+	Alias, ColumnRef, Expr, Func, IntoColumnRef, IntoIden, OverStatement, SelectStatement,
+	SimpleExpr, WindowStatement,
+};
+
+/// `PARTITION BY` columns, empty means the whole result set is one partition
+#[derive(Debug, Clone, Default)]
+pub struct PartitionBy(Vec<ColumnRef>);
+
+impl PartitionBy {
+	pub fn none() -> Self {
+		Self::default()
+	}
+
+	pub fn col<C: IntoColumnRef>(col: C) -> Self {
+		Self(vec![col.into_column_ref()])
+	}
+
+	pub fn cols<I, C>(cols: I) -> Self
+	where
+		I: IntoIterator<Item = C>,
+		C: IntoColumnRef,
+	{
+		Self(
+			cols.into_iter()
+				.map(IntoColumnRef::into_column_ref)
+				.collect(),
+		)
+	}
+}
+
+/// `ORDER BY` inside the window
+#[derive(Debug, Clone)]
+pub struct OrderBy(Vec<(ColumnRef, Order)>);
+
+impl OrderBy {
+	pub fn new<C: IntoColumnRef>(col: C, order: Order) -> Self {
+		Self(vec![(col.into_column_ref(), order)])
+	}
+
+	pub fn asc<C: IntoColumnRef>(col: C) -> Self {
+		Self::new(col, Order::Asc)
+	}
+
+	pub fn desc<C: IntoColumnRef>(col: C) -> Self {
+		Self::new(col, Order::Desc)
+	}
+
+	/// Adds a tie breaker
+	pub fn then<C: IntoColumnRef>(mut self, col: C, order: Order) -> Self {
+		self.0.push((col.into_column_ref(), order));
+		self
+	}
+}
+
+/// A window function call with its `OVER (..)` clause
+#[derive(Debug, Clone)]
+pub struct WindowFn {
+	expr: SimpleExpr,
+	window: WindowStatement,
+}
+
+impl WindowFn {
+	pub fn new(expr: impl Into<SimpleExpr>, partition: PartitionBy, order: Option<OrderBy>) -> Self {
+		let mut window = WindowStatement::new();
+		for col in partition.0 {
+			window.partition_by(col);
+		}
+		for (col, order) in order.map(|o| o.0).unwrap_or_default() {
+			window.order_by(col, order);
+		}
+		Self {
+			expr: expr.into(),
+			window,
+		}
+	}
+
+	/// `ROW_NUMBER()`, 1-based and unique within a partition
+	pub fn row_number(partition: PartitionBy, order: Option<OrderBy>) -> Self {
+		Self::new(Expr::cust("ROW_NUMBER()"), partition, order)
+	}
+
+	/// `RANK()`, peers share a rank and leave gaps
+	pub fn rank(partition: PartitionBy, order: Option<OrderBy>) -> Self {
+		Self::new(Expr::cust("RANK()"), partition, order)
+	}
+
+	/// `LAG(col, offset)`, the value `offset` rows before the current one or `NULL`
+	pub fn lag<C: IntoColumnRef>(
+		col: C,
+		offset: u32,
+		partition: PartitionBy,
+		order: Option<OrderBy>,
+	) -> Self {
+		let lag = Func::cust(Alias::new("LAG"))
+			.arg(Expr::col(col))
+			.arg(offset);
+		Self::new(lag, partition, order)
+	}
+
+	/// `SUM(col)`, a running total when `order` is set, the partition total otherwise
+	pub fn sum_over<C: IntoColumnRef>(
+		col: C,
+		partition: PartitionBy,
+		order: Option<OrderBy>,
+	) -> Self {
+		Self::new(Func::sum(Expr::col(col)), partition, order)
+	}
+
+	/// References the output column `alias`, e.g. to filter or sort it from an outer query
+	pub fn as_col(alias: &str) -> ColumnRef {
+		ColumnRef::Column(Alias::new(alias).into_iden())
+	}
+
+	/// Adds `<fn> OVER (..) AS alias` to the select list
+	pub fn select_as<'a>(
+		self,
+		query: &'a mut SelectStatement,
+		alias: &str,
+	) -> &'a mut SelectStatement {
+		query.expr_window_as(self.expr, self.window, Alias::new(alias))
+	}
+
+	pub fn into_parts(self) -> (SimpleExpr, WindowStatement) {
+		(self.expr, self.window)
+	}
+}
+
+/// Adds window function columns to a [`SelectStatement`]
+///
+/// ```ignore
+/// sea_orm_window_expr!(query,
+///     "rn" => row_number() partition [Trade::Category] order [Trade::Timestamp => Asc],
+///     "prev_price" => lag(Trade::Price, 1) partition [Trade::Category] order [Trade::Timestamp => Asc],
+///     "total" => sum_over(Trade::Amount) partition [],
+/// );
+/// ```
+#[macro_export]
+macro_rules! sea_orm_window_expr {
+	($query:expr, $(
+		$alias:literal => $func:ident ( $($arg:expr),* )
+			partition [$($part:expr),* $(,)?]
+			$(order [$($ord:expr => $dir:ident),+ $(,)?])?
+	),+ $(,)?) => {{
+		use $crate::sea_ext::window::{OrderBy, PartitionBy, WindowFn};
+		use ::sea_orm::sea_query::{ColumnRef, IntoColumnRef, Order};
+
+		let query: &mut ::sea_orm::sea_query::SelectStatement = &mut $query;
+		$(
+			let cols: ::std::vec::Vec<ColumnRef> = ::std::vec![$(IntoColumnRef::into_column_ref($part)),*];
+			let partition = PartitionBy::cols(cols);
+			#[allow(unused_mut)]
+			let mut order: ::std::option::Option<OrderBy> = None;
+			$($(
+				order = Some(match order {
+					Some(o) => o.then($ord, Order::$dir),
+					None => OrderBy::new($ord, Order::$dir),
+				});
+			)+)?
+			WindowFn::$func($($arg,)* partition, order).select_as(query, $alias);
+		)+
+		query
+	}};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sea_orm::DeriveIden;
+	use sea_orm::sea_query::{PostgresQueryBuilder, Query};
+
+	#[derive(DeriveIden)]
+	enum Trade {
+		Table,
+		Id,
+		Category,
+		Timestamp,
+		Price,
+	}
+
+	#[test]
+	fn test_window_sql() {
+		let mut query = Query::select();
+		query.column(Trade::Id).from(Trade::Table);
+		WindowFn::row_number(
+			PartitionBy::col(Trade::Category),
+			Some(OrderBy::asc(Trade::Timestamp)),
+		)
+		.select_as(&mut query, "rn");
+		WindowFn::lag(
+			Trade::Price,
+			1,
+			PartitionBy::none(),
+			Some(OrderBy::asc(Trade::Id)),
+		)
+		.select_as(&mut query, "prev");
+		query.order_by(WindowFn::as_col("rn"), Order::Desc);
+		assert_eq!(
+			query.to_string(PostgresQueryBuilder),
+			r#"SELECT "id", ROW_NUMBER() OVER ( PARTITION BY "category" ORDER BY "timestamp" ASC ) AS "rn", LAG("price", 1) OVER (  ORDER BY "id" ASC ) AS "prev" FROM "trade" ORDER BY "rn" DESC"#
+		);
+	}
+
+	#[test]
+	fn test_window_macro() {
+		let mut query = Query::select();
+		query.from(Trade::Table);
+		crate::sea_orm_window_expr!(query,
+			"rk" => rank() partition [Trade::Category] order [Trade::Price => Desc, Trade::Id => Asc],
+			"total" => sum_over(Trade::Price) partition [],
+		);
+		assert_eq!(
+			query.to_string(PostgresQueryBuilder),
+			r#"SELECT RANK() OVER ( PARTITION BY "category" ORDER BY "price" DESC, "id" ASC ) AS "rk", SUM("price") OVER (  ) AS "total" FROM "trade""#
+		);
+	}
+}
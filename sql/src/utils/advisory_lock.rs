@@ -0,0 +1,164 @@
+//! Cross-replica mutual exclusion on Postgres session advisory locks.
+//!
+//! The lock belongs to the session that took it, so a [`LockGuard`] holds its own pooled
+//! connection until it is dropped. If that connection is lost the server ends the session and
+//! the lock is released with it, so a job that outlives its connection may overlap with the next
+//! holder. Keep guarded jobs shorter than the connection's idle/keepalive limits.
+
+use crate::error::DBErr;
+use base_infra::result::AppResult;
+use base_infra::utils::hash::fnv1a;
+use base_infra::{err, map_err};
+use sea_orm::sqlx::pool::PoolConnection;
+use sea_orm::sqlx::{Postgres, query_scalar};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::warn;
+
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Postgres advisory lock, keyed by an `i64` shared by every replica
+pub struct PgAdvisoryLock;
+
+impl PgAdvisoryLock {
+	/// Stable key for a job name, e.g. `PgAdvisoryLock::key("cron:settle")`
+	pub fn key(name: &str) -> i64 {
+		fnv1a(name.as_bytes()) as i64
+	}
+
+	/// `None` when another session holds the lock
+	pub async fn try_lock(db: &DatabaseConnection, key: i64) -> AppResult<Option<LockGuard>> {
+		let mut conn = acquire(db, key).await?;
+		if try_acquire(&mut conn, key).await? {
+			return Ok(Some(LockGuard::new(conn, key)));
+		}
+		Ok(None)
+	}
+
+	/// Retries [`PgAdvisoryLock::try_lock`] on one connection until `timeout`, `None` if the lock
+	/// was never free
+	pub async fn lock_with_timeout(
+		db: &DatabaseConnection,
+		key: i64,
+		timeout: Duration,
+	) -> AppResult<Option<LockGuard>> {
+		let deadline = Instant::now() + timeout;
+		let mut conn = acquire(db, key).await?;
+		loop {
+			if try_acquire(&mut conn, key).await? {
+				return Ok(Some(LockGuard::new(conn, key)));
+			}
+			let now = Instant::now();
+			if now >= deadline {
+				return Ok(None);
+			}
+			tokio::time::sleep(RETRY_INTERVAL.min(deadline - now)).await;
+		}
+	}
+
+	/// Runs `job` only if this replica gets the lock, `None` when another one holds it. Meant
+	/// for cron jobs that must not run on several replicas at once.
+	///
+	/// There is no shared job scheduler to mark jobs `singleton` on yet, so each job wraps its
+	/// body in this call itself.
+	pub async fn run_singleton<F, Fut, T>(
+		db: &DatabaseConnection,
+		key: i64,
+		job: F,
+	) -> AppResult<Option<T>>
+	where
+		F: FnOnce() -> Fut,
+		Fut: Future<Output = AppResult<T>>,
+	{
+		let Some(guard) = Self::try_lock(db, key).await? else {
+			return Ok(None);
+		};
+		let res = job().await;
+		guard.unlock().await?;
+		res.map(Some)
+	}
+}
+
+/// Holds the advisory lock and the connection it was taken on, unlocks on drop
+pub struct LockGuard {
+	conn: Option<PoolConnection<Postgres>>,
+	key: i64,
+}
+
+impl LockGuard {
+	fn new(conn: PoolConnection<Postgres>, key: i64) -> Self {
+		Self {
+			conn: Some(conn),
+			key,
+		}
+	}
+
+	pub fn key(&self) -> i64 {
+		self.key
+	}
+
+	/// Releases the lock now, `false` if the session no longer held it
+	pub async fn unlock(mut self) -> AppResult<bool> {
+		match self.conn.take() {
+			Some(conn) => unlock(conn, self.key).await,
+			None => Ok(false),
+		}
+	}
+}
+
+impl Drop for LockGuard {
+	fn drop(&mut self) {
+		let Some(conn) = self.conn.take() else {
+			return;
+		};
+		let key = self.key;
+		match tokio::runtime::Handle::try_current() {
+			Ok(handle) => {
+				handle.spawn(async move {
+					if let Err(e) = unlock(conn, key).await {
+						warn!("advisory lock {key} unlock failed: {e}");
+					}
+				});
+			}
+			// closing the session releases every lock it holds
+			Err(_) => drop(conn.detach()),
+		}
+	}
+}
+
+async fn acquire(db: &DatabaseConnection, key: i64) -> AppResult<PoolConnection<Postgres>> {
+	if db.get_database_backend() != DbBackend::Postgres {
+		return err!(
+			&DBErr::AdvisoryLockUnsupported,
+			format!("{:?}", db.get_database_backend())
+		);
+	}
+	db.get_postgres_connection_pool()
+		.acquire()
+		.await
+		.map_err(map_err!(&DBErr::AdvisoryLockErr, key))
+}
+
+async fn try_acquire(conn: &mut PoolConnection<Postgres>, key: i64) -> AppResult<bool> {
+	query_scalar("SELECT pg_try_advisory_lock($1)")
+		.bind(key)
+		.fetch_one(&mut **conn)
+		.await
+		.map_err(map_err!(&DBErr::AdvisoryLockErr, key))
+}
+
+/// Unlocks and returns the connection to the pool, or closes it when the unlock failed so the
+/// lock cannot leak into the next borrower's session
+async fn unlock(mut conn: PoolConnection<Postgres>, key: i64) -> AppResult<bool> {
+	let res = query_scalar("SELECT pg_advisory_unlock($1)")
+		.bind(key)
+		.fetch_one(&mut *conn)
+		.await
+		.map_err(map_err!(&DBErr::AdvisoryLockErr, key));
+	if res.is_err() {
+		drop(conn.detach());
+	}
+	res
+}
@@ -1 +1,6 @@
+#[cfg(feature = "pgsql")]
+mod advisory_lock;
 pub mod big_decimal;
+
+#[cfg(feature = "pgsql")]
+pub use advisory_lock::*;
@@ -0,0 +1,31 @@
+use base_infra::result::{ErrorCode, SysErr};
+use base_infra::runtimes::Deadline;
+use sea_orm::Database;
+use sql_infra::DatabaseConn;
+use sql_infra::db_tx::DbTxCommit;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_begin_tx_deadline() {
+	let db = DatabaseConn::new(Database::connect("sqlite::memory:").await.unwrap());
+	db.begin_tx("no deadline")
+		.await
+		.unwrap()
+		.commit_tx("no deadline")
+		.await
+		.unwrap();
+
+	Deadline::after(Duration::from_secs(5))
+		.scope(async {
+			let tx = db.begin_tx("in time").await.unwrap();
+			tx.commit_tx("in time").await.unwrap();
+		})
+		.await;
+
+	let err = Deadline::after(Duration::ZERO)
+		.scope(db.begin_tx("expired"))
+		.await
+		.err()
+		.unwrap();
+	assert_eq!(err.err_code().code(), SysErr::DeadlineExceeded.code());
+}
@@ -0,0 +1,83 @@
+use base_infra::result::AppResult;
+use sql_infra::autogen_delegate_repo_trait;
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct MemRepo {
+	name: String,
+	rows: HashMap<u64, String>,
+}
+
+#[derive(Default)]
+struct CachedRepo {
+	repo: MemRepo,
+}
+
+impl CachedRepo {
+	fn repo(&self) -> &MemRepo {
+		&self.repo
+	}
+
+	fn repo_mut(&mut self) -> &mut MemRepo {
+		&mut self.repo
+	}
+}
+
+autogen_delegate_repo_trait! {
+	impl UserRepo for CachedRepo {
+		delegate_to: repo();
+		delegate_mut_to: repo_mut();
+
+		async fn find(&self, id: u64) -> AppResult<Option<String>>;
+		async fn insert_cached(&mut self, id: u64) -> AppResult<()>;
+		async fn name<'a>(&'a self) -> &'a str;
+		fn len(&self) -> usize;
+		fn clear(&mut self) -> usize;
+	}
+}
+
+#[async_trait::async_trait]
+impl UserRepo for MemRepo {
+	async fn find(&self, id: u64) -> AppResult<Option<String>> {
+		Ok(self.rows.get(&id).cloned())
+	}
+
+	async fn insert_cached(&mut self, id: u64) -> AppResult<()> {
+		self.rows.insert(id, format!("user-{id}"));
+		Ok(())
+	}
+
+	async fn name<'a>(&'a self) -> &'a str {
+		&self.name
+	}
+
+	fn len(&self) -> usize {
+		self.rows.len()
+	}
+
+	fn clear(&mut self) -> usize {
+		let removed = self.rows.len();
+		self.rows.clear();
+		removed
+	}
+}
+
+#[tokio::test]
+async fn test_mut_delegate() {
+	let mut cached = CachedRepo {
+		repo: MemRepo {
+			name: "users".to_string(),
+			..Default::default()
+		},
+	};
+
+	cached.insert_cached(7).await.unwrap();
+	assert_eq!(cached.repo.rows.get(&7).map(String::as_str), Some("user-7"));
+	assert_eq!(cached.find(7).await.unwrap().as_deref(), Some("user-7"));
+	assert_eq!(cached.find(8).await.unwrap(), None);
+	assert_eq!(UserRepo::len(&cached), 1);
+	assert_eq!(cached.name().await, "users");
+
+	assert_eq!(cached.clear(), 1);
+	assert_eq!(UserRepo::len(&cached), 0);
+}
@@ -0,0 +1,223 @@
+use base_infra::result::{AppError, AppResult, SysErr};
+use sea_orm::{Database, DatabaseConnection, EntityTrait, TransactionTrait};
+use sql_infra::outbox::{
+	self, OutboxEvent, OutboxRelay, OutboxRelayConfig, OutboxStatus, Publisher, outbox_event,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Records published ids, failing an event as many times as configured
+#[derive(Clone, Default)]
+struct MockPublisher {
+	published: Arc<Mutex<Vec<i64>>>,
+	failures: Arc<Mutex<HashMap<i64, u32>>>,
+}
+
+impl MockPublisher {
+	fn fail(&self, id: i64, times: u32) {
+		self.failures.lock().unwrap().insert(id, times);
+	}
+
+	fn published(&self) -> Vec<i64> {
+		self.published.lock().unwrap().clone()
+	}
+}
+
+#[async_trait::async_trait]
+impl Publisher for MockPublisher {
+	async fn publish(&self, event: &OutboxEvent) -> AppResult<()> {
+		if let Some(left) = self.failures.lock().unwrap().get_mut(&event.id)
+			&& *left > 0
+		{
+			*left -= 1;
+			return Err(AppError::ExtCode(
+				&SysErr::InternalError,
+				format!("broker down for {}", event.id),
+			));
+		}
+		self.published.lock().unwrap().push(event.id);
+		Ok(())
+	}
+}
+
+async fn sqlite() -> DatabaseConnection {
+	let db = Database::connect("sqlite::memory:").await.unwrap();
+	outbox::create_outbox_table(&db).await.unwrap();
+	// idempotent, migrations may run it again
+	outbox::create_outbox_table(&db).await.unwrap();
+	db
+}
+
+async fn enqueue_n(db: &DatabaseConnection, n: usize) -> Vec<i64> {
+	let txn = db.begin().await.unwrap();
+	let mut ids = vec![];
+	for i in 0..n {
+		let payload = serde_json::json!({ "seq": i });
+		ids.push(outbox::enqueue(&txn, "orders", &payload).await.unwrap());
+	}
+	txn.commit().await.unwrap();
+	ids
+}
+
+async fn event(db: &DatabaseConnection, id: i64) -> OutboxEvent {
+	outbox_event::Entity::find_by_id(id)
+		.one(db)
+		.await
+		.unwrap()
+		.unwrap()
+}
+
+fn relay(db: &DatabaseConnection, max_attempts: i32) -> (OutboxRelay<MockPublisher>, MockPublisher) {
+	let publisher = MockPublisher::default();
+	let config = OutboxRelayConfig {
+		poll_interval_ms: 10,
+		batch_size: 10,
+		max_attempts,
+	};
+	(
+		OutboxRelay::new(db.clone(), publisher.clone(), config),
+		publisher,
+	)
+}
+
+#[tokio::test]
+async fn test_publish_in_order() {
+	let db = sqlite().await;
+	let ids = enqueue_n(&db, 3).await;
+
+	// rolled back with the business rows, never published
+	let txn = db.begin().await.unwrap();
+	outbox::enqueue(&txn, "orders", &serde_json::json!({}))
+		.await
+		.unwrap();
+	txn.rollback().await.unwrap();
+
+	let (relay, publisher) = relay(&db, 5);
+	assert_eq!(outbox::backlog(&db).await.unwrap(), 3);
+	assert_eq!(relay.relay_once().await.unwrap(), 3);
+	assert_eq!(publisher.published(), ids);
+	assert_eq!(relay.stats().backlog(), 0);
+	assert_eq!(relay.stats().published(), 3);
+
+	let first = event(&db, ids[0]).await;
+	assert_eq!(first.status, OutboxStatus::Published);
+	assert!(first.published_at.is_some());
+	assert_eq!(
+		first.payload_json().unwrap(),
+		serde_json::json!({ "seq": 0 })
+	);
+
+	assert_eq!(relay.relay_once().await.unwrap(), 0);
+	assert_eq!(publisher.published(), ids);
+}
+
+#[tokio::test]
+async fn test_retry_keeps_order() {
+	let db = sqlite().await;
+	let ids = enqueue_n(&db, 3).await;
+	let (relay, publisher) = relay(&db, 5);
+	publisher.fail(ids[1], 2);
+
+	// the failed event blocks the ones after it
+	assert_eq!(relay.relay_once().await.unwrap(), 1);
+	assert_eq!(publisher.published(), vec![ids[0]]);
+	assert_eq!(relay.relay_once().await.unwrap(), 0);
+	let failed = event(&db, ids[1]).await;
+	assert_eq!(failed.status, OutboxStatus::Pending);
+	assert_eq!(failed.attempts, 2);
+	assert_eq!(
+		failed
+			.last_error
+			.as_deref()
+			.map(|e| e.contains("broker down")),
+		Some(true)
+	);
+	assert_eq!(relay.stats().backlog(), 2);
+
+	assert_eq!(relay.relay_once().await.unwrap(), 2);
+	assert_eq!(publisher.published(), ids);
+	assert_eq!(event(&db, ids[1]).await.status, OutboxStatus::Published);
+}
+
+#[tokio::test]
+async fn test_dead_letter() {
+	let db = sqlite().await;
+	let ids = enqueue_n(&db, 2).await;
+	let (relay, publisher) = relay(&db, 2);
+	publisher.fail(ids[0], u32::MAX);
+
+	assert_eq!(relay.relay_once().await.unwrap(), 0);
+	// out of attempts, the next event goes out in the same poll
+	assert_eq!(relay.relay_once().await.unwrap(), 1);
+	assert_eq!(publisher.published(), vec![ids[1]]);
+
+	let dead = event(&db, ids[0]).await;
+	assert_eq!(dead.status, OutboxStatus::Dead);
+	assert_eq!(dead.attempts, 2);
+	assert_eq!(relay.stats().dead(), 1);
+	assert_eq!(relay.stats().backlog(), 0);
+	assert_eq!(relay.relay_once().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_mark_published_idempotent() {
+	let db = sqlite().await;
+	let ids = enqueue_n(&db, 1).await;
+
+	// a consumer already saw the event when the relay re-delivers and marks it again
+	assert!(outbox::mark_published(&db, ids[0]).await.unwrap());
+	let published_at = event(&db, ids[0]).await.published_at;
+	assert!(!outbox::mark_published(&db, ids[0]).await.unwrap());
+	assert_eq!(event(&db, ids[0]).await.published_at, published_at);
+
+	let (relay, publisher) = relay(&db, 5);
+	assert_eq!(relay.relay_once().await.unwrap(), 0);
+	assert!(publisher.published().is_empty());
+}
+
+#[tokio::test]
+async fn test_background_relay() {
+	let db = sqlite().await;
+	let (relay, publisher) = relay(&db, 5);
+	let handle = relay.start();
+	assert!(handle.is_running());
+
+	let ids = enqueue_n(&db, 25).await;
+	for _ in 0..100 {
+		if publisher.published().len() == ids.len() {
+			break;
+		}
+		tokio::time::sleep(Duration::from_millis(10)).await;
+	}
+	assert_eq!(publisher.published(), ids);
+
+	let stats = handle.stats().clone();
+	handle.stop().await.unwrap();
+	assert_eq!(stats.published(), ids.len() as u64);
+}
+
+#[tokio::test]
+async fn test_prometheus_metrics() {
+	let db = sqlite().await;
+	enqueue_n(&db, 2).await;
+	let (relay, publisher) = relay(&db, 5);
+	publisher.fail(1, 1);
+	relay.relay_once().await.unwrap();
+
+	let registry = prometheus::Registry::new();
+	relay.to_prometheus_metrics(&registry).unwrap();
+	let values: HashMap<_, _> = registry
+		.gather()
+		.iter()
+		.map(|f| {
+			(
+				f.name().to_string(),
+				f.get_metric()[0].get_gauge().get_value(),
+			)
+		})
+		.collect();
+	assert_eq!(values["outbox_backlog"], 2.0);
+	assert_eq!(values["outbox_published_total"], 0.0);
+	assert_eq!(values["outbox_dead_total"], 0.0);
+}
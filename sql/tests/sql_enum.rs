@@ -0,0 +1,136 @@
+use sea_orm::sea_query::{ColumnType, Iden, StringLen, ValueType};
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement, Value};
+use sql_infra::{SqlEnum, sql_enum};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OrderStatus {
+	Pending,
+	Filled,
+	Cancelled,
+}
+
+sql_enum!(OrderStatus {
+	Pending,
+	#[sql_name = "filled_all"]
+	Filled,
+	Cancelled,
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SqlEnum)]
+enum Side {
+	#[sql_name = "bid"]
+	Buy,
+	Sell,
+}
+
+const ALL: [OrderStatus; 3] = [
+	OrderStatus::Pending,
+	OrderStatus::Filled,
+	OrderStatus::Cancelled,
+];
+
+async fn sqlite() -> DatabaseConnection {
+	let db = Database::connect("sqlite::memory:").await.unwrap();
+	db.execute_unprepared(
+		"CREATE TABLE orders (id INTEGER PRIMARY KEY, status VARCHAR NOT NULL, prev VARCHAR)",
+	)
+	.await
+	.unwrap();
+	db
+}
+
+#[test]
+fn test_names() {
+	assert_eq!(OrderStatus::Pending.sql_name(), "Pending");
+	assert_eq!(OrderStatus::Filled.sql_name(), "filled_all");
+	assert_eq!(OrderStatus::Filled.to_string(), "filled_all");
+	assert_eq!(
+		OrderStatus::from_sql_name("filled_all"),
+		Some(OrderStatus::Filled)
+	);
+	assert_eq!(OrderStatus::from_sql_name("Filled"), None);
+
+	assert_eq!(
+		OrderStatus::column_type(),
+		ColumnType::String(StringLen::None)
+	);
+	assert_eq!(OrderStatus::type_name(), "OrderStatus");
+	for status in ALL {
+		let value: Value = status.into();
+		assert_eq!(
+			value,
+			Value::String(Some(Box::new(status.sql_name().to_string())))
+		);
+		assert_eq!(<OrderStatus as ValueType>::try_from(value).unwrap(), status);
+	}
+	assert!(<OrderStatus as ValueType>::try_from(Value::String(None)).is_err());
+	assert_eq!(Value::from(None::<OrderStatus>), Value::String(None));
+}
+
+#[test]
+fn test_derive() {
+	assert_eq!(Side::Buy.sql_name(), "bid");
+	assert_eq!(Side::Sell.sql_name(), "Sell");
+	assert_eq!(Side::from_sql_name("bid"), Some(Side::Buy));
+	assert_eq!(Side::from_sql_name("Buy"), None);
+	assert_eq!(Side::column_type(), ColumnType::String(StringLen::None));
+	for side in [Side::Buy, Side::Sell] {
+		let value: Value = side.into();
+		assert_eq!(
+			value,
+			Value::String(Some(Box::new(side.sql_name().to_string())))
+		);
+		assert_eq!(<Side as ValueType>::try_from(value).unwrap(), side);
+	}
+	assert_eq!(Value::from(None::<Side>), Value::String(None));
+}
+
+#[tokio::test]
+async fn test_sqlite_round_trip() {
+	let db = sqlite().await;
+	for (id, status) in ALL.into_iter().enumerate() {
+		let prev = (id > 0).then(|| ALL[id - 1]);
+		db.execute(Statement::from_sql_and_values(
+			DbBackend::Sqlite,
+			"INSERT INTO orders (id, status, prev) VALUES ($1, $2, $3)",
+			[(id as i64).into(), status.into(), prev.into()],
+		))
+		.await
+		.unwrap();
+	}
+
+	let rows = db
+		.query_all(Statement::from_string(
+			DbBackend::Sqlite,
+			"SELECT id, status, prev FROM orders ORDER BY id",
+		))
+		.await
+		.unwrap();
+	assert_eq!(rows.len(), ALL.len());
+	for (id, row) in rows.iter().enumerate() {
+		let status: OrderStatus = row.try_get("", "status").unwrap();
+		let prev: Option<OrderStatus> = row.try_get("", "prev").unwrap();
+		let raw: String = row.try_get("", "status").unwrap();
+		assert_eq!(status, ALL[id]);
+		assert_eq!(prev, (id > 0).then(|| ALL[id - 1]));
+		assert_eq!(raw, ALL[id].sql_name());
+	}
+
+	db.execute_unprepared("INSERT INTO orders (id, status) VALUES (9, 'Unknown')")
+		.await
+		.unwrap();
+	let row = db
+		.query_one(Statement::from_string(
+			DbBackend::Sqlite,
+			"SELECT status FROM orders WHERE id = 9",
+		))
+		.await
+		.unwrap()
+		.unwrap();
+	let err = row.try_get::<OrderStatus>("", "status").unwrap_err();
+	assert!(
+		err.to_string()
+			.contains("unknown OrderStatus value: Unknown"),
+		"{err}"
+	);
+}
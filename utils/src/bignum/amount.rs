@@ -0,0 +1,310 @@
+use crate::error::UtlErr;
+use alloy_primitives::U256;
+use base_infra::err;
+use base_infra::result::AppResult;
+use base_infra::types::primitives::U256Wrapper;
+use bigdecimal::num_bigint::{BigInt, Sign};
+use bigdecimal::{BigDecimal, RoundingMode, Signed};
+
+/// Rounding applied when an amount has more fraction digits than the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundMode {
+	/// Towards negative infinity
+	Floor,
+	/// Towards positive infinity
+	Ceil,
+	/// Ties away from zero
+	#[default]
+	HalfUp,
+	/// Ties to the even neighbour, i.e. banker's rounding
+	HalfEven,
+}
+
+impl From<RoundMode> for RoundingMode {
+	fn from(mode: RoundMode) -> Self {
+		match mode {
+			RoundMode::Floor => RoundingMode::Floor,
+			RoundMode::Ceil => RoundingMode::Ceiling,
+			RoundMode::HalfUp => RoundingMode::HalfUp,
+			RoundMode::HalfEven => RoundingMode::HalfEven,
+		}
+	}
+}
+
+/// `dec` rounded to `places` fraction digits
+pub fn round_to(dec: &BigDecimal, places: u32, mode: RoundMode) -> BigDecimal {
+	dec.with_scale_round(places as i64, mode.into())
+}
+
+/// `dec / 10^decimals`, e.g. wei to ether with `decimals = 18`. Exact.
+pub fn scale_down(dec: &BigDecimal, decimals: u32) -> BigDecimal {
+	let (digits, scale) = dec.as_bigint_and_exponent();
+	BigDecimal::new(digits, scale + decimals as i64)
+}
+
+/// `dec * 10^decimals`, e.g. ether to wei with `decimals = 18`. Exact.
+pub fn scale_up(dec: &BigDecimal, decimals: u32) -> BigDecimal {
+	let (digits, scale) = dec.as_bigint_and_exponent();
+	BigDecimal::new(digits, scale - decimals as i64)
+}
+
+/// Raw integer units of the token amount `dec`, erroring when `dec` has more than `decimals`
+/// fraction digits
+pub fn to_u256_raw(dec: &BigDecimal, decimals: u32) -> AppResult<U256Wrapper> {
+	let raw = scale_up(dec, decimals);
+	if !raw.is_integer() {
+		return err!(
+			&UtlErr::PrecisionLoss,
+			format!("{dec} has more than {decimals} decimals")
+		);
+	}
+	integer_to_u256(&raw)
+}
+
+/// [`to_u256_raw`] rounding extra fraction digits with `mode` instead of erroring
+pub fn to_u256_raw_rounded(
+	dec: &BigDecimal,
+	decimals: u32,
+	mode: RoundMode,
+) -> AppResult<U256Wrapper> {
+	integer_to_u256(&round_to(&scale_up(dec, decimals), 0, mode))
+}
+
+/// Token amount of `raw` integer units, the inverse of [`to_u256_raw`]
+pub fn from_u256_raw(raw: U256Wrapper, decimals: u32) -> BigDecimal {
+	let digits = BigInt::from_bytes_be(Sign::Plus, &raw.0.to_be_bytes::<32>());
+	BigDecimal::new(digits, decimals as i64)
+}
+
+fn integer_to_u256(raw: &BigDecimal) -> AppResult<U256Wrapper> {
+	if raw.is_negative() {
+		return err!(&UtlErr::NegativeAmount, raw);
+	}
+	let (digits, _) = raw.with_scale(0).into_bigint_and_exponent();
+	let (_, bytes) = digits.to_bytes_be();
+	match U256::try_from_be_slice(&bytes) {
+		Some(value) => Ok(U256Wrapper(value)),
+		None => err!(&UtlErr::U256Overflow, raw),
+	}
+}
+
+/// Display formatting of amounts, `1234567.891` as `1,234,567.89` with the defaults
+#[derive(Debug, Clone, Copy)]
+pub struct AmountFormat {
+	/// Fraction digits kept, trailing zeros are trimmed
+	pub max_fraction_digits: u32,
+	pub mode: RoundMode,
+	/// Thousands separator of the integer part, `None` for none
+	pub separator: Option<char>,
+	/// Error instead of rounding when `dec` has more than `max_fraction_digits`
+	pub strict: bool,
+}
+
+impl Default for AmountFormat {
+	fn default() -> Self {
+		Self {
+			max_fraction_digits: 2,
+			mode: RoundMode::HalfUp,
+			separator: Some(','),
+			strict: false,
+		}
+	}
+}
+
+impl AmountFormat {
+	pub fn new(max_fraction_digits: u32) -> Self {
+		Self {
+			max_fraction_digits,
+			..Self::default()
+		}
+	}
+
+	pub fn format(&self, dec: &BigDecimal) -> AppResult<String> {
+		let rounded = round_to(dec, self.max_fraction_digits, self.mode);
+		if self.strict && rounded != *dec {
+			return err!(
+				&UtlErr::PrecisionLoss,
+				format!(
+					"{dec} has more than {} fraction digits",
+					self.max_fraction_digits
+				)
+			);
+		}
+
+		let plain = rounded.abs().to_plain_string();
+		let (int_part, frac_part) = plain.split_once('.').unwrap_or((&plain, ""));
+		let frac_part = frac_part.trim_end_matches('0');
+
+		let mut out = String::with_capacity(plain.len() + plain.len() / 3 + 1);
+		if rounded.is_negative() {
+			out.push('-');
+		}
+		for (i, digit) in int_part.chars().enumerate() {
+			if i > 0 && (int_part.len() - i) % 3 == 0 {
+				if let Some(separator) = self.separator {
+					out.push(separator);
+				}
+			}
+			out.push(digit);
+		}
+		if !frac_part.is_empty() {
+			out.push('.');
+			out.push_str(frac_part);
+		}
+		Ok(out)
+	}
+}
+
+/// [`AmountFormat::format`] with `max_fraction_digits`, rounding half-up
+pub fn format_amount(dec: &BigDecimal, max_fraction_digits: u32) -> String {
+	AmountFormat::new(max_fraction_digits)
+		.format(dec)
+		.unwrap_or_else(|_| dec.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use base_infra::result::AppError;
+	use std::str::FromStr;
+
+	fn dec(s: &str) -> BigDecimal {
+		BigDecimal::from_str(s).unwrap()
+	}
+
+	fn code<T: std::fmt::Debug>(res: AppResult<T>) -> &'static str {
+		match res {
+			Err(AppError::ExtCode(code, _)) => code.code(),
+			other => panic!("unexpected result: {other:?}"),
+		}
+	}
+
+	#[test]
+	fn test_round_to() {
+		let cases = [
+			("2.345", RoundMode::Floor, "2.34"),
+			("2.345", RoundMode::Ceil, "2.35"),
+			("2.345", RoundMode::HalfUp, "2.35"),
+			("2.345", RoundMode::HalfEven, "2.34"),
+			("2.355", RoundMode::HalfEven, "2.36"),
+			("-2.345", RoundMode::Floor, "-2.35"),
+			("-2.345", RoundMode::Ceil, "-2.34"),
+			("-2.345", RoundMode::HalfUp, "-2.35"),
+		];
+		for (input, mode, expected) in cases {
+			assert_eq!(
+				round_to(&dec(input), 2, mode),
+				dec(expected),
+				"{input} {mode:?}"
+			);
+		}
+
+		// 1/3, repeating
+		let third = dec("1") / dec("3");
+		assert_eq!(round_to(&third, 4, RoundMode::HalfUp), dec("0.3333"));
+		assert_eq!(round_to(&third, 4, RoundMode::Ceil), dec("0.3334"));
+	}
+
+	#[test]
+	fn test_scale() {
+		let wei = dec("1500000000000000000");
+		assert_eq!(scale_down(&wei, 18), dec("1.5"));
+		assert_eq!(scale_up(&dec("1.5"), 18), wei);
+		assert_eq!(scale_up(&scale_down(&dec("1"), 18), 18), dec("1"));
+		assert_eq!(scale_down(&dec("0.1"), 0), dec("0.1"));
+	}
+
+	#[test]
+	fn test_u256_raw_roundtrip() {
+		let raw = to_u256_raw(&dec("1.5"), 18).unwrap();
+		assert_eq!(raw, U256Wrapper::from(1_500_000_000_000_000_000u64));
+		assert_eq!(from_u256_raw(raw, 18), dec("1.5"));
+
+		assert_eq!(to_u256_raw(&dec("0"), 18).unwrap(), U256Wrapper::ZERO);
+		assert_eq!(
+			to_u256_raw(&dec("0.000000000000000001"), 18).unwrap(),
+			U256Wrapper::from(1)
+		);
+
+		let max = from_u256_raw(U256Wrapper::MAX, 18);
+		assert_eq!(to_u256_raw(&max, 18).unwrap(), U256Wrapper::MAX);
+		assert_eq!(
+			max,
+			dec("115792089237316195423570985008687907853269984665640564039457.584007913129639935")
+		);
+	}
+
+	#[test]
+	fn test_u256_raw_errors() {
+		// 19 decimals
+		let too_precise = dec("0.0000000000000000001");
+		assert_eq!(code(to_u256_raw(&too_precise, 18)), "BGN005");
+		let third = dec("1") / dec("3");
+		assert_eq!(code(to_u256_raw(&third, 18)), "BGN005");
+
+		assert_eq!(code(to_u256_raw(&dec("-1"), 18)), "BGN004");
+		assert_eq!(
+			code(to_u256_raw_rounded(&dec("-0.1"), 0, RoundMode::Floor)),
+			"BGN004"
+		);
+
+		let max = from_u256_raw(U256Wrapper::MAX, 0);
+		assert_eq!(code(to_u256_raw(&(max.clone() + dec("1")), 0)), "BGN006");
+		assert_eq!(
+			code(to_u256_raw_rounded(
+				&(max + dec("0.5")),
+				0,
+				RoundMode::HalfUp
+			)),
+			"BGN006"
+		);
+	}
+
+	#[test]
+	fn test_u256_raw_rounded() {
+		let third = dec("1") / dec("3");
+		let down = to_u256_raw_rounded(&third, 18, RoundMode::Floor).unwrap();
+		assert_eq!(down.to_string(), "333333333333333333");
+		let up = to_u256_raw_rounded(&third, 18, RoundMode::Ceil).unwrap();
+		assert_eq!(up.to_string(), "333333333333333334");
+		let even = to_u256_raw_rounded(&dec("2.5"), 0, RoundMode::HalfEven).unwrap();
+		assert_eq!(even, U256Wrapper::from(2u64));
+	}
+
+	#[test]
+	fn test_format_amount() {
+		assert_eq!(format_amount(&dec("1234567.891"), 2), "1,234,567.89");
+		assert_eq!(format_amount(&dec("1234567.895"), 2), "1,234,567.9");
+		assert_eq!(format_amount(&dec("999.999"), 2), "1,000");
+		assert_eq!(format_amount(&dec("100"), 2), "100");
+		assert_eq!(format_amount(&dec("0.5"), 0), "1");
+		assert_eq!(format_amount(&dec("-1234.5"), 2), "-1,234.5");
+		assert_eq!(format_amount(&dec("-0.001"), 2), "0");
+
+		let wei = from_u256_raw(U256Wrapper::MAX, 18);
+		assert_eq!(
+			format_amount(&wei, 4),
+			"115,792,089,237,316,195,423,570,985,008,687,907,853,269,984,665,640,564,039,457.584"
+		);
+
+		let plain = AmountFormat {
+			separator: None,
+			..AmountFormat::new(6)
+		};
+		assert_eq!(
+			plain.format(&dec("1234567.1234567")).unwrap(),
+			"1234567.123457"
+		);
+	}
+
+	#[test]
+	fn test_format_amount_strict() {
+		let strict = AmountFormat {
+			strict: true,
+			..AmountFormat::new(2)
+		};
+		assert_eq!(strict.format(&dec("1234.50")).unwrap(), "1,234.5");
+		assert_eq!(code(strict.format(&dec("1234.501"))), "BGN005");
+		assert_eq!(code(strict.format(&(dec("1") / dec("3")))), "BGN005");
+	}
+}
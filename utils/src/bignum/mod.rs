@@ -1,7 +1,15 @@
+mod amount;
+mod rate;
+
 use crate::error::UtlErr;
-use base_infra::nar_err;
 use base_infra::result::AppResult;
-use bigdecimal::{BigDecimal, ToPrimitive};
+use base_infra::{err, map_err, nar_err};
+use bigdecimal::num_bigint::{BigInt, Sign};
+use bigdecimal::{BigDecimal, RoundingMode, Signed, ToPrimitive, Zero};
+use ruint::aliases::{U128, U256};
+
+pub use amount::*;
+pub use rate::*;
 
 pub trait ToFloat {
 	fn to_f32(&self) -> AppResult<f32>;
@@ -22,10 +30,71 @@ impl ToFloat for BigDecimal {
 	}
 }
 
+pub trait ToUint {
+	fn to_u256(&self) -> AppResult<U256>;
+	fn to_u128(&self) -> AppResult<U128>;
+}
+
+impl ToUint for BigDecimal {
+	fn to_u256(&self) -> AppResult<U256> {
+		U256::from_str_radix(&uint_digits(self)?, 10)
+			.map_err(map_err!(&UtlErr::BigDecToUint, format!("{self} to U256")))
+	}
+
+	fn to_u128(&self) -> AppResult<U128> {
+		U128::from_str_radix(&uint_digits(self)?, 10)
+			.map_err(map_err!(&UtlErr::BigDecToUint, format!("{self} to U128")))
+	}
+}
+
+/// Plain integer digits of `dec`, rejecting negative and fractional values
+fn uint_digits(dec: &BigDecimal) -> AppResult<String> {
+	if dec.is_negative() || !dec.is_integer() {
+		return err!(&UtlErr::BigDecToUint, dec);
+	}
+	Ok(dec.with_scale(0).to_plain_string())
+}
+
+pub fn from_u256(v: &U256) -> BigDecimal {
+	BigDecimal::from(BigInt::from_bytes_be(Sign::Plus, &v.to_be_bytes::<32>()))
+}
+
+fn checked_div(numerator: &BigDecimal, denominator: &BigDecimal) -> AppResult<BigDecimal> {
+	if denominator.is_zero() {
+		return err!(&UtlErr::DivisionByZero);
+	}
+	Ok(numerator / denominator)
+}
+
+/// `amount * bps / 10000`
+pub fn basis_points_of(amount: &BigDecimal, bps: u32) -> AppResult<BigDecimal> {
+	checked_div(&(amount * BigDecimal::from(bps)), &BigDecimal::from(10_000))
+}
+
+/// `amount * pct / 100`
+pub fn percentage_of(amount: &BigDecimal, pct: &BigDecimal) -> AppResult<BigDecimal> {
+	checked_div(&(amount * pct), &BigDecimal::from(100))
+}
+
+/// `numerator / denominator` rounded half-up to `precision` decimal places
+pub fn proportion(
+	numerator: &BigDecimal,
+	denominator: &BigDecimal,
+	precision: u32,
+) -> AppResult<BigDecimal> {
+	checked_div(numerator, denominator)
+		.map(|v| v.with_scale_round(precision as i64, RoundingMode::HalfUp))
+}
+
 #[cfg(test)]
 mod tests {
-	use super::ToFloat;
-	use bigdecimal::BigDecimal;
+	// not a glob, `ToPrimitive` would make `to_f32`/`to_u128` ambiguous
+	use super::{
+		BigDecimal, ToFloat, ToUint, U128, U256, Zero, basis_points_of, from_u256, percentage_of,
+		proportion,
+	};
+	use base_infra::result::AppError;
+	use std::str::FromStr;
 
 	#[test]
 	fn test_to_float() {
@@ -35,4 +104,61 @@ mod tests {
 		assert_eq!(f32, 1.0);
 		assert_eq!(f64, 1.0);
 	}
+
+	#[test]
+	fn test_to_uint() {
+		let max = from_u256(&U256::MAX);
+		assert_eq!(
+			max.to_string(),
+			"115792089237316195423570985008687907853269984665640564039457584007913129639935"
+		);
+		assert_eq!(max.to_u256().unwrap(), U256::MAX);
+		assert!((max + BigDecimal::from(1)).to_u256().is_err());
+
+		assert_eq!(BigDecimal::zero().to_u256().unwrap(), U256::ZERO);
+		assert_eq!(BigDecimal::zero().to_u128().unwrap(), U128::ZERO);
+
+		let u128_max = BigDecimal::from(u128::MAX);
+		assert_eq!(u128_max.to_u128().unwrap(), U128::MAX);
+		match (u128_max + BigDecimal::from(1)).to_u128() {
+			Err(AppError::ExtAnyhow(code, _, _)) => assert_eq!(code.code(), "BGN008"),
+			other => panic!("unexpected result: {other:?}"),
+		}
+
+		assert!(BigDecimal::from(-1).to_u256().is_err());
+		assert!(BigDecimal::from_str("1.5").unwrap().to_u256().is_err());
+		assert_eq!(
+			BigDecimal::from_str("1.000").unwrap().to_u128().unwrap(),
+			U128::from(1)
+		);
+
+		let v = U256::from(123_456_789u64) << 128usize;
+		assert_eq!(from_u256(&v).to_u256().unwrap(), v);
+	}
+
+	#[test]
+	fn test_ratio_helpers() {
+		let amount = BigDecimal::from(1000);
+		assert_eq!(basis_points_of(&amount, 100).unwrap(), BigDecimal::from(10));
+		assert_eq!(
+			basis_points_of(&BigDecimal::from_str("0.01").unwrap(), 1).unwrap(),
+			BigDecimal::from_str("0.000001").unwrap()
+		);
+
+		let half = percentage_of(&BigDecimal::from(200), &BigDecimal::from(50)).unwrap();
+		assert_eq!(half, BigDecimal::from(100));
+
+		let third = proportion(&BigDecimal::from(1), &BigDecimal::from(3), 6).unwrap();
+		assert_eq!(third, BigDecimal::from_str("0.333333").unwrap());
+		let two_thirds = proportion(&BigDecimal::from(2), &BigDecimal::from(3), 2).unwrap();
+		assert_eq!(two_thirds, BigDecimal::from_str("0.67").unwrap());
+	}
+
+	#[test]
+	fn test_proportion_div_by_zero() {
+		match proportion(&BigDecimal::from(1), &BigDecimal::zero(), 6) {
+			Err(AppError::ErrCode(code)) => assert_eq!(code.code(), "BGN003"),
+			other => panic!("unexpected result: {other:?}"),
+		}
+	}
 }
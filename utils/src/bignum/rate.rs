@@ -0,0 +1,410 @@
+use crate::bignum::{RoundMode, from_u256_raw, round_to, to_u256_raw_rounded};
+use crate::error::UtlErr;
+use base_infra::err;
+use base_infra::result::{AppError, AppResult};
+use base_infra::types::primitives::U256Wrapper;
+use bigdecimal::{BigDecimal, One, Signed, Zero};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+const BPS_PER_UNIT: u32 = 10_000;
+
+/// Basis points, `1 bps = 0.01%`, within `0..=10_000`
+///
+/// Deserializes from numeric bps (`30`) or a percent string (`"0.3%"`), serializes as bps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Bps(u32);
+
+impl Bps {
+	pub const ZERO: Bps = Bps(0);
+	pub const MAX: Bps = Bps(BPS_PER_UNIT);
+
+	pub fn new(bps: u32) -> AppResult<Self> {
+		if bps > BPS_PER_UNIT {
+			return err!(&UtlErr::InvalidRate, format!("{bps} bps exceeds 10000"));
+		}
+		Ok(Self(bps))
+	}
+
+	pub fn value(&self) -> u32 {
+		self.0
+	}
+
+	/// `bps / 10_000`, e.g. `0.003` for 30 bps
+	pub fn ratio(&self) -> BigDecimal {
+		BigDecimal::new(self.0.into(), 4)
+	}
+
+	/// Whole bps of `ratio`, erroring on fractional bps
+	pub fn from_ratio(ratio: &BigDecimal) -> AppResult<Self> {
+		Percent::from_ratio(ratio)?.to_bps()
+	}
+
+	/// `value * bps / 10_000`, see [`RateAmount`] for the rounding
+	pub fn apply_to<V: RateAmount>(&self, value: &V, mode: RoundMode) -> AppResult<V> {
+		value.mul_ratio(&self.ratio(), mode)
+	}
+
+	/// Combined rate of charging `self` then `other` on the remainder,
+	/// `a + b - a * b`, exact and therefore a [`Percent`]
+	pub fn compose(self, other: Bps) -> Percent {
+		Percent::from(self).compose(&Percent::from(other))
+	}
+}
+
+impl Display for Bps {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}bps", self.0)
+	}
+}
+
+impl From<Bps> for BigDecimal {
+	fn from(bps: Bps) -> Self {
+		bps.ratio()
+	}
+}
+
+impl TryFrom<&BigDecimal> for Bps {
+	type Error = AppError;
+
+	fn try_from(ratio: &BigDecimal) -> Result<Self, Self::Error> {
+		Self::from_ratio(ratio)
+	}
+}
+
+/// `"30"` as bps, `"0.3%"` as percent
+impl FromStr for Bps {
+	type Err = AppError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		if s.ends_with('%') {
+			return Percent::from_str(s)?.to_bps();
+		}
+		match s.parse::<u32>() {
+			Ok(bps) => Self::new(bps),
+			Err(e) => err!(&UtlErr::InvalidRate, format!("`{s}`: {e}")),
+		}
+	}
+}
+
+impl Serialize for Bps {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_u32(self.0)
+	}
+}
+
+impl<'de> Deserialize<'de> for Bps {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let rate = match RateRepr::deserialize(deserializer)? {
+			RateRepr::Bps(bps) => Bps::new(bps),
+			RateRepr::Str(s) => Bps::from_str(&s),
+		};
+		rate.map_err(serde::de::Error::custom)
+	}
+}
+
+/// Percentage within `0..=100`, finer than [`Bps`], e.g. `0.005%`
+///
+/// Deserializes from a percent string (`"0.3%"`) or numeric bps (`30`), serializes as
+/// `"0.3%"`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Percent(BigDecimal);
+
+impl Percent {
+	pub fn new(pct: BigDecimal) -> AppResult<Self> {
+		if pct.is_negative() || pct > BigDecimal::from(100) {
+			return err!(&UtlErr::InvalidRate, format!("{pct}% is outside 0..=100"));
+		}
+		Ok(Self(pct.normalized()))
+	}
+
+	/// The percentage, e.g. `0.3` for 0.3%
+	pub fn value(&self) -> &BigDecimal {
+		&self.0
+	}
+
+	/// `pct / 100`, e.g. `0.003` for 0.3%
+	pub fn ratio(&self) -> BigDecimal {
+		let (digits, scale) = self.0.as_bigint_and_exponent();
+		BigDecimal::new(digits, scale + 2)
+	}
+
+	pub fn from_ratio(ratio: &BigDecimal) -> AppResult<Self> {
+		let (digits, scale) = ratio.as_bigint_and_exponent();
+		Self::new(BigDecimal::new(digits, scale - 2))
+	}
+
+	/// Exact bps, erroring on fractional bps such as `0.005%`
+	pub fn to_bps(&self) -> AppResult<Bps> {
+		let bps = &self.0 * BigDecimal::from(100);
+		if !bps.is_integer() {
+			return err!(&UtlErr::PrecisionLoss, format!("{self} is not a whole bps"));
+		}
+		self.to_bps_rounded(RoundMode::Floor)
+	}
+
+	pub fn to_bps_rounded(&self, mode: RoundMode) -> AppResult<Bps> {
+		let bps = round_to(&(&self.0 * BigDecimal::from(100)), 0, mode);
+		let (digits, _) = bps.into_bigint_and_exponent();
+		match u32::try_from(digits) {
+			Ok(bps) => Bps::new(bps),
+			Err(e) => err!(&UtlErr::InvalidRate, e),
+		}
+	}
+
+	/// `value * pct / 100`, see [`RateAmount`] for the rounding
+	pub fn apply_to<V: RateAmount>(&self, value: &V, mode: RoundMode) -> AppResult<V> {
+		value.mul_ratio(&self.ratio(), mode)
+	}
+
+	/// Combined rate of charging `self` then `other` on the remainder, `a + b - a * b`.
+	/// Exact, so `a.compose(b).compose(c) == a.compose(b.compose(c))`.
+	pub fn compose(&self, other: &Percent) -> Percent {
+		let (a, b) = (self.ratio(), other.ratio());
+		let remaining = (BigDecimal::one() - &a) * (BigDecimal::one() - &b);
+		let (digits, scale) = (BigDecimal::one() - remaining).into_bigint_and_exponent();
+		Self(BigDecimal::new(digits, scale - 2).normalized())
+	}
+}
+
+impl Display for Percent {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let pct = if self.0.is_zero() {
+			BigDecimal::zero()
+		} else {
+			self.0.normalized()
+		};
+		write!(f, "{}%", pct.to_plain_string())
+	}
+}
+
+impl From<Bps> for Percent {
+	fn from(bps: Bps) -> Self {
+		Self(BigDecimal::new(bps.0.into(), 2).normalized())
+	}
+}
+
+impl From<Percent> for BigDecimal {
+	fn from(pct: Percent) -> Self {
+		pct.ratio()
+	}
+}
+
+impl TryFrom<&BigDecimal> for Percent {
+	type Error = AppError;
+
+	fn try_from(ratio: &BigDecimal) -> Result<Self, Self::Error> {
+		Self::from_ratio(ratio)
+	}
+}
+
+/// `"0.3%"`, the `%` is required
+impl FromStr for Percent {
+	type Err = AppError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let Some(pct) = s.trim().strip_suffix('%') else {
+			return err!(&UtlErr::InvalidRate, format!("`{s}` is missing `%`"));
+		};
+		match BigDecimal::from_str(pct.trim()) {
+			Ok(pct) => Self::new(pct),
+			Err(e) => err!(&UtlErr::InvalidRate, format!("`{s}`: {e}")),
+		}
+	}
+}
+
+impl Serialize for Percent {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+impl<'de> Deserialize<'de> for Percent {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let rate = match RateRepr::deserialize(deserializer)? {
+			RateRepr::Bps(bps) => Bps::new(bps).map(Percent::from),
+			RateRepr::Str(s) => Percent::from_str(&s),
+		};
+		rate.map_err(serde::de::Error::custom)
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RateRepr {
+	Bps(u32),
+	Str(String),
+}
+
+/// Amounts a [`Bps`] / [`Percent`] applies to
+///
+/// The product is computed exactly, then rounded with `mode` to the scale of the amount:
+/// the fraction digits of a `BigDecimal`, whole units of a `U256Wrapper`.
+pub trait RateAmount: Sized {
+	fn mul_ratio(&self, ratio: &BigDecimal, mode: RoundMode) -> AppResult<Self>;
+}
+
+impl RateAmount for BigDecimal {
+	fn mul_ratio(&self, ratio: &BigDecimal, mode: RoundMode) -> AppResult<Self> {
+		let places = self.fractional_digit_count().max(0) as u32;
+		Ok(round_to(&(self * ratio), places, mode))
+	}
+}
+
+impl RateAmount for U256Wrapper {
+	fn mul_ratio(&self, ratio: &BigDecimal, mode: RoundMode) -> AppResult<Self> {
+		to_u256_raw_rounded(&(from_u256_raw(*self, 0) * ratio), 0, mode)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use alloy_primitives::U256;
+
+	fn dec(s: &str) -> BigDecimal {
+		BigDecimal::from_str(s).unwrap()
+	}
+
+	fn pct(s: &str) -> Percent {
+		Percent::from_str(s).unwrap()
+	}
+
+	#[test]
+	fn test_range() {
+		assert!(Bps::new(10_000).is_ok());
+		assert!(Bps::new(10_001).is_err());
+		assert!(Percent::new(dec("100")).is_ok());
+		assert!(Percent::new(dec("100.01")).is_err());
+		assert!(Percent::new(dec("-0.01")).is_err());
+		assert!(Percent::from_str("0.3").is_err());
+	}
+
+	#[test]
+	fn test_conversions() {
+		let bps = Bps::new(30).unwrap();
+		assert_eq!(bps.ratio(), dec("0.003"));
+		assert_eq!(BigDecimal::from(bps), dec("0.003"));
+		assert_eq!(Bps::from_ratio(&dec("0.003")).unwrap(), bps);
+		assert_eq!(Percent::from(bps), pct("0.3%"));
+		assert_eq!(pct("0.30%").to_bps().unwrap(), bps);
+		assert_eq!(Percent::try_from(&dec("0.0025")).unwrap(), pct("0.25%"));
+
+		// half a bps
+		assert!(pct("0.005%").to_bps().is_err());
+		assert!(Bps::from_ratio(&dec("0.00005")).is_err());
+		assert_eq!(
+			pct("0.005%").to_bps_rounded(RoundMode::Ceil).unwrap(),
+			Bps(1)
+		);
+		assert_eq!(
+			pct("0.005%").to_bps_rounded(RoundMode::Floor).unwrap(),
+			Bps(0)
+		);
+
+		assert_eq!(pct("0.3%").to_string(), "0.3%");
+		assert_eq!(pct("10%").to_string(), "10%");
+		assert_eq!(pct("0.000%").to_string(), "0%");
+	}
+
+	#[test]
+	fn test_apply_to_rounding() {
+		let fee = Bps::new(30).unwrap();
+		assert_eq!(
+			fee.apply_to(&dec("1000.00"), RoundMode::HalfUp).unwrap(),
+			dec("3.00")
+		);
+
+		// 0.01 * 0.003 = 0.00003, rounded at the amount's 2 decimals
+		let tiny = dec("0.01");
+		assert_eq!(fee.apply_to(&tiny, RoundMode::Floor).unwrap(), dec("0"));
+		assert_eq!(fee.apply_to(&tiny, RoundMode::Ceil).unwrap(), dec("0.01"));
+		assert_eq!(fee.apply_to(&tiny, RoundMode::HalfUp).unwrap(), dec("0"));
+
+		// 1 wei
+		let wei = U256Wrapper::from(1u64);
+		assert_eq!(
+			fee.apply_to(&wei, RoundMode::Floor).unwrap(),
+			U256Wrapper::ZERO
+		);
+		assert_eq!(fee.apply_to(&wei, RoundMode::Ceil).unwrap(), wei);
+
+		// 5000 * 0.0001 = 0.5, tie
+		let one_bps = Bps::new(1).unwrap();
+		let half = U256Wrapper::from(5_000u64);
+		assert_eq!(
+			one_bps.apply_to(&half, RoundMode::HalfEven).unwrap(),
+			U256Wrapper::ZERO
+		);
+		assert_eq!(one_bps.apply_to(&half, RoundMode::HalfUp).unwrap(), wei);
+
+		// no overflow at the top of the range
+		assert_eq!(
+			Bps::MAX
+				.apply_to(&U256Wrapper::MAX, RoundMode::Floor)
+				.unwrap(),
+			U256Wrapper::MAX
+		);
+		let max_fee = fee.apply_to(&U256Wrapper::MAX, RoundMode::Floor).unwrap();
+		let (q, r) = U256Wrapper::MAX.0.div_rem(U256::from(1000));
+		assert_eq!(
+			max_fee.0,
+			q * U256::from(3) + r * U256::from(3) / U256::from(1000)
+		);
+
+		assert_eq!(
+			pct("0.005%")
+				.apply_to(&dec("200.000"), RoundMode::HalfUp)
+				.unwrap(),
+			dec("0.010")
+		);
+	}
+
+	#[test]
+	fn test_compose() {
+		let a = Bps::new(30).unwrap();
+		let b = Bps::new(50).unwrap();
+		// 1 - 0.997 * 0.995
+		assert_eq!(a.compose(b), pct("0.7985%"));
+		assert_eq!(a.compose(b), b.compose(a));
+		assert_eq!(a.compose(Bps::ZERO), Percent::from(a));
+		assert_eq!(a.compose(Bps::MAX), pct("100%"));
+
+		let c = pct("1.25%");
+		let left = a.compose(b).compose(&c);
+		let right = Percent::from(a).compose(&Percent::from(b).compose(&c));
+		assert_eq!(left, right);
+
+		// composed rates are generally not whole bps
+		assert!(left.to_bps().is_err());
+		let amount = dec("10000");
+		let sequential = {
+			let after_a = &amount - a.apply_to(&amount, RoundMode::HalfUp).unwrap();
+			let after_b = &after_a - b.apply_to(&after_a, RoundMode::HalfUp).unwrap();
+			&after_b - c.apply_to(&after_b, RoundMode::HalfUp).unwrap()
+		};
+		let combined = &amount - left.apply_to(&amount, RoundMode::HalfUp).unwrap();
+		assert!((sequential - combined).abs() <= dec("1"));
+	}
+
+	#[test]
+	fn test_serde() {
+		let bps: Bps = serde_json::from_str("30").unwrap();
+		assert_eq!(bps, Bps(30));
+		let bps: Bps = serde_json::from_str("\"0.3%\"").unwrap();
+		assert_eq!(bps, Bps(30));
+		assert_eq!(serde_json::to_string(&bps).unwrap(), "30");
+		assert!(serde_json::from_str::<Bps>("10001").is_err());
+		assert!(serde_json::from_str::<Bps>("\"0.005%\"").is_err());
+		assert!(serde_json::from_str::<Bps>("-1").is_err());
+
+		let p: Percent = serde_json::from_str("\"0.005%\"").unwrap();
+		assert_eq!(p, pct("0.005%"));
+		assert_eq!(serde_json::to_string(&p).unwrap(), "\"0.005%\"");
+		let p: Percent = serde_json::from_str("25").unwrap();
+		assert_eq!(p, pct("0.25%"));
+		assert!(serde_json::from_str::<Percent>("\"101%\"").is_err());
+	}
+}
@@ -2,7 +2,7 @@ use crate::chrono::ts_to_naive_datetime;
 use crate::error::UtlErr;
 use base_infra::result::AppResult;
 use base_infra::{err, map_err, nar_err};
-use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use chrono::{DateTime, Days, Local, Months, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
 use std::fmt::{Display, Formatter};
 
 pub trait TsToDateTime<T> {
@@ -63,6 +63,50 @@ impl DiffDays<DateTime<Local>> for DateTime<Local> {
 	}
 }
 
+impl DiffDays<NaiveDate> for DateTime<Utc> {
+	fn diff_days(self, to: NaiveDate) -> i64 {
+		self.date_naive().diff_days(to)
+	}
+}
+
+impl DiffDays<DateTime<Utc>> for NaiveDate {
+	fn diff_days(self, to: DateTime<Utc>) -> i64 {
+		self.diff_days(to.date_naive())
+	}
+}
+
+pub trait DiffHours<Rhs = Self> {
+	fn diff_hours(self, to: Rhs) -> i64;
+}
+
+impl DiffHours<NaiveDateTime> for NaiveDateTime {
+	fn diff_hours(self, to: NaiveDateTime) -> i64 {
+		self.signed_duration_since(to).num_hours().abs()
+	}
+}
+
+impl DiffHours<DateTime<Utc>> for DateTime<Utc> {
+	fn diff_hours(self, to: DateTime<Utc>) -> i64 {
+		self.signed_duration_since(to).num_hours().abs()
+	}
+}
+
+pub trait DiffMinutes<Rhs = Self> {
+	fn diff_minutes(self, to: Rhs) -> i64;
+}
+
+impl DiffMinutes<NaiveDateTime> for NaiveDateTime {
+	fn diff_minutes(self, to: NaiveDateTime) -> i64 {
+		self.signed_duration_since(to).num_minutes().abs()
+	}
+}
+
+impl DiffMinutes<DateTime<Utc>> for DateTime<Utc> {
+	fn diff_minutes(self, to: DateTime<Utc>) -> i64 {
+		self.signed_duration_since(to).num_minutes().abs()
+	}
+}
+
 pub trait DateTimeUtcExt {
 	fn utc_from_str(dt_str: &str) -> AppResult<DateTime<Utc>> {
 		NaiveDateTime::parse_from_str(dt_str, "%Y-%m-%d %H:%M:%S")
@@ -162,6 +206,72 @@ pub trait TimelikeTruncate: Timelike + Sized {
 impl TimelikeTruncate for DateTime<Utc> {}
 impl TimelikeTruncate for NaiveDateTime {}
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateStep {
+	Days(u32),
+	Weeks(u32),
+	Months(u32),
+}
+
+/// Dates from `start` (inclusive) to `end` (exclusive) every `step`
+///
+/// Dates are computed from `start`, so monthly steps keep the day of month where it exists:
+/// Jan 31 steps to Feb 29 then Mar 31.
+#[derive(Debug, Clone)]
+pub struct DateRange {
+	start: NaiveDate,
+	end: NaiveDate,
+	step: DateStep,
+	index: u32,
+}
+
+impl DateRange {
+	pub fn new(start: NaiveDate, end: NaiveDate, step: DateStep) -> AppResult<DateRange> {
+		if start > end {
+			return err!(
+				&UtlErr::InvalidDateRange,
+				format!("start {start} is after end {end}")
+			);
+		}
+		if matches!(
+			step,
+			DateStep::Days(0) | DateStep::Weeks(0) | DateStep::Months(0)
+		) {
+			return err!(&UtlErr::InvalidDateRange, "step must be positive");
+		}
+		Ok(Self {
+			start,
+			end,
+			step,
+			index: 0,
+		})
+	}
+
+	fn nth_date(&self, n: u32) -> Option<NaiveDate> {
+		match self.step {
+			DateStep::Days(d) => self
+				.start
+				.checked_add_days(Days::new(u64::from(d) * u64::from(n))),
+			DateStep::Weeks(w) => self
+				.start
+				.checked_add_days(Days::new(7 * u64::from(w) * u64::from(n))),
+			DateStep::Months(m) => self
+				.start
+				.checked_add_months(Months::new(m.checked_mul(n)?)),
+		}
+	}
+}
+
+impl Iterator for DateRange {
+	type Item = NaiveDate;
+
+	fn next(&mut self) -> Option<NaiveDate> {
+		let current = self.nth_date(self.index).filter(|d| *d < self.end)?;
+		self.index += 1;
+		Some(current)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -220,10 +330,82 @@ mod tests {
 		println!("{:?}", dt);
 	}
 
+	#[test]
+	fn test_diff_days_mixed() {
+		let dt = DateTime::utc_from_str("2024-01-10 23:00:00").unwrap();
+		let date = NaiveDate::from_ymd_opt(2024, 1, 8).unwrap();
+		assert_eq!(dt.diff_days(date), 2);
+		assert_eq!(date.diff_days(dt), 2);
+	}
+
+	#[test]
+	fn test_diff_hours_and_minutes() {
+		let from = DateTime::utc_from_str("2024-01-10 08:00:00").unwrap();
+		let to = DateTime::utc_from_str("2024-01-11 09:00:00").unwrap();
+		assert_eq!(from.diff_hours(to), 25);
+		assert_eq!(from.naive_utc().diff_hours(to.naive_utc()), 25);
+		assert_eq!(from.diff_minutes(to), 25 * 60);
+
+		let later = DateTime::utc_from_str("2024-01-10 11:30:00").unwrap();
+		assert_eq!(from.diff_days(later), 0);
+		assert_eq!(from.diff_hours(later), 3);
+		assert_eq!(from.naive_utc().diff_minutes(later.naive_utc()), 210);
+	}
+
 	#[test]
 	fn test_from_timestamp_micros() {
 		let micros = 1627840000_001_001;
 		let dt = NaiveDateTime::utc_from_micros(micros).unwrap();
 		println!("{:?}", dt);
 	}
+
+	fn ymd(y: i32, m: u32, d: u32) -> NaiveDate {
+		NaiveDate::from_ymd_opt(y, m, d).unwrap()
+	}
+
+	#[test]
+	fn test_date_range() {
+		// end is exclusive
+		let days: Vec<_> = DateRange::new(ymd(2024, 1, 1), ymd(2024, 1, 8), DateStep::Days(1))
+			.unwrap()
+			.collect();
+		assert_eq!(days.len(), 7);
+		assert_eq!((days[0], days[6]), (ymd(2024, 1, 1), ymd(2024, 1, 7)));
+
+		let weeks: Vec<_> = DateRange::new(ymd(2024, 1, 1), ymd(2024, 3, 1), DateStep::Weeks(1))
+			.unwrap()
+			.collect();
+		assert_eq!(weeks.len(), 9);
+		assert_eq!((weeks[0], weeks[8]), (ymd(2024, 1, 1), ymd(2024, 2, 26)));
+
+		let months: Vec<_> = DateRange::new(ymd(2024, 1, 1), ymd(2025, 1, 1), DateStep::Months(1))
+			.unwrap()
+			.collect();
+		assert_eq!(months.len(), 12);
+		assert_eq!((months[0], months[11]), (ymd(2024, 1, 1), ymd(2024, 12, 1)));
+
+		let month_ends: Vec<_> =
+			DateRange::new(ymd(2024, 1, 31), ymd(2024, 4, 1), DateStep::Months(1))
+				.unwrap()
+				.collect();
+		assert_eq!(
+			month_ends,
+			[ymd(2024, 1, 31), ymd(2024, 2, 29), ymd(2024, 3, 31)]
+		);
+
+		let same = ymd(2024, 5, 5);
+		assert_eq!(
+			DateRange::new(same, same, DateStep::Days(1))
+				.unwrap()
+				.count(),
+			0
+		);
+	}
+
+	#[test]
+	fn test_invalid_date_range() {
+		let err = DateRange::new(ymd(2024, 2, 1), ymd(2024, 1, 1), DateStep::Days(1)).unwrap_err();
+		assert!(err.to_string().contains("CHR005"));
+		assert!(DateRange::new(ymd(2024, 1, 1), ymd(2024, 2, 1), DateStep::Weeks(0)).is_err());
+	}
 }
@@ -0,0 +1,201 @@
+use crate::error::UtlErr;
+use base_infra::err;
+use base_infra::result::AppResult;
+use chrono::{DateTime, Duration, Utc};
+
+/// Units from largest to smallest, each allowed once and in this order
+const UNITS: [(&str, i64); 5] = [
+	("d", 86_400_000),
+	("h", 3_600_000),
+	("m", 60_000),
+	("s", 1_000),
+	("ms", 1),
+];
+
+/// Parses `"5m"`, `"2h30m"`, `"1d 12h"` or `"0"`.
+///
+/// Units are `d`, `h`, `m`, `s` and `ms`, largest first, each at most once. Negative values
+/// are rejected.
+pub fn parse_duration(input: &str) -> AppResult<Duration> {
+	let mut rest = input.trim();
+	if rest.is_empty() {
+		return err!(&UtlErr::InvalidDuration, "empty duration");
+	}
+	if rest == "0" {
+		return Ok(Duration::zero());
+	}
+
+	let mut total_ms: i64 = 0;
+	let mut last_unit: Option<usize> = None;
+	while !rest.is_empty() {
+		let digits = rest
+			.find(|c: char| !c.is_ascii_digit())
+			.unwrap_or(rest.len());
+		let token_len = rest[digits..]
+			.find(|c: char| c.is_ascii_digit() || c.is_whitespace())
+			.map_or(rest.len(), |i| digits + i);
+		let token = &rest[..token_len];
+		let (value, unit) = token.split_at(digits);
+
+		let Ok(value) = value.parse::<i64>() else {
+			return err!(
+				&UtlErr::InvalidDuration,
+				format!("bad token `{token}` in `{input}`")
+			);
+		};
+		let Some(index) = UNITS.iter().position(|(name, _)| *name == unit) else {
+			return err!(
+				&UtlErr::InvalidDuration,
+				format!("bad unit in `{token}` of `{input}`")
+			);
+		};
+		if last_unit.is_some_and(|last| last >= index) {
+			return err!(
+				&UtlErr::InvalidDuration,
+				format!("`{token}` repeated or out of order in `{input}`")
+			);
+		}
+		last_unit = Some(index);
+
+		let Some(sum) = value
+			.checked_mul(UNITS[index].1)
+			.and_then(|ms| total_ms.checked_add(ms))
+		else {
+			return err!(&UtlErr::InvalidDuration, format!("`{input}` overflows"));
+		};
+		total_ms = sum;
+		rest = rest[token_len..].trim_start();
+	}
+
+	match Duration::try_milliseconds(total_ms) {
+		Some(duration) => Ok(duration),
+		None => err!(&UtlErr::InvalidDuration, format!("`{input}` overflows")),
+	}
+}
+
+/// Compact form accepted by [`parse_duration`], e.g. `2h30m`, `1d`, `1s500ms`, `0s`
+pub fn humanize_duration(duration: Duration) -> String {
+	let ms = duration.num_milliseconds();
+	if ms == 0 {
+		return "0s".to_string();
+	}
+
+	let mut out = String::new();
+	if ms < 0 {
+		out.push('-');
+	}
+	let mut rest = ms.unsigned_abs();
+	for (name, unit_ms) in UNITS {
+		let count = rest / unit_ms as u64;
+		if count > 0 {
+			out.push_str(&format!("{count}{name}"));
+			rest %= unit_ms as u64;
+		}
+	}
+	out
+}
+
+/// `at` relative to `now` in its largest whole unit: `"in 2h"`, `"3d ago"`, `"just now"`
+pub fn format_relative(at: DateTime<Utc>, now: DateTime<Utc>) -> String {
+	let delta = at.signed_duration_since(now);
+	let secs = delta.num_seconds().unsigned_abs();
+	let (count, name) = match secs {
+		0 => return "just now".to_string(),
+		s if s >= 86_400 => (s / 86_400, "d"),
+		s if s >= 3_600 => (s / 3_600, "h"),
+		s if s >= 60 => (s / 60, "m"),
+		s => (s, "s"),
+	};
+	if delta > Duration::zero() {
+		format!("in {count}{name}")
+	} else {
+		format!("{count}{name} ago")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use base_infra::result::AppError;
+
+	fn parse_err(input: &str) -> String {
+		match parse_duration(input) {
+			Err(AppError::ExtCode(code, msg)) => {
+				assert_eq!(code.code(), "CHR006");
+				msg
+			}
+			other => panic!("`{input}` parsed as {other:?}"),
+		}
+	}
+
+	#[test]
+	fn test_parse_units() {
+		assert_eq!(parse_duration("1d").unwrap(), Duration::days(1));
+		assert_eq!(parse_duration("2h").unwrap(), Duration::hours(2));
+		assert_eq!(parse_duration("5m").unwrap(), Duration::minutes(5));
+		assert_eq!(parse_duration("30s").unwrap(), Duration::seconds(30));
+		assert_eq!(
+			parse_duration("250ms").unwrap(),
+			Duration::milliseconds(250)
+		);
+		assert_eq!(
+			parse_duration("2h30m").unwrap(),
+			Duration::hours(2) + Duration::minutes(30)
+		);
+		assert_eq!(
+			parse_duration(" 1d 12h 1s ").unwrap(),
+			Duration::hours(36) + Duration::seconds(1)
+		);
+		assert_eq!(parse_duration("0").unwrap(), Duration::zero());
+		assert_eq!(parse_duration("0s").unwrap(), Duration::zero());
+		assert_eq!(parse_duration("90m").unwrap(), Duration::minutes(90));
+	}
+
+	#[test]
+	fn test_parse_errors() {
+		assert!(parse_err("30m2h").contains("`2h`"));
+		assert!(parse_err("5m5m").contains("`5m`"));
+		assert!(parse_err("1s1m").contains("`1m`"));
+		assert!(parse_err("5x").contains("`5x`"));
+		assert!(parse_err("2h30").contains("`30`"));
+		assert!(parse_err("-5m").contains("`-`"));
+		assert!(parse_err("h").contains("`h`"));
+		assert!(parse_err("1.5h").contains("`1.`"));
+		parse_err("");
+		parse_err("99999999999999999999d");
+		parse_err("9999999999999d");
+	}
+
+	#[test]
+	fn test_humanize_round_trip() {
+		let cases = [
+			(Duration::zero(), "0s"),
+			(Duration::minutes(5), "5m"),
+			(Duration::minutes(150), "2h30m"),
+			(Duration::days(1), "1d"),
+			(Duration::milliseconds(1_500), "1s500ms"),
+			(Duration::hours(25) + Duration::seconds(7), "1d1h7s"),
+		];
+		for (duration, text) in cases {
+			assert_eq!(humanize_duration(duration), text);
+			assert_eq!(parse_duration(text).unwrap(), duration, "{text}");
+		}
+		assert_eq!(humanize_duration(-Duration::minutes(5)), "-5m");
+	}
+
+	#[test]
+	fn test_format_relative() {
+		let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+		let at = |d: Duration| now + d;
+		assert_eq!(format_relative(at(Duration::hours(2)), now), "in 2h");
+		assert_eq!(format_relative(at(-Duration::days(3)), now), "3d ago");
+		assert_eq!(format_relative(at(-Duration::minutes(3)), now), "3m ago");
+		assert_eq!(format_relative(at(Duration::seconds(59)), now), "in 59s");
+		assert_eq!(format_relative(at(Duration::minutes(119)), now), "in 1h");
+		assert_eq!(
+			format_relative(at(Duration::milliseconds(400)), now),
+			"just now"
+		);
+		assert_eq!(format_relative(now, now), "just now");
+	}
+}
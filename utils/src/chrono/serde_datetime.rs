@@ -1,5 +1,5 @@
-use crate::chrono::ts_to_naive_datetime;
-use chrono::NaiveDateTime;
+use crate::chrono::{humanize_duration, parse_duration, ts_to_naive_datetime};
+use chrono::{DateTime, Duration, NaiveDateTime, SecondsFormat, Utc};
 use serde::de::Error as DeError;
 use serde::{Deserialize, Deserializer, Serializer};
 use std::str::FromStr;
@@ -51,6 +51,171 @@ pub mod serde_option_naive_datetime {
 	}
 }
 
+/// `DateTime<Utc>` from RFC3339 strings, unix seconds or millis (by magnitude) and float
+/// seconds, serialized as RFC3339
+pub mod flexible {
+	use super::*;
+
+	pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&value.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		FlexibleInput::deserialize(deserializer)?.into_datetime()
+	}
+}
+
+/// [`flexible`] deserialization, serialized as unix millis
+pub mod as_millis {
+	use super::*;
+
+	pub fn serialize<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_i64(value.timestamp_millis())
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		flexible::deserialize(deserializer)
+	}
+}
+
+/// Optional [`flexible`], `null` and `""` read as `None`. Add `#[serde(default)]` to also
+/// accept an absent field.
+pub mod option_flexible {
+	use super::*;
+
+	pub fn serialize<S>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match value {
+			Some(dt) => flexible::serialize(dt, serializer),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		match Option::<FlexibleInput>::deserialize(deserializer)? {
+			Some(FlexibleInput::String(s)) if s.trim().is_empty() => Ok(None),
+			Some(input) => input.into_datetime().map(Some),
+			None => Ok(None),
+		}
+	}
+}
+
+/// Optional [`as_millis`], see [`option_flexible`]
+pub mod option_as_millis {
+	use super::*;
+
+	pub fn serialize<S>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		match value {
+			Some(dt) => as_millis::serialize(dt, serializer),
+			None => serializer.serialize_none(),
+		}
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		option_flexible::deserialize(deserializer)
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FlexibleInput {
+	Int(i64),
+	Float(f64),
+	String(String),
+}
+
+impl FlexibleInput {
+	fn into_datetime<E: DeError>(self) -> Result<DateTime<Utc>, E> {
+		match self {
+			FlexibleInput::Int(ts) => unix_to_datetime(ts),
+			FlexibleInput::Float(secs) => {
+				let whole = secs.floor();
+				let nanos = ((secs - whole) * 1e9).round().min(999_999_999.0) as u32;
+				(whole.is_finite() && whole.abs() < i64::MAX as f64)
+					.then(|| DateTime::from_timestamp(whole as i64, nanos))
+					.flatten()
+					.ok_or_else(|| DeError::custom(format!("invalid unix timestamp: {secs}")))
+			}
+			FlexibleInput::String(value) => {
+				let trimmed = value.trim();
+				if let Ok(ts) = trimmed.parse::<i64>() {
+					return unix_to_datetime(ts);
+				}
+				DateTime::parse_from_rfc3339(trimmed)
+					.map(|dt| dt.with_timezone(&Utc))
+					.map_err(|_| DeError::custom(format!("invalid RFC3339 datetime: {trimmed}")))
+			}
+		}
+	}
+}
+
+fn unix_to_datetime<E: DeError>(ts: i64) -> Result<DateTime<Utc>, E> {
+	ts_to_naive_datetime(ts)
+		.map(|dt| dt.and_utc())
+		.map_err(DeError::custom)
+}
+
+/// Durations as `"2h30m"` strings, see [`parse_duration`]. Integers are read as seconds.
+///
+/// ```ignore
+/// #[derive(Deserialize)]
+/// struct PoolConfig {
+///     #[serde(with = "serde_duration")]
+///     idle_timeout: chrono::Duration,
+/// }
+/// ```
+pub mod serde_duration {
+	use super::*;
+
+	pub fn serialize<S>(value: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&humanize_duration(*value))
+	}
+
+	pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		match DurationInput::deserialize(deserializer)? {
+			DurationInput::Secs(secs) => Duration::try_seconds(secs as i64)
+				.ok_or_else(|| DeError::custom(format!("duration out of range: {secs}s"))),
+			DurationInput::String(s) => parse_duration(&s).map_err(DeError::custom),
+		}
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DurationInput {
+	Secs(u32),
+	String(String),
+}
+
 #[derive(Deserialize)]
 #[serde(untagged)]
 pub enum TimestampInput {
@@ -100,3 +265,111 @@ where
 	let datetime = ts_to_naive_datetime(timestamp);
 	datetime.map_err(|_e| DeError::custom(format!("invalid unix timestamp: {timestamp}")))
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Debug, PartialEq, Serialize, Deserialize)]
+	struct Event {
+		#[serde(with = "flexible")]
+		at: DateTime<Utc>,
+		#[serde(with = "as_millis")]
+		at_ms: DateTime<Utc>,
+		#[serde(default, with = "option_flexible")]
+		until: Option<DateTime<Utc>>,
+		#[serde(default, with = "option_as_millis")]
+		until_ms: Option<DateTime<Utc>>,
+	}
+
+	fn at(json: &str) -> DateTime<Utc> {
+		let event: Event =
+			serde_json::from_str(&format!(r#"{{"at":{json},"at_ms":{json}}}"#)).unwrap();
+		assert_eq!(event.at, event.at_ms);
+		event.at
+	}
+
+	#[test]
+	fn test_flexible_shapes() {
+		let expected = DateTime::from_timestamp(1_734_947_195, 0).unwrap();
+		assert_eq!(at(r#""2024-12-23T09:46:35Z""#), expected);
+		assert_eq!(at(r#""2024-12-23T17:46:35+08:00""#), expected);
+		assert_eq!(at("1734947195"), expected);
+		assert_eq!(at("1734947195000"), expected);
+		assert_eq!(at(r#""1734947195""#), expected);
+		assert_eq!(at("1734947195.0"), expected);
+
+		let millis = DateTime::from_timestamp_millis(1_734_947_195_250).unwrap();
+		assert_eq!(at("1734947195250"), millis);
+		assert_eq!(at("1734947195.25"), millis);
+		assert_eq!(at(r#""2024-12-23T09:46:35.250Z""#), millis);
+
+		for bad in [
+			r#""yesterday""#,
+			r#""2024-12-23 09:46:35""#,
+			"true",
+			"1e300",
+		] {
+			let json = format!(r#"{{"at":{bad},"at_ms":0}}"#);
+			assert!(serde_json::from_str::<Event>(&json).is_err(), "{bad}");
+		}
+	}
+
+	#[test]
+	fn test_flexible_option() {
+		let event: Event = serde_json::from_str(r#"{"at":0,"at_ms":0}"#).unwrap();
+		assert_eq!((event.until, event.until_ms), (None, None));
+		let event: Event =
+			serde_json::from_str(r#"{"at":0,"at_ms":0,"until":null,"until_ms":""}"#).unwrap();
+		assert_eq!((event.until, event.until_ms), (None, None));
+
+		let event: Event = serde_json::from_str(
+			r#"{"at":0,"at_ms":0,"until":"2024-12-23T09:46:35Z","until_ms":1734947195000}"#,
+		)
+		.unwrap();
+		let expected = DateTime::from_timestamp(1_734_947_195, 0);
+		assert_eq!((event.until, event.until_ms), (expected, expected));
+	}
+
+	#[test]
+	fn test_flexible_round_trip() {
+		let event = Event {
+			at: DateTime::from_timestamp_millis(1_734_947_195_250).unwrap(),
+			at_ms: DateTime::from_timestamp(1_734_947_195, 0).unwrap(),
+			until: DateTime::from_timestamp(1_734_947_195, 123_456_789),
+			until_ms: None,
+		};
+		let json = serde_json::to_string(&event).unwrap();
+		assert_eq!(
+			json,
+			r#"{"at":"2024-12-23T09:46:35.250Z","at_ms":1734947195000,"until":"2024-12-23T09:46:35.123456789Z","until_ms":null}"#
+		);
+		let back: Event = serde_json::from_str(&json).unwrap();
+		assert_eq!(back, event);
+		assert_eq!(serde_json::to_string(&back).unwrap(), json);
+	}
+
+	#[derive(Debug, PartialEq, Serialize, Deserialize)]
+	struct PoolConfig {
+		#[serde(with = "serde_duration")]
+		idle_timeout: Duration,
+	}
+
+	#[test]
+	fn test_serde_duration() {
+		let config: PoolConfig = serde_json::from_str(r#"{"idle_timeout":"2h30m"}"#).unwrap();
+		assert_eq!(config.idle_timeout, Duration::minutes(150));
+		assert_eq!(
+			serde_json::to_string(&config).unwrap(),
+			r#"{"idle_timeout":"2h30m"}"#
+		);
+
+		let config: PoolConfig = serde_json::from_str(r#"{"idle_timeout":90}"#).unwrap();
+		assert_eq!(config.idle_timeout, Duration::seconds(90));
+
+		let err = serde_json::from_str::<PoolConfig>(r#"{"idle_timeout":"5m2h"}"#).unwrap_err();
+		assert!(err.to_string().contains("`2h`"), "{err}");
+		assert!(serde_json::from_str::<PoolConfig>(r#"{"idle_timeout":-1}"#).is_err());
+	}
+}
@@ -0,0 +1,168 @@
+use crate::error::UtlErr;
+use base_infra::nar_err;
+use base_infra::result::AppResult;
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+/// `|ts|` from here on is millis, before it seconds (year 33658 as seconds)
+const MILLIS_THRESHOLD: u64 = 1_000_000_000_000;
+/// Millis end at year 5138; `[1e14, 1e15)` is as likely late millis as early micros
+const MILLIS_LIMIT: u64 = 100_000_000_000_000;
+const MICROS_THRESHOLD: u64 = 1_000_000_000_000_000;
+/// Micros end at year 5138; `[1e17, 1e18)` is as likely late micros as early nanos
+const MICROS_LIMIT: u64 = 100_000_000_000_000_000;
+const NANOS_THRESHOLD: u64 = 1_000_000_000_000_000_000;
+
+/// Unit of a unix timestamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TsUnit {
+	/// Detected by magnitude, see [`TsUnit::detect`]
+	#[default]
+	Auto,
+	Secs,
+	Millis,
+	Micros,
+	Nanos,
+}
+
+impl TsUnit {
+	/// Unit of `timestamp` by its magnitude:
+	///
+	/// | `\|ts\|`         | unit   | dates       |
+	/// |----------------|--------|-------------|
+	/// | `< 1e12`       | secs   | up to 33658 |
+	/// | `[1e12, 1e14)` | millis | 2001 - 5138 |
+	/// | `[1e15, 1e17)` | micros | 2001 - 5138 |
+	/// | `>= 1e18`      | nanos  | 2001 - 2262 |
+	///
+	/// `None` for the ambiguous bands in between.
+	pub fn detect(timestamp: i64) -> Option<TsUnit> {
+		match timestamp.unsigned_abs() {
+			ts if ts < MILLIS_THRESHOLD => Some(TsUnit::Secs),
+			ts if ts < MILLIS_LIMIT => Some(TsUnit::Millis),
+			ts if ts < MICROS_THRESHOLD => None,
+			ts if ts < MICROS_LIMIT => Some(TsUnit::Micros),
+			ts if ts < NANOS_THRESHOLD => None,
+			_ => Some(TsUnit::Nanos),
+		}
+	}
+
+	/// The unit itself, or the detected one for [`TsUnit::Auto`]
+	pub fn resolve(self, timestamp: i64) -> Option<TsUnit> {
+		match self {
+			TsUnit::Auto => TsUnit::detect(timestamp),
+			unit => Some(unit),
+		}
+	}
+}
+
+/// [`ts_to_naive_datetime_with`] in [`TsUnit::Auto`]
+pub fn ts_to_naive_datetime(timestamp: i64) -> AppResult<NaiveDateTime> {
+	ts_to_naive_datetime_with(timestamp, TsUnit::Auto)
+}
+
+/// Errors on an ambiguous magnitude in [`TsUnit::Auto`] and on timestamps out of chrono's range
+pub fn ts_to_naive_datetime_with(timestamp: i64, unit: TsUnit) -> AppResult<NaiveDateTime> {
+	let invalid = || format!("{timestamp} ({unit:?})");
+	let datetime: Option<DateTime<Utc>> = match unit.resolve(timestamp) {
+		Some(TsUnit::Secs) => DateTime::from_timestamp(timestamp, 0),
+		Some(TsUnit::Millis) => DateTime::from_timestamp_millis(timestamp),
+		Some(TsUnit::Micros) => DateTime::from_timestamp_micros(timestamp),
+		Some(TsUnit::Nanos) => Some(DateTime::from_timestamp_nanos(timestamp)),
+		Some(TsUnit::Auto) | None => None,
+	};
+
+	datetime
+		.map(|dt| dt.naive_utc())
+		.ok_or_else(nar_err!(&UtlErr::InvalidTimestamp, invalid()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SECS: i64 = 1_734_947_195;
+
+	fn expected() -> NaiveDateTime {
+		DateTime::from_timestamp(SECS, 0).unwrap().naive_utc()
+	}
+
+	#[test]
+	fn test_detect_units() {
+		let cases = [
+			(SECS, TsUnit::Secs),
+			(SECS * 1_000, TsUnit::Millis),
+			(SECS * 1_000_000, TsUnit::Micros),
+			(SECS * 1_000_000_000, TsUnit::Nanos),
+		];
+		for (ts, unit) in cases {
+			assert_eq!(TsUnit::detect(ts), Some(unit), "{ts}");
+			assert_eq!(TsUnit::detect(-ts), Some(unit), "{ts}");
+			assert_eq!(ts_to_naive_datetime(ts).unwrap(), expected(), "{ts}");
+			assert_eq!(ts_to_naive_datetime_with(ts, unit).unwrap(), expected());
+		}
+
+		let millis = DateTime::from_timestamp_millis(SECS * 1_000 + 250).unwrap();
+		assert_eq!(
+			ts_to_naive_datetime(SECS * 1_000 + 250).unwrap(),
+			millis.naive_utc()
+		);
+		let micros = DateTime::from_timestamp_micros(SECS * 1_000_000 + 1).unwrap();
+		assert_eq!(
+			ts_to_naive_datetime(SECS * 1_000_000 + 1).unwrap(),
+			micros.naive_utc()
+		);
+		let nanos = DateTime::from_timestamp_nanos(SECS * 1_000_000_000 + 1);
+		assert_eq!(
+			ts_to_naive_datetime(SECS * 1_000_000_000 + 1).unwrap(),
+			nanos.naive_utc()
+		);
+	}
+
+	#[test]
+	fn test_detect_boundaries() {
+		let cases = [
+			(0, Some(TsUnit::Secs)),
+			(999_999_999_999, Some(TsUnit::Secs)),
+			(1_000_000_000_000, Some(TsUnit::Millis)),
+			(99_999_999_999_999, Some(TsUnit::Millis)),
+			(100_000_000_000_000, None),
+			(999_999_999_999_999, None),
+			(1_000_000_000_000_000, Some(TsUnit::Micros)),
+			(99_999_999_999_999_999, Some(TsUnit::Micros)),
+			(100_000_000_000_000_000, None),
+			(999_999_999_999_999_999, None),
+			(1_000_000_000_000_000_000, Some(TsUnit::Nanos)),
+			(i64::MAX, Some(TsUnit::Nanos)),
+			(i64::MIN, Some(TsUnit::Nanos)),
+		];
+		for (ts, unit) in cases {
+			assert_eq!(TsUnit::detect(ts), unit, "{ts}");
+			assert_eq!(ts_to_naive_datetime(ts).is_ok(), unit.is_some(), "{ts}");
+		}
+	}
+
+	#[test]
+	fn test_explicit_unit() {
+		// ambiguous in auto mode, fine once the unit is known
+		let ts = 500_000_000_000_000;
+		let err = ts_to_naive_datetime(ts).unwrap_err();
+		assert!(err.to_string().contains("500000000000000 (Auto)"), "{err}");
+		let micros = DateTime::from_timestamp_micros(ts).unwrap().naive_utc();
+		assert_eq!(
+			ts_to_naive_datetime_with(ts, TsUnit::Micros).unwrap(),
+			micros
+		);
+		assert_eq!(TsUnit::Auto.resolve(ts), None);
+		assert_eq!(TsUnit::Millis.resolve(ts), Some(TsUnit::Millis));
+
+		// secs below the millis threshold keep the old behavior
+		let secs = DateTime::from_timestamp(999_999_999_999, 0)
+			.unwrap()
+			.naive_utc();
+		assert_eq!(ts_to_naive_datetime(999_999_999_999).unwrap(), secs);
+
+		assert!(ts_to_naive_datetime_with(i64::MAX, TsUnit::Secs).is_err());
+		assert!(ts_to_naive_datetime_with(i64::MAX, TsUnit::Millis).is_err());
+		assert!(ts_to_naive_datetime_with(SECS, TsUnit::Nanos).is_ok());
+	}
+}
@@ -0,0 +1,270 @@
+use crate::chrono::date_util::DateRange;
+use crate::error::UtlErr;
+use base_infra::result::AppResult;
+use base_infra::{err, nar_err};
+use chrono::{
+	DateTime, Datelike, Days, Duration, LocalResult, Months, NaiveDate, NaiveDateTime, Offset,
+	TimeZone, Timelike, Utc,
+};
+
+/// Bucket sizes of [`bucket_of`], in local time of the timezone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BucketUnit {
+	Hour,
+	Day,
+	/// ISO week, starting Monday
+	Week,
+	Month,
+}
+
+/// Start of the `unit` bucket containing `ts`, local to `tz`
+///
+/// Local times are resolved with this policy:
+/// - a local time skipped by a DST gap moves forward by the gap length, so a bucket starting in
+///   the gap starts when the gap ends
+/// - an ambiguous local time, repeated by a DST fall back, resolves to its earlier instant,
+///   except hour buckets which follow the offset of `ts` so both repeated hours are kept
+pub fn bucket_of<Tz: TimeZone>(
+	ts: DateTime<Utc>,
+	unit: BucketUnit,
+	tz: &Tz,
+) -> AppResult<DateTime<Tz>> {
+	bucket_start(&ts.with_timezone(tz), unit).ok_or_else(nar_err!(
+		&UtlErr::InvalidDateRange,
+		format!("no {unit:?} bucket for {ts}")
+	))
+}
+
+fn bucket_start<Tz: TimeZone>(local: &DateTime<Tz>, unit: BucketUnit) -> Option<DateTime<Tz>> {
+	let date = local.date_naive();
+	let start = match unit {
+		// absolute subtraction keeps the offset of `local`
+		BucketUnit::Hour => {
+			let within = Duration::minutes(local.minute().into())
+				+ Duration::seconds(local.second().into())
+				+ Duration::nanoseconds(local.nanosecond().into());
+			return Some(local.clone() - within);
+		}
+		BucketUnit::Day => date,
+		BucketUnit::Week => date - Days::new(date.weekday().num_days_from_monday().into()),
+		BucketUnit::Month => date.with_day(1)?,
+	};
+	resolve_local(&local.timezone(), start.and_time(Default::default()))
+}
+
+/// Start of the bucket after the one starting at `start`
+fn next_bucket<Tz: TimeZone>(start: &DateTime<Tz>, unit: BucketUnit) -> Option<DateTime<Tz>> {
+	let date = start.date_naive();
+	let next_date = match unit {
+		BucketUnit::Hour => return bucket_start(&(start.clone() + Duration::hours(1)), unit),
+		BucketUnit::Day => date.checked_add_days(Days::new(1))?,
+		BucketUnit::Week => date.checked_add_days(Days::new(7))?,
+		BucketUnit::Month => date.with_day(1)?.checked_add_months(Months::new(1))?,
+	};
+	resolve_local(&start.timezone(), next_date.and_time(Default::default()))
+}
+
+/// See [`bucket_of`] for the policy
+fn resolve_local<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> Option<DateTime<Tz>> {
+	match tz.from_local_datetime(&local) {
+		LocalResult::Single(dt) => Some(dt),
+		LocalResult::Ambiguous(earliest, _) => Some(earliest),
+		LocalResult::None => {
+			// read with the offset in effect before the gap
+			let before = tz
+				.from_local_datetime(&(local - Duration::days(1)))
+				.earliest()?;
+			let offset = before.offset().fix();
+			let utc = local.checked_sub_signed(Duration::seconds(offset.local_minus_utc().into()))?;
+			Some(tz.from_utc_datetime(&utc))
+		}
+	}
+}
+
+/// Local bucket boundaries from [`DateRange::days`] / [`DateRange::hours`]
+#[derive(Debug, Clone)]
+pub struct TzRange<Tz: TimeZone> {
+	next: Option<DateTime<Tz>>,
+	end: DateTime<Utc>,
+	unit: BucketUnit,
+}
+
+impl<Tz: TimeZone> Iterator for TzRange<Tz> {
+	type Item = DateTime<Tz>;
+
+	fn next(&mut self) -> Option<DateTime<Tz>> {
+		let current = self.next.take().filter(|dt| *dt < self.end)?;
+		self.next = next_bucket(&current, self.unit);
+		Some(current)
+	}
+}
+
+impl DateRange {
+	/// Start of each local day of `tz` from `start` (inclusive) to `end` (exclusive)
+	///
+	/// A day whose midnight falls in a DST gap starts when the gap ends, see [`bucket_of`]
+	pub fn days<Tz: TimeZone>(start: NaiveDate, end: NaiveDate, tz: &Tz) -> AppResult<TzRange<Tz>> {
+		if start > end {
+			return err!(
+				&UtlErr::InvalidDateRange,
+				format!("start {start} is after end {end}")
+			);
+		}
+		let midnight = |date: NaiveDate| {
+			resolve_local(tz, date.and_time(Default::default()))
+				.ok_or_else(nar_err!(&UtlErr::InvalidDateRange, date))
+		};
+		Ok(TzRange {
+			next: Some(midnight(start)?),
+			end: midnight(end)?.with_timezone(&Utc),
+			unit: BucketUnit::Day,
+		})
+	}
+
+	/// Start of each local hour of `tz` overlapping `start..end`
+	///
+	/// Hours skipped by a DST spring forward are absent, hours repeated by a fall back are
+	/// yielded twice with their distinct offsets
+	pub fn hours<Tz: TimeZone>(
+		start: DateTime<Utc>,
+		end: DateTime<Utc>,
+		tz: &Tz,
+	) -> AppResult<TzRange<Tz>> {
+		if start > end {
+			return err!(
+				&UtlErr::InvalidDateRange,
+				format!("start {start} is after end {end}")
+			);
+		}
+		Ok(TzRange {
+			next: Some(bucket_of(start, BucketUnit::Hour, tz)?),
+			end,
+			unit: BucketUnit::Hour,
+		})
+	}
+}
+
+#[cfg(all(test, feature = "tz"))]
+mod tests {
+	use super::*;
+	use chrono_tz::{America, Asia, Tz};
+
+	fn utc(s: &str) -> DateTime<Utc> {
+		s.parse().unwrap()
+	}
+
+	fn date(s: &str) -> NaiveDate {
+		s.parse().unwrap()
+	}
+
+	fn rfc3339(dts: impl Iterator<Item = DateTime<Tz>>) -> Vec<String> {
+		dts.map(|dt| dt.to_rfc3339()).collect()
+	}
+
+	#[test]
+	fn test_days_shanghai() {
+		let days = DateRange::days(date("2024-01-30"), date("2024-02-02"), &Asia::Shanghai).unwrap();
+		assert_eq!(
+			rfc3339(days),
+			[
+				"2024-01-30T00:00:00+08:00",
+				"2024-01-31T00:00:00+08:00",
+				"2024-02-01T00:00:00+08:00"
+			]
+		);
+		assert!(DateRange::days(date("2024-02-02"), date("2024-01-30"), &Asia::Shanghai).is_err());
+	}
+
+	#[test]
+	fn test_days_across_dst() {
+		let ny = America::New_York;
+		let days = DateRange::days(date("2024-03-09"), date("2024-03-12"), &ny).unwrap();
+		let days: Vec<_> = days.collect();
+		assert_eq!(
+			rfc3339(days.iter().cloned()),
+			[
+				"2024-03-09T00:00:00-05:00",
+				"2024-03-10T00:00:00-05:00",
+				"2024-03-11T00:00:00-04:00"
+			]
+		);
+		// the spring forward day is 23h long
+		assert_eq!(days[2].clone() - days[1].clone(), Duration::hours(23));
+
+		// Sao Paulo skipped midnight on 2018-11-04, the day starts at 01:00
+		let sp = America::Sao_Paulo;
+		let days = DateRange::days(date("2018-11-03"), date("2018-11-05"), &sp).unwrap();
+		assert_eq!(
+			rfc3339(days),
+			["2018-11-03T00:00:00-03:00", "2018-11-04T01:00:00-02:00"]
+		);
+	}
+
+	#[test]
+	fn test_hours_spring_forward() {
+		// 2024-03-10 02:00 EST jumps to 03:00 EDT in New York
+		let hours = DateRange::hours(
+			utc("2024-03-10T05:30:00Z"),
+			utc("2024-03-10T08:00:00Z"),
+			&America::New_York,
+		)
+		.unwrap();
+		assert_eq!(
+			rfc3339(hours),
+			[
+				"2024-03-10T00:00:00-05:00",
+				"2024-03-10T01:00:00-05:00",
+				"2024-03-10T03:00:00-04:00"
+			]
+		);
+	}
+
+	#[test]
+	fn test_hours_fall_back() {
+		// 2024-11-03 01:00-02:00 happens twice in New York
+		let hours = DateRange::hours(
+			utc("2024-11-03T04:00:00Z"),
+			utc("2024-11-03T08:00:00Z"),
+			&America::New_York,
+		)
+		.unwrap();
+		assert_eq!(
+			rfc3339(hours),
+			[
+				"2024-11-03T00:00:00-04:00",
+				"2024-11-03T01:00:00-04:00",
+				"2024-11-03T01:00:00-05:00",
+				"2024-11-03T02:00:00-05:00"
+			]
+		);
+	}
+
+	#[test]
+	fn test_bucket_of() {
+		let ny = America::New_York;
+		let ts = utc("2024-11-03T06:15:00Z"); // 01:15 EST, the second 01:xx
+		assert_eq!(
+			bucket_of(ts, BucketUnit::Hour, &ny).unwrap().to_rfc3339(),
+			"2024-11-03T01:00:00-05:00"
+		);
+		assert_eq!(
+			bucket_of(ts, BucketUnit::Day, &ny).unwrap().to_rfc3339(),
+			"2024-11-03T00:00:00-04:00"
+		);
+		assert_eq!(
+			bucket_of(ts, BucketUnit::Week, &ny).unwrap().to_rfc3339(),
+			"2024-10-28T00:00:00-04:00"
+		);
+		assert_eq!(
+			bucket_of(ts, BucketUnit::Month, &ny).unwrap().to_rfc3339(),
+			"2024-11-01T00:00:00-04:00"
+		);
+
+		// same instant, different local day
+		let ts = utc("2024-01-31T20:00:00Z");
+		let sh = bucket_of(ts, BucketUnit::Day, &Asia::Shanghai).unwrap();
+		assert_eq!(sh.to_rfc3339(), "2024-02-01T00:00:00+08:00");
+		let kolkata = bucket_of(ts, BucketUnit::Hour, &Asia::Kolkata).unwrap();
+		assert_eq!(kolkata.to_rfc3339(), "2024-02-01T01:00:00+05:30");
+	}
+}
@@ -2,6 +2,12 @@ base_infra::gen_impl_code_enum! {
 	UtlErr {
 		BigDecToF32= ("BGN001", "Failed to convert BigDecimal to f32"),
 		BigDecToF64= ("BGN002", "Failed to convert BigDecimal to f64"),
+		DivisionByZero= ("BGN003", "BigDecimal division by zero"),
+		NegativeAmount= ("BGN004", "Negative amount"),
+		PrecisionLoss= ("BGN005", "Amount would lose precision"),
+		U256Overflow= ("BGN006", "Amount overflows U256"),
+		InvalidRate= ("BGN007", "Invalid bps / percent"),
+		BigDecToUint= ("BGN008", "Failed to convert BigDecimal to unsigned integer"),
 
 		// chrono
 		InvalidTimestamp = ("CHR000", "Invalid timestamp"),
@@ -10,6 +16,8 @@ base_infra::gen_impl_code_enum! {
 		TimestampToDate = ("CHR002", "Failed to parse DateTime from timestamp"),
 		LocalDtNotExistDstGap = ("CHR003", "local time does not exist (DST gap)"),
 		TruncateDateTime = ("CHR004", "Valid DateTime when truncating to "),
+		InvalidDateRange = ("CHR005", "Invalid date range"),
+		InvalidDuration = ("CHR006", "Invalid duration"),
 
 	}
 }
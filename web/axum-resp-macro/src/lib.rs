@@ -1,27 +1,97 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{ItemFn, ReturnType, Type, TypePath, parse_macro_input};
+use syn::{
+	GenericArgument, Ident, ItemFn, LitInt, PathArguments, ReturnType, Type, TypePath,
+	parse_macro_input,
+};
 
+/// Result aliases accepted without a `result = ..` argument
+const RESULT_ALIASES: [&str; 2] = ["AppResult", "AxumResult"];
+/// Error types accepted in `Result<T, E>`
+const ERROR_TYPES: [&str; 2] = ["AppError", "AxumError"];
+
+/// Wraps a handler returning `AppResult<T>` into `AxumResult<impl IntoResponse>` with a
+/// `RespData` json body.
+///
+/// Accepted return types: `AppResult<T>`, `AxumResult<T>`, `Result<T, AppError>`,
+/// `Result<T, AxumError>`, and one-parameter aliases named by `#[resp_data(result = MyResult)]`.
+///
+/// `#[resp_data(status = 201)]` changes the success status, and headers from
+/// `web_infra::result::WithHeaders` are added when `T` implements it.
+///
+/// `#[resp_data(page)]` requires `T` to be `PageResp<U>` and flattens it into
+/// `{ code, msg, data: { items, page, size, total } }`.
 #[proc_macro_attribute]
-pub fn resp_data(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn resp_data(args: TokenStream, input: TokenStream) -> TokenStream {
+	let mut aliases: Vec<Ident> = Vec::new();
+	let mut status: u16 = 200;
+	let mut page = false;
+	let args_parser = syn::meta::parser(|meta| {
+		if meta.path.is_ident("result") {
+			let alias: syn::Path = meta.value()?.parse()?;
+			match alias.segments.last() {
+				Some(seg) => aliases.push(seg.ident.clone()),
+				None => return Err(meta.error("expected a result type alias")),
+			}
+			Ok(())
+		} else if meta.path.is_ident("status") {
+			let lit: LitInt = meta.value()?.parse()?;
+			status = lit.base10_parse()?;
+			if !(100..=599).contains(&status) {
+				return Err(syn::Error::new_spanned(lit, "status must be in 100..=599"));
+			}
+			Ok(())
+		} else if meta.path.is_ident("page") {
+			page = true;
+			Ok(())
+		} else {
+			Err(meta.error(
+				"unsupported resp_data argument, expected `result = MyResult`, `status = 201` or `page`",
+			))
+		}
+	});
+	parse_macro_input!(args with args_parser);
+
 	let mut fnc = parse_macro_input!(input as ItemFn);
 
 	// 1. Resolve return type AppResult<T>
-	let inner_ty = match parse_return_type(&fnc) {
-		Ok(inner) => inner,
+	let (output_ty, inner_ty) = match parse_return_type(&fnc, &aliases) {
+		Ok(ty) => ty,
 		Err(err) => return err.to_compile_error().into(),
 	};
+	if page && !is_page_resp(&inner_ty) {
+		return syn::Error::new_spanned(&inner_ty, "#[resp_data(page)] requires PageResp<T> data")
+			.to_compile_error()
+			.into();
+	}
+	let to_body = if page {
+		quote! { let res = ::web_infra::result::pagination::PageData::from(res); }
+	} else {
+		quote! {}
+	};
 
 	// 2. Modify the return type to  AxumResult<impl IntoResponse>
 	fnc.sig.output = syn::parse_quote! {
 		-> ::web_infra::result::AxumResult<impl ::axum::response::IntoResponse>
 	};
 
-	// 3. Wrap the function body
+	// 3. Wrap the function body, the original return type drives `?` conversions inside it
 	let block = fnc.block;
 	fnc.block = syn::parse_quote!({
-		let res: #inner_ty = (async #block).await?;
-		::web_infra::success!(res)
+		let res: #inner_ty = (async {
+			let res: #output_ty = #block;
+			res
+		})
+		.await?;
+		#to_body
+		let headers = {
+			#[allow(unused_imports)]
+			use ::web_infra::result::__private::{ViaNoHeaders as _, ViaWithHeaders as _};
+			(&::web_infra::result::__private::HeadersProbe(&res)).resp_headers()
+		};
+		let status = ::axum::http::StatusCode::from_u16(#status)
+			.expect("status checked by resp_data");
+		::web_infra::success!(res).map(|body| (status, headers, body))
 	});
 
 	// output
@@ -30,55 +100,95 @@ pub fn resp_data(_args: TokenStream, input: TokenStream) -> TokenStream {
 	})
 }
 
-fn parse_return_type(fnc: &ItemFn) -> Result<Type, syn::Error> {
+fn is_page_resp(ty: &Type) -> bool {
+	match ty {
+		Type::Path(tp) => tp
+			.path
+			.segments
+			.last()
+			.is_some_and(|seg| seg.ident == "PageResp"),
+		_ => false,
+	}
+}
+
+fn parse_return_type(fnc: &ItemFn, aliases: &[Ident]) -> Result<(Type, Type), syn::Error> {
 	let output = match &fnc.sig.output {
 		ReturnType::Type(_, ty) => ty,
 		_ => {
 			return Err(syn::Error::new_spanned(
-				&fnc.sig.output,
+				&fnc.sig,
 				"resp_data requires a return type like AppResult<T>",
 			));
 		}
 	};
 
 	let output: &Type = output;
-	match output {
-		Type::Path(tp) => unwrap_app_result(output, tp),
-		_ => Err(syn::Error::new_spanned(
-			output,
-			"Return type must be AppResult<T>",
-		)),
-	}
+	let inner = match output {
+		Type::Path(tp) => unwrap_app_result(output, tp, aliases)?,
+		_ => {
+			return Err(syn::Error::new_spanned(
+				output,
+				"Return type must be AppResult<T>",
+			));
+		}
+	};
+	Ok((output.clone(), inner))
 }
 
-/// resolve AppResult<T> ---
-fn unwrap_app_result(output: &Type, tp: &TypePath) -> Result<Type, syn::Error> {
+/// resolve AppResult<T>, its aliases and Result<T, AppError> ---
+fn unwrap_app_result(output: &Type, tp: &TypePath, aliases: &[Ident]) -> Result<Type, syn::Error> {
 	let segment = tp.path.segments.last().unwrap();
-	if segment.ident != "AppResult" {
+	let is_alias =
+		RESULT_ALIASES.iter().any(|a| segment.ident == a) || aliases.contains(&segment.ident);
+	let is_result = segment.ident == "Result";
+	if !is_alias && !is_result {
 		return Err(syn::Error::new_spanned(
 			output,
-			"Return type must be AppResult<T>",
+			"Return type must be AppResult<T> or Result<T, AppError>, \
+			 other aliases need #[resp_data(result = MyResult)]",
 		));
 	}
-	match &segment.arguments {
-		syn::PathArguments::AngleBracketed(ab) => {
-			if ab.args.len() != 1 {
+
+	let args = match &segment.arguments {
+		PathArguments::AngleBracketed(ab) => ab,
+		_ => {
+			return Err(syn::Error::new_spanned(
+				output,
+				format!("{} must have generic parameter", segment.ident),
+			));
+		}
+	};
+
+	if is_alias && args.args.len() != 1 {
+		return Err(syn::Error::new_spanned(
+			output,
+			format!(
+				"{}<T> must have exactly one generic parameter",
+				segment.ident
+			),
+		));
+	}
+	if is_result {
+		let err_ty = match args.args.iter().nth(1) {
+			Some(GenericArgument::Type(Type::Path(err))) if args.args.len() == 2 => err,
+			_ => {
 				return Err(syn::Error::new_spanned(
-					ab,
-					"AppResult<T> must have exactly one generic parameter",
+					output,
+					"Result must be Result<T, AppError> or Result<T, AxumError>",
 				));
 			}
-
-			let inner_ty = ab.args.first().unwrap();
-			if let syn::GenericArgument::Type(t) = inner_ty {
-				Ok(t.clone())
-			} else {
-				Err(syn::Error::new_spanned(inner_ty, "Invalid generic type"))
-			}
+		};
+		let err_name = &err_ty.path.segments.last().unwrap().ident;
+		if !ERROR_TYPES.iter().any(|e| err_name == e) {
+			return Err(syn::Error::new_spanned(
+				err_ty,
+				"Result error type must be AppError or AxumError",
+			));
 		}
-		_ => Err(syn::Error::new_spanned(
-			segment,
-			"AppResult<T> must have generic parameter",
-		)),
+	}
+
+	match args.args.first().unwrap() {
+		GenericArgument::Type(t) => Ok(t.clone()),
+		other => Err(syn::Error::new_spanned(other, "Invalid generic type")),
 	}
 }
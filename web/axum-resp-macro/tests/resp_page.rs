@@ -0,0 +1,30 @@
+use axum::response::IntoResponse;
+use axum_resp_macro::resp_data;
+use base_infra::result::AppResult;
+use serde::Serialize;
+use web_infra::result::pagination::{PageResp, Pagination};
+
+#[derive(Debug, Serialize)]
+struct User {
+	id: u64,
+}
+
+#[resp_data(page)]
+async fn list_users() -> AppResult<PageResp<User>> {
+	let users = vec![User { id: 3 }, User { id: 4 }];
+	Ok(PageResp::new(users, Pagination::new(2, 2, 5, 3)))
+}
+
+#[tokio::test]
+async fn test_page_envelope() {
+	let resp = list_users().await.unwrap().into_response();
+	let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+		.await
+		.unwrap();
+	let body = String::from_utf8(body.to_vec()).unwrap();
+	assert!(body.starts_with(r#"{"code":"#), "{body}");
+	assert!(
+		body.ends_with(r#""data":{"items":[{"id":3},{"id":4}],"page":2,"size":2,"total":5}}"#),
+		"{body}"
+	);
+}
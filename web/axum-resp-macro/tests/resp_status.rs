@@ -0,0 +1,60 @@
+use axum::http::{HeaderMap, HeaderValue, StatusCode, header};
+use axum::response::IntoResponse;
+use axum_resp_macro::resp_data;
+use base_infra::result::AppResult;
+use serde::Serialize;
+use web_infra::result::WithHeaders;
+
+#[derive(Debug, Serialize)]
+struct Created {
+	id: u64,
+}
+
+impl WithHeaders for Created {
+	fn headers(&self) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		let location = format!("/users/{}", self.id);
+		headers.insert(header::LOCATION, HeaderValue::from_str(&location).unwrap());
+		headers
+	}
+}
+
+#[derive(Debug, Serialize)]
+struct User {
+	name: String,
+}
+
+#[resp_data(status = 201)]
+async fn create_user() -> AppResult<Created> {
+	Ok(Created { id: 7 })
+}
+
+#[resp_data]
+async fn get_user() -> AppResult<User> {
+	Ok(User {
+		name: "alice".to_string(),
+	})
+}
+
+async fn body_json(resp: axum::response::Response) -> String {
+	let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+		.await
+		.unwrap();
+	String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_created_status_and_location() {
+	let resp = create_user().await.unwrap().into_response();
+	assert_eq!(resp.status(), StatusCode::CREATED);
+	assert_eq!(resp.headers()[header::LOCATION], "/users/7");
+	assert!(body_json(resp).await.contains(r#""id":7"#));
+}
+
+#[tokio::test]
+async fn test_default_status() {
+	let resp = get_user().await.unwrap().into_response();
+	assert_eq!(resp.status(), StatusCode::OK);
+	assert!(!resp.headers().contains_key(header::LOCATION));
+	assert!(body_json(resp).await.contains("alice"));
+}
@@ -0,0 +1,8 @@
+use axum_resp_macro::resp_data;
+
+#[resp_data]
+async fn handler() -> Result<u8, String> {
+	Ok(1)
+}
+
+fn main() {}
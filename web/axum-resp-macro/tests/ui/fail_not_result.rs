@@ -0,0 +1,13 @@
+use axum_resp_macro::resp_data;
+
+#[resp_data]
+async fn handler() -> (u8, u8) {
+	(1, 2)
+}
+
+#[resp_data(alias = MyResult)]
+async fn bad_arg() -> base_infra::result::AppResult<u8> {
+	Ok(1)
+}
+
+fn main() {}
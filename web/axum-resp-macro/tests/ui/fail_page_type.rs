@@ -0,0 +1,8 @@
+use axum_resp_macro::resp_data;
+
+#[resp_data(page)]
+async fn list_users() -> base_infra::result::AppResult<Vec<u8>> {
+	Ok(vec![1])
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use axum_resp_macro::resp_data;
+
+#[resp_data]
+async fn handler() -> MyResult<u8> {
+	Ok(1)
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+use axum_resp_macro::resp_data;
+use base_infra::result::{AppError, AppResult};
+use web_infra::result::AxumError;
+
+pub type MyResult<T> = AppResult<T>;
+
+#[resp_data]
+async fn app_result() -> AppResult<u8> {
+	Ok(1)
+}
+
+#[resp_data]
+async fn qualified() -> base_infra::result::AppResult<u8> {
+	Ok(1)
+}
+
+#[resp_data]
+async fn std_result() -> Result<u8, AppError> {
+	Ok(1)
+}
+
+#[resp_data]
+async fn axum_result() -> std::result::Result<u8, AxumError> {
+	Ok(1)
+}
+
+#[resp_data(result = MyResult)]
+async fn alias() -> MyResult<u8> {
+	Ok(1)
+}
+
+fn main() {
+	let _ = (app_result, qualified, std_result, axum_result, alias);
+}
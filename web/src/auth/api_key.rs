@@ -0,0 +1,399 @@
+use crate::result::{AxumError, WebErr};
+use arc_swap::ArcSwap;
+use axum::extract::{FromRequestParts, Request};
+use axum::response::{IntoResponse, Response};
+use base_infra::result::{AppError, AppResult, DynErrCode};
+use base_infra::{map_err, nar_err};
+use http::request::Parts;
+use http::{HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+use tracing::{debug, info, warn};
+
+/// Header carrying the raw api key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Identity attached to a request once its api key is accepted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyInfo {
+	/// Key owner, safe to log
+	pub name: String,
+	/// Scopes granted to the key
+	#[serde(default)]
+	pub scopes: Vec<String>,
+	/// Rate limit bucket tag, falls back to `name`
+	#[serde(default)]
+	pub rate_tag: Option<String>,
+}
+
+impl KeyInfo {
+	pub fn has_scope(&self, scope: &str) -> bool {
+		self.scopes.iter().any(|s| s == scope)
+	}
+
+	pub fn rate_tag(&self) -> &str {
+		self.rate_tag.as_deref().unwrap_or(&self.name)
+	}
+}
+
+/// Api key config, keyed by the hex encoded sha256 of the raw key
+///
+/// ```yaml
+/// api_keys:
+///   keys:
+///     "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08":
+///       name: billing
+///       scopes: ["orders:read"]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+	#[serde(default)]
+	pub keys: HashMap<String, KeyInfo>,
+}
+
+/// Hex encoded sha256 of a raw api key, the form stored in [`ApiKeyConfig`]
+pub fn hash_api_key(raw: &str) -> String {
+	hex::encode(Sha256::digest(raw.as_bytes()))
+}
+
+struct KeyRegistry {
+	entries: Vec<([u8; 32], KeyInfo)>,
+}
+
+impl KeyRegistry {
+	fn build(config: &ApiKeyConfig) -> AppResult<Self> {
+		let mut entries = Vec::with_capacity(config.keys.len());
+		for (hash, info) in &config.keys {
+			let msg = format!("key hash of `{}`", info.name);
+			let bytes = hex::decode(hash).map_err(map_err!(&WebErr::ApiKeyConfigErr, msg))?;
+			let digest = <[u8; 32]>::try_from(bytes.as_slice())
+				.ok()
+				.ok_or_else(nar_err!(
+					&WebErr::ApiKeyConfigErr,
+					"sha256 must be 32 bytes"
+				))?;
+			entries.push((digest, info.clone()));
+		}
+		Ok(Self { entries })
+	}
+
+	/// Compares against every entry so timing doesn't depend on which key matched
+	fn lookup(&self, raw: &str) -> Option<&KeyInfo> {
+		let digest: [u8; 32] = Sha256::digest(raw.as_bytes()).into();
+		let mut found = None;
+		for (hash, info) in &self.entries {
+			if constant_time_eq(hash, &digest) & found.is_none() {
+				found = Some(info);
+			}
+		}
+		found
+	}
+}
+
+fn constant_time_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+	a.iter()
+		.zip(b.iter())
+		.fold(0u8, |acc, (x, y)| acc | (x ^ y))
+		== 0
+}
+
+/// Shared, hot reloadable api key registry
+#[derive(Clone)]
+pub struct ApiKeyStore {
+	registry: Arc<ArcSwap<KeyRegistry>>,
+}
+
+impl ApiKeyStore {
+	pub fn new(config: &ApiKeyConfig) -> AppResult<Self> {
+		let registry = KeyRegistry::build(config)?;
+		Ok(Self {
+			registry: Arc::new(ArcSwap::from_pointee(registry)),
+		})
+	}
+
+	/// Swap in a new key set, in-flight requests keep the old one
+	pub fn reload(&self, config: &ApiKeyConfig) -> AppResult<()> {
+		let registry = KeyRegistry::build(config)?;
+		info!(
+			"Api key config reloaded with {} keys",
+			registry.entries.len()
+		);
+		self.registry.store(Arc::new(registry));
+		Ok(())
+	}
+
+	pub fn len(&self) -> usize {
+		self.registry.load().entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	fn authenticate(&self, headers: &HeaderMap) -> Result<KeyInfo, AxumError> {
+		let Some(raw) = headers
+			.get(API_KEY_HEADER)
+			.and_then(|v| v.to_str().ok())
+			.filter(|v| !v.is_empty())
+		else {
+			warn!("Request rejected: {}", WebErr::ApiKeyMissing);
+			return Err(http_err(&WebErr::ApiKeyMissing, StatusCode::UNAUTHORIZED));
+		};
+
+		match self.registry.load().lookup(raw) {
+			Some(info) => {
+				debug!(api_key = %info.name, "Api key accepted");
+				Ok(info.clone())
+			}
+			None => {
+				warn!("Request rejected: {}", WebErr::ApiKeyInvalid);
+				Err(http_err(&WebErr::ApiKeyInvalid, StatusCode::UNAUTHORIZED))
+			}
+		}
+	}
+}
+
+fn http_err(code: &'static DynErrCode, status: StatusCode) -> AxumError {
+	AxumError::AppError(AppError::HttpErr(code, status))
+}
+
+/// Checks `x-api-key` against the store and injects [`KeyInfo`] into the request extensions
+///
+/// `Router::new().route(..).layer(api_key_layer(store))`
+pub fn api_key_layer(store: ApiKeyStore) -> ApiKeyLayer {
+	ApiKeyLayer { store }
+}
+
+#[derive(Clone)]
+pub struct ApiKeyLayer {
+	store: ApiKeyStore,
+}
+
+impl<S> Layer<S> for ApiKeyLayer {
+	type Service = ApiKeyService<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		ApiKeyService {
+			inner,
+			store: self.store.clone(),
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct ApiKeyService<S> {
+	inner: S,
+	store: ApiKeyStore,
+}
+
+impl<S> Service<Request> for ApiKeyService<S>
+where
+	S: Service<Request, Response = Response>,
+	S::Future: Send + 'static,
+{
+	type Response = Response;
+	type Error = S::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
+
+	fn call(&mut self, mut req: Request) -> Self::Future {
+		match self.store.authenticate(req.headers()) {
+			Ok(info) => {
+				req.extensions_mut().insert(info);
+				Box::pin(self.inner.call(req))
+			}
+			Err(err) => Box::pin(async move { Ok(err.into_response()) }),
+		}
+	}
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for KeyInfo {
+	type Rejection = AxumError;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		parts
+			.extensions
+			.get::<KeyInfo>()
+			.cloned()
+			.ok_or_else(|| http_err(&WebErr::ApiKeyMissing, StatusCode::UNAUTHORIZED))
+	}
+}
+
+/// Scope required by a [`Scope`] extractor, declare with [`api_scope!`](crate::api_scope)
+pub trait ApiScope {
+	const SCOPE: &'static str;
+}
+
+/// Extracts the caller's [`KeyInfo`], rejecting with 403 when it lacks `T::SCOPE`
+///
+/// ```ignore
+/// api_scope!(OrdersRead = "orders:read");
+///
+/// async fn list_orders(Scope(key, _): Scope<OrdersRead>) -> AxumResult<..> { .. }
+/// ```
+pub struct Scope<T: ApiScope>(pub KeyInfo, pub PhantomData<T>);
+
+impl<S: Send + Sync, T: ApiScope> FromRequestParts<S> for Scope<T> {
+	type Rejection = AxumError;
+
+	async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+		let info = KeyInfo::from_request_parts(parts, state).await?;
+		if !info.has_scope(T::SCOPE) {
+			warn!(api_key = %info.name, scope = T::SCOPE, "{}", WebErr::ApiKeyScopeDenied);
+			return Err(http_err(&WebErr::ApiKeyScopeDenied, StatusCode::FORBIDDEN));
+		}
+		Ok(Scope(info, PhantomData))
+	}
+}
+
+/// Declare a marker type for the [`Scope`] extractor
+///
+/// `api_scope!(OrdersRead = "orders:read");`
+#[macro_export]
+macro_rules! api_scope {
+	($name:ident = $scope:expr) => {
+		pub struct $name;
+
+		impl $crate::auth::ApiScope for $name {
+			const SCOPE: &'static str = $scope;
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::http::http_trace;
+	use crate::test_util::LogBuf;
+	use axum::Router;
+	use axum::body::Body;
+	use axum::middleware::from_fn;
+	use axum::routing::get;
+	use tower::ServiceExt;
+
+	const RAW_KEY: &str = "sk-test-0123456789abcdef";
+
+	api_scope!(OrdersRead = "orders:read");
+	api_scope!(OrdersWrite = "orders:write");
+
+	fn config(raw: &str, scopes: &[&str]) -> ApiKeyConfig {
+		let info = KeyInfo {
+			name: "billing".to_string(),
+			scopes: scopes.iter().map(|s| s.to_string()).collect(),
+			rate_tag: None,
+		};
+		ApiKeyConfig {
+			keys: HashMap::from([(hash_api_key(raw), info)]),
+		}
+	}
+
+	fn app(store: ApiKeyStore) -> Router {
+		Router::new()
+			.route("/api/whoami", get(|key: KeyInfo| async move { key.name }))
+			.route(
+				"/api/orders",
+				get(|Scope(key, _): Scope<OrdersRead>| async move { key.name }),
+			)
+			.route(
+				"/api/orders/new",
+				get(|Scope(key, _): Scope<OrdersWrite>| async move { key.name }),
+			)
+			.layer(api_key_layer(store))
+			.layer(from_fn(http_trace))
+	}
+
+	async fn call(app: Router, uri: &str, key: Option<&str>) -> (StatusCode, String) {
+		let mut req = Request::builder().uri(uri);
+		if let Some(key) = key {
+			req = req.header(API_KEY_HEADER, key);
+		}
+		let resp = app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap();
+		let status = resp.status();
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		(status, String::from_utf8(body.to_vec()).unwrap())
+	}
+
+	#[tokio::test]
+	async fn test_valid_key() {
+		let store = ApiKeyStore::new(&config(RAW_KEY, &[])).unwrap();
+		let (status, body) = call(app(store), "/api/whoami", Some(RAW_KEY)).await;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(body, "billing");
+	}
+
+	#[tokio::test]
+	async fn test_invalid_and_missing_key() {
+		let store = ApiKeyStore::new(&config(RAW_KEY, &[])).unwrap();
+
+		let (status, body) = call(app(store.clone()), "/api/whoami", Some("sk-wrong")).await;
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+		let resp: serde_json::Value = serde_json::from_str(&body).unwrap();
+		assert_eq!(resp["code"], "AUTH02");
+
+		let (status, body) = call(app(store), "/api/whoami", None).await;
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+		let resp: serde_json::Value = serde_json::from_str(&body).unwrap();
+		assert_eq!(resp["code"], "AUTH01");
+	}
+
+	#[tokio::test]
+	async fn test_scope_enforcement() {
+		let store = ApiKeyStore::new(&config(RAW_KEY, &["orders:read"])).unwrap();
+
+		let (status, _) = call(app(store.clone()), "/api/orders", Some(RAW_KEY)).await;
+		assert_eq!(status, StatusCode::OK);
+
+		let (status, body) = call(app(store), "/api/orders/new", Some(RAW_KEY)).await;
+		assert_eq!(status, StatusCode::FORBIDDEN);
+		let resp: serde_json::Value = serde_json::from_str(&body).unwrap();
+		assert_eq!(resp["code"], "AUTH03");
+	}
+
+	#[tokio::test]
+	async fn test_reload() {
+		let store = ApiKeyStore::new(&config(RAW_KEY, &[])).unwrap();
+		let app = app(store.clone());
+
+		store.reload(&config("sk-rotated", &[])).unwrap();
+		let (status, _) = call(app.clone(), "/api/whoami", Some(RAW_KEY)).await;
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+		let (status, _) = call(app, "/api/whoami", Some("sk-rotated")).await;
+		assert_eq!(status, StatusCode::OK);
+
+		let bad = ApiKeyConfig {
+			keys: HashMap::from([(
+				"not-hex".to_string(),
+				config(RAW_KEY, &[]).keys.into_values().next().unwrap(),
+			)]),
+		};
+		assert!(store.reload(&bad).is_err());
+		assert_eq!(store.len(), 1);
+	}
+
+	#[tokio::test]
+	async fn test_raw_key_never_logged() {
+		let (logs, _guard) = LogBuf::capture();
+
+		let store = ApiKeyStore::new(&config(RAW_KEY, &[])).unwrap();
+		call(app(store.clone()), "/api/whoami", Some(RAW_KEY)).await;
+		call(app(store.clone()), "/api/orders", Some(RAW_KEY)).await;
+		call(app(store), "/api/whoami", Some("sk-leaked-secret")).await;
+
+		let logs = logs.contents();
+		assert!(logs.contains("billing"));
+		assert!(!logs.contains(RAW_KEY));
+		assert!(!logs.contains("sk-leaked-secret"));
+	}
+}
@@ -0,0 +1,462 @@
+use crate::EXPONENTIAL_SECONDS;
+use crate::HTTP_TIMEOUT;
+use crate::http::inject_request_id;
+use crate::result::WebErr;
+use base_infra::map_err;
+use base_infra::result::{AppError, AppResult, ErrorCode, SysErr};
+use base_infra::runtimes::Deadline;
+use base_infra::tools::retry::Retry;
+use http::{HeaderMap, HeaderName, HeaderValue, Method};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ClientConfig {
+	pub base_url: String,
+	/// Seconds per attempt
+	pub timeout: u64,
+	/// Extra attempts after a 5xx, connect error or timeout
+	pub max_retries: usize,
+	pub default_headers: HashMap<String, String>,
+}
+
+impl Default for ClientConfig {
+	fn default() -> Self {
+		Self {
+			base_url: String::new(),
+			timeout: *HTTP_TIMEOUT,
+			max_retries: 2,
+			default_headers: HashMap::new(),
+		}
+	}
+}
+
+/// Request counters and a latency histogram over [`EXPONENTIAL_SECONDS`], exported by the
+/// metrics layer
+#[derive(Debug)]
+pub struct ClientStats {
+	requests: AtomicU64,
+	failures: AtomicU64,
+	attempts: AtomicU64,
+	/// One bucket per bound plus `+Inf`
+	latency_buckets: Vec<AtomicU64>,
+	latency_sum_micros: AtomicU64,
+}
+
+impl Default for ClientStats {
+	fn default() -> Self {
+		Self {
+			requests: AtomicU64::new(0),
+			failures: AtomicU64::new(0),
+			attempts: AtomicU64::new(0),
+			latency_buckets: (0..=EXPONENTIAL_SECONDS.len())
+				.map(|_| AtomicU64::new(0))
+				.collect(),
+			latency_sum_micros: AtomicU64::new(0),
+		}
+	}
+}
+
+impl ClientStats {
+	pub fn requests(&self) -> u64 {
+		self.requests.load(Ordering::Relaxed)
+	}
+
+	pub fn failures(&self) -> u64 {
+		self.failures.load(Ordering::Relaxed)
+	}
+
+	/// Requests sent including retries
+	pub fn attempts(&self) -> u64 {
+		self.attempts.load(Ordering::Relaxed)
+	}
+
+	/// Non-cumulative counts, the last one is `+Inf`
+	pub fn latency_buckets(&self) -> Vec<u64> {
+		self.latency_buckets
+			.iter()
+			.map(|b| b.load(Ordering::Relaxed))
+			.collect()
+	}
+
+	pub fn latency_sum(&self) -> Duration {
+		Duration::from_micros(self.latency_sum_micros.load(Ordering::Relaxed))
+	}
+
+	fn record(&self, elapsed: Duration, ok: bool) {
+		self.requests.fetch_add(1, Ordering::Relaxed);
+		if !ok {
+			self.failures.fetch_add(1, Ordering::Relaxed);
+		}
+		let secs = elapsed.as_secs_f64();
+		let idx = EXPONENTIAL_SECONDS
+			.iter()
+			.position(|bound| secs <= *bound)
+			.unwrap_or(EXPONENTIAL_SECONDS.len());
+		self.latency_buckets[idx].fetch_add(1, Ordering::Relaxed);
+		self.latency_sum_micros
+			.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+	}
+}
+
+/// `{ code, msg, data }` body written by `RespData`
+#[derive(Deserialize)]
+struct Envelope {
+	code: String,
+	msg: String,
+	#[serde(default)]
+	data: serde_json::Value,
+}
+
+/// Json client for calls between services
+///
+/// Each call carries the current request id, retries 5xx, connect errors and timeouts with the
+/// base [`Retry`] backoff, caps each attempt by the current [`Deadline`], and unwraps `RespData`
+/// envelopes: a non-success `code` becomes an [`WebErr::RemoteErr`] holding the remote code and
+/// msg.
+#[derive(Clone)]
+pub struct HttpClient {
+	base_url: String,
+	client: Client,
+	max_retries: usize,
+	timeout: Duration,
+	stats: Arc<ClientStats>,
+}
+
+impl HttpClient {
+	pub fn new(config: ClientConfig) -> AppResult<Self> {
+		let mut headers = HeaderMap::new();
+		for (name, value) in &config.default_headers {
+			let name = HeaderName::from_bytes(name.as_bytes())
+				.map_err(map_err!(&WebErr::ClientConfigErr, name))?;
+			let value =
+				HeaderValue::from_str(value).map_err(map_err!(&WebErr::ClientConfigErr, name))?;
+			headers.insert(name, value);
+		}
+		let timeout = Duration::from_secs(config.timeout);
+		let client = Client::builder()
+			.timeout(timeout)
+			.default_headers(headers)
+			.build()
+			.map_err(map_err!(&WebErr::ClientConfigErr))?;
+		Ok(Self {
+			base_url: config.base_url.trim_end_matches('/').to_string(),
+			client,
+			max_retries: config.max_retries,
+			timeout,
+			stats: Arc::new(ClientStats::default()),
+		})
+	}
+
+	pub fn stats(&self) -> Arc<ClientStats> {
+		self.stats.clone()
+	}
+
+	pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> AppResult<T> {
+		self.send_json::<(), T>(Method::GET, path, None).await
+	}
+
+	pub async fn post_json<B, T>(&self, path: &str, body: &B) -> AppResult<T>
+	where
+		B: Serialize + ?Sized,
+		T: DeserializeOwned,
+	{
+		self.send_json(Method::POST, path, Some(body)).await
+	}
+
+	pub async fn put_json<B, T>(&self, path: &str, body: &B) -> AppResult<T>
+	where
+		B: Serialize + ?Sized,
+		T: DeserializeOwned,
+	{
+		self.send_json(Method::PUT, path, Some(body)).await
+	}
+
+	pub async fn delete_json<T: DeserializeOwned>(&self, path: &str) -> AppResult<T> {
+		self.send_json::<(), T>(Method::DELETE, path, None).await
+	}
+
+	pub async fn send_json<B, T>(&self, method: Method, path: &str, body: Option<&B>) -> AppResult<T>
+	where
+		B: Serialize + ?Sized,
+		T: DeserializeOwned,
+	{
+		let url = format!("{}/{}", self.base_url, path.trim_start_matches('/'));
+		let start = Instant::now();
+		// outer `Err` is retried, the inner result is final
+		let res = Retry::run(Some(self.max_retries), || {
+			self.attempt::<B, T>(method.clone(), &url, body)
+		})
+		.await
+		.and_then(|res| res);
+		let elapsed = start.elapsed();
+		self.stats.record(elapsed, res.is_ok());
+		debug!(%method, url, ?elapsed, ok = res.is_ok(), "http client call");
+		res
+	}
+
+	async fn attempt<B, T>(
+		&self,
+		method: Method,
+		url: &str,
+		body: Option<&B>,
+	) -> Result<AppResult<T>, AppError>
+	where
+		B: Serialize + ?Sized,
+		T: DeserializeOwned,
+	{
+		// no budget left for another attempt, stop retrying
+		if let Err(e) = Deadline::check() {
+			return Ok(Err(e));
+		}
+		self.stats.attempts.fetch_add(1, Ordering::Relaxed);
+		let mut headers = HeaderMap::new();
+		inject_request_id(&mut headers);
+		let mut req = self
+			.client
+			.request(method, url)
+			.headers(headers)
+			.timeout(Deadline::cap(self.timeout));
+		if let Some(body) = body {
+			req = req.json(body);
+		}
+
+		let resp = match req.send().await {
+			Ok(resp) => resp,
+			Err(e) if e.is_timeout() && Deadline::check().is_err() => {
+				return Ok(Err(AppError::ExtAnyhow(
+					&SysErr::DeadlineExceeded,
+					url.to_string(),
+					e.into(),
+				)));
+			}
+			Err(e) if e.is_connect() || e.is_timeout() => {
+				return Err(AppError::ExtAnyhow(
+					&WebErr::ClientRequestErr,
+					url.to_string(),
+					e.into(),
+				));
+			}
+			Err(e) => {
+				return Ok(Err(AppError::ExtAnyhow(
+					&WebErr::ClientRequestErr,
+					url.to_string(),
+					e.into(),
+				)));
+			}
+		};
+		let status = resp.status();
+		if status.is_server_error() {
+			return Err(AppError::ExtCode(
+				&WebErr::ClientStatusErr,
+				format!("{status} from {url}"),
+			));
+		}
+		let bytes = match resp.bytes().await {
+			Ok(bytes) => bytes,
+			Err(e) => {
+				return Ok(Err(AppError::ExtAnyhow(
+					&WebErr::ClientRequestErr,
+					url.to_string(),
+					e.into(),
+				)));
+			}
+		};
+		Ok(parse_body(status, url, &bytes))
+	}
+}
+
+fn parse_body<T: DeserializeOwned>(
+	status: http::StatusCode,
+	url: &str,
+	bytes: &[u8],
+) -> AppResult<T> {
+	if let Ok(envelope) = serde_json::from_slice::<Envelope>(bytes) {
+		if envelope.code != SysErr::Success.code() {
+			return Err(AppError::ExtCode(
+				&WebErr::RemoteErr,
+				format!("[{}] {}", envelope.code, envelope.msg),
+			));
+		}
+		return serde_json::from_value(envelope.data)
+			.map_err(map_err!(&WebErr::ClientDecodeErr, url));
+	}
+	if !status.is_success() {
+		return Err(AppError::ExtCode(
+			&WebErr::ClientStatusErr,
+			format!("{status} from {url}"),
+		));
+	}
+	serde_json::from_slice(bytes).map_err(map_err!(&WebErr::ClientDecodeErr, url))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::http::{DeadlineLayer, REQUEST_ID_HEADER, with_request_id};
+	use axum::Json;
+	use axum::Router;
+	use axum::body::Body;
+	use axum::http::StatusCode;
+	use axum::response::IntoResponse;
+	use axum::routing::get;
+	use base_infra::result::RespData;
+	use std::sync::atomic::AtomicUsize;
+	use tokio::net::TcpListener;
+	use tower::ServiceExt;
+
+	async fn start_server() -> String {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let app = Router::new()
+			.route(
+				"/flaky",
+				get(move || async move {
+					if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+						StatusCode::SERVICE_UNAVAILABLE.into_response()
+					} else {
+						Json(RespData::success(7u32)).into_response()
+					}
+				}),
+			)
+			.route(
+				"/biz_err",
+				get(|| async { Json(RespData::with_code(&WebErr::ApiKeyInvalid)) }),
+			)
+			.route(
+				"/echo_id",
+				get(|headers: HeaderMap| async move {
+					let id = headers
+						.get(REQUEST_ID_HEADER)
+						.and_then(|v| v.to_str().ok())
+						.unwrap_or_default()
+						.to_string();
+					Json(RespData::success(id))
+				}),
+			)
+			.route("/plain", get(|| async { Json(vec![1u8, 2]) }))
+			.route(
+				"/slow",
+				get(|| async {
+					tokio::time::sleep(Duration::from_secs(5)).await;
+					Json(RespData::success(0u32))
+				}),
+			);
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+		tokio::spawn(async move { axum::serve(listener, app).await });
+		format!("http://{addr}")
+	}
+
+	async fn client() -> HttpClient {
+		let config = ClientConfig {
+			base_url: start_server().await,
+			max_retries: 1,
+			..Default::default()
+		};
+		HttpClient::new(config).unwrap()
+	}
+
+	#[tokio::test]
+	async fn test_retry_on_503() {
+		let client = client().await;
+		let value: u32 = client.get_json("/flaky").await.unwrap();
+		assert_eq!(value, 7);
+		let stats = client.stats();
+		assert_eq!(stats.requests(), 1);
+		assert_eq!(stats.attempts(), 2);
+		assert_eq!(stats.latency_buckets().iter().sum::<u64>(), 1);
+
+		let plain: Vec<u8> = client.get_json("plain").await.unwrap();
+		assert_eq!(plain, [1, 2]);
+	}
+
+	#[tokio::test]
+	async fn test_envelope_error() {
+		let client = client().await;
+		match client.get_json::<u32>("/biz_err").await {
+			Err(AppError::ExtCode(code, msg)) => {
+				assert_eq!(code.code(), WebErr::RemoteErr.code());
+				assert!(msg.contains(WebErr::ApiKeyInvalid.code()), "{msg}");
+				assert!(msg.contains(WebErr::ApiKeyInvalid.message()), "{msg}");
+			}
+			other => panic!("unexpected {other:?}"),
+		}
+		assert_eq!(client.stats().attempts(), 1);
+		assert_eq!(client.stats().failures(), 1);
+	}
+
+	#[tokio::test]
+	async fn test_request_id_propagation() {
+		let client = client().await;
+		let id: String = with_request_id("req-42".to_string(), client.get_json("/echo_id"))
+			.await
+			.unwrap();
+		assert_eq!(id, "req-42");
+		let id: String = client.get_json("/echo_id").await.unwrap();
+		assert_eq!(id, "");
+	}
+
+	#[tokio::test]
+	async fn test_deadline_caps_attempts() {
+		let client = client().await;
+		let start = Instant::now();
+		let res = Deadline::after(Duration::from_millis(300))
+			.scope(client.get_json::<u32>("/slow"))
+			.await;
+		let err = res.unwrap_err();
+		assert_eq!(err.err_code().code(), SysErr::DeadlineExceeded.code());
+		assert!(start.elapsed() < Duration::from_secs(2));
+		// the timed out attempt is not retried
+		assert_eq!(client.stats().attempts(), 1);
+
+		let res = Deadline::after(Duration::ZERO)
+			.scope(client.get_json::<u32>("/plain"))
+			.await;
+		assert_eq!(
+			res.unwrap_err().err_code().code(),
+			SysErr::DeadlineExceeded.code()
+		);
+		assert_eq!(client.stats().attempts(), 1);
+	}
+
+	#[tokio::test]
+	async fn test_route_deadline_stops_upstream_call() {
+		let client = client().await;
+		let upstream = client.clone();
+		let app = Router::new()
+			.route(
+				"/api/proxy",
+				get(move || {
+					let upstream = upstream.clone();
+					async move {
+						let start = Instant::now();
+						let res = upstream.get_json::<u32>("/slow").await;
+						let code = res.err().map(|e| e.err_code().code().to_string());
+						Json((code, start.elapsed().as_millis() as u64))
+					}
+				}),
+			)
+			.layer(DeadlineLayer::new(Duration::from_millis(300)));
+
+		let req = http::Request::builder()
+			.uri("/api/proxy")
+			.body(Body::empty())
+			.unwrap();
+		let resp = app.oneshot(req).await.unwrap();
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		let (code, elapsed_ms): (Option<String>, u64) = serde_json::from_slice(&body).unwrap();
+		// `/slow` replies after 5s, the handler gives up at the route deadline
+		assert_eq!(code.as_deref(), Some(SysErr::DeadlineExceeded.code()));
+		assert!(elapsed_ms < 2_000, "{elapsed_ms}ms");
+		assert_eq!(client.stats().attempts(), 1);
+	}
+}
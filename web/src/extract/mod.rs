@@ -0,0 +1,204 @@
+use crate::result::WebErr;
+use axum::Json;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::response::{IntoResponse, Response};
+use base_infra::result::{AppError, DynErrCode, ErrorCode, RespData};
+use base_infra::validator::{FieldError, FieldErrors, Validator};
+use http::request::Parts;
+use http::{HeaderMap, StatusCode, header};
+use serde::de::DeserializeOwned;
+use std::fmt::Display;
+
+mod upload;
+pub use upload::*;
+
+/// Json body deserialized into `T` and checked by its [`Validator`]
+///
+/// Failures are answered with a 422 `RespData` whose `errors` lists the failing fields, see
+/// [`ValidationRejection`].
+pub struct Validated<T>(pub T);
+
+/// Query string deserialized into `T` and checked by its [`Validator`]
+pub struct ValidatedQuery<T>(pub T);
+
+/// `application/x-www-form-urlencoded` body deserialized into `T` and checked by its
+/// [`Validator`]
+pub struct ValidatedForm<T>(pub T);
+
+/// Rejection of the validated extractors
+///
+/// Deserialize and validation errors are `422` with [`WebErr::ValidationFailed`] and one
+/// [`FieldError`] per field, the serde path is used as field name. Unreadable payloads keep
+/// their own status and code.
+#[derive(Debug)]
+pub struct ValidationRejection {
+	status: StatusCode,
+	code: &'static DynErrCode,
+	msg: String,
+	fields: Vec<FieldError>,
+}
+
+impl ValidationRejection {
+	fn invalid(fields: Vec<FieldError>) -> Self {
+		Self {
+			status: StatusCode::UNPROCESSABLE_ENTITY,
+			code: &WebErr::ValidationFailed,
+			msg: WebErr::ValidationFailed.message().to_string(),
+			fields,
+		}
+	}
+
+	fn malformed(status: StatusCode, code: &'static DynErrCode, err: impl Display) -> Self {
+		Self {
+			status,
+			code,
+			msg: format!("{}: {}", code.message(), err),
+			fields: vec![],
+		}
+	}
+
+	fn deserialize<E: Display>(err: serde_path_to_error::Error<E>) -> Self {
+		let path = err.path().to_string();
+		let field = if path == "." { String::new() } else { path };
+		Self::invalid(vec![FieldError {
+			field,
+			code: WebErr::ValidationFailed.code().to_string(),
+			msg: err.into_inner().to_string(),
+		}])
+	}
+
+	pub fn status(&self) -> StatusCode {
+		self.status
+	}
+
+	pub fn fields(&self) -> &[FieldError] {
+		&self.fields
+	}
+}
+
+impl IntoResponse for ValidationRejection {
+	fn into_response(self) -> Response {
+		tracing::error!("ErrorCode[{}] {} {:?}", self.code, self.msg, self.fields);
+		let resp = RespData::<()> {
+			code: self.code.code().to_string(),
+			msg: self.msg,
+			data: None,
+			errors: (!self.fields.is_empty()).then_some(self.fields),
+		};
+		(self.status, Json(resp)).into_response()
+	}
+}
+
+/// Runs the validator, a [`FieldErrors`] result keeps its fields
+fn validate<T: Validator>(value: T) -> Result<T, ValidationRejection> {
+	let err = match value.validate() {
+		Ok(()) => return Ok(value),
+		Err(err) => err,
+	};
+	let field_errors = match &err {
+		AppError::Anyhow(_, e) | AppError::ExtAnyhow(_, _, e) => e.downcast_ref::<FieldErrors>(),
+		_ => None,
+	};
+	let fields = match field_errors {
+		Some(errors) => errors.fields().to_vec(),
+		None => {
+			let resp = RespData::with_app_error(err);
+			vec![FieldError {
+				field: String::new(),
+				code: resp.code,
+				msg: resp.msg,
+			}]
+		}
+	};
+	Err(ValidationRejection::invalid(fields))
+}
+
+fn has_content_type(headers: &HeaderMap, expected: &str) -> bool {
+	headers
+		.get(header::CONTENT_TYPE)
+		.and_then(|v| v.to_str().ok())
+		.and_then(|v| v.split(';').next())
+		.is_some_and(|mime| {
+			let mime = mime.trim();
+			mime.eq_ignore_ascii_case(expected)
+				|| (expected.ends_with("/json") && mime.ends_with("+json"))
+		})
+}
+
+fn from_urlencoded<T: DeserializeOwned>(input: &[u8]) -> Result<T, ValidationRejection> {
+	let de = serde_urlencoded::Deserializer::new(form_urlencoded::parse(input));
+	serde_path_to_error::deserialize(de).map_err(ValidationRejection::deserialize)
+}
+
+impl<T, S> FromRequest<S> for Validated<T>
+where
+	T: DeserializeOwned + Validator,
+	S: Send + Sync,
+{
+	type Rejection = ValidationRejection;
+
+	async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+		if !has_content_type(req.headers(), "application/json") {
+			return Err(ValidationRejection::malformed(
+				StatusCode::UNSUPPORTED_MEDIA_TYPE,
+				&WebErr::ReqJsonErr,
+				"expected `content-type: application/json`",
+			));
+		}
+		let bytes = Bytes::from_request(req, state).await.map_err(|e| {
+			ValidationRejection::malformed(e.status(), &WebErr::ReqJsonErr, e.body_text())
+		})?;
+
+		let de = &mut serde_json::Deserializer::from_slice(&bytes);
+		let value = match serde_path_to_error::deserialize(de) {
+			Ok(value) => value,
+			Err(e) if e.inner().is_data() => return Err(ValidationRejection::deserialize(e)),
+			Err(e) => {
+				return Err(ValidationRejection::malformed(
+					StatusCode::BAD_REQUEST,
+					&WebErr::ReqJsonErr,
+					e.into_inner(),
+				));
+			}
+		};
+		Ok(Self(validate(value)?))
+	}
+}
+
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+	T: DeserializeOwned + Validator,
+	S: Send + Sync,
+{
+	type Rejection = ValidationRejection;
+
+	async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+		let query = parts.uri.query().unwrap_or_default();
+		let value = from_urlencoded(query.as_bytes())?;
+		Ok(Self(validate(value)?))
+	}
+}
+
+impl<T, S> FromRequest<S> for ValidatedForm<T>
+where
+	T: DeserializeOwned + Validator,
+	S: Send + Sync,
+{
+	type Rejection = ValidationRejection;
+
+	async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+		if !has_content_type(req.headers(), "application/x-www-form-urlencoded") {
+			return Err(ValidationRejection::malformed(
+				StatusCode::UNSUPPORTED_MEDIA_TYPE,
+				&WebErr::ReqFormErr,
+				"expected `content-type: application/x-www-form-urlencoded`",
+			));
+		}
+		let bytes = Bytes::from_request(req, state).await.map_err(|e| {
+			ValidationRejection::malformed(e.status(), &WebErr::ReqFormErr, e.body_text())
+		})?;
+		let value = from_urlencoded(&bytes)?;
+		Ok(Self(validate(value)?))
+	}
+}
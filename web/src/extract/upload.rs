@@ -0,0 +1,424 @@
+use crate::result::WebErr;
+use axum::Json;
+use axum::body::Bytes;
+use axum::extract::multipart::{Field, MultipartError};
+use axum::extract::{FromRequest, Multipart, Request};
+use axum::response::{IntoResponse, Response};
+use base_infra::map_err;
+use base_infra::result::{AppResult, DynErrCode, RespData};
+use http::StatusCode;
+use serde::Deserialize;
+use std::fmt::Display;
+use tempfile::TempPath;
+use tokio::io::AsyncWriteExt;
+
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Limits of the [`Upload`] extractor, read from a request extension or the defaults
+///
+/// ```ignore
+/// Router::new()
+///     .route("/upload", post(handler))
+///     .layer(Extension(UploadConfig { max_file_bytes: 20 << 20, ..Default::default() }))
+///     .layer(DefaultBodyLimit::max(64 << 20));
+/// ```
+///
+/// The whole body is still capped by axum's `DefaultBodyLimit`, raise it together with
+/// `max_file_bytes`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UploadConfig {
+	pub max_file_bytes: u64,
+	pub max_files: usize,
+	/// Exact types or `type/*`, empty allows any type
+	pub allowed_mime: Vec<String>,
+	/// Files larger than this are written to a temp file instead of memory
+	pub spool_to_disk_over: u64,
+}
+
+impl Default for UploadConfig {
+	fn default() -> Self {
+		Self {
+			max_file_bytes: 10 * 1024 * 1024,
+			max_files: 8,
+			allowed_mime: vec![],
+			spool_to_disk_over: 1024 * 1024,
+		}
+	}
+}
+
+impl UploadConfig {
+	fn mime_allowed(&self, mime: &str) -> bool {
+		let mime = mime.split(';').next().unwrap_or_default().trim();
+		self.allowed_mime.is_empty()
+			|| self
+				.allowed_mime
+				.iter()
+				.any(|allowed| match allowed.strip_suffix("/*") {
+					Some(kind) => mime
+						.split_once('/')
+						.is_some_and(|(k, _)| k.eq_ignore_ascii_case(kind)),
+					None => allowed.eq_ignore_ascii_case(mime),
+				})
+	}
+}
+
+/// File content, temp files are deleted when dropped
+#[derive(Debug)]
+pub enum PathOrBytes {
+	Bytes(Bytes),
+	Path(TempPath),
+}
+
+#[derive(Debug)]
+pub struct UploadedFile {
+	/// Form field name
+	pub name: String,
+	pub file_name: Option<String>,
+	pub content_type: String,
+	pub size: u64,
+	pub path_or_bytes: PathOrBytes,
+}
+
+impl UploadedFile {
+	/// The content, read back from disk for spooled files
+	pub async fn bytes(&self) -> AppResult<Bytes> {
+		match &self.path_or_bytes {
+			PathOrBytes::Bytes(bytes) => Ok(bytes.clone()),
+			PathOrBytes::Path(path) => tokio::fs::read(path)
+				.await
+				.map(Bytes::from)
+				.map_err(map_err!(&WebErr::UploadSpoolErr)),
+		}
+	}
+}
+
+/// `multipart/form-data` body checked against [`UploadConfig`] while it streams in
+///
+/// Parts with a file name are files, the others are text fields.
+#[derive(Debug, Default)]
+pub struct Upload {
+	files: Vec<UploadedFile>,
+	fields: Vec<(String, String)>,
+}
+
+impl Upload {
+	pub fn files(&self) -> &[UploadedFile] {
+		&self.files
+	}
+
+	pub fn into_files(self) -> Vec<UploadedFile> {
+		self.files
+	}
+
+	pub fn fields(&self) -> &[(String, String)] {
+		&self.fields
+	}
+
+	/// First text field named `name`
+	pub fn field(&self, name: &str) -> Option<&str> {
+		self.fields
+			.iter()
+			.find(|(n, _)| n == name)
+			.map(|(_, v)| v.as_str())
+	}
+}
+
+/// Rejection of [`Upload`], `413` for size and count limits, `415` for a disallowed type
+#[derive(Debug)]
+pub struct UploadRejection {
+	status: StatusCode,
+	code: &'static DynErrCode,
+	msg: String,
+}
+
+impl UploadRejection {
+	fn new(status: StatusCode, code: &'static DynErrCode, detail: impl Display) -> Self {
+		Self {
+			status,
+			code,
+			msg: format!("{} {}", code.message(), detail),
+		}
+	}
+
+	fn multipart(err: MultipartError) -> Self {
+		let code = match err.status() {
+			StatusCode::PAYLOAD_TOO_LARGE => &WebErr::UploadTooLarge,
+			_ => &WebErr::UploadErr,
+		};
+		Self::new(err.status(), code, err.body_text())
+	}
+
+	pub fn status(&self) -> StatusCode {
+		self.status
+	}
+
+	pub fn code(&self) -> &'static DynErrCode {
+		self.code
+	}
+}
+
+impl IntoResponse for UploadRejection {
+	fn into_response(self) -> Response {
+		tracing::error!("ErrorCode[{}] {}", self.code, self.msg);
+		let resp = RespData::<()>::with(self.code.code(), &self.msg);
+		(self.status, Json(resp)).into_response()
+	}
+}
+
+impl<S> FromRequest<S> for Upload
+where
+	S: Send + Sync,
+{
+	type Rejection = UploadRejection;
+
+	async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+		let config = req
+			.extensions()
+			.get::<UploadConfig>()
+			.cloned()
+			.unwrap_or_default();
+		let mut multipart = Multipart::from_request(req, state)
+			.await
+			.map_err(|e| UploadRejection::new(e.status(), &WebErr::UploadErr, e.body_text()))?;
+
+		let mut upload = Upload::default();
+		while let Some(field) = multipart
+			.next_field()
+			.await
+			.map_err(UploadRejection::multipart)?
+		{
+			let name = field.name().unwrap_or_default().to_string();
+			if field.file_name().is_none() {
+				let value = read_field(field, &config).await?;
+				upload.fields.push((name, value));
+				continue;
+			}
+
+			if upload.files.len() >= config.max_files {
+				return Err(UploadRejection::new(
+					StatusCode::PAYLOAD_TOO_LARGE,
+					&WebErr::UploadTooManyFiles,
+					format!("more than {} files", config.max_files),
+				));
+			}
+			let content_type = field
+				.content_type()
+				.unwrap_or(DEFAULT_CONTENT_TYPE)
+				.to_string();
+			if !config.mime_allowed(&content_type) {
+				return Err(UploadRejection::new(
+					StatusCode::UNSUPPORTED_MEDIA_TYPE,
+					&WebErr::UploadMimeNotAllowed,
+					content_type,
+				));
+			}
+			let file_name = field.file_name().map(str::to_string);
+			let (size, path_or_bytes) = read_file(field, &config).await?;
+			upload.files.push(UploadedFile {
+				name,
+				file_name,
+				content_type,
+				size,
+				path_or_bytes,
+			});
+		}
+		Ok(upload)
+	}
+}
+
+fn too_large(config: &UploadConfig) -> UploadRejection {
+	UploadRejection::new(
+		StatusCode::PAYLOAD_TOO_LARGE,
+		&WebErr::UploadTooLarge,
+		format!("part exceeds {} bytes", config.max_file_bytes),
+	)
+}
+
+fn spool_err(err: std::io::Error) -> UploadRejection {
+	tracing::error!("upload spool error: {err}");
+	UploadRejection::new(
+		StatusCode::INTERNAL_SERVER_ERROR,
+		&WebErr::UploadSpoolErr,
+		"",
+	)
+}
+
+async fn read_field(mut field: Field<'_>, config: &UploadConfig) -> Result<String, UploadRejection> {
+	let mut buf = Vec::new();
+	while let Some(chunk) = field.chunk().await.map_err(UploadRejection::multipart)? {
+		if (buf.len() + chunk.len()) as u64 > config.max_file_bytes {
+			return Err(too_large(config));
+		}
+		buf.extend_from_slice(&chunk);
+	}
+	String::from_utf8(buf)
+		.map_err(|e| UploadRejection::new(StatusCode::BAD_REQUEST, &WebErr::UploadErr, e))
+}
+
+/// Buffers in memory up to `spool_to_disk_over`, then continues into a temp file
+async fn read_file(
+	mut field: Field<'_>,
+	config: &UploadConfig,
+) -> Result<(u64, PathOrBytes), UploadRejection> {
+	let mut size = 0u64;
+	let mut buf = Vec::new();
+	let mut spool: Option<(tokio::fs::File, TempPath)> = None;
+	while let Some(chunk) = field.chunk().await.map_err(UploadRejection::multipart)? {
+		size += chunk.len() as u64;
+		if size > config.max_file_bytes {
+			return Err(too_large(config));
+		}
+		if spool.is_none() && size > config.spool_to_disk_over {
+			let (file, path) = tempfile::NamedTempFile::new()
+				.map_err(spool_err)?
+				.into_parts();
+			let mut file = tokio::fs::File::from_std(file);
+			file.write_all(&buf).await.map_err(spool_err)?;
+			buf.clear();
+			spool = Some((file, path));
+		}
+		match &mut spool {
+			Some((file, _)) => file.write_all(&chunk).await.map_err(spool_err)?,
+			None => buf.extend_from_slice(&chunk),
+		}
+	}
+
+	let content = match spool {
+		Some((mut file, path)) => {
+			file.flush().await.map_err(spool_err)?;
+			PathOrBytes::Path(path)
+		}
+		None => PathOrBytes::Bytes(Bytes::from(buf)),
+	};
+	Ok((size, content))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::body::Body;
+	use axum::routing::post;
+	use axum::{Extension, Router};
+	use base_infra::result::ErrorCode;
+	use tower::ServiceExt;
+
+	const BOUNDARY: &str = "X-UPLOAD-BOUNDARY";
+
+	fn multipart_body(parts: &[(&str, Option<(&str, &str)>, &[u8])]) -> Vec<u8> {
+		let mut body = Vec::new();
+		for (name, file, content) in parts {
+			body.extend_from_slice(format!("--{BOUNDARY}\r\n").as_bytes());
+			match file {
+				Some((file_name, mime)) => body.extend_from_slice(
+					format!(
+						"content-disposition: form-data; name=\"{name}\"; filename=\"{file_name}\"\r\ncontent-type: {mime}\r\n\r\n"
+					)
+					.as_bytes(),
+				),
+				None => body.extend_from_slice(
+					format!("content-disposition: form-data; name=\"{name}\"\r\n\r\n").as_bytes(),
+				),
+			}
+			body.extend_from_slice(content);
+			body.extend_from_slice(b"\r\n");
+		}
+		body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+		body
+	}
+
+	async fn handler(upload: Upload) -> String {
+		let mut out = format!("title={};", upload.field("title").unwrap_or_default());
+		for file in upload.files() {
+			let kind = match file.path_or_bytes {
+				PathOrBytes::Bytes(_) => "mem",
+				PathOrBytes::Path(_) => "disk",
+			};
+			let bytes = file.bytes().await.unwrap();
+			out.push_str(&format!(
+				"{}:{}:{}:{}:{};",
+				file.name,
+				file.content_type,
+				file.size,
+				kind,
+				bytes.len()
+			));
+		}
+		out
+	}
+
+	async fn send(body: Vec<u8>) -> (StatusCode, String) {
+		let config = UploadConfig {
+			max_file_bytes: 1024,
+			max_files: 2,
+			allowed_mime: vec!["image/*".to_string(), "text/plain".to_string()],
+			spool_to_disk_over: 100,
+		};
+		let app = Router::new()
+			.route("/upload", post(handler))
+			.layer(Extension(config));
+		let req = Request::post("/upload")
+			.header(
+				http::header::CONTENT_TYPE,
+				format!("multipart/form-data; boundary={BOUNDARY}"),
+			)
+			.body(Body::from(body))
+			.unwrap();
+		let resp = app.oneshot(req).await.unwrap();
+		let status = resp.status();
+		let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		(status, String::from_utf8(bytes.to_vec()).unwrap())
+	}
+
+	#[tokio::test]
+	async fn test_upload_files_and_fields() {
+		let large = vec![b'x'; 500];
+		let body = multipart_body(&[
+			("title", None, b"hello"),
+			("small", Some(("a.txt", "text/plain")), b"tiny"),
+			("large", Some(("b.png", "image/png")), &large),
+		]);
+		let (status, text) = send(body).await;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(
+			text,
+			"title=hello;small:text/plain:4:mem:4;large:image/png:500:disk:500;"
+		);
+	}
+
+	#[tokio::test]
+	async fn test_upload_too_large() {
+		let huge = vec![b'x'; 2048];
+		let body = multipart_body(&[("f", Some(("big.png", "image/png")), &huge)]);
+		let (status, text) = send(body).await;
+		assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+		let resp: serde_json::Value = serde_json::from_str(&text).unwrap();
+		assert_eq!(resp["code"], WebErr::UploadTooLarge.code());
+
+		let body = multipart_body(&[
+			("a", Some(("a.txt", "text/plain")), b"1"),
+			("b", Some(("b.txt", "text/plain")), b"2"),
+			("c", Some(("c.txt", "text/plain")), b"3"),
+		]);
+		let (status, text) = send(body).await;
+		assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+		assert!(text.contains(WebErr::UploadTooManyFiles.code()));
+	}
+
+	#[tokio::test]
+	async fn test_upload_mime_not_allowed() {
+		let body = multipart_body(&[("f", Some(("x.exe", "application/x-msdownload")), b"MZ")]);
+		let (status, text) = send(body).await;
+		assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+		let resp: serde_json::Value = serde_json::from_str(&text).unwrap();
+		assert_eq!(resp["code"], WebErr::UploadMimeNotAllowed.code());
+		assert!(
+			resp["msg"]
+				.as_str()
+				.unwrap()
+				.contains("application/x-msdownload")
+		);
+	}
+}
@@ -0,0 +1,46 @@
+use axum::Json;
+use base_infra::result::RespData;
+use base_infra::tools::build_info::BuildInfo;
+
+pub const BUILD_INFO_PATH: &str = "/buildz";
+
+/// Build metadata in the `RespData` envelope
+///
+/// `Router::new().route(BUILD_INFO_PATH, get(build_info_handler))`
+pub async fn build_info_handler() -> Json<RespData<BuildInfo>> {
+	Json(RespData::success(BuildInfo::current().clone()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::Router;
+	use axum::body::Body;
+	use axum::routing::get;
+	use http::{Request, StatusCode};
+	use tower::ServiceExt;
+
+	#[tokio::test]
+	async fn test_build_info_handler() {
+		let app = Router::new().route(BUILD_INFO_PATH, get(build_info_handler));
+		let req = Request::get(BUILD_INFO_PATH).body(Body::empty()).unwrap();
+		let resp = app.oneshot(req).await.unwrap();
+		assert_eq!(resp.status(), StatusCode::OK);
+
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+		assert_eq!(json["code"], RespData::success(()).code);
+		for field in [
+			"version",
+			"git_hash",
+			"build_timestamp",
+			"rustc_version",
+			"profile",
+		] {
+			let value = json["data"][field].as_str().unwrap();
+			assert!(!value.is_empty(), "{field}");
+		}
+	}
+}
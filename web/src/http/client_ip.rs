@@ -0,0 +1,142 @@
+use axum::extract::ConnectInfo;
+use http::Request;
+use ipnetwork::IpNetwork;
+use serde::Deserialize;
+use std::net::{IpAddr, SocketAddr};
+
+pub const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// Reverse proxies allowed to report the client address
+///
+/// ```yaml
+/// trusted_proxies:
+///   cidrs: ["10.0.0.0/8"]
+///   header: x-forwarded-for
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TrustedProxies {
+	pub cidrs: Vec<IpNetwork>,
+	/// Header each proxy appends the address it got the request from to
+	pub header: String,
+}
+
+impl Default for TrustedProxies {
+	fn default() -> Self {
+		Self {
+			cidrs: vec![],
+			header: X_FORWARDED_FOR.to_string(),
+		}
+	}
+}
+
+impl TrustedProxies {
+	pub fn new(cidrs: Vec<IpNetwork>) -> Self {
+		Self {
+			cidrs,
+			..Default::default()
+		}
+	}
+
+	pub fn is_trusted(&self, ip: IpAddr) -> bool {
+		let ip = canonical_ip(ip);
+		self.cidrs.iter().any(|net| net.contains(ip))
+	}
+
+	/// Client address of a request
+	///
+	/// The connection address, unless it is a trusted proxy: then `header` is walked from the
+	/// right and the first address that is not a trusted proxy wins. Entries left of it were
+	/// written by the client and are never used. Needs
+	/// `into_make_service_with_connect_info::<SocketAddr>()`.
+	pub fn client_ip<B>(&self, req: &Request<B>) -> Option<IpAddr> {
+		let peer = req
+			.extensions()
+			.get::<ConnectInfo<SocketAddr>>()
+			.map(|ConnectInfo(addr)| canonical_ip(addr.ip()))?;
+		if !self.is_trusted(peer) {
+			return Some(peer);
+		}
+
+		let hops = req
+			.headers()
+			.get_all(self.header.as_str())
+			.iter()
+			.filter_map(|v| v.to_str().ok())
+			.flat_map(|v| v.split(','))
+			.collect::<Vec<_>>();
+		let mut client = peer;
+		for hop in hops.into_iter().rev() {
+			// a malformed hop ends the chain, nothing before it can be trusted
+			let Some(ip) = parse_hop(hop) else { break };
+			client = ip;
+			if !self.is_trusted(ip) {
+				break;
+			}
+		}
+		Some(client)
+	}
+}
+
+/// `::ffff:10.0.0.1` from a dual stack listener is the v4 address
+pub(crate) fn canonical_ip(ip: IpAddr) -> IpAddr {
+	match ip {
+		IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+		v4 => v4,
+	}
+}
+
+/// `1.2.3.4`, `1.2.3.4:80`, `::1` or `[::1]:80`
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+	let hop = hop.trim();
+	hop.parse::<IpAddr>()
+		.or_else(|_| hop.parse::<SocketAddr>().map(|addr| addr.ip()))
+		.ok()
+		.map(canonical_ip)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn request(peer: &str, forwarded: &[&str]) -> Request<()> {
+		let mut req = Request::builder();
+		for value in forwarded {
+			req = req.header(X_FORWARDED_FOR, *value);
+		}
+		let mut req = req.body(()).unwrap();
+		let addr = SocketAddr::new(peer.parse().unwrap(), 40000);
+		req.extensions_mut().insert(ConnectInfo(addr));
+		req
+	}
+
+	fn ip(s: &str) -> Option<IpAddr> {
+		Some(s.parse().unwrap())
+	}
+
+	#[test]
+	fn test_client_ip() {
+		let proxies = TrustedProxies::new(vec!["10.0.0.0/8".parse().unwrap()]);
+
+		// direct clients can't choose their address
+		assert_eq!(
+			proxies.client_ip(&request("8.8.8.8", &["127.0.0.1"])),
+			ip("8.8.8.8")
+		);
+		assert_eq!(proxies.client_ip(&request("10.0.0.1", &[])), ip("10.0.0.1"));
+
+		// the right-most untrusted hop, whatever the client put in front of it
+		let req = request("10.0.0.1", &["127.0.0.1, 9.9.9.9, 10.0.0.2"]);
+		assert_eq!(proxies.client_ip(&req), ip("9.9.9.9"));
+		let req = request("10.0.0.1", &["127.0.0.1", "9.9.9.9:443", "10.0.0.2"]);
+		assert_eq!(proxies.client_ip(&req), ip("9.9.9.9"));
+
+		let req = request("10.0.0.1", &["127.0.0.1, bogus, 10.0.0.2"]);
+		assert_eq!(proxies.client_ip(&req), ip("10.0.0.2"));
+		let req = request("::ffff:10.0.0.1", &["10.0.0.3, 10.0.0.2"]);
+		assert_eq!(proxies.client_ip(&req), ip("10.0.0.3"));
+
+		let req = Request::builder().body(()).unwrap();
+		assert_eq!(proxies.client_ip(&req), None);
+	}
+}
@@ -0,0 +1,233 @@
+use axum::body::HttpBody;
+use axum::extract::DefaultBodyLimit;
+use http::header;
+use serde::Deserialize;
+use std::sync::Arc;
+use tower::ServiceBuilder;
+use tower::layer::util::{Identity, Stack};
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{Predicate, SizeAbove};
+use tower_http::decompression::RequestDecompressionLayer;
+
+/// Never compressed, streamed or already compressed
+const ALWAYS_EXCLUDED: [&str; 2] = ["text/event-stream", "application/grpc"];
+
+/// Response compression settings
+///
+/// ```yaml
+/// compression:
+///   br: false
+///   min_size: 1024
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+	pub gzip: bool,
+	pub br: bool,
+	pub zstd: bool,
+	/// Bodies smaller than this are sent as is, unknown sizes are compressed
+	pub min_size: u16,
+	/// Content type prefixes left alone, e.g. `image/` or `application/zip`
+	pub exclude_content_types: Vec<String>,
+}
+
+impl Default for CompressionConfig {
+	fn default() -> Self {
+		Self {
+			gzip: true,
+			br: true,
+			zstd: true,
+			min_size: 256,
+			exclude_content_types: [
+				"image/",
+				"video/",
+				"audio/",
+				"font/woff",
+				"application/zip",
+				"application/gzip",
+				"application/zstd",
+				"application/octet-stream",
+			]
+			.map(String::from)
+			.to_vec(),
+		}
+	}
+}
+
+/// [`Predicate`] built from a [`CompressionConfig`]
+#[derive(Debug, Clone)]
+pub struct CompressPredicate {
+	size: SizeAbove,
+	excluded: Arc<[String]>,
+}
+
+impl Predicate for CompressPredicate {
+	fn should_compress<B>(&self, response: &http::Response<B>) -> bool
+	where
+		B: HttpBody,
+	{
+		let content_type = response
+			.headers()
+			.get(header::CONTENT_TYPE)
+			.and_then(|v| v.to_str().ok())
+			.unwrap_or_default()
+			.to_ascii_lowercase();
+		let excluded = ALWAYS_EXCLUDED
+			.iter()
+			.any(|ct| content_type.starts_with(ct))
+			|| self
+				.excluded
+				.iter()
+				.any(|ct| content_type.starts_with(&ct.to_ascii_lowercase()));
+		!excluded && self.size.should_compress(response)
+	}
+}
+
+/// Compresses responses with the encodings enabled in `config` that the client accepts
+///
+/// SSE, gRPC and the configured content types are never compressed.
+pub fn compression_layer(config: &CompressionConfig) -> CompressionLayer<CompressPredicate> {
+	CompressionLayer::new()
+		.gzip(config.gzip)
+		.br(config.br)
+		.zstd(config.zstd)
+		.no_deflate()
+		.compress_when(CompressPredicate {
+			size: SizeAbove::new(config.min_size),
+			excluded: config.exclude_content_types.clone().into(),
+		})
+}
+
+/// Decompresses gzip, br and zstd request bodies, an unsupported `content-encoding` is 415
+///
+/// `max_body_bytes` replaces the `DefaultBodyLimit` and applies to the decompressed body, so a
+/// small compressed payload can't expand past it.
+pub fn decompression_layer(
+	max_body_bytes: usize,
+) -> ServiceBuilder<Stack<DefaultBodyLimit, Stack<RequestDecompressionLayer, Identity>>> {
+	ServiceBuilder::new()
+		.layer(RequestDecompressionLayer::new().no_deflate())
+		.layer(DefaultBodyLimit::max(max_body_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::result::stream_ok;
+	use axum::Router;
+	use axum::body::{Body, Bytes};
+	use axum::response::IntoResponse;
+	use axum::response::sse::Event;
+	use axum::routing::{get, post};
+	use base_infra::result::AppError;
+	use flate2::Compression;
+	use flate2::write::GzEncoder;
+	use http::{Request, StatusCode};
+	use std::io::Write;
+	use tower::ServiceExt;
+
+	fn app() -> Router {
+		Router::new()
+			.route("/big", get(|| async { "x".repeat(4096) }))
+			.route("/small", get(|| async { "tiny" }))
+			.route(
+				"/png",
+				get(|| async { ([(header::CONTENT_TYPE, "image/png")], vec![0u8; 4096]) }),
+			)
+			.route(
+				"/events",
+				get(|| async {
+					let events = futures::stream::iter((0..100).map(|i| {
+						Ok::<_, AppError>(Event::default().data("y".repeat(64) + &i.to_string()))
+					}));
+					stream_ok(events).into_response()
+				}),
+			)
+			.layer(compression_layer(&CompressionConfig::default()))
+	}
+
+	async fn encoding_of(app: &Router, path: &str, accept: &str) -> Option<String> {
+		let req = Request::get(path)
+			.header(header::ACCEPT_ENCODING, accept)
+			.body(Body::empty())
+			.unwrap();
+		let resp = app.clone().oneshot(req).await.unwrap();
+		assert_eq!(resp.status(), StatusCode::OK);
+		resp.headers()
+			.get(header::CONTENT_ENCODING)
+			.map(|v| v.to_str().unwrap().to_string())
+	}
+
+	#[tokio::test]
+	async fn test_accept_encoding_negotiation() {
+		let app = app();
+		assert_eq!(
+			encoding_of(&app, "/big", "gzip").await.as_deref(),
+			Some("gzip")
+		);
+		assert_eq!(encoding_of(&app, "/big", "br").await.as_deref(), Some("br"));
+		assert_eq!(
+			encoding_of(&app, "/big", "zstd").await.as_deref(),
+			Some("zstd")
+		);
+		assert_eq!(encoding_of(&app, "/big", "identity").await, None);
+
+		let gzip_only = Router::new()
+			.route("/big", get(|| async { "x".repeat(4096) }))
+			.layer(compression_layer(&CompressionConfig {
+				br: false,
+				zstd: false,
+				..Default::default()
+			}));
+		assert_eq!(
+			encoding_of(&gzip_only, "/big", "br, gzip;q=0.5")
+				.await
+				.as_deref(),
+			Some("gzip")
+		);
+	}
+
+	#[tokio::test]
+	async fn test_skipped_responses() {
+		let app = app();
+		assert_eq!(encoding_of(&app, "/small", "gzip").await, None);
+		assert_eq!(encoding_of(&app, "/png", "gzip").await, None);
+		assert_eq!(encoding_of(&app, "/events", "gzip, br").await, None);
+	}
+
+	fn gzip(data: &[u8]) -> Vec<u8> {
+		let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+		encoder.write_all(data).unwrap();
+		encoder.finish().unwrap()
+	}
+
+	#[tokio::test]
+	async fn test_decompressed_size_limit() {
+		let app = Router::new()
+			.route(
+				"/echo",
+				post(|body: Bytes| async move { body.len().to_string() }),
+			)
+			.layer(decompression_layer(1024));
+		let send = |body: Vec<u8>| {
+			let req = Request::post("/echo")
+				.header(header::CONTENT_ENCODING, "gzip")
+				.body(Body::from(body))
+				.unwrap();
+			app.clone().oneshot(req)
+		};
+
+		let resp = send(gzip(&[b'a'; 512])).await.unwrap();
+		assert_eq!(resp.status(), StatusCode::OK);
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		assert_eq!(&body[..], b"512");
+
+		// compresses to a few dozen bytes, expands past the limit
+		let bomb = gzip(&vec![0u8; 512 * 1024]);
+		assert!(bomb.len() < 1024);
+		let resp = send(bomb).await.unwrap();
+		assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+	}
+}
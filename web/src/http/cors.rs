@@ -0,0 +1,291 @@
+use crate::result::WebErr;
+use base_infra::result::AppResult;
+use base_infra::{err, map_err};
+use http::{HeaderName, HeaderValue, Method};
+use serde::Deserialize;
+use std::time::Duration;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+use tracing::info;
+
+const WILDCARD: &str = "*";
+
+/// CORS policy, usually loaded from the `[cors]` section of the app config
+///
+/// `allowed_origins` entries are exact origins (`https://app.example.com`), suffix wildcards
+/// (`*.example.com` or `https://*.example.com`) or `*` for any origin.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CorsConfig {
+	pub allowed_origins: Vec<String>,
+	pub allowed_methods: Vec<String>,
+	pub allowed_headers: Vec<String>,
+	pub allow_credentials: bool,
+	pub max_age_secs: u64,
+}
+
+impl Default for CorsConfig {
+	fn default() -> Self {
+		Self {
+			allowed_origins: Vec::new(),
+			allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE"]
+				.map(String::from)
+				.to_vec(),
+			allowed_headers: ["content-type", "authorization"].map(String::from).to_vec(),
+			allow_credentials: false,
+			max_age_secs: 3600,
+		}
+	}
+}
+
+enum OriginRule {
+	Exact(HeaderValue),
+	/// `scheme` is `None` for `*.example.com`, `suffix` keeps the leading dot
+	Suffix {
+		scheme: Option<String>,
+		suffix: String,
+	},
+}
+
+impl OriginRule {
+	fn parse(origin: &str) -> AppResult<Self> {
+		let (scheme, host) = match origin.split_once("://") {
+			Some((scheme, host)) => (Some(scheme.to_ascii_lowercase()), host),
+			None => (None, origin),
+		};
+		if let Some(suffix) = host.strip_prefix("*.") {
+			if suffix.is_empty() || suffix.contains('*') {
+				return err!(
+					&WebErr::CorsConfigErr,
+					format!("invalid origin pattern {origin}")
+				);
+			}
+			return Ok(Self::Suffix {
+				scheme,
+				suffix: format!(".{}", suffix.to_ascii_lowercase()),
+			});
+		}
+		if scheme.is_none() || origin.contains('*') {
+			return err!(&WebErr::CorsConfigErr, format!("invalid origin {origin}"));
+		}
+		let value = HeaderValue::from_str(origin).map_err(map_err!(
+			&WebErr::CorsConfigErr,
+			format!("invalid origin {origin}")
+		))?;
+		Ok(Self::Exact(value))
+	}
+
+	fn matches(&self, origin: &HeaderValue) -> bool {
+		match self {
+			OriginRule::Exact(value) => value == origin,
+			OriginRule::Suffix { scheme, suffix } => {
+				let Ok(origin) = origin.to_str() else {
+					return false;
+				};
+				let Some((origin_scheme, host)) = origin.split_once("://") else {
+					return false;
+				};
+				if scheme
+					.as_deref()
+					.is_some_and(|s| !s.eq_ignore_ascii_case(origin_scheme))
+				{
+					return false;
+				}
+				// drop the port before matching the host
+				let host = host.rsplit_once(':').map_or(host, |(h, _)| h);
+				host.to_ascii_lowercase().ends_with(suffix.as_str())
+			}
+		}
+	}
+}
+
+fn allow_origin(config: &CorsConfig) -> AppResult<AllowOrigin> {
+	if config.allowed_origins.iter().any(|o| o == WILDCARD) {
+		return Ok(AllowOrigin::any());
+	}
+	let rules = config
+		.allowed_origins
+		.iter()
+		.map(|o| OriginRule::parse(o))
+		.collect::<AppResult<Vec<_>>>()?;
+	if rules.iter().all(|r| matches!(r, OriginRule::Exact(_))) {
+		let exact = rules.into_iter().filter_map(|r| match r {
+			OriginRule::Exact(value) => Some(value),
+			OriginRule::Suffix { .. } => None,
+		});
+		return Ok(AllowOrigin::list(exact));
+	}
+	Ok(AllowOrigin::predicate(move |origin, _| {
+		rules.iter().any(|rule| rule.matches(origin))
+	}))
+}
+
+fn allow_methods(config: &CorsConfig) -> AppResult<AllowMethods> {
+	if config.allowed_methods.iter().any(|m| m == WILDCARD) {
+		return Ok(AllowMethods::any());
+	}
+	let methods = config
+		.allowed_methods
+		.iter()
+		.map(|m| {
+			Method::from_bytes(m.to_ascii_uppercase().as_bytes()).map_err(map_err!(
+				&WebErr::CorsConfigErr,
+				format!("invalid method {m}")
+			))
+		})
+		.collect::<AppResult<Vec<_>>>()?;
+	Ok(AllowMethods::list(methods))
+}
+
+fn allow_headers(config: &CorsConfig) -> AppResult<AllowHeaders> {
+	if config.allowed_headers.iter().any(|h| h == WILDCARD) {
+		return Ok(AllowHeaders::any());
+	}
+	let headers = config
+		.allowed_headers
+		.iter()
+		.map(|h| {
+			HeaderName::from_bytes(h.as_bytes()).map_err(map_err!(
+				&WebErr::CorsConfigErr,
+				format!("invalid header {h}")
+			))
+		})
+		.collect::<AppResult<Vec<_>>>()?;
+	Ok(AllowHeaders::list(headers))
+}
+
+/// Builds a [`CorsLayer`] from config, failing at startup on combinations browsers reject
+/// (credentials together with a `*` origin, method or header).
+pub fn cors_layer(config: &CorsConfig) -> AppResult<CorsLayer> {
+	if config.allow_credentials {
+		let wildcard = [
+			("origin", &config.allowed_origins),
+			("method", &config.allowed_methods),
+			("header", &config.allowed_headers),
+		]
+		.into_iter()
+		.find(|(_, values)| values.iter().any(|v| v == WILDCARD));
+		if let Some((kind, _)) = wildcard {
+			return err!(
+				&WebErr::CorsConfigErr,
+				format!("allow_credentials can not be used with a wildcard {kind}")
+			);
+		}
+	}
+
+	let layer = CorsLayer::new()
+		.allow_origin(allow_origin(config)?)
+		.allow_methods(allow_methods(config)?)
+		.allow_headers(allow_headers(config)?)
+		.allow_credentials(config.allow_credentials)
+		.max_age(Duration::from_secs(config.max_age_secs));
+
+	info!(
+		origins = ?config.allowed_origins,
+		methods = ?config.allowed_methods,
+		headers = ?config.allowed_headers,
+		credentials = config.allow_credentials,
+		max_age_secs = config.max_age_secs,
+		"cors policy"
+	);
+	Ok(layer)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::Router;
+	use axum::body::Body;
+	use axum::routing::get;
+	use base_infra::result::AppError;
+	use http::{Request, StatusCode, header};
+	use tower::ServiceExt;
+
+	fn config() -> CorsConfig {
+		CorsConfig {
+			allowed_origins: vec![
+				"https://app.example.com".to_string(),
+				"https://*.partner.io".to_string(),
+			],
+			allow_credentials: true,
+			max_age_secs: 600,
+			..Default::default()
+		}
+	}
+
+	async fn preflight(config: &CorsConfig, origin: &str) -> http::Response<Body> {
+		let app = Router::new()
+			.route("/api", get(|| async { "ok" }))
+			.layer(cors_layer(config).unwrap());
+		let req = Request::builder()
+			.method(Method::OPTIONS)
+			.uri("/api")
+			.header(header::ORIGIN, origin)
+			.header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+			.body(Body::empty())
+			.unwrap();
+		app.oneshot(req).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn test_preflight_allowed_origin() {
+		let resp = preflight(&config(), "https://app.example.com").await;
+		assert_eq!(resp.status(), StatusCode::OK);
+		let headers = resp.headers();
+		assert_eq!(
+			headers[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+			"https://app.example.com"
+		);
+		assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+		assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "600");
+		let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS]
+			.to_str()
+			.unwrap();
+		assert!(methods.contains("POST"));
+
+		let resp = preflight(&config(), "https://api.partner.io:8443").await;
+		assert_eq!(
+			resp.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+			"https://api.partner.io:8443"
+		);
+	}
+
+	#[tokio::test]
+	async fn test_preflight_disallowed_origin() {
+		for origin in [
+			"https://evil.com",
+			"http://api.partner.io",
+			"https://partner.io.evil.com",
+		] {
+			let resp = preflight(&config(), origin).await;
+			assert!(
+				!resp
+					.headers()
+					.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN),
+				"{origin}"
+			);
+		}
+	}
+
+	#[test]
+	fn test_invalid_combination() {
+		let config = CorsConfig {
+			allowed_origins: vec!["*".to_string()],
+			allow_credentials: true,
+			..Default::default()
+		};
+		match cors_layer(&config) {
+			Err(AppError::ExtCode(code, msg)) => {
+				assert_eq!(code.code(), "CORS01");
+				assert!(msg.contains("origin"));
+			}
+			Err(e) => panic!("unexpected error: {e}"),
+			Ok(_) => panic!("expected error"),
+		}
+
+		let config = CorsConfig {
+			allowed_origins: vec!["app.example.com".to_string()],
+			..Default::default()
+		};
+		assert!(cors_layer(&config).is_err());
+	}
+}
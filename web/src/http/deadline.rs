@@ -0,0 +1,74 @@
+use base_infra::runtimes::Deadline;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::{Layer, Service};
+
+/// Sets a [`Deadline`] of request start + `timeout` for the inner service, so db and http client
+/// calls made by the handler stop once the request can no longer be answered in time
+///
+/// It only informs downstream calls, pair it with a `TimeoutLayer` to cut the request itself.
+#[derive(Debug, Clone, Copy)]
+pub struct DeadlineLayer {
+	timeout: Duration,
+}
+
+impl DeadlineLayer {
+	pub fn new(timeout: Duration) -> Self {
+		Self { timeout }
+	}
+}
+
+impl<S> Layer<S> for DeadlineLayer {
+	type Service = DeadlineService<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		DeadlineService {
+			inner,
+			timeout: self.timeout,
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct DeadlineService<S> {
+	inner: S,
+	timeout: Duration,
+}
+
+impl<S, R> Service<R> for DeadlineService<S>
+where
+	S: Service<R>,
+	S::Future: Send + 'static,
+{
+	type Response = S::Response;
+	type Error = S::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
+
+	fn call(&mut self, req: R) -> Self::Future {
+		let deadline = Deadline::after(self.timeout);
+		Box::pin(deadline.scope(self.inner.call(req)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::convert::Infallible;
+	use tower::{ServiceExt, service_fn};
+
+	#[tokio::test]
+	async fn test_deadline_scope() {
+		let svc = DeadlineLayer::new(Duration::from_secs(2)).layer(service_fn(|_: ()| async {
+			Ok::<_, Infallible>(Deadline::remaining())
+		}));
+		let left = svc.oneshot(()).await.unwrap().unwrap();
+		assert!(left <= Duration::from_secs(2) && left > Duration::from_secs(1));
+		assert_eq!(Deadline::remaining(), None);
+	}
+}
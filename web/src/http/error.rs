@@ -1,29 +1,43 @@
 use crate::HTTP_TIMEOUT;
+use crate::http::current_request_id;
 use crate::result::WebErr;
 use anyhow::anyhow;
-use axum::response::IntoResponse;
+use axum::body::Body;
+use axum::response::{IntoResponse, Response};
 use axum::{BoxError, Json};
+use base_infra::config::RtEnv;
 use base_infra::result::RespData;
-use http::StatusCode;
+use http::{StatusCode, Uri};
+use std::any::Any;
+use std::time::Duration;
+use tower_http::catch_panic::{CatchPanicLayer, ResponseForPanic};
+use tracing::{error, warn};
 
 /// Adds a custom handler for tower's `TimeoutLayer`, see https://docs.rs/axum/latest/axum/middleware/index.html#commonly-used-middleware.
 pub async fn handle_timeout_error(err: BoxError) -> impl IntoResponse {
+	timeout_error_response(err, Duration::from_secs(*HTTP_TIMEOUT))
+}
+
+/// [`handle_timeout_error`] for a `TimeoutLayer` of `timeout`
+pub fn timeout_error_response(err: BoxError, timeout: Duration) -> Response {
 	if err.is::<tower::timeout::error::Elapsed>() {
 		let timeout_err = anyhow!(
 			"request took longer than the configured {} second timeout",
-			*HTTP_TIMEOUT
+			timeout.as_secs()
 		);
 
 		(
 			StatusCode::REQUEST_TIMEOUT,
 			Json(RespData::with_anyhow(&WebErr::RequestTimeout, timeout_err)),
 		)
+			.into_response()
 	} else {
 		let err = anyhow!("unhandled internal error: {}", err);
 		(
 			StatusCode::INTERNAL_SERVER_ERROR,
 			Json(RespData::with_anyhow(&WebErr::InternalServerError, err)),
 		)
+			.into_response()
 	}
 }
 
@@ -33,3 +47,129 @@ pub async fn handle_404() -> impl IntoResponse {
 		Json(RespData::with_code(&WebErr::NotFound)),
 	)
 }
+
+/// Router fallback answering unknown routes with a 404 `RespData` body
+///
+/// `Router::new().fallback(not_found_handler)`
+pub async fn not_found_handler(uri: Uri) -> impl IntoResponse {
+	warn!("route not found: {}", uri.path());
+	(
+		StatusCode::NOT_FOUND,
+		Json(RespData::with_ext_code(
+			&WebErr::NotFound,
+			uri.path().to_string(),
+		)),
+	)
+}
+
+/// Turns handler panics into a 500 `RespData` body instead of a dropped connection
+///
+/// The panic message is logged with the request id, and only sent to the client in
+/// `RtEnv::Development`. Place it inside `http_trace` so the request id is known.
+pub fn catch_panic_layer(rt_env: RtEnv) -> CatchPanicLayer<PanicResponder> {
+	CatchPanicLayer::custom(PanicResponder { rt_env })
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PanicResponder {
+	rt_env: RtEnv,
+}
+
+impl ResponseForPanic for PanicResponder {
+	type ResponseBody = Body;
+
+	fn response_for_panic(&mut self, err: Box<dyn Any + Send + 'static>) -> Response<Body> {
+		let msg = if let Some(s) = err.downcast_ref::<&str>() {
+			s.to_string()
+		} else if let Some(s) = err.downcast_ref::<String>() {
+			s.clone()
+		} else {
+			"unknown panic".to_string()
+		};
+		let tid = current_request_id().unwrap_or_default();
+		error!(
+			tid,
+			panic = msg,
+			"ErrorCode[{}] handler panicked",
+			WebErr::InternalPanic
+		);
+
+		let resp = if self.rt_env.is_dev() {
+			RespData::with_ext_code(&WebErr::InternalPanic, msg)
+		} else {
+			RespData::with_code(&WebErr::InternalPanic)
+		};
+		(StatusCode::INTERNAL_SERVER_ERROR, Json(resp)).into_response()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::http::with_request_id;
+	use crate::test_util::LogBuf;
+	use axum::Router;
+	use axum::routing::get;
+	use base_infra::result::ErrorCode;
+	use http::Request;
+	use serde_json::Value;
+	use tower::ServiceExt;
+
+	fn app(rt_env: RtEnv) -> Router {
+		Router::new()
+			.route("/ok", get(|| async { "ok" }))
+			.route(
+				"/boom",
+				get(|| async {
+					if true {
+						panic!("secret db password in panic");
+					}
+					"unreachable"
+				}),
+			)
+			.fallback(not_found_handler)
+			.layer(catch_panic_layer(rt_env))
+	}
+
+	async fn call(app: Router, path: &str) -> (StatusCode, Value) {
+		let req = Request::builder().uri(path).body(Body::empty()).unwrap();
+		let resp = with_request_id("tid-panic-1".to_string(), app.oneshot(req))
+			.await
+			.unwrap();
+		let status = resp.status();
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		(status, serde_json::from_slice(&body).unwrap())
+	}
+
+	#[tokio::test]
+	async fn test_not_found_envelope() {
+		let (status, body) = call(app(RtEnv::Production), "/missing").await;
+		assert_eq!(status, StatusCode::NOT_FOUND);
+		assert_eq!(body["code"], WebErr::NotFound.code());
+		assert!(body["msg"].as_str().unwrap().contains("/missing"));
+		assert!(body["data"].is_null());
+	}
+
+	#[tokio::test]
+	async fn test_panic_envelope() {
+		let (logs, _guard) = LogBuf::capture();
+		let (status, body) = call(app(RtEnv::Production), "/boom").await;
+		assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+		assert_eq!(body["code"], WebErr::InternalPanic.code());
+		assert_eq!(body["msg"], WebErr::InternalPanic.message());
+
+		let logs = logs.contents();
+		assert!(logs.contains("tid-panic-1"), "{logs}");
+		assert!(logs.contains("secret db password in panic"), "{logs}");
+
+		let (_, body) = call(app(RtEnv::Development), "/boom").await;
+		assert!(
+			body["msg"]
+				.as_str()
+				.unwrap()
+				.contains("secret db password in panic")
+		);
+	}
+}
@@ -0,0 +1,47 @@
+use axum::Json;
+use axum::extract::State;
+use base_infra::result::RespData;
+use base_infra::tools::flags::{FeatureFlags, FlagConfig};
+use std::collections::BTreeMap;
+
+pub const FLAGS_PATH: &str = "/debug/flags";
+
+/// Effective feature flags, overrides applied, in the `RespData` envelope
+///
+/// `Router::new().route(FLAGS_PATH, get(flags_handler)).with_state(flags)`
+pub async fn flags_handler(
+	State(flags): State<FeatureFlags>,
+) -> Json<RespData<BTreeMap<String, FlagConfig>>> {
+	Json(RespData::success(flags.effective()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::Router;
+	use axum::body::Body;
+	use axum::routing::get;
+	use http::{Request, StatusCode};
+	use std::collections::HashMap;
+	use tower::ServiceExt;
+
+	#[tokio::test]
+	async fn test_flags_handler() {
+		let flags = FeatureFlags::new(HashMap::from([("checkout".to_string(), FlagConfig::on())]));
+		flags.set_override("search", FlagConfig::off());
+		let app = Router::new()
+			.route(FLAGS_PATH, get(flags_handler))
+			.with_state(flags);
+		let req = Request::get(FLAGS_PATH).body(Body::empty()).unwrap();
+		let resp = app.oneshot(req).await.unwrap();
+		assert_eq!(resp.status(), StatusCode::OK);
+
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+		assert_eq!(json["data"]["checkout"]["enabled"], true);
+		assert_eq!(json["data"]["checkout"]["rollout_pct"], 100);
+		assert_eq!(json["data"]["search"]["enabled"], false);
+	}
+}
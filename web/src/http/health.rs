@@ -0,0 +1,437 @@
+use crate::result::WebErr;
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use base_infra::err;
+use base_infra::result::AppResult;
+use http::StatusCode;
+use sea_orm::DatabaseConnection;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::time::Instant;
+
+pub const HEALTHZ_PATH: &str = "/healthz";
+pub const READYZ_PATH: &str = "/readyz";
+pub const STARTUPZ_PATH: &str = "/startupz";
+
+const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A named dependency check run by `/readyz` and `/startupz`
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+	fn name(&self) -> &str;
+
+	async fn check(&self) -> AppResult<()>;
+}
+
+struct FnCheck<F> {
+	name: String,
+	f: F,
+}
+
+#[async_trait]
+impl<F, Fut> HealthCheck for FnCheck<F>
+where
+	F: Fn() -> Fut + Send + Sync,
+	Fut: Future<Output = AppResult<()>> + Send,
+{
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	async fn check(&self) -> AppResult<()> {
+		(self.f)().await
+	}
+}
+
+/// Pings the sql pool
+pub struct DbPingCheck {
+	name: String,
+	conn: DatabaseConnection,
+}
+
+impl DbPingCheck {
+	pub fn new(name: impl Into<String>, conn: DatabaseConnection) -> Self {
+		Self {
+			name: name.into(),
+			conn,
+		}
+	}
+}
+
+#[async_trait]
+impl HealthCheck for DbPingCheck {
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	async fn check(&self) -> AppResult<()> {
+		sql_infra::ping_db(&self.conn).await
+	}
+}
+
+/// Checks RocksDB background errors via [`rksdb_infra::schemadb::RksDB::health`]
+#[cfg(feature = "rksdb")]
+pub struct RksDbCheck {
+	name: String,
+	db: Arc<rksdb_infra::schemadb::RksDB>,
+}
+
+#[cfg(feature = "rksdb")]
+impl RksDbCheck {
+	pub fn new(name: impl Into<String>, db: Arc<rksdb_infra::schemadb::RksDB>) -> Self {
+		Self {
+			name: name.into(),
+			db,
+		}
+	}
+}
+
+#[cfg(feature = "rksdb")]
+#[async_trait]
+impl HealthCheck for RksDbCheck {
+	fn name(&self) -> &str {
+		&self.name
+	}
+
+	async fn check(&self) -> AppResult<()> {
+		self.db.health()
+	}
+}
+
+/// Passes once [`cache_infra::init_cache`] has run
+#[cfg(feature = "cache")]
+pub struct CacheInitCheck;
+
+#[cfg(feature = "cache")]
+#[async_trait]
+impl HealthCheck for CacheInitCheck {
+	fn name(&self) -> &str {
+		"cache"
+	}
+
+	async fn check(&self) -> AppResult<()> {
+		if cache_infra::cache_initialized() {
+			return Ok(());
+		}
+		err!(&WebErr::CacheNotInitialized)
+	}
+}
+
+/// Process-level flags shared with the app, e.g. to fail liveness after an unrecoverable error
+#[derive(Clone, Default)]
+pub struct HealthState {
+	fatal: Arc<AtomicBool>,
+	started: Arc<AtomicBool>,
+}
+
+impl HealthState {
+	pub fn set_fatal(&self) {
+		self.fatal.store(true, Ordering::Release);
+	}
+
+	pub fn is_fatal(&self) -> bool {
+		self.fatal.load(Ordering::Acquire)
+	}
+
+	pub fn set_started(&self) {
+		self.started.store(true, Ordering::Release);
+	}
+
+	pub fn is_started(&self) -> bool {
+		self.started.load(Ordering::Acquire)
+	}
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckStatus {
+	pub name: String,
+	pub ok: bool,
+	pub latency_ms: u64,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+	pub ok: bool,
+	pub checks: Vec<CheckStatus>,
+}
+
+impl IntoResponse for HealthReport {
+	fn into_response(self) -> Response {
+		let status = if self.ok {
+			StatusCode::OK
+		} else {
+			StatusCode::SERVICE_UNAVAILABLE
+		};
+		(status, Json(self)).into_response()
+	}
+}
+
+/// `/healthz`, `/readyz` and `/startupz` routes
+///
+/// - `/healthz` liveness, 200 unless [`HealthState::set_fatal`] was called
+/// - `/readyz` runs every check concurrently with a per-check timeout, 503 if any fails
+/// - `/startupz` 200 once all checks passed one time or [`HealthState::set_started`] was called
+/// - `/metrics` the registry given to `with_metrics`, with the `metrics` feature
+#[derive(Clone)]
+pub struct HealthRouter {
+	checks: Vec<Arc<dyn HealthCheck>>,
+	timeout: Duration,
+	state: HealthState,
+	#[cfg(feature = "metrics")]
+	metrics: Option<Arc<prometheus::Registry>>,
+}
+
+impl Default for HealthRouter {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl HealthRouter {
+	pub fn new() -> Self {
+		Self {
+			checks: Vec::new(),
+			timeout: DEFAULT_CHECK_TIMEOUT,
+			state: HealthState::default(),
+			#[cfg(feature = "metrics")]
+			metrics: None,
+		}
+	}
+
+	/// Per-check timeout, defaults to 3s
+	pub fn with_timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+	/// Also serves `registry` on [`crate::http::METRICS_PATH`]
+	#[cfg(feature = "metrics")]
+	pub fn with_metrics(mut self, registry: Arc<prometheus::Registry>) -> Self {
+		self.metrics = Some(registry);
+		self
+	}
+
+	pub fn register(mut self, check: impl HealthCheck + 'static) -> Self {
+		self.checks.push(Arc::new(check));
+		self
+	}
+
+	/// Registers an async closure as a check
+	pub fn check<F, Fut>(self, name: impl Into<String>, f: F) -> Self
+	where
+		F: Fn() -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = AppResult<()>> + Send + 'static,
+	{
+		self.register(FnCheck {
+			name: name.into(),
+			f,
+		})
+	}
+
+	pub fn state(&self) -> HealthState {
+		self.state.clone()
+	}
+
+	pub async fn run_checks(&self) -> HealthReport {
+		let checks = self.checks.iter().map(|check| async move {
+			let start = Instant::now();
+			let res = match tokio::time::timeout(self.timeout, check.check()).await {
+				Ok(res) => res,
+				Err(_) => err!(&WebErr::HealthCheckTimeout, check.name()),
+			};
+			CheckStatus {
+				name: check.name().to_string(),
+				ok: res.is_ok(),
+				latency_ms: start.elapsed().as_millis() as u64,
+				error: res.err().map(|e| e.to_string()),
+			}
+		});
+		let checks = futures::future::join_all(checks).await;
+		HealthReport {
+			ok: checks.iter().all(|c| c.ok),
+			checks,
+		}
+	}
+
+	pub fn into_router<S>(self) -> Router<S>
+	where
+		S: Clone + Send + Sync + 'static,
+	{
+		#[cfg(feature = "metrics")]
+		let metrics = self.metrics.clone();
+		let router = Router::new()
+			.route(HEALTHZ_PATH, get(healthz))
+			.route(READYZ_PATH, get(readyz))
+			.route(STARTUPZ_PATH, get(startupz))
+			.with_state(Arc::new(self));
+		#[cfg(feature = "metrics")]
+		let router = match metrics {
+			Some(registry) => router.merge(crate::http::metrics_router(registry)),
+			None => router,
+		};
+		router
+	}
+
+	/// Merges the health routes into an existing app router
+	pub fn merge_into<S>(self, router: Router<S>) -> Router<S>
+	where
+		S: Clone + Send + Sync + 'static,
+	{
+		router.merge(self.into_router())
+	}
+}
+
+async fn healthz(State(health): State<Arc<HealthRouter>>) -> HealthReport {
+	HealthReport {
+		ok: !health.state.is_fatal(),
+		checks: Vec::new(),
+	}
+}
+
+async fn readyz(State(health): State<Arc<HealthRouter>>) -> HealthReport {
+	health.run_checks().await
+}
+
+async fn startupz(State(health): State<Arc<HealthRouter>>) -> HealthReport {
+	if health.state.is_started() {
+		return HealthReport {
+			ok: true,
+			checks: Vec::new(),
+		};
+	}
+	let report = health.run_checks().await;
+	if report.ok {
+		health.state.set_started();
+	}
+	report
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::body::Body;
+	use base_infra::result::AppError;
+	use http::Request;
+	use serde_json::Value;
+	use tower::ServiceExt;
+
+	fn app() -> (Router, HealthState) {
+		let health = HealthRouter::new()
+			.with_timeout(Duration::from_millis(100))
+			.check("pass", || async { Ok(()) })
+			.check("fail", || async {
+				Err(AppError::ErrCode(&WebErr::InternalServerError))
+			});
+		let state = health.state();
+		let router = Router::new().route("/", get(|| async { "app" }));
+		(health.merge_into(router), state)
+	}
+
+	async fn call(app: &Router, path: &str) -> (StatusCode, Value) {
+		let req = Request::builder().uri(path).body(Body::empty()).unwrap();
+		let resp = app.clone().oneshot(req).await.unwrap();
+		let status = resp.status();
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		(status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+	}
+
+	#[tokio::test]
+	async fn test_readyz_reports_each_check() {
+		let (app, _) = app();
+		let (status, body) = call(&app, READYZ_PATH).await;
+		assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+		assert_eq!(body["ok"], false);
+
+		let checks = body["checks"].as_array().unwrap();
+		assert_eq!(checks.len(), 2);
+		assert_eq!(checks[0]["name"], "pass");
+		assert_eq!(checks[0]["ok"], true);
+		assert!(checks[0]["latency_ms"].is_u64());
+		assert!(checks[0].get("error").is_none());
+		assert_eq!(checks[1]["name"], "fail");
+		assert_eq!(checks[1]["ok"], false);
+		assert!(checks[1]["error"].as_str().unwrap().contains("WEB005"));
+
+		let (status, _) = call(&app, STARTUPZ_PATH).await;
+		assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+		// existing routes are untouched
+		let req = Request::builder().uri("/").body(Body::empty()).unwrap();
+		let resp = app.clone().oneshot(req).await.unwrap();
+		assert_eq!(resp.status(), StatusCode::OK);
+	}
+
+	#[tokio::test]
+	async fn test_healthz_and_fatal_flag() {
+		let (app, state) = app();
+		let (status, body) = call(&app, HEALTHZ_PATH).await;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(body["ok"], true);
+
+		state.set_fatal();
+		let (status, _) = call(&app, HEALTHZ_PATH).await;
+		assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_check_timeout_and_startup() {
+		let app = HealthRouter::new()
+			.with_timeout(Duration::from_millis(50))
+			.check("slow", || async {
+				tokio::time::sleep(Duration::from_secs(1)).await;
+				Ok(())
+			})
+			.into_router::<()>();
+		let (status, body) = call(&app, READYZ_PATH).await;
+		assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+		assert!(
+			body["checks"][0]["error"]
+				.as_str()
+				.unwrap()
+				.contains("HLTH01")
+		);
+
+		let ok = HealthRouter::new().check("pass", || async { Ok(()) });
+		let state = ok.state();
+		let app = ok.into_router::<()>();
+		let (status, _) = call(&app, STARTUPZ_PATH).await;
+		assert_eq!(status, StatusCode::OK);
+		assert!(state.is_started());
+	}
+
+	#[cfg(feature = "metrics")]
+	#[tokio::test]
+	async fn test_with_metrics() {
+		let registry = Arc::new(prometheus::Registry::new());
+		let counter = prometheus::IntCounter::new("jobs_total", "Jobs run").unwrap();
+		registry.register(Box::new(counter.clone())).unwrap();
+		counter.inc();
+
+		let app = HealthRouter::new()
+			.with_metrics(registry)
+			.into_router::<()>();
+		let req = Request::builder()
+			.uri(crate::http::METRICS_PATH)
+			.body(Body::empty())
+			.unwrap();
+		let resp = app.clone().oneshot(req).await.unwrap();
+		assert_eq!(resp.status(), StatusCode::OK);
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		assert!(String::from_utf8_lossy(&body).contains("jobs_total 1"));
+
+		let (status, _) = call(&app, HEALTHZ_PATH).await;
+		assert_eq!(status, StatusCode::OK);
+	}
+}
@@ -0,0 +1,661 @@
+use crate::result::{AxumError, WebErr};
+use axum::body::{Body, Bytes};
+use axum::extract::Request;
+use axum::response::{IntoResponse, Response};
+use base_infra::result::{AppError, DynErrCode};
+use futures::StreamExt;
+use http::header::{CONTENT_TYPE, LOCATION};
+use http::{HeaderName, HeaderValue, Method, StatusCode};
+use moka::sync::Cache;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tower::{Layer, Service};
+use tracing::warn;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set to `true` on responses served from the store
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+const MAX_KEY_LEN: usize = 255;
+
+/// Idempotency config
+///
+/// ```yaml
+/// idempotency:
+///   ttl_secs: 86400
+///   body_limit: 1048576
+///   replay_headers: [content-type, location]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdempotencyConfig {
+	/// How long a recorded response is replayed
+	#[serde(default = "default_ttl_secs")]
+	pub ttl_secs: u64,
+	/// Max request and response body buffered, larger requests get 413. A larger response is
+	/// passed through once, retries with its key get 422.
+	#[serde(default = "default_body_limit")]
+	pub body_limit: usize,
+	/// Response headers recorded with the body, others are dropped on replay
+	#[serde(default = "default_replay_headers")]
+	pub replay_headers: Vec<String>,
+}
+
+fn default_ttl_secs() -> u64 {
+	24 * 3600
+}
+
+fn default_body_limit() -> usize {
+	1024 * 1024
+}
+
+fn default_replay_headers() -> Vec<String> {
+	vec![CONTENT_TYPE.to_string(), LOCATION.to_string()]
+}
+
+impl Default for IdempotencyConfig {
+	fn default() -> Self {
+		Self {
+			ttl_secs: default_ttl_secs(),
+			body_limit: default_body_limit(),
+			replay_headers: default_replay_headers(),
+		}
+	}
+}
+
+/// A recorded response and the fingerprint of the request that produced it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdempotentResponse {
+	/// Hex SHA-256 of method, path with query and body
+	pub fingerprint: String,
+	pub status: u16,
+	pub headers: Vec<(String, String)>,
+	/// `None` when the body was over [`IdempotencyConfig::body_limit`]
+	pub body: Option<Vec<u8>>,
+}
+
+impl IdempotentResponse {
+	fn to_response(&self, replayed: bool) -> Response {
+		let Some(body) = &self.body else {
+			return reject(
+				&WebErr::IdempotencyNotReplayable,
+				StatusCode::UNPROCESSABLE_ENTITY,
+			);
+		};
+		let mut resp = Response::new(Body::from(body.clone()));
+		*resp.status_mut() = StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK);
+		let headers = resp.headers_mut();
+		for (name, value) in &self.headers {
+			if let (Ok(name), Ok(value)) = (
+				HeaderName::try_from(name.as_str()),
+				HeaderValue::try_from(value.as_str()),
+			) {
+				headers.append(name, value);
+			}
+		}
+		if replayed {
+			headers.insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+		}
+		resp
+	}
+}
+
+/// Recorded responses by idempotency key, in-memory by default; a shared store (e.g. RksDB or
+/// Redis) can implement this
+#[async_trait::async_trait]
+pub trait IdempotencyStore: Send + Sync {
+	/// The unexpired response recorded for `key`
+	async fn get(&self, key: &str) -> Option<IdempotentResponse>;
+
+	async fn put(&self, key: &str, resp: IdempotentResponse, ttl: Duration);
+}
+
+pub struct MemIdempotencyStore {
+	entries: Cache<String, Arc<(Instant, IdempotentResponse)>>,
+}
+
+impl MemIdempotencyStore {
+	pub fn new(max_capacity: u64) -> Self {
+		Self {
+			entries: Cache::new(max_capacity),
+		}
+	}
+}
+
+#[async_trait::async_trait]
+impl IdempotencyStore for MemIdempotencyStore {
+	async fn get(&self, key: &str) -> Option<IdempotentResponse> {
+		let entry = self.entries.get(key)?;
+		if entry.0 <= Instant::now() {
+			self.entries.invalidate(key);
+			return None;
+		}
+		Some(entry.1.clone())
+	}
+
+	async fn put(&self, key: &str, resp: IdempotentResponse, ttl: Duration) {
+		let expires_at = Instant::now() + ttl;
+		self.entries
+			.insert(key.to_string(), Arc::new((expires_at, resp)));
+	}
+}
+
+/// Replays the recorded response of a request carrying `Idempotency-Key`
+///
+/// - same key and same method, path, query and body: the recorded response, without calling the
+///   handler
+/// - same key, different request: 409
+/// - concurrent duplicates wait for the first one instead of running the handler again
+///
+/// Only 2xx and 4xx responses are recorded, a 5xx can be retried with the same key. Requests
+/// without the header and safe methods pass through. Keys are global, place it inside the auth
+/// layers and let clients use unique keys (e.g. a UUID).
+pub fn idempotency_layer(store: Arc<dyn IdempotencyStore>) -> IdempotencyLayer {
+	IdempotencyLayer::with_config(IdempotencyConfig::default(), store)
+}
+
+type InFlight = HashMap<String, (String, watch::Receiver<Option<Arc<IdempotentResponse>>>)>;
+
+#[derive(Clone)]
+pub struct IdempotencyLayer {
+	config: Arc<IdempotencyConfig>,
+	store: Arc<dyn IdempotencyStore>,
+	in_flight: Arc<Mutex<InFlight>>,
+}
+
+impl IdempotencyLayer {
+	pub fn with_config(config: IdempotencyConfig, store: Arc<dyn IdempotencyStore>) -> Self {
+		Self {
+			config: Arc::new(config),
+			store,
+			in_flight: Arc::default(),
+		}
+	}
+
+	fn ttl(&self) -> Duration {
+		Duration::from_secs(self.config.ttl_secs)
+	}
+
+	fn recorded(
+		&self,
+		resp: &Response,
+		fingerprint: String,
+		body: Option<&[u8]>,
+	) -> IdempotentResponse {
+		let headers = self
+			.config
+			.replay_headers
+			.iter()
+			.flat_map(|name| {
+				resp.headers()
+					.get_all(name.as_str())
+					.iter()
+					.filter_map(|v| {
+						let value = v.to_str().ok()?;
+						Some((name.to_ascii_lowercase(), value.to_string()))
+					})
+			})
+			.collect();
+		IdempotentResponse {
+			fingerprint,
+			status: resp.status().as_u16(),
+			headers,
+			body: body.map(<[u8]>::to_vec),
+		}
+	}
+
+	async fn claim(&self, key: &str, fingerprint: &str) -> Claim {
+		if let Some(recorded) = self.store.get(key).await {
+			if recorded.fingerprint != fingerprint {
+				return Claim::Conflict;
+			}
+			return Claim::Replay(recorded);
+		}
+
+		let guard = {
+			let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+			if let Some((running, rx)) = in_flight.get(key) {
+				if running != fingerprint {
+					return Claim::Conflict;
+				}
+				return Claim::Wait(rx.clone());
+			}
+			let (tx, rx) = watch::channel(None);
+			in_flight.insert(key.to_string(), (fingerprint.to_string(), rx));
+			LeadGuard {
+				key: key.to_string(),
+				in_flight: self.in_flight.clone(),
+				tx,
+			}
+		};
+
+		// a leader may have recorded its response and left since the lookup above
+		match self.store.get(key).await {
+			Some(recorded) if recorded.fingerprint != fingerprint => Claim::Conflict,
+			Some(recorded) => {
+				guard.tx.send_replace(Some(Arc::new(recorded.clone())));
+				Claim::Replay(recorded)
+			}
+			None => Claim::Lead(guard),
+		}
+	}
+}
+
+impl<S> Layer<S> for IdempotencyLayer {
+	type Service = IdempotencyService<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		IdempotencyService {
+			inner,
+			layer: self.clone(),
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct IdempotencyService<S> {
+	inner: S,
+	layer: IdempotencyLayer,
+}
+
+/// Outcome of looking a key up in the store and the in-flight requests
+enum Claim {
+	Replay(IdempotentResponse),
+	Conflict,
+	Wait(watch::Receiver<Option<Arc<IdempotentResponse>>>),
+	Lead(LeadGuard),
+}
+
+/// Removes the in-flight entry when the leading request ends, also on cancel or panic
+struct LeadGuard {
+	key: String,
+	in_flight: Arc<Mutex<InFlight>>,
+	tx: watch::Sender<Option<Arc<IdempotentResponse>>>,
+}
+
+impl Drop for LeadGuard {
+	fn drop(&mut self) {
+		let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+		in_flight.remove(&self.key);
+	}
+}
+
+impl<S> Service<Request> for IdempotencyService<S>
+where
+	S: Service<Request, Response = Response> + Clone + Send + 'static,
+	S::Future: Send + 'static,
+{
+	type Response = Response;
+	type Error = S::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
+
+	fn call(&mut self, req: Request) -> Self::Future {
+		let clone = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, clone);
+		let layer = self.layer.clone();
+
+		Box::pin(async move {
+			let key = match req.headers().get(IDEMPOTENCY_KEY_HEADER) {
+				Some(key) if !is_safe(req.method()) => key,
+				_ => return inner.call(req).await,
+			};
+			let key = match key.to_str() {
+				Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key.to_string(),
+				_ => {
+					return Ok(reject(
+						&WebErr::IdempotencyKeyInvalid,
+						StatusCode::BAD_REQUEST,
+					));
+				}
+			};
+
+			let (parts, body) = req.into_parts();
+			let bytes = match axum::body::to_bytes(body, layer.config.body_limit).await {
+				Ok(bytes) => bytes,
+				Err(e) => {
+					warn!(uri = %parts.uri, "{}, reason: {}", WebErr::IdempotencyBodyTooLarge, e);
+					return Ok(reject(
+						&WebErr::IdempotencyBodyTooLarge,
+						StatusCode::PAYLOAD_TOO_LARGE,
+					));
+				}
+			};
+			let fingerprint = hex::encode(
+				Sha256::new()
+					.chain_update(parts.method.as_str())
+					.chain_update(b"\n")
+					.chain_update(path_and_query(&parts.uri))
+					.chain_update(b"\n")
+					.chain_update(&bytes)
+					.finalize(),
+			);
+
+			let guard = loop {
+				match layer.claim(&key, &fingerprint).await {
+					Claim::Replay(recorded) => return Ok(recorded.to_response(true)),
+					Claim::Conflict => {
+						warn!(key = %key, uri = %parts.uri, "{}", WebErr::IdempotencyKeyConflict);
+						return Ok(reject(
+							&WebErr::IdempotencyKeyConflict,
+							StatusCode::CONFLICT,
+						));
+					}
+					Claim::Wait(mut rx) => {
+						if let Ok(done) = rx.wait_for(Option::is_some).await
+							&& let Some(recorded) = done.as_ref()
+						{
+							return Ok(recorded.to_response(true));
+						}
+						// the first one was not recorded, e.g. a 5xx, try again
+					}
+					Claim::Lead(guard) => break guard,
+				}
+			};
+
+			let resp = inner
+				.call(Request::from_parts(parts, Body::from(bytes)))
+				.await?;
+			let status = resp.status();
+			if !(status.is_success() || status.is_client_error()) {
+				return Ok(resp);
+			}
+
+			let (resp_parts, body) = resp.into_parts();
+			let (resp, recorded) = match buffer_body(body, layer.config.body_limit).await {
+				Buffered::Full(body) => {
+					let resp = Response::from_parts(resp_parts, Body::from(body.clone()));
+					let recorded = layer.recorded(&resp, fingerprint, Some(&body));
+					(resp, recorded)
+				}
+				Buffered::Overflow(body) => {
+					warn!(key = %key, "{}", WebErr::IdempotencyNotReplayable);
+					let resp = Response::from_parts(resp_parts, body);
+					let recorded = layer.recorded(&resp, fingerprint, None);
+					(resp, recorded)
+				}
+				// nothing recorded, the client sees the failed body and may retry
+				Buffered::Failed(body) => return Ok(Response::from_parts(resp_parts, body)),
+			};
+			layer.store.put(&key, recorded.clone(), layer.ttl()).await;
+			guard.tx.send_replace(Some(Arc::new(recorded)));
+			Ok(resp)
+		})
+	}
+}
+
+enum Buffered {
+	Full(Bytes),
+	/// Over the limit, the read chunks followed by the rest
+	Overflow(Body),
+	/// The body errored, the read chunks followed by the error
+	Failed(Body),
+}
+
+async fn buffer_body(body: Body, limit: usize) -> Buffered {
+	let mut stream = body.into_data_stream();
+	let mut chunks = Vec::new();
+	let mut len = 0;
+	while let Some(chunk) = stream.next().await {
+		let failed = chunk.is_err();
+		len += chunk.as_ref().map_or(0, Bytes::len);
+		chunks.push(chunk);
+		if failed || len > limit {
+			let body = Body::from_stream(futures::stream::iter(chunks).chain(stream));
+			return if failed {
+				Buffered::Failed(body)
+			} else {
+				Buffered::Overflow(body)
+			};
+		}
+	}
+	let chunks = chunks.into_iter().flatten().collect::<Vec<_>>();
+	Buffered::Full(Bytes::from(chunks.concat()))
+}
+
+fn path_and_query(uri: &http::Uri) -> &str {
+	uri.path_and_query().map_or(uri.path(), |pq| pq.as_str())
+}
+
+fn is_safe(method: &Method) -> bool {
+	matches!(
+		*method,
+		Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+	)
+}
+
+fn reject(code: &'static DynErrCode, status: StatusCode) -> Response {
+	AxumError::AppError(AppError::HttpErr(code, status)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::Router;
+	use axum::routing::post;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use tower::ServiceExt;
+
+	fn app(calls: Arc<AtomicUsize>, config: IdempotencyConfig) -> Router {
+		let store = Arc::new(MemIdempotencyStore::new(100));
+		let handler = move |body: String| {
+			let calls = calls.clone();
+			async move {
+				let n = calls.fetch_add(1, Ordering::SeqCst) + 1;
+				tokio::time::sleep(Duration::from_millis(50)).await;
+				if body == "fail" {
+					return (StatusCode::SERVICE_UNAVAILABLE, "retry").into_response();
+				}
+				let headers = [(LOCATION, format!("/payments/{n}"))];
+				(StatusCode::CREATED, headers, format!("payment {n}: {body}")).into_response()
+			}
+		};
+		Router::new()
+			.route("/payments", post(handler))
+			.layer(IdempotencyLayer::with_config(config, store))
+	}
+
+	async fn call(app: &Router, key: Option<&str>, body: &str) -> (Response, String) {
+		call_uri(app, "/payments", key, body).await
+	}
+
+	async fn call_uri(app: &Router, uri: &str, key: Option<&str>, body: &str) -> (Response, String) {
+		let mut req = Request::builder().method("POST").uri(uri);
+		if let Some(key) = key {
+			req = req.header(IDEMPOTENCY_KEY_HEADER, key);
+		}
+		let req = req.body(Body::from(body.to_string())).unwrap();
+		let resp = app.clone().oneshot(req).await.unwrap();
+		let (parts, body) = resp.into_parts();
+		let body = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+		(
+			Response::from_parts(parts, Body::empty()),
+			String::from_utf8(body.to_vec()).unwrap(),
+		)
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_replay() {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let app = app(calls.clone(), IdempotencyConfig::default());
+
+		let (first, body) = call(&app, Some("k1"), "10 USD").await;
+		assert_eq!(first.status(), StatusCode::CREATED);
+		assert_eq!(body, "payment 1: 10 USD");
+		assert!(first.headers().get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+
+		let (replay, replay_body) = call(&app, Some("k1"), "10 USD").await;
+		assert_eq!(replay.status(), StatusCode::CREATED);
+		assert_eq!(replay_body, body);
+		assert_eq!(replay.headers()[LOCATION], "/payments/1");
+		assert_eq!(replay.headers()[IDEMPOTENT_REPLAYED_HEADER], "true");
+		assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+		// no key, or another key, runs the handler
+		assert_eq!(call(&app, None, "10 USD").await.1, "payment 2: 10 USD");
+		assert_eq!(
+			call(&app, Some("k2"), "10 USD").await.1,
+			"payment 3: 10 USD"
+		);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_conflict() {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let app = app(calls.clone(), IdempotencyConfig::default());
+
+		call(&app, Some("k1"), "10 USD").await;
+		let (resp, body) = call(&app, Some("k1"), "99 USD").await;
+		assert_eq!(resp.status(), StatusCode::CONFLICT);
+		let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+		assert_eq!(body["code"], "IDEM01");
+		assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+		let (resp, _) = call(&app, Some(&"k".repeat(MAX_KEY_LEN + 1)), "10 USD").await;
+		assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+		// the query is part of the request
+		call_uri(&app, "/payments?to=ann", Some("k2"), "10 USD").await;
+		let (resp, _) = call_uri(&app, "/payments?to=bob", Some("k2"), "10 USD").await;
+		assert_eq!(resp.status(), StatusCode::CONFLICT);
+		let (resp, _) = call_uri(&app, "/payments?to=ann", Some("k2"), "10 USD").await;
+		assert_eq!(resp.headers()[IDEMPOTENT_REPLAYED_HEADER], "true");
+		assert_eq!(calls.load(Ordering::SeqCst), 2);
+	}
+
+	/// Misses the first lookup of each key, as if the leader recorded right after it
+	struct LateStore {
+		inner: MemIdempotencyStore,
+		missed: Mutex<Vec<String>>,
+	}
+
+	#[async_trait::async_trait]
+	impl IdempotencyStore for LateStore {
+		async fn get(&self, key: &str) -> Option<IdempotentResponse> {
+			let first = {
+				let mut missed = self.missed.lock().unwrap();
+				let first = !missed.iter().any(|k| k == key);
+				if first {
+					missed.push(key.to_string());
+				}
+				first
+			};
+			if first {
+				return None;
+			}
+			self.inner.get(key).await
+		}
+
+		async fn put(&self, key: &str, resp: IdempotentResponse, ttl: Duration) {
+			self.inner.put(key, resp, ttl).await
+		}
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_recorded_after_lookup() {
+		let store = Arc::new(LateStore {
+			inner: MemIdempotencyStore::new(100),
+			missed: Mutex::default(),
+		});
+		let layer = IdempotencyLayer::with_config(IdempotencyConfig::default(), store.clone());
+		let recorded = IdempotentResponse {
+			fingerprint: "f1".to_string(),
+			status: 201,
+			headers: vec![],
+			body: Some(b"payment 1".to_vec()),
+		};
+		store.put("k1", recorded.clone(), layer.ttl()).await;
+
+		assert!(matches!(layer.claim("k1", "f1").await, Claim::Replay(r) if r == recorded));
+		assert!(layer.in_flight.lock().unwrap().is_empty());
+		store.missed.lock().unwrap().clear();
+		assert!(matches!(layer.claim("k1", "f2").await, Claim::Conflict));
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_response_over_limit() {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let config = IdempotencyConfig {
+			body_limit: 16,
+			..Default::default()
+		};
+		let app = app(calls.clone(), config);
+
+		// passed through whole the first time
+		let (resp, body) = call(&app, Some("k1"), "10 USD").await;
+		assert_eq!(resp.status(), StatusCode::CREATED);
+		assert_eq!(body, "payment 1: 10 USD");
+
+		let (resp, body) = call(&app, Some("k1"), "10 USD").await;
+		assert_eq!(resp.status(), StatusCode::UNPROCESSABLE_ENTITY);
+		let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+		assert_eq!(body["code"], "IDEM04");
+		assert_eq!(calls.load(Ordering::SeqCst), 1);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_expiry() {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let config = IdempotencyConfig {
+			ttl_secs: 60,
+			..Default::default()
+		};
+		let app = app(calls.clone(), config);
+
+		call(&app, Some("k1"), "10 USD").await;
+		tokio::time::advance(Duration::from_secs(30)).await;
+		assert_eq!(
+			call(&app, Some("k1"), "10 USD").await.1,
+			"payment 1: 10 USD"
+		);
+
+		tokio::time::advance(Duration::from_secs(31)).await;
+		assert_eq!(
+			call(&app, Some("k1"), "10 USD").await.1,
+			"payment 2: 10 USD"
+		);
+		assert_eq!(calls.load(Ordering::SeqCst), 2);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_concurrent_duplicates() {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let app = app(calls.clone(), IdempotencyConfig::default());
+
+		let (a, b, c) = tokio::join!(
+			call(&app, Some("k1"), "10 USD"),
+			call(&app, Some("k1"), "10 USD"),
+			call(&app, Some("k1"), "11 USD"),
+		);
+		assert_eq!(calls.load(Ordering::SeqCst), 1);
+		assert_eq!(a.1, "payment 1: 10 USD");
+		assert_eq!(b.1, a.1);
+		assert_eq!(b.0.headers()[IDEMPOTENT_REPLAYED_HEADER], "true");
+		assert_eq!(c.0.status(), StatusCode::CONFLICT);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_server_error_not_recorded() {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let app = app(calls.clone(), IdempotencyConfig::default());
+
+		let (a, b) = tokio::join!(
+			call(&app, Some("k1"), "fail"),
+			call(&app, Some("k1"), "fail")
+		);
+		assert_eq!(a.0.status(), StatusCode::SERVICE_UNAVAILABLE);
+		assert_eq!(b.0.status(), StatusCode::SERVICE_UNAVAILABLE);
+		// the waiting duplicate ran the handler itself
+		assert_eq!(calls.load(Ordering::SeqCst), 2);
+	}
+}
@@ -0,0 +1,211 @@
+use crate::http::{TrustedProxies, canonical_ip};
+use crate::result::{AxumError, WebErr};
+use axum::extract::Request;
+use axum::response::{IntoResponse, Response};
+use base_infra::result::AppError;
+use http::StatusCode;
+use ipnetwork::IpNetwork;
+use serde::Deserialize;
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+use tracing::warn;
+
+/// Allowed client networks
+///
+/// ```yaml
+/// ip_acl:
+///   allowed_cidrs: ["10.0.0.0/8", "127.0.0.1/32"]
+///   trusted_proxies:
+///     cidrs: ["10.0.0.0/8"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct IpAclConfig {
+	pub allowed_cidrs: Vec<IpNetwork>,
+	/// Proxies whose forwarded header is used for the client ip, see
+	/// [`TrustedProxies::client_ip`]. Leave empty when clients reach the server directly.
+	pub trusted_proxies: TrustedProxies,
+}
+
+impl IpAclConfig {
+	pub fn localhost_only() -> Self {
+		Self::with_cidrs(&["127.0.0.0/8", "::1/128"])
+	}
+
+	/// Loopback plus the RFC 1918 and IPv6 unique local ranges
+	pub fn private_networks() -> Self {
+		Self::with_cidrs(&[
+			"127.0.0.0/8",
+			"10.0.0.0/8",
+			"172.16.0.0/12",
+			"192.168.0.0/16",
+			"::1/128",
+			"fc00::/7",
+		])
+	}
+
+	pub fn trust_proxies(mut self, proxies: TrustedProxies) -> Self {
+		self.trusted_proxies = proxies;
+		self
+	}
+
+	fn with_cidrs(cidrs: &[&str]) -> Self {
+		Self {
+			allowed_cidrs: cidrs
+				.iter()
+				.map(|c| c.parse().expect("valid builtin cidr"))
+				.collect(),
+			trusted_proxies: TrustedProxies::default(),
+		}
+	}
+
+	pub fn is_allowed(&self, ip: IpAddr) -> bool {
+		let ip = canonical_ip(ip);
+		self.allowed_cidrs.iter().any(|net| net.contains(ip))
+	}
+}
+
+/// Rejects clients outside `allowed_cidrs` with 403, e.g. for admin routes
+///
+/// The connection address needs `into_make_service_with_connect_info::<SocketAddr>()`, a request
+/// without a resolvable ip is rejected.
+#[derive(Clone)]
+pub struct IpAclLayer {
+	config: Arc<IpAclConfig>,
+}
+
+impl IpAclLayer {
+	pub fn new(config: IpAclConfig) -> Self {
+		Self {
+			config: Arc::new(config),
+		}
+	}
+}
+
+impl<S> Layer<S> for IpAclLayer {
+	type Service = IpAclService<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		IpAclService {
+			inner,
+			config: self.config.clone(),
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct IpAclService<S> {
+	inner: S,
+	config: Arc<IpAclConfig>,
+}
+
+impl<S> Service<Request> for IpAclService<S>
+where
+	S: Service<Request, Response = Response> + Clone + Send + 'static,
+	S::Future: Send + 'static,
+{
+	type Response = Response;
+	type Error = S::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
+
+	fn call(&mut self, req: Request) -> Self::Future {
+		let clone = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, clone);
+		let config = self.config.clone();
+
+		Box::pin(async move {
+			let ip = config.trusted_proxies.client_ip(&req);
+			if ip.is_some_and(|ip| config.is_allowed(ip)) {
+				return inner.call(req).await;
+			}
+			warn!(client = ?ip, path = req.uri().path(), "{}", WebErr::IpNotAllowed);
+			let err = AppError::HttpErr(&WebErr::IpNotAllowed, StatusCode::FORBIDDEN);
+			Ok(AxumError::AppError(err).into_response())
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::Router;
+	use axum::body::Body;
+	use axum::extract::ConnectInfo;
+	use axum::routing::get;
+	use base_infra::result::ErrorCode;
+	use std::net::SocketAddr;
+	use tower::ServiceExt;
+
+	fn app(config: IpAclConfig) -> Router {
+		Router::new()
+			.route("/admin", get(|| async { "ok" }))
+			.layer(IpAclLayer::new(config))
+	}
+
+	async fn call(config: IpAclConfig, peer: &str, forwarded: Option<&str>) -> Response {
+		let mut req = Request::builder().uri("/admin");
+		if let Some(ip) = forwarded {
+			req = req.header("x-forwarded-for", ip);
+		}
+		let mut req = req.body(Body::empty()).unwrap();
+		let addr: SocketAddr = format!("{peer}:40000").parse().unwrap();
+		req.extensions_mut().insert(ConnectInfo(addr));
+		app(config).oneshot(req).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn test_localhost_only() {
+		let resp = call(IpAclConfig::localhost_only(), "127.0.0.1", None).await;
+		assert_eq!(resp.status(), StatusCode::OK);
+
+		let resp = call(IpAclConfig::localhost_only(), "8.8.8.8", None).await;
+		assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		let body = String::from_utf8(body.to_vec()).unwrap();
+		assert!(body.contains(WebErr::IpNotAllowed.code()), "{body}");
+
+		assert!(IpAclConfig::private_networks().is_allowed("192.168.1.20".parse().unwrap()));
+		assert!(IpAclConfig::private_networks().is_allowed("::ffff:10.1.2.3".parse().unwrap()));
+		assert!(!IpAclConfig::private_networks().is_allowed("172.32.0.1".parse().unwrap()));
+	}
+
+	#[tokio::test]
+	async fn test_forwarded_header_trust() {
+		// spoofed header is ignored unless trusted
+		let resp = call(IpAclConfig::localhost_only(), "8.8.8.8", Some("127.0.0.1")).await;
+		assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+		let proxies = TrustedProxies::new(vec!["10.0.0.0/24".parse().unwrap()]);
+		let trusted = IpAclConfig::private_networks().trust_proxies(proxies);
+		let resp = call(trusted.clone(), "10.0.0.2", Some("10.1.1.1, 10.0.0.3")).await;
+		assert_eq!(resp.status(), StatusCode::OK);
+		let resp = call(trusted.clone(), "10.0.0.2", Some("8.8.8.8")).await;
+		assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+		// only a trusted peer may forward
+		let resp = call(trusted, "8.8.8.8", Some("10.1.1.1")).await;
+		assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+	}
+
+	#[tokio::test]
+	async fn test_spoofed_forwarded_rejected() {
+		// the client prepends an allowed ip, the proxy appends the real one
+		let proxies = TrustedProxies::new(vec!["10.0.0.2/32".parse().unwrap()]);
+		let config = IpAclConfig::localhost_only().trust_proxies(proxies);
+		let resp = call(config.clone(), "10.0.0.2", Some("127.0.0.1, 8.8.8.8")).await;
+		assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+		let resp = call(config, "10.0.0.2", Some("8.8.8.8, 127.0.0.1")).await;
+		assert_eq!(resp.status(), StatusCode::OK);
+	}
+}
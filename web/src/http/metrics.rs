@@ -0,0 +1,287 @@
+use crate::http::RateLimitStats;
+use crate::result::{AxumError, WebErr};
+use axum::Router;
+use axum::extract::{MatchedPath, Request, State};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use base_infra::map_err;
+use base_infra::result::{AppError, AppResult};
+use http::{StatusCode, header};
+use prometheus::core::{Collector, Desc};
+use prometheus::proto::MetricFamily;
+use prometheus::{
+	HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tower::{Layer, Service};
+use tracing::warn;
+
+pub const METRICS_PATH: &str = "/metrics";
+
+const TEXT_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+/// Path label of requests no route matched, keeps 404 scans from exploding the label set
+const UNMATCHED_PATH: &str = "unmatched";
+
+/// `GET /metrics` serving `registry` in the Prometheus text format
+pub fn metrics_router<S>(registry: Arc<Registry>) -> Router<S>
+where
+	S: Clone + Send + Sync + 'static,
+{
+	Router::new()
+		.route(METRICS_PATH, get(metrics))
+		.with_state(registry)
+}
+
+async fn metrics(State(registry): State<Arc<Registry>>) -> Response {
+	match TextEncoder::new().encode_to_string(&registry.gather()) {
+		Ok(body) => ([(header::CONTENT_TYPE, TEXT_CONTENT_TYPE)], body).into_response(),
+		Err(e) => {
+			warn!("{}: {e}", WebErr::MetricsEncodeErr);
+			let err = AppError::HttpErr(&WebErr::MetricsEncodeErr, StatusCode::INTERNAL_SERVER_ERROR);
+			AxumError::AppError(err).into_response()
+		}
+	}
+}
+
+/// Counts requests into `http_requests_total{method,path,status}` and times them into
+/// `http_request_duration_seconds{method,path}`, `path` being the matched route template
+#[derive(Clone)]
+pub struct MetricsLayer {
+	requests: IntCounterVec,
+	duration: HistogramVec,
+}
+
+impl MetricsLayer {
+	/// Registers the request metrics in `registry`, fails if they are registered already
+	pub fn new(registry: &Registry) -> AppResult<Self> {
+		let requests = IntCounterVec::new(
+			Opts::new("http_requests_total", "Http requests handled"),
+			&["method", "path", "status"],
+		)
+		.map_err(map_err!(&WebErr::MetricsRegisterErr, "http_requests_total"))?;
+		let duration = HistogramVec::new(
+			HistogramOpts::new("http_request_duration_seconds", "Http request latency"),
+			&["method", "path"],
+		)
+		.map_err(map_err!(
+			&WebErr::MetricsRegisterErr,
+			"http_request_duration_seconds"
+		))?;
+
+		registry
+			.register(Box::new(requests.clone()))
+			.map_err(map_err!(&WebErr::MetricsRegisterErr, "http_requests_total"))?;
+		registry
+			.register(Box::new(duration.clone()))
+			.map_err(map_err!(
+				&WebErr::MetricsRegisterErr,
+				"http_request_duration_seconds"
+			))?;
+		Ok(Self { requests, duration })
+	}
+}
+
+impl<S> Layer<S> for MetricsLayer {
+	type Service = MetricsService<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		MetricsService {
+			inner,
+			layer: self.clone(),
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct MetricsService<S> {
+	inner: S,
+	layer: MetricsLayer,
+}
+
+impl<S> Service<Request> for MetricsService<S>
+where
+	S: Service<Request, Response = Response> + Clone + Send + 'static,
+	S::Future: Send + 'static,
+{
+	type Response = Response;
+	type Error = S::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
+
+	fn call(&mut self, req: Request) -> Self::Future {
+		let clone = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, clone);
+		let layer = self.layer.clone();
+		let method = req.method().to_string();
+		let path = req
+			.extensions()
+			.get::<MatchedPath>()
+			.map_or(UNMATCHED_PATH, |p| p.as_str())
+			.to_string();
+
+		Box::pin(async move {
+			let start = Instant::now();
+			let resp = inner.call(req).await?;
+			let status = resp.status().as_u16().to_string();
+			layer
+				.requests
+				.with_label_values(&[method.as_str(), path.as_str(), status.as_str()])
+				.inc();
+			layer
+				.duration
+				.with_label_values(&[method.as_str(), path.as_str()])
+				.observe(start.elapsed().as_secs_f64());
+			Ok(resp)
+		})
+	}
+}
+
+/// Exports [`RateLimitStats`] as `rate_limit_allowed_total` and `rate_limit_limited_total`
+pub struct RateLimitCollector {
+	stats: Arc<RateLimitStats>,
+	allowed: IntCounter,
+	limited: IntCounter,
+}
+
+impl RateLimitCollector {
+	/// Registers `stats`, e.g. from [`RateLimitLayer::stats`](super::RateLimitLayer::stats), in
+	/// `registry`
+	pub fn register(registry: &Registry, stats: Arc<RateLimitStats>) -> AppResult<()> {
+		let counter = |name: &str, help: &str| {
+			IntCounter::new(name, help).map_err(map_err!(&WebErr::MetricsRegisterErr, name))
+		};
+		let allowed = counter(
+			"rate_limit_allowed_total",
+			"Requests passed by the rate limit",
+		)?;
+		let limited = counter("rate_limit_limited_total", "Requests rejected with 429")?;
+		let collector = Self {
+			stats,
+			allowed,
+			limited,
+		};
+		registry
+			.register(Box::new(collector))
+			.map_err(map_err!(&WebErr::MetricsRegisterErr, "rate_limit"))
+	}
+}
+
+impl Collector for RateLimitCollector {
+	fn desc(&self) -> Vec<&Desc> {
+		self.allowed
+			.desc()
+			.into_iter()
+			.chain(self.limited.desc())
+			.collect()
+	}
+
+	fn collect(&self) -> Vec<MetricFamily> {
+		// the stats only grow, catch the counters up on them
+		self.allowed
+			.inc_by(self.stats.allowed().saturating_sub(self.allowed.get()));
+		self.limited
+			.inc_by(self.stats.limited().saturating_sub(self.limited.get()));
+		self.allowed
+			.collect()
+			.into_iter()
+			.chain(self.limited.collect())
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::body::Body;
+	use prometheus::IntCounter;
+	use tower::ServiceExt;
+
+	async fn get_text(app: &Router, path: &str) -> (StatusCode, Option<String>, String) {
+		let req = Request::builder().uri(path).body(Body::empty()).unwrap();
+		let resp = app.clone().oneshot(req).await.unwrap();
+		let status = resp.status();
+		let content_type = resp
+			.headers()
+			.get(header::CONTENT_TYPE)
+			.map(|v| v.to_str().unwrap().to_string());
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		(
+			status,
+			content_type,
+			String::from_utf8(body.to_vec()).unwrap(),
+		)
+	}
+
+	#[tokio::test]
+	async fn test_metrics_endpoint() {
+		let registry = Arc::new(Registry::new());
+		let counter = IntCounter::new("orders_created_total", "Orders created").unwrap();
+		registry.register(Box::new(counter.clone())).unwrap();
+		counter.inc_by(3);
+
+		let app = metrics_router::<()>(registry);
+		let (status, content_type, body) = get_text(&app, METRICS_PATH).await;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(content_type.as_deref(), Some(TEXT_CONTENT_TYPE));
+		assert!(
+			body.contains("# TYPE orders_created_total counter"),
+			"{body}"
+		);
+		assert!(body.contains("orders_created_total 3"), "{body}");
+	}
+
+	#[tokio::test]
+	async fn test_metrics_layer() {
+		let registry = Arc::new(Registry::new());
+		let layer = MetricsLayer::new(&registry).unwrap();
+		assert!(MetricsLayer::new(&registry).is_err());
+
+		let app = Router::new()
+			.route("/users/{id}", get(|| async { "user" }))
+			.layer(layer)
+			.merge(metrics_router(registry));
+		for id in 1..=2 {
+			let (status, ..) = get_text(&app, &format!("/users/{id}")).await;
+			assert_eq!(status, StatusCode::OK);
+		}
+
+		let (_, _, body) = get_text(&app, METRICS_PATH).await;
+		let line = r#"http_requests_total{method="GET",path="/users/{id}",status="200"} 2"#;
+		assert!(body.contains(line), "{body}");
+		assert!(
+			body.contains("http_request_duration_seconds_count"),
+			"{body}"
+		);
+		// the metrics route itself sits outside the layer
+		assert!(!body.contains(METRICS_PATH), "{body}");
+	}
+
+	#[tokio::test]
+	async fn test_rate_limit_collector() {
+		let registry = Registry::new();
+		let layer = crate::http::rate_limit_layer(Default::default());
+		RateLimitCollector::register(&registry, layer.stats()).unwrap();
+
+		let app = Router::new()
+			.route("/items", get(|| async { "ok" }))
+			.layer(layer);
+		for _ in 0..2 {
+			let (status, ..) = get_text(&app, "/items").await;
+			assert_eq!(status, StatusCode::OK);
+		}
+		let body = TextEncoder::new()
+			.encode_to_string(&registry.gather())
+			.unwrap();
+		assert!(body.contains("rate_limit_allowed_total 2"), "{body}");
+		assert!(body.contains("rate_limit_limited_total 0"), "{body}");
+	}
+}
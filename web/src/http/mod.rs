@@ -1,17 +1,44 @@
+mod build_info;
+mod client_ip;
+mod compression;
+mod cors;
+mod deadline;
 mod error;
+mod flags;
+pub mod health;
+mod idempotency;
+mod ip_acl;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod rate_limit;
+mod request_id;
+mod spa;
 mod trace;
+mod webhook;
 
+pub use build_info::*;
+pub use client_ip::*;
+pub use compression::*;
+pub use cors::*;
+pub use deadline::*;
 pub use error::*;
+pub use flags::*;
+pub use idempotency::*;
+pub use ip_acl::*;
+#[cfg(feature = "metrics")]
+pub use metrics::*;
+pub use rate_limit::*;
+pub use request_id::*;
+pub use spa::*;
 pub use trace::*;
+pub use webhook::*;
 
-use base_infra::utils::uuid::UID;
 use http::Request;
 use tracing::{Span, info, info_span};
 
-pub fn make_span<B>(_request: &Request<B>) -> Span {
-	// let headers = request.headers();
-	let trace_id = UID.v4_simple_str();
-	info_span!("api", tid = trace_id.to_string())
+pub fn make_span<B>(request: &Request<B>) -> Span {
+	let trace_id = resolve_request_id(request.headers(), REQUEST_ID_HEADER);
+	info_span!("api", tid = trace_id)
 }
 
 pub fn accept_trace<B>(request: Request<B>) -> Request<B> {
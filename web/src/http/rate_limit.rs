@@ -0,0 +1,455 @@
+use crate::auth::KeyInfo;
+use crate::http::TrustedProxies;
+use crate::result::{AxumError, WebErr};
+use axum::extract::Request;
+use axum::response::{IntoResponse, Response};
+use base_infra::result::AppError;
+use http::header::RETRY_AFTER;
+use http::{HeaderValue, StatusCode};
+use moka::sync::Cache;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Instant;
+use tower::{Layer, Service};
+use tracing::warn;
+
+/// Bucket size and refill speed
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct RateLimitRule {
+	/// Max tokens a bucket holds, i.e. the allowed burst
+	pub burst: u32,
+	/// Tokens added per second
+	pub refill_per_sec: f64,
+}
+
+impl Default for RateLimitRule {
+	fn default() -> Self {
+		Self {
+			burst: 20,
+			refill_per_sec: 10.0,
+		}
+	}
+}
+
+/// Rate limit config
+///
+/// ```yaml
+/// rate_limit:
+///   default: { burst: 20, refill_per_sec: 10 }
+///   routes:
+///     /api/login: { burst: 5, refill_per_sec: 0.1 }
+///   trusted_proxies:
+///     cidrs: ["10.0.0.0/8"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+	#[serde(default)]
+	pub default: RateLimitRule,
+	/// Path prefix overrides matched at a segment boundary (`/api/user` covers `/api/user/1` but
+	/// not `/api/users`), the longest matching prefix wins
+	#[serde(default)]
+	pub routes: HashMap<String, RateLimitRule>,
+	/// Idle buckets are evicted after this many seconds
+	#[serde(default = "default_idle_secs")]
+	pub idle_secs: u64,
+	#[serde(default = "default_max_buckets")]
+	pub max_buckets: u64,
+	/// Proxies whose forwarded header is used for the client ip
+	#[serde(default)]
+	pub trusted_proxies: TrustedProxies,
+}
+
+fn default_idle_secs() -> u64 {
+	600
+}
+
+fn default_max_buckets() -> u64 {
+	100_000
+}
+
+impl Default for RateLimitConfig {
+	fn default() -> Self {
+		Self {
+			default: RateLimitRule::default(),
+			routes: HashMap::new(),
+			idle_secs: default_idle_secs(),
+			max_buckets: default_max_buckets(),
+			trusted_proxies: TrustedProxies::default(),
+		}
+	}
+}
+
+impl RateLimitConfig {
+	/// Returns the matched route prefix (empty for the default rule) and its rule
+	fn rule_for<'a>(&'a self, path: &str) -> (&'a str, &'a RateLimitRule) {
+		self.routes
+			.iter()
+			.filter(|(prefix, _)| matches_prefix(path, prefix))
+			.max_by_key(|(prefix, _)| prefix.len())
+			.map(|(prefix, rule)| (prefix.as_str(), rule))
+			.unwrap_or(("", &self.default))
+	}
+}
+
+/// `prefix` equals `path` or ends at one of its segment boundaries, e.g. `/api/` or `/api`
+fn matches_prefix(path: &str, prefix: &str) -> bool {
+	match path.strip_prefix(prefix) {
+		Some(rest) => rest.is_empty() || rest.starts_with('/') || prefix.ends_with('/'),
+		None => false,
+	}
+}
+
+/// Caller identity set by an upstream auth layer, e.g. the JWT subject
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientSubject(pub String);
+
+/// Resolve the bucket owner: api key tag, then subject, then remote ip
+fn client_identity(req: &Request, proxies: &TrustedProxies) -> String {
+	if let Some(key) = req.extensions().get::<KeyInfo>() {
+		return format!("key:{}", key.rate_tag());
+	}
+	if let Some(subject) = req.extensions().get::<ClientSubject>() {
+		return format!("sub:{}", subject.0);
+	}
+
+	match proxies.client_ip(req) {
+		Some(ip) => format!("ip:{ip}"),
+		None => "ip:unknown".to_string(),
+	}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBucket {
+	tokens: f64,
+	last_refill: Instant,
+}
+
+impl TokenBucket {
+	pub fn new(rule: &RateLimitRule) -> Self {
+		Self {
+			tokens: rule.burst as f64,
+			last_refill: Instant::now(),
+		}
+	}
+
+	/// Take one token, or return how long until one is available
+	pub fn try_acquire(&mut self, rule: &RateLimitRule) -> Result<(), Duration> {
+		let now = Instant::now();
+		let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * rule.refill_per_sec).min(rule.burst as f64);
+		self.last_refill = now;
+
+		if self.tokens >= 1.0 {
+			self.tokens -= 1.0;
+			return Ok(());
+		}
+		if rule.refill_per_sec <= 0.0 {
+			return Err(Duration::MAX);
+		}
+		Err(Duration::from_secs_f64(
+			(1.0 - self.tokens) / rule.refill_per_sec,
+		))
+	}
+}
+
+/// Bucket storage, in-memory by default; a shared store (e.g. Redis) can implement this
+#[async_trait::async_trait]
+pub trait RateLimitStore: Send + Sync {
+	/// Ok when the request may pass, otherwise the time to wait before retrying
+	async fn acquire(&self, key: &str, rule: &RateLimitRule) -> Result<(), Duration>;
+}
+
+pub struct MemRateLimitStore {
+	buckets: Cache<String, Arc<Mutex<TokenBucket>>>,
+}
+
+impl MemRateLimitStore {
+	pub fn new(idle: Duration, max_capacity: u64) -> Self {
+		let buckets = Cache::builder()
+			.time_to_idle(idle)
+			.max_capacity(max_capacity)
+			.build();
+		Self { buckets }
+	}
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for MemRateLimitStore {
+	async fn acquire(&self, key: &str, rule: &RateLimitRule) -> Result<(), Duration> {
+		let bucket = self
+			.buckets
+			.get_with_by_ref(key, || Arc::new(Mutex::new(TokenBucket::new(rule))));
+		let mut bucket = bucket.lock().unwrap_or_else(|e| e.into_inner());
+		bucket.try_acquire(rule)
+	}
+}
+
+/// Allowed/limited counters, exported to Prometheus by `RateLimitCollector` (feature `metrics`)
+#[derive(Debug, Default)]
+pub struct RateLimitStats {
+	allowed: AtomicU64,
+	limited: AtomicU64,
+}
+
+impl RateLimitStats {
+	pub fn allowed(&self) -> u64 {
+		self.allowed.load(Ordering::Relaxed)
+	}
+
+	pub fn limited(&self) -> u64 {
+		self.limited.load(Ordering::Relaxed)
+	}
+}
+
+/// Token bucket rate limiting keyed by client identity, over limit gets 429 with `Retry-After`
+///
+/// Place it inside `api_key_layer` so the key name is used as identity.
+pub fn rate_limit_layer(config: RateLimitConfig) -> RateLimitLayer {
+	let store = MemRateLimitStore::new(Duration::from_secs(config.idle_secs), config.max_buckets);
+	RateLimitLayer::with_store(config, Arc::new(store))
+}
+
+#[derive(Clone)]
+pub struct RateLimitLayer {
+	config: Arc<RateLimitConfig>,
+	store: Arc<dyn RateLimitStore>,
+	stats: Arc<RateLimitStats>,
+}
+
+impl RateLimitLayer {
+	pub fn with_store(config: RateLimitConfig, store: Arc<dyn RateLimitStore>) -> Self {
+		Self {
+			config: Arc::new(config),
+			store,
+			stats: Arc::new(RateLimitStats::default()),
+		}
+	}
+
+	pub fn stats(&self) -> Arc<RateLimitStats> {
+		self.stats.clone()
+	}
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+	type Service = RateLimitService<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		RateLimitService {
+			inner,
+			layer: self.clone(),
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+	inner: S,
+	layer: RateLimitLayer,
+}
+
+impl<S> Service<Request> for RateLimitService<S>
+where
+	S: Service<Request, Response = Response> + Clone + Send + 'static,
+	S::Future: Send + 'static,
+{
+	type Response = Response;
+	type Error = S::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
+
+	fn call(&mut self, req: Request) -> Self::Future {
+		let clone = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, clone);
+		let layer = self.layer.clone();
+
+		Box::pin(async move {
+			let client = client_identity(&req, &layer.config.trusted_proxies);
+			let (route, rule) = layer.config.rule_for(req.uri().path());
+			let key = format!("{route}|{client}");
+
+			match layer.store.acquire(&key, rule).await {
+				Ok(()) => {
+					layer.stats.allowed.fetch_add(1, Ordering::Relaxed);
+					inner.call(req).await
+				}
+				Err(wait) => {
+					layer.stats.limited.fetch_add(1, Ordering::Relaxed);
+					warn!(client = %client, route = %route, "{}", WebErr::TooManyRequests);
+					Ok(too_many_requests(wait))
+				}
+			}
+		})
+	}
+}
+
+fn too_many_requests(wait: Duration) -> Response {
+	let err = AppError::HttpErr(&WebErr::TooManyRequests, StatusCode::TOO_MANY_REQUESTS);
+	let mut resp = AxumError::AppError(err).into_response();
+	let secs = wait.as_secs_f64().ceil().clamp(1.0, u32::MAX as f64) as u64;
+	resp.headers_mut()
+		.insert(RETRY_AFTER, HeaderValue::from(secs));
+	resp
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::Router;
+	use axum::body::Body;
+	use axum::extract::ConnectInfo;
+	use axum::routing::get;
+	use std::net::SocketAddr;
+	use tower::ServiceExt;
+
+	fn app(layer: RateLimitLayer) -> Router {
+		Router::new()
+			.route("/api/items", get(|| async { "ok" }))
+			.route("/api/login", get(|| async { "ok" }))
+			.layer(layer)
+	}
+
+	async fn call(app: &Router, uri: &str, ip: &str) -> Response {
+		call_via(app, uri, ip, None).await
+	}
+
+	async fn call_via(app: &Router, uri: &str, peer: &str, forwarded: Option<&str>) -> Response {
+		let mut req = Request::builder().uri(uri);
+		if let Some(ip) = forwarded {
+			req = req.header("x-forwarded-for", ip);
+		}
+		let mut req = req.body(Body::empty()).unwrap();
+		let addr: SocketAddr = format!("{peer}:40000").parse().unwrap();
+		req.extensions_mut().insert(ConnectInfo(addr));
+		app.clone().oneshot(req).await.unwrap()
+	}
+
+	fn config() -> RateLimitConfig {
+		RateLimitConfig {
+			default: RateLimitRule {
+				burst: 2,
+				refill_per_sec: 1.0,
+			},
+			routes: HashMap::from([(
+				"/api/login".to_string(),
+				RateLimitRule {
+					burst: 1,
+					refill_per_sec: 0.1,
+				},
+			)]),
+			..Default::default()
+		}
+	}
+
+	#[test]
+	fn test_rule_for_segment_boundary() {
+		let mut config = config();
+		config.routes.insert(
+			"/api/".to_string(),
+			RateLimitRule {
+				burst: 3,
+				refill_per_sec: 1.0,
+			},
+		);
+		assert_eq!(config.rule_for("/api/login").0, "/api/login");
+		assert_eq!(config.rule_for("/api/login/otp").0, "/api/login");
+		assert_eq!(config.rule_for("/api/logins").0, "/api/");
+		assert_eq!(config.rule_for("/api/login-admin").0, "/api/");
+		assert_eq!(config.rule_for("/api").0, "");
+		assert_eq!(config.rule_for("/health").0, "");
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_bucket_refill() {
+		let layer = rate_limit_layer(config());
+		let stats = layer.stats();
+		let app = app(layer);
+
+		assert_eq!(
+			call(&app, "/api/items", "1.1.1.1").await.status(),
+			StatusCode::OK
+		);
+		assert_eq!(
+			call(&app, "/api/items", "1.1.1.1").await.status(),
+			StatusCode::OK
+		);
+		let resp = call(&app, "/api/items", "1.1.1.1").await;
+		assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+		tokio::time::advance(Duration::from_secs(1)).await;
+		assert_eq!(
+			call(&app, "/api/items", "1.1.1.1").await.status(),
+			StatusCode::OK
+		);
+		assert_eq!(stats.allowed(), 3);
+		assert_eq!(stats.limited(), 1);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_per_key_isolation() {
+		let app = app(rate_limit_layer(config()));
+
+		assert_eq!(
+			call(&app, "/api/login", "1.1.1.1").await.status(),
+			StatusCode::OK
+		);
+		let resp = call(&app, "/api/login", "1.1.1.1").await;
+		assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+		// Other client and other route keep their own buckets
+		assert_eq!(
+			call(&app, "/api/login", "2.2.2.2").await.status(),
+			StatusCode::OK
+		);
+		assert_eq!(
+			call(&app, "/api/items", "1.1.1.1").await.status(),
+			StatusCode::OK
+		);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_limited_response() {
+		let app = app(rate_limit_layer(config()));
+		call(&app, "/api/login", "1.1.1.1").await;
+		let resp = call(&app, "/api/login", "1.1.1.1").await;
+
+		assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+		assert_eq!(resp.headers()[RETRY_AFTER], "10");
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+		assert_eq!(body["code"], "LIMIT1");
+		assert!(body["data"].is_null());
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn test_forwarded_identity() {
+		let config = RateLimitConfig {
+			trusted_proxies: TrustedProxies::new(vec!["10.0.0.0/8".parse().unwrap()]),
+			..config()
+		};
+		let app = app(rate_limit_layer(config));
+
+		let resp = call_via(&app, "/api/login", "10.0.0.1", Some("1.1.1.1")).await;
+		assert_eq!(resp.status(), StatusCode::OK);
+		let resp = call_via(&app, "/api/login", "10.0.0.1", Some("1.1.1.1")).await;
+		assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+		let resp = call_via(&app, "/api/login", "10.0.0.1", Some("2.2.2.2")).await;
+		assert_eq!(resp.status(), StatusCode::OK);
+
+		// a direct client can't get a fresh bucket by forging the header
+		let resp = call_via(&app, "/api/login", "3.3.3.3", Some("4.4.4.4")).await;
+		assert_eq!(resp.status(), StatusCode::OK);
+		let resp = call_via(&app, "/api/login", "3.3.3.3", Some("5.5.5.5")).await;
+		assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+	}
+}
@@ -0,0 +1,139 @@
+use base_infra::utils::uuid::UID;
+use http::{HeaderMap, HeaderValue};
+use std::future::Future;
+use tracing::warn;
+
+/// Default header carrying the request id between services
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+	static CURRENT_REQUEST_ID: String;
+}
+
+/// Accepts ids made of `[A-Za-z0-9-_.:]`, 1~128 chars
+pub fn is_valid_request_id(id: &str) -> bool {
+	!id.is_empty()
+		&& id.len() <= MAX_REQUEST_ID_LEN
+		&& id
+			.bytes()
+			.all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+/// Keeps the visible ascii chars of `value`, at most `max_len` of them, so the result is always a
+/// valid header value
+pub fn sanitize_header_value(value: &str, max_len: usize) -> String {
+	value
+		.chars()
+		.filter(|c| c.is_ascii_graphic())
+		.take(max_len)
+		.collect()
+}
+
+/// Incoming request id from `header` when valid. A malformed one is replaced by a fresh id, never
+/// edited into a different one, and logged as `rejected_request_id`.
+pub fn resolve_request_id(headers: &HeaderMap, header: &str) -> String {
+	let Some(value) = headers.get(header) else {
+		return UID.v4_simple_str();
+	};
+	match value.to_str() {
+		Ok(id) if is_valid_request_id(id) => id.to_string(),
+		_ => {
+			let rejected = sanitize_header_value(
+				&String::from_utf8_lossy(value.as_bytes()),
+				MAX_REQUEST_ID_LEN,
+			);
+			let fresh = UID.v4_simple_str();
+			warn!(
+				rejected_request_id = rejected,
+				request_id = fresh,
+				"malformed incoming request id"
+			);
+			fresh
+		}
+	}
+}
+
+/// `request_id` as a header value, a fresh id with a warning if it can't be one
+pub fn request_id_header_value(request_id: &str) -> HeaderValue {
+	HeaderValue::from_str(request_id).unwrap_or_else(|e| {
+		let fresh = UID.v4_simple_str();
+		warn!(request_id, fresh, "invalid request id header value: {e}");
+		HeaderValue::from_str(&fresh).expect("uuid is a valid header value")
+	})
+}
+
+/// Run `fut` with `request_id` as the current request id
+pub async fn with_request_id<F: Future>(request_id: String, fut: F) -> F::Output {
+	CURRENT_REQUEST_ID.scope(request_id, fut).await
+}
+
+/// Request id of the request being handled, set by the http_trace middleware
+pub fn current_request_id() -> Option<String> {
+	CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Add the current request id to outgoing headers so downstream services share the tid
+///
+/// `client.get(url).headers(headers)` after `inject_request_id(&mut headers)`
+pub fn inject_request_id(headers: &mut HeaderMap) {
+	if let Some(id) = current_request_id() {
+		let id = sanitize_header_value(&id, MAX_REQUEST_ID_LEN);
+		headers.insert(REQUEST_ID_HEADER, request_id_header_value(&id));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_valid_request_id() {
+		assert!(is_valid_request_id("5f2b8c1e9a4d4e2f"));
+		assert!(is_valid_request_id("gw-01:req_42.a"));
+		assert!(!is_valid_request_id(""));
+		assert!(!is_valid_request_id("has space"));
+		assert!(!is_valid_request_id("ünïcode"));
+		assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+	}
+
+	#[test]
+	fn test_sanitize_header_value() {
+		assert_eq!(sanitize_header_value("req-1", 8), "req-1");
+		assert_eq!(sanitize_header_value("ré q\t\u{7f}\0-1", 8), "rq-1");
+		assert_eq!(sanitize_header_value(&"a".repeat(300), 8), "aaaaaaaa");
+		assert_eq!(sanitize_header_value("ünï", 8), "n");
+		assert_eq!(request_id_header_value("ok-1"), "ok-1");
+		assert!(is_valid_request_id(
+			request_id_header_value("bad\nid").to_str().unwrap()
+		));
+	}
+
+	#[test]
+	fn test_resolve_request_id() {
+		let mut headers = HeaderMap::new();
+		assert!(is_valid_request_id(&resolve_request_id(
+			&headers,
+			REQUEST_ID_HEADER
+		)));
+
+		headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("gw-01:req_42"));
+		assert_eq!(
+			resolve_request_id(&headers, REQUEST_ID_HEADER),
+			"gw-01:req_42"
+		);
+
+		headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("req 42"));
+		let id = resolve_request_id(&headers, REQUEST_ID_HEADER);
+		assert!(is_valid_request_id(&id));
+		assert_ne!(id, "req42");
+	}
+
+	#[tokio::test]
+	async fn test_current_request_id() {
+		assert_eq!(current_request_id(), None);
+		let id = with_request_id("req-1".to_string(), async { current_request_id() }).await;
+		assert_eq!(id.as_deref(), Some("req-1"));
+	}
+}
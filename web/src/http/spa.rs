@@ -0,0 +1,255 @@
+use crate::http::not_found_handler;
+use axum::Router;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::response::IntoResponse;
+use http::{HeaderValue, header};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+use tower::ServiceExt;
+use tower_http::services::{ServeDir, ServeFile};
+
+const CACHE_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const CACHE_NONE: &str = "no-cache";
+
+/// Single page app serving options
+///
+/// ```yaml
+/// spa:
+///   cache_immutable_prefixes: ["/assets/"]
+///   api_prefixes: ["/api"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SpaConfig {
+	/// Paths holding content hashed file names, cached for a year and never falling back
+	pub cache_immutable_prefixes: Vec<String>,
+	/// Paths answered with a 404 `RespData` instead of the index when no route matched
+	pub api_prefixes: Vec<String>,
+	/// Entry page relative to the asset dir, served with `no-cache`
+	pub index: String,
+	/// Serve `.br` / `.gz` siblings when the client accepts them
+	pub compressed: bool,
+}
+
+impl Default for SpaConfig {
+	fn default() -> Self {
+		Self {
+			cache_immutable_prefixes: vec!["/assets/".to_string()],
+			api_prefixes: vec!["/api".to_string()],
+			index: "index.html".to_string(),
+			compressed: true,
+		}
+	}
+}
+
+impl SpaConfig {
+	fn is_api(&self, path: &str) -> bool {
+		self.api_prefixes
+			.iter()
+			.any(|prefix| has_prefix(path, prefix))
+	}
+
+	fn is_immutable(&self, path: &str) -> bool {
+		self.cache_immutable_prefixes
+			.iter()
+			.any(|prefix| has_prefix(path, prefix))
+	}
+}
+
+/// `/api` matches `/api` and `/api/..` but not `/apis`
+fn has_prefix(path: &str, prefix: &str) -> bool {
+	match path.strip_prefix(prefix) {
+		Some(rest) => prefix.ends_with('/') || rest.is_empty() || rest.starts_with('/'),
+		None => false,
+	}
+}
+
+/// Serves a built frontend from `dir`, meant as the app router's fallback
+///
+/// Unknown paths get the index so client side routes survive a reload, except under
+/// `api_prefixes` and `cache_immutable_prefixes` which answer 404.
+///
+/// ```ignore
+/// let app = Router::new().nest("/api", api).merge(spa_router("dist", SpaConfig::default()));
+/// ```
+pub fn spa_router(dir: impl AsRef<Path>, config: SpaConfig) -> Router {
+	let dir = dir.as_ref();
+	let mut assets = ServeDir::new(dir);
+	let mut index = ServeFile::new(dir.join(&config.index));
+	if config.compressed {
+		assets = assets.precompressed_br().precompressed_gzip();
+		index = index.precompressed_br().precompressed_gzip();
+	}
+	let pages = assets.clone().fallback(index);
+	let config = Arc::new(config);
+
+	Router::new().fallback(move |req: Request| {
+		let (assets, pages, config) = (assets.clone(), pages.clone(), config.clone());
+		async move {
+			let path = req.uri().path().to_string();
+			if config.is_api(&path) {
+				return not_found_handler(req.uri().clone()).await.into_response();
+			}
+			let immutable = config.is_immutable(&path);
+			let mut resp = if immutable {
+				assets.oneshot(req).await
+			} else {
+				pages.oneshot(req).await
+			}
+			.map(|resp| resp.map(Body::new))
+			.unwrap_or_else(|e| match e {});
+
+			if resp.status().is_success() || resp.status().is_redirection() {
+				let cache = if immutable {
+					CACHE_IMMUTABLE
+				} else {
+					CACHE_NONE
+				};
+				resp.headers_mut()
+					.insert(header::CACHE_CONTROL, HeaderValue::from_static(cache));
+			}
+			if config.compressed {
+				resp.headers_mut()
+					.append(header::VARY, HeaderValue::from_static("accept-encoding"));
+			}
+			resp
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::response::Response;
+	use axum::routing::get;
+	use http::StatusCode;
+	use std::path::PathBuf;
+
+	struct AssetDir(PathBuf);
+
+	impl AssetDir {
+		fn new() -> Self {
+			let dir = std::env::temp_dir().join(format!(
+				"spa-{}",
+				base_infra::utils::uuid::UID.v4_simple_str()
+			));
+			let files: [(&str, &[u8]); 6] = [
+				("index.html", b"<html>app</html>"),
+				("index.html.gz", b"gz-index"),
+				("favicon.ico", b"icon"),
+				("assets/app.3f2a.js", b"console.log(1)"),
+				("assets/app.3f2a.js.br", b"br-js"),
+				("assets/app.3f2a.js.gz", b"gz-js"),
+			];
+			for (name, content) in files {
+				let path = dir.join(name);
+				std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+				std::fs::write(path, content).unwrap();
+			}
+			Self(dir)
+		}
+	}
+
+	impl Drop for AssetDir {
+		fn drop(&mut self) {
+			let _ = std::fs::remove_dir_all(&self.0);
+		}
+	}
+
+	async fn get_path(app: &Router, path: &str, encoding: Option<&str>) -> (Response, String) {
+		let mut req = Request::get(path);
+		if let Some(encoding) = encoding {
+			req = req.header(header::ACCEPT_ENCODING, encoding);
+		}
+		let resp = app
+			.clone()
+			.oneshot(req.body(Body::empty()).unwrap())
+			.await
+			.unwrap();
+		let (parts, body) = resp.into_parts();
+		let bytes = axum::body::to_bytes(body, usize::MAX).await.unwrap();
+		(
+			Response::from_parts(parts, Body::empty()),
+			String::from_utf8_lossy(&bytes).into_owned(),
+		)
+	}
+
+	fn app(dir: &AssetDir) -> Router {
+		Router::new()
+			.route("/api/ping", get(|| async { "pong" }))
+			.merge(spa_router(&dir.0, SpaConfig::default()))
+	}
+
+	#[tokio::test]
+	async fn test_spa_fallback() {
+		let dir = AssetDir::new();
+		let app = app(&dir);
+
+		let (resp, body) = get_path(&app, "/api/ping", None).await;
+		assert_eq!((resp.status(), body.as_str()), (StatusCode::OK, "pong"));
+
+		for path in ["/", "/users/42", "/settings"] {
+			let (resp, body) = get_path(&app, path, None).await;
+			assert_eq!(resp.status(), StatusCode::OK, "{path}");
+			assert_eq!(body, "<html>app</html>");
+			assert_eq!(resp.headers()[header::CACHE_CONTROL], "no-cache");
+		}
+
+		// api and hashed asset paths never get the index
+		for path in ["/api/missing", "/api", "/assets/gone.js"] {
+			let (resp, body) = get_path(&app, path, None).await;
+			assert_eq!(resp.status(), StatusCode::NOT_FOUND, "{path}");
+			assert!(!body.contains("<html>"));
+		}
+		let (resp, body) = get_path(&app, "/api/missing", None).await;
+		assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/json");
+		assert!(body.contains("/api/missing"));
+
+		// `/apis` is not under `/api`
+		let (resp, _) = get_path(&app, "/apis", None).await;
+		assert_eq!(resp.status(), StatusCode::OK);
+	}
+
+	#[tokio::test]
+	async fn test_cache_headers() {
+		let dir = AssetDir::new();
+		let app = app(&dir);
+
+		let (resp, body) = get_path(&app, "/assets/app.3f2a.js", None).await;
+		assert_eq!(body, "console.log(1)");
+		assert_eq!(
+			resp.headers()[header::CACHE_CONTROL],
+			"public, max-age=31536000, immutable"
+		);
+
+		let (resp, body) = get_path(&app, "/favicon.ico", None).await;
+		assert_eq!(body, "icon");
+		assert_eq!(resp.headers()[header::CACHE_CONTROL], "no-cache");
+	}
+
+	#[tokio::test]
+	async fn test_encoding_negotiation() {
+		let dir = AssetDir::new();
+		let app = app(&dir);
+
+		let (resp, body) = get_path(&app, "/assets/app.3f2a.js", Some("gzip;q=0.5, br")).await;
+		assert_eq!(body, "br-js");
+		assert_eq!(resp.headers()[header::CONTENT_ENCODING], "br");
+		assert_eq!(resp.headers()[header::VARY], "accept-encoding");
+
+		let (resp, body) = get_path(&app, "/assets/app.3f2a.js", Some("gzip")).await;
+		assert_eq!(body, "gz-js");
+		assert_eq!(resp.headers()[header::CONTENT_ENCODING], "gzip");
+
+		let (resp, body) = get_path(&app, "/assets/app.3f2a.js", Some("identity")).await;
+		assert_eq!(body, "console.log(1)");
+		assert!(!resp.headers().contains_key(header::CONTENT_ENCODING));
+
+		// the index has only a `.gz` variant
+		let (resp, body) = get_path(&app, "/deep/link", Some("br, gzip")).await;
+		assert_eq!(body, "gz-index");
+		assert_eq!(resp.headers()[header::CONTENT_ENCODING], "gzip");
+	}
+}
@@ -1,11 +1,23 @@
-use axum::body::{Body, Bytes};
+use crate::http::{
+	REQUEST_ID_HEADER, TrustedProxies, request_id_header_value, resolve_request_id, with_request_id,
+};
+use axum::body::{Body, Bytes, HttpBody};
 use axum::extract::Request;
-use axum::http::header::CONTENT_TYPE;
+use axum::http::HeaderMap;
+use axum::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
 use axum::middleware::Next;
 use axum::response::Response;
-use base_infra::utils::uuid::UID;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
 use std::time::Instant;
-use tracing::{Instrument, info, info_span};
+use tower::{Layer, Service};
+use tracing::{Instrument, debug, info, info_span, warn};
 
 #[derive(Debug, Clone)]
 pub struct RequestInfo {
@@ -18,8 +30,15 @@ pub struct RequestInfo {
 }
 
 impl RequestInfo {
+	/// Honors an incoming `x-request-id` and trusts no proxy, see [`RequestInfo::with_id_header`]
 	pub fn new(req: &Request) -> Self {
-		let request_id = UID.v4_simple_str();
+		Self::with_id_header(req, REQUEST_ID_HEADER, &TrustedProxies::default())
+	}
+
+	/// Takes the request id from `id_header` when present and valid, otherwise generates one.
+	/// `remote_addr` is [`TrustedProxies::client_ip`].
+	pub fn with_id_header(req: &Request, id_header: &str, proxies: &TrustedProxies) -> Self {
+		let request_id = resolve_request_id(req.headers(), id_header);
 		let method = req.method().to_string();
 		let path = req.uri().path().to_string();
 
@@ -29,12 +48,7 @@ impl RequestInfo {
 			.and_then(|v| v.to_str().ok())
 			.map(|s| s.to_string());
 
-		let remote_addr = req
-			.headers()
-			.get("x-forwarded-for")
-			.or_else(|| req.headers().get("x-real-ip"))
-			.and_then(|v| v.to_str().ok())
-			.map(|s| s.to_string());
+		let remote_addr = proxies.client_ip(req).map(|ip| ip.to_string());
 
 		Self {
 			request_id,
@@ -47,10 +61,86 @@ impl RequestInfo {
 	}
 }
 
-fn should_log_body(req: &Request, body_bytes: &Bytes) -> bool {
+/// Target of the access log events
+pub const ACCESS_LOG_TARGET: &str = "access_log";
+
+/// One line per request, emitted as json under [`ACCESS_LOG_TARGET`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessLogEntry {
+	pub path: String,
+	pub method: String,
+	pub status_code: u16,
+	pub duration_ms: u128,
+	pub request_id: String,
+	pub user_agent: Option<String>,
+	pub remote_addr: Option<String>,
+	/// `0` when [`AccessLogConfig::include_body_size`] is off
+	pub request_body_bytes: usize,
+}
+
+/// access log config, part of [`HttpTraceConfig`]
+///
+/// ```yaml
+/// http_trace:
+///   access_log:
+///     stream: orders-api
+///     include_body_size: false
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AccessLogConfig {
+	/// Recorded as the `stream` field. tracing targets are static, so events always use
+	/// [`ACCESS_LOG_TARGET`] and aggregators can split services by this value.
+	pub stream: String,
+	/// Fill `request_body_bytes` from the buffered body or `content-length`
+	pub include_body_size: bool,
+}
+
+impl Default for AccessLogConfig {
+	fn default() -> Self {
+		Self {
+			stream: ACCESS_LOG_TARGET.to_string(),
+			include_body_size: true,
+		}
+	}
+}
+
+impl AccessLogConfig {
+	fn emit(&self, request_info: RequestInfo, status_code: u16, request_body_bytes: usize) {
+		let entry = AccessLogEntry {
+			path: request_info.path,
+			method: request_info.method,
+			status_code,
+			duration_ms: request_info.start_time.elapsed().as_millis(),
+			request_id: request_info.request_id,
+			user_agent: request_info.user_agent,
+			remote_addr: request_info.remote_addr,
+			request_body_bytes: if self.include_body_size {
+				request_body_bytes
+			} else {
+				0
+			},
+		};
+		match serde_json::to_string(&entry) {
+			Ok(json) => info!(target: ACCESS_LOG_TARGET, stream = %self.stream, entry = %json),
+			Err(e) => warn!(target: ACCESS_LOG_TARGET, "Failed to serialize access log: {e}"),
+		}
+	}
+}
+
+/// Size announced by `content-length` or the body itself, for requests whose body is not read
+fn declared_body_size(req: &Request) -> usize {
+	req.headers()
+		.get(CONTENT_LENGTH)
+		.and_then(|v| v.to_str().ok())
+		.and_then(|v| v.parse().ok())
+		.or_else(|| req.body().size_hint().exact().map(|len| len as usize))
+		.unwrap_or_default()
+}
+
+fn should_log_body(req: &Request, body_bytes: &Bytes, max_body_bytes: usize) -> bool {
 	// Skip logging if body is too large
-	if body_bytes.len() > 1024 * 10 {
-		// 10KB limit
+	if body_bytes.len() > max_body_bytes {
 		return false;
 	}
 
@@ -67,6 +157,53 @@ fn should_log_body(req: &Request, body_bytes: &Bytes) -> bool {
 	std::str::from_utf8(body_bytes).is_ok()
 }
 
+/// Only bodies with a known small size and a text content-type are buffered,
+/// so streaming responses (SSE, chunked downloads) pass through untouched
+fn should_log_response(resp: &Response, max_body_bytes: usize) -> bool {
+	let content_type = resp
+		.headers()
+		.get(CONTENT_TYPE)
+		.and_then(|v| v.to_str().ok())
+		.unwrap_or_default();
+	if content_type.starts_with("text/event-stream") {
+		return false;
+	}
+
+	match resp.body().size_hint().exact() {
+		Some(len) if len as usize <= max_body_bytes => {}
+		_ => return false,
+	}
+
+	content_type.is_empty()
+		|| content_type.starts_with("application/json")
+		|| content_type.starts_with("text/")
+}
+
+async fn capture_response_body(resp: Response, max_body_bytes: usize) -> (Response, Option<String>) {
+	if !should_log_response(&resp, max_body_bytes) {
+		return (resp, None);
+	}
+
+	let (parts, body) = resp.into_parts();
+	let body_bytes = match axum::body::to_bytes(body, max_body_bytes).await {
+		Ok(bytes) => bytes,
+		Err(e) => {
+			warn!(target: "http_request", "Failed to read response body: {e}");
+			return (Response::from_parts(parts, Body::empty()), None);
+		}
+	};
+
+	let body_str = match std::str::from_utf8(&body_bytes) {
+		Ok(s) if contains_sensitive_fields(s) => "<response contains sensitive data>".to_string(),
+		Ok(s) => s.to_string(),
+		Err(_) => format!("<binary data {} bytes>", body_bytes.len()),
+	};
+	(
+		Response::from_parts(parts, Body::from(body_bytes)),
+		Some(body_str),
+	)
+}
+
 fn contains_sensitive_fields(body_str: &str) -> bool {
 	let sensitive_fields = [
 		// Private keys
@@ -107,38 +244,262 @@ fn contains_sensitive_fields(body_str: &str) -> bool {
 		.any(|&field| body_lower.contains(field))
 }
 
-// if !req.uri().path().starts_with("/api") {
-//     return next.run(req).await;
-// }
+/// http_trace config
+///
+/// ```yaml
+/// http_trace:
+///   include_prefixes: ["/api/"]
+///   exclude_paths: ["/metrics", "/healthz"]
+///   sample_ratio: 0.1
+///   capture_headers: ["content-type", "authorization"]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HttpTraceConfig {
+	/// Only paths starting with one of these are traced, empty traces every path. Other paths
+	/// still get an access log line.
+	pub include_prefixes: Vec<String>,
+	/// Paths bypassing the middleware entirely, e.g. `/metrics`
+	pub exclude_paths: Vec<String>,
+	/// Ratio of traced requests whose body is captured, 0.0 ~ 1.0
+	pub sample_ratio: f64,
+	/// Seed for the sampling rng, mostly for tests
+	pub sample_seed: Option<u64>,
+	/// Bodies larger than this are logged as `<binary data n bytes>`
+	pub max_body_bytes: usize,
+	/// Request headers recorded in the request log
+	pub capture_headers: Vec<String>,
+	/// Captured headers whose values are replaced with `<redacted>`
+	pub redact_headers: Vec<String>,
+	/// Log sampled response bodies, streaming bodies (e.g. SSE) are never buffered
+	pub capture_response: bool,
+	/// Incoming header honored as the request id
+	pub request_id_header: String,
+	/// Structured json line per request, see [`AccessLogEntry`]
+	pub access_log: AccessLogConfig,
+	/// Proxies whose forwarded header is used for `remote_addr`
+	pub trusted_proxies: TrustedProxies,
+}
+
+impl Default for HttpTraceConfig {
+	fn default() -> Self {
+		Self {
+			include_prefixes: ["/api/", "/v1/", "/v2/", "/v3/"].map(String::from).to_vec(),
+			exclude_paths: vec![],
+			sample_ratio: 1.0,
+			sample_seed: None,
+			max_body_bytes: 1024 * 10,
+			capture_headers: vec![],
+			redact_headers: ["authorization", "cookie", "x-api-key"]
+				.map(String::from)
+				.to_vec(),
+			capture_response: true,
+			request_id_header: REQUEST_ID_HEADER.to_string(),
+			access_log: AccessLogConfig::default(),
+			trusted_proxies: TrustedProxies::default(),
+		}
+	}
+}
+
+impl HttpTraceConfig {
+	fn is_excluded(&self, path: &str) -> bool {
+		self.exclude_paths.iter().any(|p| p == path)
+	}
+
+	fn is_traced(&self, path: &str) -> bool {
+		self.include_prefixes.is_empty()
+			|| self
+				.include_prefixes
+				.iter()
+				.any(|p| path.starts_with(p.as_str()))
+	}
+
+	fn captured_headers(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+		self.capture_headers
+			.iter()
+			.filter_map(|name| {
+				let value = headers.get(name.as_str())?;
+				let redact = self
+					.redact_headers
+					.iter()
+					.any(|r| r.eq_ignore_ascii_case(name));
+				let value = if redact {
+					"<redacted>".to_string()
+				} else {
+					String::from_utf8_lossy(value.as_bytes()).to_string()
+				};
+				Some((name.to_lowercase(), value))
+			})
+			.collect()
+	}
+}
+
+/// Decides which traced requests get their body captured
+struct Sampler {
+	ratio: f64,
+	rng: Mutex<StdRng>,
+}
+
+impl Sampler {
+	fn new(ratio: f64, seed: Option<u64>) -> Self {
+		let rng = match seed {
+			Some(seed) => StdRng::seed_from_u64(seed),
+			None => StdRng::from_os_rng(),
+		};
+		Self {
+			ratio: ratio.clamp(0.0, 1.0),
+			rng: Mutex::new(rng),
+		}
+	}
+
+	fn sample(&self) -> bool {
+		if self.ratio >= 1.0 {
+			return true;
+		}
+		if self.ratio <= 0.0 {
+			return false;
+		}
+		let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+		rng.random_bool(self.ratio)
+	}
+}
+
+struct TraceState {
+	config: HttpTraceConfig,
+	sampler: Sampler,
+}
+
+impl TraceState {
+	fn new(config: HttpTraceConfig) -> Self {
+		let sampler = Sampler::new(config.sample_ratio, config.sample_seed);
+		Self { config, sampler }
+	}
+}
+
+lazy_static::lazy_static! {
+	static ref DEFAULT_TRACE: TraceState = TraceState::new(HttpTraceConfig::default());
+}
+
+/// Trace `/api/`, `/v1/` ~ `/v3/` requests with the default [`HttpTraceConfig`]
+///
+/// `Router::new().layer(axum::middleware::from_fn(http_trace))`
 pub async fn http_trace(req: Request, next: Next) -> Response {
-	let filter = ["/api/", "/v1/", "/v2/", "/v3/"];
-	let ok = filter.into_iter().any(|p| req.uri().path().starts_with(p));
-	if !ok {
-		return next.run(req).await;
+	trace_request(&DEFAULT_TRACE, req, |req| next.run(req)).await
+}
+
+/// Configurable [`http_trace`]
+///
+/// `Router::new().layer(http_trace_with(config))`
+pub fn http_trace_with(config: HttpTraceConfig) -> HttpTraceLayer {
+	HttpTraceLayer {
+		state: Arc::new(TraceState::new(config)),
+	}
+}
+
+#[derive(Clone)]
+pub struct HttpTraceLayer {
+	state: Arc<TraceState>,
+}
+
+impl<S> Layer<S> for HttpTraceLayer {
+	type Service = HttpTraceService<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		HttpTraceService {
+			inner,
+			state: self.state.clone(),
+		}
 	}
+}
+
+#[derive(Clone)]
+pub struct HttpTraceService<S> {
+	inner: S,
+	state: Arc<TraceState>,
+}
 
-	let request_info = RequestInfo::new(&req);
-	// Split request parts and body
-	let (parts, body) = req.into_parts();
+impl<S> Service<Request> for HttpTraceService<S>
+where
+	S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+	S::Future: Send + 'static,
+{
+	type Response = Response;
+	type Error = Infallible;
+	type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
 
-	// Read request body
-	let body_bytes = axum::body::to_bytes(body, usize::MAX)
-		.await
-		.unwrap_or_else(|_| Bytes::new());
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
 
-	// Rebuild request to restore body
-	let req = Request::from_parts(parts, Body::from(body_bytes.clone()));
+	fn call(&mut self, req: Request) -> Self::Future {
+		let clone = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, clone);
+		let state = self.state.clone();
 
-	// Log body content (may need to check content-type)
-	let body_str = if should_log_body(&req, &body_bytes) {
-		let body_content = String::from_utf8_lossy(&body_bytes).to_string();
-		if contains_sensitive_fields(&body_content) {
-			"<request contains sensitive data>".to_string()
+		Box::pin(async move {
+			let run = |req| async move {
+				match inner.call(req).await {
+					Ok(resp) => resp,
+					Err(never) => match never {},
+				}
+			};
+			Ok(trace_request(&state, req, run).await)
+		})
+	}
+}
+
+async fn trace_request<F, Fut>(state: &TraceState, req: Request, run: F) -> Response
+where
+	F: FnOnce(Request) -> Fut,
+	Fut: Future<Output = Response>,
+{
+	let config = &state.config;
+	if config.is_excluded(req.uri().path()) {
+		return run(req).await;
+	}
+
+	let request_info =
+		RequestInfo::with_id_header(&req, &config.request_id_header, &config.trusted_proxies);
+	let mut request_body_bytes = declared_body_size(&req);
+	if !config.is_traced(&request_info.path) {
+		let response = run(req).await;
+		let status_code = response.status().as_u16();
+		config
+			.access_log
+			.emit(request_info, status_code, request_body_bytes);
+		return response;
+	}
+	let headers = config.captured_headers(req.headers());
+
+	// Unsampled requests keep the span/tid but skip body capture
+	let sampled = state.sampler.sample();
+	let (req, body_str) = if sampled {
+		// Split request parts and body
+		let (parts, body) = req.into_parts();
+
+		// Read request body
+		let body_bytes = axum::body::to_bytes(body, usize::MAX)
+			.await
+			.unwrap_or_else(|_| Bytes::new());
+		request_body_bytes = body_bytes.len();
+
+		// Rebuild request to restore body
+		let req = Request::from_parts(parts, Body::from(body_bytes.clone()));
+
+		// Log body content (may need to check content-type)
+		let body_str = if should_log_body(&req, &body_bytes, config.max_body_bytes) {
+			let body_content = String::from_utf8_lossy(&body_bytes).to_string();
+			if contains_sensitive_fields(&body_content) {
+				"<request contains sensitive data>".to_string()
+			} else {
+				body_content
+			}
 		} else {
-			body_content
-		}
+			format!("<binary data {} bytes>", body_bytes.len())
+		};
+		(req, body_str)
 	} else {
-		format!("<binary data {} bytes>", body_bytes.len())
+		(req, "<not sampled>".to_string())
 	};
 
 	// Create a span with request_id; subsequent API handlers run within it
@@ -155,19 +516,34 @@ pub async fn http_trace(req: Request, next: Next) -> Response {
 			method = %request_info.method,
 			user_agent = ?request_info.user_agent,
 			remote_addr = ?request_info.remote_addr,
+			headers = ?headers,
 			request_body = %body_str,
 			">>>Request started:"
 		);
 
-		let mut response = next.run(req).await;
+		let request_id = request_info.request_id.clone();
+		let mut response = with_request_id(request_id, run(req)).await;
 
 		let duration = request_info.start_time.elapsed();
 		let status_code = response.status().as_u16();
 
 		// Add request-id to response headers
-		response
-			.headers_mut()
-			.insert("request-id", request_info.request_id.parse().unwrap());
+		response.headers_mut().insert(
+			"request-id",
+			request_id_header_value(&request_info.request_id),
+		);
+
+		if sampled && config.capture_response {
+			let (resp, body) = capture_response_body(response, config.max_body_bytes).await;
+			response = resp;
+			if let Some(body) = body {
+				if status_code >= 400 {
+					warn!(target: "http_request", status_code, response_body = %body, "Response body:");
+				} else {
+					debug!(target: "http_request", status_code, response_body = %body, "Response body:");
+				}
+			}
+		}
 
 		info!(
 			target: "http_request",
@@ -176,8 +552,367 @@ pub async fn http_trace(req: Request, next: Next) -> Response {
 			"<<<Request completed:"
 		);
 
+		config
+			.access_log
+			.emit(request_info, status_code, request_body_bytes);
+
 		response
 	}
 	.instrument(span)
 	.await
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::http::handle_404;
+	use crate::test_util::LogBuf;
+	use axum::Router;
+	use axum::extract::ConnectInfo;
+	use axum::response::IntoResponse;
+	use axum::routing::get;
+	use http::{HeaderValue, StatusCode};
+	use std::net::SocketAddr;
+	use tower::ServiceExt;
+
+	fn app(config: HttpTraceConfig) -> Router {
+		Router::new()
+			.route("/api/ping", get(|| async { "pong" }))
+			.route("/metrics", get(|| async { "metrics" }))
+			.layer(http_trace_with(config))
+	}
+
+	async fn call(app: Router, uri: &str) -> Response {
+		let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+		app.oneshot(req).await.unwrap()
+	}
+
+	#[tokio::test]
+	async fn test_exclude_paths() {
+		let config = HttpTraceConfig {
+			include_prefixes: vec![],
+			exclude_paths: vec!["/metrics".to_string()],
+			..Default::default()
+		};
+
+		let resp = call(app(config.clone()), "/api/ping").await;
+		assert!(resp.headers().contains_key("request-id"));
+		let resp = call(app(config), "/metrics").await;
+		assert!(!resp.headers().contains_key("request-id"));
+	}
+
+	#[tokio::test]
+	async fn test_default_prefixes() {
+		let resp = call(app(HttpTraceConfig::default()), "/metrics").await;
+		assert!(!resp.headers().contains_key("request-id"));
+	}
+
+	#[test]
+	fn test_sampling_deterministic() {
+		let draw = |seed| {
+			let sampler = Sampler::new(0.5, Some(seed));
+			(0..64).map(|_| sampler.sample()).collect::<Vec<_>>()
+		};
+		assert_eq!(draw(7), draw(7));
+		assert_ne!(draw(7), draw(8));
+
+		let sampled = draw(7).into_iter().filter(|s| *s).count();
+		assert!(sampled > 0 && sampled < 64);
+
+		assert!(Sampler::new(1.0, None).sample());
+		assert!(!Sampler::new(0.0, None).sample());
+	}
+
+	#[tokio::test]
+	async fn test_unsampled_still_traced() {
+		let config = HttpTraceConfig {
+			sample_ratio: 0.0,
+			..Default::default()
+		};
+		let resp = call(app(config), "/api/ping").await;
+		assert!(resp.headers().contains_key("request-id"));
+	}
+
+	#[tokio::test]
+	async fn test_response_body_logged() {
+		let (logs, _guard) = LogBuf::capture();
+		let app = Router::new()
+			.route(
+				"/api/fail",
+				get(|| async { handle_404().await.into_response() }),
+			)
+			.layer(http_trace_with(HttpTraceConfig::default()));
+
+		let resp = call(app, "/api/fail").await;
+		assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		assert!(String::from_utf8_lossy(&body).contains("WEB003"));
+
+		let logs = logs.contents();
+		assert!(logs.contains("WARN"));
+		assert!(logs.contains("response_body="));
+		assert!(logs.contains("WEB003"));
+	}
+
+	#[tokio::test]
+	async fn test_sse_passthrough() {
+		let (logs, _guard) = LogBuf::capture();
+		let sse = "data: tick\n\n";
+		let app = Router::new()
+			.route(
+				"/api/events",
+				get(move || async move { ([(CONTENT_TYPE, "text/event-stream")], sse) }),
+			)
+			.layer(http_trace_with(HttpTraceConfig::default()));
+
+		let resp = call(app, "/api/events").await;
+		assert_eq!(resp.headers()[CONTENT_TYPE], "text/event-stream");
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		assert_eq!(&body[..], sse.as_bytes());
+		assert!(!logs.contents().contains("response_body="));
+	}
+
+	#[tokio::test]
+	async fn test_response_capture_disabled() {
+		let (logs, _guard) = LogBuf::capture();
+		let config = HttpTraceConfig {
+			capture_response: false,
+			..Default::default()
+		};
+		let resp = call(app(config), "/api/ping").await;
+		assert_eq!(resp.status(), StatusCode::OK);
+		assert!(!logs.contents().contains("response_body="));
+	}
+
+	fn echo_id_app() -> Router {
+		Router::new()
+			.route(
+				"/api/id",
+				get(|| async { crate::http::current_request_id().unwrap_or_default() }),
+			)
+			.layer(http_trace_with(HttpTraceConfig::default()))
+	}
+
+	async fn call_with_id(app: Router, id: &str) -> (String, String) {
+		call_with_id_value(app, HeaderValue::from_str(id).unwrap()).await
+	}
+
+	async fn call_with_id_value(app: Router, id: HeaderValue) -> (String, String) {
+		let req = Request::builder()
+			.uri("/api/id")
+			.header(REQUEST_ID_HEADER, id)
+			.body(Body::empty())
+			.unwrap();
+		let resp = app.oneshot(req).await.unwrap();
+		assert_eq!(resp.status(), StatusCode::OK);
+		let echoed = resp.headers()["request-id"].to_str().unwrap().to_string();
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		(echoed, String::from_utf8(body.to_vec()).unwrap())
+	}
+
+	#[tokio::test]
+	async fn test_request_id_passthrough() {
+		let (echoed, current) = call_with_id(echo_id_app(), "gateway-req-1").await;
+		assert_eq!(echoed, "gateway-req-1");
+		assert_eq!(current, "gateway-req-1");
+	}
+
+	#[tokio::test]
+	async fn test_invalid_request_id_regenerated() {
+		let (echoed, current) = call_with_id(echo_id_app(), "bad id;drop").await;
+		assert_ne!(echoed, "bad id;drop");
+		assert!(crate::http::is_valid_request_id(&echoed));
+		assert_eq!(echoed, current);
+	}
+
+	#[tokio::test]
+	async fn test_hostile_request_id_replaced() {
+		let unicode = HeaderValue::from_bytes("req-ü-1".as_bytes()).unwrap();
+		let (echoed, current) = call_with_id_value(echo_id_app(), unicode).await;
+		assert!(crate::http::is_valid_request_id(&echoed));
+		assert_ne!(echoed, "req--1");
+		assert_eq!(current, echoed);
+
+		let long = "a".repeat(10_000);
+		let (echoed, _) = call_with_id(echo_id_app(), &long).await;
+		assert!(crate::http::is_valid_request_id(&echoed));
+		assert_ne!(echoed, "a".repeat(128));
+
+		let (echoed, _) = call_with_id(echo_id_app(), "req\t42").await;
+		assert!(crate::http::is_valid_request_id(&echoed));
+		assert_ne!(echoed, "req42");
+
+		let (echoed, _) = call_with_id(echo_id_app(), "\t").await;
+		assert!(crate::http::is_valid_request_id(&echoed));
+	}
+
+	#[tokio::test]
+	async fn test_nested_service_propagation() {
+		let downstream = echo_id_app();
+		let upstream = Router::new()
+			.route(
+				"/api/id",
+				get(move || {
+					let downstream = downstream.clone();
+					async move {
+						// what a reqwest client would send with the injected headers
+						let mut headers = HeaderMap::new();
+						crate::http::inject_request_id(&mut headers);
+						let id = headers[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+						call_with_id(downstream, &id).await.1
+					}
+				}),
+			)
+			.layer(http_trace_with(HttpTraceConfig::default()));
+
+		let (echoed, downstream_id) = call_with_id(upstream, "edge-7").await;
+		assert_eq!(echoed, "edge-7");
+		assert_eq!(downstream_id, "edge-7");
+	}
+
+	fn access_logs(logs: &LogBuf) -> Vec<(String, AccessLogEntry)> {
+		logs.contents()
+			.lines()
+			.filter(|line| line.contains(ACCESS_LOG_TARGET))
+			.map(|line| {
+				let stream = line.split("stream=").nth(1).unwrap();
+				let stream = stream.split_whitespace().next().unwrap().to_string();
+				let json = line.split_once("entry=").unwrap().1;
+				(stream, serde_json::from_str(json).unwrap())
+			})
+			.collect()
+	}
+
+	#[tokio::test]
+	async fn test_access_log() {
+		let (logs, _guard) = LogBuf::capture();
+		let config = HttpTraceConfig {
+			trusted_proxies: TrustedProxies::new(vec!["10.0.0.1/32".parse().unwrap()]),
+			..Default::default()
+		};
+		let app = Router::new()
+			.route(
+				"/api/echo",
+				axum::routing::post(|body: String| async move { body }),
+			)
+			.layer(http_trace_with(config));
+
+		let mut req = Request::builder()
+			.method("POST")
+			.uri("/api/echo")
+			.header(REQUEST_ID_HEADER, "req-42")
+			.header("user-agent", "curl/8.5")
+			.header("x-forwarded-for", "1.2.3.4, 10.0.0.7")
+			.header(CONTENT_TYPE, "text/plain")
+			.body(Body::from("hello access log"))
+			.unwrap();
+		let peer: SocketAddr = "10.0.0.1:40000".parse().unwrap();
+		req.extensions_mut().insert(ConnectInfo(peer));
+		let resp = app.oneshot(req).await.unwrap();
+		assert_eq!(resp.status(), StatusCode::OK);
+
+		let entries = access_logs(&logs);
+		assert_eq!(entries.len(), 1);
+		let (stream, entry) = &entries[0];
+		assert_eq!(stream, ACCESS_LOG_TARGET);
+		assert!(entry.duration_ms < 10_000);
+		assert_eq!(
+			entry,
+			&AccessLogEntry {
+				path: "/api/echo".to_string(),
+				method: "POST".to_string(),
+				status_code: 200,
+				duration_ms: entry.duration_ms,
+				request_id: "req-42".to_string(),
+				user_agent: Some("curl/8.5".to_string()),
+				remote_addr: Some("10.0.0.7".to_string()),
+				request_body_bytes: 16,
+			}
+		);
+	}
+
+	#[tokio::test]
+	async fn test_access_log_config() {
+		let (logs, _guard) = LogBuf::capture();
+		let config: HttpTraceConfig = serde_json::from_value(serde_json::json!({
+			"sample_ratio": 0.0,
+			"access_log": { "stream": "orders-api" }
+		}))
+		.unwrap();
+		assert!(config.access_log.include_body_size);
+
+		let req = Request::builder()
+			.uri("/api/missing")
+			.header(CONTENT_LENGTH, "5")
+			.body(Body::from("abcde"))
+			.unwrap();
+		let resp = app(config.clone()).oneshot(req).await.unwrap();
+		assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+		// untraced paths are access logged too, excluded ones are not
+		call(app(config.clone()), "/metrics").await;
+		let excluded = HttpTraceConfig {
+			exclude_paths: vec!["/metrics".to_string()],
+			..config.clone()
+		};
+		call(app(excluded), "/metrics").await;
+
+		let config = HttpTraceConfig {
+			access_log: AccessLogConfig {
+				include_body_size: false,
+				..config.access_log
+			},
+			..config
+		};
+		let req = Request::builder()
+			.uri("/api/ping")
+			.header(CONTENT_LENGTH, "3")
+			.body(Body::from("abc"))
+			.unwrap();
+		app(config).oneshot(req).await.unwrap();
+
+		let entries = access_logs(&logs);
+		assert_eq!(entries.len(), 3);
+		let (stream, entry) = &entries[0];
+		assert_eq!(stream, "orders-api");
+		assert_eq!(entry.status_code, 404);
+		assert_eq!(entry.request_body_bytes, 5);
+		assert_eq!(entry.user_agent, None);
+		assert_eq!(entry.remote_addr, None);
+		assert!(!entry.request_id.is_empty());
+		assert_eq!(entries[1].1.path, "/metrics");
+		assert_eq!(entries[1].1.status_code, 200);
+		assert_eq!(entries[2].1.path, "/api/ping");
+		assert_eq!(entries[2].1.request_body_bytes, 0);
+	}
+
+	#[test]
+	fn test_header_redaction() {
+		let config = HttpTraceConfig {
+			capture_headers: ["content-type", "X-Api-Key", "authorization", "accept"]
+				.map(String::from)
+				.to_vec(),
+			..Default::default()
+		};
+		let mut headers = HeaderMap::new();
+		headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+		headers.insert("x-api-key", "sk-secret".parse().unwrap());
+		headers.insert("authorization", "Bearer secret".parse().unwrap());
+
+		let captured = config.captured_headers(&headers);
+		assert_eq!(
+			captured,
+			vec![
+				("content-type".to_string(), "application/json".to_string()),
+				("x-api-key".to_string(), "<redacted>".to_string()),
+				("authorization".to_string(), "<redacted>".to_string()),
+			]
+		);
+	}
+}
@@ -0,0 +1,213 @@
+use crate::result::{AxumError, WebErr};
+use axum::body::Body;
+use axum::extract::Request;
+use axum::response::{IntoResponse, Response};
+use base_infra::result::{AppError, DynErrCode};
+use base_infra::utils::hash::verify_hmac_sha256_hex;
+use http::StatusCode;
+use serde::Deserialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+use tracing::warn;
+
+const DEFAULT_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Shared secret and where the sender puts its hex HMAC-SHA256 signature,
+/// e.g. `x-hub-signature-256` with prefix `sha256=` for GitHub
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+	pub secret: String,
+	pub header_name: String,
+	#[serde(default)]
+	pub signature_prefix: Option<String>,
+}
+
+impl WebhookConfig {
+	fn verify(&self, signature: &str, body: &[u8]) -> bool {
+		let signature = match &self.signature_prefix {
+			Some(prefix) => match signature.strip_prefix(prefix.as_str()) {
+				Some(sig) => sig,
+				None => return false,
+			},
+			None => signature,
+		};
+		verify_hmac_sha256_hex(self.secret.as_bytes(), body, signature)
+	}
+}
+
+/// Verifies the raw body signature before the request reaches any extractor,
+/// the body is put back untouched for downstream handlers.
+#[derive(Clone)]
+pub struct WebhookVerifyLayer {
+	config: Arc<WebhookConfig>,
+	body_limit: usize,
+}
+
+impl WebhookVerifyLayer {
+	pub fn new(config: WebhookConfig) -> Self {
+		Self {
+			config: Arc::new(config),
+			body_limit: DEFAULT_BODY_LIMIT,
+		}
+	}
+
+	/// Max payload size buffered for verification, defaults to 2 MiB
+	pub fn body_limit(mut self, limit: usize) -> Self {
+		self.body_limit = limit;
+		self
+	}
+}
+
+impl<S> Layer<S> for WebhookVerifyLayer {
+	type Service = WebhookVerifyService<S>;
+
+	fn layer(&self, inner: S) -> Self::Service {
+		WebhookVerifyService {
+			inner,
+			layer: self.clone(),
+		}
+	}
+}
+
+#[derive(Clone)]
+pub struct WebhookVerifyService<S> {
+	inner: S,
+	layer: WebhookVerifyLayer,
+}
+
+impl<S> Service<Request> for WebhookVerifyService<S>
+where
+	S: Service<Request, Response = Response> + Clone + Send + 'static,
+	S::Future: Send + 'static,
+{
+	type Response = Response;
+	type Error = S::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.inner.poll_ready(cx)
+	}
+
+	fn call(&mut self, req: Request) -> Self::Future {
+		let clone = self.inner.clone();
+		let mut inner = std::mem::replace(&mut self.inner, clone);
+		let layer = self.layer.clone();
+
+		Box::pin(async move {
+			let (parts, body) = req.into_parts();
+			let bytes = match axum::body::to_bytes(body, layer.body_limit).await {
+				Ok(bytes) => bytes,
+				Err(e) => {
+					warn!(uri = %parts.uri, "{}, reason: {}", WebErr::WebhookBodyTooLarge, e);
+					return Ok(reject(
+						&WebErr::WebhookBodyTooLarge,
+						StatusCode::PAYLOAD_TOO_LARGE,
+					));
+				}
+			};
+
+			let signature = parts
+				.headers
+				.get(layer.config.header_name.as_str())
+				.and_then(|v| v.to_str().ok());
+			let verified = signature.is_some_and(|sig| layer.config.verify(sig, &bytes));
+			if !verified {
+				warn!(uri = %parts.uri, header = %layer.config.header_name, "{}", WebErr::WebhookSignatureInvalid);
+				return Ok(reject(
+					&WebErr::WebhookSignatureInvalid,
+					StatusCode::UNAUTHORIZED,
+				));
+			}
+
+			inner
+				.call(Request::from_parts(parts, Body::from(bytes)))
+				.await
+		})
+	}
+}
+
+fn reject(code: &'static DynErrCode, status: StatusCode) -> Response {
+	AxumError::AppError(AppError::HttpErr(code, status)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::Router;
+	use axum::routing::post;
+	use base_infra::utils::hash::hmac_sha256_hex;
+	use tower::ServiceExt;
+
+	const SECRET: &str = "whsec_test";
+	const HEADER: &str = "x-hub-signature-256";
+
+	fn app() -> Router {
+		let config = WebhookConfig {
+			secret: SECRET.to_string(),
+			header_name: HEADER.to_string(),
+			signature_prefix: Some("sha256=".to_string()),
+		};
+		Router::new()
+			.route("/hook", post(|body: String| async move { body }))
+			.layer(WebhookVerifyLayer::new(config).body_limit(1024))
+	}
+
+	async fn send(body: &str, signature: Option<String>) -> (StatusCode, String) {
+		let mut req = Request::builder().method("POST").uri("/hook");
+		if let Some(sig) = signature {
+			req = req.header(HEADER, sig);
+		}
+		let resp = app()
+			.oneshot(req.body(Body::from(body.to_string())).unwrap())
+			.await
+			.unwrap();
+		let status = resp.status();
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		(status, String::from_utf8(body.to_vec()).unwrap())
+	}
+
+	#[tokio::test]
+	async fn test_signed_body_reaches_handler() {
+		let payload = r#"{"event":"push"}"#;
+		let sig = format!(
+			"sha256={}",
+			hmac_sha256_hex(SECRET.as_bytes(), payload.as_bytes())
+		);
+		let (status, body) = send(payload, Some(sig)).await;
+		assert_eq!(status, StatusCode::OK);
+		assert_eq!(body, payload);
+	}
+
+	#[tokio::test]
+	async fn test_bad_signature_rejected() {
+		let payload = r#"{"event":"push"}"#;
+		let wrong = format!("sha256={}", hmac_sha256_hex(b"other", payload.as_bytes()));
+		let (status, body) = send(payload, Some(wrong)).await;
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+		assert!(body.contains("HOOK01"));
+
+		// right digest without the expected prefix
+		let unprefixed = hmac_sha256_hex(SECRET.as_bytes(), payload.as_bytes());
+		let (status, _) = send(payload, Some(unprefixed)).await;
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+		let (status, _) = send(payload, None).await;
+		assert_eq!(status, StatusCode::UNAUTHORIZED);
+	}
+
+	#[tokio::test]
+	async fn test_body_limit() {
+		let payload = "x".repeat(2048);
+		let sig = format!(
+			"sha256={}",
+			hmac_sha256_hex(SECRET.as_bytes(), payload.as_bytes())
+		);
+		let (status, _) = send(&payload, Some(sig)).await;
+		assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+	}
+}
@@ -1,5 +1,14 @@
+pub mod auth;
+pub mod client;
+pub mod extract;
 pub mod http;
+#[cfg(feature = "utoipa")]
+pub mod openapi;
 pub mod result;
+pub mod server;
+pub mod ws;
+#[cfg(test)]
+mod test_util;
 
 lazy_static::lazy_static! {
 	pub static ref HTTP_TIMEOUT: u64 = 30;
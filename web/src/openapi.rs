@@ -0,0 +1,190 @@
+use base_infra::result::{DynErrCode, ErrorCode, SysErr};
+use base_infra::validator::FieldError;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use utoipa::{PartialSchema, ToSchema};
+use utoipa::openapi::example::ExampleBuilder;
+use utoipa::openapi::{
+	ContentBuilder, ObjectBuilder, OpenApi, Ref, RefOr, Response, ResponseBuilder, Type,
+};
+
+/// Schema name of the error code catalog added by [`inject_error_codes`]
+pub const ERROR_CODES_SCHEMA: &str = "ErrorCodes";
+
+/// Error envelope, a `RespData` without `data`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RespError {
+	/// Error code, e.g. `WEB003`
+	pub code: String,
+	/// Error message
+	pub msg: String,
+	/// Failing fields, only set for validation and business errors that name them
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub errors: Option<Vec<FieldError>>,
+}
+
+fn string_schema() -> ObjectBuilder {
+	ObjectBuilder::new().schema_type(Type::String)
+}
+
+/// `{ code, msg, data: T }` schema of a success response
+fn success_schema<T: ToSchema>() -> ObjectBuilder {
+	ObjectBuilder::new()
+		.property("code", string_schema())
+		.required("code")
+		.property("msg", string_schema())
+		.required("msg")
+		.property("data", Ref::from_schema_name(T::name()))
+}
+
+/// `200` with the `RespData<T>` envelope, and a `default` [`RespError`] response with one
+/// example per error code
+///
+/// Error envelopes are answered with status 200 as well, `default` only documents them.
+pub fn envelope_responses<T: ToSchema>(
+	codes: &[&'static DynErrCode],
+) -> BTreeMap<String, RefOr<Response>> {
+	let success = ContentBuilder::new()
+		.schema(Some(success_schema::<T>()))
+		.build();
+	let examples = codes.iter().map(|code| {
+		let example = ExampleBuilder::new()
+			.summary(code.message())
+			.value(Some(json!({ "code": code.code(), "msg": code.message() })));
+		(code.code(), example)
+	});
+	let errors = ContentBuilder::new()
+		.schema(Some(Ref::from_schema_name(RespError::name())))
+		.examples_from_iter(examples)
+		.build();
+
+	BTreeMap::from([
+		(
+			"200".to_string(),
+			ResponseBuilder::new()
+				.description(SysErr::Success.message())
+				.content("application/json", success)
+				.into(),
+		),
+		(
+			"default".to_string(),
+			ResponseBuilder::new()
+				.description("Error envelope")
+				.content("application/json", errors)
+				.into(),
+		),
+	])
+}
+
+/// Adds [`RespError`], its [`FieldError`] and an [`ERROR_CODES_SCHEMA`] string enum listing
+/// `codes` with their messages to the components of `openapi`
+pub fn inject_error_codes(openapi: &mut OpenApi, codes: &[&'static DynErrCode]) {
+	let description = codes
+		.iter()
+		.map(|code| format!("- `{}`: {}", code.code(), code.message()))
+		.collect::<Vec<_>>()
+		.join("\n");
+	let catalog = string_schema()
+		.enum_values(Some(codes.iter().map(|code| code.code())))
+		.description(Some(description));
+
+	let components = openapi.components.get_or_insert_with(Default::default);
+	components
+		.schemas
+		.insert(RespError::name().to_string(), RespError::schema());
+	components
+		.schemas
+		.insert(FieldError::name().to_string(), FieldError::schema());
+	components
+		.schemas
+		.insert(ERROR_CODES_SCHEMA.to_string(), catalog.into());
+}
+
+/// Documents `RespData` responses of an endpoint
+///
+/// `api_responses!(User, [&WebErr::NotFound])` is the response map, see [`envelope_responses`].
+/// `api_responses!(struct UserResponses, User, [..])` declares a `utoipa::IntoResponses` type for
+/// `#[utoipa::path(responses(UserResponses))]`.
+#[macro_export]
+macro_rules! api_responses {
+	(struct $name:ident, $success:ty, [$($code:expr),* $(,)?]) => {
+		pub struct $name;
+
+		impl ::utoipa::IntoResponses for $name {
+			fn responses() -> ::std::collections::BTreeMap<
+				String,
+				::utoipa::openapi::RefOr<::utoipa::openapi::Response>,
+			> {
+				$crate::api_responses!($success, [$($code),*])
+			}
+		}
+	};
+	($success:ty, [$($code:expr),* $(,)?]) => {
+		$crate::openapi::envelope_responses::<$success>(&[$($code),*])
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::result::WebErr;
+	use axum::Json;
+	use serde_json::Value;
+	use utoipa::OpenApi as _;
+
+	#[derive(Debug, Serialize, ToSchema)]
+	struct User {
+		id: u64,
+	}
+
+	api_responses!(struct UserResponses, User, [&WebErr::NotFound, &WebErr::ApiKeyInvalid]);
+
+	#[utoipa::path(get, path = "/users/{id}", responses(UserResponses))]
+	#[allow(dead_code)]
+	async fn get_user() -> Json<User> {
+		Json(User { id: 1 })
+	}
+
+	#[derive(utoipa::OpenApi)]
+	#[openapi(paths(get_user), components(schemas(User)))]
+	struct ApiDoc;
+
+	#[test]
+	fn test_openapi_doc() {
+		let mut doc = ApiDoc::openapi();
+		inject_error_codes(&mut doc, &[&WebErr::NotFound, &WebErr::ApiKeyInvalid]);
+		let doc: Value = serde_json::from_str(&doc.to_json().unwrap()).unwrap();
+
+		let schemas = &doc["components"]["schemas"];
+		assert!(schemas["RespError"]["properties"]["code"].is_object());
+		assert_eq!(
+			schemas["RespError"]["properties"]["errors"]["items"]["$ref"],
+			"#/components/schemas/FieldError"
+		);
+		for field in ["field", "code", "msg"] {
+			assert!(
+				schemas["FieldError"]["properties"][field].is_object(),
+				"{field}"
+			);
+		}
+		assert!(schemas["User"].is_object());
+		assert_eq!(
+			schemas[ERROR_CODES_SCHEMA]["enum"],
+			json!(["WEB003", "AUTH02"])
+		);
+
+		let responses = &doc["paths"]["/users/{id}"]["get"]["responses"];
+		let success = &responses["200"]["content"]["application/json"]["schema"];
+		assert_eq!(
+			success["properties"]["data"]["$ref"],
+			"#/components/schemas/User"
+		);
+		let examples = &responses["default"]["content"]["application/json"]["examples"];
+		assert_eq!(examples["WEB003"]["value"]["code"], "WEB003");
+		assert_eq!(
+			examples["AUTH02"]["value"]["msg"],
+			WebErr::ApiKeyInvalid.message()
+		);
+	}
+}
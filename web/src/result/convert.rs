@@ -0,0 +1,71 @@
+use crate::result::{AxumError, WebErr};
+use base_infra::result::{AppError, DynErrCode, SysErr};
+use http::StatusCode;
+use sea_orm::{DbErr, SqlErr};
+use sql_infra::error::DBErr;
+
+/// `RecordNotFound` is 404, unique and foreign key violations are 409
+///
+/// Constraint violations are only recognized with a sea-orm sqlx driver enabled, e.g. the
+/// `sqlite`/`pgsql` features of sql-infra; anything else stays an internal error.
+impl From<DbErr> for AxumError {
+	fn from(err: DbErr) -> Self {
+		let classified: Option<(&'static DynErrCode, StatusCode)> = match (&err, err.sql_err()) {
+			(DbErr::RecordNotFound(_), _) => Some((&DBErr::RecordNotFound, StatusCode::NOT_FOUND)),
+			(_, Some(SqlErr::UniqueConstraintViolation(_))) => {
+				Some((&DBErr::UniqueViolation, StatusCode::CONFLICT))
+			}
+			(_, Some(SqlErr::ForeignKeyConstraintViolation(_))) => {
+				Some((&DBErr::ForeignKeyViolation, StatusCode::CONFLICT))
+			}
+			_ => None,
+		};
+		http_err(classified, err.into())
+	}
+}
+
+/// Timeouts are 504, connection failures 502
+impl From<reqwest::Error> for AxumError {
+	fn from(err: reqwest::Error) -> Self {
+		let classified: Option<(&'static DynErrCode, StatusCode)> = if err.is_timeout() {
+			Some((&WebErr::UpstreamTimeout, StatusCode::GATEWAY_TIMEOUT))
+		} else if err.is_connect() {
+			Some((&WebErr::UpstreamUnavailable, StatusCode::BAD_GATEWAY))
+		} else {
+			None
+		};
+		http_err(classified, err.into())
+	}
+}
+
+/// Classifies a [`DbErr`] or [`reqwest::Error`] at the root of the chain, the rest is internal
+impl From<anyhow::Error> for AxumError {
+	fn from(err: anyhow::Error) -> Self {
+		let err = match err.downcast::<DbErr>() {
+			Ok(db_err) => return db_err.into(),
+			Err(err) => err,
+		};
+		match err.downcast::<reqwest::Error>() {
+			Ok(req_err) => req_err.into(),
+			Err(err) => AxumError::AppError(AppError::Anyhow(&SysErr::InternalError, err)),
+		}
+	}
+}
+
+fn http_err(classified: Option<(&'static DynErrCode, StatusCode)>, err: anyhow::Error) -> AxumError {
+	match classified {
+		Some((code, status)) => {
+			tracing::error!(
+				"ErrorCode[{}] http status: {}, reason: {}",
+				code,
+				status,
+				err
+			);
+			AxumError::AppError(AppError::HttpErr(code, status))
+		}
+		None => {
+			tracing::error!("ErrorCode[{}] reason: {}", SysErr::InternalError, err);
+			AxumError::AppError(AppError::Anyhow(&SysErr::InternalError, err))
+		}
+	}
+}
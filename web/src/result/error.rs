@@ -8,8 +8,56 @@ gen_impl_code_enum! {
 		NotFound = ("WEB003", "The requested resource does not exist on this server!"),
 		RequestTimeout = ("WEB004", "Request timeout"),
 		InternalServerError = ("WEB005", "unhandled internal error"),
+		InternalPanic = ("WEB006", "Internal server error"),
 
 		ReqJsonErr = ("AXUM01", "Error in the json payload"),
 		QueryParamsErr = ("AXUM02", ""),
+		ValidationFailed = ("AXUM03", "Request validation failed"),
+		ReqFormErr = ("AXUM04", "Error in the form payload"),
+
+		ApiKeyMissing = ("AUTH01", "Missing api key"),
+		ApiKeyInvalid = ("AUTH02", "Invalid api key"),
+		ApiKeyScopeDenied = ("AUTH03", "Api key lacks the required scope"),
+		ApiKeyConfigErr = ("AUTH04", "Invalid api key config"),
+
+		TooManyRequests = ("LIMIT1", "Too many requests"),
+		IpNotAllowed = ("ACL001", "Client ip not allowed"),
+
+		CorsConfigErr = ("CORS01", "Invalid cors config"),
+
+		WebhookSignatureInvalid = ("HOOK01", "Invalid webhook signature"),
+		WebhookBodyTooLarge = ("HOOK02", "Webhook payload too large"),
+
+		IdempotencyKeyConflict = ("IDEM01", "Idempotency key reused with a different request"),
+		IdempotencyBodyTooLarge = ("IDEM02", "Idempotent request payload too large"),
+		IdempotencyKeyInvalid = ("IDEM03", "Invalid idempotency key"),
+		IdempotencyNotReplayable = ("IDEM04", "Idempotent response too large to replay"),
+
+		ServerErr = ("SRV002", "Http server error"),
+
+		HealthCheckTimeout = ("HLTH01", "Health check timed out"),
+		CacheNotInitialized = ("HLTH02", "Memory cache not initialized"),
+
+		MetricsRegisterErr = ("MET001", "Metrics register failed"),
+		MetricsEncodeErr = ("MET002", "Metrics encode failed"),
+
+		ClientConfigErr = ("CLI001", "Invalid http client config"),
+		ClientRequestErr = ("CLI002", "Http client request failed"),
+		ClientStatusErr = ("CLI003", "Http client got an error status"),
+		ClientDecodeErr = ("CLI004", "Http client failed to decode response"),
+		RemoteErr = ("CLI005", "Remote service error"),
+		UpstreamTimeout = ("CLI006", "Upstream request timed out"),
+		UpstreamUnavailable = ("CLI007", "Upstream service unavailable"),
+
+		WsClosed = ("WS0001", "WebSocket closed abnormally"),
+		WsBadMessage = ("WS0002", "Invalid websocket message"),
+		WsHeartbeatTimeout = ("WS0003", "WebSocket heartbeat timeout"),
+		WsSessionClosed = ("WS0004", "WebSocket session closed"),
+
+		UploadErr = ("UPL001", "Invalid multipart upload"),
+		UploadTooLarge = ("UPL002", "Upload too large"),
+		UploadTooManyFiles = ("UPL003", "Too many files in upload"),
+		UploadMimeNotAllowed = ("UPL004", "Upload content type not allowed"),
+		UploadSpoolErr = ("UPL005", "Failed to store upload"),
 	}
 }
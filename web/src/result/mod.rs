@@ -1,22 +1,72 @@
 mod axum;
+mod convert;
 mod error;
 pub mod pagination;
+mod stream;
+mod version;
 
 pub use axum::*;
 use base_infra::result::RespData;
+use base_infra::validator::FieldError;
 pub use error::*;
+use http::HeaderMap;
 use serde::Serialize;
+use std::marker::PhantomData;
+pub use stream::*;
 #[cfg(feature = "utoipa")]
 use utoipa::ToSchema;
+pub use version::*;
 
 pub type AxumResult<T> = Result<T, AxumError>;
 
+/// Extra headers merged into a `#[resp_data]` success response, e.g. `Location` for 201
+pub trait WithHeaders {
+	fn headers(&self) -> HeaderMap;
+}
+
+/// Lets `#[resp_data]` pick up [`WithHeaders`] only when the data type implements it
+#[doc(hidden)]
+pub mod __private {
+	use super::WithHeaders;
+	use http::HeaderMap;
+
+	pub struct HeadersProbe<'a, T>(pub &'a T);
+
+	pub trait ViaWithHeaders {
+		fn resp_headers(&self) -> HeaderMap;
+	}
+
+	impl<T: WithHeaders> ViaWithHeaders for HeadersProbe<'_, T> {
+		fn resp_headers(&self) -> HeaderMap {
+			self.0.headers()
+		}
+	}
+
+	pub trait ViaNoHeaders {
+		fn resp_headers(&self) -> HeaderMap;
+	}
+
+	impl<T> ViaNoHeaders for &HeadersProbe<'_, T> {
+		fn resp_headers(&self) -> HeaderMap {
+			HeaderMap::new()
+		}
+	}
+}
+
 #[cfg(feature = "utoipa")]
 #[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct AxumResp<T: ToSchema> {
 	code: String,
 	msg: String,
 	data: Option<T>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	api_version: Option<String>,
+	/// Unix milliseconds, `v2` only
+	#[serde(skip_serializing_if = "Option::is_none")]
+	ts: Option<u64>,
+	/// Failing fields of a validation or business error
+	#[serde(skip_serializing_if = "Option::is_none")]
+	errors: Option<Vec<FieldError>>,
 }
 #[cfg(not(feature = "utoipa"))]
 #[derive(Debug, Clone, Serialize)]
@@ -24,36 +74,139 @@ pub struct AxumResp<T> {
 	code: String,
 	msg: String,
 	data: Option<T>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	api_version: Option<String>,
+	/// Unix milliseconds, `v2` only
+	#[serde(skip_serializing_if = "Option::is_none")]
+	ts: Option<u64>,
+	/// Failing fields of a validation or business error
+	#[serde(skip_serializing_if = "Option::is_none")]
+	errors: Option<Vec<FieldError>>,
+}
+
+/// Envelope fields derived from the api version: `(api_version, ts)`
+fn version_fields(version: Option<ApiVersion>) -> (Option<String>, Option<u64>) {
+	let ts = match version {
+		Some(ApiVersion::V2) => base_infra::utils::time::unix_timestamp_millis()
+			.ok()
+			.map(|ms| ms as u64),
+		_ => None,
+	};
+	(version.map(|v| v.as_str().to_string()), ts)
+}
+
+/// Builds an [`AxumResp`] for [`ApiVersion::current`] unless a version is set explicitly
+///
+/// `AxumRespBuilder::new().version(ApiVersion::V2).build(data)`
+#[derive(Debug, Clone, Copy)]
+pub struct AxumRespBuilder<T> {
+	version: Option<ApiVersion>,
+	_data: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for AxumRespBuilder<T> {
+	fn default() -> Self {
+		Self {
+			version: ApiVersion::current(),
+			_data: PhantomData,
+		}
+	}
+}
+
+impl<T> AxumRespBuilder<T> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn version(self, v: ApiVersion) -> Self {
+		Self {
+			version: Some(v),
+			..self
+		}
+	}
+}
+
+#[cfg(feature = "utoipa")]
+impl<T: ToSchema> AxumRespBuilder<T> {
+	pub fn build(self, data: T) -> AxumResp<T> {
+		self.build_resp(RespData::success(data))
+	}
+
+	pub fn build_resp(self, resp: RespData<T>) -> AxumResp<T> {
+		let (api_version, ts) = version_fields(self.version);
+		AxumResp {
+			code: resp.code,
+			msg: resp.msg,
+			data: resp.data,
+			api_version,
+			ts,
+			errors: resp.errors,
+		}
+	}
+}
+#[cfg(not(feature = "utoipa"))]
+impl<T> AxumRespBuilder<T> {
+	pub fn build(self, data: T) -> AxumResp<T> {
+		self.build_resp(RespData::success(data))
+	}
+
+	pub fn build_resp(self, resp: RespData<T>) -> AxumResp<T> {
+		let (api_version, ts) = version_fields(self.version);
+		AxumResp {
+			code: resp.code,
+			msg: resp.msg,
+			data: resp.data,
+			api_version,
+			ts,
+			errors: resp.errors,
+		}
+	}
 }
 
 #[cfg(feature = "utoipa")]
 impl<T: ToSchema> From<RespData<T>> for AxumResp<T> {
 	fn from(value: RespData<T>) -> Self {
-		Self {
-			code: value.code,
-			msg: value.msg,
-			data: value.data,
-		}
+		AxumRespBuilder::new().build_resp(value)
 	}
 }
 #[cfg(not(feature = "utoipa"))]
 impl<T> From<RespData<T>> for AxumResp<T> {
 	fn from(value: RespData<T>) -> Self {
-		Self {
-			code: value.code,
-			msg: value.msg,
-			data: value.data,
-		}
+		AxumRespBuilder::new().build_resp(value)
 	}
 }
 
 /// Ok(AppJson(RespData::success(admin)))
+///
+/// `success!(admin, version = ApiVersion::V2)` returns a versioned [`AxumResp`] instead
 #[macro_export]
 macro_rules! success {
 	($data:expr) => {{
 		tracing::debug!(response_data=?$data);
 		Ok($crate::result::AppJson(base_infra::result::RespData::success($data)))
 	}};
+
+	($data:expr, version = $version:expr) => {{
+		tracing::debug!(response_data=?$data);
+		Ok($crate::result::AppJson(
+			$crate::result::AxumRespBuilder::new()
+				.version($version)
+				.build($data),
+		))
+	}};
+}
+
+/// Ok(AppJson(RespData::success(items).with_meta(meta))), `meta` serialized next to `data`
+///
+/// `success_with_meta!(items, PageMeta { total, page, page_size })`
+#[macro_export]
+macro_rules! success_with_meta {
+	($data:expr, $meta:expr) => {{
+		tracing::debug!(response_data=?$data);
+		Ok($crate::result::AppJson(
+			base_infra::result::RespData::success($data).with_meta($meta),
+		))
+	}};
 }
 
 /// return Err(AxumError::*)
@@ -57,6 +57,63 @@ impl<T> PageResp<T> {
 	}
 }
 
+/// Flattened page body used by `#[resp_data(page)]`, serialized as
+/// `{ items, page, size, total }`, or `has_next` in place of `total` when the count was skipped
+#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PageData<T> {
+	/// Paged data list
+	pub items: Vec<T>,
+	/// Current page number
+	pub page: u64,
+	/// Page size
+	pub size: u64,
+	/// Total record count, left out when the count was skipped
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub total: Option<u64>,
+	/// Set when the count was skipped or estimated
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub has_next: Option<bool>,
+}
+
+#[cfg(feature = "utoipa")]
+impl<T: ToSchema> From<PageResp<T>> for PageData<T> {
+	fn from(v: PageResp<T>) -> Self {
+		Self::new(v.list, &v.pagination)
+	}
+}
+#[cfg(not(feature = "utoipa"))]
+impl<T> From<PageResp<T>> for PageData<T> {
+	fn from(v: PageResp<T>) -> Self {
+		Self::new(v.list, &v.pagination)
+	}
+}
+
+impl<T> PageData<T> {
+	fn new(items: Vec<T>, pagination: &Pagination) -> Self {
+		Self {
+			items,
+			page: pagination.page,
+			size: pagination.page_size,
+			total: pagination.total,
+			has_next: pagination.has_next,
+		}
+	}
+}
+
+/// OpenAPI schema of the `#[resp_data(page)]` response,
+/// e.g. `responses((status = 200, body = PageRespData<User>))`
+#[cfg(feature = "utoipa")]
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PageRespData<T: ToSchema> {
+	/// Response code
+	pub code: String,
+	/// Response message
+	pub msg: String,
+	/// Page body
+	pub data: Option<PageData<T>>,
+}
+
 /// API pagination query
 #[cfg_attr(feature = "utoipa", derive(ToSchema))]
 #[derive(Debug, Serialize, Deserialize)]
@@ -87,18 +144,24 @@ pub struct Pagination {
 	pub page: u64,
 	/// Page size
 	pub page_size: u64,
-	/// Total record count
-	pub total: u64,
-	/// Total page count
-	pub total_pages: u64,
+	/// Total record count, left out when the count was skipped
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub total: Option<u64>,
+	/// Total page count, left out when the count was skipped
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub total_pages: Option<u64>,
+	/// Set when the count was skipped or estimated
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub has_next: Option<bool>,
 }
 impl Pagination {
 	pub fn new(page: u64, page_size: u64, total: u64, total_pages: u64) -> Self {
 		Self {
 			page,
 			page_size,
-			total,
-			total_pages,
+			total: Some(total),
+			total_pages: Some(total_pages),
+			has_next: None,
 		}
 	}
 }
@@ -119,12 +182,7 @@ impl<T> Default for PageResp<T> {
 
 impl Default for Pagination {
 	fn default() -> Self {
-		Self {
-			page: 1,
-			page_size: 20,
-			total: 0,
-			total_pages: 0,
-		}
+		Self::new(1, 20, 0, 0)
 	}
 }
 
@@ -135,6 +193,7 @@ impl From<PageQuery> for Pagination {
 			page_size: v.page_size,
 			total: v.total,
 			total_pages: v.total_pages,
+			has_next: v.has_next,
 		}
 	}
 }
@@ -146,6 +205,84 @@ impl From<Pagination> for PageQuery {
 			page_size: v.page_size,
 			total: v.total,
 			total_pages: v.total_pages,
+			has_next: v.has_next,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::result::{AppJson, AxumResult};
+	use base_infra::result::{PageMeta, RespData, RespDataWithMeta};
+	use serde_json::json;
+
+	#[derive(Debug, Serialize)]
+	#[cfg_attr(feature = "utoipa", derive(ToSchema))]
+	struct Item {
+		id: u32,
+	}
+
+	#[test]
+	fn test_page_data_json() {
+		let resp = PageResp::new_with_page(
+			vec![Item { id: 6 }, Item { id: 7 }],
+			PageQuery::new(2, 5, 12),
+		);
+		let value = serde_json::to_value(RespData::success(PageData::from(resp))).unwrap();
+		assert_eq!(
+			value["data"],
+			json!({ "items": [{ "id": 6 }, { "id": 7 }], "page": 2, "size": 5, "total": 12 })
+		);
+		assert!(value["code"].is_string());
+		assert!(value["msg"].is_string());
+
+		let resp = PageResp::new_with_page(
+			vec![Item { id: 6 }],
+			PageQuery::new(2, 5, 0).with_has_next(true),
+		);
+		let value = serde_json::to_value(&resp.pagination).unwrap();
+		assert_eq!(value, json!({ "page": 2, "pageSize": 5, "hasNext": true }));
+		let value = serde_json::to_value(PageData::from(resp)).unwrap();
+		assert_eq!(
+			value,
+			json!({ "items": [{ "id": 6 }], "page": 2, "size": 5, "has_next": true })
+		);
+	}
+
+	#[test]
+	fn test_success_with_meta() {
+		let meta = PageMeta {
+			total: 12,
+			page: 2,
+			page_size: 5,
+		};
+		let resp: AxumResult<AppJson<RespDataWithMeta<Vec<Item>, PageMeta>>> =
+			crate::success_with_meta!(vec![Item { id: 6 }], meta);
+		let value = serde_json::to_value(resp.unwrap().0).unwrap();
+		assert_eq!(value["data"], json!([{ "id": 6 }]));
+		assert_eq!(
+			value["meta"],
+			json!({ "total": 12, "page": 2, "page_size": 5 })
+		);
+	}
+
+	#[cfg(feature = "utoipa")]
+	#[test]
+	fn test_page_schema() {
+		use utoipa::PartialSchema;
+
+		let schema = serde_json::to_value(PageData::<Item>::schema()).unwrap();
+		for field in ["items", "page", "size", "total"] {
+			assert!(schema["properties"].get(field).is_some(), "{field}");
+		}
+		let envelope = serde_json::to_value(PageRespData::<Item>::schema()).unwrap();
+		for field in ["code", "msg", "data"] {
+			assert!(envelope["properties"].get(field).is_some(), "{field}");
+		}
+		let with_meta = serde_json::to_value(RespDataWithMeta::<Item, PageMeta>::schema()).unwrap();
+		for field in ["code", "msg", "data", "meta"] {
+			assert!(with_meta["properties"].get(field).is_some(), "{field}");
 		}
 	}
 }
@@ -0,0 +1,104 @@
+use axum::response::sse::{Event, KeepAlive, KeepAliveStream, Sse};
+use base_infra::result::{AppError, AppResult, RespData};
+use futures::{Stream, StreamExt, future};
+use std::convert::Infallible;
+use tokio::sync::mpsc;
+
+/// SSE event name of the terminal error event sent by [`stream_ok`]
+pub const SSE_ERROR_EVENT: &str = "error";
+
+pub type SseStream =
+	Sse<KeepAliveStream<futures::stream::BoxStream<'static, Result<Event, Infallible>>>>;
+
+/// Turns a stream of events into an SSE response with keep-alive
+///
+/// The first `Err` item is sent as an `error` event whose data is the `RespData` json
+/// (`{"code":..,"msg":..,"data":null}`) and ends the stream.
+pub fn stream_ok<S>(stream: S) -> SseStream
+where
+	S: Stream<Item = AppResult<Event>> + Send + 'static,
+{
+	let events = stream
+		.scan(false, |failed, item| {
+			if *failed {
+				return future::ready(None);
+			}
+			let event = item.unwrap_or_else(|e| {
+				*failed = true;
+				error_event(e)
+			});
+			future::ready(Some(Ok(event)))
+		})
+		.boxed();
+	Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// [`stream_ok`] over the receiving half of a channel, the stream ends when all senders drop
+pub fn stream_rx(rx: mpsc::Receiver<AppResult<Event>>) -> SseStream {
+	stream_ok(receiver_stream(rx))
+}
+
+pub fn receiver_stream<T>(mut rx: mpsc::Receiver<T>) -> impl Stream<Item = T> {
+	futures::stream::poll_fn(move |cx| rx.poll_recv(cx))
+}
+
+fn error_event(e: AppError) -> Event {
+	tracing::error!("stream error: {}", e);
+	let resp = RespData::with_app_error(e);
+	Event::default()
+		.event(SSE_ERROR_EVENT)
+		.json_data(&resp)
+		.unwrap_or_else(|_| Event::default().event(SSE_ERROR_EVENT).data(resp.code))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::result::WebErr;
+	use axum::Router;
+	use axum::body::Body;
+	use axum::routing::get;
+	use base_infra::result::ErrorCode;
+	use http::Request;
+	use tower::ServiceExt;
+
+	async fn events() -> SseStream {
+		let (tx, rx) = mpsc::channel(8);
+		tokio::spawn(async move {
+			for i in 1..=3 {
+				let _ = tx.send(Ok(Event::default().data(format!("msg-{i}")))).await;
+			}
+			let _ = tx.send(Err(AppError::ErrCode(&WebErr::ServerErr))).await;
+			let _ = tx.send(Ok(Event::default().data("dropped"))).await;
+		});
+		stream_rx(rx)
+	}
+
+	#[tokio::test]
+	async fn test_stream_error_event() {
+		let app = Router::new().route("/events", get(events));
+		let req = Request::builder()
+			.uri("/events")
+			.body(Body::empty())
+			.unwrap();
+		let resp = app.oneshot(req).await.unwrap();
+		assert_eq!(
+			resp.headers()[http::header::CONTENT_TYPE],
+			"text/event-stream"
+		);
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		let body = String::from_utf8(body.to_vec()).unwrap();
+
+		let frames: Vec<&str> = body.split("\n\n").filter(|f| !f.is_empty()).collect();
+		assert_eq!(&frames[..3], ["data: msg-1", "data: msg-2", "data: msg-3"]);
+		let expected = format!(
+			"event: error\ndata: {{\"code\":\"{}\",\"msg\":\"{}\",\"data\":null}}",
+			WebErr::ServerErr.code(),
+			WebErr::ServerErr.message()
+		);
+		assert_eq!(frames[3], expected);
+		assert_eq!(frames.len(), 4);
+	}
+}
@@ -0,0 +1,171 @@
+use axum::extract::{OriginalUri, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::future::Future;
+
+tokio::task_local! {
+	static CURRENT_API_VERSION: Option<ApiVersion>;
+}
+
+/// API contract a response envelope was produced for, taken from the `/v1/`, `/v2/`
+/// url prefix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+	V1,
+	/// Envelopes additionally carry `ts`, the Unix milliseconds of the response
+	V2,
+}
+
+impl ApiVersion {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			ApiVersion::V1 => "v1",
+			ApiVersion::V2 => "v2",
+		}
+	}
+
+	/// `/v2/orders` and `/v2` give `V2`, anything else `None`
+	pub fn from_path(path: &str) -> Option<Self> {
+		let segment = path.trim_start_matches('/').split('/').next()?;
+		match segment {
+			"v1" => Some(ApiVersion::V1),
+			"v2" => Some(ApiVersion::V2),
+			_ => None,
+		}
+	}
+
+	/// Version of the request being handled, set by [`api_version`]
+	pub fn current() -> Option<Self> {
+		CURRENT_API_VERSION.try_with(|v| *v).ok().flatten()
+	}
+}
+
+impl Display for ApiVersion {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+/// Run `fut` with `version` as the current api version
+pub async fn with_api_version<F: Future>(version: Option<ApiVersion>, fut: F) -> F::Output {
+	CURRENT_API_VERSION.scope(version, fut).await
+}
+
+/// Middleware resolving [`ApiVersion::current`] from the request path, before any `nest`
+/// prefix stripping
+///
+/// `Router::new().nest("/v2", v2).layer(axum::middleware::from_fn(api_version))`
+pub async fn api_version(req: Request, next: Next) -> Response {
+	let path = match req.extensions().get::<OriginalUri>() {
+		Some(uri) => uri.path(),
+		None => req.uri().path(),
+	};
+	let version = ApiVersion::from_path(path);
+	with_api_version(version, next.run(req)).await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::result::{AppJson, AxumResp, AxumRespBuilder, AxumResult, WebErr};
+	use axum::Router;
+	use axum::body::Body;
+	use axum::response::IntoResponse;
+	use axum::routing::get;
+	use base_infra::result::{ErrorCode, RespData};
+	use base_infra::validator::FieldError;
+	use serde_json::Value;
+	use std::time::{SystemTime, UNIX_EPOCH};
+	use tower::ServiceExt;
+
+	async fn json(resp: Response) -> Value {
+		let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+			.await
+			.unwrap();
+		serde_json::from_slice(&body).unwrap()
+	}
+
+	#[test]
+	fn test_from_path() {
+		assert_eq!(ApiVersion::from_path("/v1/orders"), Some(ApiVersion::V1));
+		assert_eq!(ApiVersion::from_path("/v2"), Some(ApiVersion::V2));
+		assert_eq!(ApiVersion::from_path("/v3/orders"), None);
+		assert_eq!(ApiVersion::from_path("/api/v1"), None);
+	}
+
+	#[tokio::test]
+	async fn test_success_v2() {
+		let resp: AxumResult<AppJson<AxumResp<Vec<u32>>>> =
+			crate::success!(vec![1, 2], version = ApiVersion::V2);
+		let body = json(resp.unwrap().into_response()).await;
+		assert_eq!(body["api_version"], "v2");
+		assert_eq!(body["data"], serde_json::json!([1, 2]));
+
+		let now = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.unwrap()
+			.as_millis() as u64;
+		let ts = body["ts"].as_u64().unwrap();
+		assert!(now.abs_diff(ts) < 1000, "ts {ts}, now {now}");
+
+		let v1 = AxumRespBuilder::new().version(ApiVersion::V1).build(1);
+		let body = json(AppJson(v1).into_response()).await;
+		assert_eq!(body["api_version"], "v1");
+		assert!(body.get("ts").is_none());
+		assert!(body.get("errors").is_none());
+
+		let field = FieldError {
+			field: "qty".to_string(),
+			code: WebErr::ValidationFailed.code().to_string(),
+			msg: "must be positive".to_string(),
+		};
+		let failed = RespData::<u32> {
+			code: WebErr::ValidationFailed.code().to_string(),
+			msg: WebErr::ValidationFailed.message().to_string(),
+			data: None,
+			errors: Some(vec![field]),
+		};
+		let resp = AxumRespBuilder::new()
+			.version(ApiVersion::V1)
+			.build_resp(failed);
+		let body = json(AppJson(resp).into_response()).await;
+		assert_eq!(body["code"], WebErr::ValidationFailed.code());
+		assert_eq!(
+			body["errors"],
+			serde_json::json!([{ "field": "qty", "code": "AXUM03", "msg": "must be positive" }])
+		);
+	}
+
+	#[tokio::test]
+	async fn test_version_from_request() {
+		let v2 = Router::new().route(
+			"/orders",
+			get(|| async { AppJson(AxumRespBuilder::new().build("ok")) }),
+		);
+		let app = Router::new()
+			.nest("/v2", v2)
+			.route(
+				"/health",
+				get(|| async { AppJson(AxumRespBuilder::new().build("ok")) }),
+			)
+			.layer(axum::middleware::from_fn(api_version));
+
+		let req = Request::builder()
+			.uri("/v2/orders")
+			.body(Body::empty())
+			.unwrap();
+		let body = json(app.clone().oneshot(req).await.unwrap()).await;
+		assert_eq!(body["api_version"], "v2");
+		assert!(body["ts"].is_u64());
+
+		let req = Request::builder()
+			.uri("/health")
+			.body(Body::empty())
+			.unwrap();
+		let body = json(app.oneshot(req).await.unwrap()).await;
+		assert!(body.get("api_version").is_none());
+	}
+}
@@ -0,0 +1,312 @@
+use crate::HTTP_TIMEOUT;
+use crate::http::{DeadlineLayer, HttpTraceConfig, http_trace_with, timeout_error_response};
+#[cfg(feature = "metrics")]
+use crate::http::{MetricsLayer, metrics_router};
+use axum::error_handling::HandleErrorLayer;
+use axum::extract::DefaultBodyLimit;
+use axum::{BoxError, Router};
+use base_infra::map_err;
+use base_infra::result::{AppResult, SysErr};
+#[cfg(feature = "metrics")]
+use prometheus::Registry;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tower::ServiceBuilder;
+use tower::timeout::TimeoutLayer;
+use tracing::info;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+	pub host: String,
+	pub port: u16,
+	/// Seconds before a request is answered with 408
+	pub request_timeout: u64,
+	/// Max request body bytes accepted by extractors
+	pub body_limit: usize,
+	/// Request ids and logging of the default middleware, every path is traced by default
+	pub http_trace: HttpTraceConfig,
+	/// Request metrics in the default middleware, served on `/metrics`
+	#[cfg(feature = "metrics")]
+	pub metrics: bool,
+}
+
+impl Default for ServerConfig {
+	fn default() -> Self {
+		Self {
+			host: "0.0.0.0".to_string(),
+			port: 3000,
+			request_timeout: *HTTP_TIMEOUT,
+			body_limit: 2 * 1024 * 1024,
+			http_trace: HttpTraceConfig {
+				include_prefixes: vec![],
+				..Default::default()
+			},
+			#[cfg(feature = "metrics")]
+			metrics: true,
+		}
+	}
+}
+
+/// Cloneable trigger for graceful shutdown
+#[derive(Clone)]
+pub struct Shutdown {
+	tx: Arc<watch::Sender<bool>>,
+}
+
+impl Default for Shutdown {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Shutdown {
+	pub fn new() -> Self {
+		Self {
+			tx: Arc::new(watch::Sender::new(false)),
+		}
+	}
+
+	pub fn trigger(&self) {
+		self.tx.send_replace(true);
+	}
+
+	pub fn is_triggered(&self) -> bool {
+		*self.tx.borrow()
+	}
+
+	/// Resolves once [`Shutdown::trigger`] was called
+	pub async fn wait(&self) {
+		let mut rx = self.tx.subscribe();
+		let _ = rx.wait_for(|triggered| *triggered).await;
+	}
+}
+
+/// Binds, serves and drains an axum [`Router`]
+///
+/// ```ignore
+/// Server::new(cfg).router(app).with_default_middleware().run().await?;
+/// ```
+pub struct Server {
+	config: ServerConfig,
+	router: Router,
+	default_middleware: bool,
+	shutdown: Option<Shutdown>,
+	#[cfg(feature = "metrics")]
+	registry: Arc<Registry>,
+}
+
+impl Server {
+	pub fn new(config: ServerConfig) -> Self {
+		Self {
+			config,
+			router: Router::new(),
+			default_middleware: false,
+			shutdown: None,
+			#[cfg(feature = "metrics")]
+			registry: Arc::new(Registry::new()),
+		}
+	}
+
+	pub fn router(mut self, router: Router) -> Self {
+		self.router = router;
+		self
+	}
+
+	/// Layers, outermost first:
+	/// 1. [`http_trace_with`] - `http_trace`, resolves the request id, logs and tags the response
+	/// 2. [`MetricsLayer`](crate::http::MetricsLayer) - when `metrics` is on, with `/metrics`
+	///    served outside the middleware
+	/// 3. timeout - `request_timeout`, answered by [`timeout_error_response`]
+	/// 4. [`DeadlineLayer`] - the same `request_timeout` as a deadline for db and http client calls
+	/// 5. body limit - `body_limit`
+	pub fn with_default_middleware(mut self) -> Self {
+		self.default_middleware = true;
+		self
+	}
+
+	/// Registry of the request metrics and `/metrics`, a new one by default
+	#[cfg(feature = "metrics")]
+	pub fn with_registry(mut self, registry: Arc<Registry>) -> Self {
+		self.registry = registry;
+		self
+	}
+
+	/// Without a handle the server stops on ctrl-c
+	pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+		self.shutdown = Some(shutdown);
+		self
+	}
+
+	pub async fn run(self) -> AppResult<()> {
+		let addr = format!("{}:{}", self.config.host, self.config.port);
+		let listener = TcpListener::bind(&addr)
+			.await
+			.map_err(map_err!(&SysErr::ServerBindErr, addr))?;
+		self.serve(listener).await
+	}
+
+	/// Serves on an already bound listener, e.g. port `0` in tests
+	pub async fn serve(self, listener: TcpListener) -> AppResult<()> {
+		let addr = listener
+			.local_addr()
+			.map_err(map_err!(&SysErr::ServerBindErr))?;
+		let app = self.build_router()?;
+		info!("server listening on http://{}", addr);
+
+		let shutdown = self.shutdown;
+		axum::serve(
+			listener,
+			app.into_make_service_with_connect_info::<SocketAddr>(),
+		)
+		.with_graceful_shutdown(async move {
+			match shutdown {
+				Some(shutdown) => shutdown.wait().await,
+				None => {
+					let _ = tokio::signal::ctrl_c().await;
+				}
+			}
+			info!("server shutting down, draining in-flight requests");
+		})
+		.await
+		.map_err(map_err!(&SysErr::ServerStartErr))?;
+
+		info!("server on {} stopped", addr);
+		Ok(())
+	}
+
+	fn build_router(&self) -> AppResult<Router> {
+		if !self.default_middleware {
+			return Ok(self.router.clone());
+		}
+		let timeout = Duration::from_secs(self.config.request_timeout);
+		let router = self
+			.router
+			.clone()
+			.layer(DefaultBodyLimit::max(self.config.body_limit))
+			.layer(
+				ServiceBuilder::new()
+					.layer(HandleErrorLayer::new(move |err: BoxError| async move {
+						timeout_error_response(err, timeout)
+					}))
+					.layer(TimeoutLayer::new(timeout))
+					.layer(DeadlineLayer::new(timeout)),
+			);
+		#[cfg(feature = "metrics")]
+		let router = if self.config.metrics {
+			router
+				.layer(MetricsLayer::new(&self.registry)?)
+				.merge(metrics_router(self.registry.clone()))
+		} else {
+			router
+		};
+		Ok(router.layer(http_trace_with(self.config.http_trace.clone())))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::routing::get;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+	use tokio::net::TcpStream;
+
+	async fn get_raw(addr: SocketAddr, path: &str) -> String {
+		let mut stream = TcpStream::connect(addr).await.unwrap();
+		let req = format!("GET {path} HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n");
+		stream.write_all(req.as_bytes()).await.unwrap();
+		let mut buf = String::new();
+		stream.read_to_string(&mut buf).await.unwrap();
+		buf
+	}
+
+	#[tokio::test]
+	async fn test_serve_and_drain() {
+		let router = Router::new()
+			.route("/api/ping", get(|| async { "pong" }))
+			.route(
+				"/api/slow",
+				get(|| async {
+					tokio::time::sleep(Duration::from_millis(300)).await;
+					"done"
+				}),
+			);
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+		let shutdown = Shutdown::new();
+		let server = Server::new(ServerConfig::default())
+			.router(router)
+			.with_default_middleware()
+			.with_shutdown(shutdown.clone());
+		let handle = tokio::spawn(server.serve(listener));
+
+		let resp = get_raw(addr, "/api/ping").await;
+		assert!(resp.starts_with("HTTP/1.1 200"));
+		assert!(resp.to_ascii_lowercase().contains("request-id"));
+		assert!(resp.ends_with("pong"));
+
+		let in_flight = tokio::spawn(get_raw(addr, "/api/slow"));
+		tokio::time::sleep(Duration::from_millis(50)).await;
+		shutdown.trigger();
+		assert!(shutdown.is_triggered());
+
+		let resp = in_flight.await.unwrap();
+		assert!(resp.starts_with("HTTP/1.1 200"));
+		assert!(resp.ends_with("done"));
+		handle.await.unwrap().unwrap();
+	}
+
+	#[tokio::test]
+	async fn test_request_timeout() {
+		let router = Router::new().route(
+			"/stuck",
+			get(|| async {
+				tokio::time::sleep(Duration::from_secs(5)).await;
+				"late"
+			}),
+		);
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+		let shutdown = Shutdown::new();
+		let config = ServerConfig {
+			request_timeout: 0,
+			..Default::default()
+		};
+		let server = Server::new(config)
+			.router(router)
+			.with_default_middleware()
+			.with_shutdown(shutdown.clone());
+		let handle = tokio::spawn(server.serve(listener));
+
+		let resp = get_raw(addr, "/stuck").await;
+		assert!(resp.starts_with("HTTP/1.1 408"));
+		shutdown.trigger();
+		handle.await.unwrap().unwrap();
+	}
+
+	#[cfg(feature = "metrics")]
+	#[tokio::test]
+	async fn test_default_metrics() {
+		let router = Router::new().route("/api/ping", get(|| async { "pong" }));
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+		let shutdown = Shutdown::new();
+		let server = Server::new(ServerConfig::default())
+			.router(router)
+			.with_default_middleware()
+			.with_shutdown(shutdown.clone());
+		let handle = tokio::spawn(server.serve(listener));
+
+		assert!(get_raw(addr, "/api/ping").await.starts_with("HTTP/1.1 200"));
+		let resp = get_raw(addr, "/metrics").await;
+		assert!(resp.starts_with("HTTP/1.1 200"));
+		let line = r#"http_requests_total{method="GET",path="/api/ping",status="200"} 1"#;
+		assert!(resp.contains(line));
+		shutdown.trigger();
+		handle.await.unwrap().unwrap();
+	}
+}
@@ -0,0 +1,36 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tracing::subscriber::DefaultGuard;
+
+/// Collects the log output of the current thread
+#[derive(Clone, Default)]
+pub(crate) struct LogBuf(Arc<Mutex<Vec<u8>>>);
+
+impl LogBuf {
+	/// Install a TRACE level subscriber writing into the returned buffer until the guard drops
+	pub(crate) fn capture() -> (Self, DefaultGuard) {
+		let logs = Self::default();
+		let writer = logs.clone();
+		let subscriber = tracing_subscriber::fmt()
+			.with_max_level(tracing::Level::TRACE)
+			.with_ansi(false)
+			.with_writer(move || writer.clone())
+			.finish();
+		(logs, tracing::subscriber::set_default(subscriber))
+	}
+
+	pub(crate) fn contents(&self) -> String {
+		String::from_utf8_lossy(&self.0.lock().unwrap()).to_string()
+	}
+}
+
+impl Write for LogBuf {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.0.lock().unwrap().extend_from_slice(buf);
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
@@ -0,0 +1,395 @@
+use crate::http::current_request_id;
+use crate::result::WebErr;
+use axum::body::Bytes;
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code};
+use axum::routing::{MethodRouter, get};
+use base_infra::result::{AppError, AppResult, RespData};
+use base_infra::utils::uuid::UID;
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{Notify, mpsc, watch};
+use tokio::time::{Instant, interval_at};
+use tracing::{Instrument, debug, info_span, warn};
+
+#[derive(Debug, Clone)]
+pub struct WsConfig {
+	/// A ping is sent every interval
+	pub ping_interval: Duration,
+	/// Pings left unanswered before the session is closed
+	pub max_missed_pongs: u32,
+	/// Outgoing messages buffered per session, the oldest is dropped when full
+	pub send_queue: usize,
+	/// Incoming messages buffered before the socket stops being read
+	pub recv_queue: usize,
+}
+
+impl Default for WsConfig {
+	fn default() -> Self {
+		Self {
+			ping_interval: Duration::from_secs(30),
+			max_missed_pongs: 2,
+			send_queue: 64,
+			recv_queue: 64,
+		}
+	}
+}
+
+enum Incoming {
+	Text(String),
+	Closed(Option<CloseFrame>),
+	Failed(axum::Error),
+}
+
+struct Outgoing {
+	queue: Mutex<VecDeque<Message>>,
+	capacity: usize,
+	notify: Notify,
+	closed: AtomicBool,
+	dropped: AtomicU64,
+}
+
+impl Outgoing {
+	fn push(&self, msg: Message) {
+		let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+		if queue.len() >= self.capacity {
+			queue.pop_front();
+			self.dropped.fetch_add(1, Ordering::Relaxed);
+			warn!("ws send queue full, dropped the oldest message");
+		}
+		queue.push_back(msg);
+		drop(queue);
+		self.notify.notify_one();
+	}
+
+	fn pop(&self) -> Option<Message> {
+		self.queue
+			.lock()
+			.unwrap_or_else(|e| e.into_inner())
+			.pop_front()
+	}
+}
+
+/// Json framed websocket with heartbeat
+///
+/// Reading and writing run in background tasks, so pongs are seen and queued messages flushed
+/// even while the handler is busy. Dropping the session flushes the queue and closes the socket.
+pub struct WsSession<In, Out> {
+	incoming: mpsc::Receiver<Incoming>,
+	outgoing: Arc<Outgoing>,
+	timed_out: watch::Receiver<bool>,
+	_types: PhantomData<fn(In) -> Out>,
+}
+
+impl<In: DeserializeOwned, Out: Serialize> WsSession<In, Out> {
+	pub fn new(socket: WebSocket, config: WsConfig) -> Self {
+		let (sink, stream) = socket.split();
+		let (incoming_tx, incoming) = mpsc::channel(config.recv_queue.max(1));
+		let (timeout_tx, timed_out) = watch::channel(false);
+		let missed = Arc::new(AtomicU32::new(0));
+		let outgoing = Arc::new(Outgoing {
+			queue: Mutex::new(VecDeque::new()),
+			capacity: config.send_queue.max(1),
+			notify: Notify::new(),
+			closed: AtomicBool::new(false),
+			dropped: AtomicU64::new(0),
+		});
+
+		tokio::spawn(read_loop(stream, incoming_tx, missed.clone()).in_current_span());
+		tokio::spawn(
+			write_loop(sink, outgoing.clone(), missed, timeout_tx, config).in_current_span(),
+		);
+		Self {
+			incoming,
+			outgoing,
+			timed_out,
+			_types: PhantomData,
+		}
+	}
+
+	/// Next message, `Ok(None)` once the peer closed normally
+	///
+	/// A malformed message is answered with a `RespData` error frame and returned as
+	/// [`WebErr::WsBadMessage`], the session stays usable.
+	pub async fn recv(&mut self) -> AppResult<Option<In>> {
+		if *self.timed_out.borrow() {
+			return Err(AppError::ErrCode(&WebErr::WsHeartbeatTimeout));
+		}
+		let incoming = tokio::select! {
+			incoming = self.incoming.recv() => incoming,
+			_ = self.timed_out.changed() => {
+				return Err(AppError::ErrCode(&WebErr::WsHeartbeatTimeout));
+			}
+		};
+		match incoming {
+			Some(Incoming::Text(text)) => match serde_json::from_str(&text) {
+				Ok(msg) => Ok(Some(msg)),
+				Err(e) => {
+					let err = AppError::ExtCode(&WebErr::WsBadMessage, e.to_string());
+					self.send_error(&err);
+					Err(err)
+				}
+			},
+			Some(Incoming::Closed(frame)) => close_result(frame),
+			Some(Incoming::Failed(e)) => Err(AppError::Anyhow(&WebErr::WsClosed, e.into())),
+			None => Ok(None),
+		}
+	}
+
+	pub fn send(&self, msg: &Out) -> AppResult<()> {
+		if self.outgoing.closed.load(Ordering::Acquire) {
+			return Err(AppError::ErrCode(&WebErr::WsSessionClosed));
+		}
+		let text = serde_json::to_string(msg)
+			.map_err(|e| AppError::ExtCode(&WebErr::WsBadMessage, format!("serialize: {e}")))?;
+		self.outgoing.push(Message::Text(text.into()));
+		Ok(())
+	}
+
+	/// Sends `err` as a `{ code, msg }` frame
+	pub fn send_error(&self, err: &AppError) {
+		let resp = RespData::with(err_code(err), &err.get_reason());
+		if let Ok(text) = serde_json::to_string(&resp) {
+			self.outgoing.push(Message::Text(text.into()));
+		}
+	}
+
+	/// Messages dropped because the send queue was full
+	pub fn dropped(&self) -> u64 {
+		self.outgoing.dropped.load(Ordering::Relaxed)
+	}
+
+	/// Flushes queued messages and closes with `1000`
+	pub fn close(self) {}
+}
+
+impl<In, Out> Drop for WsSession<In, Out> {
+	fn drop(&mut self) {
+		self.outgoing.closed.store(true, Ordering::Release);
+		self.outgoing.notify.notify_one();
+	}
+}
+
+fn err_code(err: &AppError) -> &'static str {
+	match err {
+		AppError::ErrCode(code)
+		| AppError::ExtCode(code, _)
+		| AppError::Anyhow(code, _)
+		| AppError::ExtAnyhow(code, _, _)
+		| AppError::HttpErr(code, _) => code.code(),
+	}
+}
+
+/// Normal and going-away closes end the session, other codes are errors
+fn close_result<T>(frame: Option<CloseFrame>) -> AppResult<Option<T>> {
+	match frame {
+		None => Ok(None),
+		Some(frame) if matches!(frame.code, close_code::NORMAL | close_code::AWAY) => Ok(None),
+		Some(frame) => Err(AppError::ExtCode(
+			&WebErr::WsClosed,
+			format!("close code {}: {}", frame.code, frame.reason.as_str()),
+		)),
+	}
+}
+
+async fn read_loop(
+	mut stream: SplitStream<WebSocket>,
+	tx: mpsc::Sender<Incoming>,
+	missed: Arc<AtomicU32>,
+) {
+	while let Some(msg) = stream.next().await {
+		let incoming = match msg {
+			Ok(Message::Text(text)) => Incoming::Text(text.as_str().to_string()),
+			Ok(Message::Binary(bytes)) => Incoming::Text(String::from_utf8_lossy(&bytes).into()),
+			Ok(Message::Pong(_)) => {
+				missed.store(0, Ordering::Relaxed);
+				continue;
+			}
+			// answered by axum
+			Ok(Message::Ping(_)) => continue,
+			Ok(Message::Close(frame)) => Incoming::Closed(frame),
+			Err(e) => Incoming::Failed(e),
+		};
+		let last = !matches!(incoming, Incoming::Text(_));
+		if tx.send(incoming).await.is_err() || last {
+			break;
+		}
+	}
+}
+
+async fn write_loop(
+	mut sink: SplitSink<WebSocket, Message>,
+	outgoing: Arc<Outgoing>,
+	missed: Arc<AtomicU32>,
+	timed_out: watch::Sender<bool>,
+	config: WsConfig,
+) {
+	let mut ticker = interval_at(Instant::now() + config.ping_interval, config.ping_interval);
+	loop {
+		tokio::select! {
+			_ = outgoing.notify.notified() => {
+				while let Some(msg) = outgoing.pop() {
+					if sink.send(msg).await.is_err() {
+						return;
+					}
+				}
+				if outgoing.closed.load(Ordering::Acquire) {
+					let _ = sink.send(close_frame(close_code::NORMAL, "")).await;
+					return;
+				}
+			}
+			_ = ticker.tick() => {
+				if missed.fetch_add(1, Ordering::Relaxed) >= config.max_missed_pongs {
+					warn!("{}", WebErr::WsHeartbeatTimeout);
+					outgoing.closed.store(true, Ordering::Release);
+					timed_out.send_replace(true);
+					let _ = sink.send(close_frame(close_code::POLICY, "heartbeat timeout")).await;
+					return;
+				}
+				if sink.send(Message::Ping(Bytes::new())).await.is_err() {
+					return;
+				}
+			}
+		}
+	}
+}
+
+fn close_frame(code: u16, reason: &'static str) -> Message {
+	Message::Close(Some(CloseFrame {
+		code,
+		reason: reason.into(),
+	}))
+}
+
+/// `GET` route upgrading to a [`WsSession`], the handler runs in a `ws` span carrying the
+/// request's tid
+///
+/// ```ignore
+/// Router::new().route("/ws", ws_route(WsConfig::default(), |mut session: WsSession<In, Out>| async move {
+///     while let Ok(Some(msg)) = session.recv().await { /* .. */ }
+/// }))
+/// ```
+pub fn ws_route<S, In, Out, F, Fut>(config: WsConfig, handler: F) -> MethodRouter<S>
+where
+	S: Clone + Send + Sync + 'static,
+	In: DeserializeOwned + Send + 'static,
+	Out: Serialize + Send + 'static,
+	F: Fn(WsSession<In, Out>) -> Fut + Clone + Send + Sync + 'static,
+	Fut: Future<Output = ()> + Send + 'static,
+{
+	get(move |ws: WebSocketUpgrade| {
+		let (config, handler) = (config.clone(), handler.clone());
+		async move {
+			let tid = current_request_id().unwrap_or_else(|| UID.v4_simple_str());
+			ws.on_upgrade(move |socket| {
+				let span = info_span!("ws", tid = %tid);
+				async move {
+					debug!("ws session started");
+					handler(WsSession::new(socket, config)).await;
+					debug!("ws session finished");
+				}
+				.instrument(span)
+			})
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use axum::Router;
+	use base_infra::result::ErrorCode;
+	use serde::Deserialize;
+	use std::net::SocketAddr;
+	use tokio::net::TcpListener;
+	use tokio_tungstenite::connect_async;
+	use tokio_tungstenite::tungstenite::Message as WsMessage;
+	use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+
+	#[derive(Debug, Serialize, Deserialize, PartialEq)]
+	struct Echo {
+		n: u32,
+	}
+
+	async fn echo(mut session: WsSession<Echo, Echo>) {
+		loop {
+			match session.recv().await {
+				Ok(Some(msg)) => session.send(&msg).unwrap(),
+				Ok(None) => break,
+				Err(e) if err_code(&e) == WebErr::WsBadMessage.code() => continue,
+				Err(_) => break,
+			}
+		}
+	}
+
+	async fn serve(config: WsConfig) -> SocketAddr {
+		let app = Router::new().route("/ws", ws_route(config, echo));
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+		tokio::spawn(async move { axum::serve(listener, app).await });
+		addr
+	}
+
+	async fn next_text<S>(client: &mut S) -> String
+	where
+		S: futures::Stream<Item = Result<WsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin,
+	{
+		loop {
+			match client.next().await.unwrap().unwrap() {
+				WsMessage::Text(text) => return text.as_str().to_string(),
+				WsMessage::Ping(_) | WsMessage::Pong(_) => continue,
+				other => panic!("unexpected {other:?}"),
+			}
+		}
+	}
+
+	#[tokio::test]
+	async fn test_echo_and_bad_json() {
+		let addr = serve(WsConfig::default()).await;
+		let (mut client, _) = connect_async(format!("ws://{addr}/ws")).await.unwrap();
+
+		client.send(WsMessage::text(r#"{"n":7}"#)).await.unwrap();
+		let echo: Echo = serde_json::from_str(&next_text(&mut client).await).unwrap();
+		assert_eq!(echo, Echo { n: 7 });
+
+		client.send(WsMessage::text("not json")).await.unwrap();
+		let frame: serde_json::Value = serde_json::from_str(&next_text(&mut client).await).unwrap();
+		assert_eq!(frame["code"], WebErr::WsBadMessage.code());
+		assert!(frame["msg"].as_str().unwrap().contains("expected"));
+
+		client.send(WsMessage::text(r#"{"n":8}"#)).await.unwrap();
+		assert!(next_text(&mut client).await.contains('8'));
+		client.close(None).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn test_heartbeat_timeout() {
+		let config = WsConfig {
+			ping_interval: Duration::from_millis(50),
+			max_missed_pongs: 1,
+			..Default::default()
+		};
+		let addr = serve(config).await;
+		let (mut client, _) = connect_async(format!("ws://{addr}/ws")).await.unwrap();
+
+		// not reading means no pongs are sent back
+		tokio::time::sleep(Duration::from_millis(300)).await;
+		let mut close = None;
+		while let Some(Ok(msg)) = client.next().await {
+			if let WsMessage::Close(frame) = msg {
+				close = frame;
+				break;
+			}
+		}
+		let close = close.expect("close frame");
+		assert_eq!(close.code, CloseCode::Policy);
+		assert_eq!(close.reason.as_str(), "heartbeat timeout");
+	}
+}
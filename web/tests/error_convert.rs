@@ -0,0 +1,107 @@
+use axum::response::IntoResponse;
+use base_infra::result::{ErrorCode, SysErr};
+use http::StatusCode;
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbErr};
+use serde_json::Value;
+use sql_infra::error::DBErr;
+use std::time::Duration;
+use web_infra::result::{AxumError, WebErr};
+
+async fn respond(err: AxumError) -> (StatusCode, Value) {
+	let resp = err.into_response();
+	let status = resp.status();
+	let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+		.await
+		.unwrap();
+	(status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn sqlite() -> DatabaseConnection {
+	let db = Database::connect("sqlite::memory:").await.unwrap();
+	for sql in [
+		"PRAGMA foreign_keys = ON",
+		"CREATE TABLE team (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE)",
+		"CREATE TABLE member (id INTEGER PRIMARY KEY, team_id INTEGER NOT NULL REFERENCES team(id))",
+		"INSERT INTO team (id, name) VALUES (1, 'core')",
+	] {
+		db.execute_unprepared(sql).await.unwrap();
+	}
+	db
+}
+
+#[tokio::test]
+async fn test_db_errors() {
+	let (status, body) = respond(DbErr::RecordNotFound("team 7".to_string()).into()).await;
+	assert_eq!(status, StatusCode::NOT_FOUND);
+	assert_eq!(body["code"], DBErr::RecordNotFound.code());
+
+	let db = sqlite().await;
+	let unique = db
+		.execute_unprepared("INSERT INTO team (id, name) VALUES (2, 'core')")
+		.await
+		.unwrap_err();
+	let (status, body) = respond(unique.into()).await;
+	assert_eq!(status, StatusCode::CONFLICT);
+	assert_eq!(body["code"], DBErr::UniqueViolation.code());
+
+	let foreign_key = db
+		.execute_unprepared("INSERT INTO member (id, team_id) VALUES (1, 42)")
+		.await
+		.unwrap_err();
+	let (status, body) = respond(foreign_key.into()).await;
+	assert_eq!(status, StatusCode::CONFLICT);
+	assert_eq!(body["code"], DBErr::ForeignKeyViolation.code());
+
+	let other = db
+		.execute_unprepared("SELECT * FROM missing")
+		.await
+		.unwrap_err();
+	let (status, body) = respond(other.into()).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(body["code"], SysErr::InternalError.code());
+}
+
+#[tokio::test]
+async fn test_reqwest_errors() {
+	// accepts but never answers
+	let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let silent = listener.local_addr().unwrap();
+	let client = reqwest::Client::builder()
+		.timeout(Duration::from_millis(100))
+		.build()
+		.unwrap();
+	let timeout = client
+		.get(format!("http://{silent}/"))
+		.send()
+		.await
+		.unwrap_err();
+	let (status, body) = respond(timeout.into()).await;
+	assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+	assert_eq!(body["code"], WebErr::UpstreamTimeout.code());
+
+	let closed = {
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		listener.local_addr().unwrap()
+	};
+	let refused = client
+		.get(format!("http://{closed}/"))
+		.send()
+		.await
+		.unwrap_err();
+	let (status, body) = respond(refused.into()).await;
+	assert_eq!(status, StatusCode::BAD_GATEWAY);
+	assert_eq!(body["code"], WebErr::UpstreamUnavailable.code());
+	drop(listener);
+}
+
+#[tokio::test]
+async fn test_anyhow_errors() {
+	let wrapped = anyhow::Error::new(DbErr::RecordNotFound("user 1".to_string()));
+	let (status, body) = respond(wrapped.into()).await;
+	assert_eq!(status, StatusCode::NOT_FOUND);
+	assert_eq!(body["code"], DBErr::RecordNotFound.code());
+
+	let (status, body) = respond(anyhow::anyhow!("boom").into()).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(body["code"], SysErr::InternalError.code());
+}
@@ -0,0 +1,172 @@
+use axum::Router;
+use axum::body::Body;
+use axum::routing::{get, post};
+use axum_resp_macro::resp_data;
+use base_infra::result::{AppResult, ErrorCode, SysErr};
+use base_infra::validator::{Checker, FieldErrors};
+use http::{Request, StatusCode, header};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tower::ServiceExt;
+use web_infra::extract::{Validated, ValidatedQuery};
+use web_infra::result::WebErr;
+
+#[derive(Debug, Deserialize, Serialize)]
+struct CreateUser {
+	name: String,
+	age: u32,
+}
+
+impl Checker for CreateUser {
+	fn check(&self) -> AppResult<()> {
+		let mut errors = FieldErrors::new();
+		if self.name.trim().is_empty() {
+			errors.add("name", "must not be empty");
+		}
+		if !(18..=150).contains(&self.age) {
+			errors.add("age", "must be in 18..=150");
+		}
+		errors.into_result(&SysErr::InvalidParams)
+	}
+}
+
+#[derive(Debug, Deserialize)]
+struct Search {
+	page: u32,
+}
+
+impl Checker for Search {
+	fn check(&self) -> AppResult<()> {
+		base_infra::assert_true!(self.page == 0, &SysErr::InvalidParams, "page starts from 1");
+		Ok(())
+	}
+}
+
+#[resp_data]
+async fn create_user(Validated(user): Validated<CreateUser>) -> AppResult<CreateUser> {
+	// business rule checked after validation, reported the same way
+	if user.name == "admin" {
+		let mut errors = FieldErrors::new();
+		errors.add("name", "is taken");
+		errors.into_result(&SysErr::InvalidParams)?;
+	}
+	Ok(user)
+}
+
+#[resp_data]
+async fn search(ValidatedQuery(search): ValidatedQuery<Search>) -> AppResult<u32> {
+	Ok(search.page)
+}
+
+fn app() -> Router {
+	Router::new()
+		.route("/users", post(create_user))
+		.route("/search", get(search))
+}
+
+async fn call(req: Request<Body>) -> (StatusCode, Value) {
+	let resp = app().oneshot(req).await.unwrap();
+	let status = resp.status();
+	let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+		.await
+		.unwrap();
+	(status, serde_json::from_slice(&body).unwrap())
+}
+
+fn post_json(body: &str) -> Request<Body> {
+	Request::builder()
+		.method("POST")
+		.uri("/users")
+		.header(header::CONTENT_TYPE, "application/json")
+		.body(Body::from(body.to_string()))
+		.unwrap()
+}
+
+#[tokio::test]
+async fn test_happy_path() {
+	let (status, body) = call(post_json(r#"{"name":"alice","age":30}"#)).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(body["code"], SysErr::Success.code());
+	assert_eq!(body["data"]["name"], "alice");
+}
+
+#[tokio::test]
+async fn test_checker_failure() {
+	let (status, body) = call(post_json(r#"{"name":" ","age":3}"#)).await;
+	assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+	assert_eq!(body["code"], WebErr::ValidationFailed.code());
+	assert_eq!(body["data"], Value::Null);
+	let code = SysErr::InvalidParams.code();
+	assert_eq!(
+		body["errors"],
+		serde_json::json!([
+			{ "field": "name", "code": code, "msg": "must not be empty" },
+			{ "field": "age", "code": code, "msg": "must be in 18..=150" },
+		])
+	);
+
+	let req = Request::builder()
+		.uri("/search?page=0")
+		.body(Body::empty())
+		.unwrap();
+	let (status, body) = call(req).await;
+	assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+	assert_eq!(body["errors"][0]["code"], SysErr::InvalidParams.code());
+	assert!(
+		body["errors"][0]["msg"]
+			.as_str()
+			.unwrap()
+			.contains("page starts from 1")
+	);
+}
+
+#[tokio::test]
+async fn test_serde_type_error() {
+	let (status, body) = call(post_json(r#"{"name":"bob","age":"old"}"#)).await;
+	assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+	assert_eq!(body["code"], WebErr::ValidationFailed.code());
+	assert_eq!(body["errors"][0]["field"], "age");
+	assert_eq!(body["errors"][0]["code"], WebErr::ValidationFailed.code());
+	assert!(
+		body["errors"][0]["msg"]
+			.as_str()
+			.unwrap()
+			.contains("invalid type")
+	);
+
+	let (status, body) = call(post_json(r#"{"name":"bob"}"#)).await;
+	assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+	assert!(
+		body["errors"][0]["msg"]
+			.as_str()
+			.unwrap()
+			.contains("missing field `age`")
+	);
+
+	let req = Request::builder()
+		.uri("/search?page=abc")
+		.body(Body::empty())
+		.unwrap();
+	let (status, body) = call(req).await;
+	assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+	assert_eq!(body["errors"][0]["field"], "page");
+
+	let (status, body) = call(post_json("{not json")).await;
+	assert_eq!(status, StatusCode::BAD_REQUEST);
+	assert_eq!(body["code"], WebErr::ReqJsonErr.code());
+	assert!(body.get("errors").is_none());
+}
+
+#[tokio::test]
+async fn test_handler_field_errors() {
+	let (status, body) = call(post_json(r#"{"name":"admin","age":30}"#)).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(body["code"], SysErr::InvalidParams.code());
+	assert_eq!(
+		body["errors"],
+		serde_json::json!([{ "field": "name", "code": SysErr::InvalidParams.code(), "msg": "is taken" }])
+	);
+
+	let (_, body) = call(post_json(r#"{"name":"alice","age":30}"#)).await;
+	assert!(body.get("errors").is_none());
+}